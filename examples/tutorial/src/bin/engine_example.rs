@@ -24,7 +24,7 @@ use rustmetrics::utils::tracing_timer::CustomOffsetTime;
 use serde_json::to_string_pretty;
 use std::collections::HashMap;
 use std::fs::write;
-use std::rc::Rc;
+use std::sync::Arc;
 use std::time::Instant;
 use time::{macros::datetime, Duration};
 use tracing::{info, span, Level};
@@ -350,13 +350,13 @@ fn main() -> Result<()> {
     let inst7 = Instrument::Stock(stock);
 
     let inst_vec = vec![
-        Rc::new(inst1),
-        Rc::new(inst2),
-        Rc::new(inst3),
-        Rc::new(inst4),
-        Rc::new(inst5),
-        Rc::new(inst6),
-        Rc::new(inst7),
+        Arc::new(inst1),
+        Arc::new(inst2),
+        Arc::new(inst3),
+        Arc::new(inst4),
+        Arc::new(inst5),
+        Arc::new(inst6),
+        Arc::new(inst7),
     ];
 
     // make a calculation configuration
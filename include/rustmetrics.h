@@ -0,0 +1,79 @@
+#ifndef RUSTMETRICS_H
+#define RUSTMETRICS_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum {
+  RUSTMETRICS_STATUS_OK = 0,
+  // the engine is null
+  RUSTMETRICS_STATUS_NULL_POINTER = 1,
+  // the json is null, not utf-8 or not of the expected layout
+  RUSTMETRICS_STATUS_INVALID_ARGUMENT = 2,
+  // the inputs are missing or the calculation failed
+  RUSTMETRICS_STATUS_CALCULATION_ERROR = 3,
+  RUSTMETRICS_STATUS_PANIC = 4,
+} RustmetricsStatus;
+
+// An engine with its inputs and the results of the last run. Opaque in C.
+typedef struct RustmetricsEngine RustmetricsEngine;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// version of the library, e.g., "0.1.0". The string is static.
+const char *rustmetrics_version(void);
+
+// creates an engine with the default configuration
+RustmetricsEngine *rustmetrics_engine_new(void);
+
+// # Safety
+// The engine must be created by rustmetrics_engine_new and not be freed yet, or be null.
+void rustmetrics_engine_free(RustmetricsEngine *engine);
+
+// # Safety
+// The engine must be valid or null, and the json must be a nul-terminated string or null.
+RustmetricsStatus rustmetrics_engine_set_configuration(RustmetricsEngine *engine, const char *json);
+
+// # Safety
+// The engine must be valid or null, and the json must be a nul-terminated string or null.
+RustmetricsStatus rustmetrics_engine_set_portfolio(RustmetricsEngine *engine, const char *json);
+
+// # Safety
+// The engine must be valid or null, and the json must be a nul-terminated string or null.
+RustmetricsStatus rustmetrics_engine_set_market_data(RustmetricsEngine *engine, const char *json);
+
+// calculates the portfolio on the market data. The results of the previous run are dropped.
+//
+// # Safety
+// The engine must be valid or null.
+RustmetricsStatus rustmetrics_engine_run(RustmetricsEngine *engine);
+
+// json of the results of the last run keyed by the instrument ids,
+// or null on an error. The string is freed by rustmetrics_string_free.
+//
+// # Safety
+// The engine must be valid or null.
+char *rustmetrics_engine_get_results(RustmetricsEngine *engine);
+
+// message of the error of the last call on the engine ("" if it succeeded), or null for a null engine.
+// The string is owned by the engine and valid until the next call on it.
+//
+// # Safety
+// The engine must be valid or null.
+const char *rustmetrics_engine_last_error(const RustmetricsEngine *engine);
+
+// # Safety
+// The string must be returned by this library and not be freed yet, or be null.
+void rustmetrics_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RUSTMETRICS_H */
@@ -31,7 +31,7 @@ use time::{Date, Time, UtcOffset};
 /// assert_eq!(ordered_datetime, vec![date1, date2, date3]);
 /// assert_eq!(ordered_value, vec![100.0, 200.0, 300.0]);
 /// ```
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct DailyValueData {
     /// A map of dates to their corresponding real values.
     /// Each entry represents a daily data point.
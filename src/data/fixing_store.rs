@@ -0,0 +1,386 @@
+use crate::data::daily_value_data::DailyValueData;
+use crate::definitions::Real;
+use crate::time::calendar::Calendar;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use std::collections::BTreeMap;
+use time::{Date, OffsetDateTime, Time, UtcOffset};
+
+/// A value of a fixing with the time when it is recorded.
+/// A correction is a new version recorded later.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FixingVersion {
+    value: Real,
+    #[serde(with = "crate::time::datetime_serde::datetime")]
+    recorded_at: OffsetDateTime,
+}
+
+impl FixingVersion {
+    pub fn get_value(&self) -> Real {
+        self.value
+    }
+
+    pub fn get_recorded_at(&self) -> &OffsetDateTime {
+        &self.recorded_at
+    }
+}
+
+/// Fixings of a rate index, an fx rate or an equity with the conventions of DailyValueData
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixingSeries {
+    close_time: Time,
+    utc_offset: UtcOffset,
+    calendar: Calendar,
+    name: String,
+    id: StaticId,
+    #[serde(with = "date_key_map")]
+    fixings: BTreeMap<Date, Vec<FixingVersion>>,
+}
+
+// the dates are not strings in json, so the map is a list of [date, versions]
+mod date_key_map {
+    use super::FixingVersion;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::BTreeMap;
+    use time::Date;
+
+    pub fn serialize<S: Serializer>(
+        map: &BTreeMap<Date, Vec<FixingVersion>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<Date, Vec<FixingVersion>>, D::Error> {
+        let entries = Vec::<(Date, Vec<FixingVersion>)>::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
+impl FixingSeries {
+    /// the series of the conventions of the data without the values
+    pub fn from_data(data: &DailyValueData) -> FixingSeries {
+        FixingSeries {
+            close_time: *data.get_close_time(),
+            utc_offset: *data.get_utc_offset(),
+            calendar: data.get_calendar().clone(),
+            name: data.get_name().clone(),
+            id: data.get_id(),
+            fixings: BTreeMap::new(),
+        }
+    }
+
+    pub fn get_id(&self) -> StaticId {
+        self.id
+    }
+
+    pub fn get_fixings(&self) -> &BTreeMap<Date, Vec<FixingVersion>> {
+        &self.fixings
+    }
+
+    /// the last version recorded at or before as_of, or the last version if as_of is None
+    pub fn get_fixing(&self, date: &Date, as_of: Option<&OffsetDateTime>) -> Option<Real> {
+        let versions = self.fixings.get(date)?;
+        match as_of {
+            None => versions.last(),
+            Some(as_of) => versions
+                .iter()
+                .rev()
+                .find(|version| version.recorded_at <= *as_of),
+        }
+        .map(|version| version.value)
+    }
+
+    /// DailyValueData of the fixings known at as_of (all the last versions if as_of is None)
+    pub fn to_daily_value_data(&self, as_of: Option<&OffsetDateTime>) -> DailyValueData {
+        let value = self
+            .fixings
+            .keys()
+            .filter_map(|date| self.get_fixing(date, as_of).map(|value| (*date, value)))
+            .collect();
+        DailyValueData::new(
+            value,
+            self.close_time,
+            self.utc_offset,
+            self.calendar.clone(),
+            self.name.clone(),
+            self.id,
+        )
+    }
+}
+
+/// Repository of the historical fixings keyed by the ids of the rate indices, fx rates and equities,
+/// from which the past_daily_value_data of EngineGenerator::with_data is built for each run,
+/// e.g., for the floating coupons already fixed and the averaging of NDF and Asian options.
+///
+/// The fixings are kept with the times when they are recorded:
+/// * append adds the fixing of a new date
+/// * correct adds a new version of a fixing already recorded, e.g., a republished index
+/// * the queries with as_of give the fixings as they were known at the time,
+///   so that the past runs can be reproduced
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FixingStore {
+    series: FxHashMap<StaticId, FixingSeries>,
+}
+
+impl FixingStore {
+    pub fn new() -> FixingStore {
+        FixingStore::default()
+    }
+
+    /// adds a series of the conventions (close time, utc offset, calendar) of the data.
+    /// The values of the data are not recorded (see ingest).
+    pub fn register(&mut self, data: &DailyValueData) -> Result<()> {
+        if self.series.contains_key(&data.get_id()) {
+            return Err(anyhow!(
+                "({}:{}) the fixings of {} are already registered",
+                file!(),
+                line!(),
+                data.get_id()
+            ));
+        }
+        self.series
+            .insert(data.get_id(), FixingSeries::from_data(data));
+        Ok(())
+    }
+
+    pub fn get_series(&self, id: &StaticId) -> Option<&FixingSeries> {
+        self.series.get(id)
+    }
+
+    pub fn get_ids(&self) -> Vec<StaticId> {
+        let mut ids: Vec<StaticId> = self.series.keys().copied().collect();
+        ids.sort_by_key(|id| id.to_string());
+        ids
+    }
+
+    fn get_series_mut(&mut self, id: &StaticId) -> Result<&mut FixingSeries> {
+        self.series.get_mut(id).ok_or_else(|| {
+            anyhow!(
+                "({}:{}) the fixings of {} are not registered",
+                file!(),
+                line!(),
+                id
+            )
+        })
+    }
+
+    /// records the fixing of a date which has no fixing yet
+    pub fn append(
+        &mut self,
+        id: &StaticId,
+        date: Date,
+        value: Real,
+        recorded_at: OffsetDateTime,
+    ) -> Result<()> {
+        let series = self.get_series_mut(id)?;
+        if series.fixings.contains_key(&date) {
+            return Err(anyhow!(
+                "({}:{}) the fixing of {} on {} is already recorded (use correct)",
+                file!(),
+                line!(),
+                id,
+                date
+            ));
+        }
+        series
+            .fixings
+            .insert(date, vec![FixingVersion { value, recorded_at }]);
+        Ok(())
+    }
+
+    /// records a new version of the fixing of a date, recorded after the previous versions
+    pub fn correct(
+        &mut self,
+        id: &StaticId,
+        date: Date,
+        value: Real,
+        recorded_at: OffsetDateTime,
+    ) -> Result<()> {
+        let series = self.get_series_mut(id)?;
+        let versions = series.fixings.get_mut(&date).ok_or_else(|| {
+            anyhow!(
+                "({}:{}) no fixing of {} on {} to correct",
+                file!(),
+                line!(),
+                id,
+                date
+            )
+        })?;
+        let last = versions.last().unwrap();
+        if recorded_at < last.recorded_at {
+            return Err(anyhow!(
+                "({}:{}) the correction of {} on {} at {} is recorded before the last version at {}",
+                file!(),
+                line!(),
+                id,
+                date,
+                recorded_at,
+                last.recorded_at
+            ));
+        }
+        versions.push(FixingVersion { value, recorded_at });
+        Ok(())
+    }
+
+    /// Records the values of the data, e.g., a daily file of the fixings,
+    /// registering the series if it is new.
+    /// The new dates are appended and the changed values are corrected.
+    /// It returns the number of the recorded fixings.
+    pub fn ingest(&mut self, data: &DailyValueData, recorded_at: OffsetDateTime) -> Result<usize> {
+        let id = data.get_id();
+        if !self.series.contains_key(&id) {
+            self.register(data)?;
+        }
+        let (dates, values) = data.get_ordered_data_by_date();
+        let mut count = 0;
+        for (date, value) in dates.into_iter().zip(values) {
+            match self.get_fixing(&id, &date, None) {
+                None => self.append(&id, date, value, recorded_at)?,
+                Some(last) if last != value => self.correct(&id, date, value, recorded_at)?,
+                Some(_) => continue,
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// the fixing known at as_of, or the last version if as_of is None
+    pub fn get_fixing(
+        &self,
+        id: &StaticId,
+        date: &Date,
+        as_of: Option<&OffsetDateTime>,
+    ) -> Option<Real> {
+        self.series.get(id)?.get_fixing(date, as_of)
+    }
+
+    /// all the versions of the fixing in the order of the records
+    pub fn get_versions(&self, id: &StaticId, date: &Date) -> Option<&Vec<FixingVersion>> {
+        self.series.get(id)?.fixings.get(date)
+    }
+
+    pub fn to_daily_value_data(
+        &self,
+        id: &StaticId,
+        as_of: Option<&OffsetDateTime>,
+    ) -> Option<DailyValueData> {
+        self.series
+            .get(id)
+            .map(|series| series.to_daily_value_data(as_of))
+    }
+
+    /// the past_daily_value_data of EngineGenerator::with_data with the fixings known at as_of
+    pub fn to_past_daily_value_data(
+        &self,
+        as_of: Option<&OffsetDateTime>,
+    ) -> FxHashMap<StaticId, DailyValueData> {
+        self.series
+            .iter()
+            .map(|(id, series)| (*id, series.to_daily_value_data(as_of)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, datetime};
+
+    fn cd91() -> DailyValueData {
+        let mut data = DailyValueData {
+            id: StaticId::from_str("CD91", "KAP"),
+            name: "CD91".to_string(),
+            ..Default::default()
+        };
+        data.insert(date!(2024 - 03 - 11), 0.0365);
+        data.insert(date!(2024 - 03 - 12), 0.0366);
+        data
+    }
+
+    #[test]
+    fn test_fixing_store() -> Result<()> {
+        let id = StaticId::from_str("CD91", "KAP");
+        let mut store = FixingStore::new();
+        assert_eq!(
+            store.ingest(&cd91(), datetime!(2024-03-12 18:00:00 +09:00))?,
+            2
+        );
+        // the same file again records nothing
+        assert_eq!(
+            store.ingest(&cd91(), datetime!(2024-03-12 19:00:00 +09:00))?,
+            0
+        );
+
+        store.append(
+            &id,
+            date!(2024 - 03 - 13),
+            0.0367,
+            datetime!(2024-03-13 18:00:00 +09:00),
+        )?;
+        assert!(store
+            .append(
+                &id,
+                date!(2024 - 03 - 13),
+                0.0368,
+                datetime!(2024-03-13 19:00:00 +09:00)
+            )
+            .is_err());
+        // republished on the next day
+        store.correct(
+            &id,
+            date!(2024 - 03 - 13),
+            0.0368,
+            datetime!(2024-03-14 09:00:00 +09:00),
+        )?;
+        assert!(store
+            .correct(
+                &id,
+                date!(2024 - 03 - 13),
+                0.0369,
+                datetime!(2024-03-13 20:00:00 +09:00)
+            )
+            .is_err());
+        assert!(store
+            .correct(
+                &id,
+                date!(2024 - 03 - 14),
+                0.0369,
+                datetime!(2024-03-14 18:00:00 +09:00)
+            )
+            .is_err());
+
+        let date = date!(2024 - 03 - 13);
+        assert_eq!(store.get_fixing(&id, &date, None), Some(0.0368));
+        let before_correction = datetime!(2024-03-13 23:00:00 +09:00);
+        assert_eq!(
+            store.get_fixing(&id, &date, Some(&before_correction)),
+            Some(0.0367)
+        );
+        let before_fixing = datetime!(2024-03-13 12:00:00 +09:00);
+        assert_eq!(store.get_fixing(&id, &date, Some(&before_fixing)), None);
+        assert_eq!(store.get_versions(&id, &date).unwrap().len(), 2);
+
+        let past_data = store.to_past_daily_value_data(Some(&before_correction));
+        let data = &past_data[&id];
+        assert_eq!(data.get_value().len(), 3);
+        assert_eq!(data.get(&date), Some(&0.0367));
+        assert_eq!(data.get_calendar(), cd91().get_calendar());
+
+        let json = serde_json::to_string(&store)?;
+        let loaded: FixingStore = serde_json::from_str(&json)?;
+        assert_eq!(loaded, store);
+
+        let unknown = StaticId::from_str("KOFR", "KAP");
+        assert!(store
+            .append(&unknown, date, 0.035, datetime!(2024-03-13 18:00:00 +09:00))
+            .is_err());
+        assert!(store.register(&cd91()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+use crate::currency::Currency;
+use crate::data::vector_data::VectorData;
+use crate::definitions::{Real, Time};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+use anyhow::{anyhow, Result};
+use ndarray::Array1;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use time::OffsetDateTime;
+
+/// A quote of a short-term interest rate futures (e.g., 3M SOFR, 3M KOFR)
+/// The price is quoted as 100 - (rate in percent) over the period [start_date, end_date]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RateFuturesQuote {
+    #[serde(with = "crate::time::datetime_serde::datetime")]
+    pub start_date: OffsetDateTime,
+    #[serde(with = "crate::time::datetime_serde::datetime")]
+    pub end_date: OffsetDateTime,
+    pub price: Real,
+}
+
+impl RateFuturesQuote {
+    pub fn new(
+        start_date: OffsetDateTime,
+        end_date: OffsetDateTime,
+        price: Real,
+    ) -> RateFuturesQuote {
+        RateFuturesQuote {
+            start_date,
+            end_date,
+            price,
+        }
+    }
+
+    /// simple rate implied by the futures price
+    pub fn get_futures_rate(&self) -> Real {
+        (100.0 - self.price) / 100.0
+    }
+}
+
+/// Strip of rate futures which generates a zero rate VectorData.
+/// The generated data can be used in place of a market zero curve data
+/// for the curves in rate_index_forward_curve_map.
+///
+/// * The futures rate is converted to a forward rate by the convexity adjustment of Ho-Lee model,
+///   i.e., forward = futures - 0.5 * sigma^2 * t1 * t2
+/// * The period from market_datetime to the start of the first futures is the stub.
+///   If stub_rate (continuous compounding) is not given, the first forward rate is extended flat.
+/// * Gaps between contracts are filled by the next contract's forward rate,
+///   and overlaps are resolved by the piecewise flat forward rates already bootstrapped.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FuturesStrip {
+    quotes: Vec<RateFuturesQuote>,
+    #[serde(with = "crate::time::datetime_serde::datetime")]
+    market_datetime: OffsetDateTime,
+    convexity_volatility: Real,
+    stub_rate: Option<Real>,
+    currency: Currency,
+    name: String,
+    id: StaticId,
+}
+
+impl FuturesStrip {
+    pub fn new(
+        quotes: Vec<RateFuturesQuote>,
+        market_datetime: OffsetDateTime,
+        currency: Currency,
+        name: String,
+        id: StaticId,
+    ) -> Result<FuturesStrip> {
+        if quotes.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) quotes of FuturesStrip ({}) is empty",
+                file!(),
+                line!(),
+                name
+            ));
+        }
+
+        for quote in quotes.iter() {
+            if quote.start_date >= quote.end_date {
+                return Err(anyhow!(
+                    "({}:{}) start_date must be earlier than end_date in FuturesStrip ({})\n\
+                    quote: {:?}",
+                    file!(),
+                    line!(),
+                    name,
+                    quote
+                ));
+            }
+        }
+
+        for i in 1..quotes.len() {
+            if quotes[i].end_date <= quotes[i - 1].end_date {
+                return Err(anyhow!(
+                    "({}:{}) end_dates of FuturesStrip ({}) must be strictly increasing\n\
+                    previous: {:?}, current: {:?}",
+                    file!(),
+                    line!(),
+                    name,
+                    quotes[i - 1],
+                    quotes[i]
+                ));
+            }
+        }
+
+        if quotes[0].end_date <= market_datetime {
+            return Err(anyhow!(
+                "({}:{}) the first futures in FuturesStrip ({}) ends before market_datetime ({:?})",
+                file!(),
+                line!(),
+                name,
+                market_datetime
+            ));
+        }
+
+        Ok(FuturesStrip {
+            quotes,
+            market_datetime,
+            convexity_volatility: 0.0,
+            stub_rate: None,
+            currency,
+            name,
+            id,
+        })
+    }
+
+    pub fn with_convexity_volatility(mut self, convexity_volatility: Real) -> FuturesStrip {
+        self.convexity_volatility = convexity_volatility;
+        self
+    }
+
+    pub fn with_stub_rate(mut self, stub_rate: Real) -> FuturesStrip {
+        self.stub_rate = Some(stub_rate);
+        self
+    }
+
+    pub fn get_quotes(&self) -> &Vec<RateFuturesQuote> {
+        &self.quotes
+    }
+
+    pub fn get_id(&self) -> StaticId {
+        self.id
+    }
+
+    pub fn get_name_clone(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Ho-Lee convexity adjustment: 0.5 * sigma^2 * t1 * t2
+    pub fn convexity_adjustment(&self, t1: Time, t2: Time) -> Real {
+        0.5 * self.convexity_volatility * self.convexity_volatility * t1.max(0.0) * t2
+    }
+
+    /// continuously compounded forward rates of each futures after the convexity adjustment
+    pub fn get_forward_rates(&self) -> Array1<Real> {
+        let time_calculator = NullCalendar::default();
+        self.quotes
+            .iter()
+            .map(|quote| {
+                let t1 =
+                    time_calculator.get_time_difference(&self.market_datetime, &quote.start_date);
+                let t2 =
+                    time_calculator.get_time_difference(&self.market_datetime, &quote.end_date);
+                let tau = t2 - t1;
+                let simple = quote.get_futures_rate() - self.convexity_adjustment(t1, t2);
+                (1.0 + simple * tau).ln() / tau
+            })
+            .collect()
+    }
+
+    /// Bootstrap the zero rates (continuous compounding) on the stub end and the end date of each futures.
+    /// The result has dates and market_datetime so that ZeroCurve can be built directly from it.
+    pub fn to_vector_data(&self) -> Result<VectorData> {
+        let time_calculator = NullCalendar::default();
+        let forwards = self.get_forward_rates();
+
+        // (time, -log(discount factor)) nodes. piecewise linear in time means piecewise flat forward
+        let mut nodes: Vec<(Time, Real)> = vec![(0.0, 0.0)];
+        let mut dates: Vec<OffsetDateTime> = Vec::new();
+
+        let first_start = self.quotes[0].start_date;
+        if first_start > self.market_datetime {
+            let t = time_calculator.get_time_difference(&self.market_datetime, &first_start);
+            let rate = self.stub_rate.unwrap_or(forwards[0]);
+            nodes.push((t, rate * t));
+            dates.push(first_start);
+        }
+
+        for (quote, forward) in self.quotes.iter().zip(forwards.iter()) {
+            let t1 = time_calculator
+                .get_time_difference(&self.market_datetime, &quote.start_date)
+                .max(0.0);
+            let t2 = time_calculator.get_time_difference(&self.market_datetime, &quote.end_date);
+            let (t_last, l_last) = *nodes.last().unwrap();
+
+            let l_start = if t1 >= t_last {
+                l_last + forward * (t1 - t_last)
+            } else {
+                let idx = nodes.iter().rposition(|(t, _)| *t <= t1).unwrap();
+                let (t_prev, l_prev) = nodes[idx];
+                let (t_next, l_next) = nodes[idx + 1];
+                l_prev + (l_next - l_prev) * (t1 - t_prev) / (t_next - t_prev)
+            };
+
+            nodes.push((t2, l_start + forward * (t2 - t1)));
+            dates.push(quote.end_date);
+        }
+
+        let value: Array1<Real> = nodes.iter().skip(1).map(|(t, l)| l / t).collect();
+
+        VectorData::new(
+            value,
+            Some(dates),
+            None,
+            Some(self.market_datetime),
+            self.currency,
+            self.name.clone(),
+            self.id,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation_date::EvaluationDate;
+    use crate::parameters::zero_curve::ZeroCurve;
+    use std::sync::{Arc, RwLock};
+    use time::macros::datetime;
+
+    fn test_quotes() -> Vec<RateFuturesQuote> {
+        vec![
+            RateFuturesQuote::new(
+                datetime!(2024-03-20 00:00:00 +09:00),
+                datetime!(2024-06-20 00:00:00 +09:00),
+                96.5,
+            ),
+            RateFuturesQuote::new(
+                datetime!(2024-06-20 00:00:00 +09:00),
+                datetime!(2024-09-20 00:00:00 +09:00),
+                96.7,
+            ),
+            RateFuturesQuote::new(
+                datetime!(2024-09-20 00:00:00 +09:00),
+                datetime!(2024-12-20 00:00:00 +09:00),
+                96.9,
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_futures_strip_forward_reproduction() -> Result<()> {
+        let market_datetime = datetime!(2024-01-02 16:30:00 +09:00);
+        let strip = FuturesStrip::new(
+            test_quotes(),
+            market_datetime,
+            Currency::KRW,
+            "KOFR Futures Strip".to_string(),
+            StaticId::from_str("KOFR Futures Strip", "test"),
+        )?
+        .with_stub_rate(0.035);
+
+        let data = strip.to_vector_data()?;
+        assert_eq!(data.get_value_clone().len(), 4);
+
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(market_datetime)));
+        let curve = ZeroCurve::new(
+            evaluation_date,
+            &data,
+            "KOFR Futures Strip".to_string(),
+            StaticId::from_str("KOFR Futures Strip", "test"),
+        )?;
+
+        // the forward rate between the contract dates must reproduce the futures rate
+        // (the contracts are contiguous, so the previous node is the start of each contract)
+        let values = data.get_value_clone();
+        let times = data.get_times_clone();
+        for (i, quote) in strip.get_quotes().iter().enumerate() {
+            let df1 = (-values[i] * times[i]).exp();
+            let df2 = (-values[i + 1] * times[i + 1]).exp();
+            let simple = (df1 / df2 - 1.0) / (times[i + 1] - times[i]);
+            assert!(
+                (simple - quote.get_futures_rate()).abs() < 1.0e-5,
+                "simple: {}, futures rate: {}",
+                simple,
+                quote.get_futures_rate()
+            );
+        }
+
+        assert!(curve.get_discount_factor(0.5)? < 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_futures_strip_convexity_adjustment() -> Result<()> {
+        let market_datetime = datetime!(2024-01-02 16:30:00 +09:00);
+        let strip = FuturesStrip::new(
+            test_quotes(),
+            market_datetime,
+            Currency::KRW,
+            "KOFR Futures Strip".to_string(),
+            StaticId::from_str("KOFR Futures Strip", "test"),
+        )?;
+
+        let unadjusted = strip.clone().to_vector_data()?.get_value_clone();
+        let adjusted = strip
+            .with_convexity_volatility(0.01)
+            .to_vector_data()?
+            .get_value_clone();
+
+        for (a, u) in adjusted.iter().zip(unadjusted.iter()) {
+            assert!(a < u, "adjusted: {}, unadjusted: {}", a, u);
+        }
+        Ok(())
+    }
+}
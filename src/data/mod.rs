@@ -3,3 +3,7 @@ pub mod value_data;
 pub mod vector_data;
 //pub mod observable;
 pub mod daily_value_data;
+pub mod fixing_store;
+pub mod futures_strip;
+pub mod nelson_siegel;
+pub mod quote_conversion;
@@ -0,0 +1,595 @@
+use crate::currency::Currency;
+use crate::data::vector_data::VectorData;
+use crate::definitions::{Real, Time};
+use crate::instrument::InstrumentTrait;
+use crate::instruments::bond::Bond;
+use crate::math::optimizers::{
+    levenberg_marquardt, BoxConstraints, OptimizationResult, OptimizerOptions,
+};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Result};
+use ndarray::Array1;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use time::OffsetDateTime;
+
+/// the initial decays of the multi-start fit
+const TAU_STARTS: [Real; 4] = [0.3, 1.0, 3.0, 10.0];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum NelsonSiegelType {
+    NelsonSiegel,
+    Svensson,
+}
+
+/// Parameters of Nelson-Siegel(-Svensson) zero rate (continuous compounding):
+///
+/// r(t) = beta0
+///      + beta1 * (1 - exp(-t/tau1)) / (t/tau1)
+///      + beta2 * ((1 - exp(-t/tau1)) / (t/tau1) - exp(-t/tau1))
+///      + beta3 * ((1 - exp(-t/tau2)) / (t/tau2) - exp(-t/tau2))
+///
+/// beta3 and tau2 are not used in the Nelson-Siegel model.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct NelsonSiegelParameters {
+    pub model_type: NelsonSiegelType,
+    pub beta0: Real,
+    pub beta1: Real,
+    pub beta2: Real,
+    pub beta3: Real,
+    pub tau1: Real,
+    pub tau2: Real,
+}
+
+impl NelsonSiegelParameters {
+    pub fn new_nelson_siegel(beta0: Real, beta1: Real, beta2: Real, tau1: Real) -> Self {
+        NelsonSiegelParameters {
+            model_type: NelsonSiegelType::NelsonSiegel,
+            beta0,
+            beta1,
+            beta2,
+            beta3: 0.0,
+            tau1,
+            tau2: 1.0,
+        }
+    }
+
+    pub fn new_svensson(
+        beta0: Real,
+        beta1: Real,
+        beta2: Real,
+        beta3: Real,
+        tau1: Real,
+        tau2: Real,
+    ) -> Self {
+        NelsonSiegelParameters {
+            model_type: NelsonSiegelType::Svensson,
+            beta0,
+            beta1,
+            beta2,
+            beta3,
+            tau1,
+            tau2,
+        }
+    }
+
+    /// (loading of the slope, loading of the curvature)
+    fn loadings(t: Time, tau: Real) -> (Real, Real) {
+        if t <= 1.0e-8 {
+            return (1.0, 0.0);
+        }
+        let x = t / tau;
+        let decay = (-x).exp();
+        let slope = (1.0 - decay) / x;
+        (slope, slope - decay)
+    }
+
+    pub fn get_zero_rate(&self, t: Time) -> Real {
+        let (slope1, curvature1) = Self::loadings(t, self.tau1);
+        let mut res = self.beta0 + self.beta1 * slope1 + self.beta2 * curvature1;
+        if self.model_type == NelsonSiegelType::Svensson {
+            let (_, curvature2) = Self::loadings(t, self.tau2);
+            res += self.beta3 * curvature2;
+        }
+        res
+    }
+
+    pub fn get_discount_factor(&self, t: Time) -> Real {
+        (-self.get_zero_rate(t) * t).exp()
+    }
+}
+
+/// Fits Nelson-Siegel(-Svensson) parameters to zero yields or bond prices.
+///
+/// The fit is a Levenberg-Marquardt minimization of the squared yield errors with the constraints
+/// * beta0 > 0 (long rate) and beta0 + beta1 > 0 (short rate), imposed by penalty
+/// * tau_bounds.0 <= tau <= tau_bounds.1, imposed by the box constraints on ln(tau)
+///
+/// The price errors of bonds are divided by the dollar durations so that they are the yield errors,
+/// which keeps the short and the long bonds comparable. The decays are fitted from a few starts
+/// since the cost is not convex in tau, and the fit of the least cost is taken.
+///
+/// The fitted curve is exported as a zero rate VectorData so that a ZeroCurve can be built from it,
+/// e.g., for a smooth government curve in place of a bootstrapped one.
+#[derive(Debug, Clone)]
+pub struct NelsonSiegelFitter {
+    model_type: NelsonSiegelType,
+    market_datetime: OffsetDateTime,
+    tau_bounds: (Real, Real),
+    max_iters: u64,
+    tolerance: Real,
+    currency: Currency,
+    name: String,
+    id: StaticId,
+}
+
+struct BondQuote {
+    // (time, amount)
+    cashflows: Vec<(Time, Real)>,
+    price: Real,
+    // price * duration, which turns the price error into the yield error
+    dollar_duration: Real,
+}
+
+struct NelsonSiegelProblem {
+    // yield fitting: (time, yield)
+    yields: Vec<(Time, Real)>,
+    // price fitting
+    bonds: Vec<BondQuote>,
+}
+
+impl NelsonSiegelProblem {
+    fn residuals(&self, params: &NelsonSiegelParameters) -> Vec<Real> {
+        let mut res = Vec::with_capacity(self.yields.len() + self.bonds.len() + 2);
+        for (t, y) in self.yields.iter() {
+            res.push(params.get_zero_rate(*t) - y);
+        }
+        for bond in self.bonds.iter() {
+            let model: Real = bond
+                .cashflows
+                .iter()
+                .map(|(t, amount)| amount * params.get_discount_factor(*t))
+                .sum();
+            res.push((model - bond.price) / bond.dollar_duration);
+        }
+        // beta0 > 0, beta0 + beta1 > 0
+        let weight = (1.0e3 as Real).sqrt();
+        res.push(weight * (-params.beta0).max(0.0));
+        res.push(weight * (-params.beta0 - params.beta1).max(0.0));
+        res
+    }
+}
+
+impl NelsonSiegelFitter {
+    pub fn new(
+        model_type: NelsonSiegelType,
+        market_datetime: OffsetDateTime,
+        currency: Currency,
+        name: String,
+        id: StaticId,
+    ) -> NelsonSiegelFitter {
+        NelsonSiegelFitter {
+            model_type,
+            market_datetime,
+            tau_bounds: (0.05, 30.0),
+            max_iters: 2_000,
+            tolerance: 1.0e-10,
+            currency,
+            name,
+            id,
+        }
+    }
+
+    pub fn with_tau_bounds(mut self, lower: Real, upper: Real) -> Result<NelsonSiegelFitter> {
+        if lower <= 0.0 || lower >= upper {
+            return Err(anyhow!(
+                "({}:{}) invalid tau bounds ({}, {}) in NelsonSiegelFitter ({})",
+                file!(),
+                line!(),
+                lower,
+                upper,
+                self.name
+            ));
+        }
+        self.tau_bounds = (lower, upper);
+        Ok(self)
+    }
+
+    pub fn with_max_iters(mut self, max_iters: u64) -> NelsonSiegelFitter {
+        self.max_iters = max_iters;
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: Real) -> NelsonSiegelFitter {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// x = (beta0, beta1, beta2, ln(tau1)) or (beta0, beta1, beta2, ln(tau1), beta3, ln(tau2))
+    fn to_parameters(&self, x: &[Real]) -> NelsonSiegelParameters {
+        let (lower, upper) = self.tau_bounds;
+        let tau = |log_tau: Real| log_tau.exp().clamp(lower, upper);
+        match self.model_type {
+            NelsonSiegelType::NelsonSiegel => {
+                NelsonSiegelParameters::new_nelson_siegel(x[0], x[1], x[2], tau(x[3]))
+            }
+            NelsonSiegelType::Svensson => NelsonSiegelParameters::new_svensson(
+                x[0],
+                x[1],
+                x[2],
+                x[4],
+                tau(x[3]),
+                tau(x[5]),
+            ),
+        }
+    }
+
+    fn minimize(
+        &self,
+        problem: NelsonSiegelProblem,
+        short: Real,
+        long: Real,
+    ) -> Result<(NelsonSiegelParameters, OptimizationResult)> {
+        let (lower, upper) = (self.tau_bounds.0.ln(), self.tau_bounds.1.ln());
+        let mut constraints = BoxConstraints::unbounded(4).with_bound(3, lower, upper)?;
+        if self.model_type == NelsonSiegelType::Svensson {
+            constraints = BoxConstraints::unbounded(6)
+                .with_bound(3, lower, upper)?
+                .with_bound(5, lower, upper)?;
+        }
+        // the residuals are the yields, so the gradient can not be resolved below the precision of Real
+        let options = OptimizerOptions::default()
+            .with_max_iterations(self.max_iters as usize)
+            .with_cost_tolerance(self.tolerance)
+            .with_gradient_tolerance(Real::EPSILON);
+
+        let mut residuals = |x: &[Real]| Ok(problem.residuals(&self.to_parameters(x)));
+        let mut best: Option<OptimizationResult> = None;
+        for tau in TAU_STARTS {
+            let mut init = vec![long, short - long, 0.0, tau.ln().clamp(lower, upper)];
+            if self.model_type == NelsonSiegelType::Svensson {
+                init.extend([0.0, (5.0 * tau).ln().clamp(lower, upper)]);
+            }
+            let res = levenberg_marquardt(&mut residuals, &init, Some(&constraints), &options)
+                .map_err(|e| {
+                    anyhow!(
+                        "({}:{}) failed to fit NelsonSiegelFitter ({}): {}",
+                        file!(),
+                        line!(),
+                        self.name,
+                        e
+                    )
+                })?;
+            if best.as_ref().is_none_or(|best| res.get_cost() < best.get_cost()) {
+                best = Some(res);
+            }
+        }
+        let res = best.ok_or_else(|| {
+            anyhow!("({}:{}) no start in NelsonSiegelFitter ({})", file!(), line!(), self.name)
+        })?;
+        Ok((self.to_parameters(res.get_params()), res))
+    }
+
+    /// yields are continuously compounded zero rates on the times
+    pub fn fit_to_yields(
+        &self,
+        times: &Array1<Time>,
+        yields: &Array1<Real>,
+    ) -> Result<NelsonSiegelParameters> {
+        Ok(self.fit_to_yields_with_result(times, yields)?.0)
+    }
+
+    /// fit_to_yields with the convergence diagnostics of the optimizer
+    pub fn fit_to_yields_with_result(
+        &self,
+        times: &Array1<Time>,
+        yields: &Array1<Real>,
+    ) -> Result<(NelsonSiegelParameters, OptimizationResult)> {
+        if times.is_empty() || times.len() != yields.len() {
+            return Err(anyhow!(
+                "({}:{}) times ({}) and yields ({}) must have the same non-zero length in NelsonSiegelFitter ({})",
+                file!(),
+                line!(),
+                times.len(),
+                yields.len(),
+                self.name
+            ));
+        }
+        let problem = NelsonSiegelProblem {
+            yields: times.iter().copied().zip(yields.iter().copied()).collect(),
+            bonds: vec![],
+        };
+        self.minimize(problem, yields[0], yields[yields.len() - 1])
+    }
+
+    /// prices are dirty prices per unit notional of fixed coupon bonds on market_datetime
+    pub fn fit_to_bond_prices(
+        &self,
+        bonds: &[Bond],
+        prices: &[Real],
+    ) -> Result<NelsonSiegelParameters> {
+        Ok(self.fit_to_bond_prices_with_result(bonds, prices)?.0)
+    }
+
+    /// fit_to_bond_prices with the convergence diagnostics of the optimizer
+    pub fn fit_to_bond_prices_with_result(
+        &self,
+        bonds: &[Bond],
+        prices: &[Real],
+    ) -> Result<(NelsonSiegelParameters, OptimizationResult)> {
+        if bonds.is_empty() || bonds.len() != prices.len() {
+            return Err(anyhow!(
+                "({}:{}) bonds ({}) and prices ({}) must have the same non-zero length in NelsonSiegelFitter ({})",
+                file!(),
+                line!(),
+                bonds.len(),
+                prices.len(),
+                self.name
+            ));
+        }
+        let time_calculator = NullCalendar::default();
+        let mut bond_data = Vec::new();
+        let mut yields = Vec::new();
+        for (bond, price) in bonds.iter().zip(prices.iter()) {
+            if !price.is_finite() || *price <= 0.0 {
+                return Err(anyhow!(
+                    "({}:{}) non-positive price {} of {} ({}) in NelsonSiegelFitter ({})",
+                    file!(),
+                    line!(),
+                    price,
+                    bond.get_name(),
+                    bond.get_id(),
+                    self.name
+                ));
+            }
+            if bond.get_rate_index()?.is_some() {
+                return Err(anyhow!(
+                    "({}:{}) floating rate bond {} ({}) can not be used in NelsonSiegelFitter ({})",
+                    file!(),
+                    line!(),
+                    bond.get_name(),
+                    bond.get_id(),
+                    self.name
+                ));
+            }
+            let mut cashflows: Vec<(Time, Real)> = bond
+                .get_cashflows(&self.market_datetime, None, None)?
+                .iter()
+                .filter(|(date, _)| date.date() > self.market_datetime.date())
+                .map(|(date, amount)| {
+                    (
+                        time_calculator.get_time_difference(&self.market_datetime, date),
+                        *amount,
+                    )
+                })
+                .collect();
+            if cashflows.is_empty() {
+                return Err(anyhow!(
+                    "({}:{}) {} ({}) has no cashflow after {} in NelsonSiegelFitter ({})",
+                    file!(),
+                    line!(),
+                    bond.get_name(),
+                    bond.get_id(),
+                    self.market_datetime,
+                    self.name
+                ));
+            }
+            cashflows.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            // rough yield for the initial guess
+            let t_last = cashflows[cashflows.len() - 1].0;
+            let amount: Real = cashflows.iter().map(|(_, a)| a).sum();
+            let rough_yield = (amount / price).ln() / t_last.max(1.0e-4);
+            yields.push((t_last, rough_yield));
+            // the Macaulay duration on the rough yield
+            let (value, weighted_time) =
+                cashflows.iter().fold((0.0, 0.0), |(v, wt), (t, a)| {
+                    let pv = a * (-rough_yield * t).exp();
+                    (v + pv, wt + t * pv)
+                });
+            let duration = (weighted_time / value).max(1.0e-4);
+            bond_data.push(BondQuote {
+                cashflows,
+                price: *price,
+                dollar_duration: price * duration,
+            });
+        }
+        yields.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let problem = NelsonSiegelProblem {
+            yields: vec![],
+            bonds: bond_data,
+        };
+        self.minimize(problem, yields[0].1, yields[yields.len() - 1].1)
+    }
+
+    /// zero rates of the fitted curve on the times
+    pub fn to_vector_data(
+        &self,
+        parameters: &NelsonSiegelParameters,
+        times: Array1<Time>,
+    ) -> Result<VectorData> {
+        let value = times.map(|t| parameters.get_zero_rate(*t));
+        VectorData::new(
+            value,
+            None,
+            Some(times),
+            Some(self.market_datetime),
+            self.currency,
+            self.name.clone(),
+            self.id,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::{CreditRating, IssuerType, RankType};
+    use crate::instruments::bond::BondInfo;
+    use crate::time::conventions::{BusinessDayConvention, DayCountConvention, PaymentFrequency};
+    use crate::time::jointcalendar::JointCalendar;
+    use crate::{AccountingLevel, InstInfo, InstType};
+    use ndarray::array;
+    use time::macros::datetime;
+
+    fn make_bond(code: &str, maturity: OffsetDateTime, coupon: Real) -> Result<Bond> {
+        let inst_info = InstInfo::new(
+            StaticId::from_str(code, "KRX"),
+            code.to_string(),
+            InstType::Bond,
+            Currency::KRW,
+            10_000.0,
+            Some(datetime!(2023-01-02 16:30:00 +09:00)),
+            Some(maturity),
+            AccountingLevel::L1,
+        );
+        let bond_info = BondInfo {
+            issuer_type: IssuerType::Government,
+            credit_rating: CreditRating::None,
+            issuer_id: StaticId::from_str("Korea Gov", "KRX"),
+            rank: RankType::Undefined,
+        };
+        Bond::new_from_conventions(
+            inst_info,
+            bond_info,
+            false,
+            None,
+            None,
+            None,
+            Some(coupon),
+            None,
+            None,
+            None,
+            JointCalendar::default(),
+            true,
+            DayCountConvention::StreetConvention,
+            BusinessDayConvention::Unadjusted,
+            PaymentFrequency::SemiAnnually,
+            0,
+            0,
+        )
+    }
+
+    fn price_bond(
+        bond: &Bond,
+        dt: &OffsetDateTime,
+        params: &NelsonSiegelParameters,
+    ) -> Result<Real> {
+        let time_calculator = NullCalendar::default();
+        Ok(bond
+            .get_cashflows(dt, None, None)?
+            .iter()
+            .filter(|(date, _)| date.date() > dt.date())
+            .map(|(date, amount)| {
+                amount * params.get_discount_factor(time_calculator.get_time_difference(dt, date))
+            })
+            .sum())
+    }
+
+    #[test]
+    fn test_nelson_siegel_fit_to_bond_prices() -> Result<()> {
+        let market_datetime = datetime!(2024-01-02 16:30:00 +09:00);
+        let target = NelsonSiegelParameters::new_nelson_siegel(0.04, -0.01, 0.005, 2.0);
+        let bonds = vec![
+            make_bond("KTB1Y", datetime!(2025-01-02 16:30:00 +09:00), 0.030)?,
+            make_bond("KTB2Y", datetime!(2026-01-02 16:30:00 +09:00), 0.032)?,
+            make_bond("KTB3Y", datetime!(2027-01-02 16:30:00 +09:00), 0.033)?,
+            make_bond("KTB5Y", datetime!(2029-01-02 16:30:00 +09:00), 0.035)?,
+            make_bond("KTB10Y", datetime!(2034-01-02 16:30:00 +09:00), 0.036)?,
+            make_bond("KTB20Y", datetime!(2043-01-02 16:30:00 +09:00), 0.038)?,
+        ];
+        let prices = bonds
+            .iter()
+            .map(|bond| price_bond(bond, &market_datetime, &target))
+            .collect::<Result<Vec<Real>>>()?;
+
+        let fitter = NelsonSiegelFitter::new(
+            NelsonSiegelType::NelsonSiegel,
+            market_datetime,
+            Currency::KRW,
+            "KRWGOV NS".to_string(),
+            StaticId::from_str("KRWGOV NS", "test"),
+        );
+        let (fitted, result) = fitter.fit_to_bond_prices_with_result(&bonds, &prices)?;
+        // a single start may converge to the local minimum at tau = 0.74 of the price error 2.5e-4
+        assert!(result.is_converged(), "{:?}", result);
+        assert!((fitted.tau1 - 2.0).abs() < 1.0e-3, "{:?}", fitted);
+        for (bond, price) in bonds.iter().zip(prices.iter()) {
+            let fitted_price = price_bond(bond, &market_datetime, &fitted)?;
+            assert!(
+                (fitted_price - price).abs() < 1.0e-5,
+                "{}: fitted price {}, price {}",
+                bond.get_name(),
+                fitted_price,
+                price
+            );
+        }
+
+        // a matured bond and a zero price have no initial yield
+        let matured = make_bond("KTB-MATURED", datetime!(2023-07-02 16:30:00 +09:00), 0.03)?;
+        assert!(fitter.fit_to_bond_prices(&[matured], &[1.0]).is_err());
+        assert!(fitter.fit_to_bond_prices(&bonds[..1], &[0.0]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_nelson_siegel_fit_to_yields() -> Result<()> {
+        let market_datetime = datetime!(2024-01-02 16:30:00 +09:00);
+        let target = NelsonSiegelParameters::new_nelson_siegel(0.04, -0.01, 0.005, 2.0);
+        let times = array![0.25, 0.5, 1.0, 2.0, 3.0, 5.0, 7.0, 10.0, 20.0];
+        let yields = times.map(|t| target.get_zero_rate(*t));
+
+        let fitter = NelsonSiegelFitter::new(
+            NelsonSiegelType::NelsonSiegel,
+            market_datetime,
+            Currency::KRW,
+            "KRWGOV NS".to_string(),
+            StaticId::from_str("KRWGOV NS", "test"),
+        );
+        let (fitted, result) = fitter.fit_to_yields_with_result(&times, &yields)?;
+        assert!(result.is_converged(), "{:?}", result);
+        assert!(result.get_cost() < 1.0e-6);
+        assert!((fitted.tau1 - 2.0).abs() < 1.0e-3, "{:?}", fitted);
+        for t in times.iter() {
+            assert!(
+                (fitted.get_zero_rate(*t) - target.get_zero_rate(*t)).abs() < 2.0e-4,
+                "t: {}, fitted: {:?}",
+                t,
+                fitted
+            );
+        }
+
+        let data = fitter.to_vector_data(&fitted, times.clone())?;
+        assert_eq!(data.get_value_clone().len(), times.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_svensson_constraints() -> Result<()> {
+        let market_datetime = datetime!(2024-01-02 16:30:00 +09:00);
+        let times = array![0.25, 1.0, 2.0, 5.0, 10.0, 30.0];
+        let yields = array![0.035, 0.033, 0.032, 0.033, 0.034, 0.036];
+
+        let fitter = NelsonSiegelFitter::new(
+            NelsonSiegelType::Svensson,
+            market_datetime,
+            Currency::KRW,
+            "KRWGOV NSS".to_string(),
+            StaticId::from_str("KRWGOV NSS", "test"),
+        )
+        .with_tau_bounds(0.1, 10.0)?;
+        let fitted = fitter.fit_to_yields(&times, &yields)?;
+        assert!(fitted.beta0 > 0.0 && fitted.beta0 + fitted.beta1 > 0.0);
+        assert!((0.1..=10.0).contains(&fitted.tau1) && (0.1..=10.0).contains(&fitted.tau2));
+        for (t, y) in times.iter().zip(yields.iter()) {
+            assert!(
+                (fitted.get_zero_rate(*t) - y).abs() < 1.0e-3,
+                "t: {}, fitted: {:?}",
+                t,
+                fitted
+            );
+        }
+        Ok(())
+    }
+}
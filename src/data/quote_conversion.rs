@@ -0,0 +1,686 @@
+use crate::currency::{Currency, FxCode};
+use crate::data::{futures_strip::RateFuturesQuote, vector_data::VectorData};
+use crate::definitions::{Real, Time};
+use crate::math::interpolator::{ExtraPolationType, InterpolatorReal1D};
+use crate::math::interpolators::linear_interpolator::LinearInterpolator1D;
+use crate::math::solvers::{brent, SolverOptions};
+use crate::time::{
+    calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar,
+    conventions::PaymentFrequency,
+};
+use crate::utils::string_arithmetic::sub_period;
+//
+use anyhow::{anyhow, Context, Result};
+use ndarray::Array1;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use time::OffsetDateTime;
+
+/// A market quote of a rate instrument starting at the market datetime
+/// (the times are ACT/365 from the market datetime as in VectorData)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum CurveQuote {
+    /// simple rate to the maturity
+    Deposit {
+        #[serde(with = "crate::time::datetime_serde::datetime")]
+        maturity: OffsetDateTime,
+        rate: Real,
+    },
+    /// rate futures price (100 - rate in percent), whose maturity is the end date
+    Futures(RateFuturesQuote),
+    /// par rate of a swap whose fixed leg pays at the frequency,
+    /// discounted on the curve itself (single curve)
+    ParSwap {
+        #[serde(with = "crate::time::datetime_serde::datetime")]
+        maturity: OffsetDateTime,
+        rate: Real,
+        fixed_frequency: PaymentFrequency,
+    },
+    /// yield to maturity of a bond paying the coupon rate at the frequency,
+    /// compounded at the frequency. The price is the dirty price of the remaining coupons.
+    BondYield {
+        #[serde(with = "crate::time::datetime_serde::datetime")]
+        maturity: OffsetDateTime,
+        coupon_rate: Real,
+        yield_to_maturity: Real,
+        frequency: PaymentFrequency,
+    },
+}
+
+impl CurveQuote {
+    pub fn get_maturity(&self) -> OffsetDateTime {
+        match self {
+            CurveQuote::Deposit { maturity, .. }
+            | CurveQuote::ParSwap { maturity, .. }
+            | CurveQuote::BondYield { maturity, .. } => *maturity,
+            CurveQuote::Futures(quote) => quote.end_date,
+        }
+    }
+}
+
+/// The payment dates rolled backward from the maturity by the frequency,
+/// so that the first period is the short one. Only the maturity if the frequency is None.
+fn get_payment_dates(
+    market_datetime: &OffsetDateTime,
+    maturity: &OffsetDateTime,
+    frequency: PaymentFrequency,
+) -> Vec<OffsetDateTime> {
+    if frequency == PaymentFrequency::None {
+        return vec![*maturity];
+    }
+    let mut dates = vec![*maturity];
+    for multiple in 1.. {
+        let date = sub_period(maturity, &frequency.to_string_with_multiple(multiple));
+        if date <= *market_datetime {
+            break;
+        }
+        dates.push(date);
+    }
+    dates.reverse();
+    dates
+}
+
+/// Zero rates (continuous compounding) bootstrapped from the quotes of deposits, rate futures,
+/// par swaps and bond yields, so that the raw broker quotes can drive the engine as a zero curve data.
+///
+/// * The quotes are bootstrapped in the order of the maturities, one zero rate on each maturity.
+/// * The zero rates are linear in time and flat outside the nodes as in ZeroCurve,
+///   and the rate on each maturity is solved by Brent's method (math::solvers::brent) so that the quote is repriced.
+/// * The futures rate is converted to a forward rate by the convexity adjustment of Ho-Lee model
+///   as in FuturesStrip.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CurveQuotes {
+    quotes: Vec<CurveQuote>,
+    #[serde(with = "crate::time::datetime_serde::datetime")]
+    market_datetime: OffsetDateTime,
+    convexity_volatility: Real,
+    currency: Currency,
+    name: String,
+    id: StaticId,
+}
+
+impl CurveQuotes {
+    pub fn new(
+        mut quotes: Vec<CurveQuote>,
+        market_datetime: OffsetDateTime,
+        currency: Currency,
+        name: String,
+        id: StaticId,
+    ) -> Result<CurveQuotes> {
+        if quotes.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) quotes of CurveQuotes ({}) is empty",
+                file!(),
+                line!(),
+                name
+            ));
+        }
+        quotes.sort_by_key(|quote| quote.get_maturity());
+        for i in 0..quotes.len() {
+            if quotes[i].get_maturity() <= market_datetime {
+                return Err(anyhow!(
+                    "({}:{}) {:?} in CurveQuotes ({}) matures before market_datetime ({:?})",
+                    file!(),
+                    line!(),
+                    quotes[i],
+                    name,
+                    market_datetime
+                ));
+            }
+            if i > 0 && quotes[i].get_maturity() == quotes[i - 1].get_maturity() {
+                return Err(anyhow!(
+                    "({}:{}) two quotes in CurveQuotes ({}) mature at the same time\n\
+                    {:?}\n{:?}",
+                    file!(),
+                    line!(),
+                    name,
+                    quotes[i - 1],
+                    quotes[i]
+                ));
+            }
+            if let CurveQuote::Futures(quote) = &quotes[i] {
+                if quote.start_date >= quote.end_date {
+                    return Err(anyhow!(
+                        "({}:{}) start_date must be earlier than end_date in CurveQuotes ({})\n\
+                        quote: {:?}",
+                        file!(),
+                        line!(),
+                        name,
+                        quote
+                    ));
+                }
+            }
+        }
+
+        Ok(CurveQuotes {
+            quotes,
+            market_datetime,
+            convexity_volatility: 0.0,
+            currency,
+            name,
+            id,
+        })
+    }
+
+    pub fn with_convexity_volatility(mut self, convexity_volatility: Real) -> CurveQuotes {
+        self.convexity_volatility = convexity_volatility;
+        self
+    }
+
+    /// sorted by the maturities
+    pub fn get_quotes(&self) -> &Vec<CurveQuote> {
+        &self.quotes
+    }
+
+    pub fn get_id(&self) -> StaticId {
+        self.id
+    }
+
+    /// The zero rates on the maturities of the quotes.
+    /// The result has dates and market_datetime so that ZeroCurve can be built directly from it.
+    pub fn to_vector_data(&self) -> Result<VectorData> {
+        let mut nodes: Vec<(Time, Real)> = Vec::with_capacity(self.quotes.len());
+        for quote in self.quotes.iter() {
+            let maturity = self.get_time(&quote.get_maturity());
+            let times = self.get_times(quote);
+            let residual = |rate: Real| {
+                let discount = |t: Time| get_discount_factor(&nodes, (maturity, rate), t);
+                self.get_residual(quote, &times, discount)
+            };
+            let rate = brent(
+                &mut |rate| Ok(residual(rate)),
+                RATE_BOUNDS.0,
+                RATE_BOUNDS.1,
+                &SolverOptions::default(),
+            )
+            .with_context(|| {
+                anyhow!(
+                    "({}:{}) no zero rate in [{}, {}] reprices {:?} in CurveQuotes ({})",
+                    file!(),
+                    line!(),
+                    RATE_BOUNDS.0,
+                    RATE_BOUNDS.1,
+                    quote,
+                    self.name
+                )
+            })?;
+            nodes.push((maturity, rate));
+        }
+
+        VectorData::new(
+            nodes.iter().map(|(_, rate)| *rate).collect(),
+            Some(
+                self.quotes
+                    .iter()
+                    .map(|quote| quote.get_maturity())
+                    .collect(),
+            ),
+            None,
+            Some(self.market_datetime),
+            self.currency,
+            self.name.clone(),
+            self.id,
+        )
+    }
+
+    fn get_time(&self, date: &OffsetDateTime) -> Time {
+        NullCalendar::default().get_time_difference(&self.market_datetime, date)
+    }
+
+    /// the times of the discount factors in the residual of the quote,
+    /// i.e., the payment times of the swaps and the bonds
+    fn get_times(&self, quote: &CurveQuote) -> Vec<Time> {
+        match quote {
+            CurveQuote::Deposit { maturity, .. } => vec![self.get_time(maturity)],
+            CurveQuote::Futures(quote) => vec![
+                self.get_time(&quote.start_date).max(0.0),
+                self.get_time(&quote.end_date),
+            ],
+            CurveQuote::ParSwap {
+                maturity,
+                fixed_frequency: frequency,
+                ..
+            }
+            | CurveQuote::BondYield {
+                maturity,
+                frequency,
+                ..
+            } => get_payment_dates(&self.market_datetime, maturity, *frequency)
+                .iter()
+                .map(|date| self.get_time(date))
+                .collect(),
+        }
+    }
+
+    /// the quote repriced on the discount factors minus the quote, which decreases in the zero rate
+    fn get_residual<F: Fn(Time) -> Real>(
+        &self,
+        quote: &CurveQuote,
+        times: &[Time],
+        discount: F,
+    ) -> Real {
+        match quote {
+            CurveQuote::Deposit { rate, .. } => {
+                let t = times[0];
+                discount(t) * (1.0 + rate * t) - 1.0
+            }
+            CurveQuote::Futures(quote) => {
+                let (t1, t2) = (times[0], times[1]);
+                let convexity_adjustment =
+                    0.5 * self.convexity_volatility * self.convexity_volatility * t1 * t2;
+                let forward = quote.get_futures_rate() - convexity_adjustment;
+                1.0 + forward * (t2 - t1) - discount(t1) / discount(t2)
+            }
+            CurveQuote::ParSwap { rate, .. } => {
+                let mut previous: Time = 0.0;
+                let mut res: Real = -1.0;
+                for t in times.iter() {
+                    res += rate * (t - previous) * discount(*t);
+                    previous = *t;
+                }
+                res + discount(previous)
+            }
+            CurveQuote::BondYield {
+                coupon_rate,
+                yield_to_maturity,
+                frequency,
+                ..
+            } => {
+                let m = frequency.as_real().max(1.0);
+                let coupon = if *frequency == PaymentFrequency::None {
+                    0.0
+                } else {
+                    coupon_rate / m
+                };
+                let mut res: Real = 0.0;
+                for (i, t) in times.iter().enumerate() {
+                    let amount = if i == times.len() - 1 {
+                        1.0 + coupon
+                    } else {
+                        coupon
+                    };
+                    res += amount * (discount(*t) - (1.0 + yield_to_maturity / m).powf(-m * t));
+                }
+                res
+            }
+        }
+    }
+}
+
+const RATE_BOUNDS: (Real, Real) = (-1.0, 1.0);
+
+/// exp(-r(t) t) where r is linear in time on the nodes and the trial node, and flat outside
+fn get_discount_factor(nodes: &[(Time, Real)], trial: (Time, Real), t: Time) -> Real {
+    if t <= 0.0 {
+        return 1.0;
+    }
+    let (t_last, r_last) = nodes.last().copied().unwrap_or(trial);
+    let rate = if t >= trial.0 {
+        trial.1
+    } else if t >= t_last {
+        r_last + (trial.1 - r_last) * (t - t_last) / (trial.0 - t_last)
+    } else {
+        match nodes.iter().position(|(node_t, _)| *node_t >= t) {
+            Some(0) => nodes[0].1,
+            Some(i) => {
+                let (t0, r0) = nodes[i - 1];
+                let (t1, r1) = nodes[i];
+                r0 + (r1 - r0) * (t - t0) / (t1 - t0)
+            }
+            None => r_last,
+        }
+    };
+    (-rate * t).exp()
+}
+
+
+/// A quote of the fx forward points to the maturity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FxPointQuote {
+    #[serde(with = "crate::time::datetime_serde::datetime")]
+    pub maturity: OffsetDateTime,
+    pub points: Real,
+}
+
+impl FxPointQuote {
+    pub fn new(maturity: OffsetDateTime, points: Real) -> FxPointQuote {
+        FxPointQuote { maturity, points }
+    }
+}
+
+/// Fx forward points of the pair, where the forward is spot + points / point_unit,
+/// e.g., point_unit = 1 for USD/KRW (points in won) and 10,000 for EUR/USD (points in pips).
+///
+/// The zero curve of a currency implied by the points is given by the covered interest parity
+/// on the zero curve of the other currency: F / S = exp((r2 - r1) t) for FxCode (currency1, currency2).
+/// The spot is taken at the market datetime, i.e., the spot lag is not considered.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FxForwardPoints {
+    fx_code: FxCode,
+    spot: Real,
+    point_unit: Real,
+    quotes: Vec<FxPointQuote>,
+    #[serde(with = "crate::time::datetime_serde::datetime")]
+    market_datetime: OffsetDateTime,
+    name: String,
+    id: StaticId,
+}
+
+impl FxForwardPoints {
+    pub fn new(
+        fx_code: FxCode,
+        spot: Real,
+        mut quotes: Vec<FxPointQuote>,
+        market_datetime: OffsetDateTime,
+        name: String,
+        id: StaticId,
+    ) -> Result<FxForwardPoints> {
+        if quotes.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) quotes of FxForwardPoints ({}) is empty",
+                file!(),
+                line!(),
+                name
+            ));
+        }
+        if spot <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) spot of FxForwardPoints ({}) must be positive, but {} is given",
+                file!(),
+                line!(),
+                name,
+                spot
+            ));
+        }
+        quotes.sort_by_key(|quote| quote.maturity);
+        for i in 0..quotes.len() {
+            if quotes[i].maturity <= market_datetime
+                || (i > 0 && quotes[i].maturity == quotes[i - 1].maturity)
+            {
+                return Err(anyhow!(
+                    "({}:{}) the maturities of FxForwardPoints ({}) must be distinct \
+                    and after market_datetime ({:?})\nquote: {:?}",
+                    file!(),
+                    line!(),
+                    name,
+                    market_datetime,
+                    quotes[i]
+                ));
+            }
+        }
+
+        Ok(FxForwardPoints {
+            fx_code,
+            spot,
+            point_unit: 1.0,
+            quotes,
+            market_datetime,
+            name,
+            id,
+        })
+    }
+
+    pub fn with_point_unit(mut self, point_unit: Real) -> Result<FxForwardPoints> {
+        if point_unit <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) point_unit of FxForwardPoints ({}) must be positive, but {} is given",
+                file!(),
+                line!(),
+                self.name,
+                point_unit
+            ));
+        }
+        self.point_unit = point_unit;
+        Ok(self)
+    }
+
+    pub fn get_fx_code(&self) -> FxCode {
+        self.fx_code
+    }
+
+    /// the outright forwards on the maturities
+    pub fn get_forwards(&self) -> Array1<Real> {
+        self.quotes
+            .iter()
+            .map(|quote| self.spot + quote.points / self.point_unit)
+            .collect()
+    }
+
+    /// The zero rates of the other currency of the pair implied by the points
+    /// on the zero curve data of a currency in the pair, on the maturities of the quotes.
+    pub fn to_vector_data(&self, known_curve: &VectorData) -> Result<VectorData> {
+        let known_currency = known_curve.currency;
+        // r_implied = r_known + sign * ln(F / S) / t
+        let (currency, sign) = if known_currency == self.fx_code.get_currency2() {
+            (self.fx_code.get_currency1(), -1.0)
+        } else if known_currency == self.fx_code.get_currency1() {
+            (self.fx_code.get_currency2(), 1.0)
+        } else {
+            return Err(anyhow!(
+                "({}:{}) the curve {} in {} is not in the currencies of FxForwardPoints ({})",
+                file!(),
+                line!(),
+                known_curve.get_name_clone(),
+                known_currency,
+                self.name
+            ));
+        };
+
+        let times = known_curve.get_times_clone();
+        let rates = known_curve.get_value_clone();
+        let interpolator = if rates.len() > 1 {
+            Some(LinearInterpolator1D::new(
+                times,
+                rates.clone(),
+                ExtraPolationType::Flat,
+                true,
+            )?)
+        } else {
+            None
+        };
+
+        let time_calculator = NullCalendar::default();
+        let mut value = Array1::zeros(self.quotes.len());
+        for (i, (quote, forward)) in self.quotes.iter().zip(self.get_forwards()).enumerate() {
+            if forward <= 0.0 {
+                return Err(anyhow!(
+                    "({}:{}) the forward {} on {:?} of FxForwardPoints ({}) is not positive",
+                    file!(),
+                    line!(),
+                    forward,
+                    quote.maturity,
+                    self.name
+                ));
+            }
+            let t = time_calculator.get_time_difference(&self.market_datetime, &quote.maturity);
+            let known_rate = match &interpolator {
+                Some(interpolator) => interpolator.interpolate(t)?,
+                None => rates[0],
+            };
+            value[i] = known_rate + sign * (forward / self.spot).ln() / t;
+        }
+
+        VectorData::new(
+            value,
+            Some(self.quotes.iter().map(|quote| quote.maturity).collect()),
+            None,
+            Some(self.market_datetime),
+            currency,
+            self.name.clone(),
+            self.id,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation_date::EvaluationDate;
+    use crate::parameters::zero_curve::ZeroCurve;
+    use crate::utils::string_arithmetic::add_period;
+    use std::sync::{Arc, RwLock};
+    use time::macros::datetime;
+
+    #[test]
+    fn test_curve_quotes_on_flat_curve() -> Result<()> {
+        // the quotes on the flat zero rate of 3% are bootstrapped back to 3%
+        let market_datetime = datetime!(2024-01-02 16:30:00 +09:00);
+        let r: Real = 0.03;
+        let time_calculator = NullCalendar::default();
+        let time =
+            |date: &OffsetDateTime| time_calculator.get_time_difference(&market_datetime, date);
+        let discount = |t: Time| (-r * t).exp();
+
+        let deposit_maturity = add_period(&market_datetime, "3M");
+        let t = time(&deposit_maturity);
+        let deposit = CurveQuote::Deposit {
+            maturity: deposit_maturity,
+            rate: (1.0 / discount(t) - 1.0) / t,
+        };
+
+        let (start, end) = (deposit_maturity, add_period(&market_datetime, "6M"));
+        let (t1, t2) = (time(&start), time(&end));
+        let futures_rate = (discount(t1) / discount(t2) - 1.0) / (t2 - t1);
+        let futures = CurveQuote::Futures(RateFuturesQuote::new(
+            start,
+            end,
+            100.0 - 100.0 * futures_rate,
+        ));
+
+        let swap_maturity = add_period(&market_datetime, "2Y");
+        let mut annuity: Real = 0.0;
+        let mut previous: Time = 0.0;
+        for date in get_payment_dates(
+            &market_datetime,
+            &swap_maturity,
+            PaymentFrequency::Quarterly,
+        ) {
+            let t = time(&date);
+            annuity += (t - previous) * discount(t);
+            previous = t;
+        }
+        let swap = CurveQuote::ParSwap {
+            maturity: swap_maturity,
+            rate: (1.0 - discount(previous)) / annuity,
+            fixed_frequency: PaymentFrequency::Quarterly,
+        };
+
+        // (1 + y/2)^(-2t) = exp(-rt)
+        let bond = CurveQuote::BondYield {
+            maturity: add_period(&market_datetime, "5Y"),
+            coupon_rate: 0.04,
+            yield_to_maturity: 2.0 * ((r / 2.0).exp() - 1.0),
+            frequency: PaymentFrequency::SemiAnnually,
+        };
+
+        let quotes = CurveQuotes::new(
+            vec![bond, swap, futures, deposit],
+            market_datetime,
+            Currency::KRW,
+            "KRW Quotes".to_string(),
+            StaticId::from_str("KRW Quotes", "test"),
+        )?;
+        assert_eq!(quotes.get_quotes()[0].get_maturity(), deposit_maturity);
+
+        let data = quotes.to_vector_data()?;
+        assert_eq!(data.get_value_clone().len(), 4);
+        for rate in data.get_value_clone().iter() {
+            assert!((rate - r).abs() < 1.0e-4, "rate: {}", rate);
+        }
+
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(market_datetime)));
+        let curve = ZeroCurve::new(
+            evaluation_date,
+            &data,
+            "KRW Quotes".to_string(),
+            StaticId::from_str("KRW Quotes", "test"),
+        )?;
+        assert!((curve.get_discount_factor(1.0)? - discount(1.0)).abs() < 1.0e-4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_curve_quotes_validation() {
+        let market_datetime = datetime!(2024-01-02 16:30:00 +09:00);
+        let maturity = datetime!(2025-01-02 16:30:00 +09:00);
+        let id = StaticId::from_str("KRW Quotes", "test");
+        let quotes = vec![
+            CurveQuote::Deposit {
+                maturity,
+                rate: 0.03,
+            },
+            CurveQuote::ParSwap {
+                maturity,
+                rate: 0.03,
+                fixed_frequency: PaymentFrequency::Quarterly,
+            },
+        ];
+        assert!(
+            CurveQuotes::new(quotes, market_datetime, Currency::KRW, "".to_string(), id).is_err()
+        );
+
+        // a deposit rate which no zero rate in [-100%, 100%] reprices
+        let quotes = vec![CurveQuote::Deposit {
+            maturity,
+            rate: 10.0,
+        }];
+        let quotes =
+            CurveQuotes::new(quotes, market_datetime, Currency::KRW, "".to_string(), id).unwrap();
+        assert!(quotes.to_vector_data().is_err());
+    }
+
+    #[test]
+    fn test_fx_forward_points() -> Result<()> {
+        // USD rate of 5% implied by the USD/KRW points on the KRW rate of 3.5%
+        let market_datetime = datetime!(2024-01-02 16:30:00 +09:00);
+        let krw_curve = VectorData::new(
+            Array1::from(vec![0.035, 0.035]),
+            Some(vec![
+                datetime!(2024-07-02 16:30:00 +09:00),
+                datetime!(2025-01-02 16:30:00 +09:00),
+            ]),
+            None,
+            Some(market_datetime),
+            Currency::KRW,
+            "KRWCRS".to_string(),
+            StaticId::from_str("KRWCRS", "test"),
+        )?;
+        let spot: Real = 1300.0;
+        let time_calculator = NullCalendar::default();
+        let quotes: Vec<FxPointQuote> = ["3M", "6M", "1Y", "2Y"]
+            .iter()
+            .map(|tenor| {
+                let maturity = add_period(&market_datetime, tenor);
+                let t = time_calculator.get_time_difference(&market_datetime, &maturity);
+                let forward = spot * ((0.035 - 0.05) * t).exp();
+                FxPointQuote::new(maturity, forward - spot)
+            })
+            .collect();
+        let points = FxForwardPoints::new(
+            FxCode::new(Currency::USD, Currency::KRW),
+            spot,
+            quotes,
+            market_datetime,
+            "USD implied".to_string(),
+            StaticId::from_str("USD implied", "test"),
+        )?;
+
+        let usd_curve = points.to_vector_data(&krw_curve)?;
+        assert_eq!(usd_curve.currency, Currency::USD);
+        for rate in usd_curve.get_value_clone().iter() {
+            assert!((rate - 0.05).abs() < 1.0e-4, "rate: {}", rate);
+        }
+
+        // and back to the KRW rate on the USD curve
+        let krw_implied = points.to_vector_data(&usd_curve)?;
+        assert_eq!(krw_implied.currency, Currency::KRW);
+        for rate in krw_implied.get_value_clone().iter() {
+            assert!((rate - 0.035).abs() < 1.0e-4, "rate: {}", rate);
+        }
+
+        let eur_curve = VectorData::test_curve_data(0.03, Currency::EUR)?;
+        assert!(points.to_vector_data(&eur_curve).is_err());
+        assert!(points.with_point_unit(0.0).is_err());
+        Ok(())
+    }
+}
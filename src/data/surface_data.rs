@@ -3,6 +3,7 @@ use crate::definitions::Real;
 use crate::Tenor;
 use ndarray::{Array1, Array2};
 use serde::{Deserialize, Serialize};
+use crate::utils::platform::now_utc;
 use time::OffsetDateTime;
 use static_id::static_id::StaticId;
 use anyhow::Result;
@@ -19,7 +20,7 @@ use anyhow::Result;
 /// 100.0, Some(datetime!(2022-04-14 15:40:00 +09:00))).unwrap();
 /// println!("{:?}", surface_data);
 /// ```
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct SurfaceData {
     /// The spot price of the underlying asset.
     /// This is an Option because the market data's spot price might differ from the valuation spot price.
@@ -29,12 +30,14 @@ pub struct SurfaceData {
     /// Rows typically correspond to different expiry dates, and columns to different strike prices.
     pub value: Array2<Real>,
     /// Vector of expiry dates for the volatility surface.
+    #[serde(with = "crate::time::datetime_serde::vec_datetime")]
     pub dates: Vec<OffsetDateTime>,
     /// Array of strike prices for the volatility surface.
     pub strikes: Array1<Real>,
     /// The currency of the underlying asset.
     pub currency: Currency,
     /// The datetime when this market data was captured or created.
+    #[serde(default, with = "crate::time::datetime_serde::option_datetime")]
     pub market_datetime: Option<OffsetDateTime>,
     /// A name or identifier for this volatility surface.
     pub name: String,
@@ -100,7 +103,7 @@ impl SurfaceData {
     }
 
     pub fn test_data(spot: Real, datetime: Option<OffsetDateTime>) -> Result<SurfaceData> {
-        let datetime = datetime.unwrap_or_else(OffsetDateTime::now_utc);
+        let datetime = datetime.unwrap_or_else(now_utc);
         let dates = vec![
             Tenor::new_from_string("1M")?.apply(&datetime),
             Tenor::new_from_string("2M")?.apply(&datetime),
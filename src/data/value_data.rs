@@ -1,6 +1,6 @@
 use crate::currency::Currency;
 use crate::definitions::Real;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use time::OffsetDateTime;
@@ -27,7 +27,7 @@ use static_id::static_id::StaticId;
 ///
 /// assert!(value_data.get_value() == 1.0);
 /// ```
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
 pub struct ValueData {
     /// The numerical value of the data point.
     /// This could represent volatility, stock price, or any other constant financial metric.
@@ -35,6 +35,7 @@ pub struct ValueData {
 
     /// The date and time of the market data point, if available.
     /// This field is optional as some constant data might not have a specific market datetime.
+    #[serde(default, with = "crate::time::datetime_serde::option_datetime")]
     pub market_datetime: Option<OffsetDateTime>,
 
     /// The currency in which the value is denominated.
@@ -48,6 +49,14 @@ pub struct ValueData {
     /// A unique identifier for this data point.
     /// Can be created using StaticId::from_str().
     pub id: StaticId,
+
+    /// The bid quote, if available.
+    #[serde(default)]
+    pub bid: Option<Real>,
+
+    /// The ask quote, if available.
+    #[serde(default)]
+    pub ask: Option<Real>,
 }
 
 impl Debug for ValueData {
@@ -55,6 +64,8 @@ impl Debug for ValueData {
         f.debug_struct("ValueData")
             .field("value", &self.value)
             .field("market_datetime", &self.market_datetime)
+            .field("bid", &self.bid)
+            .field("ask", &self.ask)
             .field("name", &self.name)
             .field("code", &self.id)
             .finish()
@@ -88,9 +99,45 @@ impl ValueData {
             currency,
             name,
             id,
+            bid: None,
+            ask: None,
         })
     }
 
+    /// Sets the bid and ask quotes. The value is kept as the mark of the data point.
+    pub fn with_bid_ask(mut self, bid: Real, ask: Real) -> Result<ValueData> {
+        if bid > ask {
+            return Err(anyhow!(
+                "({}:{}) bid ({}) is larger than ask ({}) in {} ({})",
+                file!(),
+                line!(),
+                bid,
+                ask,
+                self.name,
+                self.id,
+            ));
+        }
+        self.bid = Some(bid);
+        self.ask = Some(ask);
+        Ok(self)
+    }
+
+    pub fn get_bid(&self) -> Option<Real> {
+        self.bid
+    }
+
+    pub fn get_ask(&self) -> Option<Real> {
+        self.ask
+    }
+
+    /// The middle of bid and ask. If either of them is not given, this returns the value
+    pub fn get_mid(&self) -> Real {
+        match (self.bid, self.ask) {
+            (Some(bid), Some(ask)) => 0.5 * (bid + ask),
+            _ => self.value,
+        }
+    }
+
     pub fn get_value(&self) -> Real {
         self.value
     }
@@ -126,6 +173,12 @@ mod tests {
         )
         .expect("Failed to create ValueData");
         assert!(value_data.get_value() == 1.0);
+        assert!(value_data.get_mid() == 1.0);
+
+        let value_data = value_data.with_bid_ask(0.9, 1.2)?;
+        assert_eq!(value_data.get_bid(), Some(0.9));
+        assert!((value_data.get_mid() - 1.05).abs() < 1.0e-6);
+        assert!(value_data.clone().with_bid_ask(1.2, 0.9).is_err());
         Ok(())
     }
 }
@@ -2,6 +2,7 @@ use crate::currency::Currency;
 use crate::definitions::{Real, Time};
 use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
 use std::fmt;
+use crate::utils::platform::now_utc;
 use time::OffsetDateTime;
 use static_id::static_id::StaticId;
 use anyhow::{anyhow, Result};
@@ -40,18 +41,20 @@ use serde::{Deserialize, Serialize};
 /// assert_eq!(vector_data.get_value_clone(), deserialized.get_value_clone());
 /// assert_eq!(vector_data.get_times_clone(), deserialized.get_times_clone());
 /// ```
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct VectorData {
     /// The vector of numerical values.
     pub value: Array1<Real>,
 
     /// Optional vector of dates corresponding to each value.
+    #[serde(default, with = "crate::time::datetime_serde::option_vec_datetime")]
     pub dates: Option<Vec<OffsetDateTime>>,
 
     /// Vector of times corresponding to each value.
     pub times: Array1<Time>,
 
     /// The market datetime for this data set, if applicable.
+    #[serde(default, with = "crate::time::datetime_serde::option_datetime")]
     pub market_datetime: Option<OffsetDateTime>,
 
     /// The currency in which the values are denominated.
@@ -151,12 +154,12 @@ impl VectorData {
             Ok(res)
         } else if let Some(times) = times {
             if value.len() != times.len() {
-                return Err(anyhow!(
+                Err(anyhow!(
                     "The length of value and times must be the same\n\
                     value: {:?}, times: {:?}",
                     value,
                     times,
-                ));
+                ))
             } else {
                 let res = VectorData {
                     value,
@@ -170,7 +173,7 @@ impl VectorData {
                 Ok(res)
             }
         } else {
-            return Err(anyhow!("dates and times are both None"));
+            Err(anyhow!("dates and times are both None"))
         }
     }
 
@@ -209,7 +212,7 @@ impl VectorData {
 
     pub fn test_dividend_data(value: Real, currency: Currency) -> Result<VectorData> {
         let values = Array1::from(vec![value]);
-        let dates = vec![OffsetDateTime::now_utc() + time::Duration::days(1)];
+        let dates = vec![now_utc() + time::Duration::days(1)];
         
         let name = "test_vector_data".to_string();
         let id = StaticId::from_str(name.as_str(), "test");
@@ -3,7 +3,11 @@ use crate::currency::{self, Currency};
 use time;
 /// Note! must be a variable that can derive Copy and PartialOrd trait.
 /// Of course, it would be highly likely either f32 or f64.
+/// The feature "f64" switches it to f64, e.g., for the large notionals.
+#[cfg(not(feature = "f64"))]
 pub type Real = f32;
+#[cfg(feature = "f64")]
+pub type Real = f64;
 pub type Time = Real;
 
 pub type Natural = u32;
@@ -1,3 +1,4 @@
+use crate::definitions::Real;
 use serde::{Deserialize, Serialize};
 //use std::fmt;
 use std::hash::Hash;
@@ -88,6 +89,184 @@ pub enum StickynessType {
     StickyToStrike,
 }
 
+/// Interpolation of volatility surfaces in the expiry dimension.
+/// TotalVariance interpolates sigma^2 * t linearly in t (at the same forward moneyness),
+/// which does not create calendar arbitrage between the given expiries.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy, Default)]
+pub enum VolatilityTimeInterpolation {
+    Volatility,
+    #[default]
+    TotalVariance,
+}
+
+/// Interpolation of the market quotes of volatility surfaces on (expiry, moneyness).
+/// Bicubic is smooth in both dimensions, and ThinPlateSpline is smooth and extrapolates linearly
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy, Default)]
+pub enum SurfaceInterpolation {
+    #[default]
+    Bilinear,
+    Bicubic,
+    ThinPlateSpline,
+}
+
+/// The side of the quotes at which the positions are marked for the exit-price valuation.
+/// Conservative marks each instrument at the worse of bid and ask for its holder,
+/// i.e., the lower value for a long position and the higher value for a short position.
+/// If bid or ask is not given, the quote is marked at its value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy, Default)]
+pub enum MarkingSide {
+    #[default]
+    Mid,
+    Bid,
+    Ask,
+    Conservative,
+}
+
+/// Shape of the zero rate bump of a key-rate tenor.
+/// Triangle bumps the zero rate by 1 at the key tenor, decreasing linearly to 0 at the adjacent key tenors.
+/// FlatForward bumps the instantaneous forward rates flat between the previous and the key tenor.
+/// In both cases, the bumps of all key tenors add up to a parallel bump.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy, Default)]
+pub enum KeyRateBumpScheme {
+    #[default]
+    Triangle,
+    FlatForward,
+}
+
+/// How the P&L of the VaR scenarios is obtained.
+/// FullRevaluation reprices the instruments on the shocked market data.
+/// Sensitivity applies the shocks to the greeks of the base date (TaylorPnlPredictor).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy, Default)]
+pub enum VarMethod {
+    #[default]
+    FullRevaluation,
+    Sensitivity,
+}
+
+/// Distribution of the risk factor moves in Monte Carlo VaR.
+/// StudentT is scaled to have the given covariance, so the degrees of freedom must be larger than 2.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy, Default)]
+pub enum ScenarioDistribution {
+    #[default]
+    Normal,
+    StudentT { degrees_of_freedom: Real },
+}
+
+/// How the first order greeks (delta, vega, rho) are calculated.
+/// AutomaticDifferentiation takes the derivatives of the pricers supporting dual numbers in one pass
+/// and falls back to BumpAndRevalue for the others. Gamma is always bumped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy, Default)]
+pub enum GreekMethod {
+    #[default]
+    BumpAndRevalue,
+    AutomaticDifferentiation,
+}
+
+/// Risk factor classes with their own bump sizes in CalculationConfiguration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub enum RiskFactorClass {
+    Spot,
+    Volatility,
+    Rate,
+    Dividend,
+    Fx,
+}
+
+/// Relative bumps are proportional to the level of the risk factor, e.g., 1% of the spot,
+/// and absolute bumps are added to it, e.g., 1bp on the zero rates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub enum BumpType {
+    Relative,
+    Absolute,
+}
+
+/// Finite difference of a greek on the bumps of the risk factor, e.g., for the bump h of the volatility
+/// - Forward: (V(σ + h) - V(σ)) / h
+/// - Backward: (V(σ) - V(σ - h)) / h
+/// - Central: (V(σ + h) - V(σ - h)) / 2h
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash, Default)]
+pub enum FiniteDifferenceScheme {
+    #[default]
+    Forward,
+    Backward,
+    Central,
+}
+
+/// Shift of the evaluation date in theta
+/// - Fixed: theta_day calendar days
+/// - BusinessDay: theta_day business days in the calendar of the instrument
+///   (the theta calendar in CalculationConfiguration if the instrument has none),
+///   so that the theta on Friday runs over the weekend to Monday
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash, Default)]
+pub enum ThetaDayMode {
+    #[default]
+    Fixed,
+    BusinessDay,
+}
+
+/// Roles of the zero curves in pricing an instrument
+/// - Discount: discount curves including the crs curves of the legs in each currency
+/// - Forward: projection curves of the rate indices
+/// - Collateral: collateral curves of the underlying forwards
+/// - Borrowing: borrowing fee curves of the underlying forwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Hash)]
+pub enum CurveRole {
+    Discount,
+    Forward,
+    Collateral,
+    Borrowing,
+}
+
+/// Risk classes of the FRTB sensitivities based method (SBM)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Hash)]
+pub enum FrtbRiskClass {
+    Girr,
+    Csr,
+    Equity,
+    Fx,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Hash)]
+pub enum FrtbRiskMeasure {
+    Delta,
+    Vega,
+    CurvatureUp,
+    CurvatureDown,
+}
+
+/// FRTB liquidity horizons of the risk factors in days (MAR33.12)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Hash)]
+pub enum LiquidityHorizon {
+    Days10,
+    Days20,
+    Days40,
+    Days60,
+    Days120,
+}
+
+impl LiquidityHorizon {
+    pub fn as_days(&self) -> usize {
+        match self {
+            LiquidityHorizon::Days10 => 10,
+            LiquidityHorizon::Days20 => 20,
+            LiquidityHorizon::Days40 => 40,
+            LiquidityHorizon::Days60 => 60,
+            LiquidityHorizon::Days120 => 120,
+        }
+    }
+
+    /// all horizons in the increasing order
+    pub fn all() -> [LiquidityHorizon; 5] {
+        [
+            LiquidityHorizon::Days10,
+            LiquidityHorizon::Days20,
+            LiquidityHorizon::Days40,
+            LiquidityHorizon::Days60,
+            LiquidityHorizon::Days120,
+        ]
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Copy, Default)]
 pub enum VanillaOptionCalculationMethod {
     MonteCarlo = 0,
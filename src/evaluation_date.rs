@@ -4,22 +4,24 @@ use crate::parameters::{
     discrete_ratio_dividend::DiscreteRatioDividend, market_price::MarketPrice,
 };
 use serde::{Deserialize, Serialize};
-use std::cell::RefCell;
+use std::sync::RwLock;
 use std::fmt::Debug;
-use std::rc::Rc;
+use std::sync::Arc;
 use std::{
     cmp::Ordering,
     ops::{Add, AddAssign, Sub, SubAssign},
 };
+use crate::utils::platform::now_utc;
 use time::{Date, OffsetDateTime};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct EvaluationDate {
+    #[serde(with = "crate::time::datetime_serde::datetime")]
     date: OffsetDateTime,
     #[serde(skip)]
-    marketprice_observers: Vec<Rc<RefCell<MarketPrice>>>,
+    marketprice_observers: Vec<Arc<RwLock<MarketPrice>>>,
     #[serde(skip)]
-    dividend_observers: Vec<Rc<RefCell<DiscreteRatioDividend>>>,
+    dividend_observers: Vec<Arc<RwLock<DiscreteRatioDividend>>>,
 }
 
 impl PartialEq<OffsetDateTime> for EvaluationDate {
@@ -57,7 +59,7 @@ impl Debug for EvaluationDate {
 impl Default for EvaluationDate {
     fn default() -> EvaluationDate {
         EvaluationDate {
-            date: OffsetDateTime::now_utc(),
+            date: now_utc(),
             marketprice_observers: vec![],
             dividend_observers: vec![],
         }
@@ -90,11 +92,11 @@ impl EvaluationDate {
         self.notify_observers();
     }
 
-    pub fn add_dividend_observer(&mut self, observer: Rc<RefCell<DiscreteRatioDividend>>) {
+    pub fn add_dividend_observer(&mut self, observer: Arc<RwLock<DiscreteRatioDividend>>) {
         self.dividend_observers.push(observer);
     }
 
-    pub fn add_marketprice_observer(&mut self, observer: Rc<RefCell<MarketPrice>>) {
+    pub fn add_marketprice_observer(&mut self, observer: Arc<RwLock<MarketPrice>>) {
         self.marketprice_observers.push(observer);
     }
 
@@ -102,7 +104,7 @@ impl EvaluationDate {
         for marketprice_observer in self.marketprice_observers.iter() {
             {
                 marketprice_observer
-                    .borrow_mut()
+                    .write().unwrap()
                     .update_evaluation_date(self)
                     .expect("Failed to update market price observer");
             }
@@ -111,7 +113,7 @@ impl EvaluationDate {
         for dividend_observer in self.dividend_observers.iter() {
             {
                 dividend_observer
-                    .borrow_mut()
+                    .write().unwrap()
                     .update_evaluation_date(self)
                     .expect("Failed to update dividend observer");
             }
@@ -121,12 +123,12 @@ impl EvaluationDate {
     pub fn display_observers(&self) {
         println!("Market Price Observers:");
         for observer in self.marketprice_observers.iter() {
-            println!("{:?}", observer.borrow().get_name());
+            println!("{:?}", observer.read().unwrap().get_name());
         }
 
         println!("Dividend Observers:");
         for observer in self.dividend_observers.iter() {
-            println!("{:?}", observer.borrow().get_name());
+            println!("{:?}", observer.read().unwrap().get_name());
         }
     }
 }
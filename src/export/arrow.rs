@@ -0,0 +1,327 @@
+use crate::definitions::Real;
+use crate::export::result_table::ResultTable;
+use crate::pricing_engines::calculation_result::CalculationResult;
+//
+use anyhow::{anyhow, Context, Result};
+use arrow_array::{
+    ArrayRef, Float64Array, RecordBatch, StringArray, TimestampNanosecondArray, UInt64Array,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use rustc_hash::FxHashMap;
+use static_id::static_id::StaticId;
+use std::sync::Arc;
+use time::OffsetDateTime;
+
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into()))
+}
+
+fn timestamps(dates: Vec<Option<&OffsetDateTime>>) -> ArrayRef {
+    let nanos: Vec<Option<i64>> = dates
+        .into_iter()
+        .map(|date| date.map(|date| date.unix_timestamp_nanos() as i64))
+        .collect();
+    Arc::new(TimestampNanosecondArray::from(nanos).with_timezone("UTC"))
+}
+
+fn floats(values: Vec<Option<Real>>) -> ArrayRef {
+    let values: Vec<Option<f64>> = values
+        .into_iter()
+        .map(|value| value.map(|value| value as f64))
+        .collect();
+    Arc::new(Float64Array::from(values))
+}
+
+fn indices(values: Vec<Option<usize>>) -> ArrayRef {
+    let values: Vec<Option<u64>> = values
+        .into_iter()
+        .map(|value| value.map(|value| value as u64))
+        .collect();
+    Arc::new(UInt64Array::from(values))
+}
+
+fn strings(values: Vec<Option<String>>) -> ArrayRef {
+    Arc::new(StringArray::from(values))
+}
+
+/// Arrow record batches of the calculation results (ResultTable), e.g., for loading into data warehouses.
+/// - results: a row per instrument with its value and scalar results
+/// - greeks: a row per greek on a key (see GreekRow) in the long format
+/// - cashflows: a row per cashflow on a unit notional
+///
+/// The dates are UTC timestamps in nanoseconds and the numbers are f64.
+#[derive(Debug, Clone)]
+pub struct ResultBatches {
+    results: RecordBatch,
+    greeks: RecordBatch,
+    cashflows: RecordBatch,
+}
+
+impl ResultBatches {
+    pub fn new(results: &FxHashMap<StaticId, CalculationResult>) -> Result<ResultBatches> {
+        ResultBatches::from_table(&ResultTable::new(results))
+    }
+
+    pub fn from_table(table: &ResultTable) -> Result<ResultBatches> {
+        let rows = table.get_results();
+        let results_schema = Schema::new(vec![
+            Field::new("inst_id", DataType::Utf8, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("inst_type", DataType::Utf8, true),
+            Field::new("currency", DataType::Utf8, true),
+            Field::new("unit_notional", DataType::Float64, true),
+            Field::new("maturity", timestamp_type(), true),
+            Field::new("evaluation_date", timestamp_type(), true),
+            Field::new("value", DataType::Float64, true),
+            Field::new("theta", DataType::Float64, true),
+            Field::new("carry", DataType::Float64, true),
+            Field::new("roll_down", DataType::Float64, true),
+            Field::new("exit_value", DataType::Float64, true),
+            Field::new("bid_ask_adjustment", DataType::Float64, true),
+        ]);
+        let results = RecordBatch::try_new(
+            Arc::new(results_schema),
+            vec![
+                strings(rows.iter().map(|r| Some(r.inst_id.clone())).collect()),
+                strings(rows.iter().map(|r| r.name.clone()).collect()),
+                strings(rows.iter().map(|r| r.inst_type.clone()).collect()),
+                strings(
+                    rows.iter()
+                        .map(|r| r.currency.map(|c| c.to_string()))
+                        .collect(),
+                ),
+                floats(rows.iter().map(|r| r.unit_notional).collect()),
+                timestamps(rows.iter().map(|r| r.maturity.as_ref()).collect()),
+                timestamps(rows.iter().map(|r| r.evaluation_date.as_ref()).collect()),
+                floats(rows.iter().map(|r| r.value).collect()),
+                floats(rows.iter().map(|r| r.theta).collect()),
+                floats(rows.iter().map(|r| r.carry).collect()),
+                floats(rows.iter().map(|r| r.roll_down).collect()),
+                floats(rows.iter().map(|r| r.exit_value).collect()),
+                floats(rows.iter().map(|r| r.bid_ask_adjustment).collect()),
+            ],
+        )
+        .with_context(|| anyhow!("({}:{}) failed to build the result batch", file!(), line!()))?;
+
+        let rows = table.get_greeks();
+        let greeks_schema = Schema::new(vec![
+            Field::new("inst_id", DataType::Utf8, false),
+            Field::new("greek", DataType::Utf8, false),
+            Field::new("key", DataType::Utf8, false),
+            Field::new("sub_key", DataType::Utf8, true),
+            Field::new("row", DataType::UInt64, true),
+            Field::new("column", DataType::UInt64, true),
+            Field::new("value", DataType::Float64, false),
+        ]);
+        let greeks = RecordBatch::try_new(
+            Arc::new(greeks_schema),
+            vec![
+                strings(rows.iter().map(|r| Some(r.inst_id.clone())).collect()),
+                strings(rows.iter().map(|r| Some(r.greek.to_string())).collect()),
+                strings(rows.iter().map(|r| Some(r.key.clone())).collect()),
+                strings(rows.iter().map(|r| r.sub_key.clone()).collect()),
+                indices(rows.iter().map(|r| r.row).collect()),
+                indices(rows.iter().map(|r| r.column).collect()),
+                floats(rows.iter().map(|r| Some(r.value)).collect()),
+            ],
+        )
+        .with_context(|| anyhow!("({}:{}) failed to build the greek batch", file!(), line!()))?;
+
+        let rows = table.get_cashflows();
+        let cashflows_schema = Schema::new(vec![
+            Field::new("inst_id", DataType::Utf8, false),
+            Field::new("payment_date", timestamp_type(), false),
+            Field::new("accrual_start_date", timestamp_type(), true),
+            Field::new("accrual_end_date", timestamp_type(), true),
+            Field::new("notional", DataType::Float64, false),
+            Field::new("rate", DataType::Float64, true),
+            Field::new("amount", DataType::Float64, false),
+            Field::new("currency", DataType::Utf8, false),
+            Field::new("leg", DataType::Utf8, false),
+            Field::new("cashflow_type", DataType::Utf8, false),
+        ]);
+        let cashflows = RecordBatch::try_new(
+            Arc::new(cashflows_schema),
+            vec![
+                strings(rows.iter().map(|r| Some(r.inst_id.clone())).collect()),
+                timestamps(rows.iter().map(|r| Some(&r.payment_date)).collect()),
+                timestamps(rows.iter().map(|r| r.accrual_start_date.as_ref()).collect()),
+                timestamps(rows.iter().map(|r| r.accrual_end_date.as_ref()).collect()),
+                floats(rows.iter().map(|r| Some(r.notional)).collect()),
+                floats(rows.iter().map(|r| r.rate).collect()),
+                floats(rows.iter().map(|r| Some(r.amount)).collect()),
+                strings(rows.iter().map(|r| Some(r.currency.to_string())).collect()),
+                strings(
+                    rows.iter()
+                        .map(|r| Some(r.leg.as_str().to_string()))
+                        .collect(),
+                ),
+                strings(
+                    rows.iter()
+                        .map(|r| Some(r.cashflow_type.as_str().to_string()))
+                        .collect(),
+                ),
+            ],
+        )
+        .with_context(|| {
+            anyhow!(
+                "({}:{}) failed to build the cashflow batch",
+                file!(),
+                line!()
+            )
+        })?;
+
+        Ok(ResultBatches {
+            results,
+            greeks,
+            cashflows,
+        })
+    }
+
+    pub fn get_results(&self) -> &RecordBatch {
+        &self.results
+    }
+
+    pub fn get_greeks(&self) -> &RecordBatch {
+        &self.greeks
+    }
+
+    pub fn get_cashflows(&self) -> &RecordBatch {
+        &self.cashflows
+    }
+
+    /// writes results.parquet, greeks.parquet and cashflows.parquet in the directory
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet<P: AsRef<std::path::Path>>(&self, directory: P) -> Result<()> {
+        let directory = directory.as_ref();
+        for (name, batch) in [
+            ("results", &self.results),
+            ("greeks", &self.greeks),
+            ("cashflows", &self.cashflows),
+        ] {
+            let path = directory.join(format!("{}.parquet", name));
+            write_parquet_file(&path, batch).with_context(|| {
+                anyhow!(
+                    "({}:{}) failed to write {}",
+                    file!(),
+                    line!(),
+                    path.display()
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet_file(path: &std::path::Path, batch: &RecordBatch) -> Result<()> {
+    let file = std::fs::File::create(path)?;
+    let mut writer = parquet::arrow::ArrowWriter::try_new(file, batch.schema(), None)?;
+    writer.write(batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::instruments::cashflow::{Cashflow, CashflowLeg, CashflowType};
+    use crate::instruments::inst_info::InstInfo;
+    use crate::pricing_engines::npv_result::NpvResult;
+    use arrow_array::Array;
+    use time::macros::datetime;
+
+    fn calculation_results() -> FxHashMap<StaticId, CalculationResult> {
+        let inst_id = StaticId::from_str("KRW IRS", "KRX");
+        let mut result = CalculationResult::new(
+            InstInfo {
+                id: inst_id,
+                ..InstInfo::default()
+            },
+            datetime!(2024-03-13 16:30:00 +09:00),
+        );
+        result.set_npv(NpvResult::new_from_npv(100.0));
+        result.set_value().unwrap();
+        result.set_single_rho_structure(StaticId::from_str("KRWIRS", "KAP"), vec![-0.5, -0.25]);
+        result.set_cashflows(vec![Cashflow::new(
+            datetime!(2024-09-13 00:00:00 +09:00),
+            0.02,
+            Currency::KRW,
+            CashflowLeg::Fixed,
+            CashflowType::Fixed,
+        )
+        .with_rate(0.04)]);
+        let mut results = FxHashMap::default();
+        results.insert(inst_id, result);
+        results
+    }
+
+    #[test]
+    fn test_result_batches() -> Result<()> {
+        let batches = ResultBatches::new(&calculation_results())?;
+
+        let results = batches.get_results();
+        assert_eq!(results.num_rows(), 1);
+        let value = results
+            .column_by_name("value")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(value.value(0), 100.0);
+        assert!(results.column_by_name("theta").unwrap().is_null(0));
+        let evaluation_date = results
+            .column_by_name("evaluation_date")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<TimestampNanosecondArray>()
+            .unwrap();
+        assert_eq!(
+            evaluation_date.value(0) as i128,
+            datetime!(2024-03-13 07:30:00 UTC).unix_timestamp_nanos()
+        );
+
+        let greeks = batches.get_greeks();
+        assert_eq!(greeks.num_rows(), 2);
+        let row = greeks
+            .column_by_name("row")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        assert_eq!(row.value(1), 1);
+
+        let cashflows = batches.get_cashflows();
+        assert_eq!(cashflows.num_rows(), 1);
+        let rate = cashflows
+            .column_by_name("rate")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert!((rate.value(0) - 0.04).abs() < 1e-7);
+        Ok(())
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_write_parquet() -> Result<()> {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let directory =
+            std::env::temp_dir().join(format!("rustmetrics_parquet_{}", std::process::id()));
+        std::fs::create_dir_all(&directory)?;
+        let batches = ResultBatches::new(&calculation_results())?;
+        batches.write_parquet(&directory)?;
+
+        let file = std::fs::File::open(directory.join("greeks.parquet"))?;
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
+        let read: Vec<RecordBatch> = reader.collect::<std::result::Result<_, _>>()?;
+        std::fs::remove_dir_all(&directory)?;
+
+        assert_eq!(read.len(), 1);
+        assert_eq!(&read[0], batches.get_greeks());
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+use crate::definitions::Real;
+use crate::export::result_table::{GreekRow, ResultTable};
+use crate::pricing_engines::calculation_result::CalculationResult;
+//
+use anyhow::{anyhow, Context, Result};
+use rustc_hash::{FxHashMap, FxHashSet};
+use static_id::static_id::StaticId;
+use std::io::Write;
+use std::path::Path;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+const RESULT_COLUMNS: [&str; 13] = [
+    "inst_id",
+    "name",
+    "inst_type",
+    "currency",
+    "unit_notional",
+    "maturity",
+    "evaluation_date",
+    "value",
+    "theta",
+    "carry",
+    "roll_down",
+    "exit_value",
+    "bid_ask_adjustment",
+];
+
+const CASHFLOW_COLUMNS: [&str; 10] = [
+    "inst_id",
+    "payment_date",
+    "accrual_start_date",
+    "accrual_end_date",
+    "notional",
+    "rate",
+    "amount",
+    "currency",
+    "leg",
+    "cashflow_type",
+];
+
+/// CSV files of the calculation results (ResultTable) for the users without serde, e.g., operations teams.
+/// - results: a row per instrument with its scalar results and a column per greek on a key,
+///   e.g., "delta:KOSPI2", "rho_structure:KRWIRS:3" (bucket), "delta_rho:KOSPI2:KRWIRS", "vega_matrix:KOSPI2:1:2"
+/// - cashflows: a row per cashflow on a unit notional in the long format
+///
+/// The numbers are written in the shortest form that parses back to the same value
+/// unless the decimal places are given, and the dates are in RFC 3339.
+/// The empty results, e.g., greeks not calculated for an instrument, are written as the missing value.
+#[derive(Debug, Clone)]
+pub struct CsvWriter {
+    delimiter: char,
+    decimal_places: Option<usize>,
+    thousands_separator: bool,
+    missing_value: String,
+}
+
+impl Default for CsvWriter {
+    fn default() -> CsvWriter {
+        CsvWriter {
+            delimiter: ',',
+            decimal_places: None,
+            thousands_separator: false,
+            missing_value: "".to_string(),
+        }
+    }
+}
+
+impl CsvWriter {
+    pub fn new() -> CsvWriter {
+        CsvWriter::default()
+    }
+
+    pub fn with_delimiter(mut self, delimiter: char) -> Result<CsvWriter> {
+        if delimiter == '"' || delimiter == '\n' || delimiter == '\r' {
+            return Err(anyhow!(
+                "({}:{}) {:?} can not be the delimiter",
+                file!(),
+                line!(),
+                delimiter
+            ));
+        }
+        self.delimiter = delimiter;
+        Ok(self)
+    }
+
+    pub fn with_decimal_places(mut self, decimal_places: usize) -> CsvWriter {
+        self.decimal_places = Some(decimal_places);
+        self
+    }
+
+    /// e.g., 1,234,567.89. The fields with the delimiter are quoted.
+    pub fn with_thousands_separator(mut self, thousands_separator: bool) -> CsvWriter {
+        self.thousands_separator = thousands_separator;
+        self
+    }
+
+    pub fn with_missing_value(mut self, missing_value: &str) -> CsvWriter {
+        self.missing_value = missing_value.to_string();
+        self
+    }
+
+    pub fn format_number(&self, number: Real) -> String {
+        let formatted = match self.decimal_places {
+            Some(decimal_places) => format!("{:.*}", decimal_places, number),
+            None => format!("{}", number),
+        };
+        if !self.thousands_separator || !number.is_finite() {
+            return formatted;
+        }
+
+        let (sign, unsigned) = match formatted.strip_prefix('-') {
+            Some(unsigned) => ("-", unsigned),
+            None => ("", formatted.as_str()),
+        };
+        let (integer_part, decimal_part) = match unsigned.find('.') {
+            Some(pos) => unsigned.split_at(pos),
+            None => (unsigned, ""),
+        };
+        let mut res = String::from(sign);
+        for (count, c) in integer_part.chars().enumerate() {
+            if count > 0 && (integer_part.len() - count) % 3 == 0 {
+                res.push(',');
+            }
+            res.push(c);
+        }
+        res.push_str(decimal_part);
+        res
+    }
+
+    fn format_date(&self, date: &OffsetDateTime) -> Result<String> {
+        date.format(&Rfc3339)
+            .with_context(|| anyhow!("({}:{}) failed to format {}", file!(), line!(), date))
+    }
+
+    fn number_field(&self, number: Option<Real>) -> String {
+        match number {
+            Some(number) => self.format_number(number),
+            None => self.missing_value.clone(),
+        }
+    }
+
+    fn date_field(&self, date: Option<&OffsetDateTime>) -> Result<String> {
+        match date {
+            Some(date) => self.format_date(date),
+            None => Ok(self.missing_value.clone()),
+        }
+    }
+
+    fn write_record<W: Write>(&self, writer: &mut W, fields: &[String]) -> Result<()> {
+        let record = fields
+            .iter()
+            .map(|field| self.quote(field))
+            .collect::<Vec<String>>()
+            .join(&self.delimiter.to_string());
+        writeln!(writer, "{}", record)
+            .with_context(|| anyhow!("({}:{}) failed to write a csv record", file!(), line!()))
+    }
+
+    fn quote(&self, field: &str) -> String {
+        if field.contains(self.delimiter)
+            || field.contains('"')
+            || field.contains('\n')
+            || field.contains('\r')
+        {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+
+    /// the column name of a greek, e.g., "rho_structure:KRWIRS:3"
+    fn greek_column(greek: &GreekRow) -> String {
+        let mut column = format!("{}:{}", greek.greek, greek.key);
+        if let Some(sub_key) = &greek.sub_key {
+            column.push(':');
+            column.push_str(sub_key);
+        }
+        for index in [greek.row, greek.column].iter().flatten() {
+            column.push_str(&format!(":{}", index));
+        }
+        column
+    }
+
+    /// the greek columns are in the order they first appear in the table
+    pub fn write_results<W: Write>(&self, table: &ResultTable, writer: &mut W) -> Result<()> {
+        let mut greek_columns = Vec::<String>::new();
+        let mut column_set = FxHashSet::<String>::default();
+        let mut greeks = FxHashMap::<(String, String), Real>::default();
+        for greek in table.get_greeks().iter() {
+            let column = CsvWriter::greek_column(greek);
+            if column_set.insert(column.clone()) {
+                greek_columns.push(column.clone());
+            }
+            greeks.insert((greek.inst_id.clone(), column), greek.value);
+        }
+
+        let mut header: Vec<String> = RESULT_COLUMNS.iter().map(|c| c.to_string()).collect();
+        header.extend(greek_columns.iter().cloned());
+        self.write_record(writer, &header)?;
+
+        for row in table.get_results().iter() {
+            let mut fields = vec![
+                row.inst_id.clone(),
+                row.name
+                    .clone()
+                    .unwrap_or_else(|| self.missing_value.clone()),
+                row.inst_type
+                    .clone()
+                    .unwrap_or_else(|| self.missing_value.clone()),
+                row.currency
+                    .map(|currency| currency.to_string())
+                    .unwrap_or_else(|| self.missing_value.clone()),
+                self.number_field(row.unit_notional),
+                self.date_field(row.maturity.as_ref())?,
+                self.date_field(row.evaluation_date.as_ref())?,
+                self.number_field(row.value),
+                self.number_field(row.theta),
+                self.number_field(row.carry),
+                self.number_field(row.roll_down),
+                self.number_field(row.exit_value),
+                self.number_field(row.bid_ask_adjustment),
+            ];
+            for column in greek_columns.iter() {
+                let value = greeks.get(&(row.inst_id.clone(), column.clone())).copied();
+                fields.push(self.number_field(value));
+            }
+            self.write_record(writer, &fields)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_cashflows<W: Write>(&self, table: &ResultTable, writer: &mut W) -> Result<()> {
+        let header: Vec<String> = CASHFLOW_COLUMNS.iter().map(|c| c.to_string()).collect();
+        self.write_record(writer, &header)?;
+
+        for row in table.get_cashflows().iter() {
+            let fields = vec![
+                row.inst_id.clone(),
+                self.format_date(&row.payment_date)?,
+                self.date_field(row.accrual_start_date.as_ref())?,
+                self.date_field(row.accrual_end_date.as_ref())?,
+                self.format_number(row.notional),
+                self.number_field(row.rate),
+                self.format_number(row.amount),
+                row.currency.to_string(),
+                row.leg.as_str().to_string(),
+                row.cashflow_type.as_str().to_string(),
+            ];
+            self.write_record(writer, &fields)?;
+        }
+        Ok(())
+    }
+
+    pub fn results_to_string(&self, table: &ResultTable) -> Result<String> {
+        let mut buffer = Vec::<u8>::new();
+        self.write_results(table, &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    pub fn cashflows_to_string(&self, table: &ResultTable) -> Result<String> {
+        let mut buffer = Vec::<u8>::new();
+        self.write_cashflows(table, &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    /// writes the results and the cashflows of the calculation results in the files
+    pub fn write_files<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        results: &FxHashMap<StaticId, CalculationResult>,
+        results_path: P,
+        cashflows_path: Q,
+    ) -> Result<()> {
+        let table = ResultTable::new(results);
+        for (path, is_results) in [
+            (results_path.as_ref(), true),
+            (cashflows_path.as_ref(), false),
+        ] {
+            let file = std::fs::File::create(path).with_context(|| {
+                anyhow!(
+                    "({}:{}) failed to create {}",
+                    file!(),
+                    line!(),
+                    path.display()
+                )
+            })?;
+            let mut writer = std::io::BufWriter::new(file);
+            if is_results {
+                self.write_results(&table, &mut writer)?;
+            } else {
+                self.write_cashflows(&table, &mut writer)?;
+            }
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::instruments::cashflow::{Cashflow, CashflowLeg, CashflowType};
+    use crate::instruments::inst_info::InstInfo;
+    use crate::pricing_engines::npv_result::NpvResult;
+    use time::macros::datetime;
+
+    fn result_table() -> ResultTable {
+        let evaluation_date = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let curve_id = StaticId::from_str("KRWIRS", "KAP");
+        let inst_id = StaticId::from_str("A", "KRX");
+        let mut result = CalculationResult::new(
+            InstInfo {
+                id: inst_id,
+                name: "Option, \"A\"".to_string(),
+                ..InstInfo::default()
+            },
+            evaluation_date,
+        );
+        result.set_npv(NpvResult::new_from_npv(1_234_567.5));
+        result.set_value().unwrap();
+        result.set_single_delta(und_id, 2.0);
+        result.set_single_rho_structure(curve_id, vec![-0.5, -0.3]);
+        result.set_cashflows(vec![Cashflow::new(
+            datetime!(2024-09-13 00:00:00 +09:00),
+            -0.02,
+            Currency::KRW,
+            CashflowLeg::Single,
+            CashflowType::Expected,
+        )]);
+
+        let mut other = CalculationResult::new(
+            InstInfo {
+                id: StaticId::from_str("B", "KRX"),
+                ..InstInfo::default()
+            },
+            evaluation_date,
+        );
+        other.set_single_vega(und_id, 0.5);
+
+        let mut results = FxHashMap::default();
+        results.insert(inst_id, result);
+        results.insert(StaticId::from_str("B", "KRX"), other);
+        ResultTable::new(&results)
+    }
+
+    #[test]
+    fn test_format_number() -> Result<()> {
+        let writer = CsvWriter::new();
+        assert_eq!(writer.format_number(0.25), "0.25");
+        let writer = writer.with_decimal_places(2).with_thousands_separator(true);
+        assert_eq!(writer.format_number(1_234_567.5), "1,234,567.50");
+        assert_eq!(writer.format_number(-123_456.0), "-123,456.00");
+        assert_eq!(writer.format_number(12.0), "12.00");
+        assert!(CsvWriter::new().with_delimiter('"').is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_writer() -> Result<()> {
+        let table = result_table();
+        let writer = CsvWriter::new()
+            .with_decimal_places(1)
+            .with_thousands_separator(true)
+            .with_missing_value("NA");
+        let results = writer.results_to_string(&table)?;
+        let lines: Vec<&str> = results.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let delta = format!("delta:{}", StaticId::from_str("KOSPI2", "KRX"));
+        let rho = format!("rho_structure:{}", StaticId::from_str("KRWIRS", "KAP"));
+        assert!(lines[0].ends_with(&format!(
+            "bid_ask_adjustment,{},{}:0,{}:1,vega:{}",
+            delta,
+            rho,
+            rho,
+            StaticId::from_str("KOSPI2", "KRX")
+        )));
+        assert!(lines[1].contains("\"Option, \"\"A\"\"\""));
+        assert!(lines[1].contains("\"1,234,567.5\""));
+        assert!(lines[1].contains("2024-03-13T16:30:00+09:00"));
+        assert!(lines[1].ends_with(",2.0,-0.5,-0.3,NA"));
+        assert!(lines[2].ends_with(",NA,NA,NA,0.5"));
+
+        let cashflows = writer.with_delimiter(';')?.cashflows_to_string(&table)?;
+        let lines: Vec<&str> = cashflows.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(
+            lines[1].ends_with(";2024-09-13T00:00:00+09:00;NA;NA;1.0;NA;-0.0;KRW;Single;Expected")
+        );
+        Ok(())
+    }
+}
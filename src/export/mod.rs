@@ -0,0 +1,4 @@
+pub mod csv;
+pub mod result_table;
+#[cfg(feature = "arrow")]
+pub mod arrow;
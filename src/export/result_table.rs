@@ -0,0 +1,357 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::instruments::cashflow::{CashflowLeg, CashflowType};
+use crate::pricing_engines::calculation_result::CalculationResult;
+//
+use rustc_hash::FxHashMap;
+use static_id::static_id::StaticId;
+use std::fmt::Display;
+use time::OffsetDateTime;
+
+/// the scalar results of an instrument
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultRow {
+    pub inst_id: String,
+    pub name: Option<String>,
+    pub inst_type: Option<String>,
+    pub currency: Option<Currency>,
+    pub unit_notional: Option<Real>,
+    pub maturity: Option<OffsetDateTime>,
+    pub evaluation_date: Option<OffsetDateTime>,
+    pub value: Option<Real>,
+    pub theta: Option<Real>,
+    pub carry: Option<Real>,
+    pub roll_down: Option<Real>,
+    pub exit_value: Option<Real>,
+    pub bid_ask_adjustment: Option<Real>,
+}
+
+/// A greek of an instrument on a key, e.g., ("delta", "KOSPI2"), ("rho", "KRWIRS").
+/// sub_key is the curve of delta_rho,
+/// row is the bucket of the structures and fx_exposure_ladder (and the tenor of vega_matrix),
+/// and column is the moneyness of vega_matrix
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreekRow {
+    pub inst_id: String,
+    pub greek: &'static str,
+    pub key: String,
+    pub sub_key: Option<String>,
+    pub row: Option<usize>,
+    pub column: Option<usize>,
+    pub value: Real,
+}
+
+/// a cashflow of an instrument on a unit notional (see Cashflow)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CashflowRow {
+    pub inst_id: String,
+    pub payment_date: OffsetDateTime,
+    pub accrual_start_date: Option<OffsetDateTime>,
+    pub accrual_end_date: Option<OffsetDateTime>,
+    pub notional: Real,
+    pub rate: Option<Real>,
+    pub amount: Real,
+    pub currency: Currency,
+    pub leg: CashflowLeg,
+    pub cashflow_type: CashflowType,
+}
+
+/// The calculation results flattened in three tables for the exporters,
+/// i.e., the scalar results, the greeks in the long format, and the cashflows.
+/// The rows are in the order of the instrument ids, and the greeks in the order of their keys,
+/// so that the same results give the same tables.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultTable {
+    results: Vec<ResultRow>,
+    greeks: Vec<GreekRow>,
+    cashflows: Vec<CashflowRow>,
+}
+
+fn push_greeks<K: Display>(
+    greeks: &mut Vec<GreekRow>,
+    inst_id: &str,
+    greek: &'static str,
+    values: Option<&FxHashMap<K, Real>>,
+) {
+    if let Some(values) = values {
+        let mut values: Vec<(String, Real)> =
+            values.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        values.sort_by(|a, b| a.0.cmp(&b.0));
+        for (key, value) in values {
+            greeks.push(GreekRow {
+                inst_id: inst_id.to_string(),
+                greek,
+                key,
+                sub_key: None,
+                row: None,
+                column: None,
+                value,
+            });
+        }
+    }
+}
+
+fn push_structures<K: Display>(
+    greeks: &mut Vec<GreekRow>,
+    inst_id: &str,
+    greek: &'static str,
+    values: Option<&FxHashMap<K, Vec<Real>>>,
+) {
+    if let Some(values) = values {
+        let mut values: Vec<(String, &Vec<Real>)> =
+            values.iter().map(|(k, v)| (k.to_string(), v)).collect();
+        values.sort_by(|a, b| a.0.cmp(&b.0));
+        for (key, structure) in values {
+            for (row, value) in structure.iter().enumerate() {
+                greeks.push(GreekRow {
+                    inst_id: inst_id.to_string(),
+                    greek,
+                    key: key.clone(),
+                    sub_key: None,
+                    row: Some(row),
+                    column: None,
+                    value: *value,
+                });
+            }
+        }
+    }
+}
+
+/// The greeks of a result in the long format, which are shared by the exporters and the result comparator.
+/// The greeks are in the order of their keys.
+pub fn get_greek_rows(inst_id: &str, result: &CalculationResult) -> Vec<GreekRow> {
+    let mut rows = Vec::new();
+    let greeks = &mut rows;
+    push_greeks(greeks, inst_id, "fx_exposure", result.get_fx_exposure());
+    push_structures(
+        greeks,
+        inst_id,
+        "fx_exposure_ladder",
+        result.get_fx_exposure_ladder(),
+    );
+    push_greeks(greeks, inst_id, "fx_delta", result.get_fx_delta());
+    push_greeks(greeks, inst_id, "fx_gamma", result.get_fx_gamma());
+    push_greeks(greeks, inst_id, "delta", result.get_delta());
+    push_greeks(greeks, inst_id, "gamma", result.get_gamma());
+    push_greeks(greeks, inst_id, "vega", result.get_vega());
+    push_structures(
+        greeks,
+        inst_id,
+        "vega_structure",
+        result.get_vega_structure(),
+    );
+    if let Some(vega_matrix) = result.get_vega_matrix() {
+        let mut keys: Vec<&StaticId> = vega_matrix.keys().collect();
+        keys.sort_by_key(|id| id.to_string());
+        for key in keys {
+            for ((row, column), value) in vega_matrix[key].indexed_iter() {
+                greeks.push(GreekRow {
+                    inst_id: inst_id.to_string(),
+                    greek: "vega_matrix",
+                    key: key.to_string(),
+                    sub_key: None,
+                    row: Some(row),
+                    column: Some(column),
+                    value: *value,
+                });
+            }
+        }
+    }
+    push_greeks(greeks, inst_id, "rho", result.get_rho());
+    if let Some(rho_by_role) = result.get_rho_by_role() {
+        let rho_by_role: FxHashMap<String, Real> = rho_by_role
+            .iter()
+            .map(|(role, value)| (format!("{:?}", role), *value))
+            .collect();
+        push_greeks(greeks, inst_id, "rho_by_role", Some(&rho_by_role));
+    }
+    push_structures(greeks, inst_id, "rho_structure", result.get_rho_structure());
+    push_structures(
+        greeks,
+        inst_id,
+        "gamma_structure",
+        result.get_gamma_structure(),
+    );
+    push_structures(greeks, inst_id, "key_rate_dv01", result.get_key_rate_dv01());
+    if let Some(delta_rho) = result.get_delta_rho() {
+        let mut keys: Vec<&StaticId> = delta_rho.keys().collect();
+        keys.sort_by_key(|id| id.to_string());
+        for key in keys {
+            let mut curves: Vec<(String, Real)> = delta_rho[key]
+                .iter()
+                .map(|(curve, value)| (curve.to_string(), *value))
+                .collect();
+            curves.sort_by(|a, b| a.0.cmp(&b.0));
+            for (curve, value) in curves {
+                greeks.push(GreekRow {
+                    inst_id: inst_id.to_string(),
+                    greek: "delta_rho",
+                    key: key.to_string(),
+                    sub_key: Some(curve),
+                    row: None,
+                    column: None,
+                    value,
+                });
+            }
+        }
+    }
+    push_greeks(greeks, inst_id, "cs01", result.get_cs01());
+    push_structures(
+        greeks,
+        inst_id,
+        "cs01_structure",
+        result.get_cs01_structure(),
+    );
+    push_greeks(greeks, inst_id, "div_delta", result.get_div_delta());
+    push_greeks(greeks, inst_id, "div_carry", result.get_div_carry());
+    push_structures(greeks, inst_id, "div_structure", result.get_div_structure());
+    rows
+}
+
+impl ResultTable {
+    pub fn new(results: &FxHashMap<StaticId, CalculationResult>) -> ResultTable {
+        let mut ids: Vec<&StaticId> = results.keys().collect();
+        ids.sort_by_key(|id| id.to_string());
+
+        let mut table = ResultTable::default();
+        for id in ids {
+            table.push(&id.to_string(), &results[id]);
+        }
+        table
+    }
+
+    fn push(&mut self, inst_id: &str, result: &CalculationResult) {
+        let info = result.get_instrument_info();
+        self.results.push(ResultRow {
+            inst_id: inst_id.to_string(),
+            name: info.map(|info| info.get_name().clone()),
+            inst_type: info.map(|info| info.type_name().to_string()),
+            currency: info.map(|info| info.get_currency()),
+            unit_notional: info.map(|info| info.get_unit_notional()),
+            maturity: info.and_then(|info| info.get_maturity().cloned()),
+            evaluation_date: result.get_evaluation_date().cloned(),
+            value: result.get_value(),
+            theta: result.get_theta(),
+            carry: result.get_carry(),
+            roll_down: result.get_roll_down(),
+            exit_value: result.get_exit_value(),
+            bid_ask_adjustment: result.get_bid_ask_adjustment(),
+        });
+
+        self.greeks.extend(get_greek_rows(inst_id, result));
+
+        if let Some(cashflows) = result.get_cashflows() {
+            for cashflow in cashflows.iter() {
+                self.cashflows.push(CashflowRow {
+                    inst_id: inst_id.to_string(),
+                    payment_date: *cashflow.get_payment_date(),
+                    accrual_start_date: cashflow.get_accrual_start_date().cloned(),
+                    accrual_end_date: cashflow.get_accrual_end_date().cloned(),
+                    notional: cashflow.get_notional(),
+                    rate: cashflow.get_rate(),
+                    amount: cashflow.get_amount(),
+                    currency: cashflow.get_currency(),
+                    leg: cashflow.get_leg(),
+                    cashflow_type: cashflow.get_cashflow_type(),
+                });
+            }
+        }
+    }
+
+    pub fn get_results(&self) -> &Vec<ResultRow> {
+        &self.results
+    }
+
+    pub fn get_greeks(&self) -> &Vec<GreekRow> {
+        &self.greeks
+    }
+
+    pub fn get_cashflows(&self) -> &Vec<CashflowRow> {
+        &self.cashflows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruments::cashflow::Cashflow;
+    use crate::instruments::inst_info::InstInfo;
+    use crate::pricing_engines::npv_result::NpvResult;
+    use ndarray::array;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_result_table() {
+        let evaluation_date = datetime!(2024-03-13 16:30:00 +09:00);
+        let inst_id = StaticId::from_str("KRW IRS", "KRX");
+        let curve_id = StaticId::from_str("KRWIRS", "KAP");
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let mut result = CalculationResult::new(
+            InstInfo {
+                id: inst_id,
+                name: "KRW IRS".to_string(),
+                ..InstInfo::default()
+            },
+            evaluation_date,
+        );
+        result.set_npv(NpvResult::new_from_npv(100.0));
+        result.set_value().unwrap();
+        result.set_single_delta(und_id, 2.0);
+        result.set_single_rho(curve_id, -1.0);
+        result.set_single_rho_structure(curve_id, vec![-0.5, -0.5]);
+        result.set_single_delta_rho(und_id, curve_id, 0.1);
+        result.set_single_vega_matrix(und_id, array![[1.0, 2.0], [3.0, 4.0]]);
+        result.set_cashflows(vec![Cashflow::new(
+            datetime!(2024-09-13 00:00:00 +09:00),
+            0.02,
+            Currency::KRW,
+            CashflowLeg::Fixed,
+            CashflowType::Fixed,
+        )]);
+        let mut other = CalculationResult::new(InstInfo::default(), evaluation_date);
+        other.set_single_delta(und_id, 1.0);
+
+        let mut results = FxHashMap::default();
+        results.insert(inst_id, result);
+        results.insert(StaticId::from_str("A", "KRX"), other);
+        let table = ResultTable::new(&results);
+
+        let rows = table.get_results();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].inst_id, StaticId::from_str("A", "KRX").to_string());
+        assert_eq!(rows[1].name, Some("KRW IRS".to_string()));
+        assert_eq!(rows[1].value, Some(100.0));
+        assert_eq!(rows[1].theta, None);
+
+        let greeks: Vec<(&str, Option<usize>, Option<usize>, Real)> = table
+            .get_greeks()
+            .iter()
+            .filter(|g| g.inst_id == inst_id.to_string())
+            .map(|g| (g.greek, g.row, g.column, g.value))
+            .collect();
+        assert_eq!(
+            greeks,
+            vec![
+                ("delta", None, None, 2.0),
+                ("vega_matrix", Some(0), Some(0), 1.0),
+                ("vega_matrix", Some(0), Some(1), 2.0),
+                ("vega_matrix", Some(1), Some(0), 3.0),
+                ("vega_matrix", Some(1), Some(1), 4.0),
+                ("rho", None, None, -1.0),
+                ("rho_structure", Some(0), None, -0.5),
+                ("rho_structure", Some(1), None, -0.5),
+                ("delta_rho", None, None, 0.1),
+            ]
+        );
+        let delta_rho = table
+            .get_greeks()
+            .iter()
+            .find(|g| g.greek == "delta_rho")
+            .unwrap();
+        assert_eq!(delta_rho.key, und_id.to_string());
+        assert_eq!(delta_rho.sub_key, Some(curve_id.to_string()));
+
+        assert_eq!(table.get_cashflows().len(), 1);
+        assert_eq!(table.get_cashflows()[0].amount, 0.02);
+    }
+}
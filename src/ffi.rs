@@ -0,0 +1,426 @@
+//! C API for embedding the engine in other systems, e.g., C++ or C# risk systems (feature `ffi`).
+//!
+//! The inputs and the outputs are json strings:
+//! 1. `rustmetrics_engine_new` creates an engine, which is freed by `rustmetrics_engine_free`.
+//! 2. `rustmetrics_engine_set_configuration` sets
+//!    `{"calculation_configuration": ..., "match_parameter": ..., "instrument_categories": [...]}`
+//!    (all optional, the defaults and a category of all the instruments if omitted).
+//! 3. `rustmetrics_engine_set_portfolio` sets the instruments
+//!    (a list of `Instrument` or the schema-versioned json of `Instruments`).
+//! 4. `rustmetrics_engine_set_market_data` sets a `MarketDataSet` (or its schema-versioned json).
+//!    The evaluation datetime of the calculation is the one of the market data.
+//! 5. `rustmetrics_engine_run` calculates the instruments.
+//! 6. `rustmetrics_engine_get_results` returns the results keyed by the instrument ids,
+//!    which is freed by `rustmetrics_string_free`.
+//!
+//! The functions except the getters return a `RustmetricsStatus`, and the message of the last error
+//! is given by `rustmetrics_engine_last_error`. The panics are caught and returned as `Panic`.
+//!
+//! The crate is an rlib, and the shared or the static library of the API is built by
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib` (or `--crate-type staticlib`).
+//! The header `include/rustmetrics.h` is generated by cbindgen:
+//! `cbindgen --config cbindgen.toml --output include/rustmetrics.h`
+use crate::instrument::Instruments;
+use crate::pricing_engines::{
+    calculation_configuration::CalculationConfiguration, calculation_result::CalculationResult,
+    engine_generator::InstrumentCategory, match_parameter::MatchParameter,
+    pnl_explain::MarketDataSet,
+};
+use crate::utils::versioned::from_versioned_json;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+use static_id::static_id::StaticId;
+use std::collections::BTreeMap;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RustmetricsStatus {
+    Ok = 0,
+    /// the engine is null
+    NullPointer = 1,
+    /// the json is null, not utf-8 or not of the expected layout
+    InvalidArgument = 2,
+    /// the inputs are missing or the calculation failed
+    CalculationError = 3,
+    Panic = 4,
+}
+
+#[derive(Deserialize, Default)]
+struct Configuration {
+    #[serde(default)]
+    calculation_configuration: CalculationConfiguration,
+    #[serde(default)]
+    match_parameter: MatchParameter,
+    #[serde(default)]
+    instrument_categories: Vec<InstrumentCategory>,
+}
+
+/// An engine with its inputs and the results of the last run. Opaque in C.
+pub struct RustmetricsEngine {
+    configuration: Configuration,
+    instruments: Instruments,
+    market_data: Option<MarketDataSet>,
+    results: FxHashMap<StaticId, CalculationResult>,
+    last_error: CString,
+}
+
+impl RustmetricsEngine {
+    fn set_error(&mut self, message: String) {
+        // an interior nul can not be in a C string
+        self.last_error = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    }
+
+    fn run(&mut self) -> Result<()> {
+        let market_data = self
+            .market_data
+            .as_ref()
+            .ok_or_else(|| anyhow!("({}:{}) market data is not set", file!(), line!()))?;
+        let mut instrument_categories = self.configuration.instrument_categories.clone();
+        if instrument_categories.is_empty() {
+            instrument_categories.push(InstrumentCategory::default());
+        }
+        self.results = market_data.run_engine(
+            self.configuration.calculation_configuration.clone(),
+            self.configuration.match_parameter.clone(),
+            self.instruments.clone(),
+            instrument_categories,
+        )?;
+        Ok(())
+    }
+
+    fn results_json(&self) -> Result<String> {
+        let results: BTreeMap<String, &CalculationResult> = self
+            .results
+            .iter()
+            .map(|(id, result)| (id.to_string(), result))
+            .collect();
+        Ok(serde_json::to_string(&results)?)
+    }
+}
+
+unsafe fn read_str<'a>(s: *const c_char) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(anyhow!("({}:{}) the json is null", file!(), line!()));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|e| anyhow!("({}:{}) the json is not utf-8: {}", file!(), line!(), e))
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic".to_string(),
+        },
+    }
+}
+
+/// runs f on the engine, and records the error (or the panic) in the engine
+unsafe fn call(
+    engine: *mut RustmetricsEngine,
+    status_on_error: RustmetricsStatus,
+    f: impl FnOnce(&mut RustmetricsEngine) -> Result<()>,
+) -> RustmetricsStatus {
+    let Some(engine) = engine.as_mut() else {
+        return RustmetricsStatus::NullPointer;
+    };
+    match panic::catch_unwind(AssertUnwindSafe(|| f(&mut *engine))) {
+        Ok(Ok(())) => {
+            engine.set_error(String::new());
+            RustmetricsStatus::Ok
+        }
+        Ok(Err(e)) => {
+            engine.set_error(format!("{:#}", e));
+            status_on_error
+        }
+        Err(payload) => {
+            engine.set_error(panic_message(payload));
+            RustmetricsStatus::Panic
+        }
+    }
+}
+
+/// version of the library, e.g., "0.1.0". The string is static.
+#[no_mangle]
+pub extern "C" fn rustmetrics_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+/// creates an engine with the default configuration
+#[no_mangle]
+pub extern "C" fn rustmetrics_engine_new() -> *mut RustmetricsEngine {
+    Box::into_raw(Box::new(RustmetricsEngine {
+        configuration: Configuration::default(),
+        instruments: Instruments::default(),
+        market_data: None,
+        results: FxHashMap::default(),
+        last_error: CString::default(),
+    }))
+}
+
+/// # Safety
+/// The engine must be created by rustmetrics_engine_new and not be freed yet, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn rustmetrics_engine_free(engine: *mut RustmetricsEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
+
+/// # Safety
+/// The engine must be valid or null, and the json must be a nul-terminated string or null.
+#[no_mangle]
+pub unsafe extern "C" fn rustmetrics_engine_set_configuration(
+    engine: *mut RustmetricsEngine,
+    json: *const c_char,
+) -> RustmetricsStatus {
+    call(engine, RustmetricsStatus::InvalidArgument, |engine| {
+        engine.configuration = serde_json::from_str(read_str(json)?)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// The engine must be valid or null, and the json must be a nul-terminated string or null.
+#[no_mangle]
+pub unsafe extern "C" fn rustmetrics_engine_set_portfolio(
+    engine: *mut RustmetricsEngine,
+    json: *const c_char,
+) -> RustmetricsStatus {
+    call(engine, RustmetricsStatus::InvalidArgument, |engine| {
+        engine.instruments = from_versioned_json::<Instruments>(read_str(json)?)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// The engine must be valid or null, and the json must be a nul-terminated string or null.
+#[no_mangle]
+pub unsafe extern "C" fn rustmetrics_engine_set_market_data(
+    engine: *mut RustmetricsEngine,
+    json: *const c_char,
+) -> RustmetricsStatus {
+    call(engine, RustmetricsStatus::InvalidArgument, |engine| {
+        engine.market_data = Some(from_versioned_json::<MarketDataSet>(read_str(json)?)?);
+        Ok(())
+    })
+}
+
+/// calculates the portfolio on the market data. The results of the previous run are dropped.
+///
+/// # Safety
+/// The engine must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rustmetrics_engine_run(
+    engine: *mut RustmetricsEngine,
+) -> RustmetricsStatus {
+    call(engine, RustmetricsStatus::CalculationError, |engine| {
+        engine.results.clear();
+        engine.run()
+    })
+}
+
+/// json of the results of the last run keyed by the instrument ids,
+/// or null on an error. The string is freed by rustmetrics_string_free.
+///
+/// # Safety
+/// The engine must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rustmetrics_engine_get_results(
+    engine: *mut RustmetricsEngine,
+) -> *mut c_char {
+    let mut results = ptr::null_mut();
+    call(engine, RustmetricsStatus::CalculationError, |engine| {
+        results = CString::new(engine.results_json()?)?.into_raw();
+        Ok(())
+    });
+    results
+}
+
+/// message of the error of the last call on the engine ("" if it succeeded), or null for a null engine.
+/// The string is owned by the engine and valid until the next call on it.
+///
+/// # Safety
+/// The engine must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rustmetrics_engine_last_error(
+    engine: *const RustmetricsEngine,
+) -> *const c_char {
+    match engine.as_ref() {
+        Some(engine) => engine.last_error.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// # Safety
+/// The string must be returned by this library and not be freed yet, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn rustmetrics_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::data::{value_data::ValueData, vector_data::VectorData};
+    use crate::instrument::Instrument;
+    use crate::instruments::{futures::Futures, inst_info::InstInfo};
+    use crate::utils::versioned::to_versioned_json;
+    use crate::InstType;
+    use ndarray::array;
+    use serde_json::Value;
+    use std::sync::Arc;
+    use time::macros::datetime;
+
+    fn c_string(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    unsafe fn last_error(engine: *const RustmetricsEngine) -> String {
+        CStr::from_ptr(rustmetrics_engine_last_error(engine))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    fn test_ffi() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let futures_id = StaticId::from_str("KOSPI2 Fut", "KRX");
+        let futures = Futures::new(
+            InstInfo {
+                id: futures_id,
+                issue_date: Some(datetime!(2024-01-02 00:00:00 +09:00)),
+                maturity: Some(datetime!(2024-09-13 00:00:00 +09:00)),
+                currency: Currency::KRW,
+                inst_type: InstType::Futures,
+                unit_notional: 250_000.0,
+                ..InstInfo::default()
+            },
+            350.0,
+            None,
+            Currency::KRW,
+            und_id,
+        );
+        let portfolio = to_versioned_json(&Instruments::new(vec![Arc::new(Instrument::Futures(
+            futures,
+        ))]))?;
+
+        let curve_id = StaticId::from_str("KSD", "DataProvider");
+        let mut curve_data = FxHashMap::default();
+        curve_data.insert(
+            curve_id,
+            VectorData::new(
+                array![0.035, 0.035],
+                Some(vec![
+                    datetime!(2025-03-13 00:00:00 +09:00),
+                    datetime!(2026-03-13 00:00:00 +09:00),
+                ]),
+                None,
+                Some(dt),
+                Currency::KRW,
+                "KSD".to_string(),
+                curve_id,
+            )?,
+        );
+        let mut stock_data = FxHashMap::default();
+        stock_data.insert(
+            und_id,
+            ValueData::new(350.0, Some(dt), Currency::KRW, "KOSPI2".to_string(), und_id)?,
+        );
+        let market_data = MarketDataSet::new(dt).with_data(
+            FxHashMap::default(),
+            stock_data,
+            curve_data,
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+        );
+        let mut collateral_curve_map = FxHashMap::default();
+        collateral_curve_map.insert(und_id, curve_id);
+        // the borrowing fee is given by the same curve
+        let borrowing_curve_map = collateral_curve_map.clone();
+        let match_parameter = MatchParameter::new(
+            collateral_curve_map,
+            borrowing_curve_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+        );
+        let configuration = serde_json::json!({ "match_parameter": match_parameter }).to_string();
+
+        unsafe {
+            let engine = rustmetrics_engine_new();
+            // the market data is not set yet
+            assert_eq!(
+                rustmetrics_engine_run(engine),
+                RustmetricsStatus::CalculationError
+            );
+            assert!(last_error(engine).contains("market data is not set"));
+
+            assert_eq!(
+                rustmetrics_engine_set_portfolio(engine, c_string("[{\"Swap\": {}}]").as_ptr()),
+                RustmetricsStatus::InvalidArgument
+            );
+            assert_eq!(
+                rustmetrics_engine_set_market_data(engine, ptr::null()),
+                RustmetricsStatus::InvalidArgument
+            );
+            assert!(last_error(engine).contains("null"));
+
+            let configuration = c_string(&configuration);
+            let portfolio = c_string(&portfolio);
+            let market_data = c_string(&serde_json::to_string(&market_data)?);
+            assert_eq!(
+                rustmetrics_engine_set_configuration(engine, configuration.as_ptr()),
+                RustmetricsStatus::Ok
+            );
+            assert_eq!(
+                rustmetrics_engine_set_portfolio(engine, portfolio.as_ptr()),
+                RustmetricsStatus::Ok
+            );
+            assert_eq!(
+                rustmetrics_engine_set_market_data(engine, market_data.as_ptr()),
+                RustmetricsStatus::Ok
+            );
+            let status = rustmetrics_engine_run(engine);
+            assert_eq!(status, RustmetricsStatus::Ok, "{}", last_error(engine));
+            assert_eq!(last_error(engine), "");
+
+            let results = rustmetrics_engine_get_results(engine);
+            assert!(!results.is_null());
+            let json: Value = serde_json::from_str(CStr::from_ptr(results).to_str()?)?;
+            rustmetrics_string_free(results);
+            rustmetrics_engine_free(engine);
+
+            let result: CalculationResult =
+                serde_json::from_value(json[futures_id.to_string()].clone())?;
+            assert!(result.get_value().is_some());
+
+            assert_eq!(
+                rustmetrics_engine_run(ptr::null_mut()),
+                RustmetricsStatus::NullPointer
+            );
+            assert!(rustmetrics_engine_last_error(ptr::null()).is_null());
+            assert_eq!(
+                CStr::from_ptr(rustmetrics_version()).to_str()?,
+                env!("CARGO_PKG_VERSION")
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::enums::{FrtbRiskClass, FrtbRiskMeasure, LiquidityHorizon};
+use crate::frtb::sbm::{SbmReport, SbmSensitivity};
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+/// base horizon of the expected shortfall in days (MAR33.4)
+pub const BASE_HORIZON_DAYS: usize = 10;
+/// GIRR currencies on 10 days besides the reporting currency (MAR33.12)
+pub const GIRR_SPECIFIED_CURRENCIES: [&str; 6] = ["EUR", "USD", "GBP", "AUD", "JPY", "CAD"];
+/// fx pairs on 10 days (MAR33.12), pairs of the currencies in the tree
+pub const FX_SPECIFIED_PAIRS: [(&str, &str); 9] = [
+    ("USD", "EUR"),
+    ("USD", "JPY"),
+    ("USD", "GBP"),
+    ("USD", "AUD"),
+    ("USD", "CAD"),
+    ("USD", "CHF"),
+    ("USD", "CNY"),
+    ("USD", "NZD"),
+    ("USD", "KRW"),
+];
+
+/// Tags the SBM sensitivities with the liquidity horizons of MAR33.12 by the risk class and the bucket.
+///
+/// - GIRR: 10 days for the reporting currency and GIRR_SPECIFIED_CURRENCIES, 20 days for the others, 60 days for vega
+/// - CSR: sovereign IG (bucket 1) 20 days, sovereign HY (9) and IG (2-8, 17) 40 days,
+///   HY (10-15, 18) 60 days, others (16) and vega 120 days
+/// - Equity: large cap (1-8, 12, 13) 10 days, small cap (9, 10) 20 days, other sector (11) 60 days.
+///   Vega is 20 days for large cap and 60 days for the others
+/// - Fx: 10 days for FX_SPECIFIED_PAIRS against the reporting currency, 20 days for the others, 40 days for vega
+///
+/// Curvature follows delta. The horizons can be overridden by the risk factor with with_liquidity_horizon.
+#[derive(Debug, Clone, Default)]
+pub struct LiquidityHorizonMapper {
+    reporting_currency: Currency,
+    overrides: FxHashMap<String, LiquidityHorizon>,
+}
+
+impl LiquidityHorizonMapper {
+    pub fn new(reporting_currency: Currency) -> LiquidityHorizonMapper {
+        LiquidityHorizonMapper {
+            reporting_currency,
+            overrides: FxHashMap::default(),
+        }
+    }
+
+    /// horizon of all measures on the risk factor, e.g., the curve id or the underlying id
+    pub fn with_liquidity_horizon(
+        mut self,
+        risk_factor: &str,
+        liquidity_horizon: LiquidityHorizon,
+    ) -> LiquidityHorizonMapper {
+        self.overrides
+            .insert(risk_factor.to_string(), liquidity_horizon);
+        self
+    }
+
+    pub fn get_reporting_currency(&self) -> Currency {
+        self.reporting_currency
+    }
+
+    pub fn get_liquidity_horizon(&self, sensitivity: &SbmSensitivity) -> LiquidityHorizon {
+        if let Some(lh) = self.overrides.get(sensitivity.get_risk_factor()) {
+            return *lh;
+        }
+        let bucket = sensitivity.get_bucket().as_str();
+        let is_vega = sensitivity.get_risk_measure() == FrtbRiskMeasure::Vega;
+        match sensitivity.get_risk_class() {
+            FrtbRiskClass::Girr => {
+                if is_vega {
+                    LiquidityHorizon::Days60
+                } else if bucket == self.reporting_currency.as_str()
+                    || GIRR_SPECIFIED_CURRENCIES.contains(&bucket)
+                {
+                    LiquidityHorizon::Days10
+                } else {
+                    LiquidityHorizon::Days20
+                }
+            }
+            FrtbRiskClass::Csr => match (is_vega, bucket.parse::<usize>().unwrap_or(0)) {
+                (true, _) => LiquidityHorizon::Days120,
+                (false, 1) => LiquidityHorizon::Days20,
+                (false, 2..=9) | (false, 17) => LiquidityHorizon::Days40,
+                (false, 10..=15) | (false, 18) => LiquidityHorizon::Days60,
+                _ => LiquidityHorizon::Days120,
+            },
+            FrtbRiskClass::Equity => match (is_vega, bucket.parse::<usize>().unwrap_or(0)) {
+                (false, 1..=8) | (false, 12) | (false, 13) => LiquidityHorizon::Days10,
+                (false, 9) | (false, 10) => LiquidityHorizon::Days20,
+                (true, 1..=8) | (true, 12) | (true, 13) => LiquidityHorizon::Days20,
+                _ => LiquidityHorizon::Days60,
+            },
+            FrtbRiskClass::Fx => {
+                let reporting = self.reporting_currency.as_str();
+                let specified = FX_SPECIFIED_PAIRS.iter().any(|(c1, c2)| {
+                    (*c1 == bucket && *c2 == reporting) || (*c1 == reporting && *c2 == bucket)
+                });
+                if is_vega {
+                    LiquidityHorizon::Days40
+                } else if specified {
+                    LiquidityHorizon::Days10
+                } else {
+                    LiquidityHorizon::Days20
+                }
+            }
+        }
+    }
+
+    pub fn generate(&self, report: &SbmReport) -> LiquidityHorizonReport {
+        let mut sensitivities: FxHashMap<LiquidityHorizon, Vec<SbmSensitivity>> =
+            FxHashMap::default();
+        for s in report.get_sensitivities().iter() {
+            sensitivities
+                .entry(self.get_liquidity_horizon(s))
+                .or_default()
+                .push(s.clone());
+        }
+        LiquidityHorizonReport {
+            reporting_currency: report.get_reporting_currency(),
+            sensitivities,
+        }
+    }
+}
+
+/// SBM sensitivities bucketed by the liquidity horizons
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiquidityHorizonReport {
+    reporting_currency: Currency,
+    sensitivities: FxHashMap<LiquidityHorizon, Vec<SbmSensitivity>>,
+}
+
+impl LiquidityHorizonReport {
+    pub fn get_reporting_currency(&self) -> Currency {
+        self.reporting_currency
+    }
+
+    /// sensitivities on the horizon
+    pub fn get_sensitivities(&self, liquidity_horizon: LiquidityHorizon) -> Vec<&SbmSensitivity> {
+        self.sensitivities
+            .get(&liquidity_horizon)
+            .map(|v| v.iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// sensitivities on the horizons longer than or equal to the given horizon,
+    /// which are the risk factors shocked for ES_T(P, j) (MAR33.4)
+    pub fn get_cascade_sensitivities(
+        &self,
+        liquidity_horizon: LiquidityHorizon,
+    ) -> Vec<&SbmSensitivity> {
+        LiquidityHorizon::all()
+            .iter()
+            .filter(|lh| **lh >= liquidity_horizon)
+            .flat_map(|lh| self.get_sensitivities(*lh))
+            .collect()
+    }
+
+    /// sum of the sensitivities of each risk class and measure on the horizon
+    pub fn get_net_sensitivities(
+        &self,
+        liquidity_horizon: LiquidityHorizon,
+    ) -> FxHashMap<(FrtbRiskClass, FrtbRiskMeasure), Real> {
+        let mut res = FxHashMap::default();
+        for s in self.get_sensitivities(liquidity_horizon) {
+            *res.entry((s.get_risk_class(), s.get_risk_measure()))
+                .or_insert(0.0) += s.get_sensitivity();
+        }
+        res
+    }
+}
+
+/// sqrt((LH_j - LH_{j-1}) / T) of MAR33.4 with LH_0 = 0, which is 1 for 10 days
+pub fn get_scaling_factor(liquidity_horizon: LiquidityHorizon) -> Real {
+    let days = liquidity_horizon.as_days();
+    let previous = LiquidityHorizon::all()
+        .iter()
+        .map(|lh| lh.as_days())
+        .filter(|d| *d < days)
+        .max()
+        .unwrap_or(0);
+    ((days - previous) as Real / BASE_HORIZON_DAYS as Real).sqrt()
+}
+
+/// liquidity adjusted ES of MAR33.4 from the partial expected shortfalls
+/// ES_T(P, j) of the 10 day shocks on the risk factors with horizons longer than or equal to LH_j.
+/// partial_es is in the order of LiquidityHorizon::all(), where the first is ES_T(P) of the whole portfolio.
+pub fn get_liquidity_adjusted_es(partial_es: &[Real]) -> Result<Real> {
+    let horizons = LiquidityHorizon::all();
+    if partial_es.len() != horizons.len() {
+        return Err(anyhow!(
+            "({}:{}) {} partial expected shortfalls are required, but {} are given",
+            file!(),
+            line!(),
+            horizons.len(),
+            partial_es.len(),
+        ));
+    }
+    let sum_squares: Real = horizons
+        .iter()
+        .zip(partial_es.iter())
+        .map(|(lh, es)| (es * get_scaling_factor(*lh)).powi(2))
+        .sum();
+    Ok(sum_squares.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_liquidity_horizon_mapper() -> Result<()> {
+        let sensitivity = |class, measure, bucket: &str, risk_factor: &str, value| {
+            SbmSensitivity::new(
+                class,
+                measure,
+                bucket.to_string(),
+                risk_factor.to_string(),
+                None,
+                None,
+                value,
+            )
+        };
+        let report = SbmReport::new(
+            Currency::KRW,
+            vec![
+                sensitivity(
+                    FrtbRiskClass::Girr,
+                    FrtbRiskMeasure::Delta,
+                    "KRW",
+                    "KRWIRS",
+                    1.0,
+                ),
+                sensitivity(
+                    FrtbRiskClass::Girr,
+                    FrtbRiskMeasure::Delta,
+                    "CNY",
+                    "CNYIRS",
+                    2.0,
+                ),
+                sensitivity(FrtbRiskClass::Csr, FrtbRiskMeasure::Delta, "1", "KTB", 3.0),
+                sensitivity(
+                    FrtbRiskClass::Csr,
+                    FrtbRiskMeasure::Delta,
+                    "16",
+                    "KRW-AA",
+                    4.0,
+                ),
+                sensitivity(
+                    FrtbRiskClass::Equity,
+                    FrtbRiskMeasure::Delta,
+                    "12",
+                    "KOSPI2",
+                    5.0,
+                ),
+                sensitivity(
+                    FrtbRiskClass::Equity,
+                    FrtbRiskMeasure::Vega,
+                    "12",
+                    "KOSPI2",
+                    6.0,
+                ),
+                sensitivity(
+                    FrtbRiskClass::Equity,
+                    FrtbRiskMeasure::Delta,
+                    "11",
+                    "ABC",
+                    7.0,
+                ),
+                sensitivity(FrtbRiskClass::Fx, FrtbRiskMeasure::Delta, "USD", "USD", 8.0),
+                sensitivity(FrtbRiskClass::Fx, FrtbRiskMeasure::Delta, "EUR", "EUR", 9.0),
+            ],
+        );
+        let mapper = LiquidityHorizonMapper::new(Currency::KRW);
+        let horizons: Vec<LiquidityHorizon> = report
+            .get_sensitivities()
+            .iter()
+            .map(|s| mapper.get_liquidity_horizon(s))
+            .collect();
+        assert_eq!(
+            horizons,
+            vec![
+                LiquidityHorizon::Days10,
+                LiquidityHorizon::Days20,
+                LiquidityHorizon::Days20,
+                LiquidityHorizon::Days120,
+                LiquidityHorizon::Days10,
+                LiquidityHorizon::Days20,
+                LiquidityHorizon::Days60,
+                LiquidityHorizon::Days10,
+                LiquidityHorizon::Days20,
+            ]
+        );
+
+        let mapper = mapper.with_liquidity_horizon("ABC", LiquidityHorizon::Days20);
+        let lh_report = mapper.generate(&report);
+        let net = lh_report.get_net_sensitivities(LiquidityHorizon::Days20);
+        assert_eq!(net[&(FrtbRiskClass::Girr, FrtbRiskMeasure::Delta)], 2.0);
+        assert_eq!(net[&(FrtbRiskClass::Equity, FrtbRiskMeasure::Delta)], 7.0);
+        assert_eq!(net[&(FrtbRiskClass::Equity, FrtbRiskMeasure::Vega)], 6.0);
+        assert!(lh_report
+            .get_sensitivities(LiquidityHorizon::Days60)
+            .is_empty());
+        assert_eq!(
+            lh_report
+                .get_cascade_sensitivities(LiquidityHorizon::Days10)
+                .len(),
+            report.get_sensitivities().len()
+        );
+        assert_eq!(
+            lh_report
+                .get_cascade_sensitivities(LiquidityHorizon::Days40)
+                .len(),
+            1
+        );
+
+        // sqrt(1 + 1 * 1 + 0 + 0 + 1 * 6)
+        assert!(
+            (get_scaling_factor(LiquidityHorizon::Days120) - (6.0 as Real).sqrt()).abs() < 1.0e-6
+        );
+        let es = get_liquidity_adjusted_es(&[1.0, 1.0, 0.0, 0.0, 1.0])?;
+        assert!((es - (8.0 as Real).sqrt()).abs() < 1.0e-5);
+        assert!(get_liquidity_adjusted_es(&[1.0]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,2 @@
+pub mod liquidity_horizon;
+pub mod sbm;
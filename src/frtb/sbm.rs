@@ -0,0 +1,618 @@
+use crate::currency::Currency;
+use crate::definitions::{Real, DELTA_PNL_UNIT, GAMMA_PNL_UNIT, RHO_PNL_UNIT, VEGA_PNL_UNIT};
+use crate::enums::{FrtbRiskClass, FrtbRiskMeasure};
+use crate::instrument::{InstrumentTrait, Instruments};
+use crate::pricing_engines::{
+    calculation_configuration::CalculationConfiguration, calculation_result::CalculationResult,
+    pnl_explain::MarketDataSet, pnl_predictor::get_delta_underlying_id,
+};
+use crate::time::period::Tenor;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+
+/// GIRR delta vertices in years (MAR21.8)
+pub const GIRR_TENORS: [Real; 10] = [0.25, 0.5, 1.0, 2.0, 3.0, 5.0, 10.0, 15.0, 20.0, 30.0];
+pub const GIRR_TENOR_LABELS: [&str; 10] = [
+    "3M", "6M", "1Y", "2Y", "3Y", "5Y", "10Y", "15Y", "20Y", "30Y",
+];
+/// CSR delta vertices in years (MAR21.11)
+pub const CSR_TENORS: [Real; 5] = [0.5, 1.0, 3.0, 5.0, 10.0];
+pub const CSR_TENOR_LABELS: [&str; 5] = ["6M", "1Y", "3Y", "5Y", "10Y"];
+/// option maturities of the vega risk factors (MAR21.12)
+pub const VEGA_TENORS: [Real; 5] = [0.5, 1.0, 3.0, 5.0, 10.0];
+pub const VEGA_TENOR_LABELS: [&str; 5] = ["6M", "1Y", "3Y", "5Y", "10Y"];
+/// equity delta risk weights of the spot by bucket 1, ..., 13 (MAR21.77),
+/// which are also the curvature shocks (MAR21.98)
+pub const EQUITY_SPOT_RISK_WEIGHTS: [Real; 13] = [
+    0.55, 0.60, 0.45, 0.55, 0.30, 0.35, 0.40, 0.50, 0.70, 0.50, 0.70, 0.15, 0.25,
+];
+/// "other sector" buckets for the names without a given bucket
+pub const EQUITY_OTHER_BUCKET: usize = 11;
+pub const CSR_OTHER_BUCKET: usize = 16;
+
+/// A sensitivity in the reporting currency on an FRTB risk factor.
+///
+/// - bucket: currency (GIRR), sector bucket number (CSR, Equity), or currency (Fx)
+/// - risk_factor: curve id (GIRR, CSR), underlying id (Equity), or currency (Fx)
+/// - tenor: vertex of GIRR and CSR delta, option maturity of vega, None for the others
+/// - instrument_id: None for the netted sensitivities
+///
+/// Delta is the value change for a unit relative move of the spot or fx (s = ΔV / 0.01 for 1%)
+/// and for a unit move of rate or spread (s = ΔV / 0.0001 for 1bp).
+/// Vega is ∂V/∂σ · σ. Curvature is the CVR of the up and down shocks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SbmSensitivity {
+    risk_class: FrtbRiskClass,
+    risk_measure: FrtbRiskMeasure,
+    bucket: String,
+    risk_factor: String,
+    tenor: Option<String>,
+    instrument_id: Option<StaticId>,
+    sensitivity: Real,
+}
+
+impl SbmSensitivity {
+    pub fn new(
+        risk_class: FrtbRiskClass,
+        risk_measure: FrtbRiskMeasure,
+        bucket: String,
+        risk_factor: String,
+        tenor: Option<String>,
+        instrument_id: Option<StaticId>,
+        sensitivity: Real,
+    ) -> SbmSensitivity {
+        SbmSensitivity {
+            risk_class,
+            risk_measure,
+            bucket,
+            risk_factor,
+            tenor,
+            instrument_id,
+            sensitivity,
+        }
+    }
+
+    pub fn get_risk_class(&self) -> FrtbRiskClass {
+        self.risk_class
+    }
+
+    pub fn get_risk_measure(&self) -> FrtbRiskMeasure {
+        self.risk_measure
+    }
+
+    pub fn get_bucket(&self) -> &String {
+        &self.bucket
+    }
+
+    pub fn get_risk_factor(&self) -> &String {
+        &self.risk_factor
+    }
+
+    pub fn get_tenor(&self) -> Option<&String> {
+        self.tenor.as_ref()
+    }
+
+    pub fn get_instrument_id(&self) -> Option<StaticId> {
+        self.instrument_id
+    }
+
+    pub fn get_sensitivity(&self) -> Real {
+        self.sensitivity
+    }
+}
+
+/// SBM sensitivities of a portfolio for the capital calculation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SbmReport {
+    reporting_currency: Currency,
+    sensitivities: Vec<SbmSensitivity>,
+}
+
+impl SbmReport {
+    pub fn new(reporting_currency: Currency, sensitivities: Vec<SbmSensitivity>) -> SbmReport {
+        SbmReport {
+            reporting_currency,
+            sensitivities,
+        }
+    }
+
+    pub fn get_reporting_currency(&self) -> Currency {
+        self.reporting_currency
+    }
+
+    /// sensitivities by instrument
+    pub fn get_sensitivities(&self) -> &Vec<SbmSensitivity> {
+        &self.sensitivities
+    }
+
+    pub fn get_risk_class_sensitivities(&self, risk_class: FrtbRiskClass) -> Vec<&SbmSensitivity> {
+        self.sensitivities
+            .iter()
+            .filter(|s| s.risk_class == risk_class)
+            .collect()
+    }
+
+    /// sensitivities netted across the instruments on each risk factor, in the order of appearance
+    pub fn get_net_sensitivities(&self) -> Vec<SbmSensitivity> {
+        let mut res: Vec<SbmSensitivity> = vec![];
+        let mut index = FxHashMap::default();
+        for s in self.sensitivities.iter() {
+            let key = (
+                s.risk_class,
+                s.risk_measure,
+                s.bucket.clone(),
+                s.risk_factor.clone(),
+                s.tenor.clone(),
+            );
+            match index.get(&key) {
+                Some(&i) => {
+                    let net: &mut SbmSensitivity = &mut res[i];
+                    net.sensitivity += s.sensitivity;
+                }
+                None => {
+                    index.insert(key, res.len());
+                    res.push(SbmSensitivity {
+                        instrument_id: None,
+                        ..s.clone()
+                    });
+                }
+            }
+        }
+        res
+    }
+}
+
+/// Maps the greeks in CalculationResult to the FRTB SBM risk factors.
+///
+/// - GIRR delta: rho_structure of each curve, bucketed by the curve currency
+/// - CSR delta: cs01_structure of each credit curve, bucketed by with_credit_bucket (default 16)
+/// - Equity delta, vega and curvature: delta, vega_structure (or vega) and gamma of each underlying,
+///   bucketed by with_equity_bucket (default 11)
+/// - Fx delta: fx exposure of each currency other than the reporting currency
+///
+/// The structures are computed on the buckets (t_{i-1}, t_i] of the tenors in CalculationConfiguration,
+/// and the sensitivity of a bucket is allocated linearly to the adjacent FRTB vertices of t_i.
+/// A parallel vega without vega_structure is put on the maturity of the instrument.
+/// Curvature is approximated by gamma: CVR+ = CVR- = -0.5 Γ (RW S)², where RW is the spot risk weight of the bucket.
+/// The greeks need to be calculated beforehand; those not in the results are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct SbmSensitivityMapper {
+    reporting_currency: Currency,
+    equity_buckets: FxHashMap<StaticId, usize>,
+    credit_buckets: FxHashMap<StaticId, usize>,
+}
+
+impl SbmSensitivityMapper {
+    pub fn new(reporting_currency: Currency) -> SbmSensitivityMapper {
+        SbmSensitivityMapper {
+            reporting_currency,
+            equity_buckets: FxHashMap::default(),
+            credit_buckets: FxHashMap::default(),
+        }
+    }
+
+    pub fn with_equity_bucket(
+        mut self,
+        und_id: StaticId,
+        bucket: usize,
+    ) -> Result<SbmSensitivityMapper> {
+        if bucket == 0 || bucket > EQUITY_SPOT_RISK_WEIGHTS.len() {
+            return Err(anyhow!(
+                "({}:{}) equity bucket must be in 1..={}, but {} is given for {}",
+                file!(),
+                line!(),
+                EQUITY_SPOT_RISK_WEIGHTS.len(),
+                bucket,
+                und_id,
+            ));
+        }
+        self.equity_buckets.insert(und_id, bucket);
+        Ok(self)
+    }
+
+    pub fn with_credit_bucket(
+        mut self,
+        curve_id: StaticId,
+        bucket: usize,
+    ) -> Result<SbmSensitivityMapper> {
+        if bucket == 0 || bucket > 18 {
+            return Err(anyhow!(
+                "({}:{}) credit bucket must be in 1..=18, but {} is given for {}",
+                file!(),
+                line!(),
+                bucket,
+                curve_id,
+            ));
+        }
+        self.credit_buckets.insert(curve_id, bucket);
+        Ok(self)
+    }
+
+    pub fn get_reporting_currency(&self) -> Currency {
+        self.reporting_currency
+    }
+
+    pub fn get_equity_bucket(&self, und_id: &StaticId) -> usize {
+        *self
+            .equity_buckets
+            .get(und_id)
+            .unwrap_or(&EQUITY_OTHER_BUCKET)
+    }
+
+    pub fn get_credit_bucket(&self, curve_id: &StaticId) -> usize {
+        *self
+            .credit_buckets
+            .get(curve_id)
+            .unwrap_or(&CSR_OTHER_BUCKET)
+    }
+
+    pub fn generate(
+        &self,
+        calculation_configuration: &CalculationConfiguration,
+        instruments: &Instruments,
+        results: &FxHashMap<StaticId, CalculationResult>,
+        market_data: &MarketDataSet,
+    ) -> Result<SbmReport> {
+        let rho_times = get_tenor_years(calculation_configuration.get_rho_structure_tenors());
+        let cs01_times = get_tenor_years(calculation_configuration.get_cs01_structure_tenors());
+        let vega_times = get_tenor_years(calculation_configuration.get_vega_structure_tenors());
+        let evaluation_datetime = market_data.get_evaluation_datetime();
+
+        let mut sensitivities = vec![];
+        for inst in instruments.iter() {
+            let inst_id = inst.get_id();
+            let result = results.get(&inst_id).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) calculation result is not given for {}",
+                    file!(),
+                    line!(),
+                    inst_id,
+                )
+            })?;
+            let fx_rate = market_data.get_fx_rate(inst.get_currency(), self.reporting_currency)?;
+            let mut push = |risk_class,
+                            risk_measure,
+                            bucket: String,
+                            risk_factor: String,
+                            tenor: Option<&str>,
+                            value: Real| {
+                if value != 0.0 {
+                    sensitivities.push(SbmSensitivity {
+                        risk_class,
+                        risk_measure,
+                        bucket,
+                        risk_factor,
+                        tenor: tenor.map(|t| t.to_string()),
+                        instrument_id: Some(inst_id),
+                        sensitivity: value,
+                    });
+                }
+            };
+
+            // GIRR delta
+            if let Some(rho_structure) = result.get_rho_structure() {
+                for (curve_id, structure) in rho_structure.iter() {
+                    let bucket = match market_data.get_curve_data().get(curve_id) {
+                        Some(curve) => curve.currency,
+                        None => inst.get_currency(),
+                    };
+                    let vertices = allocate_to_vertices(&rho_times, structure, &GIRR_TENORS);
+                    for (label, value) in GIRR_TENOR_LABELS.iter().zip(vertices) {
+                        push(
+                            FrtbRiskClass::Girr,
+                            FrtbRiskMeasure::Delta,
+                            bucket.as_str().to_string(),
+                            curve_id.to_string(),
+                            Some(label),
+                            value / RHO_PNL_UNIT * fx_rate,
+                        );
+                    }
+                }
+            }
+
+            // CSR delta: cs01 is the value change for 1bp down of the spread
+            if let Some(cs01_structure) = result.get_cs01_structure() {
+                for (curve_id, structure) in cs01_structure.iter() {
+                    let bucket = self.get_credit_bucket(curve_id);
+                    let vertices = allocate_to_vertices(&cs01_times, structure, &CSR_TENORS);
+                    for (label, value) in CSR_TENOR_LABELS.iter().zip(vertices) {
+                        push(
+                            FrtbRiskClass::Csr,
+                            FrtbRiskMeasure::Delta,
+                            bucket.to_string(),
+                            curve_id.to_string(),
+                            Some(label),
+                            -value / RHO_PNL_UNIT * fx_rate,
+                        );
+                    }
+                }
+            }
+
+            // Equity delta and curvature
+            if let Some(delta) = result.get_delta() {
+                for (delta_id, value) in delta.iter() {
+                    let und_id = get_delta_underlying_id(inst.as_ref(), delta_id);
+                    push(
+                        FrtbRiskClass::Equity,
+                        FrtbRiskMeasure::Delta,
+                        self.get_equity_bucket(&und_id).to_string(),
+                        und_id.to_string(),
+                        None,
+                        value / DELTA_PNL_UNIT * fx_rate,
+                    );
+                }
+            }
+            if let Some(gamma) = result.get_gamma() {
+                for (gamma_id, value) in gamma.iter() {
+                    let und_id = get_delta_underlying_id(inst.as_ref(), gamma_id);
+                    let bucket = self.get_equity_bucket(&und_id);
+                    let risk_weight = EQUITY_SPOT_RISK_WEIGHTS[bucket - 1];
+                    // gamma is the second order P&L of a 1% move, 0.5 Γ (0.01 S)²
+                    let cvr = -value * (risk_weight / GAMMA_PNL_UNIT).powi(2) * fx_rate;
+                    for risk_measure in
+                        [FrtbRiskMeasure::CurvatureUp, FrtbRiskMeasure::CurvatureDown]
+                    {
+                        push(
+                            FrtbRiskClass::Equity,
+                            risk_measure,
+                            bucket.to_string(),
+                            und_id.to_string(),
+                            None,
+                            cvr,
+                        );
+                    }
+                }
+            }
+
+            // Equity vega
+            let vega_structure = result.get_vega_structure();
+            if let Some(vega) = result.get_vega() {
+                for (und_id, value) in vega.iter() {
+                    let volatility = market_data.get_volatility_level(und_id).ok_or_else(|| {
+                        anyhow!(
+                            "({}:{}) no volatility of {} for the vega of {}",
+                            file!(),
+                            line!(),
+                            und_id,
+                            inst_id,
+                        )
+                    })?;
+                    let vertices = match vega_structure.and_then(|v| v.get(und_id)) {
+                        Some(structure) => {
+                            allocate_to_vertices(&vega_times, structure, &VEGA_TENORS)
+                        }
+                        None => {
+                            let maturity = inst
+                                .get_maturity()
+                                .map(|dt| {
+                                    (*dt - evaluation_datetime).whole_seconds() as Real
+                                        / 86_400.0
+                                        / 365.0
+                                })
+                                .unwrap_or(0.0);
+                            allocate_to_vertices(&[maturity], &[*value], &VEGA_TENORS)
+                        }
+                    };
+                    for (label, value) in VEGA_TENOR_LABELS.iter().zip(vertices) {
+                        push(
+                            FrtbRiskClass::Equity,
+                            FrtbRiskMeasure::Vega,
+                            self.get_equity_bucket(und_id).to_string(),
+                            und_id.to_string(),
+                            Some(label),
+                            value / VEGA_PNL_UNIT * volatility * fx_rate,
+                        );
+                    }
+                }
+            }
+
+            // Fx delta
+            if let Some(fx_exposure) = result.get_fx_exposure() {
+                for (currency, amount) in fx_exposure.iter() {
+                    if *currency == self.reporting_currency {
+                        continue;
+                    }
+                    let rate = market_data.get_fx_rate(*currency, self.reporting_currency)?;
+                    push(
+                        FrtbRiskClass::Fx,
+                        FrtbRiskMeasure::Delta,
+                        currency.as_str().to_string(),
+                        currency.as_str().to_string(),
+                        None,
+                        amount * rate,
+                    );
+                }
+            }
+        }
+
+        Ok(SbmReport {
+            reporting_currency: self.reporting_currency,
+            sensitivities,
+        })
+    }
+}
+
+/// tenor in years with a month of 1/12 year and a day of 1/365 year
+pub(crate) fn get_tenor_years(tenors: &[Tenor]) -> Vec<Real> {
+    tenors
+        .iter()
+        .map(|t| t.years() as Real + t.months() as Real / 12.0 + t.days() as Real / 365.0)
+        .collect()
+}
+
+/// allocates values at the times to the vertices linearly, and to the end vertices out of the range
+fn allocate_to_vertices(times: &[Real], values: &[Real], vertices: &[Real]) -> Vec<Real> {
+    let mut res = vec![0.0; vertices.len()];
+    let last = vertices.len() - 1;
+    for (&t, &value) in times.iter().zip(values.iter()) {
+        if t <= vertices[0] {
+            res[0] += value;
+        } else if t >= vertices[last] {
+            res[last] += value;
+        } else {
+            let i = vertices.iter().position(|&v| v >= t).unwrap();
+            let weight = (vertices[i] - t) / (vertices[i] - vertices[i - 1]);
+            res[i - 1] += value * weight;
+            res[i] += value * (1.0 - weight);
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{value_data::ValueData, vector_data::VectorData};
+    use crate::instrument::Instrument;
+    use crate::instruments::stock::Stock;
+    use crate::{InstInfo, InstType, StockRankType};
+    use std::sync::Arc;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_sbm_sensitivity_mapper() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let curve_id = StaticId::from_str("KRWIRS", "DataProvider");
+        let credit_id = StaticId::from_str("KRW-AA", "DataProvider");
+        let config = CalculationConfiguration::default();
+
+        let mut instruments = vec![];
+        let mut results = FxHashMap::default();
+        for code in ["A", "B"] {
+            let inst_info = InstInfo {
+                id: StaticId::from_str(code, "KRX"),
+                currency: Currency::KRW,
+                inst_type: InstType::Stock,
+                unit_notional: 1.0,
+                name: code.to_string(),
+                ..Default::default()
+            };
+            let mut result = CalculationResult::new(inst_info.clone(), dt);
+            result.set_single_delta(und_id, 3.5);
+            result.set_single_gamma(und_id, 0.1);
+            result.set_single_vega(und_id, 0.5);
+            // 2.0 on 1M and 1.0 on 1Y6M
+            let mut rho_structure = vec![0.0; config.get_rho_structure_tenors().len()];
+            rho_structure[0] = 2.0;
+            rho_structure[6] = 1.0;
+            result.set_single_rho_structure(curve_id, rho_structure);
+            // 1.0 on 7Y
+            let mut cs01_structure = vec![0.0; config.get_cs01_structure_tenors().len()];
+            cs01_structure[6] = 1.0;
+            result.set_single_cs01_structure(credit_id, cs01_structure);
+            let mut fx_exposure = FxHashMap::default();
+            fx_exposure.insert(Currency::KRW, 5.0);
+            fx_exposure.insert(Currency::USD, 100.0);
+            result.set_fx_exposure(fx_exposure);
+            results.insert(inst_info.id, result);
+
+            instruments.push(Arc::new(Instrument::Stock(Stock {
+                underlying_ids: vec![inst_info.id],
+                inst_info,
+                rank_type: StockRankType::Common,
+            })));
+        }
+        let instruments = Instruments::new(instruments);
+
+        let mut curve_map = FxHashMap::default();
+        curve_map.insert(curve_id, VectorData::test_curve_data(0.03, Currency::KRW)?);
+        let mut vol_map = FxHashMap::default();
+        vol_map.insert(
+            und_id,
+            ValueData::new(0.2, Some(dt), Currency::KRW, "KOSPI2".to_string(), und_id)?,
+        );
+        let mut fx_map = FxHashMap::default();
+        let fx_id = StaticId::from_str("USDKRW", "DataProvider");
+        fx_map.insert(
+            crate::currency::FxCode::new(Currency::USD, Currency::KRW),
+            ValueData::new(1300.0, Some(dt), Currency::KRW, "USDKRW".to_string(), fx_id)?,
+        );
+        let market_data = MarketDataSet::new(dt).with_data(
+            fx_map,
+            FxHashMap::default(),
+            curve_map,
+            FxHashMap::default(),
+            vol_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+        );
+
+        let mapper = SbmSensitivityMapper::new(Currency::KRW).with_equity_bucket(und_id, 12)?;
+        assert!(mapper.clone().with_equity_bucket(und_id, 14).is_err());
+        assert!(mapper.clone().with_credit_bucket(credit_id, 0).is_err());
+        let report = mapper.generate(&config, &instruments, &results, &market_data)?;
+        let net = report.get_net_sensitivities();
+        let find = |class, measure, tenor: Option<&str>| -> Real {
+            net.iter()
+                .find(|s| {
+                    s.get_risk_class() == class
+                        && s.get_risk_measure() == measure
+                        && s.get_tenor().map(|t| t.as_str()) == tenor
+                })
+                .map(|s| s.get_sensitivity())
+                .unwrap_or(0.0)
+        };
+        let close = |x: Real, y: Real| (x - y).abs() < 1.0e-3 * y.abs().max(1.0);
+
+        // two instruments with the same greeks
+        assert!(close(
+            find(FrtbRiskClass::Girr, FrtbRiskMeasure::Delta, Some("3M")),
+            2.0 * 2.0e4
+        ));
+        assert!(close(
+            find(FrtbRiskClass::Girr, FrtbRiskMeasure::Delta, Some("1Y")),
+            2.0 * 0.5e4
+        ));
+        assert!(close(
+            find(FrtbRiskClass::Girr, FrtbRiskMeasure::Delta, Some("2Y")),
+            2.0 * 0.5e4
+        ));
+        assert!(close(
+            find(FrtbRiskClass::Csr, FrtbRiskMeasure::Delta, Some("5Y")),
+            -2.0 * 0.6e4
+        ));
+        assert!(close(
+            find(FrtbRiskClass::Csr, FrtbRiskMeasure::Delta, Some("10Y")),
+            -2.0 * 0.4e4
+        ));
+        assert!(close(
+            find(FrtbRiskClass::Equity, FrtbRiskMeasure::Delta, None),
+            2.0 * 350.0
+        ));
+        // 0.1 * (0.15 / 0.01)^2
+        assert!(close(
+            find(FrtbRiskClass::Equity, FrtbRiskMeasure::CurvatureUp, None),
+            -2.0 * 22.5
+        ));
+        assert!(close(
+            find(FrtbRiskClass::Equity, FrtbRiskMeasure::CurvatureDown, None),
+            -2.0 * 22.5
+        ));
+        // vega 0.5 / 0.01 * 0.2 on the shortest maturity without the maturity
+        assert!(close(
+            find(FrtbRiskClass::Equity, FrtbRiskMeasure::Vega, Some("6M")),
+            2.0 * 10.0
+        ));
+        assert!(close(
+            find(FrtbRiskClass::Fx, FrtbRiskMeasure::Delta, None),
+            2.0 * 130_000.0
+        ));
+
+        let girr = report.get_risk_class_sensitivities(FrtbRiskClass::Girr);
+        assert!(girr.iter().all(|s| s.get_bucket() == "KRW"));
+        assert_eq!(girr.len(), 2 * 3);
+        let equity = report.get_risk_class_sensitivities(FrtbRiskClass::Equity);
+        assert!(equity
+            .iter()
+            .all(|s| s.get_bucket() == "12" && s.get_instrument_id().is_some()));
+        let csr = report.get_risk_class_sensitivities(FrtbRiskClass::Csr);
+        assert!(csr.iter().all(|s| s.get_bucket() == "16"));
+        Ok(())
+    }
+}
@@ -9,6 +9,7 @@ use crate::instruments::{
     bond::Bond,
     bond_futures::BondFutures,
     cash::Cash,
+    cashflow::Cashflow,
     futures::Futures,
     fx_futures::FxFutures,
     ktbf::KTBF,
@@ -21,15 +22,19 @@ use crate::parameters::{
     past_price::DailyClosePrice, rate_index::RateIndex, zero_curve::ZeroCurve,
 };
 use crate::pricing_engines::match_parameter::MatchParameter;
-use crate::time::{conventions::PaymentFrequency, jointcalendar::JointCalendar};
+use crate::time::{
+    conventions::{DayCountConvention, PaymentFrequency},
+    jointcalendar::JointCalendar,
+    settlement::SettlementConvention,
+};
 //
 use static_id::static_id::StaticId;
 use anyhow::{anyhow, Context, Result};
 use enum_dispatch::enum_dispatch;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
-    cell::RefCell,
     ops::Index,
-    rc::Rc,
+    sync::{Arc, RwLock},
 };
 use rustc_hash::{
     FxHashMap,
@@ -63,6 +68,7 @@ pub trait InstrumentTrait {
     fn get_all_fxcodes_for_pricing(&self) -> Vec<FxCode> { vec![] }
 
     fn get_underlying_ids_requiring_volatility(&self) -> Vec<StaticId> { vec![] }
+
     /// only for bonds, so None must be allowed
     fn get_credit_rating(&self) -> Result<CreditRating> {
         let err = || anyhow!(
@@ -117,19 +123,31 @@ pub trait InstrumentTrait {
     fn get_cashflows(
         &self,
         _pricing_date: &OffsetDateTime,
-        _forward_curve: Option<Rc<RefCell<ZeroCurve>>>,
-        _past_data: Option<Rc<DailyClosePrice>>,
+        _forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        _past_data: Option<Arc<DailyClosePrice>>,
     ) -> Result<FxHashMap<OffsetDateTime, Real>> {
         Err(anyhow!(
             "not supported instrument type on get_coupon_cashflow"
         ))
     }
 
+    /// typed cashflows paid on and after the pricing date on a unit notional, sorted by the payment date
+    fn get_cashflow_details(
+        &self,
+        _pricing_date: &OffsetDateTime,
+        _forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        _past_data: Option<Arc<DailyClosePrice>>,
+    ) -> Result<Vec<Cashflow>> {
+        Err(anyhow!(
+            "not supported instrument type on get_cashflow_details"
+        ))
+    }
+
     fn get_floating_cashflows(
         &self,
         _pricing_date: &OffsetDateTime,
-        _forward_curve: Option<Rc<RefCell<ZeroCurve>>>,
-        _past_data: Option<Rc<DailyClosePrice>>,
+        _forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        _past_data: Option<Arc<DailyClosePrice>>,
     ) -> Result<FxHashMap<OffsetDateTime, Real>> {
         Err(anyhow!(
             "not supported instrument type on get_floating_cashflows"
@@ -149,6 +167,28 @@ pub trait InstrumentTrait {
         Err(anyhow!("not supported instrument type on get_pricing_date"))
     }
 
+    fn get_daycounter(&self) -> Result<DayCountConvention> {
+        Err(anyhow!("not supported instrument type on get_daycounter"))
+    }
+
+    /// (call date, call price on a unit notional) of the issuer's call options
+    fn get_call_schedule(&self) -> Result<&Vec<(OffsetDateTime, Real)>> {
+        Err(anyhow!("not supported instrument type on get_call_schedule"))
+    }
+
+    /// coupon accrued from the start of the coupon period containing the date, on a unit notional.
+    /// It is negative in the ex-coupon period where the coupon is not paid to the buyer
+    fn get_accrued_interest(
+        &self,
+        _date: &OffsetDateTime,
+        _forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        _past_data: Option<Arc<DailyClosePrice>>,
+    ) -> Result<Real> {
+        Err(anyhow!(
+            "not supported instrument type on get_accrued_interest"
+        ))
+    }
+
     fn is_coupon_strip(&self) -> Result<bool> {
         Err(anyhow!("not supported instrument type on is_coupon_strip"))
     }
@@ -177,6 +217,16 @@ pub trait InstrumentTrait {
         Err(anyhow!("not supported instrument type on get_schedule"))
     }
 
+    /// true if a schedule of the instrument was generated before the latest holiday updates (time::holiday_updates)
+    fn has_outdated_schedules(&self) -> bool {
+        false
+    }
+
+    /// regenerate the outdated schedules with the current holidays
+    fn refresh_schedules(&mut self) -> Result<()> {
+        Ok(())
+    }
+
     fn get_fixed_leg_currency(&self) -> Result<Currency> {
         Err(anyhow!(
             "not supported instrument type on get_fixed_leg_currency"
@@ -213,6 +263,13 @@ pub trait InstrumentTrait {
         Err(anyhow!("not supported instrument type on get_fx_code"))
     }
 
+    /// spot convention of the fx rate used in pricing, only for FxFutures
+    fn get_fx_spot_convention(&self) -> Result<Option<&SettlementConvention>> {
+        Err(anyhow!(
+            "not supported instrument type on get_fx_spot_convention"
+        ))
+    }
+
     fn get_floating_to_fixed_fxcode(&self) -> Result<Option<FxCode>> {
         Err(anyhow!(
             "get_floating_to_fixed_fx allowed only for PlainSwap"
@@ -227,7 +284,7 @@ pub trait InstrumentTrait {
 }
 
 #[enum_dispatch(InstrumentTrait)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Instrument {
     Futures(Futures),
     Bond(Bond),
@@ -248,7 +305,21 @@ pub enum Instrument {
 /// GROUP3: Vec<&'static str> = vec!["StructuredProduct"];
 #[derive(Clone, Debug, Default)]
 pub struct Instruments {
-    instruments: Vec<Rc<Instrument>>,
+    instruments: Vec<Arc<Instrument>>,
+}
+
+// serialized as the sequence of the instruments
+impl Serialize for Instruments {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.instruments.iter().map(|inst| inst.as_ref()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Instruments {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Instruments, D::Error> {
+        let instruments = Vec::<Instrument>::deserialize(deserializer)?;
+        Ok(Instruments::new(instruments.into_iter().map(Arc::new).collect()))
+    }
 }
 
 impl Index<usize> for Instruments {
@@ -260,11 +331,11 @@ impl Index<usize> for Instruments {
 }
 
 impl Instruments {
-    pub fn iter(&self) -> std::slice::Iter<'_, Rc<Instrument>> {
+    pub fn iter(&self) -> std::slice::Iter<'_, Arc<Instrument>> {
         self.instruments.iter()
     }
 
-    pub fn new(instruments: Vec<Rc<Instrument>>) -> Instruments {
+    pub fn new(instruments: Vec<Arc<Instrument>>) -> Instruments {
         Instruments { instruments }
     }
 
@@ -276,8 +347,8 @@ impl Instruments {
         self.instruments.is_empty()
     }
 
-    pub fn get_instruments_clone(&self) -> Vec<Rc<Instrument>> {
-        let mut res = Vec::<Rc<Instrument>>::new();
+    pub fn get_instruments_clone(&self) -> Vec<Arc<Instrument>> {
+        let mut res = Vec::<Arc<Instrument>>::new();
         for instrument in self.instruments.iter() {
             res.push(instrument.clone());
         }
@@ -379,9 +450,9 @@ impl Instruments {
         &self,
         und_id: StaticId,
         exclude_type: Option<Vec<&str>>,
-    ) -> Vec<Rc<Instrument>> {
+    ) -> Vec<Arc<Instrument>> {
         let exclude_type = exclude_type.unwrap_or_default();
-        let mut res = Vec::<Rc<Instrument>>::new();
+        let mut res = Vec::<Arc<Instrument>>::new();
         for instrument in self.instruments.iter() {
             let ids = instrument.get_underlying_ids();
             let type_name = instrument.get_type_name();
@@ -392,8 +463,8 @@ impl Instruments {
         res
     }
 
-    pub fn instruments_with_currency(&self, currency: Currency) -> Vec<Rc<Instrument>> {
-        let mut res = Vec::<Rc<Instrument>>::new();
+    pub fn instruments_with_currency(&self, currency: Currency) -> Vec<Arc<Instrument>> {
+        let mut res = Vec::<Arc<Instrument>>::new();
         for instrument in self.instruments.iter() {
             if instrument.get_currency() == currency {
                 res.push(instrument.clone());
@@ -402,8 +473,8 @@ impl Instruments {
         res
     }
 
-    pub fn instruments_with_types(&self, type_names: Vec<&str>) -> Vec<Rc<Instrument>> {
-        let mut res = Vec::<Rc<Instrument>>::new();
+    pub fn instruments_with_types(&self, type_names: Vec<&str>) -> Vec<Arc<Instrument>> {
+        let mut res = Vec::<Arc<Instrument>>::new();
         for instrument in self.instruments.iter() {
             let type_name = instrument.get_type_name();
             if type_names.contains(&type_name) {
@@ -413,13 +484,24 @@ impl Instruments {
         res
     }
 
+    /// instruments whose pricing depends on the fx rate, i.e., fx_code is in get_all_fxcodes_for_pricing
+    pub fn instruments_using_fx(&self, fx_code: &FxCode) -> Vec<Arc<Instrument>> {
+        let mut res = Vec::<Arc<Instrument>>::new();
+        for instrument in self.instruments.iter() {
+            if instrument.get_all_fxcodes_for_pricing().contains(fx_code) {
+                res.push(instrument.clone());
+            }
+        }
+        res
+    }
+
     pub fn instruments_using_curve(
         &self,
         curve_id: StaticId,
         match_parameter: &MatchParameter,
         exclude_type: Option<Vec<&str>>,
-    ) -> Result<Vec<Rc<Instrument>>> {
-        let mut res = Vec::<Rc<Instrument>>::new();
+    ) -> Result<Vec<Arc<Instrument>>> {
+        let mut res = Vec::<Arc<Instrument>>::new();
         let exclude_type = exclude_type.unwrap_or_default();
         // 1) discount curve
         // 2) collateral curves
@@ -429,8 +511,11 @@ impl Instruments {
             if exclude_type.contains(&instrument.get_type_name()) {
                 continue;
             }
-            // 1)
-            if match_parameter.get_discount_curve_id(instrument)? == curve_id {
+            // 1) (including the spread curves layered on curve_id)
+            if match_parameter.is_curve_or_spread_over(
+                &match_parameter.get_discount_curve_id(instrument)?,
+                &curve_id,
+            ) {
                 res.push(instrument.clone());
             }
             // 2)
@@ -441,7 +526,10 @@ impl Instruments {
                 res.push(instrument.clone());
             }
             // 3) forward curve
-            if match_parameter.get_rate_index_curve_id(instrument)? == curve_id {
+            if match_parameter.is_curve_or_spread_over(
+                &match_parameter.get_rate_index_curve_id(instrument)?,
+                &curve_id,
+            ) {
                 res.push(instrument.clone());
             }
             // 4) crs curve
@@ -452,6 +540,20 @@ impl Instruments {
             if match_parameter.get_floating_crs_curve_id(instrument)? == curve_id {
                 res.push(instrument.clone());
             }
+            // 6) counterparty funding spread curve
+            if match_parameter
+                .get_funding_spread_curve_ids(instrument)?
+                .contains(&curve_id)
+            {
+                res.push(instrument.clone());
+            }
+            // 7) repo curves of the underlying bonds
+            if match_parameter
+                .get_repo_curve_ids(instrument)
+                .contains(&curve_id)
+            {
+                res.push(instrument.clone());
+            }
         }
         Ok(res)
     }
@@ -493,21 +595,42 @@ impl Instruments {
                     res.push(*id);
                 }
             }
+            let funding_spread_curve_ids = match_parameter.get_funding_spread_curve_ids(instrument)?;
+            for id in funding_spread_curve_ids.iter() {
+                if !res.contains(id) {
+                    res.push(*id);
+                }
+            }
+            let repo_curve_ids = match_parameter.get_repo_curve_ids(instrument);
+            for id in repo_curve_ids.iter() {
+                if !res.contains(id) {
+                    res.push(*id);
+                }
+            }
+        }
+        // base curves of spread curves
+        let spread_curve_ids = res.clone();
+        for id in spread_curve_ids.iter() {
+            if let Some(base_id) = match_parameter.get_base_curve_id(id) {
+                if !res.contains(&base_id) {
+                    res.push(base_id);
+                }
+            }
         }
         Ok(res)
     }
 
     pub fn instruments_with_maturity_upto(
         &self,
-        instruments: Option<&Vec<Rc<Instrument>>>,
+        instruments: Option<&Vec<Arc<Instrument>>>,
         maturity: &OffsetDateTime,
         exlucde_type: Option<Vec<&str>>,
-    ) -> Vec<Rc<Instrument>> {
+    ) -> Vec<Arc<Instrument>> {
         let exlucde_type = exlucde_type.unwrap_or_default();
 
         match instruments {
             Some(instruments) => {
-                let mut res = Vec::<Rc<Instrument>>::new();
+                let mut res = Vec::<Arc<Instrument>>::new();
                 for instrument in instruments.iter() {
                     if exlucde_type.contains(&instrument.get_type_name()) {
                         continue;
@@ -521,7 +644,7 @@ impl Instruments {
                 res
             }
             None => {
-                let mut res = Vec::<Rc<Instrument>>::new();
+                let mut res = Vec::<Arc<Instrument>>::new();
                 for instrument in self.instruments.iter() {
                     if exlucde_type.contains(&instrument.get_type_name()) {
                         continue;
@@ -539,15 +662,15 @@ impl Instruments {
 
     pub fn instruments_with_maturity_over(
         &self,
-        instruments: Option<&Vec<Rc<Instrument>>>,
+        instruments: Option<&Vec<Arc<Instrument>>>,
         maturity: &OffsetDateTime,
         exclude_type: Option<Vec<&str>>,
-    ) -> Vec<Rc<Instrument>> {
+    ) -> Vec<Arc<Instrument>> {
         let exclude_type = exclude_type.unwrap_or_default();
 
         match instruments {
             Some(instruments) => {
-                let mut res = Vec::<Rc<Instrument>>::new();
+                let mut res = Vec::<Arc<Instrument>>::new();
                 for instrument in instruments.iter() {
                     if exclude_type.contains(&instrument.get_type_name()) {
                         continue;
@@ -566,7 +689,7 @@ impl Instruments {
                 res
             }
             None => {
-                let mut res = Vec::<Rc<Instrument>>::new();
+                let mut res = Vec::<Arc<Instrument>>::new();
                 for instrument in self.instruments.iter() {
                     if exclude_type.contains(&instrument.get_type_name()) {
                         continue;
@@ -593,7 +716,7 @@ impl Instruments {
     /// Therefore, if there is no maturity, it is considered as the longest maturity
     pub fn get_shortest_maturity(
         &self,
-        instruments: Option<&Vec<Rc<Instrument>>>,
+        instruments: Option<&Vec<Arc<Instrument>>>,
     ) -> Option<OffsetDateTime> {
         match instruments {
             Some(instruments) => {
@@ -635,7 +758,7 @@ impl Instruments {
     /// Therefore, if there is no maturity, it is considered as the longest maturity
     pub fn get_longest_maturity(
         &self,
-        instruments: Option<&Vec<Rc<Instrument>>>,
+        instruments: Option<&Vec<Arc<Instrument>>>,
     ) -> Option<OffsetDateTime> {
         match instruments {
             Some(instruments) => {
@@ -685,7 +808,7 @@ impl Instruments {
 
     pub fn get_all_inst_id(
         &self,
-        instruments: Option<&Vec<Rc<Instrument>>>,
+        instruments: Option<&Vec<Arc<Instrument>>>,
     ) -> Vec<StaticId> {
         match instruments {
             Some(instruments) => {
@@ -707,7 +830,7 @@ impl Instruments {
 
     pub fn get_all_unerlying_ids_requiring_volatility(
         &self,
-        instruments: Option<&Vec<Rc<Instrument>>>,
+        instruments: Option<&Vec<Arc<Instrument>>>,
     ) -> Vec<StaticId> {
         match instruments {
             Some(instruments) => {
@@ -831,7 +954,7 @@ mod tests {
             inst_type: InstType::PlainSwap,
             currency: Currency::KRW,
             unit_notional: 10_000_000_000.0,
-            issue_date: Some(issue_date.clone()),
+            issue_date: Some(issue_date),
             maturity: Some(maturity_date),
             accounting_level: AccountingLevel::L2,
         };
@@ -845,7 +968,7 @@ mod tests {
             None,
             None,
             //
-            issue_date.clone(),
+            issue_date,
             //
             Some(0.02),
             Some(rate_index.clone()),
@@ -867,9 +990,9 @@ mod tests {
 
         // make Instrument using fut1, fut2, irs
         let instruments = Instruments::new(vec![
-            Rc::new(Instrument::Futures(fut1.clone())),
-            Rc::new(Instrument::Futures(fut2.clone())),
-            Rc::new(Instrument::PlainSwap(irs.clone())),
+            Arc::new(Instrument::Futures(fut1.clone())),
+            Arc::new(Instrument::Futures(fut2.clone())),
+            Arc::new(Instrument::PlainSwap(irs.clone())),
         ]);
 
         // make MatchParameter
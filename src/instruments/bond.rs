@@ -1,13 +1,17 @@
 use crate::definitions::Real;
 use crate::enums::{CreditRating, IssuerType, RankType};
 use crate::instrument::InstrumentTrait;
-use crate::instruments::schedule::{build_schedule, Schedule};
+use crate::instruments::cashflow::{sum_by_payment_date, Cashflow, CashflowLeg, CashflowType};
+use crate::instruments::schedule::{
+    build_schedule_with_options, BaseSchedule, Schedule, ScheduleOptions, StubType,
+};
 use crate::parameters::zero_curve::ZeroCurve;
 use crate::parameters::{past_price::DailyClosePrice, rate_index::RateIndex};
 use crate::time::{
     calendar_trait::CalendarTrait,
     conventions::{BusinessDayConvention, DayCountConvention, PaymentFrequency},
     jointcalendar::JointCalendar,
+    settlement::SettlementConvention,
 };
 use crate::InstInfo;
 use crate::Tenor;
@@ -15,11 +19,9 @@ use static_id::static_id::StaticId;
 //
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
-use std::{
-    cell::RefCell, 
-    rc::Rc
-};
+use std::sync::{Arc, RwLock};
 use rustc_hash::FxHashMap;
+use crate::utils::platform::now_utc;
 use time::OffsetDateTime;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
@@ -45,8 +47,11 @@ pub struct Bond {
     pub floating_compound_tenor: Option<Tenor>,
     pub fixed_coupon_rate: Option<Real>,
     //
+    #[serde(with = "crate::time::datetime_serde::datetime")]
     pub effective_date: OffsetDateTime,
+    #[serde(default, with = "crate::time::datetime_serde::option_datetime")]
     pub pricing_date: Option<OffsetDateTime>,
+    #[serde(with = "crate::time::datetime_serde::datetime")]
     pub settlement_date: OffsetDateTime,
     //
     pub calendar: JointCalendar,
@@ -56,6 +61,15 @@ pub struct Bond {
     pub payment_frequency: PaymentFrequency,
     pub payment_gap_days: i64,
     pub fixing_gap_days: i64,
+    /// (call date, call price on a unit notional) of the issuer's call options
+    #[serde(default)]
+    pub call_schedule: Vec<(OffsetDateTime, Real)>,
+    /// business days before the payment date from which the coupon is paid to the holder on the record date
+    #[serde(default)]
+    pub ex_coupon_days: i64,
+    /// the end of month rule and the stub of the schedule (see with_schedule_options)
+    #[serde(default)]
+    pub schedule_options: ScheduleOptions,
 }
 
 impl Default for Bond {
@@ -77,9 +91,9 @@ impl Default for Bond {
             floating_compound_tenor: None,
             fixed_coupon_rate: None,
             //
-            effective_date: OffsetDateTime::now_utc(),
+            effective_date: now_utc(),
             pricing_date: None,
-            settlement_date: OffsetDateTime::now_utc(),
+            settlement_date: now_utc(),
             //
             calendar: JointCalendar::default(),
             //
@@ -88,12 +102,15 @@ impl Default for Bond {
             payment_frequency: PaymentFrequency::SemiAnnually,
             payment_gap_days: 0,
             fixing_gap_days: 0,
+            call_schedule: vec![],
+            ex_coupon_days: 0,
+            schedule_options: ScheduleOptions::default(),
         }
     }
 }
 
 impl Bond {
-    //#[allow(clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         inst_info: InstInfo,
         bond_info: BondInfo,
@@ -201,10 +218,13 @@ impl Bond {
             payment_frequency,
             payment_gap_days,
             fixing_gap_days,
+            call_schedule: vec![],
+            ex_coupon_days: 0,
+            schedule_options: ScheduleOptions::default(),
         })
     }
 
-    //#[allow(clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments)]
     pub fn new_from_conventions(
         inst_info: InstInfo,
         bond_info: BondInfo,
@@ -249,7 +269,7 @@ impl Bond {
         };
 
         let maturity = inst_info.get_maturity().unwrap();
-        let schedule = build_schedule(
+        let schedule = build_schedule_with_options(
             forward_generation,
             &effective_date,
             maturity,
@@ -258,6 +278,7 @@ impl Bond {
             &payment_frequency,
             fixing_gap_days,
             payment_gap_days,
+            &ScheduleOptions::default(),
         )
         .with_context(|| {
             anyhow!(
@@ -295,6 +316,9 @@ impl Bond {
             payment_frequency,
             fixing_gap_days,
             payment_gap_days,
+            call_schedule: vec![],
+            ex_coupon_days: 0,
+            schedule_options: ScheduleOptions::default(),
         })
     }
 
@@ -305,6 +329,195 @@ impl Bond {
     pub fn set_inst_info(&mut self, inst_info: InstInfo) {
         self.inst_info = inst_info;
     }
+
+    /// issuer's call options, (call date, call price on a unit notional), which are sorted by the call date.
+    /// The call price is paid in place of the cashflows after the call date,
+    /// while the coupon on the call date is paid anyway.
+    pub fn with_call_schedule(mut self, mut call_schedule: Vec<(OffsetDateTime, Real)>) -> Result<Bond> {
+        call_schedule.sort_by_key(|(date, _)| *date);
+        if let Some((date, _)) = call_schedule.last() {
+            let maturity = self.inst_info.get_maturity().ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) maturity is not given for the callable bond {}",
+                    file!(),
+                    line!(),
+                    self.inst_info.id,
+                )
+            })?;
+            if date > maturity {
+                return Err(anyhow!(
+                    "({}:{}) call date {} of {} is after the maturity {}",
+                    file!(),
+                    line!(),
+                    date,
+                    self.inst_info.id,
+                    maturity,
+                ));
+            }
+        }
+        self.call_schedule = call_schedule;
+        Ok(self)
+    }
+
+    /// Rebuilds the schedule from the conventions with the rules of the options,
+    /// e.g., the end of month rule for a bond issued on the last business day of a month.
+    pub fn with_schedule_options(
+        mut self,
+        forward_generation: bool,
+        schedule_options: ScheduleOptions,
+    ) -> Result<Bond> {
+        let maturity = *self.inst_info.get_maturity().ok_or_else(|| {
+            anyhow!(
+                "({}:{}) maturity is not given for {}",
+                file!(),
+                line!(),
+                self.inst_info.id,
+            )
+        })?;
+        self.schedule = build_schedule_with_options(
+            forward_generation,
+            &self.effective_date,
+            &maturity,
+            &self.calendar,
+            &self.busi_convention,
+            &self.payment_frequency,
+            self.fixing_gap_days,
+            self.payment_gap_days,
+            &schedule_options,
+        )
+        .with_context(|| {
+            anyhow!(
+                "({}:{}) Failed to build schedule of {}",
+                file!(),
+                line!(),
+                self.inst_info.id
+            )
+        })?;
+        self.schedule_options = schedule_options;
+        Ok(self)
+    }
+
+    /// Rebuilds the schedule with the stub, which decides the direction of the generation.
+    /// The floating rate of the stub is interpolated by RateIndex::get_stub_rate if the rate index has stub tenors.
+    pub fn with_stub(self, stub: StubType) -> Result<Bond> {
+        let schedule_options = self.schedule_options.with_stub(stub);
+        self.with_schedule_options(true, schedule_options)
+    }
+
+    pub fn get_schedule_options(&self) -> &ScheduleOptions {
+        &self.schedule_options
+    }
+
+    /// ex-coupon period of ex_coupon_days business days before the payment date.
+    /// The buyer settling in the period does not receive the coupon, so the accrued interest is negative.
+    pub fn with_ex_coupon_days(mut self, ex_coupon_days: i64) -> Result<Bond> {
+        if ex_coupon_days < 0 {
+            return Err(anyhow!(
+                "({}:{}) ex_coupon_days ({}) of {} must be non-negative",
+                file!(),
+                line!(),
+                ex_coupon_days,
+                self.inst_info.id,
+            ));
+        }
+        self.ex_coupon_days = ex_coupon_days;
+        Ok(self)
+    }
+
+    /// the first date of the ex-coupon period of the base schedule, which is the payment date without the period
+    pub fn get_ex_coupon_date(&self, base_schedule: &BaseSchedule) -> OffsetDateTime {
+        self.calendar
+            .add_business_days(base_schedule.get_payment_date(), -self.ex_coupon_days)
+    }
+
+    /// true if the coupon of the base schedule is not paid to the holder settling on the date
+    pub fn is_ex_coupon(&self, base_schedule: &BaseSchedule, date: &OffsetDateTime) -> bool {
+        self.ex_coupon_days > 0
+            && self.get_ex_coupon_date(base_schedule).date() <= date.date()
+            && date.date() < base_schedule.get_payment_date().date()
+    }
+
+    /// amount paid on the settlement date for a unit of the bond traded at the clean price (on a unit notional),
+    /// i.e., (clean price + accrued interest) * unit notional
+    pub fn get_settlement_amount(
+        &self,
+        clean_price: Real,
+        settlement_date: &OffsetDateTime,
+        forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        past_data: Option<Arc<DailyClosePrice>>,
+    ) -> Result<Real> {
+        let accrued_interest =
+            self.get_accrued_interest(settlement_date, forward_curve, past_data)?;
+        Ok((clean_price + accrued_interest) * self.inst_info.get_unit_notional())
+    }
+
+    /// get_settlement_amount of a trade settled following the convention, e.g., T+1 for KTB in KRX
+    pub fn get_settlement_amount_for_trade(
+        &self,
+        clean_price: Real,
+        trade_date: &OffsetDateTime,
+        settlement_convention: &SettlementConvention,
+        forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        past_data: Option<Arc<DailyClosePrice>>,
+    ) -> Result<Real> {
+        let settlement_date = settlement_convention.get_settlement_date(trade_date);
+        self.get_settlement_amount(clean_price, &settlement_date, forward_curve, past_data)
+    }
+
+    /// coupon amount of the base schedule on a unit notional.
+    /// The given amount is used if exists, otherwise the amount is calculated from the rate index or the fixed coupon rate.
+    fn get_coupon_amount(
+        &self,
+        base_schedule: &BaseSchedule,
+        pricing_date: &OffsetDateTime,
+        forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        past_data: Option<Arc<DailyClosePrice>>,
+    ) -> Result<Real> {
+        if let Some(amount) = base_schedule.get_amount() {
+            return Ok(amount);
+        }
+        match self.rate_index.as_ref() {
+            Some(rate_index) => {
+                // the case of frn
+                let forward_curve = forward_curve.ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) forward curve is not given for the floating coupon of {}",
+                        file!(),
+                        line!(),
+                        self.inst_info.id,
+                    )
+                })?;
+                rate_index.get_coupon_amount(
+                    base_schedule,
+                    self.floating_coupon_spread,
+                    forward_curve,
+                    past_data.unwrap_or(Arc::new(DailyClosePrice::default())),
+                    pricing_date,
+                    self.floating_compound_tenor.as_ref(),
+                    &self.calendar,
+                    &self.daycounter,
+                    self.fixing_gap_days,
+                )
+            }
+            None => {
+                // the case of fixed rate bond
+                let frac = self.calendar.year_fraction(
+                    base_schedule.get_calc_start_date(),
+                    base_schedule.get_calc_end_date(),
+                    &self.daycounter,
+                )?;
+                let rate = self.fixed_coupon_rate.ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) fixed coupon rate is not given for {}",
+                        file!(),
+                        line!(),
+                        self.inst_info.id,
+                    )
+                })?;
+                Ok(frac * rate)
+            }
+        }
+    }
 }
 
 impl InstrumentTrait for Bond {
@@ -320,6 +533,17 @@ impl InstrumentTrait for Bond {
         Ok(&self.schedule)
     }
 
+    fn has_outdated_schedules(&self) -> bool {
+        self.schedule.is_outdated()
+    }
+
+    fn refresh_schedules(&mut self) -> Result<()> {
+        if self.schedule.is_outdated() {
+            self.schedule = self.schedule.regenerate()?;
+        }
+        Ok(())
+    }
+
     fn get_calendar(&self) -> Result<&JointCalendar> {
         Ok(&self.calendar)
     }
@@ -328,6 +552,65 @@ impl InstrumentTrait for Bond {
         Ok(self.payment_frequency)
     }
 
+    fn get_daycounter(&self) -> Result<DayCountConvention> {
+        Ok(self.daycounter)
+    }
+
+    fn get_call_schedule(&self) -> Result<&Vec<(OffsetDateTime, Real)>> {
+        Ok(&self.call_schedule)
+    }
+
+    /// coupon accrued in the period (calc_start_date, calc_end_date) containing the date.
+    /// Fixed coupons accrue on the daycounter from calc_start_date, so that odd first or last periods are
+    /// accrued on their own length, and the other coupons accrue pro rata on the year fraction.
+    /// In the ex-coupon period, the accrued interest is negative by the coupon remaining to calc_end_date.
+    /// Zero on the coupon dates and for coupon strips
+    fn get_accrued_interest(
+        &self,
+        date: &OffsetDateTime,
+        forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        past_data: Option<Arc<DailyClosePrice>>,
+    ) -> Result<Real> {
+        if self.is_coupon_strip {
+            return Ok(0.0);
+        }
+        let base_schedule = match self.schedule.iter().find(|base_schedule| {
+            base_schedule.get_calc_start_date().date() < date.date()
+                && date.date() < base_schedule.get_calc_end_date().date()
+        }) {
+            Some(base_schedule) => base_schedule,
+            None => return Ok(0.0),
+        };
+        let start_date = base_schedule.get_calc_start_date();
+        let accrued_fraction = self.calendar.year_fraction(start_date, date, &self.daycounter)?;
+        let accrued = match (
+            base_schedule.get_amount(),
+            self.rate_index.as_ref(),
+            self.fixed_coupon_rate,
+        ) {
+            (None, None, Some(rate)) => rate * accrued_fraction,
+            _ => {
+                let amount = self.get_coupon_amount(
+                    base_schedule,
+                    date,
+                    forward_curve.clone(),
+                    past_data.clone(),
+                )?;
+                let period = self.calendar.year_fraction(
+                    start_date,
+                    base_schedule.get_calc_end_date(),
+                    &self.daycounter,
+                )?;
+                amount * accrued_fraction / period
+            }
+        };
+        if self.is_ex_coupon(base_schedule, date) {
+            let amount = self.get_coupon_amount(base_schedule, date, forward_curve, past_data)?;
+            return Ok(accrued - amount);
+        }
+        Ok(accrued)
+    }
+
     fn get_type_name(&self) -> &'static str {
         "Bond"
     }
@@ -355,64 +638,65 @@ impl InstrumentTrait for Bond {
     fn get_cashflows(
         &self,
         pricing_date: &OffsetDateTime,
-        forward_curve: Option<Rc<RefCell<ZeroCurve>>>,
-        past_data: Option<Rc<DailyClosePrice>>,
+        forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        past_data: Option<Arc<DailyClosePrice>>,
     ) -> Result<FxHashMap<OffsetDateTime, Real>> {
-        let mut res = FxHashMap::default();
+        let cashflows = self.get_cashflow_details(pricing_date, forward_curve, past_data)?;
+        Ok(sum_by_payment_date(&cashflows))
+    }
+
+    fn get_cashflow_details(
+        &self,
+        pricing_date: &OffsetDateTime,
+        forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        past_data: Option<Arc<DailyClosePrice>>,
+    ) -> Result<Vec<Cashflow>> {
+        let currency = self.inst_info.currency;
+        let cashflow_type = match self.rate_index {
+            Some(_) => CashflowType::Floating,
+            None => CashflowType::Fixed,
+        };
+        let mut res = Vec::new();
         for base_schedule in self.schedule.iter() {
             let payment_date = base_schedule.get_payment_date();
-            if payment_date.date() < pricing_date.date() {
+            // the coupon in the ex-coupon period is paid to the holder on the record date
+            if payment_date.date() < pricing_date.date()
+                || self.is_ex_coupon(base_schedule, pricing_date)
+            {
                 continue;
             }
-
-            let given_amount = base_schedule.get_amount();
-            match given_amount {
-                Some(amount) => {
-                    res.entry(*payment_date)
-                        .and_modify(|e| *e += amount)
-                        .or_insert(amount);
-                    //res.insert(payment_date.clone(), amount);
+            let amount = self.get_coupon_amount(
+                base_schedule,
+                pricing_date,
+                forward_curve.clone(),
+                past_data.clone(),
+            )?;
+            let start_date = base_schedule.get_calc_start_date();
+            let end_date = base_schedule.get_calc_end_date();
+            let rate = match (base_schedule.get_amount(), self.fixed_coupon_rate) {
+                (None, Some(rate)) if self.rate_index.is_none() => rate,
+                _ => {
+                    let frac =
+                        self.calendar
+                            .year_fraction(start_date, end_date, &self.daycounter)?;
+                    if frac > 0.0 {
+                        amount / frac
+                    } else {
+                        0.0
+                    }
                 }
-                None => {
-                    match self.rate_index.as_ref() {
-                        Some(rate_index) => {
-                            // begin of the case of frn
-                            let amount = rate_index.get_coupon_amount(
-                                base_schedule,
-                                self.floating_coupon_spread,
-                                forward_curve.clone().unwrap(),
-                                past_data
-                                    .clone()
-                                    .unwrap_or(Rc::new(DailyClosePrice::default())),
-                                pricing_date,
-                                self.floating_compound_tenor.as_ref(),
-                                &self.calendar,
-                                &self.daycounter,
-                                self.fixing_gap_days,
-                            )?;
-                            res.entry(*payment_date)
-                                .and_modify(|e| *e += amount)
-                                .or_insert(amount);
-                            //*res.entry(payment_date.clone()).or_insert(amount) += amount;
-                            //res.insert(payment_date.clone(), amount);
-                        } // end of the case of frn
-                        //
-                        None => {
-                            // begin of the case of fixed rate bond
-                            let frac = self.calendar.year_fraction(
-                                base_schedule.get_calc_start_date(),
-                                base_schedule.get_calc_end_date(),
-                                &self.daycounter,
-                            )?;
-                            let rate = self.fixed_coupon_rate.unwrap();
-                            let amount = frac * rate;
-                            res.entry(*payment_date)
-                                .and_modify(|e| *e += amount)
-                                .or_insert(amount);
-                        } // end of the case of fixed rate bond
-                    } // end of branch of bond type
-                } // where the given amount is None
-            } // end of branch of optional given amount
+            };
+            res.push(
+                Cashflow::new(
+                    *payment_date,
+                    amount,
+                    currency,
+                    CashflowLeg::Single,
+                    cashflow_type,
+                )
+                .with_accrual_period(*start_date, *end_date)
+                .with_rate(rate),
+            );
         }
 
         let maturity = self.inst_info.get_maturity().ok_or_else(|| {
@@ -425,12 +709,183 @@ impl InstrumentTrait for Bond {
             )
         })?;
 
-        if !self.is_coupon_strip()? && maturity.date() >= pricing_date.date() {
-            res.entry(*maturity)
-                .and_modify(|e| *e += 1.0)
-                .or_insert(1.0);
+        if !self.is_coupon_strip && maturity.date() >= pricing_date.date() {
+            res.push(Cashflow::new(
+                *maturity,
+                1.0,
+                currency,
+                CashflowLeg::Single,
+                CashflowType::Redemption,
+            ));
         }
-
+        res.sort_by_key(|cashflow| *cashflow.get_payment_date());
         Ok(res)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{
+        calendar::Calendar,
+        calendars::custom::CustomCalendar,
+        calendars::southkorea::{SouthKorea, SouthKoreaType},
+        holiday_updates::{add_calendar_holiday, clear_holiday_updates},
+    };
+    use crate::time::settlement::SettlementMarket;
+    use crate::{Currency, InstType};
+    //
+    use anyhow::Result;
+    use time::macros::datetime;
+
+    fn make_bond(maturity: OffsetDateTime) -> Result<Bond> {
+        let calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Settlement,
+        ))])?;
+        make_bond_on(maturity, calendar, BusinessDayConvention::Unadjusted)
+    }
+
+    fn make_bond_on(
+        maturity: OffsetDateTime,
+        calendar: JointCalendar,
+        busi_convention: BusinessDayConvention,
+    ) -> Result<Bond> {
+        let issue_date = datetime!(2020-01-15 16:30:00 +09:00);
+        let inst_info = InstInfo::new(
+            StaticId::from_str("KR1234567890", "KRX"),
+            "KRW Fixed Coupon Bond".to_string(),
+            InstType::Bond,
+            Currency::KRW,
+            10_000.0,
+            Some(issue_date),
+            Some(maturity),
+            crate::AccountingLevel::L1,
+        );
+        let bond_info = BondInfo {
+            issuer_type: IssuerType::Government,
+            credit_rating: CreditRating::None,
+            issuer_id: StaticId::from_str("Korea Gov", "KRX"),
+            rank: RankType::Undefined,
+        };
+        Bond::new_from_conventions(
+            inst_info,
+            bond_info,
+            false,
+            None,
+            None,
+            None,
+            Some(0.04),
+            None,
+            None,
+            None,
+            calendar,
+            true,
+            DayCountConvention::StreetConvention,
+            busi_convention,
+            PaymentFrequency::SemiAnnually,
+            0,
+            0,
+        )
+    }
+
+    #[test]
+    fn test_accrued_interest() -> Result<()> {
+        let bond = make_bond(datetime!(2022-01-15 16:30:00 +09:00))?.with_ex_coupon_days(5)?;
+
+        // regular period
+        let date = datetime!(2020-03-15 16:30:00 +09:00);
+        let accrued = bond.get_accrued_interest(&date, None, None)?;
+        assert!((accrued - 0.04 * 60.0 / 360.0).abs() < 1.0e-6, "accrued: {}", accrued);
+        let settlement_amount = bond.get_settlement_amount(0.99, &date, None, None)?;
+        assert!((settlement_amount - (0.99 + accrued) * 10_000.0).abs() < 1.0e-2);
+        // traded on Friday 2020-03-13 and settled on Monday 03-16 (T+1)
+        let ktb_settlement = SettlementConvention::new_from_market(
+            SettlementMarket::KrxKtb,
+            bond.calendar.clone(),
+        );
+        let settlement_amount = bond.get_settlement_amount_for_trade(
+            0.99,
+            &datetime!(2020-03-13 10:00:00 +09:00),
+            &ktb_settlement,
+            None,
+            None,
+        )?;
+        let accrued = 0.04 * 61.0 / 360.0;
+        assert!((settlement_amount - (0.99 + accrued) * 10_000.0).abs() < 1.0e-2);
+
+        // ex-coupon period from 2020-07-08, five business days before the payment on 2020-07-15
+        let date = datetime!(2020-07-10 16:30:00 +09:00);
+        let accrued = bond.get_accrued_interest(&date, None, None)?;
+        assert!((accrued + 0.04 * 5.0 / 360.0).abs() < 1.0e-6, "accrued: {}", accrued);
+        let cashflows = bond.get_cashflows(&date, None, None)?;
+        assert!(!cashflows.contains_key(&datetime!(2020-07-15 16:30:00 +09:00)));
+        assert!(cashflows.contains_key(&datetime!(2021-01-15 16:30:00 +09:00)));
+
+        let date = datetime!(2020-07-07 16:30:00 +09:00);
+        assert!(bond.get_accrued_interest(&date, None, None)? > 0.0);
+        let cashflows = bond.get_cashflows(&date, None, None)?;
+        assert!(cashflows.contains_key(&datetime!(2020-07-15 16:30:00 +09:00)));
+
+        // odd last coupon from 2022-01-15 to 2022-03-01
+        let bond = make_bond(datetime!(2022-03-01 16:30:00 +09:00))?;
+        let last_period = bond.get_schedule()?.iter().last().unwrap();
+        assert_eq!(
+            last_period.get_calc_start_date().date(),
+            datetime!(2022-01-15 16:30:00 +09:00).date()
+        );
+        let date = datetime!(2022-02-15 16:30:00 +09:00);
+        let accrued = bond.get_accrued_interest(&date, None, None)?;
+        assert!((accrued - 0.04 * 30.0 / 360.0).abs() < 1.0e-6, "accrued: {}", accrued);
+        let cashflows = bond.get_cashflows(&date, None, None)?;
+        let last_coupon = cashflows[&datetime!(2022-03-01 16:30:00 +09:00)] - 1.0;
+        assert!((last_coupon - 0.04 * 46.0 / 360.0).abs() < 1.0e-6, "coupon: {}", last_coupon);
+
+        // the odd coupon moves to the front with a short front stub
+        let bond = bond.with_stub(StubType::ShortFront)?;
+        let schedule = bond.get_schedule()?;
+        let first_period = schedule.iter().next().unwrap();
+        assert!(first_period.is_stub());
+        assert_eq!(
+            first_period.get_calc_end_date().date(),
+            datetime!(2020-03-01 16:30:00 +09:00).date()
+        );
+        assert!(schedule.iter().skip(1).all(|s| !s.is_stub()));
+        assert_eq!(
+            schedule.iter().last().unwrap().get_calc_start_date().date(),
+            datetime!(2021-09-01 16:30:00 +09:00).date()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_refresh_schedules_on_holiday_updates() -> Result<()> {
+        let name = "Bond Holiday Updates Test";
+        let custom = CustomCalendar::new(name.to_string(), vec![]);
+        let calendar = JointCalendar::new(vec![Calendar::Custom(custom.clone())])?;
+        let mut bond = make_bond_on(
+            datetime!(2022-01-15 16:30:00 +09:00),
+            calendar,
+            BusinessDayConvention::Following,
+        )?;
+        let pricing_date = datetime!(2021-03-02 16:30:00 +09:00);
+        let cashflows = bond.get_cashflows(&pricing_date, None, None)?;
+        assert!(cashflows.contains_key(&datetime!(2021-07-15 16:30:00 +09:00)));
+        assert!(!bond.has_outdated_schedules());
+
+        // the coupon on Thursday 2021-07-15 is paid on Friday 07-16 after the closure
+        add_calendar_holiday(&custom, &datetime!(2021-07-15 16:30:00 +09:00).date());
+        assert!(bond.has_outdated_schedules());
+        bond.refresh_schedules()?;
+        assert!(!bond.has_outdated_schedules());
+        let cashflows = bond.get_cashflows(&pricing_date, None, None)?;
+        assert!(!cashflows.contains_key(&datetime!(2021-07-15 16:30:00 +09:00)));
+        assert!(cashflows.contains_key(&datetime!(2021-07-16 16:30:00 +09:00)));
+        assert_eq!(
+            bond.get_schedule()?[2].get_calc_end_date().date(),
+            datetime!(2021-07-16 16:30:00 +09:00).date()
+        );
+
+        clear_holiday_updates(Some(name));
+        Ok(())
+    }
+}
@@ -0,0 +1,279 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::time::holiday_updates::get_holiday_updates_version;
+//
+use anyhow::Result;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use std::sync::{Arc, RwLock};
+use time::OffsetDateTime;
+
+/// amounts on the payment dates
+pub type CashflowMap = FxHashMap<OffsetDateTime, Real>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CashflowType {
+    /// coupon on a fixed rate
+    Fixed,
+    /// coupon on a rate index, which is projected on the forward curve if not fixed yet
+    Floating,
+    /// principal paid at the maturity, or exchanged at the start and the end of swaps
+    Redemption,
+    /// probability weighted amount of the pricer, e.g., coupons of structured products
+    Expected,
+}
+
+impl CashflowType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CashflowType::Fixed => "Fixed",
+            CashflowType::Floating => "Floating",
+            CashflowType::Redemption => "Redemption",
+            CashflowType::Expected => "Expected",
+        }
+    }
+}
+
+/// The leg of an instrument paying the cashflow.
+/// Single is for instruments without legs, e.g., bonds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CashflowLeg {
+    Single,
+    Fixed,
+    Floating,
+}
+
+impl CashflowLeg {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CashflowLeg::Single => "Single",
+            CashflowLeg::Fixed => "Fixed",
+            CashflowLeg::Floating => "Floating",
+        }
+    }
+}
+
+/// A cashflow of an instrument on a unit notional.
+/// The amount is signed, i.e., positive for receiving and negative for paying,
+/// and it is in the currency of the cashflow which may differ from the instrument currency, e.g., CRS.
+/// The accrual period and the rate are None for redemptions and expected amounts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cashflow {
+    #[serde(with = "crate::time::datetime_serde::datetime")]
+    payment_date: OffsetDateTime,
+    #[serde(default, with = "crate::time::datetime_serde::option_datetime")]
+    accrual_start_date: Option<OffsetDateTime>,
+    #[serde(default, with = "crate::time::datetime_serde::option_datetime")]
+    accrual_end_date: Option<OffsetDateTime>,
+    notional: Real,
+    rate: Option<Real>,
+    amount: Real,
+    currency: Currency,
+    leg: CashflowLeg,
+    cashflow_type: CashflowType,
+}
+
+impl Cashflow {
+    pub fn new(
+        payment_date: OffsetDateTime,
+        amount: Real,
+        currency: Currency,
+        leg: CashflowLeg,
+        cashflow_type: CashflowType,
+    ) -> Cashflow {
+        Cashflow {
+            payment_date,
+            accrual_start_date: None,
+            accrual_end_date: None,
+            notional: 1.0,
+            rate: None,
+            amount,
+            currency,
+            leg,
+            cashflow_type,
+        }
+    }
+
+    pub fn with_accrual_period(
+        mut self,
+        accrual_start_date: OffsetDateTime,
+        accrual_end_date: OffsetDateTime,
+    ) -> Cashflow {
+        self.accrual_start_date = Some(accrual_start_date);
+        self.accrual_end_date = Some(accrual_end_date);
+        self
+    }
+
+    /// notional accruing the coupon, e.g., the initial exchange amount of CRS
+    pub fn with_notional(mut self, notional: Real) -> Cashflow {
+        self.notional = notional;
+        self
+    }
+
+    /// annual rate of the coupon including the spread
+    pub fn with_rate(mut self, rate: Real) -> Cashflow {
+        self.rate = Some(rate);
+        self
+    }
+
+    /// amount and notional multiplied by the ratio, e.g., the quantity of a position
+    pub fn scaled(&self, ratio: Real) -> Cashflow {
+        Cashflow {
+            notional: self.notional * ratio,
+            amount: self.amount * ratio,
+            ..self.clone()
+        }
+    }
+
+    pub fn get_payment_date(&self) -> &OffsetDateTime {
+        &self.payment_date
+    }
+
+    pub fn get_accrual_start_date(&self) -> Option<&OffsetDateTime> {
+        self.accrual_start_date.as_ref()
+    }
+
+    pub fn get_accrual_end_date(&self) -> Option<&OffsetDateTime> {
+        self.accrual_end_date.as_ref()
+    }
+
+    pub fn get_notional(&self) -> Real {
+        self.notional
+    }
+
+    pub fn get_rate(&self) -> Option<Real> {
+        self.rate
+    }
+
+    pub fn get_amount(&self) -> Real {
+        self.amount
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn get_leg(&self) -> CashflowLeg {
+        self.leg
+    }
+
+    pub fn get_cashflow_type(&self) -> CashflowType {
+        self.cashflow_type
+    }
+}
+
+/// amounts summed on the payment dates regardless of the currencies
+pub fn sum_by_payment_date(cashflows: &[Cashflow]) -> FxHashMap<OffsetDateTime, Real> {
+    let mut res = FxHashMap::default();
+    for cashflow in cashflows.iter() {
+        res.entry(cashflow.payment_date)
+            .and_modify(|e| *e += cashflow.amount)
+            .or_insert(cashflow.amount);
+    }
+    res
+}
+
+/// Cashflows from the schedules of the instruments whose amounts do not depend on the market data,
+/// e.g., fixed rate bonds and the fixed legs of swaps, keyed by (instrument id, pricing date).
+/// The bump loops of the engine revalue an instrument many times on the same pricing date,
+/// so the schedule is turned into the cashflows once per date instead of once per revaluation.
+/// The cache is cleared when the holidays are updated (time::holiday_updates).
+#[derive(Debug, Default)]
+pub struct CashflowCache {
+    cashflows: RwLock<CashflowCacheData>,
+}
+
+#[derive(Debug, Default)]
+struct CashflowCacheData {
+    /// holiday_updates::get_holiday_updates_version of the cached cashflows
+    holiday_updates_version: u64,
+    cashflows: FxHashMap<(StaticId, OffsetDateTime), Arc<CashflowMap>>,
+}
+
+impl CashflowCache {
+    pub fn new() -> CashflowCache {
+        CashflowCache::default()
+    }
+
+    /// the cached cashflows, or the ones from generate which are cached for the next call
+    pub fn get_or_insert_with<F>(
+        &self,
+        inst_id: StaticId,
+        pricing_date: OffsetDateTime,
+        generate: F,
+    ) -> Result<Arc<CashflowMap>>
+    where
+        F: FnOnce() -> Result<CashflowMap>,
+    {
+        let version = get_holiday_updates_version();
+        {
+            let data = self.cashflows.read().unwrap();
+            if data.holiday_updates_version == version {
+                if let Some(cashflows) = data.cashflows.get(&(inst_id, pricing_date)) {
+                    return Ok(cashflows.clone());
+                }
+            }
+        }
+        let cashflows = Arc::new(generate()?);
+        let mut data = self.cashflows.write().unwrap();
+        if data.holiday_updates_version < version {
+            data.cashflows.clear();
+            data.holiday_updates_version = version;
+        }
+        // not cached if the holidays were updated again in the meantime
+        if data.holiday_updates_version == version {
+            data.cashflows.insert((inst_id, pricing_date), cashflows.clone());
+        }
+        Ok(cashflows)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cashflows.read().unwrap().cashflows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cashflows.read().unwrap().cashflows.is_empty()
+    }
+
+    /// needed if the instrument is modified under the same id
+    pub fn clear(&self) {
+        self.cashflows.write().unwrap().cashflows.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::holiday_updates::{add_holiday, clear_holiday_updates};
+    use std::cell::Cell;
+    use time::macros::{date, datetime};
+
+    #[test]
+    fn test_cashflow_cache_on_holiday_updates() -> Result<()> {
+        let cache = CashflowCache::new();
+        let inst_id = StaticId::from_str("Cashflow Cache Test", "KRX");
+        let pricing_date = datetime!(2024-01-02 16:30:00 +09:00);
+        let generated = Cell::new(0);
+        let generate = || {
+            generated.set(generated.get() + 1);
+            let mut cashflows = CashflowMap::default();
+            cashflows.insert(datetime!(2024-07-02 16:30:00 +09:00), 0.02);
+            Ok(cashflows)
+        };
+
+        cache.get_or_insert_with(inst_id, pricing_date, generate)?;
+        assert_eq!(generated.get(), 1);
+        assert_eq!(cache.len(), 1);
+
+        // the cashflows cached before the updates are generated again
+        let name = "Cashflow Cache Test";
+        add_holiday(name, &date!(2024 - 07 - 02));
+        clear_holiday_updates(Some(name));
+        let cashflows = cache.get_or_insert_with(inst_id, pricing_date, generate)?;
+        assert_eq!(generated.get(), 2);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cashflows[&datetime!(2024-07-02 16:30:00 +09:00)], 0.02);
+        Ok(())
+    }
+}
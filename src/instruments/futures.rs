@@ -1,17 +1,21 @@
 use crate::currency::Currency;
 use crate::definitions::Real;
 use crate::instrument::InstrumentTrait;
+use crate::time::jointcalendar::JointCalendar;
+use crate::time::krx_expiry::KrxExpiryRule;
 use crate::InstInfo;
 use static_id::static_id::StaticId;
 //
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use time::OffsetDateTime;
+use crate::utils::platform::now_utc;
+use time::{Month, OffsetDateTime};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Futures {
     pub inst_info: InstInfo,
     pub average_trade_price: Real,
+    #[serde(with = "crate::time::datetime_serde::datetime")]
     pub settlement_date: OffsetDateTime,
     pub underlying_currency: Currency,
     pub underlying_ids: Vec<StaticId>,
@@ -22,7 +26,7 @@ impl Default for Futures {
         Futures {
             inst_info: InstInfo::default(),
             average_trade_price: 0.0,
-            settlement_date: OffsetDateTime::now_utc(),
+            settlement_date: now_utc(),
             underlying_currency: Currency::KRW,
             underlying_ids: vec![StaticId::default()],
         }
@@ -51,6 +55,27 @@ impl Futures {
             underlying_ids: vec![underlying_id],
         }
     }
+
+    /// fill the maturity and the settlement date following the KRX rule of the contract month
+    pub fn with_krx_expiry(
+        mut self,
+        rule: KrxExpiryRule,
+        year: i32,
+        month: Month,
+        calendar: &JointCalendar,
+    ) -> Result<Futures> {
+        self.inst_info = self.inst_info.with_krx_maturity(rule, year, month, calendar)?;
+        self.settlement_date = rule.get_settlement_date(year, month, calendar)?;
+        Ok(self)
+    }
+
+    pub fn validate_krx_maturity(
+        &self,
+        rule: KrxExpiryRule,
+        calendar: &JointCalendar,
+    ) -> Result<()> {
+        self.inst_info.validate_krx_maturity(rule, calendar)
+    }
 }
 
 impl InstrumentTrait for Futures {
@@ -84,6 +109,8 @@ mod tests {
         InstType,
         AccountingLevel,
     };
+    use crate::time::calendar::Calendar;
+    use crate::time::calendars::southkorea::{SouthKorea, SouthKoreaType};
     use static_id::static_id::StaticId;
     #[test]
     fn test_stock_futures_serialization() {
@@ -103,7 +130,7 @@ mod tests {
         let und_id = StaticId::from_str("KOSPI2", "KRX");
         
         let stock_futures = Futures {
-            inst_info: inst_info,
+            inst_info,
             average_trade_price: 100.0,
             settlement_date: datetime!(2021-01-01 09:00:00 +09:00),
             underlying_currency: Currency::KRW,
@@ -114,4 +141,23 @@ mod tests {
         let deserialized: Futures = serde_json::from_str(&serialized).unwrap();
         assert_eq!(stock_futures, deserialized);
     }
+
+    #[test]
+    fn test_krx_expiry() -> Result<()> {
+        let calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Krx,
+        ))])?;
+        let rule = KrxExpiryRule::EquityDerivative;
+        let futures = Futures::default().with_krx_expiry(rule, 2024, Month::June, &calendar)?;
+        assert_eq!(
+            futures.inst_info.maturity,
+            Some(datetime!(2024-06-13 15:20:00 +09:00))
+        );
+        assert_eq!(futures.settlement_date, datetime!(2024-06-14 15:20:00 +09:00));
+        futures.validate_krx_maturity(rule, &calendar)?;
+        assert!(futures
+            .validate_krx_maturity(KrxExpiryRule::Ktbf, &calendar)
+            .is_err());
+        Ok(())
+    }
 }
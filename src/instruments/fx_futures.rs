@@ -1,20 +1,26 @@
 use crate::currency::{Currency, FxCode};
 use crate::definitions::Real;
 use crate::instrument::InstrumentTrait;
+use crate::time::settlement::SettlementConvention;
 use crate::InstInfo;
 
 //
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use crate::utils::platform::now_utc;
 use time::OffsetDateTime;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct FxFutures {
     pub inst_info: InstInfo,
     pub average_trade_price: Real,
+    #[serde(with = "crate::time::datetime_serde::datetime")]
     pub settlement_date: OffsetDateTime,
     pub underlying_currency: Currency,
     pub fx_code: FxCode,
+    /// spot convention of the fx rate, e.g., T+2 for USDKRW. None if the fx rate is for the evaluation date
+    #[serde(default)]
+    pub spot_convention: Option<SettlementConvention>,
 }
 
 impl Default for FxFutures {
@@ -22,9 +28,10 @@ impl Default for FxFutures {
         FxFutures {
             inst_info: InstInfo::default(),
             average_trade_price: 0.0,
-            settlement_date: OffsetDateTime::now_utc(),
+            settlement_date: now_utc(),
             underlying_currency: Currency::KRW,
             fx_code: FxCode::default(),
+            spot_convention: None,
         }
     }
 }
@@ -49,8 +56,14 @@ impl FxFutures {
             settlement_date,
             underlying_currency,
             fx_code,
+            spot_convention: None,
         }
     }
+
+    pub fn with_spot_convention(mut self, spot_convention: SettlementConvention) -> FxFutures {
+        self.spot_convention = Some(spot_convention);
+        self
+    }
 }
 
 impl InstrumentTrait for FxFutures {
@@ -77,6 +90,10 @@ impl InstrumentTrait for FxFutures {
     fn get_all_fxcodes_for_pricing(&self) -> Vec<FxCode> {
         vec![self.fx_code]
     }
+
+    fn get_fx_spot_convention(&self) -> Result<Option<&SettlementConvention>> {
+        Ok(self.spot_convention.as_ref())
+    }
 }
 
 #[cfg(test)]
@@ -1,8 +1,11 @@
 use crate::currency::Currency;
 use crate::definitions::Real;
+use crate::time::jointcalendar::JointCalendar;
+use crate::time::krx_expiry::KrxExpiryRule;
 use crate::utils::number_format::write_number_with_commas;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use time::OffsetDateTime;
+use time::{Month, OffsetDateTime};
 use crate::{
     InstType,
     AccountingLevel,
@@ -58,7 +61,9 @@ pub struct InstInfo {
     pub inst_type: InstType,
     pub currency: Currency,
     pub unit_notional: Real,
+    #[serde(default, with = "crate::time::datetime_serde::option_datetime")]
     pub issue_date: Option<OffsetDateTime>,
+    #[serde(default, with = "crate::time::datetime_serde::option_datetime")]
     pub maturity: Option<OffsetDateTime>,
     pub accounting_level: AccountingLevel,
 }
@@ -105,6 +110,7 @@ impl std::fmt::Debug for InstInfo {
 }
 
 impl InstInfo {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: StaticId,
         name: String,
@@ -167,6 +173,34 @@ impl InstInfo {
         self.accounting_level
     }
 
+    /// fill the maturity with the last trading day of the KRX contract month
+    pub fn with_krx_maturity(
+        mut self,
+        rule: KrxExpiryRule,
+        year: i32,
+        month: Month,
+        calendar: &JointCalendar,
+    ) -> Result<InstInfo> {
+        self.maturity = Some(rule.get_maturity(year, month, calendar)?);
+        Ok(self)
+    }
+
+    pub fn validate_krx_maturity(
+        &self,
+        rule: KrxExpiryRule,
+        calendar: &JointCalendar,
+    ) -> Result<()> {
+        match self.maturity.as_ref() {
+            Some(maturity) => rule.validate_maturity(maturity, calendar),
+            None => Err(anyhow!(
+                "({}:{}) the maturity of {} ({:?}) is not set",
+                file!(),
+                line!(),
+                self.name,
+                self.id
+            )),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -2,6 +2,8 @@ use crate::definitions::{Integer, Real};
 use crate::instrument::InstrumentTrait;
 use crate::instruments::bond::Bond;
 use crate::time::conventions::PaymentFrequency;
+use crate::time::jointcalendar::JointCalendar;
+use crate::time::krx_expiry::KrxExpiryRule;
 use static_id::static_id::StaticId;
 //
 use anyhow::{anyhow, Result};
@@ -52,6 +54,7 @@ impl KtbfVirtualBond {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct KTBF {
     pub isnt_info: InstInfo,
+    #[serde(default, with = "crate::time::datetime_serde::option_datetime")]
     pub settlement_date: Option<OffsetDateTime>,
     pub virtual_bond: KtbfVirtualBond,
     pub underlying_bonds: Vec<Bond>,
@@ -107,6 +110,14 @@ impl KTBF {
     pub fn get_underlying_bonds(&self) -> &Vec<Bond> {
         &self.underlying_bonds
     }
+
+    /// Err if the maturity is not the third Tuesday of the contract month (preceding if a KRX holiday).
+    /// To fill the maturity, use InstInfo::with_krx_maturity before KTBF::new
+    /// since the underlying bonds are priced at the maturity
+    pub fn validate_krx_maturity(&self, calendar: &JointCalendar) -> Result<()> {
+        self.isnt_info
+            .validate_krx_maturity(KrxExpiryRule::Ktbf, calendar)
+    }
 }
 
 impl InstrumentTrait for KTBF {
@@ -118,6 +129,17 @@ impl InstrumentTrait for KTBF {
         "KTBF"
     }
 
+    fn has_outdated_schedules(&self) -> bool {
+        self.underlying_bonds.iter().any(|bond| bond.has_outdated_schedules())
+    }
+
+    fn refresh_schedules(&mut self) -> Result<()> {
+        for bond in self.underlying_bonds.iter_mut() {
+            bond.refresh_schedules()?;
+        }
+        Ok(())
+    }
+
     fn get_virtual_bond_npv(&self, bond_yield: Real) -> Result<Real> {
         Ok(self.virtual_bond.npv(bond_yield))
     }
@@ -140,8 +162,11 @@ mod tests {
     };
     
     
+    use crate::time::calendar::Calendar;
+    use crate::time::calendars::southkorea::{SouthKorea, SouthKoreaType};
     use anyhow::Result;
     use time::macros::datetime;
+    use time::Month;
 
     #[test]
     fn test_serde() -> Result<()> {
@@ -190,4 +215,31 @@ mod tests {
         assert_eq!(ktbf, deserialized);
         Ok(())
     }
+
+    #[test]
+    fn test_krx_maturity() -> Result<()> {
+        let calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Krx,
+        ))])?;
+        let inst_info = InstInfo {
+            inst_type: InstType::KTBF,
+            currency: Currency::KRW,
+            ..InstInfo::default()
+        }
+        .with_krx_maturity(KrxExpiryRule::Ktbf, 2024, Month::March, &calendar)?;
+        let maturity = *inst_info.get_maturity().unwrap();
+        assert_eq!(maturity, datetime!(2024-03-19 11:30:00 +09:00));
+
+        let mut bond = Bond::default();
+        bond.set_pricing_date(maturity);
+        let ktbf = KTBF::new(
+            inst_info,
+            None,
+            KtbfVirtualBond::new(3, 0.05, PaymentFrequency::SemiAnnually, 100.0),
+            vec![bond],
+            StaticId::default(),
+        )?;
+        ktbf.validate_krx_maturity(&calendar)?;
+        Ok(())
+    }
 }
@@ -1,6 +1,7 @@
 pub mod bond;
 pub mod bond_futures;
 pub mod cash;
+pub mod cashflow;
 pub mod futures;
 pub mod fx_futures;
 pub mod inst_info;
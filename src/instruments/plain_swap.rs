@@ -1,7 +1,8 @@
 use crate::currency::{Currency, FxCode};
 use crate::definitions::Real;
 use crate::instrument::InstrumentTrait;
-use crate::instruments::schedule::{self, Schedule};
+use crate::instruments::cashflow::{sum_by_payment_date, Cashflow, CashflowLeg, CashflowType};
+use crate::instruments::schedule::{self, Schedule, ScheduleOptions, StubType};
 use crate::parameters::past_price::DailyClosePrice;
 use crate::parameters::rate_index::RateIndex;
 use crate::parameters::zero_curve::ZeroCurve;
@@ -11,7 +12,7 @@ use crate::InstInfo;
 use crate::Tenor;
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
-use std::{cell::RefCell, rc::Rc};
+use std::sync::{Arc, RwLock};
 use rustc_hash::FxHashMap;
 use time::{Duration, OffsetDateTime};
 
@@ -41,6 +42,7 @@ impl PlainSwapType {
 /// 3) FxSwap (schedule are empty)
 /// 4) FxForward (schedule are empty and initial swap is None but last swap is Some(Real))
 /// 5) FxSpot (same as FxForward but effective_date <= issue_date + 2 days)
+///
 /// Roughly in Fx or CRS case, fixed side is mostly KRW and Floating side is mostly USD
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PlainSwap {
@@ -52,9 +54,15 @@ pub struct PlainSwap {
     pub rate_index: Option<RateIndex>,
     pub floating_compound_tenor: Option<Tenor>,
     pub calendar: JointCalendar,
+    /// the calendars of the legs if they differ from calendar (see with_leg_calendars)
+    #[serde(default)]
+    pub fixed_leg_calendar: Option<JointCalendar>,
+    #[serde(default)]
+    pub floating_leg_calendar: Option<JointCalendar>,
     //unit_notional: Real,
     //
     //issue_date: OffsetDateTime,
+    #[serde(with = "crate::time::datetime_serde::datetime")]
     pub effective_date: OffsetDateTime,
     //maturity: OffsetDateTime,
     //
@@ -78,6 +86,9 @@ pub struct PlainSwap {
     //
     pub fixing_gap_days: i64,
     pub payment_gap_days: i64,
+    /// the end of month rule and the stub of the legs (see with_schedule_options)
+    #[serde(default)]
+    pub schedule_options: ScheduleOptions,
     //
     pub specific_type: PlainSwapType,
     //name: String,
@@ -91,6 +102,7 @@ impl PlainSwap {
     /// 3) FxSwap (schedule are empty)
     /// 4) FxForward (schedule are empty and initial swap is None but last swap is Some(Real))
     /// 5) FxSpot (same as FxForward but effective_date <= issue_date + 2 days)
+    ///
     /// Roughly in Fx or CRS case, fixed side is mostly KRW and Floating side is mostly USD
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -225,6 +237,8 @@ impl PlainSwap {
             rate_index,
             floating_compound_tenor,
             calendar,
+            fixed_leg_calendar: None,
+            floating_leg_calendar: None,
             //
             effective_date,
             //
@@ -248,6 +262,7 @@ impl PlainSwap {
             //
             fixing_gap_days,
             payment_gap_days,
+            schedule_options: ScheduleOptions::default(),
             //
             specific_type,
         })
@@ -258,10 +273,11 @@ impl PlainSwap {
     /// 3) FxSwap (schedule are empty)
     /// 4) FxForward (schedule are empty and initial swap is None but last swap is Some(Real))
     /// 5) FxSpot (same as FxForward but effective_date <= issue_date + 2 days)
+    ///
     /// Roughly in Fx or CRS case, fixed side is mostly KRW and Floating side is mostly USD
     /// construct IRS using PaymentFrequency, BusinessDayConvention, DayCountConvention
     /// without schedule given directly
-    //#[allow(clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments)]
     pub fn new_from_conventions(
         inst_info: InstInfo,    
         //
@@ -295,7 +311,7 @@ impl PlainSwap {
         let maturity = inst_info.get_maturity().unwrap();
         let issue_date = inst_info.get_issue_date().unwrap();
         let fixed_leg_currency = inst_info.currency;
-        let fixed_legs = schedule::build_schedule(
+        let fixed_legs = schedule::build_schedule_with_options(
             forward_generation,
             &effective_date,
             maturity,
@@ -304,6 +320,7 @@ impl PlainSwap {
             &fixed_frequency,
             fixing_gap_days,
             payment_gap_days,
+            &ScheduleOptions::default(),
         )
         .with_context(|| {
             anyhow!(
@@ -314,7 +331,7 @@ impl PlainSwap {
             )
         })?;
 
-        let floating_legs = schedule::build_schedule(
+        let floating_legs = schedule::build_schedule_with_options(
             forward_generation,
             &effective_date,
             maturity,
@@ -323,6 +340,7 @@ impl PlainSwap {
             &floating_frequency,
             fixing_gap_days,
             payment_gap_days,
+            &ScheduleOptions::default(),
         )
         .with_context(|| {
             anyhow!(
@@ -420,6 +438,8 @@ impl PlainSwap {
             rate_index,
             floating_compound_tenor,
             calendar,
+            fixed_leg_calendar: None,
+            floating_leg_calendar: None,
             //
             effective_date,
             //
@@ -443,11 +463,110 @@ impl PlainSwap {
             //
             fixing_gap_days,
             payment_gap_days,
+            schedule_options: ScheduleOptions::default(),
             //
             specific_type,
         })
     }
 
+    /// Rebuilds the legs from the conventions with a calendar for each leg,
+    /// e.g., a CRS whose KRW leg follows Seoul and USD leg follows New York and Seoul.
+    /// The year fractions and the fixings of a leg also follow its calendar.
+    pub fn with_leg_calendars(
+        mut self,
+        forward_generation: bool,
+        fixed_leg_calendar: JointCalendar,
+        floating_leg_calendar: JointCalendar,
+    ) -> Result<PlainSwap> {
+        self.fixed_leg_calendar = Some(fixed_leg_calendar);
+        self.floating_leg_calendar = Some(floating_leg_calendar);
+        self.rebuild_legs(forward_generation)?;
+        Ok(self)
+    }
+
+    /// Rebuilds the legs from the conventions with the rules of the options,
+    /// e.g., the end of month rule for a swap effective on the last business day of a month.
+    pub fn with_schedule_options(
+        mut self,
+        forward_generation: bool,
+        schedule_options: ScheduleOptions,
+    ) -> Result<PlainSwap> {
+        self.schedule_options = schedule_options;
+        self.rebuild_legs(forward_generation)?;
+        Ok(self)
+    }
+
+    /// Rebuilds the legs with the stub, which decides the direction of the generation.
+    /// The floating rate of the stub is interpolated by RateIndex::get_stub_rate if the rate index has stub tenors.
+    pub fn with_stub(self, stub: StubType) -> Result<PlainSwap> {
+        let schedule_options = self.schedule_options.with_stub(stub);
+        self.with_schedule_options(true, schedule_options)
+    }
+
+    /// builds the non-empty legs with the leg calendars and the schedule options
+    fn rebuild_legs(&mut self, forward_generation: bool) -> Result<()> {
+        let maturity = *self.get_maturity().unwrap();
+        if !self.fixed_legs.is_empty() {
+            let fixed_leg_calendar = self.get_fixed_leg_calendar();
+            self.fixed_legs = schedule::build_schedule_with_options(
+                forward_generation,
+                &self.effective_date,
+                &maturity,
+                fixed_leg_calendar,
+                &self.fixed_busi_convention,
+                &self.fixed_frequency,
+                self.fixing_gap_days,
+                self.payment_gap_days,
+                &self.schedule_options,
+            )
+            .with_context(|| {
+                anyhow!(
+                    "({}:{}) Failed to build fixed legs with {}: ({:?})",
+                    file!(),
+                    line!(),
+                    fixed_leg_calendar.calendar_name(),
+                    self.inst_info.id
+                )
+            })?;
+        }
+        if !self.floating_legs.is_empty() {
+            let floating_leg_calendar = self.get_floating_leg_calendar();
+            self.floating_legs = schedule::build_schedule_with_options(
+                forward_generation,
+                &self.effective_date,
+                &maturity,
+                floating_leg_calendar,
+                &self.floating_busi_convention,
+                &self.floating_frequency,
+                self.fixing_gap_days,
+                self.payment_gap_days,
+                &self.schedule_options,
+            )
+            .with_context(|| {
+                anyhow!(
+                    "({}:{}) Failed to build floating legs with {}: ({:?})",
+                    file!(),
+                    line!(),
+                    floating_leg_calendar.calendar_name(),
+                    self.inst_info.id
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn get_schedule_options(&self) -> &ScheduleOptions {
+        &self.schedule_options
+    }
+
+    pub fn get_fixed_leg_calendar(&self) -> &JointCalendar {
+        self.fixed_leg_calendar.as_ref().unwrap_or(&self.calendar)
+    }
+
+    pub fn get_floating_leg_calendar(&self) -> &JointCalendar {
+        self.floating_leg_calendar.as_ref().unwrap_or(&self.calendar)
+    }
+
     #[inline]
     #[must_use]
     pub fn get_fixed_legs(&self) -> &Schedule {
@@ -459,29 +578,41 @@ impl PlainSwap {
     pub fn get_floating_legs(&self) -> &Schedule {
         &self.floating_legs
     }
-}
 
-impl InstrumentTrait for PlainSwap {
-    fn get_inst_info(&self) ->  &InstInfo {
-        &self.inst_info
-    }
-
-    fn get_fixed_cashflows(
-        &self,
-        pricing_date: &OffsetDateTime,
-    ) -> Result<FxHashMap<OffsetDateTime, Real>> {
-        let mut res = FxHashMap::default();
+    fn get_fixed_cashflow_details(&self, pricing_date: &OffsetDateTime) -> Result<Vec<Cashflow>> {
+        let mut res = Vec::new();
+        let currency = self.fixed_leg_currency;
         let initial_value = self.initial_fixed_side_endorsement.unwrap_or(1.0);
 
         if self.effective_date.date() >= pricing_date.date()
             && self.initial_fixed_side_endorsement.is_some()
         {
-            res.insert(self.effective_date, initial_value);
+            res.push(
+                Cashflow::new(
+                    self.effective_date,
+                    initial_value,
+                    currency,
+                    CashflowLeg::Fixed,
+                    CashflowType::Redemption,
+                )
+                .with_notional(initial_value),
+            );
         }
 
         let maturity = self.get_maturity().unwrap();
-        if maturity.date() >= pricing_date.date() && self.last_fixed_side_payment.is_some() {
-            res.insert(*maturity, -self.last_fixed_side_payment.unwrap());
+        if let Some(last_payment) = self.last_fixed_side_payment {
+            if maturity.date() >= pricing_date.date() {
+                res.push(
+                    Cashflow::new(
+                        *maturity,
+                        -last_payment,
+                        currency,
+                        CashflowLeg::Fixed,
+                        CashflowType::Redemption,
+                    )
+                    .with_notional(last_payment),
+                );
+            }
         }
 
         if self.fixed_rate.is_none() || self.fixed_legs.is_empty() {
@@ -496,7 +627,7 @@ impl InstrumentTrait for PlainSwap {
                 continue;
             }
 
-            frac = self.calendar.year_fraction(
+            frac = self.get_fixed_leg_calendar().year_fraction(
                 base_schedule.get_calc_start_date(),
                 base_schedule.get_calc_end_date(),
                 &self.fixed_daycounter,
@@ -505,34 +636,65 @@ impl InstrumentTrait for PlainSwap {
             // an initial amount for fixed_leg is initially endorsed so it is a payment
             let amount = -fixed_rate * frac * initial_value;
 
-            res.entry(*payment_date)
-                .and_modify(|e| *e += amount)
-                .or_insert(amount);
+            res.push(
+                Cashflow::new(
+                    *payment_date,
+                    amount,
+                    currency,
+                    CashflowLeg::Fixed,
+                    CashflowType::Fixed,
+                )
+                .with_accrual_period(
+                    *base_schedule.get_calc_start_date(),
+                    *base_schedule.get_calc_end_date(),
+                )
+                .with_notional(initial_value)
+                .with_rate(fixed_rate),
+            );
         }
 
         Ok(res)
     }
 
-    fn get_floating_cashflows(
+    fn get_floating_cashflow_details(
         &self,
         pricing_date: &OffsetDateTime,
-        forward_curve: Option<Rc<RefCell<ZeroCurve>>>,
-        past_fixing_data: Option<Rc<DailyClosePrice>>,
-    ) -> Result<FxHashMap<OffsetDateTime, Real>> {
-        let mut res = FxHashMap::default();
+        forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        past_fixing_data: Option<Arc<DailyClosePrice>>,
+    ) -> Result<Vec<Cashflow>> {
+        let mut res = Vec::new();
+        let currency = self.floating_leg_currency;
         let mut initial_value = 1.0;
-        if self.effective_date.date() >= pricing_date.date()
-            && self.initial_floating_side_payment.is_some()
-        {
-            initial_value = self.initial_floating_side_payment.unwrap();
-            res.insert(self.effective_date, -initial_value);
+        if let Some(initial_payment) = self.initial_floating_side_payment {
+            if self.effective_date.date() >= pricing_date.date() {
+                initial_value = initial_payment;
+                res.push(
+                    Cashflow::new(
+                        self.effective_date,
+                        -initial_value,
+                        currency,
+                        CashflowLeg::Floating,
+                        CashflowType::Redemption,
+                    )
+                    .with_notional(initial_value),
+                );
+            }
         }
 
         let maturity = self.get_maturity().unwrap();
-        if maturity.date() >= pricing_date.date()
-            && self.last_floating_side_endorsement.is_some()
-        {
-            res.insert(*maturity, self.last_floating_side_endorsement.unwrap());
+        if let Some(last_endorsement) = self.last_floating_side_endorsement {
+            if maturity.date() >= pricing_date.date() {
+                res.push(
+                    Cashflow::new(
+                        *maturity,
+                        last_endorsement,
+                        currency,
+                        CashflowLeg::Floating,
+                        CashflowType::Redemption,
+                    )
+                    .with_notional(last_endorsement),
+                );
+            }
         }
 
         if self.rate_index.is_none() || self.floating_legs.is_empty() {
@@ -546,28 +708,101 @@ impl InstrumentTrait for PlainSwap {
                 continue;
             }
 
-            let amount = rate_index.get_coupon_amount(
+            let coupon = rate_index.get_coupon_amount(
                 base_schedule,
                 None,
                 forward_curve.clone().unwrap(),
                 past_fixing_data
                     .clone()
-                    .unwrap_or(Rc::new(DailyClosePrice::default())),
+                    .unwrap_or(Arc::new(DailyClosePrice::default())),
                 pricing_date,
                 self.floating_compound_tenor.as_ref(),
-                &self.calendar,
+                self.get_floating_leg_calendar(),
                 &self.floating_daycounter,
                 self.fixing_gap_days,
-            )? * initial_value;
+            )?;
+            let frac = self.get_floating_leg_calendar().year_fraction(
+                base_schedule.get_calc_start_date(),
+                base_schedule.get_calc_end_date(),
+                &self.floating_daycounter,
+            )?;
 
-            res.entry(*payment_date)
-                .and_modify(|e| *e += amount)
-                .or_insert(amount);
-            //res.insert(payment_date.clone(), amount);
+            let mut cashflow = Cashflow::new(
+                *payment_date,
+                coupon * initial_value,
+                currency,
+                CashflowLeg::Floating,
+                CashflowType::Floating,
+            )
+            .with_accrual_period(
+                *base_schedule.get_calc_start_date(),
+                *base_schedule.get_calc_end_date(),
+            )
+            .with_notional(initial_value);
+            if frac > 0.0 {
+                cashflow = cashflow.with_rate(coupon / frac);
+            }
+            res.push(cashflow);
         }
 
         Ok(res)
     }
+}
+
+impl InstrumentTrait for PlainSwap {
+    fn get_inst_info(&self) ->  &InstInfo {
+        &self.inst_info
+    }
+
+    fn has_outdated_schedules(&self) -> bool {
+        self.fixed_legs.is_outdated() || self.floating_legs.is_outdated()
+    }
+
+    fn refresh_schedules(&mut self) -> Result<()> {
+        if self.fixed_legs.is_outdated() {
+            self.fixed_legs = self.fixed_legs.regenerate()?;
+        }
+        if self.floating_legs.is_outdated() {
+            self.floating_legs = self.floating_legs.regenerate()?;
+        }
+        Ok(())
+    }
+
+    fn get_calendar(&self) -> Result<&JointCalendar> {
+        Ok(&self.calendar)
+    }
+
+    fn get_fixed_cashflows(
+        &self,
+        pricing_date: &OffsetDateTime,
+    ) -> Result<FxHashMap<OffsetDateTime, Real>> {
+        let cashflows = self.get_fixed_cashflow_details(pricing_date)?;
+        Ok(sum_by_payment_date(&cashflows))
+    }
+
+    fn get_floating_cashflows(
+        &self,
+        pricing_date: &OffsetDateTime,
+        forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        past_fixing_data: Option<Arc<DailyClosePrice>>,
+    ) -> Result<FxHashMap<OffsetDateTime, Real>> {
+        let cashflows =
+            self.get_floating_cashflow_details(pricing_date, forward_curve, past_fixing_data)?;
+        Ok(sum_by_payment_date(&cashflows))
+    }
+
+    /// cashflows of the fixed leg in fixed_leg_currency followed by the floating leg in floating_leg_currency
+    fn get_cashflow_details(
+        &self,
+        pricing_date: &OffsetDateTime,
+        forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        past_data: Option<Arc<DailyClosePrice>>,
+    ) -> Result<Vec<Cashflow>> {
+        let mut res = self.get_fixed_cashflow_details(pricing_date)?;
+        res.extend(self.get_floating_cashflow_details(pricing_date, forward_curve, past_data)?);
+        res.sort_by_key(|cashflow| *cashflow.get_payment_date());
+        Ok(res)
+    }
 
     fn get_rate_index(&self) -> Result<Option<&RateIndex>> {
         Ok(self.rate_index.as_ref())
@@ -624,7 +859,7 @@ mod tests {
     };
     use anyhow::Result;
     use ndarray::array;
-    use std::{cell::RefCell, rc::Rc};
+    use std::sync::{Arc, RwLock};
     use time::macros::datetime;
     use static_id::static_id::StaticId;
 
@@ -632,7 +867,7 @@ mod tests {
     fn test_crs() -> Result<()> {
         let floating_currency = Currency::USD;
         let issue_date = datetime!(2024-01-02 16:30:00 +09:00);
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(issue_date.clone())));
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(issue_date)));
         let effective_date = datetime!(2024-01-03 16:30:00 +09:00);
         let maturity = datetime!(2025-01-03 16:30:00 +09:00);
         let sk = Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Settlement));
@@ -669,8 +904,8 @@ mod tests {
             crate::InstType::PlainSwap,
             Currency::KRW,
             10_000_000.0,
-            Some(issue_date.clone()),
-            Some(maturity.clone()),
+            Some(issue_date),
+            Some(maturity),
             crate::AccountingLevel::L2,
         );
         
@@ -683,7 +918,7 @@ mod tests {
             last_fixed_side_payment,
             last_floating_side_endorsement,
             //
-            effective_date.clone(),
+            effective_date,
             //
             Some(fixed_rate),
             Some(rate_index),
@@ -713,7 +948,7 @@ mod tests {
             array![0.04, 0.04],
             None,
             Some(array![0.5, 5.0]),
-            Some(issue_date.clone()),
+            Some(issue_date),
             Currency::USD,
             "USDIRS".to_string(),
             StaticId::from_str("USDIRS", "KRX"),
@@ -727,11 +962,45 @@ mod tests {
             StaticId::from_str("USD IR Curve", "KAP"),
         )?;
         
-        let floating_curve = Rc::new(RefCell::new(usdirs_curve));
+        let floating_curve = Arc::new(RwLock::new(usdirs_curve));
         let fixed_cashflows = crs.get_fixed_cashflows(&issue_date)?;
         
-        let floating_cashflows = crs.get_floating_cashflows(&issue_date, Some(floating_curve), None)?;
-        
+        let floating_cashflows = crs.get_floating_cashflows(&issue_date, Some(floating_curve.clone()), None)?;
+
+        // typed cashflows are in the currencies of the legs and add up to the leg cashflows
+        let details = crs.get_cashflow_details(&issue_date, Some(floating_curve), None)?;
+        assert!(details
+            .windows(2)
+            .all(|w| w[0].get_payment_date() <= w[1].get_payment_date()));
+        for cashflow in details.iter() {
+            let (currency, leg_cashflows) = match cashflow.get_leg() {
+                CashflowLeg::Fixed => (Currency::KRW, &fixed_cashflows),
+                _ => (Currency::USD, &floating_cashflows),
+            };
+            assert_eq!(cashflow.get_currency(), currency);
+            let leg_sum: Real = details
+                .iter()
+                .filter(|c| {
+                    c.get_leg() == cashflow.get_leg()
+                        && c.get_payment_date() == cashflow.get_payment_date()
+                })
+                .map(|c| c.get_amount())
+                .sum();
+            assert!((leg_sum - leg_cashflows[cashflow.get_payment_date()]).abs() < 1.0e-3);
+            if cashflow.get_cashflow_type() == CashflowType::Fixed {
+                assert_eq!(cashflow.get_rate(), Some(fixed_rate));
+                assert_eq!(cashflow.get_notional(), fx_rate);
+                assert!(cashflow.get_accrual_start_date().is_some());
+            }
+        }
+        assert_eq!(
+            details
+                .iter()
+                .filter(|c| c.get_cashflow_type() == CashflowType::Redemption)
+                .count(),
+            4
+        );
+
         
         let mut fixed_keys: Vec<_> = fixed_cashflows.keys().collect();
         fixed_keys.sort();
@@ -753,9 +1022,10 @@ mod tests {
             1_330.0 as Real,
         );
 
-        assert_eq!(
-            fixed_cashflows.get(fixed_keys[4]).unwrap().clone(),
-            -1_343.2635 as Real,
+        let expected_cashflow: Real = -1_343.263_56;
+        assert!(
+            (fixed_cashflows.get(fixed_keys[4]).unwrap() - expected_cashflow).abs()
+                < 1e-6 * expected_cashflow.abs()
         );
 
         assert_eq!(
@@ -763,13 +1033,258 @@ mod tests {
             -1.0 as Real,
         );
 
+        let expected_cashflow: Real = 1.010_036;
+        assert!(
+            (floating_cashflows.get(floating_keys[4]).unwrap() - expected_cashflow).abs()
+                < 1e-6 * expected_cashflow.abs()
+        );
+
+        Ok(())
+    }
+    #[test]
+    fn test_crs_leg_calendars() -> Result<()> {
+        let issue_date = datetime!(2024-02-13 16:30:00 +09:00);
+        let effective_date = datetime!(2024-02-15 16:30:00 +09:00);
+        let maturity = datetime!(2025-02-14 16:30:00 +09:00);
+        let sk = Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Settlement));
+        let us = Calendar::UnitedStates(UnitedStates::new(UnitedStatesType::Settlement));
+        let calendar = JointCalendar::new(vec![sk, us.clone()])?;
+        let rate_index = RateIndex::new(
+            StaticId::from_str("USD Libor 3M", "KRX"),
+            crate::Tenor::new(0, 3, 0),
+            Currency::USD,
+            String::from("USD Libor 3M"),
+        )?;
+        let inst_info = crate::InstInfo::new(
+            StaticId::from_str("PlainSwap:YYY", "KRX"),
+            "MockCRS".to_string(),
+            crate::InstType::PlainSwap,
+            Currency::KRW,
+            10_000_000.0,
+            Some(issue_date),
+            Some(maturity),
+            crate::AccountingLevel::L2,
+        );
+
+        let crs = PlainSwap::new_from_conventions(
+            inst_info,
+            Currency::USD,
+            Some(1_330.0),
+            Some(1.0),
+            Some(1_330.0),
+            Some(1.0),
+            effective_date,
+            Some(0.04),
+            Some(rate_index),
+            Some(crate::Tenor::new(0, 3, 0)),
+            true,
+            DayCountConvention::Actual365Fixed,
+            DayCountConvention::Actual360,
+            BusinessDayConvention::ModifiedFollowing,
+            BusinessDayConvention::ModifiedFollowing,
+            PaymentFrequency::Quarterly,
+            PaymentFrequency::Quarterly,
+            1,
+            0,
+            calendar.clone(),
+        )?;
+        // 2024-05-15 is Buddha's birthday in Korea
+        let korean_holiday = datetime!(2024-05-15 16:30:00 +09:00);
+        let next_day = datetime!(2024-05-16 16:30:00 +09:00);
+        assert_eq!(crs.get_fixed_legs()[0].get_payment_date(), &next_day);
+        assert_eq!(crs.get_floating_legs()[0].get_payment_date(), &next_day);
+
+        // the USD leg follows New York only
+        let crs = crs.with_leg_calendars(true, calendar.clone(), JointCalendar::new(vec![us])?)?;
+        assert_eq!(crs.get_fixed_legs()[0].get_payment_date(), &next_day);
+        assert_eq!(
+            crs.get_floating_legs()[0].get_payment_date(),
+            &korean_holiday
+        );
+        assert_eq!(crs.get_fixed_leg_calendar(), &calendar);
+        assert_ne!(crs.get_floating_leg_calendar(), &calendar);
+
+        let deser: PlainSwap = serde_json::from_str(&serde_json::to_string(&crs)?)?;
+        assert_eq!(crs, deser);
+        Ok(())
+    }
+
+    #[test]
+    fn test_irs_end_of_month() -> Result<()> {
+        let issue_date = datetime!(2024-02-27 16:30:00 +09:00);
+        let effective_date = datetime!(2024-02-29 16:30:00 +09:00);
+        let maturity = datetime!(2025-02-28 16:30:00 +09:00);
+        let us = Calendar::UnitedStates(UnitedStates::new(UnitedStatesType::Settlement));
+        let calendar = JointCalendar::new(vec![us])?;
+        let rate_index = RateIndex::new(
+            StaticId::from_str("USD Libor 3M", "KRX"),
+            crate::Tenor::new(0, 3, 0),
+            Currency::USD,
+            String::from("USD Libor 3M"),
+        )?;
+        let inst_info = crate::InstInfo::new(
+            StaticId::from_str("PlainSwap:EOM", "KRX"),
+            "MockIRS".to_string(),
+            crate::InstType::PlainSwap,
+            Currency::USD,
+            10_000_000.0,
+            Some(issue_date),
+            Some(maturity),
+            crate::AccountingLevel::L2,
+        );
+
+        let irs = PlainSwap::new_from_conventions(
+            inst_info,
+            Currency::USD,
+            None,
+            None,
+            None,
+            None,
+            effective_date,
+            Some(0.04),
+            Some(rate_index),
+            None,
+            true,
+            DayCountConvention::Actual360,
+            DayCountConvention::Actual360,
+            BusinessDayConvention::ModifiedFollowing,
+            BusinessDayConvention::ModifiedFollowing,
+            PaymentFrequency::Quarterly,
+            PaymentFrequency::Quarterly,
+            2,
+            0,
+            calendar.clone(),
+        )?;
+        let payment_dates = |legs: &Schedule| {
+            legs.iter().map(|s| s.get_payment_date().date()).collect::<Vec<_>>()
+        };
         assert_eq!(
-            floating_cashflows.get(floating_keys[4]).unwrap().clone(),
-            1.010036 as Real,
+            payment_dates(irs.get_fixed_legs())[..3],
+            [
+                time::macros::date!(2024-05-29),
+                time::macros::date!(2024-08-29),
+                time::macros::date!(2024-11-29),
+            ]
         );
 
+        let eom = ScheduleOptions::new().with_end_of_month(true);
+        let irs = irs.with_schedule_options(true, eom)?;
+        let expected = vec![
+            time::macros::date!(2024-05-31),
+            time::macros::date!(2024-08-30),
+            time::macros::date!(2024-11-29),
+            time::macros::date!(2025-02-28),
+        ];
+        assert_eq!(payment_dates(irs.get_fixed_legs()), expected);
+        assert_eq!(payment_dates(irs.get_floating_legs()), expected);
+        assert_eq!(irs.get_schedule_options(), &eom);
+
+        // the options are kept when the legs are rebuilt with their calendars
+        let irs = irs.with_leg_calendars(true, calendar.clone(), calendar)?;
+        assert_eq!(payment_dates(irs.get_floating_legs()), expected);
+
+        let deser: PlainSwap = serde_json::from_str(&serde_json::to_string(&irs)?)?;
+        assert_eq!(irs, deser);
         Ok(())
     }
+
+    #[test]
+    fn test_irs_short_front_stub() -> Result<()> {
+        let issue_date = datetime!(2024-02-13 16:30:00 -05:00);
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(issue_date)));
+        let effective_date = datetime!(2024-02-15 16:30:00 -05:00);
+        let maturity = datetime!(2025-01-15 16:30:00 -05:00);
+        let us = Calendar::UnitedStates(UnitedStates::new(UnitedStatesType::Settlement));
+        let calendar = JointCalendar::new(vec![us])?;
+        let rate_index = RateIndex::new(
+            StaticId::from_str("USD Libor 3M", "KRX"),
+            crate::Tenor::new(0, 3, 0),
+            Currency::USD,
+            String::from("USD Libor 3M"),
+        )?
+        .with_stub_tenors(vec![crate::Tenor::new(0, 1, 0), crate::Tenor::new(0, 3, 0)]);
+        let inst_info = crate::InstInfo::new(
+            StaticId::from_str("PlainSwap:STUB", "KRX"),
+            "MockIRS".to_string(),
+            crate::InstType::PlainSwap,
+            Currency::USD,
+            10_000_000.0,
+            Some(issue_date),
+            Some(maturity),
+            crate::AccountingLevel::L2,
+        );
+
+        let irs = PlainSwap::new_from_conventions(
+            inst_info,
+            Currency::USD,
+            None,
+            None,
+            None,
+            None,
+            effective_date,
+            Some(0.04),
+            Some(rate_index.clone()),
+            None,
+            true,
+            DayCountConvention::Actual360,
+            DayCountConvention::Actual360,
+            BusinessDayConvention::ModifiedFollowing,
+            BusinessDayConvention::ModifiedFollowing,
+            PaymentFrequency::Quarterly,
+            PaymentFrequency::Quarterly,
+            2,
+            0,
+            calendar.clone(),
+        )?
+        .with_stub(StubType::ShortFront)?;
+        assert_eq!(irs.get_schedule_options().get_stub(), Some(StubType::ShortFront));
+
+        // two months stub from 2024-02-15 to 2024-04-15 followed by the regular quarters
+        let stubs = |legs: &Schedule| legs.iter().map(|s| s.is_stub()).collect::<Vec<_>>();
+        assert_eq!(stubs(irs.get_fixed_legs()), vec![true, false, false, false]);
+        assert_eq!(stubs(irs.get_floating_legs()), vec![true, false, false, false]);
+        let stub = irs.get_floating_legs()[0].clone();
+        assert_eq!(stub.get_calc_start_date().date(), effective_date.date());
+        assert_eq!(stub.get_calc_end_date().date(), time::macros::date!(2024-04-15));
+
+        let curve_data = VectorData::new(
+            array![0.02, 0.06],
+            None,
+            Some(array![0.1, 0.5]),
+            Some(issue_date),
+            Currency::USD,
+            "USDIRS".to_string(),
+            StaticId::from_str("USDIRS", "KAP"),
+        )?;
+        let curve = Arc::new(RwLock::new(ZeroCurve::new(
+            evaluation_date,
+            &curve_data,
+            "USDIRS".to_string(),
+            StaticId::from_str("USDIRS", "KAP"),
+        )?));
+
+        // the stub coupon takes the rate interpolated between 1M and 3M
+        let stub_rate = rate_index.get_stub_rate(&stub, &curve.read().unwrap())?;
+        let rate_3m = curve.read().unwrap().get_forward_rate_between_dates(
+            stub.get_fixing_date(),
+            &crate::Tenor::new(0, 3, 0).apply(stub.get_fixing_date()),
+            crate::enums::Compounding::Simple,
+        )?;
+        assert!(stub_rate < rate_3m);
+        let frac = calendar.year_fraction(
+            stub.get_calc_start_date(),
+            stub.get_calc_end_date(),
+            &DayCountConvention::Actual360,
+        )?;
+        let floating_cashflows = irs.get_floating_cashflows(&issue_date, Some(curve), None)?;
+        let coupon = floating_cashflows[stub.get_payment_date()];
+        assert!((coupon - stub_rate * frac).abs() < 1.0e-6, "coupon: {}", coupon);
+
+        let deser: PlainSwap = serde_json::from_str(&serde_json::to_string(&irs)?)?;
+        assert_eq!(irs, deser);
+        Ok(())
+    }
+
     #[test]
     fn test_fx_swap() -> Result<()> {
         let floating_currency = Currency::USD;
@@ -798,8 +1313,8 @@ mod tests {
             crate::InstType::PlainSwap,
             Currency::KRW,
             1.0,
-            Some(issue_date.clone()),
-            Some(maturity.clone()),
+            Some(issue_date),
+            Some(maturity),
             crate::AccountingLevel::L2,
         );
         let fx_swap = PlainSwap::new(
@@ -810,7 +1325,7 @@ mod tests {
             None,
             None,
             calendar,
-            issue_date.clone(),
+            issue_date,
             floating_currency,
             initial_fixed_side_endorsement,
             initial_floating_side_payment,
@@ -901,8 +1416,8 @@ mod tests {
             crate::InstType::PlainSwap,
             Currency::KRW,
             1.0,
-            Some(issue_date.clone()),
-            Some(maturity.clone()),
+            Some(issue_date),
+            Some(maturity),
             crate::AccountingLevel::L2,
         );
 
@@ -914,7 +1429,7 @@ mod tests {
             None,
             None,
             calendar,
-            maturity.clone(),
+            maturity,
             floating_currency,
             initial_fixed_side_endorsement,
             initial_floating_side_payment,
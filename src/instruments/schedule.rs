@@ -1,12 +1,13 @@
 use crate::definitions::Real;
 use crate::time::calendar_trait::CalendarTrait;
 use crate::time::conventions::{BusinessDayConvention, PaymentFrequency};
+use crate::time::holiday_updates::get_holiday_updates_version;
 use crate::time::jointcalendar::JointCalendar;
 use crate::utils::string_arithmetic::{add_period, sub_period};
 //
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use std::{collections::VecDeque, ops::Index};
+use std::{cmp::Ordering, collections::VecDeque, ops::Index};
 use time::{Duration, OffsetDateTime};
 
 /// A single base schudule for a coupon for bonds, IRS, etc.
@@ -19,15 +20,22 @@ use time::{Duration, OffsetDateTime};
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
 pub struct BaseSchedule {
     /// The fixing date for the coupon
+    #[serde(with = "crate::time::datetime_serde::datetime")]
     pub fixing_date: OffsetDateTime,
     /// The start date for the coupon
+    #[serde(with = "crate::time::datetime_serde::datetime")]
     pub calc_start_date: OffsetDateTime,
     /// The end date for the coupon
+    #[serde(with = "crate::time::datetime_serde::datetime")]
     pub calc_end_date: OffsetDateTime,
     /// The payment date for the coupon
+    #[serde(with = "crate::time::datetime_serde::datetime")]
     pub payment_date: OffsetDateTime,
     /// The coupon amount
     pub amount: Option<Real>, // if None, pricer calculate the coupon amount
+    /// True if the period is irregular (a stub), e.g., the first period of a backward generated schedule
+    #[serde(default)]
+    pub stub: bool,
 }
 
 impl BaseSchedule {
@@ -69,9 +77,19 @@ impl BaseSchedule {
             calc_end_date,
             payment_date,
             amount,
+            stub: false,
         }
     }
 
+    pub fn with_stub(mut self, stub: bool) -> Self {
+        self.stub = stub;
+        self
+    }
+
+    pub fn is_stub(&self) -> bool {
+        self.stub
+    }
+
     pub fn get_fixing_date(&self) -> &OffsetDateTime {
         &self.fixing_date
     }
@@ -140,10 +158,44 @@ impl BaseSchedule {
 /// assert_eq!(schedule[4].get_calc_start_date().date(), date!(2024 - 01 - 31));
 /// assert_eq!(schedule[5].get_calc_start_date().date(), date!(2024 - 04 - 30));
 /// ```
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Schedule {
     /// A list of BaseSchedule for a coupon for bonds, IRS, etc.
     data: Vec<BaseSchedule>,
+    /// the inputs of build_schedule_with_options. None if the schedule is given explicitly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    generation: Option<Box<ScheduleGeneration>>,
+}
+
+/// the inputs of build_schedule_with_options kept to regenerate the schedule after the holiday updates
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ScheduleGeneration {
+    forward_generation: bool,
+    #[serde(with = "crate::time::datetime_serde::datetime")]
+    effective_date: OffsetDateTime,
+    #[serde(with = "crate::time::datetime_serde::datetime")]
+    maturity: OffsetDateTime,
+    calendar: JointCalendar,
+    conv: BusinessDayConvention,
+    freq: PaymentFrequency,
+    fixing_gap_days: i64,
+    payment_gap_days: i64,
+    options: ScheduleOptions,
+    /// holiday_updates::get_holiday_updates_version at the generation
+    holiday_updates_version: u64,
+}
+
+// the schedules are compared by the dates only
+impl PartialEq for Schedule {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+    }
+}
+
+impl PartialOrd for Schedule {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.data.partial_cmp(&other.data)
+    }
 }
 
 impl Index<usize> for Schedule {
@@ -169,7 +221,10 @@ impl Schedule {
     /// * `data` - A vector of BaseSchedule for a coupon for bonds, IRS, etc.
     /// # Example
     pub fn new(data: Vec<BaseSchedule>) -> Self {
-        Schedule { data }
+        Schedule {
+            data,
+            generation: None,
+        }
     }
 
     pub fn len(&self) -> usize {
@@ -180,9 +235,102 @@ impl Schedule {
         self.data.is_empty()
     }
 
-    pub fn iter(&self) -> std::slice::Iter<BaseSchedule> {
+    pub fn iter(&self) -> std::slice::Iter<'_, BaseSchedule> {
         self.data.iter()
     }
+
+    /// true if the holidays were updated (time::holiday_updates) after the schedule was generated.
+    /// The schedules given explicitly by Schedule::new are never outdated
+    pub fn is_outdated(&self) -> bool {
+        self.generation
+            .as_ref()
+            .is_some_and(|g| g.holiday_updates_version != get_holiday_updates_version())
+    }
+
+    /// the schedule generated again by the same inputs with the current holidays.
+    /// The schedules given explicitly by Schedule::new are returned as they are
+    pub fn regenerate(&self) -> Result<Schedule> {
+        match &self.generation {
+            None => Ok(self.clone()),
+            Some(g) => build_schedule_with_options(
+                g.forward_generation,
+                &g.effective_date,
+                &g.maturity,
+                &g.calendar,
+                &g.conv,
+                &g.freq,
+                g.fixing_gap_days,
+                g.payment_gap_days,
+                &g.options,
+            ),
+        }
+    }
+}
+
+/// The rules of the schedule generation other than the conventions of build_schedule
+/// * end_of_month: if the date where the generation starts (effective_date in forward generation,
+///   maturity in backward generation) is the last business day of its month,
+///   all the coupon dates are the last business days of their months (the last days if Unadjusted),
+///   e.g., a swap effective on 2024-02-29 pays on 05-31, 08-30, 11-29 rather than on 05-29, 08-29, 11-29.
+///   It applies only to the monthly (or longer) frequencies.
+/// * stub: the position and the length of the irregular period, which decides the direction of the generation
+///   (forward_generation of build_schedule is ignored if it is given)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScheduleOptions {
+    end_of_month: bool,
+    #[serde(default)]
+    stub: Option<StubType>,
+}
+
+/// The irregular period of a schedule whose maturity is not a whole number of periods from the effective date
+/// * ShortFront: the first period is shorter than the others (backward generation)
+/// * LongFront: the first period is the short front stub merged with the next period
+/// * ShortBack: the last period is shorter than the others (forward generation)
+/// * LongBack: the last period is the short back stub merged with the previous period
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StubType {
+    ShortFront,
+    LongFront,
+    ShortBack,
+    LongBack,
+}
+
+impl ScheduleOptions {
+    pub fn new() -> ScheduleOptions {
+        ScheduleOptions::default()
+    }
+
+    pub fn with_end_of_month(mut self, end_of_month: bool) -> ScheduleOptions {
+        self.end_of_month = end_of_month;
+        self
+    }
+
+    pub fn with_stub(mut self, stub: StubType) -> ScheduleOptions {
+        self.stub = Some(stub);
+        self
+    }
+
+    pub fn is_end_of_month(&self) -> bool {
+        self.end_of_month
+    }
+
+    pub fn get_stub(&self) -> Option<StubType> {
+        self.stub
+    }
+}
+
+/// the last business day of the month of the date (the last day if Unadjusted)
+fn end_of_month(
+    date: &OffsetDateTime,
+    calendar: &JointCalendar,
+    conv: &BusinessDayConvention,
+) -> Result<OffsetDateTime> {
+    let last_day = calendar.last_day_of_month(date.year(), date.month());
+    let last_day = date.replace_date(last_day);
+    match conv {
+        BusinessDayConvention::Unadjusted => Ok(last_day),
+        _ => calendar.adjust(&last_day, &BusinessDayConvention::Preceding),
+    }
 }
 
 /// make a schedule for a coupon for bonds, IRS, etc.
@@ -199,6 +347,35 @@ pub fn build_schedule(
     fixing_gap_days: i64,
     payment_gap_days: i64,
 ) -> Result<Schedule> {
+    build_schedule_with_options(
+        forward_generation,
+        effective_date,
+        maturity,
+        calendar,
+        conv,
+        freq,
+        fixing_gap_days,
+        payment_gap_days,
+        &ScheduleOptions::default(),
+    )
+}
+
+/// build_schedule with the rules in ScheduleOptions, e.g., the end of month rule and the stub.
+/// The irregular period is marked by BaseSchedule::is_stub
+#[allow(clippy::too_many_arguments)]
+pub fn build_schedule_with_options(
+    forward_generation: bool,
+    effective_date: &OffsetDateTime,
+    maturity: &OffsetDateTime,
+    calendar: &JointCalendar,
+    conv: &BusinessDayConvention,
+    freq: &PaymentFrequency,
+    fixing_gap_days: i64,
+    payment_gap_days: i64,
+    options: &ScheduleOptions,
+) -> Result<Schedule> {
+    // read before the generation so that an update during the generation outdates the schedule
+    let holiday_updates_version = get_holiday_updates_version();
     if payment_gap_days < 0 || fixing_gap_days < 0 {
         // display all inputs. file and line are automatically filled by MyError
         let mut msg = String::from("payment_days and fixing_days should be non-negative\n");
@@ -212,6 +389,11 @@ pub fn build_schedule(
         return Err(anyhow!(msg));
     }
 
+    let forward_generation = match options.stub {
+        Some(StubType::ShortFront) | Some(StubType::LongFront) => false,
+        Some(StubType::ShortBack) | Some(StubType::LongBack) => true,
+        None => forward_generation,
+    };
     let mut raw_start_date_vec: VecDeque<OffsetDateTime> = VecDeque::new();
 
     match forward_generation {
@@ -237,9 +419,47 @@ pub fn build_schedule(
             }
         }
     }
+    // the irregular period is the last one in forward generation and the first one in backward generation
+    let has_stub = match forward_generation {
+        true => raw_start_date_vec.back() != Some(maturity),
+        false => raw_start_date_vec.front() != Some(effective_date),
+    };
+    // a long stub takes the regular period next to it
+    if has_stub && raw_start_date_vec.len() > 2 {
+        match options.stub {
+            Some(StubType::LongBack) => {
+                raw_start_date_vec.remove(raw_start_date_vec.len() - 2);
+            }
+            Some(StubType::LongFront) => {
+                raw_start_date_vec.remove(1);
+            }
+            _ => {}
+        }
+    }
+
+    let seed = match forward_generation {
+        true => effective_date,
+        false => maturity,
+    };
+    let monthly = matches!(
+        freq,
+        PaymentFrequency::Monthly
+            | PaymentFrequency::SemiQuarterly
+            | PaymentFrequency::Quarterly
+            | PaymentFrequency::TriAnnually
+            | PaymentFrequency::SemiAnnually
+            | PaymentFrequency::Annually
+    );
+    let end_of_month_rule = options.end_of_month
+        && monthly
+        && seed.date() >= end_of_month(seed, calendar, &BusinessDayConvention::Preceding)?.date();
+
     let calc_start_date_vec = raw_start_date_vec
         .iter()
-        .map(|x| calendar.adjust(x, conv))
+        .map(|x| match end_of_month_rule {
+            true => end_of_month(x, calendar, conv),
+            false => calendar.adjust(x, conv),
+        })
         .collect::<Result<Vec<OffsetDateTime>>>()?;
 
     let schedule_length = calc_start_date_vec.len() - 1;
@@ -301,16 +521,38 @@ pub fn build_schedule(
             ));
         }
 
-        base_schedule_vec.push(BaseSchedule::new(
-            fixing_date,
-            calc_start_date,
-            calc_end_date,
-            payment_date,
-            None,
-        ));
+        let stub = has_stub
+            && match forward_generation {
+                true => i == schedule_length - 1,
+                false => i == 0,
+            };
+        base_schedule_vec.push(
+            BaseSchedule::new(
+                fixing_date,
+                calc_start_date,
+                calc_end_date,
+                payment_date,
+                None,
+            )
+            .with_stub(stub),
+        );
     }
 
-    let schedule = Schedule::new(base_schedule_vec);
+    let schedule = Schedule {
+        data: base_schedule_vec,
+        generation: Some(Box::new(ScheduleGeneration {
+            forward_generation,
+            effective_date: *effective_date,
+            maturity: *maturity,
+            calendar: calendar.clone(),
+            conv: *conv,
+            freq: *freq,
+            fixing_gap_days,
+            payment_gap_days,
+            options: *options,
+            holiday_updates_version,
+        })),
+    };
 
     Ok(schedule)
 }
@@ -321,6 +563,7 @@ mod tests {
     use crate::time::calendar::Calendar;
     use crate::time::calendars::southkorea::{SouthKorea, SouthKoreaType};
     use time::macros::{date, datetime};
+    use time::Date;
     // make a test for
     // calendar = SouthKorea::new(SouthKoreaType::Settlement)
     // effective_date = date!(2023-08-02 16:00:00 +09:00)
@@ -510,4 +753,196 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_build_schedule_end_of_month() -> Result<()> {
+        let joint_calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Settlement,
+        ))])?;
+        let options = ScheduleOptions::new().with_end_of_month(true);
+
+        // the same swap as test_build_forward_test2 keeps the month ends
+        let schedule = build_schedule_with_options(
+            true,
+            &datetime!(2023-11-30 16:30:00 +09:00),
+            &datetime!(2024-11-29 16:30:00 +09:00),
+            &joint_calendar,
+            &BusinessDayConvention::ModifiedFollowing,
+            &PaymentFrequency::Quarterly,
+            1,
+            0,
+            &options,
+        )?;
+        let starts: Vec<Date> = schedule
+            .iter()
+            .map(|s| s.get_calc_start_date().date())
+            .collect();
+        assert_eq!(
+            starts,
+            vec![
+                date!(2023 - 11 - 30),
+                date!(2024 - 02 - 29),
+                date!(2024 - 05 - 31),
+                date!(2024 - 08 - 30),
+            ]
+        );
+
+        // Following moves the month ends on weekends to the next months without the rule
+        let effective_date = datetime!(2024-05-31 16:30:00 +09:00);
+        let maturity = datetime!(2024-09-30 16:30:00 +09:00);
+        let schedule = build_schedule(
+            true,
+            &effective_date,
+            &maturity,
+            &joint_calendar,
+            &BusinessDayConvention::Following,
+            &PaymentFrequency::Monthly,
+            1,
+            0,
+        )?;
+        assert_eq!(
+            schedule[0].get_calc_end_date().date(),
+            date!(2024 - 07 - 01)
+        );
+        assert_eq!(
+            schedule[2].get_calc_end_date().date(),
+            date!(2024 - 09 - 02)
+        );
+
+        let schedule = build_schedule_with_options(
+            true,
+            &effective_date,
+            &maturity,
+            &joint_calendar,
+            &BusinessDayConvention::Following,
+            &PaymentFrequency::Monthly,
+            1,
+            0,
+            &options,
+        )?;
+        let ends: Vec<Date> = schedule
+            .iter()
+            .map(|s| s.get_calc_end_date().date())
+            .collect();
+        assert_eq!(
+            ends,
+            vec![
+                date!(2024 - 06 - 28),
+                date!(2024 - 07 - 31),
+                date!(2024 - 08 - 30),
+                date!(2024 - 09 - 30),
+            ]
+        );
+        assert_eq!(schedule[2].get_payment_date().date(), date!(2024 - 08 - 30));
+
+        // backward generation from a month end maturity
+        let schedule = build_schedule_with_options(
+            false,
+            &datetime!(2024-01-15 16:30:00 +09:00),
+            &datetime!(2024-09-30 16:30:00 +09:00),
+            &joint_calendar,
+            &BusinessDayConvention::ModifiedFollowing,
+            &PaymentFrequency::Quarterly,
+            1,
+            0,
+            &options,
+        )?;
+        assert_eq!(
+            schedule[0].get_calc_end_date().date(),
+            date!(2024 - 03 - 29)
+        );
+        assert_eq!(
+            schedule[1].get_calc_end_date().date(),
+            date!(2024 - 06 - 28)
+        );
+
+        // a start in the middle of a month is not affected
+        let schedule = build_schedule_with_options(
+            true,
+            &datetime!(2024-01-15 16:30:00 +09:00),
+            &datetime!(2024-07-15 16:30:00 +09:00),
+            &joint_calendar,
+            &BusinessDayConvention::ModifiedFollowing,
+            &PaymentFrequency::Quarterly,
+            1,
+            0,
+            &options,
+        )?;
+        assert_eq!(
+            schedule[0].get_calc_end_date().date(),
+            date!(2024 - 04 - 15)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_schedule_stubs() -> Result<()> {
+        let joint_calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Settlement,
+        ))])?;
+        let effective_date = datetime!(2024-01-15 16:30:00 +09:00);
+        let maturity = datetime!(2024-12-31 16:30:00 +09:00);
+        let build = |stub: StubType| {
+            build_schedule_with_options(
+                true,
+                &effective_date,
+                &maturity,
+                &joint_calendar,
+                &BusinessDayConvention::ModifiedFollowing,
+                &PaymentFrequency::Quarterly,
+                1,
+                0,
+                &ScheduleOptions::new().with_stub(stub),
+            )
+        };
+        let stubs = |schedule: &Schedule| schedule.iter().map(|s| s.is_stub()).collect::<Vec<_>>();
+
+        // 01-15 ~ 03-29 (03-31 is Sunday), 03-29 ~ 06-28, 06-28 ~ 09-30, 09-30 ~ 12-31
+        let schedule = build(StubType::ShortFront)?;
+        assert_eq!(stubs(&schedule), vec![true, false, false, false]);
+        assert_eq!(
+            schedule[0].get_calc_end_date().date(),
+            date!(2024 - 03 - 29)
+        );
+
+        let schedule = build(StubType::LongFront)?;
+        assert_eq!(stubs(&schedule), vec![true, false, false]);
+        assert_eq!(
+            schedule[0].get_calc_end_date().date(),
+            date!(2024 - 06 - 28)
+        );
+
+        // 01-15 ~ 04-15, 04-15 ~ 07-15, 07-15 ~ 10-15, 10-15 ~ 12-31
+        let schedule = build(StubType::ShortBack)?;
+        assert_eq!(stubs(&schedule), vec![false, false, false, true]);
+        assert_eq!(
+            schedule[3].get_calc_start_date().date(),
+            date!(2024 - 10 - 15)
+        );
+
+        let schedule = build(StubType::LongBack)?;
+        assert_eq!(stubs(&schedule), vec![false, false, true]);
+        assert_eq!(
+            schedule[2].get_calc_start_date().date(),
+            date!(2024 - 07 - 15)
+        );
+        assert_eq!(
+            schedule[2].get_calc_end_date().date(),
+            date!(2024 - 12 - 31)
+        );
+
+        // no stub in a regular schedule
+        let schedule = build_schedule(
+            true,
+            &effective_date,
+            &datetime!(2024-07-15 16:30:00 +09:00),
+            &joint_calendar,
+            &BusinessDayConvention::ModifiedFollowing,
+            &PaymentFrequency::Quarterly,
+            1,
+            0,
+        )?;
+        assert_eq!(stubs(&schedule), vec![false, false]);
+        Ok(())
+    }
 }
@@ -2,17 +2,20 @@ use crate::currency::{Currency, FxCode};
 use crate::definitions::Real;
 use crate::enums::{OptionDailySettlementType, OptionExerciseType, OptionType};
 use crate::instrument::InstrumentTrait;
+use crate::time::jointcalendar::JointCalendar;
+use crate::time::krx_expiry::KrxExpiryRule;
 use crate::InstInfo;
 //
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use time::OffsetDateTime;
+use time::{Month, OffsetDateTime};
 use static_id::static_id::StaticId;
 //
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct VanillaOption {
     pub inst_info: InstInfo,
     pub strike: Real,
+    #[serde(with = "crate::time::datetime_serde::datetime")]
     pub settlement_date: OffsetDateTime,
     pub underlying_ids: Vec<StaticId>,
     pub underlying_currency: Currency,
@@ -80,6 +83,27 @@ impl VanillaOption {
     pub fn get_strike(&self) -> Real {
         self.strike
     }
+
+    /// fill the maturity and the settlement date following the KRX rule of the contract month
+    pub fn with_krx_expiry(
+        mut self,
+        rule: KrxExpiryRule,
+        year: i32,
+        month: Month,
+        calendar: &JointCalendar,
+    ) -> Result<VanillaOption> {
+        self.inst_info = self.inst_info.with_krx_maturity(rule, year, month, calendar)?;
+        self.settlement_date = rule.get_settlement_date(year, month, calendar)?;
+        Ok(self)
+    }
+
+    pub fn validate_krx_maturity(
+        &self,
+        rule: KrxExpiryRule,
+        calendar: &JointCalendar,
+    ) -> Result<()> {
+        self.inst_info.validate_krx_maturity(rule, calendar)
+    }
 }
 
 impl InstrumentTrait for VanillaOption {
@@ -11,7 +11,9 @@
 //!
 //! - Calculation of key risk metrics:
 //!   - Delta, Gamma, Theta, Vega, Vega Structure, Vega Matrix, Rho, Rho Structure,
-//!     Dividend Delta, Dividend Structure
+//!     Dividend Delta, Dividend Structure, Carry and Roll-down (bonds and IRS)
+//!   - Delta, Vega and Rho of analytic options by forward-mode automatic differentiation
+//!     (`GreekMethod::AutomaticDifferentiation`)
 //!
 //! ## Design Philosophy
 //!
@@ -29,8 +31,19 @@
 //! - `data`: Raw market observations
 //! - `parameters`: Objects generated from data objects for actual calculation
 //! - `instruments`: Financial instruments (e.g., Futures, FxForward, VanillaOption, IRS)
+//! - `position`: Signed quantities of instruments held in books
 //! - `time`: Calendars, conventions, handling holidays
 //! - `pricing_engines`: Engine, EngineGenerator, and Pricer
+//! - `var`: Value-at-Risk on historical and Monte Carlo scenarios, delta-normal VaR and stress tests
+//! - `frtb`: FRTB standardised approach sensitivities (SBM) from the engine's greeks
+//! - `margin`: Scenario based (SPAN-like) initial margin of listed futures and options
+//! - `limits`: Limits on the risk measures of books and their breaches
+//! - `util`: Helpers, e.g., loading portfolios from csv and json files (`util::portfolio_loader`)
+//!   and market data from csv and parquet files (`util::market_data_loader`)
+//! - `export`: Calculation results as tables, e.g., CSV files, Arrow record batches and Parquet files (feature `arrow`, `parquet`)
+//! - `storage`: Calculation runs kept in databases and reloaded for P&L explain and backtests
+//!   (sqlite with feature `sqlite`)
+//! - `ffi`: C API taking and returning json strings, for embedding in C++/C# systems (feature `ffi`)
 //!
 //! Key structs:
 //! - `CalculationConfiguration`: All information for pricing
@@ -39,6 +52,12 @@
 //! - `CalculationResult`: Contains price, greeks, and cashflows
 //! - `EngineGenerator`: Groups instruments and creates Engines for each group
 //!
+//! ## wasm32
+//!
+//! The crate compiles to `wasm32-unknown-unknown`, e.g., for pricing tools running in browsers.
+//! There are no threads and no clock there (`utils::platform`): the calculation is sequential,
+//! the timers are zero and the logs are dropped.
+//!
 //! ## Status and Future Plans
 //!
 //! Currently in the prototype stage, `rustmetrics` can handle basic plain instruments.
@@ -49,9 +68,15 @@
 //!
 //! This project is dual-licensed under Apache License, Version 2.0 and MIT license.
 
+// the casts to Real are needed when Real is f32,
+// and the literals of Real are written in the precision of f64
+#![cfg_attr(feature = "f64", allow(clippy::unnecessary_cast))]
+#![cfg_attr(not(feature = "f64"), allow(clippy::excessive_precision))]
+
 pub mod definitions;
 pub mod instrument;
 pub mod instruments;
+pub mod position;
 pub mod math;
 pub mod parameters;
 pub mod time;
@@ -62,6 +87,14 @@ pub mod data;
 pub mod enums;
 pub mod evaluation_date;
 pub mod pricing_engines;
+pub mod var;
+pub mod frtb;
+pub mod margin;
+pub mod limits;
+pub mod export;
+pub mod storage;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 #[macro_use]
 pub mod macros;
 
@@ -0,0 +1,432 @@
+use crate::currency::{Currency, FxCode};
+use crate::definitions::Real;
+use crate::instrument::InstrumentTrait;
+use crate::position::Positions;
+use crate::pricing_engines::{
+    calculation_result::CalculationResult, pnl_predictor::get_delta_underlying_id,
+    portfolio_result::PortfolioResult,
+};
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use std::fmt::Display;
+
+/// Risk measures of a book limited by LimitChecker, in the currency of the checker.
+///
+/// - Delta, Gamma: greeks on the underlying id, including the delta of the futures and stocks on it
+/// - Vega: vega on the underlying id, and TotalVega over all underlyings
+/// - Rho, Cs01: greeks on the curve id
+/// - Dv01: rho summed over the curves in the currency, e.g., KRW DV01
+/// - FxExposure: fx exposure in the currency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LimitMetric {
+    Value,
+    Delta(StaticId),
+    Gamma(StaticId),
+    Vega(StaticId),
+    TotalVega,
+    Theta,
+    Rho(StaticId),
+    Dv01(Currency),
+    Cs01(StaticId),
+    FxExposure(Currency),
+}
+
+impl Display for LimitMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LimitMetric::Value => write!(f, "Value"),
+            LimitMetric::Delta(id) => write!(f, "Delta({})", id),
+            LimitMetric::Gamma(id) => write!(f, "Gamma({})", id),
+            LimitMetric::Vega(id) => write!(f, "Vega({})", id),
+            LimitMetric::TotalVega => write!(f, "TotalVega"),
+            LimitMetric::Theta => write!(f, "Theta"),
+            LimitMetric::Rho(id) => write!(f, "Rho({})", id),
+            LimitMetric::Dv01(currency) => write!(f, "Dv01({})", currency),
+            LimitMetric::Cs01(id) => write!(f, "Cs01({})", id),
+            LimitMetric::FxExposure(currency) => write!(f, "FxExposure({})", currency),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LimitStatus {
+    Ok,
+    Warning,
+    Breach,
+}
+
+/// A limit on the absolute exposure of a metric in a book, or in all books if the book is None
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Limit {
+    book: Option<StaticId>,
+    metric: LimitMetric,
+    amount: Real,
+}
+
+impl Limit {
+    pub fn get_book(&self) -> Option<StaticId> {
+        self.book
+    }
+
+    pub fn get_metric(&self) -> LimitMetric {
+        self.metric
+    }
+
+    pub fn get_amount(&self) -> Real {
+        self.amount
+    }
+}
+
+/// exposure and utilization (|exposure| / limit in percent) of a limit
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LimitCheck {
+    limit: Limit,
+    exposure: Real,
+    utilization: Real,
+    status: LimitStatus,
+}
+
+impl LimitCheck {
+    pub fn get_limit(&self) -> &Limit {
+        &self.limit
+    }
+
+    pub fn get_exposure(&self) -> Real {
+        self.exposure
+    }
+
+    pub fn get_utilization(&self) -> Real {
+        self.utilization
+    }
+
+    pub fn get_status(&self) -> LimitStatus {
+        self.status
+    }
+}
+
+/// results of the limits in the order of registration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LimitReport {
+    currency: Currency,
+    checks: Vec<LimitCheck>,
+}
+
+impl LimitReport {
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn get_checks(&self) -> &Vec<LimitCheck> {
+        &self.checks
+    }
+
+    pub fn get_breaches(&self) -> Vec<&LimitCheck> {
+        self.checks
+            .iter()
+            .filter(|check| check.status == LimitStatus::Breach)
+            .collect()
+    }
+
+    pub fn get_warnings(&self) -> Vec<&LimitCheck> {
+        self.checks
+            .iter()
+            .filter(|check| check.status == LimitStatus::Warning)
+            .collect()
+    }
+
+    pub fn has_breach(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|check| check.status == LimitStatus::Breach)
+    }
+}
+
+/// Checks the registered limits on the results of the positions aggregated by book.
+/// A limit is breached if the utilization is over 100% and warned over the warning level (80% by default).
+/// The greeks need to be calculated beforehand; the missing greeks are taken as zero.
+#[derive(Debug, Clone)]
+pub struct LimitChecker {
+    currency: Currency,
+    limits: Vec<Limit>,
+    warning_level: Real,
+}
+
+impl LimitChecker {
+    pub fn new(currency: Currency) -> LimitChecker {
+        LimitChecker {
+            currency,
+            limits: vec![],
+            warning_level: 80.0,
+        }
+    }
+
+    /// amount: positive limit on the absolute exposure in the currency of the checker
+    pub fn with_limit(
+        mut self,
+        book: Option<StaticId>,
+        metric: LimitMetric,
+        amount: Real,
+    ) -> Result<LimitChecker> {
+        if amount <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) limit amount must be positive, but {} is given for {}",
+                file!(),
+                line!(),
+                amount,
+                metric,
+            ));
+        }
+        if self
+            .limits
+            .iter()
+            .any(|limit| limit.book == book && limit.metric == metric)
+        {
+            return Err(anyhow!(
+                "({}:{}) limit on {} is already registered for the book {:?}",
+                file!(),
+                line!(),
+                metric,
+                book,
+            ));
+        }
+        self.limits.push(Limit {
+            book,
+            metric,
+            amount,
+        });
+        Ok(self)
+    }
+
+    /// utilization in percent from which the limits are warned
+    pub fn with_warning_level(mut self, warning_level: Real) -> Result<LimitChecker> {
+        if warning_level <= 0.0 || warning_level > 100.0 {
+            return Err(anyhow!(
+                "({}:{}) warning level must be in (0, 100], but {} is given",
+                file!(),
+                line!(),
+                warning_level,
+            ));
+        }
+        self.warning_level = warning_level;
+        Ok(self)
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn get_limits(&self) -> &Vec<Limit> {
+        &self.limits
+    }
+
+    pub fn get_warning_level(&self) -> Real {
+        self.warning_level
+    }
+
+    /// fx_map: fx rates to the currency of the checker as in PortfolioResult::aggregate
+    /// curve_currencies: currencies of the curves for Dv01
+    pub fn check(
+        &self,
+        positions: &Positions,
+        results: &FxHashMap<StaticId, CalculationResult>,
+        fx_map: &FxHashMap<FxCode, Real>,
+        curve_currencies: &FxHashMap<StaticId, Currency>,
+    ) -> Result<LimitReport> {
+        let position_results = positions.get_position_results(results)?;
+        let mut book_results = FxHashMap::default();
+        for (book, book_position_results) in position_results.iter() {
+            let portfolio =
+                PortfolioResult::aggregate(book_position_results, self.currency, fx_map)?;
+            book_results.insert(*book, portfolio);
+        }
+
+        // delta and gamma keys to the underlying ids, e.g., futures id -> KOSPI2
+        let mut underlying_ids = FxHashMap::default();
+        for position in positions.iter() {
+            let inst = position.get_instrument();
+            if let Some(result) = results.get(&inst.get_id()) {
+                let keys = result
+                    .get_delta()
+                    .into_iter()
+                    .chain(result.get_gamma())
+                    .flat_map(|map| map.keys());
+                for key in keys {
+                    underlying_ids.insert(*key, get_delta_underlying_id(inst.as_ref(), key));
+                }
+            }
+        }
+
+        let mut checks = Vec::with_capacity(self.limits.len());
+        for limit in self.limits.iter() {
+            let exposure: Real = book_results
+                .iter()
+                .filter(|(book, _)| limit.book.is_none_or(|b| b == **book))
+                .map(|(_, portfolio)| {
+                    get_exposure(&limit.metric, portfolio, &underlying_ids, curve_currencies)
+                })
+                .sum();
+            let utilization = exposure.abs() / limit.amount * 100.0;
+            let status = if utilization > 100.0 {
+                LimitStatus::Breach
+            } else if utilization >= self.warning_level {
+                LimitStatus::Warning
+            } else {
+                LimitStatus::Ok
+            };
+            checks.push(LimitCheck {
+                limit: *limit,
+                exposure,
+                utilization,
+                status,
+            });
+        }
+        Ok(LimitReport {
+            currency: self.currency,
+            checks,
+        })
+    }
+}
+
+fn get_exposure(
+    metric: &LimitMetric,
+    portfolio: &PortfolioResult,
+    underlying_ids: &FxHashMap<StaticId, StaticId>,
+    curve_currencies: &FxHashMap<StaticId, Currency>,
+) -> Real {
+    let on_underlying = |map: &FxHashMap<StaticId, Real>, und_id: &StaticId| -> Real {
+        map.iter()
+            .filter(|(key, _)| underlying_ids.get(key).unwrap_or(key) == und_id)
+            .map(|(_, v)| *v)
+            .sum()
+    };
+    match metric {
+        LimitMetric::Value => portfolio.get_value(),
+        LimitMetric::Delta(und_id) => on_underlying(portfolio.get_delta(), und_id),
+        LimitMetric::Gamma(und_id) => on_underlying(portfolio.get_gamma(), und_id),
+        LimitMetric::Vega(und_id) => portfolio.get_vega().get(und_id).copied().unwrap_or(0.0),
+        LimitMetric::TotalVega => portfolio.get_vega().values().sum(),
+        LimitMetric::Theta => portfolio.get_theta(),
+        LimitMetric::Rho(curve_id) => portfolio.get_rho().get(curve_id).copied().unwrap_or(0.0),
+        LimitMetric::Dv01(currency) => portfolio
+            .get_rho()
+            .iter()
+            .filter(|(curve_id, _)| curve_currencies.get(curve_id) == Some(currency))
+            .map(|(_, v)| *v)
+            .sum(),
+        LimitMetric::Cs01(curve_id) => portfolio.get_cs01().get(curve_id).copied().unwrap_or(0.0),
+        LimitMetric::FxExposure(currency) => portfolio
+            .get_fx_exposure()
+            .get(currency)
+            .copied()
+            .unwrap_or(0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instrument::Instrument;
+    use crate::instruments::{futures::Futures, stock::Stock};
+    use crate::position::Position;
+    use crate::pricing_engines::npv_result::NpvResult;
+    use crate::{InstInfo, InstType, StockRankType};
+    use std::sync::Arc;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_limit_checker() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let curve_id = StaticId::from_str("KRWIRS", "DataProvider");
+        let (book1, book2) = (
+            StaticId::from_str("B1", "Desk"),
+            StaticId::from_str("B2", "Desk"),
+        );
+
+        let stock_info = InstInfo {
+            id: und_id,
+            currency: Currency::KRW,
+            inst_type: InstType::Stock,
+            unit_notional: 1.0,
+            ..Default::default()
+        };
+        let stock = Arc::new(Instrument::Stock(Stock {
+            underlying_ids: vec![und_id],
+            inst_info: stock_info.clone(),
+            rank_type: StockRankType::Common,
+        }));
+        let futures_info = InstInfo {
+            id: StaticId::from_str("KOSPI2 Fut", "KRX"),
+            currency: Currency::KRW,
+            inst_type: InstType::Futures,
+            unit_notional: 1.0,
+            ..Default::default()
+        };
+        let futures = Arc::new(Instrument::Futures(Futures::new(
+            futures_info.clone(),
+            350.0,
+            Some(dt),
+            Currency::KRW,
+            und_id,
+        )));
+
+        let mut results = FxHashMap::default();
+        for (info, delta, rho) in [(&stock_info, 10.0, 0.0), (&futures_info, 20.0, 5.0)] {
+            let mut result = CalculationResult::new(info.clone(), dt);
+            result.set_npv(NpvResult::new_from_npv(100.0));
+            result.set_value()?;
+            result.set_representation_currency(Currency::KRW);
+            result.set_single_delta(info.id, delta);
+            result.set_single_rho(curve_id, rho);
+            results.insert(info.id, result);
+        }
+        let positions = Positions::new(vec![
+            Position::new(stock, 2.0, book1),
+            Position::new(futures.clone(), 1.0, book1),
+            Position::new(futures, -3.0, book2),
+        ])?;
+        let mut curve_currencies = FxHashMap::default();
+        curve_currencies.insert(curve_id, Currency::KRW);
+
+        let checker = LimitChecker::new(Currency::KRW)
+            .with_limit(Some(book1), LimitMetric::Delta(und_id), 50.0)?
+            .with_limit(Some(book2), LimitMetric::Delta(und_id), 50.0)?
+            .with_limit(None, LimitMetric::Dv01(Currency::KRW), 12.0)?
+            .with_limit(None, LimitMetric::Value, 1000.0)?;
+        assert!(checker
+            .clone()
+            .with_limit(None, LimitMetric::Value, 10.0)
+            .is_err());
+        assert!(checker
+            .clone()
+            .with_limit(None, LimitMetric::Theta, 0.0)
+            .is_err());
+
+        let report = checker.check(
+            &positions,
+            &results,
+            &FxHashMap::default(),
+            &curve_currencies,
+        )?;
+        let checks = report.get_checks();
+        // book1: 2 * 10 + 20 = 40, book2: -3 * 20 = -60
+        assert_eq!(checks[0].get_exposure(), 40.0);
+        assert_eq!(checks[0].get_status(), LimitStatus::Warning);
+        assert_eq!(checks[1].get_exposure(), -60.0);
+        assert!((checks[1].get_utilization() - 120.0).abs() < 1.0e-4);
+        assert_eq!(checks[1].get_status(), LimitStatus::Breach);
+        // dv01: 5 - 15
+        assert_eq!(checks[2].get_exposure(), -10.0);
+        assert_eq!(checks[2].get_status(), LimitStatus::Warning);
+        // value: 200 + 100 - 300
+        assert_eq!(checks[3].get_exposure(), 0.0);
+        assert_eq!(checks[3].get_status(), LimitStatus::Ok);
+        assert!(report.has_breach());
+        assert_eq!(report.get_breaches().len(), 1);
+        assert_eq!(report.get_warnings().len(), 2);
+        Ok(())
+    }
+}
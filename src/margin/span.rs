@@ -0,0 +1,409 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::position::Positions;
+use crate::pricing_engines::calculation_result::CalculationResult;
+//
+use anyhow::{anyhow, Result};
+use ndarray::Array2;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+
+/// spot shifts, vol shifts and the value changes on the ladder nodes
+type LadderPnl = (Vec<Real>, Vec<Real>, Array2<Real>);
+
+/// Credit between the scanning risks of two underlyings (combined commodities),
+/// e.g., KOSPI200 and KOSDAQ150 futures and options.
+/// It is given when the net deltas of the two underlyings have the opposite signs.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InterCommoditySpread {
+    first: StaticId,
+    second: StaticId,
+    credit_rate: Real,
+}
+
+impl InterCommoditySpread {
+    pub fn get_first(&self) -> StaticId {
+        self.first
+    }
+
+    pub fn get_second(&self) -> StaticId {
+        self.second
+    }
+
+    pub fn get_credit_rate(&self) -> Real {
+        self.credit_rate
+    }
+}
+
+/// Margin of the positions on an underlying
+/// - scanning_risk: the worst loss on the spot/vol ladder nodes (zero if no node loses)
+/// - worst_spot_shift, worst_vol_shift: the node of the worst loss
+/// - net_delta: sum of the deltas (value change on 1% spot move) of the positions
+/// - spread_credit: deduction by the inter-commodity spreads
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnderlyingMargin {
+    underlying_id: StaticId,
+    scanning_risk: Real,
+    worst_spot_shift: Real,
+    worst_vol_shift: Real,
+    net_delta: Real,
+    spread_credit: Real,
+}
+
+impl UnderlyingMargin {
+    pub fn get_underlying_id(&self) -> StaticId {
+        self.underlying_id
+    }
+
+    pub fn get_scanning_risk(&self) -> Real {
+        self.scanning_risk
+    }
+
+    pub fn get_worst_spot_shift(&self) -> Real {
+        self.worst_spot_shift
+    }
+
+    pub fn get_worst_vol_shift(&self) -> Real {
+        self.worst_vol_shift
+    }
+
+    pub fn get_net_delta(&self) -> Real {
+        self.net_delta
+    }
+
+    pub fn get_spread_credit(&self) -> Real {
+        self.spread_credit
+    }
+
+    /// scanning_risk - spread_credit
+    pub fn get_requirement(&self) -> Real {
+        self.scanning_risk - self.spread_credit
+    }
+}
+
+/// Initial margin of the positions, the sum of the requirements on the underlyings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpanMargin {
+    currency: Option<Currency>,
+    underlying_margins: Vec<UnderlyingMargin>,
+}
+
+impl SpanMargin {
+    pub fn get_currency(&self) -> Option<Currency> {
+        self.currency
+    }
+
+    /// sorted by the underlying id
+    pub fn get_underlying_margins(&self) -> &Vec<UnderlyingMargin> {
+        &self.underlying_margins
+    }
+
+    pub fn get_underlying_margin(&self, und_id: &StaticId) -> Option<&UnderlyingMargin> {
+        self.underlying_margins
+            .iter()
+            .find(|margin| margin.underlying_id == *und_id)
+    }
+
+    pub fn get_scanning_risk(&self) -> Real {
+        self.underlying_margins
+            .iter()
+            .map(|margin| margin.scanning_risk)
+            .sum()
+    }
+
+    pub fn get_spread_credit(&self) -> Real {
+        self.underlying_margins
+            .iter()
+            .map(|margin| margin.spread_credit)
+            .sum()
+    }
+
+    pub fn get_requirement(&self) -> Real {
+        self.get_scanning_risk() - self.get_spread_credit()
+    }
+}
+
+/// Scenario based (SPAN-like) initial margin of listed futures and options positions, e.g., on KRX.
+///
+/// The positions on an underlying are revalued together on the nodes of the spot/vol ladder
+/// (CalculationConfiguration::with_ladder_calculation), and the scanning risk is the worst loss.
+/// The inter-commodity spread credits are then applied in the given order of priority:
+/// for the underlyings with the opposite net deltas, credit_rate * min(scanning risks left)
+/// is deducted from both of them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpanMarginCalculator {
+    inter_commodity_spreads: Vec<InterCommoditySpread>,
+}
+
+impl SpanMarginCalculator {
+    pub fn new() -> SpanMarginCalculator {
+        SpanMarginCalculator::default()
+    }
+
+    pub fn with_inter_commodity_spread(
+        mut self,
+        first: StaticId,
+        second: StaticId,
+        credit_rate: Real,
+    ) -> Result<SpanMarginCalculator> {
+        if first == second || !(0.0..=1.0).contains(&credit_rate) {
+            return Err(anyhow!(
+                "({}:{}) invalid inter-commodity spread {} / {} with credit rate {}",
+                file!(),
+                line!(),
+                first,
+                second,
+                credit_rate,
+            ));
+        }
+        self.inter_commodity_spreads.push(InterCommoditySpread {
+            first,
+            second,
+            credit_rate,
+        });
+        Ok(self)
+    }
+
+    pub fn get_inter_commodity_spreads(&self) -> &Vec<InterCommoditySpread> {
+        &self.inter_commodity_spreads
+    }
+
+    /// results: instrument id -> result with the ladders, on the unit notional of the instrument.
+    /// The positions without a ladder are not margined, unless they have a delta on an underlying.
+    pub fn calculate(
+        &self,
+        positions: &Positions,
+        results: &FxHashMap<StaticId, CalculationResult>,
+    ) -> Result<SpanMargin> {
+        let mut currency: Option<Currency> = None;
+        let mut pnls: FxHashMap<StaticId, LadderPnl> = FxHashMap::default();
+        let mut net_deltas: FxHashMap<StaticId, Real> = FxHashMap::default();
+        for position in positions.iter() {
+            let inst_id = position.get_instrument_id();
+            let result = results.get(&inst_id).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) result is not calculated for {}",
+                    file!(),
+                    line!(),
+                    inst_id,
+                )
+            })?;
+            let result = result.scaled_by_quantity(position.get_quantity());
+            let ladders = match result.get_ladder() {
+                Some(ladders) => ladders.clone(),
+                None => FxHashMap::default(),
+            };
+            if let Some(deltas) = result.get_delta() {
+                for (und_id, delta) in deltas.iter() {
+                    if *delta != 0.0 && !ladders.contains_key(und_id) {
+                        return Err(anyhow!(
+                            "({}:{}) ladder on {} is not calculated for {}",
+                            file!(),
+                            line!(),
+                            und_id,
+                            inst_id,
+                        ));
+                    }
+                }
+            }
+            if ladders.is_empty() {
+                continue;
+            }
+
+            match (currency, result.get_representation_currency()) {
+                (Some(c), Some(rc)) if c != rc => {
+                    return Err(anyhow!(
+                        "({}:{}) result of {} is in {}, not in {}",
+                        file!(),
+                        line!(),
+                        inst_id,
+                        rc,
+                        c,
+                    ));
+                }
+                (None, rc) => currency = rc,
+                _ => {}
+            }
+
+            for (und_id, ladder) in ladders.iter() {
+                let delta = result
+                    .get_delta()
+                    .and_then(|deltas| deltas.get(und_id))
+                    .copied()
+                    .unwrap_or(0.0);
+                *net_deltas.entry(*und_id).or_insert(0.0) += delta;
+                match pnls.get_mut(und_id) {
+                    Some((spot_shifts, vol_shifts, sum)) => {
+                        if spot_shifts != ladder.get_spot_shifts()
+                            || vol_shifts != ladder.get_vol_shifts()
+                        {
+                            return Err(anyhow!(
+                                "({}:{}) ladder of {} on {} is on different shifts",
+                                file!(),
+                                line!(),
+                                inst_id,
+                                und_id,
+                            ));
+                        }
+                        *sum += &ladder.get_pnls();
+                    }
+                    None => {
+                        pnls.insert(
+                            *und_id,
+                            (
+                                ladder.get_spot_shifts().clone(),
+                                ladder.get_vol_shifts().clone(),
+                                ladder.get_pnls(),
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+
+        let mut underlying_margins: Vec<UnderlyingMargin> = pnls
+            .iter()
+            .map(|(und_id, (spot_shifts, vol_shifts, pnl))| {
+                let mut margin = UnderlyingMargin {
+                    underlying_id: *und_id,
+                    scanning_risk: 0.0,
+                    worst_spot_shift: 0.0,
+                    worst_vol_shift: 0.0,
+                    net_delta: net_deltas[und_id],
+                    spread_credit: 0.0,
+                };
+                for ((i, j), v) in pnl.indexed_iter() {
+                    if -v > margin.scanning_risk {
+                        margin.scanning_risk = -v;
+                        margin.worst_spot_shift = spot_shifts[i];
+                        margin.worst_vol_shift = vol_shifts[j];
+                    }
+                }
+                margin
+            })
+            .collect();
+        underlying_margins.sort_by_key(|margin| margin.underlying_id.to_string());
+
+        for spread in self.inter_commodity_spreads.iter() {
+            let first = underlying_margins
+                .iter()
+                .position(|margin| margin.underlying_id == spread.first);
+            let second = underlying_margins
+                .iter()
+                .position(|margin| margin.underlying_id == spread.second);
+            if let (Some(first), Some(second)) = (first, second) {
+                if underlying_margins[first].net_delta * underlying_margins[second].net_delta >= 0.0
+                {
+                    continue;
+                }
+                let credit = spread.credit_rate
+                    * underlying_margins[first]
+                        .get_requirement()
+                        .min(underlying_margins[second].get_requirement());
+                underlying_margins[first].spread_credit += credit;
+                underlying_margins[second].spread_credit += credit;
+            }
+        }
+
+        Ok(SpanMargin {
+            currency,
+            underlying_margins,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instrument::Instrument;
+    use crate::instruments::{futures::Futures, inst_info::InstInfo};
+    use crate::position::Position;
+    use crate::pricing_engines::{calculation_result::Ladder, npv_result::NpvResult};
+    use crate::InstType;
+    use ndarray::array;
+    use std::sync::Arc;
+    use time::macros::datetime;
+
+    fn futures_result(
+        name: &str,
+        und_id: StaticId,
+        delta: Real,
+    ) -> Result<(Position, CalculationResult)> {
+        let inst_info = InstInfo {
+            id: StaticId::from_str(name, "KRX"),
+            name: name.to_string(),
+            inst_type: InstType::Futures,
+            currency: Currency::KRW,
+            unit_notional: 1.0,
+            ..Default::default()
+        };
+        let futures = Futures {
+            inst_info: inst_info.clone(),
+            underlying_ids: vec![und_id],
+            ..Default::default()
+        };
+        let mut result = CalculationResult::new(inst_info, datetime!(2024-03-13 16:30:00 +09:00));
+        result.set_npv(NpvResult::new_from_npv(100.0));
+        result.set_value()?;
+        result.set_single_delta(und_id, delta);
+        // linear in the spot and flat in the volatility
+        let ladder = Ladder::new(
+            vec![-0.1, 0.0, 0.1],
+            vec![-0.05, 0.05],
+            100.0,
+            array![[90.0, 90.0], [100.0, 100.0], [110.0, 110.0]],
+            None,
+        )?;
+        result.set_single_ladder(und_id, ladder);
+        let position = Position::new(
+            Arc::new(Instrument::Futures(futures)),
+            1.0,
+            StaticId::from_str("Account", "KRX"),
+        );
+        Ok((position, result))
+    }
+
+    #[test]
+    fn test_span_margin() -> Result<()> {
+        let kospi2 = StaticId::from_str("KOSPI2", "KRX");
+        let kosdaq150 = StaticId::from_str("KOSDAQ150", "KRX");
+        let (long, long_result) = futures_result("KOSPI2 Fut", kospi2, 1.0)?;
+        let (short, short_result) = futures_result("KOSDAQ150 Fut", kosdaq150, 1.0)?;
+        let short = Position::new(short.get_instrument().clone(), -2.0, short.get_book());
+        let positions = Positions::new(vec![long, short])?;
+        let mut results = FxHashMap::default();
+        results.insert(StaticId::from_str("KOSPI2 Fut", "KRX"), long_result);
+        results.insert(StaticId::from_str("KOSDAQ150 Fut", "KRX"), short_result);
+
+        let margin = SpanMarginCalculator::new().calculate(&positions, &results)?;
+        assert_eq!(margin.get_currency(), Some(Currency::KRW));
+        let kospi2_margin = margin.get_underlying_margin(&kospi2).unwrap();
+        assert!((kospi2_margin.get_scanning_risk() - 10.0).abs() < 1.0e-4);
+        assert_eq!(kospi2_margin.get_worst_spot_shift(), -0.1);
+        let kosdaq150_margin = margin.get_underlying_margin(&kosdaq150).unwrap();
+        assert!((kosdaq150_margin.get_scanning_risk() - 20.0).abs() < 1.0e-4);
+        assert_eq!(kosdaq150_margin.get_worst_spot_shift(), 0.1);
+        assert!((margin.get_requirement() - 30.0).abs() < 1.0e-4);
+
+        // 50% of the smaller scanning risk is credited on both sides
+        let calculator =
+            SpanMarginCalculator::new().with_inter_commodity_spread(kospi2, kosdaq150, 0.5)?;
+        let margin = calculator.calculate(&positions, &results)?;
+        assert!((margin.get_spread_credit() - 10.0).abs() < 1.0e-4);
+        assert!((margin.get_requirement() - 20.0).abs() < 1.0e-4);
+
+        // no credit for the positions in the same direction
+        let same_direction = Positions::new(vec![positions.iter().next().unwrap().clone()])?;
+        assert_eq!(
+            calculator
+                .calculate(&same_direction, &results)?
+                .get_spread_credit(),
+            0.0
+        );
+        assert!(SpanMarginCalculator::new()
+            .with_inter_commodity_spread(kospi2, kospi2, 0.5)
+            .is_err());
+        Ok(())
+    }
+}
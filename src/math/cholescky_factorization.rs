@@ -1,43 +1,19 @@
 use crate::definitions::Real;
+use crate::math::linalg;
 use ndarray::Array2;
 
+/// the lower triangular L with L L^T = matrix, kept for the compatibility
+#[deprecated(note = "use math::linalg::cholesky, which also checks the symmetry and factorizes in f64")]
 pub fn cholesky_decomposition(matrix: &Array2<Real>) -> Result<Array2<Real>, &'static str> {
-    let n = matrix.nrows();
-    if n != matrix.ncols() {
+    if matrix.nrows() != matrix.ncols() {
         return Err("Matrix must be square");
     }
-
-    let mut l = Array2::<Real>::zeros((n, n));
-
-    for i in 0..n {
-        for j in 0..=i {
-            let mut sum = 0.0;
-
-            // Compute the sum of L[i, k] * L[j, k] for all k < j
-            for k in 0..j {
-                sum += l[[i, k]] * l[[j, k]];
-            }
-
-            if i == j {
-                // The diagonal elements
-                let diagonal = matrix[[i, i]] - sum;
-                if diagonal <= 0.0 {
-                    return Err("Matrix is not positive definite");
-                }
-                l[[i, i]] = diagonal.sqrt();
-            } else {
-                // The off-diagonal elements
-                let value = (matrix[[i, j]] - sum) / l[[j, j]];
-                l[[i, j]] = value;
-            }
-        }
-    }
-
-    Ok(l)
+    linalg::cholesky(matrix).map_err(|_| "Matrix is not symmetric positive definite")
 }
 
 #[cfg(test)]
 mod tests {
+    #![allow(deprecated)]
     use super::*;
     use ndarray::arr2;
 
@@ -0,0 +1,276 @@
+use crate::definitions::Real;
+use crate::math::quadrature::{integrate_legendre, GaussLegendre, QuadratureOptions};
+//
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use statrs::function::erf::erfc;
+use std::f64::consts::{PI, SQRT_2};
+
+fn norm_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / SQRT_2)
+}
+
+fn norm_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * PI).sqrt()
+}
+
+fn check_correlation(rho: Real) -> Result<()> {
+    if !(-1.0..=1.0).contains(&rho) {
+        return Err(anyhow!(
+            "({}:{}) correlation must be in [-1, 1], but {} is given",
+            file!(),
+            line!(),
+            rho
+        ));
+    }
+    Ok(())
+}
+
+/// Gauss-Legendre of 6, 12 and 20 nodes on [-1, 1] for the small, middle and large correlations
+static GAUSS_LEGENDRE: Lazy<[GaussLegendre; 3]> = Lazy::new(|| {
+    [6, 12, 20].map(|n| GaussLegendre::new(n).expect("the nodes are in the range of GaussLegendre"))
+});
+
+/// P(X > h, Y > k) of the standard bivariate normal with the correlation r (Genz, 2004)
+fn bivariate_normal_upper(h: f64, k: f64, r: f64) -> f64 {
+    let (x, w) = if r.abs() < 0.3 {
+        GAUSS_LEGENDRE[0].get_nodes_and_weights()
+    } else if r.abs() < 0.75 {
+        GAUSS_LEGENDRE[1].get_nodes_and_weights()
+    } else {
+        GAUSS_LEGENDRE[2].get_nodes_and_weights()
+    };
+    let mut k = k;
+    let mut hk = h * k;
+    let mut bvn = 0.0;
+    if r.abs() < 0.925 {
+        // Drezner-Wesolowsky: the integral of the density in the correlation from 0 to r
+        let hs = 0.5 * (h * h + k * k);
+        let asr = r.asin();
+        for (wi, xi) in w.iter().zip(x.iter()) {
+            let sn = (0.5 * asr * (1.0 + xi)).sin();
+            bvn += wi * ((sn * hk - hs) / (1.0 - sn * sn)).exp();
+        }
+        return bvn * asr / (4.0 * PI) + norm_cdf(-h) * norm_cdf(-k);
+    }
+
+    // the expansion around |r| = 1
+    if r < 0.0 {
+        k = -k;
+        hk = -hk;
+    }
+    if r.abs() < 1.0 {
+        let as_ = (1.0 - r) * (1.0 + r);
+        let mut a = as_.sqrt();
+        let bs = (h - k) * (h - k);
+        let c = (4.0 - hk) / 8.0;
+        let d = (12.0 - hk) / 16.0;
+        bvn = a
+            * (-0.5 * (bs / as_ + hk)).exp()
+            * (1.0 - c * (bs - as_) * (1.0 - d * bs / 5.0) / 3.0 + c * d * as_ * as_ / 5.0);
+        if hk > -160.0 {
+            let b = bs.sqrt();
+            bvn -= (-0.5 * hk).exp()
+                * (2.0 * PI).sqrt()
+                * norm_cdf(-b / a)
+                * b
+                * (1.0 - c * bs * (1.0 - d * bs / 5.0) / 3.0);
+        }
+        a *= 0.5;
+        for (wi, xi) in w.iter().zip(x.iter()) {
+            let xs = (a * (xi + 1.0)).powi(2);
+            let rs = (1.0 - xs).sqrt();
+            let asr = -0.5 * (bs / xs + hk);
+            if asr > -100.0 {
+                bvn += a
+                    * wi
+                    * asr.exp()
+                    * ((-hk * xs / (2.0 * (1.0 + rs).powi(2))).exp() / rs
+                        - (1.0 + c * xs * (1.0 + d * xs)));
+            }
+        }
+        bvn = -bvn / (2.0 * PI);
+    }
+    if r > 0.0 {
+        bvn += norm_cdf(-h.max(k));
+    } else {
+        bvn = -bvn;
+        if k > h {
+            if h < 0.0 {
+                bvn += norm_cdf(k) - norm_cdf(h);
+            } else {
+                bvn += norm_cdf(-h) - norm_cdf(-k);
+            }
+        }
+    }
+    bvn.clamp(0.0, 1.0)
+}
+
+/// P(X <= x, Y <= y) of the standard bivariate normal with the correlation rho,
+/// by Drezner-Wesolowsky for |rho| < 0.925 and the expansion around |rho| = 1 otherwise (Genz, 2004),
+/// accurate to about 1e-15 in f64. e.g., the two-asset (spread, best/worst-of) options and the compound options.
+pub fn bivariate_normal_cdf(x: Real, y: Real, rho: Real) -> Result<Real> {
+    check_correlation(rho)?;
+    Ok(bivariate_normal_upper(-x as f64, -y as f64, rho as f64) as Real)
+}
+
+/// P(X1 <= x1, X2 <= x2, X3 <= x3) of the standard trivariate normal with the correlations
+/// (rho12, rho13, rho23), e.g., the two-asset barrier options.
+///
+/// It is the integral over the variable of the smallest correlations with the others, say X1,
+///
+/// ∫_{-∞}^{x1} φ(u) Φ2((x2 - rho12 u) / sqrt(1 - rho12²), (x3 - rho13 u) / sqrt(1 - rho13²); rho23|1) du,
+///
+/// by Gauss-Legendre quadrature to the tolerance of the options, where rho23|1 is the conditional correlation.
+pub fn trivariate_normal_cdf(
+    x: (Real, Real, Real),
+    correlations: (Real, Real, Real),
+    options: &QuadratureOptions,
+) -> Result<Real> {
+    let (rho12, rho13, rho23) = correlations;
+    for rho in [rho12, rho13, rho23] {
+        check_correlation(rho)?;
+    }
+    let (r12, r13, r23) = (rho12 as f64, rho13 as f64, rho23 as f64);
+    let determinant = 1.0 - r12 * r12 - r13 * r13 - r23 * r23 + 2.0 * r12 * r13 * r23;
+    if determinant < -1.0e-6 {
+        return Err(anyhow!(
+            "({}:{}) the correlations ({}, {}, {}) are not positive semi-definite",
+            file!(),
+            line!(),
+            rho12,
+            rho13,
+            rho23
+        ));
+    }
+
+    // (the conditioning variable, the others, their correlations with it, the correlation between the others)
+    let candidates = [
+        (x.0, x.1, x.2, r12, r13, r23),
+        (x.1, x.0, x.2, r12, r23, r13),
+        (x.2, x.0, x.1, r13, r23, r12),
+    ];
+    let (a, b, c, ra, rb, rbc) = candidates
+        .into_iter()
+        .min_by(|p, q| {
+            p.3.abs()
+                .max(p.4.abs())
+                .total_cmp(&q.3.abs().max(q.4.abs()))
+        })
+        .unwrap_or(candidates[0]);
+    if ra.abs() >= 1.0 || rb.abs() >= 1.0 {
+        // all correlations are ±1: X2 = ra X1 and X3 = rb X1
+        let (lower, upper) = [(b, ra), (c, rb)].iter().fold(
+            (f64::NEG_INFINITY, a as f64),
+            |(lower, upper), (bound, rho)| match *rho > 0.0 {
+                true => (lower, upper.min(*bound as f64)),
+                false => (lower.max(-*bound as f64), upper),
+            },
+        );
+        return Ok((norm_cdf(upper) - norm_cdf(lower)).max(0.0) as Real);
+    }
+    let (sa, sb) = ((1.0 - ra * ra).sqrt(), (1.0 - rb * rb).sqrt());
+    let conditional = ((rbc - ra * rb) / (sa * sb)).clamp(-1.0, 1.0);
+
+    // the density beyond 10 standard deviations is below the precision
+    let upper = (a as f64).min(10.0);
+    if upper <= -10.0 {
+        return Ok(0.0);
+    }
+    let mut integrand = |u: Real| {
+        let u = u as f64;
+        let p = bivariate_normal_upper(
+            -(b as f64 - ra * u) / sa,
+            -(c as f64 - rb * u) / sb,
+            conditional,
+        );
+        Ok((norm_pdf(u) * p) as Real)
+    };
+    let (res, _) = integrate_legendre(&mut integrand, -10.0, upper as Real, options)?;
+    Ok(res.clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bivariate_normal_cdf() -> Result<()> {
+        // Φ2(0, 0; ρ) = 1/4 + asin(ρ) / 2π on both algorithms
+        for rho in [-1.0, -0.95, -0.5, 0.0, 0.3, 0.8, 0.93, 0.99, 1.0] {
+            let expected = 0.25 + (rho as f64).asin() / (2.0 * PI);
+            let res = bivariate_normal_cdf(0.0, 0.0, rho)?;
+            assert!(
+                (res as f64 - expected).abs() < 1.0e-6,
+                "rho: {}, res: {}",
+                rho,
+                res
+            );
+        }
+        // the independence, the symmetry Φ2(x, y; ρ) + Φ2(x, -y; -ρ) = Φ(x) and the perfect correlations
+        let res = bivariate_normal_cdf(0.5, -1.2, 0.0)?;
+        assert!((res as f64 - norm_cdf(0.5) * norm_cdf(-1.2)).abs() < 1.0e-6);
+        for rho in [0.2, 0.6, 0.95, 0.999] {
+            let sum = bivariate_normal_cdf(0.7, 1.3, rho)? + bivariate_normal_cdf(0.7, -1.3, -rho)?;
+            assert!((sum as f64 - norm_cdf(0.7)).abs() < 1.0e-6, "rho: {}", rho);
+        }
+        assert!((bivariate_normal_cdf(0.7, 1.3, 1.0)? as f64 - norm_cdf(0.7)).abs() < 1.0e-6);
+        assert!(
+            (bivariate_normal_cdf(0.7, 1.3, -1.0)? as f64 - (norm_cdf(0.7) - norm_cdf(-1.3))).abs()
+                < 1.0e-6
+        );
+
+        // against the integral of the conditional cdf
+        let (x, y, rho): (f64, f64, f64) = (-0.4, 0.9, 0.95);
+        let mut integrand = |u: Real| {
+            let u = u as f64;
+            Ok((norm_pdf(u) * norm_cdf((y - rho * u) / (1.0 - rho * rho).sqrt())) as Real)
+        };
+        let (expected, _) = integrate_legendre(
+            &mut integrand,
+            -10.0,
+            x as Real,
+            &QuadratureOptions::default(),
+        )?;
+        let res = bivariate_normal_cdf(x as Real, y as Real, rho as Real)?;
+        assert!(
+            (res - expected).abs() < 1.0e-6,
+            "res: {}, expected: {}",
+            res,
+            expected
+        );
+        assert!(bivariate_normal_cdf(0.0, 0.0, 1.1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_trivariate_normal_cdf() -> Result<()> {
+        let options = QuadratureOptions::default().with_tolerance(1.0e-7);
+        // the orthant probability 1/8 + (asin ρ12 + asin ρ13 + asin ρ23) / 4π
+        for (r12, r13, r23) in [
+            (0.0, 0.0, 0.0),
+            (0.5, 0.3, -0.2),
+            (0.9, 0.8, 0.75),
+            (-0.4, 0.6, -0.7),
+        ] {
+            let expected = 0.125
+                + ((r12 as f64).asin() + (r13 as f64).asin() + (r23 as f64).asin()) / (4.0 * PI);
+            let res = trivariate_normal_cdf((0.0, 0.0, 0.0), (r12, r13, r23), &options)?;
+            assert!(
+                (res as f64 - expected).abs() < 1.0e-6,
+                "res: {}, expected: {}",
+                res,
+                expected
+            );
+        }
+        // the independent third variable
+        let res = trivariate_normal_cdf((0.3, -0.5, 1.1), (0.6, 0.0, 0.0), &options)?;
+        let expected = bivariate_normal_cdf(0.3, -0.5, 0.6)? as f64 * norm_cdf(1.1);
+        assert!((res as f64 - expected).abs() < 1.0e-6);
+        // the perfect correlations
+        let res = trivariate_normal_cdf((0.3, 0.5, -0.2), (1.0, -1.0, -1.0), &options)?;
+        assert!((res as f64 - (norm_cdf(0.3) - norm_cdf(0.2))).abs() < 1.0e-6);
+        assert!(trivariate_normal_cdf((0.0, 0.0, 0.0), (0.9, 0.9, -0.9), &options).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+use crate::definitions::Real;
+//
+use statrs::distribution::{Continuous, ContinuousCDF, Normal};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Dual number for the forward-mode automatic differentiation with N directions.
+///
+/// x = value + Σ_i derivatives[i] ε_i where ε_i ε_j = 0,
+/// so that f(x) = f(value) + f'(value) Σ_i derivatives[i] ε_i carries the exact first order derivatives
+/// with respect to the N input variables in one evaluation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dual<const N: usize> {
+    value: Real,
+    derivatives: [Real; N],
+}
+
+impl<const N: usize> Dual<N> {
+    pub fn constant(value: Real) -> Dual<N> {
+        Dual {
+            value,
+            derivatives: [0.0; N],
+        }
+    }
+
+    /// the index-th input variable, i.e., its derivative is the index-th unit vector
+    pub fn variable(value: Real, index: usize) -> Dual<N> {
+        let mut derivatives = [0.0; N];
+        derivatives[index] = 1.0;
+        Dual { value, derivatives }
+    }
+
+    pub fn get_value(&self) -> Real {
+        self.value
+    }
+
+    pub fn get_derivatives(&self) -> &[Real; N] {
+        &self.derivatives
+    }
+
+    pub fn get_derivative(&self, index: usize) -> Real {
+        self.derivatives[index]
+    }
+
+    /// f(x) with f(value) and f'(value) given
+    fn chain(&self, value: Real, derivative: Real) -> Dual<N> {
+        let mut derivatives = self.derivatives;
+        for d in derivatives.iter_mut() {
+            *d *= derivative;
+        }
+        Dual { value, derivatives }
+    }
+
+    pub fn exp(&self) -> Dual<N> {
+        let value = self.value.exp();
+        self.chain(value, value)
+    }
+
+    pub fn ln(&self) -> Dual<N> {
+        self.chain(self.value.ln(), 1.0 / self.value)
+    }
+
+    pub fn sqrt(&self) -> Dual<N> {
+        let value = self.value.sqrt();
+        self.chain(value, 0.5 / value)
+    }
+
+    pub fn powi(&self, n: i32) -> Dual<N> {
+        self.chain(self.value.powi(n), n as Real * self.value.powi(n - 1))
+    }
+
+    /// standard normal cumulative distribution function
+    pub fn norm_cdf(&self) -> Dual<N> {
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let x = self.value as f64;
+        self.chain(normal.cdf(x) as Real, normal.pdf(x) as Real)
+    }
+}
+
+impl<const N: usize> From<Real> for Dual<N> {
+    fn from(value: Real) -> Dual<N> {
+        Dual::constant(value)
+    }
+}
+
+impl<const N: usize> Neg for Dual<N> {
+    type Output = Dual<N>;
+
+    fn neg(self) -> Dual<N> {
+        self.chain(-self.value, -1.0)
+    }
+}
+
+impl<const N: usize> Add for Dual<N> {
+    type Output = Dual<N>;
+
+    fn add(self, rhs: Dual<N>) -> Dual<N> {
+        let mut derivatives = self.derivatives;
+        for (d, r) in derivatives.iter_mut().zip(rhs.derivatives.iter()) {
+            *d += r;
+        }
+        Dual {
+            value: self.value + rhs.value,
+            derivatives,
+        }
+    }
+}
+
+impl<const N: usize> Sub for Dual<N> {
+    type Output = Dual<N>;
+
+    fn sub(self, rhs: Dual<N>) -> Dual<N> {
+        self + (-rhs)
+    }
+}
+
+impl<const N: usize> Mul for Dual<N> {
+    type Output = Dual<N>;
+
+    fn mul(self, rhs: Dual<N>) -> Dual<N> {
+        let mut derivatives = [0.0; N];
+        for (i, d) in derivatives.iter_mut().enumerate() {
+            *d = self.derivatives[i] * rhs.value + self.value * rhs.derivatives[i];
+        }
+        Dual {
+            value: self.value * rhs.value,
+            derivatives,
+        }
+    }
+}
+
+impl<const N: usize> Div for Dual<N> {
+    type Output = Dual<N>;
+
+    fn div(self, rhs: Dual<N>) -> Dual<N> {
+        let value = self.value / rhs.value;
+        let mut derivatives = [0.0; N];
+        for (i, d) in derivatives.iter_mut().enumerate() {
+            *d = (self.derivatives[i] - value * rhs.derivatives[i]) / rhs.value;
+        }
+        Dual { value, derivatives }
+    }
+}
+
+impl<const N: usize> Add<Real> for Dual<N> {
+    type Output = Dual<N>;
+
+    fn add(self, rhs: Real) -> Dual<N> {
+        Dual {
+            value: self.value + rhs,
+            derivatives: self.derivatives,
+        }
+    }
+}
+
+impl<const N: usize> Sub<Real> for Dual<N> {
+    type Output = Dual<N>;
+
+    fn sub(self, rhs: Real) -> Dual<N> {
+        self + (-rhs)
+    }
+}
+
+impl<const N: usize> Mul<Real> for Dual<N> {
+    type Output = Dual<N>;
+
+    fn mul(self, rhs: Real) -> Dual<N> {
+        self.chain(self.value * rhs, rhs)
+    }
+}
+
+impl<const N: usize> Div<Real> for Dual<N> {
+    type Output = Dual<N>;
+
+    fn div(self, rhs: Real) -> Dual<N> {
+        self.chain(self.value / rhs, 1.0 / rhs)
+    }
+}
+
+impl<const N: usize> Add<Dual<N>> for Real {
+    type Output = Dual<N>;
+
+    fn add(self, rhs: Dual<N>) -> Dual<N> {
+        rhs + self
+    }
+}
+
+impl<const N: usize> Sub<Dual<N>> for Real {
+    type Output = Dual<N>;
+
+    fn sub(self, rhs: Dual<N>) -> Dual<N> {
+        -rhs + self
+    }
+}
+
+impl<const N: usize> Mul<Dual<N>> for Real {
+    type Output = Dual<N>;
+
+    fn mul(self, rhs: Dual<N>) -> Dual<N> {
+        rhs * self
+    }
+}
+
+impl<const N: usize> Div<Dual<N>> for Real {
+    type Output = Dual<N>;
+
+    fn div(self, rhs: Dual<N>) -> Dual<N> {
+        Dual::constant(self) / rhs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dual() {
+        // f(x, y) = exp(x y) / sqrt(x) + ln(y) * x^2 - 2 / y at (x, y) = (1.5, 0.5)
+        let (x0, y0): (Real, Real) = (1.5, 0.5);
+        let f = |x: Dual<2>, y: Dual<2>| (x * y).exp() / x.sqrt() + y.ln() * x.powi(2) - 2.0 / y;
+        let res = f(Dual::variable(x0, 0), Dual::variable(y0, 1));
+
+        let value = (x0 * y0).exp() / x0.sqrt() + y0.ln() * x0 * x0 - 2.0 / y0;
+        let dfdx = (x0 * y0).exp() * (y0 / x0.sqrt() - 0.5 * x0.powf(-1.5)) + 2.0 * x0 * y0.ln();
+        let dfdy = (x0 * y0).exp() * x0.sqrt() + x0 * x0 / y0 + 2.0 / (y0 * y0);
+        assert!((res.get_value() - value).abs() < 1.0e-5);
+        assert!((res.get_derivative(0) - dfdx).abs() < 1.0e-4);
+        assert!((res.get_derivative(1) - dfdy).abs() < 1.0e-4);
+
+        // d/dx N(x) = n(x)
+        let cdf = Dual::<1>::variable(0.3, 0).norm_cdf();
+        let pdf = (-0.5 * 0.3 * 0.3 as Real).exp() / (2.0 * std::f64::consts::PI as Real).sqrt();
+        assert!((cdf.get_value() - 0.617_911).abs() < 1.0e-5);
+        assert!((cdf.get_derivative(0) - pdf).abs() < 1.0e-6);
+
+        let c = Dual::<2>::constant(3.0);
+        assert_eq!(c.get_derivatives(), &[0.0, 0.0]);
+        assert_eq!((1.0 - c * 2.0 + 1.0).get_value(), -4.0);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::definitions::{Real, Time};
+//
+use anyhow::{anyhow, Result};
+
+/// Hull-White one factor trinomial tree fitted to a discount curve (Hull and White, 1994)
+///
+/// dr = (theta(t) - a r) dt + sigma dW
+///
+/// The tree is built on the uniform time steps from 0 to the maturity.
+/// The node (i, j) is at the time i * dt and the short rate alphas[i] + j * dx,
+/// where the alphas are fitted to reproduce the discount factors on the steps.
+/// The branching turns to the mean at j_max = ceil(0.184 / (a dt)) to keep the probabilities positive.
+#[derive(Debug, Clone)]
+pub struct HullWhiteTrinomialTree {
+    mean_reversion: Real,
+    volatility: Real,
+    dt: Time,
+    dx: Real,
+    j_max: i64,
+    alphas: Vec<Real>,
+}
+
+impl HullWhiteTrinomialTree {
+    /// discount_function: discount factor from 0 to the time, e.g., |t| curve.get_discount_factor(t)
+    pub fn new(
+        mean_reversion: Real,
+        volatility: Real,
+        maturity: Time,
+        steps: usize,
+        discount_function: impl Fn(Time) -> Result<Real>,
+    ) -> Result<HullWhiteTrinomialTree> {
+        if mean_reversion <= 0.0 || volatility <= 0.0 || maturity <= 0.0 || steps == 0 {
+            return Err(anyhow!(
+                "({}:{}) mean reversion ({}), volatility ({}), maturity ({}) and steps ({}) must be positive",
+                file!(),
+                line!(),
+                mean_reversion,
+                volatility,
+                maturity,
+                steps,
+            ));
+        }
+        let dt = maturity / steps as Real;
+        let variance = volatility * volatility * (1.0 - (-2.0 * mean_reversion * dt).exp())
+            / (2.0 * mean_reversion);
+        let dx = (3.0 * variance).sqrt();
+        let j_max = (0.184 / (mean_reversion * dt)).ceil() as i64;
+        let mut tree = HullWhiteTrinomialTree {
+            mean_reversion,
+            volatility,
+            dt,
+            dx,
+            j_max,
+            alphas: Vec::with_capacity(steps),
+        };
+
+        // forward induction on the Arrow-Debreu prices
+        let mut arrow_debreu: Vec<Real> = vec![1.0];
+        for i in 0..steps {
+            let width = tree.get_width(i);
+            let discounted: Real = arrow_debreu
+                .iter()
+                .enumerate()
+                .map(|(idx, q)| q * (-(idx as i64 - width) as Real * dx * dt).exp())
+                .sum();
+            let alpha = (discounted / discount_function((i + 1) as Time * dt)?).ln() / dt;
+            tree.alphas.push(alpha);
+
+            let next_width = tree.get_width(i + 1);
+            let mut next = vec![0.0; (2 * next_width + 1) as usize];
+            for (idx, q) in arrow_debreu.iter().enumerate() {
+                let j = idx as i64 - width;
+                let discount = (-(alpha + j as Real * dx) * dt).exp();
+                let (targets, probabilities) = tree.branch(j);
+                for (k, p) in targets.iter().zip(probabilities.iter()) {
+                    next[(k + next_width) as usize] += q * p * discount;
+                }
+            }
+            arrow_debreu = next;
+        }
+        Ok(tree)
+    }
+
+    pub fn get_mean_reversion(&self) -> Real {
+        self.mean_reversion
+    }
+
+    pub fn get_volatility(&self) -> Real {
+        self.volatility
+    }
+
+    pub fn get_dt(&self) -> Time {
+        self.dt
+    }
+
+    pub fn get_steps(&self) -> usize {
+        self.alphas.len()
+    }
+
+    /// the nearest step to the time
+    pub fn get_step(&self, time: Time) -> usize {
+        ((time / self.dt).round().max(0.0) as usize).min(self.get_steps())
+    }
+
+    /// value at 0 of the cashflows (step, amount) where the issuer can redeem at the call prices (step, price)
+    /// in place of the cashflows after the call step.
+    /// The short rates are shifted by the spread in discounting, which is the OAS for the market price.
+    pub fn price(
+        &self,
+        cashflows: &[(usize, Real)],
+        calls: &[(usize, Real)],
+        spread: Real,
+    ) -> Real {
+        let steps = self.get_steps();
+        let mut values = vec![0.0; (2 * self.get_width(steps) + 1) as usize];
+        Self::add_at_step(&mut values, cashflows, steps);
+        for i in (0..steps).rev() {
+            let width = self.get_width(i);
+            let next_width = self.get_width(i + 1);
+            let mut current = vec![0.0; (2 * width + 1) as usize];
+            for (idx, value) in current.iter_mut().enumerate() {
+                let j = idx as i64 - width;
+                let (targets, probabilities) = self.branch(j);
+                let expectation: Real = targets
+                    .iter()
+                    .zip(probabilities.iter())
+                    .map(|(k, p)| p * values[(k + next_width) as usize])
+                    .sum();
+                let rate = self.alphas[i] + j as Real * self.dx + spread;
+                *value = expectation * (-rate * self.dt).exp();
+            }
+            for (step, call_price) in calls.iter() {
+                if *step == i {
+                    for value in current.iter_mut() {
+                        *value = value.min(*call_price);
+                    }
+                }
+            }
+            Self::add_at_step(&mut current, cashflows, i);
+            values = current;
+        }
+        values[0]
+    }
+
+    fn add_at_step(values: &mut [Real], cashflows: &[(usize, Real)], step: usize) {
+        for (_, amount) in cashflows.iter().filter(|(s, _)| *s == step) {
+            for value in values.iter_mut() {
+                *value += amount;
+            }
+        }
+    }
+
+    fn get_width(&self, step: usize) -> i64 {
+        (step as i64).min(self.j_max)
+    }
+
+    /// target nodes and probabilities of the branching from the node j
+    fn branch(&self, j: i64) -> ([i64; 3], [Real; 3]) {
+        let m = -self.mean_reversion * self.dt;
+        let jm = j as Real * m;
+        let jm2 = jm * jm;
+        if j >= self.j_max {
+            (
+                [j, j - 1, j - 2],
+                [
+                    7.0 / 6.0 + (jm2 + 3.0 * jm) / 2.0,
+                    -1.0 / 3.0 - jm2 - 2.0 * jm,
+                    1.0 / 6.0 + (jm2 + jm) / 2.0,
+                ],
+            )
+        } else if j <= -self.j_max {
+            (
+                [j + 2, j + 1, j],
+                [
+                    1.0 / 6.0 + (jm2 - jm) / 2.0,
+                    -1.0 / 3.0 - jm2 + 2.0 * jm,
+                    7.0 / 6.0 + (jm2 - 3.0 * jm) / 2.0,
+                ],
+            )
+        } else {
+            (
+                [j + 1, j, j - 1],
+                [
+                    1.0 / 6.0 + (jm2 + jm) / 2.0,
+                    2.0 / 3.0 - jm2,
+                    1.0 / 6.0 + (jm2 - jm) / 2.0,
+                ],
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hull_white_trinomial_tree() -> Result<()> {
+        let discount = |t: Time| -> Result<Real> { Ok((-0.03 * t - 0.002 * t * t).exp()) };
+        let tree = HullWhiteTrinomialTree::new(0.05, 0.01, 5.0, 100, discount)?;
+
+        // zero coupon bonds are repriced by the fitted tree
+        for t in [1.0, 2.5, 5.0] {
+            let step = tree.get_step(t);
+            let price = tree.price(&[(step, 1.0)], &[], 0.0);
+            assert!(
+                (price - discount(t)?).abs() < 1.0e-4,
+                "price: {}, discount factor: {}",
+                price,
+                discount(t)?
+            );
+        }
+
+        // the issuer's call lowers the value, and the spread discounts the cashflows further
+        let cashflows: Vec<(usize, Real)> = (1..=5)
+            .map(|y| (tree.get_step(y as Time), if y == 5 { 1.05 } else { 0.05 }))
+            .collect();
+        let bullet = tree.price(&cashflows, &[], 0.0);
+        let callable = tree.price(&cashflows, &[(tree.get_step(2.0), 1.0)], 0.0);
+        assert!(
+            callable < bullet,
+            "callable: {}, bullet: {}",
+            callable,
+            bullet
+        );
+        assert!(tree.price(&cashflows, &[], 0.01) < bullet);
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use crate::definitions::Real;
+use crate::utils::find_index_ndarray::binary_search_index_ndarray;
 use anyhow::Result;
 use ndarray::Array1;
 use num_traits::Num;
@@ -34,3 +35,33 @@ where
     fn interpolate(&self, x: T) -> Result<Real>;
     fn vectorized_interpolate_for_sorted_ndarray(&self, x: &Array1<T>) -> Result<Array1<Real>>;
 }
+
+/// Interpolation on (t, x), e.g., (expiry, moneyness) of the volatility surfaces,
+/// (tenor, tenor) of the correlation term structures, and (time, spot) of the local volatility grids
+pub trait Interpolator2D {
+    fn interpolate(&self, t: Real, x: Real) -> Result<Real>;
+
+    /// The values are regarded as volatilities, and v^2 * t is interpolated linearly in t
+    /// between the increasing expiries t_knots. For t <= 0 or out of t_knots, it is interpolate itself
+    fn interpolate_total_variance_between(
+        &self,
+        t_knots: &Array1<Real>,
+        t: Real,
+        x: Real,
+    ) -> Result<Real> {
+        let n = t_knots.len();
+        if n < 2 || t <= 0.0 || t < t_knots[0] || t >= t_knots[n - 1] {
+            return self.interpolate(t, x);
+        }
+        let t_index = binary_search_index_ndarray(t_knots, t).min(n - 2);
+        let t_prev = t_knots[t_index];
+        let t_next = t_knots[t_index + 1];
+        let v_prev = self.interpolate(t_prev, x)?;
+        let v_next = self.interpolate(t_next, x)?;
+        let w_prev = v_prev * v_prev * t_prev;
+        let w_next = v_next * v_next * t_next;
+        let w = w_prev + (t - t_prev) * (w_next - w_prev) / (t_next - t_prev);
+
+        Ok((w.max(0.0) / t).sqrt())
+    }
+}
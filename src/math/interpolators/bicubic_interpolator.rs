@@ -0,0 +1,223 @@
+use crate::definitions::Real;
+use crate::math::interpolator::Interpolator2D;
+use crate::utils::find_index_ndarray::binary_search_index_ndarray;
+use anyhow::{anyhow, Result};
+use ndarray::{Array1, Array2, ArrayView1, Axis};
+
+/// second derivatives of the natural cubic spline on (domain, value)
+fn natural_spline_second_derivatives(domain: &Array1<Real>, value: ArrayView1<Real>) -> Array1<Real> {
+    let n = domain.len();
+    let mut y2 = Array1::zeros(n);
+    let mut u = Array1::<Real>::zeros(n);
+    // tridiagonal system with y2[0] = y2[n - 1] = 0
+    for i in 1..n - 1 {
+        let sig = (domain[i] - domain[i - 1]) / (domain[i + 1] - domain[i - 1]);
+        let p = sig * y2[i - 1] + 2.0;
+        y2[i] = (sig - 1.0) / p;
+        let slope_next = (value[i + 1] - value[i]) / (domain[i + 1] - domain[i]);
+        let slope_prev = (value[i] - value[i - 1]) / (domain[i] - domain[i - 1]);
+        u[i] = (6.0 * (slope_next - slope_prev) / (domain[i + 1] - domain[i - 1]) - sig * u[i - 1])
+            / p;
+    }
+    y2[n - 1] = 0.0;
+    for i in (0..n - 1).rev() {
+        y2[i] = y2[i] * y2[i + 1] + u[i];
+    }
+    y2
+}
+
+/// the second derivatives of the natural cubic splines along each row (axis = 1) or each column (axis = 0)
+fn second_derivatives_along(domain: &Array1<Real>, values: &Array2<Real>, axis: usize) -> Array2<Real> {
+    let mut res = Array2::zeros(values.raw_dim());
+    for (lane, mut res_lane) in values
+        .lanes(Axis(axis))
+        .into_iter()
+        .zip(res.lanes_mut(Axis(axis)))
+    {
+        res_lane.assign(&natural_spline_second_derivatives(domain, lane));
+    }
+    res
+}
+
+/// the natural cubic spline at x in [domain[i], domain[i + 1]]
+/// of the values (y_i, y_i+1) and the second derivatives (y2_i, y2_i+1) there
+fn evaluate_segment(
+    domain: &Array1<Real>,
+    i: usize,
+    value: (Real, Real),
+    y2: (Real, Real),
+    x: Real,
+) -> Real {
+    let h = domain[i + 1] - domain[i];
+    let a = (domain[i + 1] - x) / h;
+    let b = (x - domain[i]) / h;
+    a * value.0 + b * value.1 + ((a * a * a - a) * y2.0 + (b * b * b - b) * y2.1) * h * h / 6.0
+}
+
+/// Bicubic spline on the rectangle grid: the natural cubic splines along x on each t,
+/// and the natural cubic spline along t of the values at x.
+/// Unlike BilinearInterpolator, the first and second derivatives are continuous,
+/// e.g., for the Dupire local volatility from the implied volatility surface.
+///
+/// Out of the grid, the value at the nearest boundary is given (flat extrapolation) if allow_extrapolation.
+#[derive(Debug, Clone)]
+pub struct BicubicInterpolator {
+    t_domain: Array1<Real>,
+    x_domain: Array1<Real>,
+    values: Array2<Real>,
+    // the second derivatives along x of each row of values
+    x_second_derivatives: Array2<Real>,
+    // the second derivatives along t of each column of values
+    t_second_derivatives: Array2<Real>,
+    // the second derivatives along x of each row of t_second_derivatives
+    cross_second_derivatives: Array2<Real>,
+    allow_extrapolation: bool,
+}
+
+impl BicubicInterpolator {
+    /// values[[i, j]] is the value on (t_domain[i], x_domain[j])
+    pub fn new(
+        t_domain: Array1<Real>,
+        x_domain: Array1<Real>,
+        values: Array2<Real>,
+        allow_extrapolation: bool,
+    ) -> Result<BicubicInterpolator> {
+        let (n, m) = (t_domain.len(), x_domain.len());
+        if n < 2 || m < 2 {
+            return Err(anyhow!(
+                "({}:{}) t_domain and x_domain must have at least 2 elements\n\
+                t_domain: {:?}\n\
+                x_domain: {:?}",
+                file!(),
+                line!(),
+                t_domain,
+                x_domain
+            ));
+        }
+        if values.shape() != [n, m] {
+            return Err(anyhow!(
+                "({}:{}) the shape of values {:?} must be (t_domain, x_domain) = ({}, {})",
+                file!(),
+                line!(),
+                values.shape(),
+                n,
+                m
+            ));
+        }
+        for domain in [&t_domain, &x_domain] {
+            if domain.windows(2).into_iter().any(|w| w[0] >= w[1]) {
+                return Err(anyhow!(
+                    "({}:{}) domain must be strictly increasing: \n{:?}",
+                    file!(),
+                    line!(),
+                    domain
+                ));
+            }
+        }
+
+        // the spline along t of the splines along x is linear in the values,
+        // so the second derivatives along t are splined along x once for all
+        let x_second_derivatives = second_derivatives_along(&x_domain, &values, 1);
+        let t_second_derivatives = second_derivatives_along(&t_domain, &values, 0);
+        let cross_second_derivatives = second_derivatives_along(&x_domain, &t_second_derivatives, 1);
+        Ok(BicubicInterpolator {
+            t_domain,
+            x_domain,
+            values,
+            x_second_derivatives,
+            t_second_derivatives,
+            cross_second_derivatives,
+            allow_extrapolation,
+        })
+    }
+
+    fn bounded(&self, domain: &Array1<Real>, v: Real, name: &str) -> Result<Real> {
+        let (lower, upper) = (domain[0], domain[domain.len() - 1]);
+        if v >= lower && v <= upper {
+            return Ok(v);
+        }
+        if self.allow_extrapolation {
+            return Ok(v.clamp(lower, upper));
+        }
+        Err(anyhow!(
+            "({}:{}) {} (={}) is out of range where\n\
+            domain: Array1<Real> = {:?}",
+            file!(),
+            line!(),
+            name,
+            v,
+            domain
+        ))
+    }
+
+    pub fn get_t_domain(&self) -> &Array1<Real> {
+        &self.t_domain
+    }
+
+    pub fn get_x_domain(&self) -> &Array1<Real> {
+        &self.x_domain
+    }
+}
+
+impl Interpolator2D for BicubicInterpolator {
+    fn interpolate(&self, t: Real, x: Real) -> Result<Real> {
+        let t = self.bounded(&self.t_domain, t, "t")?;
+        let x = self.bounded(&self.x_domain, x, "x")?;
+        let j = binary_search_index_ndarray(&self.x_domain, x).min(self.x_domain.len() - 2);
+        let along_x = |values: &Array2<Real>, y2: &Array2<Real>, i: usize| {
+            evaluate_segment(
+                &self.x_domain,
+                j,
+                (values[[i, j]], values[[i, j + 1]]),
+                (y2[[i, j]], y2[[i, j + 1]]),
+                x,
+            )
+        };
+        let i = binary_search_index_ndarray(&self.t_domain, t).min(self.t_domain.len() - 2);
+        let value = (
+            along_x(&self.values, &self.x_second_derivatives, i),
+            along_x(&self.values, &self.x_second_derivatives, i + 1),
+        );
+        let y2 = (
+            along_x(&self.t_second_derivatives, &self.cross_second_derivatives, i),
+            along_x(&self.t_second_derivatives, &self.cross_second_derivatives, i + 1),
+        );
+        Ok(evaluate_segment(&self.t_domain, i, value, y2, t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_bicubic_interpolator() -> Result<()> {
+        let t_domain = Array1::linspace(0.0, 2.0, 9);
+        let x_domain = Array1::linspace(0.5, 1.5, 11);
+        let f = |t: Real, x: Real| (1.0 + t).ln() * (x - 1.0).powi(2) + 0.2 * t;
+        let values = Array2::from_shape_fn((9, 11), |(i, j)| f(t_domain[i], x_domain[j]));
+        let interpolator =
+            BicubicInterpolator::new(t_domain.clone(), x_domain.clone(), values, false)?;
+
+        // exact on the grid
+        assert!(
+            (interpolator.interpolate(t_domain[3], x_domain[7])? - f(t_domain[3], x_domain[7]))
+                .abs()
+                < 1.0e-6
+        );
+        for (t, x) in [(0.3, 0.77), (1.1, 1.03), (1.9, 1.42)] {
+            let v = interpolator.interpolate(t, x)?;
+            assert!((v - f(t, x)).abs() < 1.0e-3, "t: {}, x: {}, v: {}", t, x, v);
+        }
+        assert!(interpolator.interpolate(2.5, 1.0).is_err());
+
+        // the linear function is reproduced, and flat out of the grid
+        let values = array![[0.0, 1.0, 2.0], [1.0, 2.0, 3.0], [2.0, 3.0, 4.0]];
+        let interpolator =
+            BicubicInterpolator::new(array![0.0, 1.0, 2.0], array![0.0, 1.0, 2.0], values, true)?;
+        assert!((interpolator.interpolate(0.5, 1.5)? - 2.0).abs() < 1.0e-6);
+        assert!((interpolator.interpolate(-1.0, 3.0)? - 2.0).abs() < 1.0e-6);
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use crate::definitions::Real;
 use crate::math::{
-    interpolator::{ExtraPolationType, InterpolatorReal1D},
+    interpolator::{ExtraPolationType, Interpolator2D, InterpolatorReal1D},
     interpolators::linear_interpolator::LinearInterpolator1D,
 };
 use crate::util::is_ndarray_sorted;
@@ -174,28 +174,26 @@ impl BilinearInterpolator {
                         let v_prev = self.x_interpolator[n - 2].interpolate(x)?;
                         let t_last = self.t_domain[n - 1];
                         let t_prev = self.t_domain[n - 2];
-                        return Ok(v_last + (t - t_last) * (v_last - v_prev) / (t_last - t_prev));
+                        Ok(v_last + (t - t_last) * (v_last - v_prev) / (t_last - t_prev))
                     }
-                    ExtraPolationType::None => {
-                        return Err(anyhow!(
-                            "({}:{}) t (={}) is out of range where\n\
+                    ExtraPolationType::None => Err(anyhow!(
+                        "({}:{}) t (={}) is out of range where\n\
                         t_domain: Array1<Real> = {:?}",
-                            file!(),
-                            line!(),
-                            t,
-                            self.t_domain
-                        ))
-                    }
+                        file!(),
+                        line!(),
+                        t,
+                        self.t_domain
+                    )),
                 }
             } else {
-                return Err(anyhow!(
+                Err(anyhow!(
                     "({}:{}) t (={}) is out of range where\n\
                     t_domain: Array1<Real> = {:?}",
                     file!(),
                     line!(),
                     t,
                     self.t_domain
-                ));
+                ))
             }
         } else {
             let t_index = binary_search_index_ndarray(&self.t_domain, t);
@@ -204,9 +202,25 @@ impl BilinearInterpolator {
             let v_prev = self.x_interpolator[t_index].interpolate(x)?;
             let v_next = self.x_interpolator[t_index + 1].interpolate(x)?;
 
-            return Ok(v_prev + (t - t_prev) * (v_next - v_prev) / (t_next - t_prev));
+            Ok(v_prev + (t - t_prev) * (v_next - v_prev) / (t_next - t_prev))
         }
     }
+
+    pub fn get_t_domain(&self) -> &Array1<Real> {
+        &self.t_domain
+    }
+
+    /// The values are regarded as volatilities, and v^2 * t is interpolated linearly in t.
+    /// Out of t_domain, it follows the extrapolation of interpolate
+    pub fn interpolate_total_variance(&self, t: Real, x: Real) -> Result<Real> {
+        self.interpolate_total_variance_between(&self.t_domain, t, x)
+    }
+}
+
+impl Interpolator2D for BilinearInterpolator {
+    fn interpolate(&self, t: Real, x: Real) -> Result<Real> {
+        BilinearInterpolator::interpolate(self, t, x)
+    }
 }
 
 #[cfg(test)]
@@ -279,4 +293,30 @@ mod tests {
         assert_eq!(bilinear_interpolator.interpolate(1.5, 0.5).unwrap(), 2.0);
         Ok(())
     }
+
+    #[test]
+    fn test_interpolate_total_variance() -> Result<()> {
+        let t_domain = array![0.1, 1.0];
+        let x_domain = array![0.9, 1.1];
+        let values = array![[0.4, 0.4], [0.2, 0.2]];
+        let interpolator = BilinearInterpolator::new_from_rectangle_data(
+            t_domain,
+            x_domain,
+            values,
+            true,
+            ExtraPolationType::Flat,
+            true,
+            ExtraPolationType::Flat,
+        )?;
+        let t: Real = 0.5;
+        let vol = interpolator.interpolate_total_variance(t, 1.0)?;
+        let w = 0.016 + (t - 0.1) * (0.04 - 0.016) / 0.9;
+        assert!((vol * vol * t - w).abs() < 1.0e-6, "vol: {}", vol);
+        // the total variance is increasing in t, unlike the linear interpolation of volatility here
+        let v1 = interpolator.interpolate_total_variance(0.3, 1.0)?;
+        assert!(v1 * v1 * 0.3 < vol * vol * t);
+        // extrapolation is the same as interpolate
+        assert_eq!(interpolator.interpolate_total_variance(2.0, 1.0)?, 0.2);
+        Ok(())
+    }
 }
@@ -190,7 +190,6 @@ impl InterpolatorReal1D for LinearInterpolator1D {
 }
 
 #[cfg(test)]
-
 mod tests {
     use super::*;
     use assert_approx_eq::assert_approx_eq;
@@ -68,10 +68,9 @@ where
         let n = self.domain.len();
         if x < self.domain[0] {
             if self.allow_extrapolation {
-                if self.left_extrapolation_value.is_none() {
-                    return Ok(self.value[0]);
-                } else {
-                    return Ok(self.left_extrapolation_value.unwrap());
+                match self.left_extrapolation_value {
+                    None => return Ok(self.value[0]),
+                    Some(value) => return Ok(value),
                 }
             } else {
                 return Err(anyhow!(
@@ -82,10 +81,9 @@ where
         }
         if x > self.domain[n - 1] {
             if self.allow_extrapolation {
-                if self.right_extrapolation_value.is_none() {
-                    return Ok(self.value[n - 1]);
-                } else {
-                    return Ok(self.right_extrapolation_value.unwrap());
+                match self.right_extrapolation_value {
+                    None => return Ok(self.value[n - 1]),
+                    Some(value) => return Ok(value),
                 }
             } else {
                 return Err(anyhow!(
@@ -0,0 +1,203 @@
+use crate::definitions::Real;
+use crate::math::interpolator::Interpolator2D;
+use crate::math::linalg::solve_linear_system;
+use anyhow::{anyhow, Result};
+use ndarray::{Array1, Array2};
+
+/// r^2 ln r of the squared distance r2
+fn kernel(r2: f64) -> f64 {
+    if r2 <= 0.0 {
+        0.0
+    } else {
+        0.5 * r2 * r2.ln()
+    }
+}
+
+/// Thin plate spline on the scattered points (t, x), e.g., the quotes of a volatility surface
+/// not on a rectangle grid:
+///
+/// f(t, x) = a0 + a1 t + a2 x + Σ w_i φ(|(t, x) - (t_i, x_i)|), φ(r) = r^2 ln r
+///
+/// The axes are scaled by the ranges of the points so that, e.g., the years and the moneyness are comparable.
+/// smoothing = 0 interpolates the values, and a positive smoothing trades the fit for the smoothness (the regression).
+/// The extrapolation is given by the spline itself which is linear far from the points.
+#[derive(Debug, Clone)]
+pub struct ThinPlateSplineInterpolator {
+    points: Array2<f64>,
+    scale: (f64, f64),
+    weights: Vec<f64>,
+    // (a0, a1, a2)
+    affine: (f64, f64, f64),
+}
+
+impl ThinPlateSplineInterpolator {
+    /// points[[i, 0]] = t_i, points[[i, 1]] = x_i and values[i] is the value on (t_i, x_i)
+    pub fn new(
+        points: Array2<Real>,
+        values: Array1<Real>,
+        smoothing: Real,
+    ) -> Result<ThinPlateSplineInterpolator> {
+        let n = points.nrows();
+        if points.ncols() != 2 || n != values.len() || n < 3 {
+            return Err(anyhow!(
+                "({}:{}) points must be (n, 2) for the n (>= 3) values, but points: {:?}, values: {}",
+                file!(),
+                line!(),
+                points.shape(),
+                values.len()
+            ));
+        }
+        if smoothing < 0.0 {
+            return Err(anyhow!(
+                "({}:{}) smoothing must be non-negative, but {} is given",
+                file!(),
+                line!(),
+                smoothing
+            ));
+        }
+        let range = |j: usize| {
+            let column = points.column(j);
+            let max = column.iter().fold(Real::NEG_INFINITY, |a, b| a.max(*b));
+            let min = column.iter().fold(Real::INFINITY, |a, b| a.min(*b));
+            (max - min) as f64
+        };
+        let scale = (range(0), range(1));
+        if scale.0 <= 0.0 || scale.1 <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) the points are on a line, which does not determine a surface: {:?}",
+                file!(),
+                line!(),
+                points
+            ));
+        }
+        let points = points.mapv(|v| v as f64);
+        let scaled = Array2::from_shape_fn((n, 2), |(i, j)| {
+            points[[i, j]] / if j == 0 { scale.0 } else { scale.1 }
+        });
+
+        // [K + smoothing I, P; P^T, 0] [w; a] = [v; 0]
+        let mut matrix = Array2::<f64>::zeros((n + 3, n + 3));
+        for i in 0..n {
+            for j in 0..n {
+                let r2 = (scaled[[i, 0]] - scaled[[j, 0]]).powi(2)
+                    + (scaled[[i, 1]] - scaled[[j, 1]]).powi(2);
+                matrix[[i, j]] = kernel(r2);
+            }
+            matrix[[i, i]] += smoothing as f64;
+            let row = [1.0, scaled[[i, 0]], scaled[[i, 1]]];
+            for (k, p) in row.iter().enumerate() {
+                matrix[[i, n + k]] = *p;
+                matrix[[n + k, i]] = *p;
+            }
+        }
+        let mut rhs = values.iter().map(|v| *v as f64).collect::<Vec<f64>>();
+        rhs.extend([0.0; 3]);
+        let solution = solve_linear_system(matrix, rhs).map_err(|e| {
+            anyhow!(
+                "({}:{}) failed to fit the thin plate spline, e.g., on the duplicated points: {}",
+                file!(),
+                line!(),
+                e
+            )
+        })?;
+
+        Ok(ThinPlateSplineInterpolator {
+            points: scaled,
+            scale,
+            weights: solution[..n].to_vec(),
+            affine: (solution[n], solution[n + 1], solution[n + 2]),
+        })
+    }
+
+    /// the thin plate spline on the rectangle grid where values[[i, j]] is the value on (t_domain[i], x_domain[j])
+    pub fn new_from_rectangle_data(
+        t_domain: &Array1<Real>,
+        x_domain: &Array1<Real>,
+        values: &Array2<Real>,
+        smoothing: Real,
+    ) -> Result<ThinPlateSplineInterpolator> {
+        let (n, m) = (t_domain.len(), x_domain.len());
+        if values.shape() != [n, m] {
+            return Err(anyhow!(
+                "({}:{}) the shape of values {:?} must be (t_domain, x_domain) = ({}, {})",
+                file!(),
+                line!(),
+                values.shape(),
+                n,
+                m
+            ));
+        }
+        let points = Array2::from_shape_fn((n * m, 2), |(k, j)| {
+            if j == 0 {
+                t_domain[k / m]
+            } else {
+                x_domain[k % m]
+            }
+        });
+        let values = Array1::from_iter(values.iter().copied());
+        ThinPlateSplineInterpolator::new(points, values, smoothing)
+    }
+}
+
+impl Interpolator2D for ThinPlateSplineInterpolator {
+    fn interpolate(&self, t: Real, x: Real) -> Result<Real> {
+        let t = t as f64 / self.scale.0;
+        let x = x as f64 / self.scale.1;
+        let (a0, a1, a2) = self.affine;
+        let mut res = a0 + a1 * t + a2 * x;
+        for (point, w) in self.points.rows().into_iter().zip(self.weights.iter()) {
+            res += w * kernel((t - point[0]).powi(2) + (x - point[1]).powi(2));
+        }
+        Ok(res as Real)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_thin_plate_spline_interpolator() -> Result<()> {
+        // scattered (expiry, moneyness) quotes
+        let points = array![
+            [0.1, 0.9],
+            [0.1, 1.1],
+            [0.5, 0.8],
+            [0.5, 1.0],
+            [0.5, 1.2],
+            [1.0, 0.9],
+            [1.0, 1.05],
+            [2.0, 0.85],
+            [2.0, 1.15]
+        ];
+        let f = |t: Real, x: Real| 0.2 + 0.05 * (x - 1.0).powi(2) / (0.5 + t) - 0.01 * t;
+        let values = Array1::from_iter(points.rows().into_iter().map(|p| f(p[0], p[1])));
+        let interpolator = ThinPlateSplineInterpolator::new(points.clone(), values.clone(), 0.0)?;
+        for (p, v) in points.rows().into_iter().zip(values.iter()) {
+            assert!((interpolator.interpolate(p[0], p[1])? - v).abs() < 1.0e-5);
+        }
+        let v = interpolator.interpolate(0.75, 1.0)?;
+        assert!((v - f(0.75, 1.0)).abs() < 2.0e-3, "v: {}", v);
+
+        // the affine function is reproduced also with the smoothing
+        let affine = Array1::from_iter(points.rows().into_iter().map(|p| 1.0 + 2.0 * p[0] - p[1]));
+        let interpolator = ThinPlateSplineInterpolator::new(points.clone(), affine, 0.1)?;
+        assert!((interpolator.interpolate(3.0, 0.5)? - 6.5).abs() < 1.0e-4);
+
+        let grid = ThinPlateSplineInterpolator::new_from_rectangle_data(
+            &array![0.0, 1.0],
+            &array![0.0, 1.0, 2.0],
+            &array![[0.0, 1.0, 2.0], [1.0, 2.0, 3.0]],
+            0.0,
+        )?;
+        assert!((grid.interpolate(0.5, 1.5)? - 2.0).abs() < 1.0e-5);
+        assert!(ThinPlateSplineInterpolator::new(
+            array![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            array![0.0, 1.0, 2.0],
+            0.0
+        )
+        .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,498 @@
+use crate::definitions::Real;
+//
+use anyhow::{anyhow, Result};
+use ndarray::{s, Array1, Array2};
+
+fn check_symmetric(matrix: &Array2<Real>) -> Result<()> {
+    let n = matrix.nrows();
+    if n == 0 || n != matrix.ncols() {
+        return Err(anyhow!(
+            "({}:{}) matrix must be square and non-empty, but the shape is {:?}",
+            file!(),
+            line!(),
+            matrix.shape()
+        ));
+    }
+    let scale = matrix.iter().fold(0.0 as Real, |acc, x| acc.max(x.abs()));
+    for i in 0..n {
+        for j in 0..i {
+            if (matrix[[i, j]] - matrix[[j, i]]).abs() > 1.0e-5 * scale.max(1.0) {
+                return Err(anyhow!(
+                    "({}:{}) matrix is not symmetric at ({}, {}): {} != {}",
+                    file!(),
+                    line!(),
+                    i,
+                    j,
+                    matrix[[i, j]],
+                    matrix[[j, i]]
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// L with L L^T = matrix for the symmetric positive definite matrix, computed in f64
+pub fn cholesky(matrix: &Array2<Real>) -> Result<Array2<Real>> {
+    check_symmetric(matrix)?;
+    let l = cholesky_f64(&matrix.mapv(|v| v as f64))?;
+    Ok(l.mapv(|v| v as Real))
+}
+
+/// the Cholesky decomposition on the lower triangle of the matrix, which is assumed to be symmetric
+fn cholesky_f64(matrix: &Array2<f64>) -> Result<Array2<f64>> {
+    let n = matrix.nrows();
+    let mut l = Array2::<f64>::zeros((n, n));
+    for i in 0..n {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| l[[i, k]] * l[[j, k]]).sum();
+            if i == j {
+                let diagonal = matrix[[i, i]] - sum;
+                if diagonal <= 0.0 || !diagonal.is_finite() {
+                    return Err(anyhow!(
+                        "({}:{}) matrix is not positive definite, the pivot {} is {}",
+                        file!(),
+                        line!(),
+                        i,
+                        diagonal
+                    ));
+                }
+                l[[i, i]] = diagonal.sqrt();
+            } else {
+                l[[i, j]] = (matrix[[i, j]] - sum) / l[[j, j]];
+            }
+        }
+    }
+    Ok(l)
+}
+
+/// Gaussian elimination with partial pivoting, in f64 for the ill-conditioned systems,
+/// e.g., the par rate jacobians and the thin plate splines
+pub fn solve_linear_system(mut matrix: Array2<f64>, mut rhs: Vec<f64>) -> Result<Vec<f64>> {
+    let n = rhs.len();
+    let scale = matrix.iter().fold(0.0_f64, |acc, x| acc.max(x.abs()));
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|a, b| matrix[[*a, col]].abs().total_cmp(&matrix[[*b, col]].abs()))
+            .unwrap_or(col);
+        if matrix[[pivot, col]].abs() <= 1.0e-12 * scale {
+            return Err(anyhow!("({}:{}) singular matrix", file!(), line!()));
+        }
+        if pivot != col {
+            for j in 0..n {
+                matrix.swap([pivot, j], [col, j]);
+            }
+            rhs.swap(pivot, col);
+        }
+        for row in (col + 1)..n {
+            let factor = matrix[[row, col]] / matrix[[col, col]];
+            for j in col..n {
+                matrix[[row, j]] -= factor * matrix[[col, j]];
+            }
+            rhs[row] -= factor * rhs[col];
+        }
+    }
+    let mut res = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = ((row + 1)..n).map(|j| matrix[[row, j]] * res[j]).sum();
+        res[row] = (rhs[row] - sum) / matrix[[row, row]];
+    }
+    Ok(res)
+}
+
+/// solves matrix x = rhs for the symmetric positive definite matrix by the Cholesky decomposition in f64,
+/// e.g., the normal equations of the least squares
+pub fn solve_spd(matrix: &Array2<f64>, rhs: &[f64]) -> Result<Vec<f64>> {
+    let n = rhs.len();
+    if matrix.nrows() != n || matrix.ncols() != n {
+        return Err(anyhow!(
+            "({}:{}) the shape of the matrix {:?} does not match the length of the rhs {}",
+            file!(),
+            line!(),
+            matrix.shape(),
+            n
+        ));
+    }
+    let l = cholesky_f64(matrix)?;
+    let mut y = vec![0.0_f64; n];
+    for i in 0..n {
+        let sum: f64 = (0..i).map(|k| l[[i, k]] * y[k]).sum();
+        y[i] = (rhs[i] - sum) / l[[i, i]];
+    }
+    let mut x = vec![0.0_f64; n];
+    for i in (0..n).rev() {
+        let sum: f64 = ((i + 1)..n).map(|k| l[[k, i]] * x[k]).sum();
+        x[i] = (y[i] - sum) / l[[i, i]];
+    }
+    Ok(x)
+}
+
+/// The Cholesky decomposition with the diagonal pivoting of a positive semi-definite matrix,
+/// e.g., the covariance of the perfectly correlated risk factors.
+///
+/// factor is n x rank in the original order of the rows, so that factor factor^T = matrix,
+/// and permutation is the order of the pivots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PivotedCholesky {
+    factor: Array2<Real>,
+    permutation: Vec<usize>,
+    rank: usize,
+}
+
+impl PivotedCholesky {
+    pub fn get_factor(&self) -> &Array2<Real> {
+        &self.factor
+    }
+
+    pub fn get_permutation(&self) -> &[usize] {
+        &self.permutation
+    }
+
+    pub fn get_rank(&self) -> usize {
+        self.rank
+    }
+
+    /// the factor padded by the zero columns to n x n, in place of the Cholesky factor
+    pub fn get_square_factor(&self) -> Array2<Real> {
+        let n = self.factor.nrows();
+        let mut res = Array2::zeros((n, n));
+        res.slice_mut(s![.., ..self.rank]).assign(&self.factor);
+        res
+    }
+}
+
+/// The pivoting stops when the remaining diagonals are below tolerance * (the largest diagonal).
+/// It fails if a remaining diagonal is below -tolerance * (the largest diagonal), i.e., the matrix is indefinite.
+pub fn pivoted_cholesky(matrix: &Array2<Real>, tolerance: Real) -> Result<PivotedCholesky> {
+    check_symmetric(matrix)?;
+    let n = matrix.nrows();
+    let a = matrix.mapv(|v| v as f64);
+    let mut permutation = (0..n).collect::<Vec<usize>>();
+    let mut diagonal = (0..n).map(|i| a[[i, i]]).collect::<Vec<f64>>();
+    let threshold = tolerance as f64
+        * diagonal
+            .iter()
+            .fold(0.0_f64, |acc, d| acc.max(d.abs()))
+            .max(f64::MIN_POSITIVE);
+    let mut l = Array2::<f64>::zeros((n, n));
+    let mut rank = n;
+    for k in 0..n {
+        let pivot = (k..n)
+            .max_by(|a, b| diagonal[*a].total_cmp(&diagonal[*b]))
+            .unwrap_or(k);
+        if diagonal[pivot] <= threshold {
+            rank = k;
+            break;
+        }
+        if pivot != k {
+            permutation.swap(pivot, k);
+            diagonal.swap(pivot, k);
+            for j in 0..k {
+                l.swap([pivot, j], [k, j]);
+            }
+        }
+        l[[k, k]] = diagonal[k].sqrt();
+        for i in (k + 1)..n {
+            let sum: f64 = (0..k).map(|j| l[[i, j]] * l[[k, j]]).sum();
+            l[[i, k]] = (a[[permutation[i], permutation[k]]] - sum) / l[[k, k]];
+            diagonal[i] -= l[[i, k]] * l[[i, k]];
+        }
+    }
+    if let Some(i) = (rank..n).find(|i| diagonal[*i] < -threshold) {
+        return Err(anyhow!(
+            "({}:{}) matrix is not positive semi-definite, the remaining diagonal of {} is {}",
+            file!(),
+            line!(),
+            permutation[i],
+            diagonal[i]
+        ));
+    }
+
+    let mut factor = Array2::zeros((n, rank));
+    for i in 0..n {
+        for j in 0..rank {
+            factor[[permutation[i], j]] = l[[i, j]] as Real;
+        }
+    }
+    Ok(PivotedCholesky {
+        factor,
+        permutation,
+        rank,
+    })
+}
+
+/// The Cholesky factor, or the pivoted one padded to n x n if the matrix is only semi-definite.
+/// In both cases, factor factor^T = matrix, but the pivoted factor is not lower triangular.
+pub fn cholesky_with_fallback(matrix: &Array2<Real>) -> Result<Array2<Real>> {
+    match cholesky(matrix) {
+        Ok(l) => Ok(l),
+        Err(_) => {
+            let tolerance = 10.0 * matrix.nrows() as Real * Real::EPSILON;
+            Ok(pivoted_cholesky(matrix, tolerance)?.get_square_factor())
+        }
+    }
+}
+
+/// cyclic Jacobi rotations on the symmetric matrix in f64.
+/// The result is (eigenvalues in ascending order, eigenvectors in the columns)
+fn jacobi_eigen(mut a: Array2<f64>) -> (Vec<f64>, Array2<f64>) {
+    let n = a.nrows();
+    let mut v = Array2::<f64>::eye(n);
+    let norm: f64 = a.iter().map(|x| x * x).sum();
+    for _ in 0..100 {
+        let off: f64 = (0..n)
+            .flat_map(|p| ((p + 1)..n).map(move |q| (p, q)))
+            .map(|(p, q)| a[[p, q]] * a[[p, q]])
+            .sum();
+        if off <= 1.0e-30 * norm {
+            break;
+        }
+        for p in 0..n {
+            for q in (p + 1)..n {
+                if a[[p, q]] == 0.0 {
+                    continue;
+                }
+                let theta = (a[[q, q]] - a[[p, p]]) / (2.0 * a[[p, q]]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let (akp, akq) = (a[[k, p]], a[[k, q]]);
+                    a[[k, p]] = c * akp - s * akq;
+                    a[[k, q]] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[[p, k]], a[[q, k]]);
+                    a[[p, k]] = c * apk - s * aqk;
+                    a[[q, k]] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (v[[k, p]], v[[k, q]]);
+                    v[[k, p]] = c * vkp - s * vkq;
+                    v[[k, q]] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    let mut order = (0..n).collect::<Vec<usize>>();
+    order.sort_by(|i, j| a[[*i, *i]].total_cmp(&a[[*j, *j]]));
+    let values = order.iter().map(|i| a[[*i, *i]]).collect();
+    let vectors = Array2::from_shape_fn((n, n), |(k, j)| v[[k, order[j]]]);
+    (values, vectors)
+}
+
+/// (eigenvalues in ascending order, eigenvectors in the columns) of the symmetric matrix
+pub fn symmetric_eigen(matrix: &Array2<Real>) -> Result<(Array1<Real>, Array2<Real>)> {
+    check_symmetric(matrix)?;
+    let (values, vectors) = jacobi_eigen(matrix.mapv(|v| v as f64));
+    Ok((
+        values.into_iter().map(|v| v as Real).collect(),
+        vectors.mapv(|v| v as Real),
+    ))
+}
+
+/// * tolerance: the relative Frobenius distance between the last two projections to stop
+/// * max_iterations: the iterations of the alternating projections
+/// * min_eigenvalue: the eigenvalues are floored at this, e.g., a small positive number for a positive definite result
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearestCorrelationOptions {
+    tolerance: Real,
+    max_iterations: usize,
+    min_eigenvalue: Real,
+}
+
+impl Default for NearestCorrelationOptions {
+    fn default() -> NearestCorrelationOptions {
+        NearestCorrelationOptions {
+            tolerance: 1.0e-8,
+            max_iterations: 500,
+            min_eigenvalue: 0.0,
+        }
+    }
+}
+
+impl NearestCorrelationOptions {
+    pub fn with_tolerance(mut self, tolerance: Real) -> NearestCorrelationOptions {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> NearestCorrelationOptions {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    pub fn with_min_eigenvalue(mut self, min_eigenvalue: Real) -> NearestCorrelationOptions {
+        self.min_eigenvalue = min_eigenvalue;
+        self
+    }
+}
+
+/// The nearest correlation matrix in the Frobenius norm (Higham, 2002) of the symmetric matrix with the unit diagonal,
+/// e.g., a correlation estimated pairwise on the different histories or amended by hand, which is not positive semi-definite.
+/// The alternating projections onto the positive semi-definite matrices and the unit diagonal matrices,
+/// with Dykstra's correction on the former.
+pub fn nearest_correlation(
+    matrix: &Array2<Real>,
+    options: &NearestCorrelationOptions,
+) -> Result<Array2<Real>> {
+    check_symmetric(matrix)?;
+    let n = matrix.nrows();
+    let floor = options.min_eigenvalue as f64;
+    let project_psd = |r: &Array2<f64>| {
+        let (values, vectors) = jacobi_eigen(r.clone());
+        let values = Array1::from_iter(values.into_iter().map(|v| v.max(floor)));
+        (&vectors * &values).dot(&vectors.t())
+    };
+
+    let mut y = matrix.mapv(|v| v as f64);
+    let mut correction = Array2::<f64>::zeros((n, n));
+    for _ in 0..options.max_iterations {
+        let r = &y - &correction;
+        let x = project_psd(&r);
+        correction = &x - &r;
+        let y_next = {
+            let mut y_next = x.clone();
+            y_next.diag_mut().fill(1.0);
+            y_next
+        };
+        let distance = (&y_next - &x).mapv(|v| v * v).sum().sqrt();
+        let change = (&y_next - &y).mapv(|v| v * v).sum().sqrt();
+        let size = y_next.mapv(|v| v * v).sum().sqrt();
+        y = y_next;
+        if distance.max(change) <= options.tolerance as f64 * size {
+            // symmetrize the round-off
+            let res = (&y + &y.t()) * 0.5;
+            return Ok(res.mapv(|v| v as Real));
+        }
+    }
+    Err(anyhow!(
+        "({}:{}) the nearest correlation did not converge in {} iterations",
+        file!(),
+        line!(),
+        options.max_iterations
+    ))
+}
+
+/// The nearest correlation of the correlation of the covariance, scaled back by the standard deviations.
+/// The zero variances are kept with the zero covariances.
+pub fn nearest_covariance(
+    covariance: &Array2<Real>,
+    options: &NearestCorrelationOptions,
+) -> Result<Array2<Real>> {
+    check_symmetric(covariance)?;
+    let n = covariance.nrows();
+    if let Some(i) = (0..n).find(|i| covariance[[*i, *i]] < 0.0) {
+        return Err(anyhow!(
+            "({}:{}) negative variance {} of the risk factor {}",
+            file!(),
+            line!(),
+            covariance[[i, i]],
+            i
+        ));
+    }
+    let stdev = Array1::from_shape_fn(n, |i| covariance[[i, i]].sqrt());
+    let correlation = Array2::from_shape_fn((n, n), |(i, j)| {
+        if i == j {
+            1.0
+        } else if stdev[i] > 0.0 && stdev[j] > 0.0 {
+            covariance[[i, j]] / (stdev[i] * stdev[j])
+        } else {
+            0.0
+        }
+    });
+    let correlation = nearest_correlation(&correlation, options)?;
+    Ok(Array2::from_shape_fn((n, n), |(i, j)| {
+        correlation[[i, j]] * stdev[i] * stdev[j]
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    fn max_abs_diff(a: &Array2<Real>, b: &Array2<Real>) -> Real {
+        (a - b).iter().fold(0.0, |acc, x| acc.max(x.abs()))
+    }
+
+    #[test]
+    fn test_cholesky_with_fallback() -> Result<()> {
+        let matrix = array![[4.0, 2.0, 0.4], [2.0, 2.0, 0.5], [0.4, 0.5, 1.0]];
+        let l = cholesky(&matrix)?;
+        assert!(max_abs_diff(&l.dot(&l.t()), &matrix) < 1.0e-5);
+        assert_eq!(l[[0, 1]], 0.0);
+
+        // the second and the third factors are perfectly correlated
+        let singular = array![[1.0, 0.3, 0.6], [0.3, 1.0, 2.0], [0.6, 2.0, 4.0]];
+        assert!(cholesky(&singular).is_err());
+        let pivoted = pivoted_cholesky(&singular, 1.0e-6)?;
+        assert_eq!(pivoted.get_rank(), 2);
+        assert_eq!(pivoted.get_permutation()[0], 2);
+        let f = cholesky_with_fallback(&singular)?;
+        assert_eq!(f.shape(), &[3, 3]);
+        assert!(max_abs_diff(&f.dot(&f.t()), &singular) < 1.0e-5);
+
+        let indefinite = array![[1.0, 0.9, 0.1], [0.9, 1.0, 0.9], [0.1, 0.9, 1.0]];
+        assert!(cholesky_with_fallback(&indefinite).is_err());
+        assert!(cholesky(&array![[1.0, 0.5], [0.2, 1.0]]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_solve_spd() -> Result<()> {
+        let matrix = array![[4.0, 2.0, 0.4], [2.0, 2.0, 0.5], [0.4, 0.5, 1.0]];
+        let x = solve_spd(&matrix, &[1.0, 2.0, 3.0])?;
+        let rhs = matrix.dot(&Array1::from(x));
+        assert!(rhs.iter().zip([1.0, 2.0, 3.0]).all(|(a, b)| (a - b).abs() < 1.0e-12));
+        assert!(solve_spd(&array![[1.0, 2.0], [2.0, 1.0]], &[1.0, 1.0]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_solve_linear_system() -> Result<()> {
+        // the zero pivot at (0, 0) needs the row exchange
+        let matrix = array![[0.0, 2.0, 1.0], [1.0, 1.0, 0.0], [3.0, 0.0, 1.0]];
+        let x = solve_linear_system(matrix.clone(), vec![3.0, 2.0, 4.0])?;
+        let rhs = matrix.dot(&Array1::from(x));
+        assert!(rhs.iter().zip([3.0, 2.0, 4.0]).all(|(a, b)| (a - b).abs() < 1.0e-12));
+        assert!(solve_linear_system(array![[1.0, 2.0], [2.0, 4.0]], vec![1.0, 1.0]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_nearest_correlation() -> Result<()> {
+        let matrix = array![[2.0, -1.0, 0.0], [-1.0, 2.0, -1.0], [0.0, -1.0, 2.0]];
+        let (values, vectors) = symmetric_eigen(&matrix)?;
+        let sqrt2 = (2.0 as Real).sqrt();
+        assert!((values[0] - (2.0 - sqrt2)).abs() < 1.0e-5);
+        assert!((values[2] - (2.0 + sqrt2)).abs() < 1.0e-5);
+        let reconstructed = (&vectors * &values).dot(&vectors.t());
+        assert!(max_abs_diff(&reconstructed, &matrix) < 1.0e-5);
+
+        // the example of Higham (2002)
+        let matrix = array![[1.0, 1.0, 0.0], [1.0, 1.0, 1.0], [0.0, 1.0, 1.0]];
+        let nearest = nearest_correlation(&matrix, &NearestCorrelationOptions::default())?;
+        let expected = array![
+            [1.0, 0.7607, 0.1573],
+            [0.7607, 1.0, 0.7607],
+            [0.1573, 0.7607, 1.0]
+        ];
+        assert!(max_abs_diff(&nearest, &expected) < 1.0e-4, "{:?}", nearest);
+        let (values, _) = symmetric_eigen(&nearest)?;
+        assert!(values[0] > -1.0e-6);
+
+        // positive definite with the floor, and a correlation is kept
+        let options = NearestCorrelationOptions::default().with_min_eigenvalue(1.0e-4);
+        let l = cholesky(&nearest_correlation(&matrix, &options)?)?;
+        assert!(l[[2, 2]] > 0.0);
+        let correlation = array![[1.0, 0.5], [0.5, 1.0]];
+        let nearest = nearest_correlation(&correlation, &NearestCorrelationOptions::default())?;
+        assert!(max_abs_diff(&nearest, &correlation) < 1.0e-6);
+
+        let covariance = matrix.mapv(|v| v * 0.04);
+        let nearest = nearest_covariance(&covariance, &NearestCorrelationOptions::default())?;
+        assert!((nearest[[0, 1]] - 0.04 * 0.7607).abs() < 1.0e-5);
+        Ok(())
+    }
+}
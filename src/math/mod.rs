@@ -1,7 +1,16 @@
 pub mod interpolator;
 pub mod interpolators {
+    pub mod bicubic_interpolator;
     pub mod bilinear_interpolator;
     pub mod linear_interpolator;
     pub mod stepwise_interpolatior;
+    pub mod thin_plate_spline_interpolator;
 }
 pub mod cholescky_factorization;
+pub mod distributions;
+pub mod dual;
+pub mod linalg;
+pub mod solvers;
+pub mod optimizers;
+pub mod quadrature;
+pub mod hull_white_lattice;
@@ -0,0 +1,570 @@
+use crate::definitions::Real;
+use crate::math::linalg::solve_spd;
+//
+use anyhow::{anyhow, Result};
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+/// Why the optimizer stopped. MaxIterations and Stalled mean that the optimizer did not converge
+/// * Stalled: Levenberg-Marquardt found no decrease of the cost even with the step damped below
+///   the parameter tolerance (or with the maximum damping), e.g., on a kink or a cliff of the cost
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Termination {
+    CostTolerance,
+    ParameterTolerance,
+    GradientTolerance,
+    MaxIterations,
+    Stalled,
+}
+
+/// The result of an optimization with the convergence diagnostics
+/// * cost: the sum of the squared residuals (Levenberg-Marquardt) or the value of the cost function (Nelder-Mead)
+/// * iterations: the iterations of the optimizer
+/// * evaluations: the evaluations of the residuals or the cost function, not including the ones for the jacobian
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptimizationResult {
+    params: Vec<Real>,
+    cost: Real,
+    iterations: usize,
+    evaluations: usize,
+    termination: Termination,
+}
+
+impl OptimizationResult {
+    pub fn get_params(&self) -> &[Real] {
+        &self.params
+    }
+
+    pub fn get_cost(&self) -> Real {
+        self.cost
+    }
+
+    pub fn get_iterations(&self) -> usize {
+        self.iterations
+    }
+
+    pub fn get_evaluations(&self) -> usize {
+        self.evaluations
+    }
+
+    pub fn get_termination(&self) -> Termination {
+        self.termination
+    }
+
+    pub fn is_converged(&self) -> bool {
+        !matches!(
+            self.termination,
+            Termination::MaxIterations | Termination::Stalled
+        )
+    }
+}
+
+/// * max_iterations: the iterations of the optimizer
+/// * cost_tolerance: the relative decrease of the cost (Levenberg-Marquardt),
+///   or the standard deviation of the costs on the simplex (Nelder-Mead)
+/// * parameter_tolerance: the relative step (Levenberg-Marquardt), or the diameter of the simplex (Nelder-Mead)
+/// * gradient_tolerance: the max norm of the gradient (Levenberg-Marquardt)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptimizerOptions {
+    max_iterations: usize,
+    cost_tolerance: Real,
+    parameter_tolerance: Real,
+    gradient_tolerance: Real,
+}
+
+impl Default for OptimizerOptions {
+    fn default() -> OptimizerOptions {
+        OptimizerOptions {
+            max_iterations: 1_000,
+            cost_tolerance: 1.0e-10,
+            parameter_tolerance: 1.0e-10,
+            gradient_tolerance: 1.0e-12,
+        }
+    }
+}
+
+impl OptimizerOptions {
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> OptimizerOptions {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    pub fn with_cost_tolerance(mut self, cost_tolerance: Real) -> OptimizerOptions {
+        self.cost_tolerance = cost_tolerance;
+        self
+    }
+
+    pub fn with_parameter_tolerance(mut self, parameter_tolerance: Real) -> OptimizerOptions {
+        self.parameter_tolerance = parameter_tolerance;
+        self
+    }
+
+    pub fn with_gradient_tolerance(mut self, gradient_tolerance: Real) -> OptimizerOptions {
+        self.gradient_tolerance = gradient_tolerance;
+        self
+    }
+
+    pub fn get_max_iterations(&self) -> usize {
+        self.max_iterations
+    }
+}
+
+/// lower <= x <= upper for each parameter, and the infinities for the unbounded ones.
+/// The trial points of the optimizers are projected onto the box.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoxConstraints {
+    lower: Vec<Real>,
+    upper: Vec<Real>,
+}
+
+impl BoxConstraints {
+    pub fn new(lower: Vec<Real>, upper: Vec<Real>) -> Result<BoxConstraints> {
+        if lower.len() != upper.len() || lower.iter().zip(upper.iter()).any(|(l, u)| l > u) {
+            return Err(anyhow!(
+                "({}:{}) invalid box constraints, lower: {:?}, upper: {:?}",
+                file!(),
+                line!(),
+                lower,
+                upper
+            ));
+        }
+        Ok(BoxConstraints { lower, upper })
+    }
+
+    pub fn unbounded(dimension: usize) -> BoxConstraints {
+        BoxConstraints {
+            lower: vec![Real::NEG_INFINITY; dimension],
+            upper: vec![Real::INFINITY; dimension],
+        }
+    }
+
+    /// bound the i-th parameter
+    pub fn with_bound(mut self, i: usize, lower: Real, upper: Real) -> Result<BoxConstraints> {
+        if i >= self.lower.len() || lower > upper {
+            return Err(anyhow!(
+                "({}:{}) invalid bound [{}, {}] of the parameter {} in the box constraints of {} parameters",
+                file!(),
+                line!(),
+                lower,
+                upper,
+                i,
+                self.lower.len()
+            ));
+        }
+        self.lower[i] = lower;
+        self.upper[i] = upper;
+        Ok(self)
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.lower.len()
+    }
+
+    pub fn project(&self, x: &mut [Real]) {
+        for (i, xi) in x.iter_mut().enumerate() {
+            *xi = xi.clamp(self.lower[i], self.upper[i]);
+        }
+    }
+
+    fn check_dimension(&self, dimension: usize) -> Result<()> {
+        if self.dimension() != dimension {
+            return Err(anyhow!(
+                "({}:{}) the box constraints have {} parameters, but {} are given",
+                file!(),
+                line!(),
+                self.dimension(),
+                dimension
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn sum_of_squares(residuals: &[Real]) -> f64 {
+    residuals.iter().map(|r| (*r as f64).powi(2)).sum()
+}
+
+const LAMBDA_INIT: f64 = 1.0e-3;
+const LAMBDA_MAX: f64 = 1.0e16;
+
+/// Levenberg-Marquardt minimization of the sum of the squared residuals with the forward difference jacobian.
+/// The steps are projected onto the box constraints if given, e.g., the positive volatilities of SABR
+/// or the bounded decays of Nelson-Siegel.
+pub fn levenberg_marquardt<F>(
+    residuals: &mut F,
+    init: &[Real],
+    constraints: Option<&BoxConstraints>,
+    options: &OptimizerOptions,
+) -> Result<OptimizationResult>
+where
+    F: FnMut(&[Real]) -> Result<Vec<Real>>,
+{
+    let n = init.len();
+    if let Some(constraints) = constraints {
+        constraints.check_dimension(n)?;
+    }
+    let project = |x: &mut [Real]| {
+        if let Some(constraints) = constraints {
+            constraints.project(x);
+        }
+    };
+
+    let mut x = init.to_vec();
+    project(&mut x);
+    let mut r = residuals(&x)?;
+    let mut cost = sum_of_squares(&r);
+    let mut evaluations = 1;
+    let mut lambda = LAMBDA_INIT;
+    let h_scale = Real::EPSILON.sqrt();
+
+    let result = |x: Vec<Real>, cost: f64, iterations, evaluations, termination| {
+        Ok(OptimizationResult {
+            params: x,
+            cost: cost as Real,
+            iterations,
+            evaluations,
+            termination,
+        })
+    };
+
+    for iteration in 1..=options.max_iterations {
+        // forward difference jacobian, backward at the upper bound
+        let mut jacobian = vec![vec![0.0_f64; n]; r.len()];
+        for j in 0..n {
+            let mut h = h_scale * x[j].abs().max(1.0);
+            if let Some(constraints) = constraints {
+                if x[j] + h > constraints.upper[j] {
+                    h = -h;
+                }
+            }
+            let mut x_h = x.clone();
+            x_h[j] += h;
+            let r_h = residuals(&x_h)?;
+            if r_h.len() != r.len() {
+                return Err(anyhow!(
+                    "({}:{}) the number of the residuals changed from {} to {}",
+                    file!(),
+                    line!(),
+                    r.len(),
+                    r_h.len()
+                ));
+            }
+            let h = (x_h[j] - x[j]) as f64;
+            for (i, row) in jacobian.iter_mut().enumerate() {
+                row[j] = (r_h[i] - r[i]) as f64 / h;
+            }
+        }
+
+        // the normal equations: (J^T J + lambda diag(J^T J)) step = -J^T r
+        let mut jtj = Array2::<f64>::zeros((n, n));
+        let mut gradient = vec![0.0_f64; n];
+        for (row, ri) in jacobian.iter().zip(r.iter()) {
+            for a in 0..n {
+                gradient[a] += row[a] * *ri as f64;
+                for b in 0..n {
+                    jtj[[a, b]] += row[a] * row[b];
+                }
+            }
+        }
+        if gradient
+            .iter()
+            .all(|g| g.abs() <= options.gradient_tolerance as f64)
+        {
+            return result(
+                x,
+                cost,
+                iteration,
+                evaluations,
+                Termination::GradientTolerance,
+            );
+        }
+
+        // the decrease of the cost by the Gauss-Newton step g^T (J^T J)^-1 g, which is small only near a minimum,
+        // unlike the decrease of a step shortened by the damping
+        let predicted_decrease = solve_spd(&jtj, &gradient)
+            .map(|s| s.iter().zip(gradient.iter()).map(|(a, b)| a * b).sum::<f64>())
+            .unwrap_or(f64::INFINITY);
+
+        loop {
+            let mut damped = jtj.clone();
+            for a in 0..n {
+                damped[[a, a]] += lambda * jtj[[a, a]].max(1.0e-12);
+            }
+            let rhs = gradient.iter().map(|g| -g).collect::<Vec<f64>>();
+            let step = match solve_spd(&damped, &rhs) {
+                Ok(step) => step,
+                Err(_) => {
+                    lambda *= 10.0;
+                    if lambda > LAMBDA_MAX {
+                        return result(x, cost, iteration, evaluations, Termination::Stalled);
+                    }
+                    continue;
+                }
+            };
+            let mut x_new = x
+                .iter()
+                .zip(step.iter())
+                .map(|(xi, si)| xi + *si as Real)
+                .collect::<Vec<Real>>();
+            project(&mut x_new);
+
+            let step_norm: Real = x_new
+                .iter()
+                .zip(x.iter())
+                .map(|(a, b)| (a - b).powi(2))
+                .sum::<Real>()
+                .sqrt();
+            let x_norm: Real = x.iter().map(|xi| xi.powi(2)).sum::<Real>().sqrt();
+            let is_small_step =
+                step_norm <= options.parameter_tolerance * (x_norm + options.parameter_tolerance);
+
+            let r_new = residuals(&x_new)?;
+            evaluations += 1;
+            let cost_new = sum_of_squares(&r_new);
+            if cost_new.is_finite() && cost_new < cost {
+                let decrease = cost - cost_new;
+                (x, r, cost) = (x_new, r_new, cost_new);
+                lambda = (lambda / 10.0).max(1.0e-12);
+                let cost_tolerance = options.cost_tolerance as f64 * cost;
+                if decrease <= cost_tolerance && predicted_decrease <= cost_tolerance {
+                    return result(x, cost, iteration, evaluations, Termination::CostTolerance);
+                }
+                if is_small_step {
+                    return result(
+                        x,
+                        cost,
+                        iteration,
+                        evaluations,
+                        Termination::ParameterTolerance,
+                    );
+                }
+                break;
+            }
+            // the damping only shortens the step further, so no decrease is found near x
+            lambda *= 10.0;
+            if is_small_step || lambda > LAMBDA_MAX {
+                return result(x, cost, iteration, evaluations, Termination::Stalled);
+            }
+        }
+    }
+    result(
+        x,
+        cost,
+        options.max_iterations,
+        evaluations,
+        Termination::MaxIterations,
+    )
+}
+
+/// Nelder-Mead minimization from the simplex of the init and the init moved by each step,
+/// where the trial points are projected onto the box constraints if given.
+pub fn nelder_mead<F>(
+    cost: &mut F,
+    init: &[Real],
+    steps: &[Real],
+    constraints: Option<&BoxConstraints>,
+    options: &OptimizerOptions,
+) -> Result<OptimizationResult>
+where
+    F: FnMut(&[Real]) -> Result<Real>,
+{
+    let n = init.len();
+    if steps.len() != n {
+        return Err(anyhow!(
+            "({}:{}) the steps ({}) and the init ({}) must have the same length",
+            file!(),
+            line!(),
+            steps.len(),
+            n
+        ));
+    }
+    if let Some(constraints) = constraints {
+        constraints.check_dimension(n)?;
+    }
+    let project = |mut x: Vec<Real>| {
+        if let Some(constraints) = constraints {
+            constraints.project(&mut x);
+        }
+        x
+    };
+
+    let mut simplex = vec![project(init.to_vec())];
+    for (i, step) in steps.iter().enumerate() {
+        let mut vertex = init.to_vec();
+        vertex[i] += step;
+        simplex.push(project(vertex));
+    }
+    let mut values = simplex
+        .iter()
+        .map(|x| cost(x))
+        .collect::<Result<Vec<Real>>>()?;
+    let mut evaluations = n + 1;
+
+    let mut termination = Termination::MaxIterations;
+    let mut iterations = options.max_iterations;
+    for iteration in 1..=options.max_iterations {
+        let mut order = (0..=n).collect::<Vec<usize>>();
+        order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+        simplex = order.iter().map(|i| simplex[*i].clone()).collect();
+        values = order.iter().map(|i| values[*i]).collect();
+
+        let mean = values.iter().sum::<Real>() / (n + 1) as Real;
+        let sd = (values.iter().map(|v| (v - mean).powi(2)).sum::<Real>() / (n + 1) as Real).sqrt();
+        if sd <= options.cost_tolerance {
+            (termination, iterations) = (Termination::CostTolerance, iteration);
+            break;
+        }
+        let diameter = simplex[1..]
+            .iter()
+            .map(|x| {
+                x.iter()
+                    .zip(simplex[0].iter())
+                    .map(|(a, b)| (a - b).abs())
+                    .fold(0.0, Real::max)
+            })
+            .fold(0.0, Real::max);
+        if diameter <= options.parameter_tolerance {
+            (termination, iterations) = (Termination::ParameterTolerance, iteration);
+            break;
+        }
+
+        let centroid = (0..n)
+            .map(|j| simplex[..n].iter().map(|x| x[j]).sum::<Real>() / n as Real)
+            .collect::<Vec<Real>>();
+        let towards = |coefficient: Real| {
+            project(
+                centroid
+                    .iter()
+                    .zip(simplex[n].iter())
+                    .map(|(c, w)| c + coefficient * (c - w))
+                    .collect(),
+            )
+        };
+
+        let reflected = towards(1.0);
+        let f_reflected = cost(&reflected)?;
+        evaluations += 1;
+        if f_reflected < values[0] {
+            let expanded = towards(2.0);
+            let f_expanded = cost(&expanded)?;
+            evaluations += 1;
+            if f_expanded < f_reflected {
+                (simplex[n], values[n]) = (expanded, f_expanded);
+            } else {
+                (simplex[n], values[n]) = (reflected, f_reflected);
+            }
+            continue;
+        }
+        if f_reflected < values[n - 1] {
+            (simplex[n], values[n]) = (reflected, f_reflected);
+            continue;
+        }
+        // outside contraction if the reflected is better than the worst, otherwise inside contraction
+        let (coefficient, threshold) = if f_reflected < values[n] {
+            (0.5, f_reflected)
+        } else {
+            (-0.5, values[n])
+        };
+        let contracted = towards(coefficient);
+        let f_contracted = cost(&contracted)?;
+        evaluations += 1;
+        if f_contracted < threshold {
+            (simplex[n], values[n]) = (contracted, f_contracted);
+            continue;
+        }
+        // shrink towards the best
+        for i in 1..=n {
+            simplex[i] = project(
+                simplex[i]
+                    .iter()
+                    .zip(simplex[0].iter())
+                    .map(|(x, best)| best + 0.5 * (x - best))
+                    .collect(),
+            );
+            values[i] = cost(&simplex[i])?;
+        }
+        evaluations += n;
+    }
+
+    let best = (0..=n)
+        .min_by(|a, b| values[*a].total_cmp(&values[*b]))
+        .unwrap_or(0);
+    Ok(OptimizationResult {
+        params: simplex[best].clone(),
+        cost: values[best],
+        iterations,
+        evaluations,
+        termination,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optimizers() -> Result<()> {
+        // exponential decay y = a exp(-b t) with the decay bounded in [0.1, 0.4]
+        let times = [0.0, 0.5, 1.0, 2.0, 3.0, 5.0];
+        let mut residuals = |x: &[Real]| {
+            Ok(times
+                .iter()
+                .map(|t| x[0] * (-x[1] * t).exp() - 2.0 * (-0.3 * t).exp())
+                .collect::<Vec<Real>>())
+        };
+        let options = OptimizerOptions::default();
+        let res = levenberg_marquardt(&mut residuals, &[1.0, 1.0], None, &options)?;
+        assert!(res.is_converged());
+        assert!(
+            (res.get_params()[0] - 2.0).abs() < 1.0e-4
+                && (res.get_params()[1] - 0.3).abs() < 1.0e-4
+        );
+
+        let constraints = BoxConstraints::unbounded(2).with_bound(1, 0.1, 0.25)?;
+        let res = levenberg_marquardt(&mut residuals, &[1.0, 1.0], Some(&constraints), &options)?;
+        assert!((res.get_params()[1] - 0.25).abs() < 1.0e-6);
+        assert!(res.get_cost() > 0.0);
+
+        // no descent from the kink of |x| + 1
+        let mut kink = |x: &[Real]| Ok(vec![x[0].abs() + 1.0]);
+        let res = levenberg_marquardt(&mut kink, &[0.0], None, &options)?;
+        assert_eq!(res.get_termination(), Termination::Stalled);
+        assert!(!res.is_converged());
+
+        // the cliff at 0 stops the descent on the non-zero gradient
+        let mut cliff = |x: &[Real]| Ok(vec![if x[0] >= 0.0 { x[0] + 1.0 } else { 10.0 }]);
+        let res = levenberg_marquardt(&mut cliff, &[0.0], None, &options)?;
+        assert_eq!(res.get_termination(), Termination::Stalled);
+        assert!(!res.is_converged() && res.get_params()[0] == 0.0);
+
+        let mut rosenbrock =
+            |x: &[Real]| Ok((1.0 - x[0]).powi(2) + 100.0 * (x[1] - x[0] * x[0]).powi(2));
+        let res = nelder_mead(&mut rosenbrock, &[-1.2, 1.0], &[0.5, 0.5], None, &options)?;
+        assert!(res.is_converged(), "{:?}", res);
+        assert!(
+            (res.get_params()[0] - 1.0).abs() < 1.0e-2
+                && (res.get_params()[1] - 1.0).abs() < 1.0e-2
+        );
+
+        let constraints = BoxConstraints::new(vec![-2.0, -2.0], vec![0.5, 2.0])?;
+        let res = nelder_mead(
+            &mut rosenbrock,
+            &[-1.2, 1.0],
+            &[0.5, 0.5],
+            Some(&constraints),
+            &options,
+        )?;
+        assert!(res.get_params()[0] <= 0.5 && (res.get_params()[0] - 0.5).abs() < 1.0e-2);
+
+        let res = nelder_mead(
+            &mut rosenbrock,
+            &[-1.2, 1.0],
+            &[0.5, 0.5],
+            None,
+            &options.with_max_iterations(5),
+        )?;
+        assert_eq!(res.get_termination(), Termination::MaxIterations);
+        assert!(BoxConstraints::new(vec![1.0], vec![0.0]).is_err());
+        Ok(())
+    }
+}
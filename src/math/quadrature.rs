@@ -0,0 +1,397 @@
+use crate::definitions::Real;
+//
+use anyhow::{anyhow, Result};
+use std::f64::consts::PI;
+
+const MAX_NODES: usize = 1024;
+const NEWTON_TOLERANCE: f64 = 1.0e-14;
+const NEWTON_MAX_ITERATIONS: usize = 100;
+
+fn check_nodes(n: usize) -> Result<()> {
+    if n == 0 || n > MAX_NODES {
+        return Err(anyhow!(
+            "({}:{}) the number of the nodes must be in [1, {}], but {} is given",
+            file!(),
+            line!(),
+            MAX_NODES,
+            n
+        ));
+    }
+    Ok(())
+}
+
+/// Gauss-Legendre quadrature on [lower, upper], exact for the polynomials of degree < 2n,
+/// e.g., the integrals over the strikes in the CMS replication
+/// or over the frequencies in the Heston semi-analytic pricing after the truncation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GaussLegendre {
+    // on [-1, 1] in ascending order
+    nodes: Vec<f64>,
+    weights: Vec<f64>,
+}
+
+impl GaussLegendre {
+    pub fn new(n: usize) -> Result<GaussLegendre> {
+        check_nodes(n)?;
+        let mut nodes = vec![0.0; n];
+        let mut weights = vec![0.0; n];
+        // the roots of the Legendre polynomial P_n by Newton's method from the Chebyshev-like guesses
+        for i in 0..n.div_ceil(2) {
+            let mut z = (PI * (i as f64 + 0.75) / (n as f64 + 0.5)).cos();
+            let mut derivative = 0.0;
+            for _ in 0..NEWTON_MAX_ITERATIONS {
+                let (mut p1, mut p2) = (1.0, 0.0);
+                for j in 0..n {
+                    let p3 = p2;
+                    p2 = p1;
+                    p1 = ((2 * j + 1) as f64 * z * p2 - j as f64 * p3) / (j + 1) as f64;
+                }
+                derivative = n as f64 * (z * p1 - p2) / (z * z - 1.0);
+                let step = p1 / derivative;
+                z -= step;
+                if step.abs() <= NEWTON_TOLERANCE {
+                    break;
+                }
+            }
+            nodes[i] = -z;
+            nodes[n - 1 - i] = z;
+            weights[i] = 2.0 / ((1.0 - z * z) * derivative * derivative);
+            weights[n - 1 - i] = weights[i];
+        }
+        Ok(GaussLegendre { nodes, weights })
+    }
+
+    pub fn get_nodes_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// (the nodes, the weights) on [-1, 1] in f64, e.g., for the integrands in f64
+    pub fn get_nodes_and_weights(&self) -> (&[f64], &[f64]) {
+        (&self.nodes, &self.weights)
+    }
+
+    /// ∫_lower^upper f(x) dx
+    pub fn integrate<F>(&self, f: &mut F, lower: Real, upper: Real) -> Result<Real>
+    where
+        F: FnMut(Real) -> Result<Real>,
+    {
+        let half = 0.5 * (upper as f64 - lower as f64);
+        let mid = 0.5 * (upper as f64 + lower as f64);
+        let mut res = 0.0;
+        for (x, w) in self.nodes.iter().zip(self.weights.iter()) {
+            res += w * f((mid + half * x) as Real)? as f64;
+        }
+        Ok((res * half) as Real)
+    }
+}
+
+/// The implicit QL iterations on the symmetric tridiagonal matrix.
+/// The result is (the eigenvalues, the first components of the normalized eigenvectors)
+fn tridiagonal_eigen(mut d: Vec<f64>, off_diagonal: Vec<f64>) -> Result<(Vec<f64>, Vec<f64>)> {
+    let n = d.len();
+    // e[i] couples i and i + 1
+    let mut e = off_diagonal;
+    e.push(0.0);
+    let mut z = vec![0.0; n];
+    z[0] = 1.0;
+    for l in 0..n {
+        let mut iterations = 0;
+        loop {
+            let mut m = l;
+            while m + 1 < n {
+                let dd = d[m].abs() + d[m + 1].abs();
+                if e[m].abs() <= f64::EPSILON * dd {
+                    break;
+                }
+                m += 1;
+            }
+            if m == l {
+                break;
+            }
+            iterations += 1;
+            if iterations > 60 {
+                return Err(anyhow!(
+                    "({}:{}) the QL iterations did not converge",
+                    file!(),
+                    line!()
+                ));
+            }
+            let mut g = (d[l + 1] - d[l]) / (2.0 * e[l]);
+            let r = g.hypot(1.0);
+            g = d[m] - d[l] + e[l] / (g + r.copysign(g));
+            let (mut s, mut c, mut p) = (1.0, 1.0, 0.0);
+            let mut deflated = false;
+            for i in (l..m).rev() {
+                let f = s * e[i];
+                let b = c * e[i];
+                let r = f.hypot(g);
+                e[i + 1] = r;
+                if r == 0.0 {
+                    d[i + 1] -= p;
+                    e[m] = 0.0;
+                    deflated = true;
+                    break;
+                }
+                s = f / r;
+                c = g / r;
+                g = d[i + 1] - p;
+                let r = (d[i] - g) * s + 2.0 * c * b;
+                p = s * r;
+                d[i + 1] = g + p;
+                g = c * r - b;
+                let zi1 = z[i + 1];
+                z[i + 1] = s * z[i] + c * zi1;
+                z[i] = c * z[i] - s * zi1;
+            }
+            if deflated {
+                continue;
+            }
+            d[l] -= p;
+            e[l] = g;
+            e[m] = 0.0;
+        }
+    }
+    Ok((d, z))
+}
+
+/// Gauss-Hermite quadrature of ∫ f(x) exp(-x^2) dx, exact for f of the polynomials of degree < 2n,
+/// e.g., the expectations over a normal factor in the quanto adjustments and the stochastic volatility.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GaussHermite {
+    // in ascending order
+    nodes: Vec<f64>,
+    weights: Vec<f64>,
+}
+
+impl GaussHermite {
+    pub fn new(n: usize) -> Result<GaussHermite> {
+        check_nodes(n)?;
+        // Golub-Welsch: the nodes are the eigenvalues of the Jacobi matrix of the Hermite polynomials,
+        // which is robust for the large n unlike Newton's method from the asymptotic guesses
+        let diagonal = vec![0.0; n];
+        let off_diagonal = (1..n)
+            .map(|k| (0.5 * k as f64).sqrt())
+            .collect::<Vec<f64>>();
+        let (values, first_components) = tridiagonal_eigen(diagonal, off_diagonal)?;
+        let mut pairs = values
+            .into_iter()
+            .zip(first_components)
+            .map(|(x, v)| (x, PI.sqrt() * v * v))
+            .collect::<Vec<(f64, f64)>>();
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (nodes, weights) = pairs.into_iter().unzip();
+        Ok(GaussHermite { nodes, weights })
+    }
+
+    pub fn get_nodes_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// ∫ f(x) exp(-x^2) dx
+    pub fn integrate<F>(&self, f: &mut F) -> Result<Real>
+    where
+        F: FnMut(Real) -> Result<Real>,
+    {
+        let mut res = 0.0;
+        for (x, w) in self.nodes.iter().zip(self.weights.iter()) {
+            res += w * f(*x as Real)? as f64;
+        }
+        Ok(res as Real)
+    }
+
+    /// E[f(Z)] for the standard normal Z
+    pub fn normal_expectation<F>(&self, f: &mut F) -> Result<Real>
+    where
+        F: FnMut(Real) -> Result<Real>,
+    {
+        let mut res = 0.0;
+        for (x, w) in self.nodes.iter().zip(self.weights.iter()) {
+            res += w * f((std::f64::consts::SQRT_2 * x) as Real)? as f64;
+        }
+        Ok((res / PI.sqrt()) as Real)
+    }
+
+    /// (z_i, w_i) with E[f(Z)] ≈ Σ w_i f(z_i) for the standard normal Z
+    pub fn get_normal_nodes(&self) -> Vec<(Real, Real)> {
+        self.nodes
+            .iter()
+            .zip(self.weights.iter())
+            .map(|(x, w)| ((std::f64::consts::SQRT_2 * x) as Real, (w / PI.sqrt()) as Real))
+            .collect()
+    }
+}
+
+/// The nodes are doubled from min_nodes until two successive estimates differ by less than
+/// tolerance * max(1, |estimate|), so that the callers give the accuracy instead of the nodes.
+/// The last doubling is clamped to max_nodes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuadratureOptions {
+    tolerance: Real,
+    min_nodes: usize,
+    max_nodes: usize,
+}
+
+impl Default for QuadratureOptions {
+    fn default() -> QuadratureOptions {
+        QuadratureOptions {
+            tolerance: 1.0e-8,
+            min_nodes: 8,
+            max_nodes: 256,
+        }
+    }
+}
+
+impl QuadratureOptions {
+    pub fn with_tolerance(mut self, tolerance: Real) -> QuadratureOptions {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_min_nodes(mut self, min_nodes: usize) -> QuadratureOptions {
+        self.min_nodes = min_nodes;
+        self
+    }
+
+    pub fn with_max_nodes(mut self, max_nodes: usize) -> QuadratureOptions {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    pub fn get_tolerance(&self) -> Real {
+        self.tolerance
+    }
+
+    /// (the estimate, the nodes of the estimate)
+    fn refine<G>(&self, mut estimate: G) -> Result<(Real, usize)>
+    where
+        G: FnMut(usize) -> Result<Real>,
+    {
+        check_nodes(self.min_nodes)?;
+        check_nodes(self.max_nodes)?;
+        if self.min_nodes >= self.max_nodes {
+            return Err(anyhow!(
+                "({}:{}) min_nodes ({}) must be less than max_nodes ({}) to compare two estimates",
+                file!(),
+                line!(),
+                self.min_nodes,
+                self.max_nodes
+            ));
+        }
+        let mut n = self.min_nodes;
+        let mut previous = estimate(n)?;
+        while n < self.max_nodes {
+            n = (2 * n).min(self.max_nodes);
+            let current = estimate(n)?;
+            if (current - previous).abs() <= self.tolerance * current.abs().max(1.0) {
+                return Ok((current, n));
+            }
+            previous = current;
+        }
+        Err(anyhow!(
+            "({}:{}) the quadrature did not reach the tolerance {} with {} nodes, the last estimate is {}",
+            file!(),
+            line!(),
+            self.tolerance,
+            n,
+            previous
+        ))
+    }
+}
+
+/// ∫_lower^upper f(x) dx by Gauss-Legendre to the tolerance of the options. The result is (the integral, the nodes)
+pub fn integrate_legendre<F>(
+    f: &mut F,
+    lower: Real,
+    upper: Real,
+    options: &QuadratureOptions,
+) -> Result<(Real, usize)>
+where
+    F: FnMut(Real) -> Result<Real>,
+{
+    options.refine(|n| GaussLegendre::new(n)?.integrate(f, lower, upper))
+}
+
+/// E[f(Z)] for the standard normal Z by Gauss-Hermite to the tolerance of the options. The result is (the expectation, the nodes)
+pub fn normal_expectation<F>(f: &mut F, options: &QuadratureOptions) -> Result<(Real, usize)>
+where
+    F: FnMut(Real) -> Result<Real>,
+{
+    options.refine(|n| GaussHermite::new(n)?.normal_expectation(f))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use statrs::distribution::{ContinuousCDF, Normal};
+
+    #[test]
+    fn test_gauss_legendre() -> Result<()> {
+        let legendre = GaussLegendre::new(5)?;
+        // exact for the degree 9
+        let res = legendre.integrate(&mut |x| Ok(x.powi(9) + x.powi(4)), -1.0, 2.0)?;
+        assert!(
+            (res - (1023.0 / 10.0 + 33.0 / 5.0)).abs() < 1.0e-4,
+            "res: {}",
+            res
+        );
+        let res = legendre.integrate(&mut |x| Ok(x.exp()), 0.0, 1.0)?;
+        assert!((res - (1.0 as Real).exp_m1()).abs() < 1.0e-6);
+
+        let options = QuadratureOptions::default().with_tolerance(1.0e-6);
+        let (res, nodes) =
+            integrate_legendre(&mut |x| Ok(1.0 / (1.0 + x * x)), 0.0, 10.0, &options)?;
+        assert!((res - (10.0 as Real).atan()).abs() < 1.0e-5 && nodes >= 16);
+        assert!(integrate_legendre(
+            &mut |x| Ok(x.abs().sqrt()),
+            -1.0,
+            1.0,
+            &options.with_max_nodes(16)
+        )
+        .is_err());
+        assert!(GaussLegendre::new(0).is_err());
+
+        // the doubling from 10 is clamped to 15 nodes
+        let options = options.with_min_nodes(10).with_max_nodes(15);
+        let (res, nodes) = integrate_legendre(&mut |x| Ok(x.exp()), 0.0, 1.0, &options)?;
+        assert!((res - (1.0 as Real).exp_m1()).abs() < 1.0e-6 && nodes == 15);
+        assert!(integrate_legendre(&mut |x| Ok(x), 0.0, 1.0, &options.with_min_nodes(15)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_gauss_hermite() -> Result<()> {
+        let hermite = GaussHermite::new(10)?;
+        assert!(
+            (hermite.integrate(&mut |_| Ok(1.0))? - (std::f64::consts::PI.sqrt() as Real)).abs()
+                < 1.0e-6
+        );
+        assert!((hermite.normal_expectation(&mut |z| Ok(z * z))? - 1.0).abs() < 1.0e-6);
+        assert!((hermite.normal_expectation(&mut |z| Ok(z.powi(4)))? - 3.0).abs() < 1.0e-5);
+        let nodes = GaussHermite::new(5)?.get_normal_nodes();
+        assert!((nodes[2].1 - 8.0 / 15.0).abs() < 1.0e-6 && (nodes[4].0 - 2.856_97).abs() < 1.0e-4);
+
+        // the Black-Scholes call as E[max(S exp(σ√t Z - σ²t/2) - K, 0)]
+        let (s, k, sigma_sqrt_t): (Real, Real, Real) = (100.0, 95.0, 0.2);
+        let normal = Normal::new(0.0, 1.0)?;
+        let d1 = ((s / k).ln() as f64 + 0.5 * (sigma_sqrt_t as f64).powi(2)) / sigma_sqrt_t as f64;
+        let d2 = d1 - sigma_sqrt_t as f64;
+        let expected = s as f64 * normal.cdf(d1) - k as f64 * normal.cdf(d2);
+        let mut payoff = |z: Real| {
+            Ok((s * (sigma_sqrt_t * z - 0.5 * sigma_sqrt_t * sigma_sqrt_t).exp() - k).max(0.0))
+        };
+        // the kink of the payoff converges slowly in the nodes
+        let res = GaussHermite::new(256)?.normal_expectation(&mut payoff)?;
+        assert!(
+            (res as f64 - expected).abs() < 1.0e-2,
+            "res: {}, expected: {}",
+            res,
+            expected
+        );
+
+        // the smooth integrand, e.g., the lognormal moment E[exp(a Z)] = exp(a² / 2)
+        let options = QuadratureOptions::default().with_tolerance(1.0e-6);
+        let (res, nodes) = normal_expectation(&mut |z| Ok((0.5 * z).exp()), &options)?;
+        assert!((res - (0.125 as Real).exp()).abs() < 1.0e-5 && nodes == 16);
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+use crate::definitions::Real;
+//
+use anyhow::{anyhow, Result};
+
+/// * tolerance: the root is accepted when the bracket (or the step) is smaller than this
+/// * max_iterations: the iterations of the solver
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolverOptions {
+    tolerance: Real,
+    max_iterations: usize,
+}
+
+impl Default for SolverOptions {
+    fn default() -> SolverOptions {
+        SolverOptions {
+            tolerance: 1.0e-10,
+            max_iterations: 100,
+        }
+    }
+}
+
+impl SolverOptions {
+    pub fn with_tolerance(mut self, tolerance: Real) -> SolverOptions {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> SolverOptions {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    pub fn get_tolerance(&self) -> Real {
+        self.tolerance
+    }
+
+    pub fn get_max_iterations(&self) -> usize {
+        self.max_iterations
+    }
+}
+
+const BRACKET_EXPANSION: Real = 1.6;
+const BRACKET_MAX_ITERATIONS: usize = 60;
+
+/// Expand [lower, upper] geometrically until f changes its sign, staying in the domain, e.g.,
+/// (-frequency, inf) for the yields compounded frequency times a year.
+/// The result is (lower, upper, f(lower), f(upper)).
+pub fn expand_bracket<F>(
+    f: &mut F,
+    lower: Real,
+    upper: Real,
+    domain: (Real, Real),
+) -> Result<(Real, Real, Real, Real)>
+where
+    F: FnMut(Real) -> Result<Real>,
+{
+    if lower >= upper || lower < domain.0 || upper > domain.1 {
+        return Err(anyhow!(
+            "({}:{}) invalid bracket [{}, {}] in the domain ({}, {})",
+            file!(),
+            line!(),
+            lower,
+            upper,
+            domain.0,
+            domain.1
+        ));
+    }
+    let (mut lower, mut upper) = (lower, upper);
+    let (mut f_lower, mut f_upper) = (f(lower)?, f(upper)?);
+    for _ in 0..BRACKET_MAX_ITERATIONS {
+        if f_lower * f_upper <= 0.0 {
+            return Ok((lower, upper, f_lower, f_upper));
+        }
+        let width = upper - lower;
+        // expand the side of the smaller residual which is likely to be closer to the root
+        if f_lower.abs() < f_upper.abs() && lower > domain.0 {
+            lower = (lower - BRACKET_EXPANSION * width).max(0.5 * (lower + domain.0));
+            f_lower = f(lower)?;
+        } else if upper < domain.1 {
+            upper = (upper + BRACKET_EXPANSION * width).min(domain.1);
+            f_upper = f(upper)?;
+        } else if lower > domain.0 {
+            lower = (lower - BRACKET_EXPANSION * width).max(0.5 * (lower + domain.0));
+            f_lower = f(lower)?;
+        } else {
+            break;
+        }
+    }
+    Err(anyhow!(
+        "({}:{}) failed to bracket a root, the last bracket is [{}, {}] with the values ({}, {})",
+        file!(),
+        line!(),
+        lower,
+        upper,
+        f_lower,
+        f_upper
+    ))
+}
+
+/// Brent's method (inverse quadratic interpolation, secant and bisection) on the bracket [lower, upper]
+/// where f(lower) and f(upper) have the opposite signs
+pub fn brent<F>(f: &mut F, lower: Real, upper: Real, options: &SolverOptions) -> Result<Real>
+where
+    F: FnMut(Real) -> Result<Real>,
+{
+    let (mut a, mut b) = (lower, upper);
+    let (mut fa, mut fb) = (f(a)?, f(b)?);
+    if fa == 0.0 {
+        return Ok(a);
+    }
+    if fb == 0.0 {
+        return Ok(b);
+    }
+    if fa * fb > 0.0 {
+        return Err(anyhow!(
+            "({}:{}) the root is not bracketed in [{}, {}], f = ({}, {})",
+            file!(),
+            line!(),
+            a,
+            b,
+            fa,
+            fb
+        ));
+    }
+
+    let (mut c, mut fc) = (b, fb);
+    let (mut d, mut e) = (b - a, b - a);
+    for _ in 0..options.max_iterations {
+        if fb * fc > 0.0 {
+            // the root is between b and c
+            (c, fc) = (a, fa);
+            d = b - a;
+            e = d;
+        }
+        if fc.abs() < fb.abs() {
+            (a, fa) = (b, fb);
+            (b, fb) = (c, fc);
+            (c, fc) = (a, fa);
+        }
+        let tol = 2.0 * Real::EPSILON * b.abs() + 0.5 * options.tolerance;
+        let m = 0.5 * (c - b);
+        if m.abs() <= tol || fb == 0.0 {
+            return Ok(b);
+        }
+        if e.abs() >= tol && fa.abs() > fb.abs() {
+            let s = fb / fa;
+            let (mut p, mut q) = if a == c {
+                // secant
+                (2.0 * m * s, 1.0 - s)
+            } else {
+                // inverse quadratic interpolation
+                let q = fa / fc;
+                let r = fb / fc;
+                (
+                    s * (2.0 * m * q * (q - r) - (b - a) * (r - 1.0)),
+                    (q - 1.0) * (r - 1.0) * (s - 1.0),
+                )
+            };
+            if p > 0.0 {
+                q = -q;
+            } else {
+                p = -p;
+            }
+            if 2.0 * p < (3.0 * m * q - (tol * q).abs()).min((e * q).abs()) {
+                e = d;
+                d = p / q;
+            } else {
+                d = m;
+                e = m;
+            }
+        } else {
+            d = m;
+            e = m;
+        }
+        (a, fa) = (b, fb);
+        b += if d.abs() > tol { d } else { tol.copysign(m) };
+        fb = f(b)?;
+    }
+    Err(anyhow!(
+        "({}:{}) Brent's method did not converge in {} iterations, the last estimate is {}",
+        file!(),
+        line!(),
+        options.max_iterations,
+        b
+    ))
+}
+
+/// Newton's method kept in the bracket [lower, upper]: the step falls back to the bisection
+/// if it leaves the bracket or does not halve the residual, e.g., on the flat derivatives.
+/// f returns (the value, the derivative)
+pub fn newton_safeguarded<F>(
+    f: &mut F,
+    lower: Real,
+    upper: Real,
+    init_guess: Real,
+    options: &SolverOptions,
+) -> Result<Real>
+where
+    F: FnMut(Real) -> Result<(Real, Real)>,
+{
+    let (f_lower, _) = f(lower)?;
+    let (f_upper, _) = f(upper)?;
+    if f_lower == 0.0 {
+        return Ok(lower);
+    }
+    if f_upper == 0.0 {
+        return Ok(upper);
+    }
+    if f_lower * f_upper > 0.0 {
+        return Err(anyhow!(
+            "({}:{}) the root is not bracketed in [{}, {}], f = ({}, {})",
+            file!(),
+            line!(),
+            lower,
+            upper,
+            f_lower,
+            f_upper
+        ));
+    }
+    // f(neg) < 0 < f(pos)
+    let (mut neg, mut pos) = if f_lower < 0.0 {
+        (lower, upper)
+    } else {
+        (upper, lower)
+    };
+
+    let mut x = if init_guess > lower && init_guess < upper {
+        init_guess
+    } else {
+        0.5 * (lower + upper)
+    };
+    let mut step_before = (upper - lower).abs();
+    let mut step = step_before;
+    let (mut fx, mut dfx) = f(x)?;
+    for _ in 0..options.max_iterations {
+        let out_of_bracket = ((x - pos) * dfx - fx) * ((x - neg) * dfx - fx) > 0.0;
+        let slow = (2.0 * fx).abs() > (step_before * dfx).abs();
+        step_before = step;
+        if out_of_bracket || slow || dfx == 0.0 {
+            step = 0.5 * (pos - neg);
+            x = neg + step;
+        } else {
+            step = fx / dfx;
+            x -= step;
+        }
+        if step.abs() <= options.tolerance + 2.0 * Real::EPSILON * x.abs() {
+            return Ok(x);
+        }
+        (fx, dfx) = f(x)?;
+        if fx == 0.0 {
+            return Ok(x);
+        }
+        if fx < 0.0 {
+            neg = x;
+        } else {
+            pos = x;
+        }
+    }
+    Err(anyhow!(
+        "({}:{}) Newton's method did not converge in {} iterations, the last estimate is {}",
+        file!(),
+        line!(),
+        options.max_iterations,
+        x
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solvers() -> Result<()> {
+        let options = SolverOptions::default();
+        let mut cubic = |x: Real| Ok(x * x * x - 2.0 * x - 5.0);
+        let root = brent(&mut cubic, 2.0, 3.0, &options)?;
+        assert!((root - 2.0945514815423265).abs() < 1.0e-6);
+        assert!(brent(&mut cubic, 3.0, 4.0, &options).is_err());
+
+        // a discount factor of a negative rate: exp(-r) = 1.002
+        let mut discount = |r: Real| Ok(((-r).exp() - 1.002, -(-r).exp()));
+        let root = newton_safeguarded(&mut discount, -1.0, 1.0, 0.03, &options)?;
+        assert!((root + (1.002 as Real).ln()).abs() < 1.0e-6);
+
+        // the flat derivative at the initial guess falls back to the bisection
+        let mut atan = |x: Real| Ok((x.atan() - 0.5, 1.0 / (1.0 + x * x)));
+        let root = newton_safeguarded(&mut atan, -100.0, 100.0, 50.0, &options)?;
+        assert!((root - (0.5 as Real).tan()).abs() < 1.0e-6);
+
+        let mut yield_price = |y: Real| Ok((1.0 + y / 2.0).powi(-20) - 1.2);
+        let (lower, upper, f_lower, f_upper) =
+            expand_bracket(&mut yield_price, 0.03, 0.04, (-2.0, Real::INFINITY))?;
+        assert!(lower < upper && f_lower * f_upper <= 0.0);
+        let root = brent(&mut yield_price, lower, upper, &options)?;
+        assert!(root < 0.0 && ((1.0 + root / 2.0).powi(-20) - 1.2).abs() < 1.0e-5);
+
+        let mut positive = |x: Real| Ok(x * x + 1.0);
+        assert!(expand_bracket(&mut positive, -1.0, 1.0, (-10.0, 10.0)).is_err());
+        Ok(())
+    }
+}
@@ -8,7 +8,7 @@ use crate::math::interpolators::stepwise_interpolatior::{
 use crate::util::to_yyyymmdd_int;
 use anyhow::{anyhow, Result};
 use ndarray::Array1;
-use std::{cell::RefCell, rc::Rc};
+use std::sync::{Arc, RwLock};
 use time;
 use time::OffsetDateTime;
 use static_id::static_id::StaticId;
@@ -21,8 +21,9 @@ enum DividendInterpolator {
 
 #[derive(Clone, Debug)]
 pub struct DiscreteRatioDividend {
-    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    evaluation_date: Arc<RwLock<EvaluationDate>>,
     ex_dividend_dates: Vec<OffsetDateTime>,
+    payment_dates: Vec<OffsetDateTime>,
     date_integers: Array1<Integer>,
     dividend_amounts: Array1<Real>,
     dividend_yields: Array1<Real>,
@@ -33,8 +34,8 @@ pub struct DiscreteRatioDividend {
 }
 
 impl DiscreteRatioDividend {
-    /// evaluation_date: Rc<RefCell<EvaluationDate>>,
-    /// data: Rc<RefCell<VectorData>>, // dividend amount (not yield)
+    /// evaluation_date: Arc<RwLock<EvaluationDate>>,
+    /// data: Arc<RwLock<VectorData>>, // dividend amount (not yield)
     /// data is used to make an inner interpolator of accumulated dividend ratio deduction
     /// data is not an attribute of DiscreteRatioDividen, but an observable variable
     ///
@@ -48,8 +49,11 @@ impl DiscreteRatioDividend {
     /// The interpolator is made from the integer domain, and the range is Real.
     ///
     /// The ex-dividend-time is 00:00:00, and the closing-time is 16:00:00
+    ///
+    /// The dates in data are ex-dividend dates on which the spot drops.
+    /// The payment dates (cash receipt) are the ex-dividend dates unless given by with_payment_dates
     pub fn new(
-        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        evaluation_date: Arc<RwLock<EvaluationDate>>,
         data: &VectorData, // dividend amount
         spot: Real,
         name: String,
@@ -79,19 +83,17 @@ impl DiscreteRatioDividend {
             //ex_dividend_times[i] = time;
         }
         // drop data of ex-dividend date and dividend amount before the evaluation-date
-        let eval_dt = evaluation_date.to_owned().borrow().get_date_clone();
+        let eval_dt = evaluation_date.to_owned().read().unwrap().get_date_clone();
         let mut ex_dividend_dates_for_interpolator = ex_dividend_dates.clone();
         let mut div_yields_vec = dividend_yields.to_vec();
         let mut date_integers_for_interpolator_vec = date_integers.to_vec();
 
         let mut i = 0;
-        let mut checker = 0;
-        while i < ex_dividend_dates.len() {
-            if ex_dividend_dates[checker] < eval_dt {
+        for ex_dividend_date in ex_dividend_dates.iter() {
+            if *ex_dividend_date < eval_dt {
                 ex_dividend_dates_for_interpolator.remove(i);
                 div_yields_vec.remove(i);
                 date_integers_for_interpolator_vec.remove(i);
-                checker += 1;
             } else {
                 i += 1;
             }
@@ -122,6 +124,7 @@ impl DiscreteRatioDividend {
 
         let res = DiscreteRatioDividend {
             evaluation_date: evaluation_date.clone(),
+            payment_dates: ex_dividend_dates.clone(),
             ex_dividend_dates,
             //time_calculator,
             date_integers,
@@ -135,6 +138,52 @@ impl DiscreteRatioDividend {
         Ok(res)
     }
 
+    pub fn with_payment_dates(mut self, payment_dates: Vec<OffsetDateTime>) -> Result<Self> {
+        self.set_payment_dates(payment_dates)?;
+        Ok(self)
+    }
+
+    /// payment_dates are matched to the ex-dividend dates in order
+    /// and a dividend can not be paid before its ex-dividend date
+    pub fn set_payment_dates(&mut self, payment_dates: Vec<OffsetDateTime>) -> Result<()> {
+        if payment_dates.len() != self.ex_dividend_dates.len() {
+            return Err(anyhow!(
+                "({}:{}) the number of payment dates ({}) is different from \
+                the number of ex-dividend dates ({}) in {} ({})",
+                file!(),
+                line!(),
+                payment_dates.len(),
+                self.ex_dividend_dates.len(),
+                self.name,
+                self.id,
+            ));
+        }
+
+        for (ex_date, payment_date) in self.ex_dividend_dates.iter().zip(payment_dates.iter()) {
+            if payment_date.date() < ex_date.date() {
+                return Err(anyhow!(
+                    "({}:{}) payment date {} is before ex-dividend date {} in {} ({})",
+                    file!(),
+                    line!(),
+                    payment_date,
+                    ex_date,
+                    self.name,
+                    self.id,
+                ));
+            }
+        }
+        self.payment_dates = payment_dates;
+        Ok(())
+    }
+
+    pub fn get_ex_dividend_dates(&self) -> &Vec<OffsetDateTime> {
+        &self.ex_dividend_dates
+    }
+
+    pub fn get_payment_dates(&self) -> &Vec<OffsetDateTime> {
+        &self.payment_dates
+    }
+
     pub fn get_deduction_ratio(&self, date: &OffsetDateTime) -> Result<Real> {
         let date_int = to_yyyymmdd_int(date);
         match self.deduction_interpolator {
@@ -156,7 +205,7 @@ impl DiscreteRatioDividend {
         Ok(result)
     }
 
-    pub fn get_evaluation_date_clone(&self) -> Rc<RefCell<EvaluationDate>> {
+    pub fn get_evaluation_date_clone(&self) -> Arc<RwLock<EvaluationDate>> {
         self.evaluation_date.clone()
     }
 
@@ -176,6 +225,15 @@ impl DiscreteRatioDividend {
             .collect()
     }
 
+    /// (payment date, dividend amount), i.e., the cash receipts of the dividends
+    pub fn get_dividend_payments(&self) -> Vec<(OffsetDateTime, Real)> {
+        self.payment_dates
+            .iter()
+            .zip(self.dividend_amounts.iter())
+            .map(|(date, amount)| (*date, *amount))
+            .collect()
+    }
+
     pub fn get_dividend_ratio(&self) -> Vec<(OffsetDateTime, Real)> {
         self.ex_dividend_dates
             .iter()
@@ -252,17 +310,18 @@ impl DiscreteRatioDividend {
         let mut div_yields_vec = self.dividend_yields.to_vec();
         let mut date_integers_for_interpolator_vec = self.date_integers.clone().to_vec();
 
+        // i: index in the remaining dates, checker: index in the original dates
         let mut i = 0;
         let mut checker = 0;
-        while i < self.ex_dividend_dates.len() {
+        while checker < self.ex_dividend_dates.len() {
             if self.ex_dividend_dates[checker] < eval_dt {
                 ex_dividend_dates_for_interpolator.remove(i);
                 div_yields_vec.remove(i);
                 date_integers_for_interpolator_vec.remove(i);
-                checker += 1;
             } else {
                 i += 1;
             }
+            checker += 1;
         }
 
         let dividend_yields_for_interpolator = Array1::from(div_yields_vec);
@@ -305,7 +364,7 @@ mod tests {
 
     #[test]
     fn test_deduction_ratio() -> Result<()> {
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(
             OffsetDateTime::new_in_offset(
                 date!(2021 - 01 - 01),
                 DEFAULT_CLOSING_TIME,
@@ -344,9 +403,9 @@ mod tests {
         )
         .expect("Failed to create DiscreteRatioDividend");
 
-        let dividend = Rc::new(RefCell::new(discrete_ratio_dividend));
+        let dividend = Arc::new(RwLock::new(discrete_ratio_dividend));
 
-        //evaluation_date.borrow_mut().add_dividend_observer(dividend.clone());
+        //evaluation_date.write().unwrap().add_dividend_observer(dividend.clone());
 
         let test_dates = vec![
             datetime!(2021-01-01 10:00:00 +09:00),
@@ -379,7 +438,7 @@ mod tests {
         ];
 
         for (date, val) in test_dates.iter().zip(test_values.iter()) {
-            let ratio = dividend.borrow().get_deduction_ratio(&date)?;
+            let ratio = dividend.read().unwrap().get_deduction_ratio(date)?;
             assert!(
                 (ratio - val) < 1.0e-10,
                 "date: {:?}, val: {:?}, ratio: {}, expected: {}",
@@ -406,10 +465,10 @@ mod tests {
         ];
 
         {
-            dividend.borrow_mut().bump_date_interval(None, None, 0.1)?;
+            dividend.write().unwrap().bump_date_interval(None, None, 0.1)?;
         }
         for (date, val) in test_dates.iter().zip(test_values.iter()) {
-            let ratio = dividend.borrow().get_deduction_ratio(&date)?;
+            let ratio = dividend.read().unwrap().get_deduction_ratio(date)?;
             assert!(
                 (ratio - val) < 1.0e-10,
                 "(after bumped) date: {:?}, val: {:?}, ratio: {}, expected: {}",
@@ -421,7 +480,7 @@ mod tests {
         }
 
         // drop the the first two ex-dividend dates by evaluation_date += "2D"
-        *evaluation_date.borrow_mut() += "2D";
+        *evaluation_date.write().unwrap() += "2D";
 
         let test_values: Vec<Real> = vec![
             1.0,
@@ -439,7 +498,7 @@ mod tests {
         ];
 
         for (date, val) in test_dates.iter().zip(test_values.iter()) {
-            let ratio = dividend.borrow().get_deduction_ratio(&date)?;
+            let ratio = dividend.read().unwrap().get_deduction_ratio(date)?;
             assert!(
                 (ratio - val) < 1.0e-10,
                 "(after add 2D from evaluation_date) date: {:?}, val: {:?}, ratio: {}, expected: {}",
@@ -451,7 +510,7 @@ mod tests {
         }
 
         // now recover again by shift evaluation_date -= "2D"
-        *evaluation_date.borrow_mut() -= "2D";
+        *evaluation_date.write().unwrap() -= "2D";
 
         let test_values: Vec<Real> = vec![
             1.0, // evaluation_date is before the first ex-dividend date
@@ -469,7 +528,7 @@ mod tests {
         ];
 
         for (date, val) in test_dates.iter().zip(test_values.iter()) {
-            let ratio = dividend.borrow().get_deduction_ratio(&date)?;
+            let ratio = dividend.read().unwrap().get_deduction_ratio(date)?;
             assert!(
                 (ratio - val) < 1.0e-10,
                 "(after add 2D and then subtract 2D from evaluation_date) date: {:?}, val: {:?}, ratio: {}, expected: {}",
@@ -482,4 +541,59 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_payment_dates() -> Result<()> {
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(
+            datetime!(2024-01-02 16:00:00 +09:00),
+        )));
+        let ex_dates = vec![
+            datetime!(2024-03-28 00:00:00 +09:00),
+            datetime!(2024-06-27 00:00:00 +09:00),
+        ];
+        let data = VectorData::new(
+            array![1.0, 2.0],
+            Some(ex_dates.clone()),
+            None,
+            Some(datetime!(2024-01-02 16:00:00 +09:00)),
+            Currency::KRW,
+            "test".to_string(),
+            StaticId::from_str("test", "test"),
+        )?;
+        let dividend = DiscreteRatioDividend::new(
+            evaluation_date.clone(),
+            &data,
+            100.0,
+            "test".to_string(),
+            StaticId::from_str("test", "test"),
+        )?;
+        // without payment dates, the dividends are paid on the ex-dividend dates
+        assert_eq!(dividend.get_payment_dates(), &ex_dates);
+
+        let payment_dates = vec![
+            datetime!(2024-04-19 00:00:00 +09:00),
+            datetime!(2024-08-16 00:00:00 +09:00),
+        ];
+        assert!(dividend
+            .clone()
+            .with_payment_dates(vec![payment_dates[0]])
+            .is_err());
+        assert!(dividend
+            .clone()
+            .with_payment_dates(vec![payment_dates[0], datetime!(2024-06-26 00:00:00 +09:00)])
+            .is_err());
+
+        let dividend = dividend.with_payment_dates(payment_dates.clone())?;
+        assert_eq!(dividend.get_ex_dividend_dates(), &ex_dates);
+        assert_eq!(
+            dividend.get_dividend_payments(),
+            vec![(payment_dates[0], 1.0), (payment_dates[1], 2.0)]
+        );
+        // the spot drop still happens on the ex-dividend date
+        assert_eq!(
+            dividend.get_deduction_ratio(&datetime!(2024-03-28 10:00:00 +09:00))?,
+            0.99
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,252 @@
+use crate::definitions::Real;
+use crate::math::dual::Dual;
+use crate::parameters::{market_price::MarketPrice, zero_curve::ZeroCurve};
+//
+use anyhow::{anyhow, Context, Result};
+use static_id::static_id::StaticId;
+use std::sync::{Arc, RwLock};
+use time::OffsetDateTime;
+
+/// Forward of an equity (or index) combined from spot, collateral (discount) curve,
+/// borrowing (repo) curve and discrete dividends:
+///
+/// F(T) = S * B(T) / P(T) * D(T)
+///
+/// where B is the borrowing discount factor, P the collateral discount factor
+/// and D the dividend deduction ratio.
+///
+/// The components are shared references, so the bumps on the spot, curves and dividends
+/// in the greek calculation are reflected in the forwards without rebuilding the object.
+/// Futures, option pricers and the local volatility surface read the forwards from here.
+#[derive(Debug, Clone)]
+pub struct EquityForwardCurve {
+    market_price: Arc<RwLock<MarketPrice>>,
+    collateral_curve: Arc<RwLock<ZeroCurve>>,
+    borrowing_curve: Arc<RwLock<ZeroCurve>>,
+    name: String,
+    id: StaticId,
+}
+
+impl EquityForwardCurve {
+    pub fn new(
+        market_price: Arc<RwLock<MarketPrice>>,
+        collateral_curve: Arc<RwLock<ZeroCurve>>,
+        borrowing_curve: Arc<RwLock<ZeroCurve>>,
+    ) -> EquityForwardCurve {
+        let name = market_price.read().unwrap().get_name().clone();
+        let id = market_price.read().unwrap().get_id();
+        EquityForwardCurve {
+            market_price,
+            collateral_curve,
+            borrowing_curve,
+            name,
+            id,
+        }
+    }
+
+    pub fn get_market_price(&self) -> &Arc<RwLock<MarketPrice>> {
+        &self.market_price
+    }
+
+    pub fn get_collateral_curve(&self) -> &Arc<RwLock<ZeroCurve>> {
+        &self.collateral_curve
+    }
+
+    pub fn get_borrowing_curve(&self) -> &Arc<RwLock<ZeroCurve>> {
+        &self.borrowing_curve
+    }
+
+    pub fn get_name_clone(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn get_id(&self) -> StaticId {
+        self.id
+    }
+
+    pub fn get_spot(&self) -> Real {
+        self.market_price.read().unwrap().get_value()
+    }
+
+    /// (P(T), B(T), D(T))
+    fn get_components(&self, datetime: &OffsetDateTime) -> Result<(Real, Real, Real)> {
+        let collateral_discount = self
+            .collateral_curve
+            .read().unwrap()
+            .get_discount_factor_at_date(datetime)
+            .with_context(|| {
+                anyhow!(
+                    "({}:{}) failed to get collateral discount factor\n\
+                    datetime: {}, name: {}, id: {}",
+                    file!(),
+                    line!(),
+                    datetime,
+                    self.name,
+                    self.id,
+                )
+            })?;
+
+        let borrowing_discount = self
+            .borrowing_curve
+            .read().unwrap()
+            .get_discount_factor_at_date(datetime)
+            .with_context(|| {
+                anyhow!(
+                    "({}:{}) failed to get borrowing discount factor\n\
+                    datetime: {}, name: {}, id: {}",
+                    file!(),
+                    line!(),
+                    datetime,
+                    self.name,
+                    self.id,
+                )
+            })?;
+
+        let dividend_deduction_ratio = self
+            .market_price
+            .read().unwrap()
+            .get_dividend_deduction_ratio(datetime)
+            .with_context(|| {
+                anyhow!(
+                    "({}:{}) failed to get dividend deduction ratio\n\
+                    datetime: {}, name: {}, id: {}",
+                    file!(),
+                    line!(),
+                    datetime,
+                    self.name,
+                    self.id,
+                )
+            })?;
+
+        Ok((collateral_discount, borrowing_discount, dividend_deduction_ratio))
+    }
+
+    /// F(T) / S = B(T) / P(T) * D(T)
+    pub fn get_forward_ratio(&self, datetime: &OffsetDateTime) -> Result<Real> {
+        let (collateral_discount, borrowing_discount, dividend_deduction_ratio) =
+            self.get_components(datetime)?;
+        Ok(borrowing_discount / collateral_discount * dividend_deduction_ratio)
+    }
+
+    /// forward from the current spot
+    pub fn get_forward(&self, datetime: &OffsetDateTime) -> Result<Real> {
+        self.get_forward_from_spot(self.get_spot(), datetime)
+    }
+
+    /// forward from a given spot, e.g., the spot when the volatility surface was calibrated
+    pub fn get_forward_from_spot(&self, spot: Real, datetime: &OffsetDateTime) -> Result<Real> {
+        let (collateral_discount, borrowing_discount, dividend_deduction_ratio) =
+            self.get_components(datetime)?;
+        Ok(spot * borrowing_discount / collateral_discount * dividend_deduction_ratio)
+    }
+
+    /// forward as a dual number of the spot and the parallel shifts of the collateral and borrowing curves.
+    /// The dividend deduction ratio is held fixed as in the bump of the spot.
+    pub fn get_forward_dual<const N: usize>(
+        &self,
+        spot: Dual<N>,
+        collateral_shift: Dual<N>,
+        borrowing_shift: Dual<N>,
+        datetime: &OffsetDateTime,
+    ) -> Result<Dual<N>> {
+        let (_, _, dividend_deduction_ratio) = self.get_components(datetime)?;
+        let collateral_discount = self
+            .collateral_curve
+            .read().unwrap()
+            .get_discount_factor_dual_at_date(datetime, collateral_shift)?;
+        let borrowing_discount = self
+            .borrowing_curve
+            .read().unwrap()
+            .get_discount_factor_dual_at_date(datetime, borrowing_shift)?;
+        Ok(spot * borrowing_discount / collateral_discount * dividend_deduction_ratio)
+    }
+
+    pub fn get_forwards(&self, datetimes: &[OffsetDateTime]) -> Result<Vec<Real>> {
+        let spot = self.get_spot();
+        datetimes
+            .iter()
+            .map(|dt| self.get_forward_from_spot(spot, dt))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::data::vector_data::VectorData;
+    use crate::evaluation_date::EvaluationDate;
+    use crate::parameters::discrete_ratio_dividend::DiscreteRatioDividend;
+    use ndarray::Array1;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_equity_forward_curve() -> Result<()> {
+        let market_datetime = datetime!(2024-01-02 00:00:00 +09:00);
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(market_datetime)));
+        let spot: Real = 350.0;
+
+        let dividend_data = VectorData::new(
+            Array1::from(vec![3.0]),
+            Some(vec![datetime!(2024-06-15 00:00:00 +09:00)]),
+            None,
+            Some(market_datetime),
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            StaticId::from_str("KOSPI2", "KRX"),
+        )?;
+        let dividend = DiscreteRatioDividend::new(
+            evaluation_date.clone(),
+            &dividend_data,
+            spot,
+            "KOSPI2".to_string(),
+            StaticId::from_str("KOSPI2", "KRX"),
+        )?;
+        let equity = Arc::new(RwLock::new(MarketPrice::new(
+            spot,
+            market_datetime,
+            Some(Arc::new(RwLock::new(dividend))),
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            StaticId::from_str("KOSPI2", "KRX"),
+        )));
+
+        let curve_data = VectorData::new(
+            Array1::from(vec![0.035]),
+            None,
+            Some(Array1::from(vec![1.0])),
+            Some(market_datetime),
+            Currency::KRW,
+            "KSD".to_string(),
+            StaticId::from_str("KSD", "test"),
+        )?;
+        let collateral_curve = Arc::new(RwLock::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &curve_data,
+            "KSD".to_string(),
+            StaticId::from_str("KSD", "test"),
+        )?));
+        let borrowing_curve = Arc::new(RwLock::new(ZeroCurve::dummy_curve()?));
+
+        let forward_curve =
+            EquityForwardCurve::new(equity.clone(), collateral_curve, borrowing_curve);
+        assert_eq!(forward_curve.get_id(), StaticId::from_str("KOSPI2", "KRX"));
+
+        let before_dividend = datetime!(2024-06-14 00:00:00 +09:00);
+        let after_dividend = datetime!(2024-06-16 00:00:00 +09:00);
+        let forwards = forward_curve.get_forwards(&[before_dividend, after_dividend])?;
+        assert!(forwards[0] > spot);
+        // 3.0 of dividend is deducted between the two dates
+        assert!((forwards[0] - forwards[1] - 3.0).abs() < 0.1, "{:?}", forwards);
+
+        // the spot bump is reflected without rebuilding the forward curve
+        let forward = forward_curve.get_forward(&after_dividend)?;
+        equity.write().unwrap().set_price(spot * 1.01);
+        let bumped = forward_curve.get_forward(&after_dividend)?;
+        assert!((bumped / forward - 1.01).abs() < 1.0e-5);
+        assert!(
+            (forward_curve.get_forward_from_spot(spot, &after_dividend)? - forward).abs() < 1.0e-3
+        );
+        Ok(())
+    }
+}
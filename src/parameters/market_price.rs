@@ -1,11 +1,13 @@
 use crate::currency::Currency;
 use crate::definitions::Real;
+use crate::enums::MarkingSide;
 use crate::evaluation_date::EvaluationDate;
 use crate::parameters::discrete_ratio_dividend::DiscreteRatioDividend;
-use anyhow::Result;
-use std::cell::RefCell;
+use crate::utils::platform::log_debug;
+use anyhow::{anyhow, Result};
+use std::sync::RwLock;
 use std::ops::{AddAssign, DivAssign, MulAssign, SubAssign};
-use std::rc::Rc;
+use std::sync::Arc;
 use time::OffsetDateTime;
 use static_id::static_id::StaticId;
 
@@ -15,10 +17,12 @@ use static_id::static_id::StaticId;
 pub struct MarketPrice {
     value: Real,
     market_datetime: OffsetDateTime,
-    dividend: Option<Rc<RefCell<DiscreteRatioDividend>>>,
+    dividend: Option<Arc<RwLock<DiscreteRatioDividend>>>,
     currency: Currency,
     name: String,
     id: StaticId,
+    // (value - bid, ask - value) so that the quotes follow the bumps on the value
+    bid_ask_spread: Option<(Real, Real)>,
 }
 
 impl MarketPrice {
@@ -33,7 +37,7 @@ impl MarketPrice {
     pub fn new(
         value: Real,
         market_datetime: OffsetDateTime,
-        dividend: Option<Rc<RefCell<DiscreteRatioDividend>>>,
+        dividend: Option<Arc<RwLock<DiscreteRatioDividend>>>,
         currency: Currency,
         name: String,
         id: StaticId,
@@ -45,6 +49,52 @@ impl MarketPrice {
             currency,
             name,
             id,
+            bid_ask_spread: None,
+        }
+    }
+
+    pub fn with_bid_ask(mut self, bid: Real, ask: Real) -> Result<MarketPrice> {
+        if bid > ask {
+            return Err(anyhow!(
+                "({}:{}) bid ({}) is larger than ask ({}) in {} ({})",
+                file!(),
+                line!(),
+                bid,
+                ask,
+                self.name,
+                self.id,
+            ));
+        }
+        // the value (e.g., the last price) need not be in [bid, ask]
+        self.bid_ask_spread = Some((self.value - bid, ask - self.value));
+        Ok(self)
+    }
+
+    pub fn has_bid_ask(&self) -> bool {
+        self.bid_ask_spread.is_some()
+    }
+
+    pub fn get_bid(&self) -> Option<Real> {
+        self.bid_ask_spread.map(|(bid_spread, _)| self.value - bid_spread)
+    }
+
+    pub fn get_ask(&self) -> Option<Real> {
+        self.bid_ask_spread.map(|(_, ask_spread)| self.value + ask_spread)
+    }
+
+    pub fn get_mid(&self) -> Real {
+        match self.bid_ask_spread {
+            Some((bid_spread, ask_spread)) => self.value + 0.5 * (ask_spread - bid_spread),
+            None => self.value,
+        }
+    }
+
+    /// Conservative is the bid, i.e., the exit price of a holder
+    pub fn get_marked_value(&self, marking_side: MarkingSide) -> Real {
+        match marking_side {
+            MarkingSide::Mid => self.get_mid(),
+            MarkingSide::Bid | MarkingSide::Conservative => self.get_bid().unwrap_or(self.value),
+            MarkingSide::Ask => self.get_ask().unwrap_or(self.value),
         }
     }
 
@@ -68,7 +118,7 @@ impl MarketPrice {
         &self.market_datetime
     }
 
-    pub fn get_dividend(&self) -> &Option<Rc<RefCell<DiscreteRatioDividend>>> {
+    pub fn get_dividend(&self) -> &Option<Arc<RwLock<DiscreteRatioDividend>>> {
         &self.dividend
     }
 
@@ -83,17 +133,40 @@ impl MarketPrice {
     /// If the dividend is None, this returns 1.0
     pub fn get_dividend_deduction_ratio(&self, datetime: &OffsetDateTime) -> Result<Real> {
         if let Some(dividend) = &self.dividend {
-            dividend.borrow().get_deduction_ratio(datetime)
+            dividend.read().unwrap().get_deduction_ratio(datetime)
         } else {
             Ok(1.0)
         }
     }
 
+    /// dividend payments (payment date, amount) with ex_date <= datetime < payment_date,
+    /// i.e., the dividends already deducted from the value but not paid yet.
+    /// If the dividend is None, this returns an empty vector
+    pub fn get_unpaid_dividend_payments(&self, datetime: &OffsetDateTime) -> Vec<(OffsetDateTime, Real)> {
+        match &self.dividend {
+            Some(dividend) => {
+                let dividend = dividend.read().unwrap();
+                dividend
+                    .get_ex_dividend_dates()
+                    .iter()
+                    .zip(dividend.get_dividend_payments())
+                    .filter(|(ex_date, (payment_date, _))| {
+                        ex_date.date() <= datetime.date() && datetime.date() < payment_date.date()
+                    })
+                    .map(|(_, payment)| payment)
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+
+    /// The value drops on the ex-dividend dates, not on the payment dates.
+    /// The payment dates are only for the cashflows of the holder.
     pub fn update_evaluation_date(&mut self, date: &EvaluationDate) -> Result<()> {
         if let Some(dividend) = &self.dividend {
             let eval_dt = date.get_date_clone();
             if self.market_datetime < eval_dt {
-                let div_ratio = dividend.borrow().get_dividend_ratio();
+                let div_ratio = dividend.read().unwrap().get_dividend_ratio();
                 // might be better to use clone()?
                 for (date, div) in div_ratio.into_iter() {
                     if (date > self.market_datetime) && (date <= eval_dt) {
@@ -103,7 +176,7 @@ impl MarketPrice {
                         let name = self.name.clone();
                         let id = self.id;
                         let value = self.value;
-                        flashlog::flash_debug!(
+                        log_debug!(
                             "ShortMaturity"; "\n{} ({}) is DEDUCTED from dividens by {} on {}\n\
                             evaluation_date: {:?}, value: {}\n",
                             name, id, div, date_clone, eval_dt_clone, value
@@ -112,7 +185,7 @@ impl MarketPrice {
                 }
                 self.market_datetime = eval_dt;
             } else {
-                let div_ratio = dividend.borrow().get_dividend_ratio();
+                let div_ratio = dividend.read().unwrap().get_dividend_ratio();
                 // might be better to use clone()?
                 for (date, div) in div_ratio.into_iter() {
                     if (date > eval_dt) && (date <= self.market_datetime) {
@@ -123,7 +196,7 @@ impl MarketPrice {
                         let id = self.id;
                         let value = self.value;
 
-                        flashlog::flash_debug!(
+                        log_debug!(
                             "ShortMaturity"; "\n{} ({}) div deduction is ROLLED back by {} on {}\n\
                             evluation_date: {:?}, value: {}\n",
                             name_str, id, div, date_clone, eval_dt_clone, value
@@ -172,8 +245,8 @@ mod tests {
     use crate::evaluation_date::EvaluationDate;
     use crate::parameters::discrete_ratio_dividend::DiscreteRatioDividend;
     use ndarray::Array1;
-    use std::cell::RefCell;
-    use std::rc::Rc;
+    use std::sync::RwLock;
+    use std::sync::Arc;
     use time;
     use time::OffsetDateTime;
 
@@ -187,7 +260,7 @@ mod tests {
             offset,
         );
 
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_dt.clone())));
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_dt)));
 
         let div_dates = vec![
             eval_dt + time::Duration::days(1),
@@ -218,24 +291,24 @@ mod tests {
         )
         .expect("failed to create DiscreteRatioDividend");
 
-        let stock = Rc::new(RefCell::new(MarketPrice::new(
+        let stock = Arc::new(RwLock::new(MarketPrice::new(
             spot,
-            eval_dt.clone(),
-            Some(Rc::new(RefCell::new(dividend))),
+            eval_dt,
+            Some(Arc::new(RwLock::new(dividend))),
             Currency::KRW,
             "MockMarketPrice".to_string(),
             StaticId::from_str("MockCode", ""),
         )));
 
         evaluation_date
-            .borrow_mut()
+            .write().unwrap()
             .add_marketprice_observer(stock.clone());
 
         let mut test_spot = spot;
-        for i in 1..div_yields.len() {
-            *evaluation_date.borrow_mut() += "1D";
-            let price = stock.borrow().get_value();
-            test_spot *= 1.0 - div_yields[i];
+        for div_yield in div_yields.iter().skip(1) {
+            *evaluation_date.write().unwrap() += "1D";
+            let price = stock.read().unwrap().get_value();
+            test_spot *= 1.0 - div_yield;
             assert!(
                 (price - (test_spot as Real)).abs() < 1.0e-10,
                 "stock: {}, test_spot at i: {}",
@@ -245,11 +318,38 @@ mod tests {
         }
 
         // get back the evaluation_date to the original
-        *evaluation_date.borrow_mut() -= "3D";
+        *evaluation_date.write().unwrap() -= "3D";
         assert!(
-            (stock.borrow().get_value() - spot).abs() < 1.0e-10,
+            (stock.read().unwrap().get_value() - spot).abs() < 1.0e-10,
             "stock: {}",
-            stock.borrow().get_value()
+            stock.read().unwrap().get_value()
         );
     }
+
+    #[test]
+    fn test_bid_ask() -> Result<()> {
+        let dt = time::macros::datetime!(2024-01-02 16:00:00 +09:00);
+        let mut stock = MarketPrice::new(
+            100.0,
+            dt,
+            None,
+            Currency::KRW,
+            "MockMarketPrice".to_string(),
+            StaticId::from_str("MockCode", ""),
+        );
+        assert_eq!(stock.get_marked_value(MarkingSide::Bid), 100.0);
+        assert!(stock.clone().with_bid_ask(100.5, 99.5).is_err());
+
+        stock = stock.with_bid_ask(99.0, 100.5)?;
+        assert_eq!(stock.get_marked_value(MarkingSide::Bid), 99.0);
+        assert_eq!(stock.get_marked_value(MarkingSide::Ask), 100.5);
+        assert_eq!(stock.get_marked_value(MarkingSide::Mid), 99.75);
+        assert_eq!(stock.get_marked_value(MarkingSide::Conservative), 99.0);
+
+        // the quotes follow the bumps
+        stock *= 1.1;
+        assert!((stock.get_bid().unwrap() - 109.0).abs() < 1.0e-4);
+        assert!((stock.get_ask().unwrap() - 110.5).abs() < 1.0e-4);
+        Ok(())
+    }
 }
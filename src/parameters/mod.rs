@@ -1,4 +1,5 @@
 pub mod discrete_ratio_dividend;
+pub mod equity_forward_curve;
 pub mod market_price;
 pub mod past_price;
 pub mod quanto;
@@ -110,6 +110,21 @@ impl DailyClosePrice {
         &self.utc_offset
     }
 
+    /// the close of the market on the date of the given datetime (in its own offset)
+    pub fn get_close_datetime(&self, datetime: &OffsetDateTime) -> OffsetDateTime {
+        OffsetDateTime::new_in_offset(datetime.date(), self.close_time, self.utc_offset)
+    }
+
+    /// the expiry of a listed option maturing on the maturity:
+    /// the close of the market if the maturity is booked as a date (at midnight),
+    /// otherwise the maturity itself, e.g., 15:20 KST of KrxExpiryRule::EquityDerivative
+    pub fn get_expiry_datetime(&self, maturity: &OffsetDateTime) -> OffsetDateTime {
+        match maturity.time() == Time::MIDNIGHT {
+            true => self.get_close_datetime(maturity),
+            false => *maturity,
+        }
+    }
+
     // Get mutable method
     pub fn get_mut(&mut self, key: &Date) -> Option<&mut Real> {
         self.value.get_mut(key)
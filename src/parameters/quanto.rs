@@ -3,14 +3,14 @@ use crate::definitions::{Real, Time};
 use crate::parameters::{
     volatilities::constant_volatility::ConstantVolatility, volatility::Volatility,
 };
-use std::{cell::RefCell, rc::Rc};
+use std::sync::{Arc, RwLock};
 use static_id::static_id::StaticId;
 
 /// Quanto parameter.
 /// It is assumed that the correlation are constant.
 #[derive(Debug, Clone)]
 pub struct Quanto {
-    fx_volatility: Rc<RefCell<Volatility>>,
+    fx_volatility: Arc<RwLock<Volatility>>,
     correlation: Real,
     fx_code: FxCode,
     underlying_id: StaticId,
@@ -18,7 +18,7 @@ pub struct Quanto {
 
 impl Quanto {
     pub fn new(
-        fx_volatility: Rc<RefCell<Volatility>>,
+        fx_volatility: Arc<RwLock<Volatility>>,
         correlation: Real,
         fx_code: FxCode,
         underlying_id: StaticId,
@@ -32,7 +32,7 @@ impl Quanto {
     }
 
     pub fn quanto_adjust(&self, t: Time, forward_moneyness: Real) -> Real {
-        self.fx_volatility.borrow().get_value(t, forward_moneyness) * self.correlation
+        self.fx_volatility.read().unwrap().get_value(t, forward_moneyness) * self.correlation
     }
 
     pub fn get_underlying_id(&self) -> StaticId {
@@ -47,7 +47,7 @@ impl Quanto {
 impl Default for Quanto {
     fn default() -> Quanto {
         Quanto {
-            fx_volatility: Rc::new(RefCell::new(Volatility::ConstantVolatility(
+            fx_volatility: Arc::new(RwLock::new(Volatility::ConstantVolatility(
                 ConstantVolatility::default(),
             ))),
             correlation: 0.0,
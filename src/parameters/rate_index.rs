@@ -7,28 +7,62 @@ use crate::instruments::schedule::BaseSchedule;
 use crate::parameters::{past_price::DailyClosePrice, zero_curve::ZeroCurve};
 use crate::time::jointcalendar::JointCalendar;
 use crate::util::min_offsetdatetime;
+use crate::utils::platform::log_warn;
 use crate::Tenor;
 //
 use static_id::static_id::StaticId;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use std::{cell::RefCell, rc::Rc};
+use std::sync::{Arc, RwLock};
 use time::{Duration, OffsetDateTime};
 
+/// Compounding conventions of RFR (overnight) indices such as KOFR, SOFR and ESTR
+/// * lookback_days: the rate of each accrual day is observed lookback_days business days earlier
+/// * lockout_days: the last lockout_days accrual days take the rate of the last observation before the lockout
+/// * observation_shift: the accrual weights are taken from the (shifted) observation period
+///   instead of the interest period
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct RfrConvention {
+    pub lookback_days: i64,
+    pub lockout_days: usize,
+    pub observation_shift: bool,
+}
+
+impl RfrConvention {
+    pub fn new(lookback_days: i64, lockout_days: usize, observation_shift: bool) -> RfrConvention {
+        RfrConvention {
+            lookback_days,
+            lockout_days,
+            observation_shift,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 /// * Tenor is forward curve calculation period\n
 /// * Compounding_tenor (Option<String>) is the period of compounding\n
+///
 /// At each the end date of the previous compounding_tenor,
 /// the rate is computed by the tenor, and compounded by the compounding_tenor.
 /// For example, if tenor is 91D and compounding_tenor is 1D, and payment_frequency is 3M,
 /// that's the case of KODEX CD ETF (A459580)
 /// Theoretically, the compound tenor can be greater than the tenor,
 /// but I have not seen such a case in the market, so I chose not to allow such case (return error)
+///
+/// If rfr_convention is given, the compounded coupons are calculated day by day
+/// following the lookback, lockout and observation shift of the convention.
+///
+/// If stub_tenors are given, e.g., 1M and 3M of USD Libor, the rate of a stub period (BaseSchedule::is_stub)
+/// is linearly interpolated between the rates of the two tenors around the length of the stub (ISDA 2006, 8.3).
 pub struct RateIndex {
     id: StaticId,
     curve_tenor: Tenor,
     currency: Currency,
     name: String, // USD LIBOR 3M, EURIBOR 6M, CD91, etc
+    #[serde(default)]
+    rfr_convention: Option<RfrConvention>,
+    #[serde(default)]
+    stub_tenors: Vec<Tenor>,
 }
 
 impl RateIndex {
@@ -43,9 +77,29 @@ impl RateIndex {
             curve_tenor,
             currency,
             name,
+            rfr_convention: None,
+            stub_tenors: vec![],
         })
     }
 
+    pub fn with_stub_tenors(mut self, stub_tenors: Vec<Tenor>) -> RateIndex {
+        self.stub_tenors = stub_tenors;
+        self
+    }
+
+    pub fn get_stub_tenors(&self) -> &Vec<Tenor> {
+        &self.stub_tenors
+    }
+
+    pub fn with_rfr_convention(mut self, rfr_convention: RfrConvention) -> RateIndex {
+        self.rfr_convention = Some(rfr_convention);
+        self
+    }
+
+    pub fn get_rfr_convention(&self) -> Option<&RfrConvention> {
+        self.rfr_convention.as_ref()
+    }
+
     #[inline]
     #[must_use]
     pub fn get_id(&self) -> StaticId {
@@ -71,8 +125,8 @@ impl RateIndex {
 
     /// base_schedule (BaseSchedule): fixing_date, calc_start_date, calc_end_date, payment_date, amount (Option)
     /// spread (Option<Real>): spread to be added to the rate. None means zero spread
-    /// forward_curve (Rc<RefCell<ZeroCurve>>): forward curve
-    /// close_data (Rc<CloseData>): historical data which is used when the fixing date is before the evaluation date
+    /// forward_curve (Arc<RwLock<ZeroCurve>>): forward curve
+    /// close_data (Arc<CloseData>): historical data which is used when the fixing date is before the evaluation date
     /// pricing_date (OffsetDateTime): evaluation date
     /// compound_tenor (Option<&String>): compounding tenor. This is optional and None means that it is not a overnight type index.
     /// For example, if the  part is CD91, Libor3M, etc, it is None, but in case of SOFR1D, it is Some(String::from("1D"))
@@ -81,8 +135,8 @@ impl RateIndex {
         &self,
         base_schedule: &BaseSchedule,
         spread: Option<Real>,
-        forward_curve: Rc<RefCell<ZeroCurve>>,
-        close_data: Rc<DailyClosePrice>,
+        forward_curve: Arc<RwLock<ZeroCurve>>,
+        close_data: Arc<DailyClosePrice>,
         pricing_date: &OffsetDateTime,
         compound_tenor: Option<&Tenor>,
         calendar: &JointCalendar,
@@ -107,16 +161,18 @@ impl RateIndex {
                             );
 
                             forward_curve
-                                .borrow()
+                                .read().unwrap()
                                 .get_forward_rate_from_evaluation_date(
                                     &curve_end_date,
                                     Compounding::Simple,
                                 )?
                         }
                     }
+                } else if base_schedule.is_stub() && !self.stub_tenors.is_empty() {
+                    self.get_stub_rate(base_schedule, &forward_curve.read().unwrap())?
                 } else {
                     // fixing_date >= eval_dt
-                    forward_curve.borrow().get_forward_rate_between_dates(
+                    forward_curve.read().unwrap().get_forward_rate_between_dates(
                         fixing_date,
                         &curve_end_date,
                         Compounding::Simple,
@@ -131,6 +187,15 @@ impl RateIndex {
 
                 Ok((res + spread) * frac)
             }
+            Some(_) if self.rfr_convention.is_some() => self.get_rfr_coupon_amount(
+                base_schedule,
+                spread,
+                forward_curve,
+                close_data,
+                pricing_date,
+                calendar,
+                daycounter,
+            ),
             Some(comp_tenor) => {
                 //some means it is an ovenight type index
                 let fixing_date = base_schedule.get_fixing_date();
@@ -140,7 +205,7 @@ impl RateIndex {
                     //let curve_end_date = add_period(fixing_date, self.curve_tenor.as_str());
                     let curve_end_date = self.curve_tenor.apply(fixing_date);   
 
-                    let first_rate = forward_curve.borrow().get_forward_rate_between_dates(
+                    let first_rate = forward_curve.read().unwrap().get_forward_rate_between_dates(
                         fixing_date,
                         &curve_end_date,
                         Compounding::Simple,
@@ -149,7 +214,7 @@ impl RateIndex {
                     let last_fixing_date = *base_schedule.get_calc_end_date() - Duration::days(fixing_days);
                     let last_calc_end_date = self.curve_tenor.apply(&last_fixing_date);
                     
-                    let last_rate = forward_curve.borrow().get_forward_rate_between_dates(
+                    let last_rate = forward_curve.read().unwrap().get_forward_rate_between_dates(
                         &last_fixing_date,
                         //&add_period(&last_fixing_date, self.curve_tenor.as_str()),
                         &last_calc_end_date,
@@ -170,7 +235,7 @@ impl RateIndex {
                 let mut next_calc_date: OffsetDateTime;
                 let calc_end_date = *base_schedule.get_calc_end_date();
 
-                let spot_rate = forward_curve.borrow().get_forward_rate_between_dates(
+                let spot_rate = forward_curve.read().unwrap().get_forward_rate_between_dates(
                     pricing_date,
                     &curve_end_date_from_eval_date,
                     Compounding::Simple,
@@ -205,7 +270,7 @@ impl RateIndex {
                             }
                         }
                     } else {
-                        rate = forward_curve.borrow().get_forward_rate_between_dates(
+                        rate = forward_curve.read().unwrap().get_forward_rate_between_dates(
                             &fixing_date,
                             //&add_period(&fixing_date, self.curve_tenor.as_str()),
                             &self.curve_tenor.apply(&fixing_date),
@@ -222,6 +287,138 @@ impl RateIndex {
             }
         }
     }
+
+    /// The forward rate of a stub period interpolated linearly in the days between the rates of the stub_tenors
+    /// whose lengths are just below and above the stub. It is flat outside of the stub_tenors.
+    pub fn get_stub_rate(
+        &self,
+        base_schedule: &BaseSchedule,
+        forward_curve: &ZeroCurve,
+    ) -> Result<Real> {
+        let fixing_date = base_schedule.get_fixing_date();
+        let calc_start_date = base_schedule.get_calc_start_date();
+        let mut points = self
+            .stub_tenors
+            .iter()
+            .map(|tenor| {
+                let days = (tenor.apply(calc_start_date) - *calc_start_date).whole_days();
+                let rate = forward_curve.get_forward_rate_between_dates(
+                    fixing_date,
+                    &tenor.apply(fixing_date),
+                    Compounding::Simple,
+                )?;
+                Ok((days, rate))
+            })
+            .collect::<Result<Vec<(i64, Real)>>>()?;
+        if points.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) no stub tenors in {}",
+                file!(),
+                line!(),
+                self.name
+            ));
+        }
+        points.sort_by_key(|(days, _)| *days);
+
+        let stub_days = (*base_schedule.get_calc_end_date() - *calc_start_date).whole_days();
+        let (first, last) = (points[0], points[points.len() - 1]);
+        if stub_days <= first.0 {
+            return Ok(first.1);
+        }
+        if stub_days >= last.0 {
+            return Ok(last.1);
+        }
+        let i = points.partition_point(|(days, _)| *days < stub_days);
+        let (lower, upper) = (points[i - 1], points[i]);
+        let weight = (stub_days - lower.0) as Real / (upper.0 - lower.0) as Real;
+        Ok(lower.1 + (upper.1 - lower.1) * weight)
+    }
+
+    /// Daily compounded coupon amount following self.rfr_convention.
+    /// The accrual days are the business days in [calc_start_date, calc_end_date).
+    /// The observations before pricing_date are taken from close_data (spot rate if missing),
+    /// and the others are the forward rates of the curve_tenor on the forward_curve.
+    #[allow(clippy::too_many_arguments)]
+    fn get_rfr_coupon_amount(
+        &self,
+        base_schedule: &BaseSchedule,
+        spread: Real,
+        forward_curve: Arc<RwLock<ZeroCurve>>,
+        close_data: Arc<DailyClosePrice>,
+        pricing_date: &OffsetDateTime,
+        calendar: &JointCalendar,
+        daycounter: &DayCountConvention,
+    ) -> Result<Real> {
+        let convention = self.rfr_convention.unwrap_or_default();
+        let calc_start_date = *base_schedule.get_calc_start_date();
+        let calc_end_date = *base_schedule.get_calc_end_date();
+
+        let mut accrual_dates = vec![calc_start_date];
+        let mut date = calc_start_date + Duration::days(1);
+        while date < calc_end_date {
+            if calendar.is_business_day(&date) {
+                accrual_dates.push(date);
+            }
+            date += Duration::days(1);
+        }
+        accrual_dates.push(calc_end_date);
+        let accrual_days = accrual_dates.len() - 1;
+
+        let observation_dates: Vec<OffsetDateTime> = accrual_dates
+            .iter()
+            .map(|d| calendar.add_business_days(d, -convention.lookback_days))
+            .collect();
+        let lockout_start = accrual_days
+            .saturating_sub(convention.lockout_days)
+            .max(1);
+
+        let spot_rate = forward_curve.read().unwrap().get_forward_rate_between_dates(
+            pricing_date,
+            &self.curve_tenor.apply(pricing_date),
+            Compounding::Simple,
+        )?;
+
+        let mut compounded_value: Real = 1.0;
+        for i in 0..accrual_days {
+            let (weight_start, weight_end) = if convention.observation_shift {
+                (&observation_dates[i], &observation_dates[i + 1])
+            } else {
+                (&accrual_dates[i], &accrual_dates[i + 1])
+            };
+            let frac = calendar.year_fraction(weight_start, weight_end, daycounter)?;
+
+            let observation_date = if i >= lockout_start {
+                observation_dates[lockout_start - 1]
+            } else {
+                observation_dates[i]
+            };
+            let rate = if &observation_date < pricing_date {
+                match close_data.get(&(observation_date.date())) {
+                    Some(rate) => *rate,
+                    None => {
+                        let (observation_date, evaluation_date) =
+                            (observation_date.date(), pricing_date.date());
+                        log_warn!(
+                            "NoData";
+                            "({}:{}) observation date = {:?} is before the evaluation date = {:?}, \
+                            but there is no rate in the observation date, thus spot rate is taken",
+                            file!(), line!(), observation_date, evaluation_date
+                        );
+                        spot_rate
+                    }
+                }
+            } else {
+                forward_curve.read().unwrap().get_forward_rate_between_dates(
+                    &observation_date,
+                    &self.curve_tenor.apply(&observation_date),
+                    Compounding::Simple,
+                )?
+            };
+
+            compounded_value *= 1.0 + (rate + spread) * frac;
+        }
+        Ok(compounded_value - 1.0)
+    }
 }
 
 #[cfg(test)]
@@ -254,7 +451,7 @@ mod tests {
     #[test]
     fn test_rate_index() -> Result<()> {
         let dt = datetime!(2024-01-01 16:30:00 +19:00);
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(dt.clone())));
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(dt)));
         let _payment_frequency = PaymentFrequency::Quarterly;
         let _business_day_convention = BusinessDayConvention::ModifiedFollowing;
         let daycounter = DayCountConvention::Actual365Fixed;
@@ -279,13 +476,13 @@ mod tests {
             array![0.03, 0.03],
             None,
             Some(array![0.1, 0.2]),
-            Some(dt.clone()),
+            Some(dt),
             Currency::USD,
             "USDOIS".to_string(),
             StaticId::from_str("USDOIS", "KAP"),
         )?;
 
-        let zero_curve = Rc::new(RefCell::new(ZeroCurve::new(
+        let zero_curve = Arc::new(RwLock::new(ZeroCurve::new(
             evaluation_date.clone(),
             &curve_data,
             "USDOIS".to_string(),
@@ -295,12 +492,12 @@ mod tests {
         let calc_end_date = dt + Duration::days(90);
         let base_schedule = BaseSchedule::new(
             calendar.adjust(
-                &(dt.clone() - Duration::days(7)),
+                &(dt - Duration::days(7)),
                 &BusinessDayConvention::Preceding,
             )?,
-            dt.clone(),
-            calc_end_date.clone(),
-            calc_end_date.clone(),
+            dt,
+            calc_end_date,
+            calc_end_date,
             None,
         );
 
@@ -313,9 +510,9 @@ mod tests {
 
         let us = UnitedStates::new(UnitedStatesType::Settlement);
         let cal = Calendar::UnitedStates(us);
-        let close_data = Rc::new(DailyClosePrice::new(
+        let close_data = Arc::new(DailyClosePrice::new(
             history_map,
-            DEFAULT_CLOSING_TIME.clone(),
+            DEFAULT_CLOSING_TIME,
             UtcOffset::from_hms(NEW_YORK_OFFSET.0, NEW_YORK_OFFSET.1, NEW_YORK_OFFSET.2).unwrap(),
             cal.clone(),
             "SOFR1D".to_string(),
@@ -327,7 +524,7 @@ mod tests {
             None,
             zero_curve.clone(),
             close_data.clone(),
-            evaluation_date.borrow().get_date(),
+            evaluation_date.read().unwrap().get_date(),
             Some(&compound_tenor),
             &calendar,
             &daycounter,
@@ -349,4 +546,176 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_rfr_convention() -> Result<()> {
+        let dt = datetime!(2024-01-10 16:30:00 -05:00);
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(dt)));
+        let daycounter = DayCountConvention::Actual360;
+        let compound_tenor = Tenor::new_from_string("1D")?;
+        let calendar = JointCalendar::new(vec![Calendar::UnitedStates(UnitedStates::new(
+            UnitedStatesType::Sofr,
+        ))])?;
+
+        // upward sloping forwards
+        let curve_data = VectorData::new(
+            array![0.02, 0.06],
+            None,
+            Some(array![0.1, 0.5]),
+            Some(dt),
+            Currency::USD,
+            "USDOIS".to_string(),
+            StaticId::from_str("USDOIS", "KAP"),
+        )?;
+        let zero_curve = Arc::new(RwLock::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &curve_data,
+            "USDOIS".to_string(),
+            StaticId::from_str("USDOIS", "KAP"),
+        )?));
+
+        // the period started before the evaluation date and the past fixings are 5%
+        let calc_start_date = datetime!(2024-01-03 16:30:00 -05:00);
+        let calc_end_date = datetime!(2024-04-03 16:30:00 -05:00);
+        let base_schedule = BaseSchedule::new(
+            calc_start_date,
+            calc_start_date,
+            calc_end_date,
+            calc_end_date,
+            None,
+        );
+        let mut history_map = FxHashMap::default();
+        let mut date = datetime!(2023-12-15 16:30:00 -05:00);
+        while date < dt {
+            history_map.insert(date.date(), 0.05);
+            date += Duration::days(1);
+        }
+        let close_data = Arc::new(DailyClosePrice::new(
+            history_map,
+            DEFAULT_CLOSING_TIME,
+            UtcOffset::from_hms(NEW_YORK_OFFSET.0, NEW_YORK_OFFSET.1, NEW_YORK_OFFSET.2).unwrap(),
+            Calendar::UnitedStates(UnitedStates::new(UnitedStatesType::Settlement)),
+            "SOFR1D".to_string(),
+            StaticId::from_str("SOFR1D", "KAP"),
+        ));
+
+        let coupon = |convention: RfrConvention| -> Result<Real> {
+            RateIndex::new(
+                StaticId::from_str("SOFR1D", "Undefined"),
+                Tenor::new_from_string("1D")?,
+                Currency::USD,
+                "SOFR1D".to_string(),
+            )?
+            .with_rfr_convention(convention)
+            .get_coupon_amount(
+                &base_schedule,
+                None,
+                zero_curve.clone(),
+                close_data.clone(),
+                &dt,
+                Some(&compound_tenor),
+                &calendar,
+                &daycounter,
+                0,
+            )
+        };
+
+        let plain = coupon(RfrConvention::default())?;
+        let lookback = coupon(RfrConvention::new(5, 0, false))?;
+        let lockout = coupon(RfrConvention::new(0, 5, false))?;
+        let shifted = coupon(RfrConvention::new(5, 0, true))?;
+
+        let period_rate = plain * 360.0 / 91.0;
+        assert!(period_rate > 0.02 && period_rate < 0.06, "period rate: {}", period_rate);
+        // more observations fall on the 5% past fixings
+        assert!(lookback > plain, "lookback: {}, plain: {}", lookback, plain);
+        // the last (highest) forwards are replaced by the earlier one
+        assert!(lockout < plain, "lockout: {}, plain: {}", lockout, plain);
+        // same observations, but weighted by the observation period
+        assert!((shifted - lookback).abs() < 1.0e-3, "shifted: {}, lookback: {}", shifted, lookback);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stub_rate() -> Result<()> {
+        let dt = datetime!(2024-01-10 16:30:00 -05:00);
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(dt)));
+        let daycounter = DayCountConvention::Actual360;
+        let calendar = JointCalendar::new(vec![Calendar::UnitedStates(UnitedStates::new(
+            UnitedStatesType::Settlement,
+        ))])?;
+        let curve_data = VectorData::new(
+            array![0.02, 0.06],
+            None,
+            Some(array![0.1, 0.5]),
+            Some(dt),
+            Currency::USD,
+            "USDIRS".to_string(),
+            StaticId::from_str("USDIRS", "KAP"),
+        )?;
+        let zero_curve = Arc::new(RwLock::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &curve_data,
+            "USDIRS".to_string(),
+            StaticId::from_str("USDIRS", "KAP"),
+        )?));
+        let rate_index = RateIndex::new(
+            StaticId::from_str("USD Libor 3M", "KRX"),
+            Tenor::new(0, 3, 0),
+            Currency::USD,
+            "USD Libor 3M".to_string(),
+        )?
+        .with_stub_tenors(vec![Tenor::new(0, 3, 0), Tenor::new(0, 1, 0)]);
+
+        // a two months stub
+        let fixing_date = datetime!(2024-02-01 16:30:00 -05:00);
+        let calc_start_date = datetime!(2024-02-02 16:30:00 -05:00);
+        let calc_end_date = datetime!(2024-04-02 16:30:00 -05:00);
+        let stub = BaseSchedule::new(
+            fixing_date,
+            calc_start_date,
+            calc_end_date,
+            calc_end_date,
+            None,
+        )
+        .with_stub(true);
+
+        let curve = zero_curve.read().unwrap();
+        let rate_1m = curve.get_forward_rate_between_dates(
+            &fixing_date,
+            &datetime!(2024-03-01 16:30:00 -05:00),
+            Compounding::Simple,
+        )?;
+        let rate_3m = curve.get_forward_rate_between_dates(
+            &fixing_date,
+            &datetime!(2024-05-01 16:30:00 -05:00),
+            Compounding::Simple,
+        )?;
+        // 60 days between 29 days (1M) and 90 days (3M) from the start
+        let expected = rate_1m + (rate_3m - rate_1m) * 31.0 / 61.0;
+        let stub_rate = rate_index.get_stub_rate(&stub, &curve)?;
+        assert!((stub_rate - expected).abs() < 1.0e-6);
+        assert!(rate_1m < stub_rate && stub_rate < rate_3m);
+        drop(curve);
+
+        let coupon = |base_schedule: &BaseSchedule| {
+            rate_index.get_coupon_amount(
+                base_schedule,
+                None,
+                zero_curve.clone(),
+                Arc::new(DailyClosePrice::default()),
+                &dt,
+                None,
+                &calendar,
+                &daycounter,
+                1,
+            )
+        };
+        let frac = 60.0 / 360.0;
+        assert!((coupon(&stub)? - stub_rate * frac).abs() < 1.0e-6);
+        // a regular period takes the 3M rate
+        let regular = stub.clone().with_stub(false);
+        assert!((coupon(&regular)? - rate_3m * frac).abs() < 1.0e-6);
+        Ok(())
+    }
 }
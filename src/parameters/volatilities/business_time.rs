@@ -0,0 +1,132 @@
+use crate::definitions::{Real, Time};
+//
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use time::{Date, Duration, OffsetDateTime, Weekday};
+
+/// Business time (volatility time) in which the variance accumulates.
+///
+/// Each calendar day has a variance weight: 1.0 on weekdays, weekend_weight on weekends,
+/// and the given weight on event dates (e.g., earnings or election days).
+/// The weight of a date is accrued over the day ending at the date.
+/// The business time is the accumulated weight normalized so that a regular year
+/// (without events) has the same length as the calendar year.
+/// Therefore, the long-dated variances are nearly unchanged,
+/// while the short-dated options around weekends or events get the variance they actually carry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BusinessTime {
+    weekend_weight: Real,
+    event_weights: FxHashMap<Date, Real>,
+}
+
+impl Default for BusinessTime {
+    fn default() -> BusinessTime {
+        BusinessTime {
+            weekend_weight: 1.0,
+            event_weights: FxHashMap::default(),
+        }
+    }
+}
+
+impl BusinessTime {
+    pub fn new(weekend_weight: Real) -> BusinessTime {
+        BusinessTime {
+            weekend_weight,
+            event_weights: FxHashMap::default(),
+        }
+    }
+
+    pub fn with_event_weights(mut self, event_weights: FxHashMap<Date, Real>) -> BusinessTime {
+        self.event_weights = event_weights;
+        self
+    }
+
+    pub fn add_event_weight(&mut self, date: Date, weight: Real) {
+        self.event_weights.insert(date, weight);
+    }
+
+    pub fn get_weekend_weight(&self) -> Real {
+        self.weekend_weight
+    }
+
+    pub fn get_event_weights(&self) -> &FxHashMap<Date, Real> {
+        &self.event_weights
+    }
+
+    fn regular_weight(&self, date: &Date) -> Real {
+        match date.weekday() {
+            Weekday::Saturday | Weekday::Sunday => self.weekend_weight,
+            _ => 1.0,
+        }
+    }
+
+    pub fn get_day_weight(&self, date: &Date) -> Real {
+        match self.event_weights.get(date) {
+            Some(weight) => *weight,
+            None => self.regular_weight(date),
+        }
+    }
+
+    /// business time of the period of calendar time t from the datetime
+    pub fn get_business_time(&self, datetime: &OffsetDateTime, t: Time) -> Time {
+        if t <= 0.0 || (self.weekend_weight == 1.0 && self.event_weights.is_empty()) {
+            return t;
+        }
+        let days = t * 365.0;
+        let full_days = days.floor() as i64;
+        let start = datetime.date();
+        let end = start + Duration::days(full_days);
+
+        let full_weeks = full_days / 7;
+        let mut res: Real = full_weeks as Real * (5.0 + 2.0 * self.weekend_weight);
+        for k in (full_weeks * 7 + 1)..=full_days {
+            res += self.regular_weight(&(start + Duration::days(k)));
+        }
+        for (date, weight) in self.event_weights.iter() {
+            if *date > start && *date <= end {
+                res += weight - self.regular_weight(date);
+            }
+        }
+        res += (days - full_days as Real) * self.get_day_weight(&(end + Duration::days(1)));
+
+        let average_weight = (5.0 + 2.0 * self.weekend_weight) / 7.0;
+        res / (365.0 * average_weight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, datetime};
+
+    #[test]
+    fn test_business_time() {
+        // 2024-01-05 is Friday
+        let friday = datetime!(2024-01-05 16:00:00 +09:00);
+        let one_day: Time = 1.0 / 365.0;
+
+        let calendar_time = BusinessTime::default();
+        assert_eq!(calendar_time.get_business_time(&friday, 3.0 * one_day), 3.0 * one_day);
+
+        let business_time = BusinessTime::new(0.0);
+        // regular year keeps its length
+        let year = business_time.get_business_time(&friday, 364.0 * one_day);
+        assert!((year - 364.0 * one_day).abs() < 1.0e-5, "year: {}", year);
+        // Friday to Monday accrues only Monday's variance
+        let weekend = business_time.get_business_time(&friday, 3.0 * one_day);
+        assert!((weekend - 1.4 * one_day).abs() < 1.0e-6, "weekend: {}", weekend);
+
+        // an earnings day on Monday carries three days of variance
+        let mut event_weights = FxHashMap::default();
+        event_weights.insert(date!(2024 - 01 - 08), 3.0);
+        let event_time = BusinessTime::new(0.0).with_event_weights(event_weights);
+        let with_event = event_time.get_business_time(&friday, 3.0 * one_day);
+        assert!((with_event - 3.0 * weekend).abs() < 1.0e-6, "with event: {}", with_event);
+        // the event is behind after Monday
+        let tuesday = datetime!(2024-01-09 16:00:00 +09:00);
+        assert_eq!(
+            event_time.get_business_time(&tuesday, 7.0 * one_day),
+            business_time.get_business_time(&tuesday, 7.0 * one_day)
+        );
+    }
+}
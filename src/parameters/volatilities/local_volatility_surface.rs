@@ -1,12 +1,17 @@
 use crate::data::{surface_data::SurfaceData, value_data::ValueData};
 use crate::definitions::{Real, Time};
-use crate::enums::StickynessType;
+use crate::enums::{StickynessType, SurfaceInterpolation, VolatilityTimeInterpolation};
 use crate::evaluation_date::EvaluationDate;
-use crate::math::interpolator::ExtraPolationType;
-use crate::math::interpolators::bilinear_interpolator::BilinearInterpolator;
-use crate::math::interpolators::linear_interpolator::LinearInterpolator1D;
+use crate::math::interpolator::{ExtraPolationType, Interpolator2D};
+use crate::math::interpolators::{
+    bicubic_interpolator::BicubicInterpolator, bilinear_interpolator::BilinearInterpolator,
+    linear_interpolator::LinearInterpolator1D,
+    thin_plate_spline_interpolator::ThinPlateSplineInterpolator,
+};
+use crate::parameters::equity_forward_curve::EquityForwardCurve;
 use crate::parameters::market_price::MarketPrice;
 use crate::parameters::{
+    volatilities::business_time::BusinessTime,
     volatilities::volatiltiy_interpolator::VolatilityInterplator, volatility::VolatilityTrait,
     zero_curve::ZeroCurve,
 };
@@ -14,7 +19,7 @@ use crate::Tenor;
 use crate::time::calendar_trait::CalendarTrait;
 use crate::time::calendars::nullcalendar::NullCalendar;
 use anyhow::{anyhow, Context, Result};
-use std::{cell::RefCell, rc::Rc};
+use std::sync::{Arc, RwLock};
 //
 use ndarray::{Array1, Array2};
 use time::OffsetDateTime;
@@ -30,16 +35,18 @@ pub struct LocalVolatilitySurface {
     imvol_spot: Real,
     forward_monenyess_imvol: BilinearInterpolator,
     //
-    evaluation_date: Rc<RefCell<EvaluationDate>>,
-    market_price: Rc<RefCell<MarketPrice>>,
-    collateral_curve: Rc<RefCell<ZeroCurve>>,
-    borrowing_curve: Rc<RefCell<ZeroCurve>>,
+    evaluation_date: Arc<RwLock<EvaluationDate>>,
+    market_price: Arc<RwLock<MarketPrice>>,
+    forward_curve: EquityForwardCurve,
     //
     stickyness_type: StickynessType,
+    business_time: Option<BusinessTime>,
+    time_interpolation: VolatilityTimeInterpolation,
+    surface_interpolation: SurfaceInterpolation,
     #[allow(dead_code)]
     lv_interpolator: VolatilityInterplator,
-    #[allow(dead_code)]
-    local_volatility: BilinearInterpolator,
+    // the Dupire local volatility on (imvol_maturity_times, forward moneyness), made in build
+    local_volatility: Option<BicubicInterpolator>,
     //
     name: String,
     id: StaticId,
@@ -48,10 +55,10 @@ pub struct LocalVolatilitySurface {
 impl LocalVolatilitySurface {
     #[allow(clippy::too_many_arguments)]
     pub fn initialize(
-        evaluation_date: Rc<RefCell<EvaluationDate>>,
-        market_price: Rc<RefCell<MarketPrice>>,
-        collateral_curve: Rc<RefCell<ZeroCurve>>,
-        borrowing_curve: Rc<RefCell<ZeroCurve>>,
+        evaluation_date: Arc<RwLock<EvaluationDate>>,
+        market_price: Arc<RwLock<MarketPrice>>,
+        collateral_curve: Arc<RwLock<ZeroCurve>>,
+        borrowing_curve: Arc<RwLock<ZeroCurve>>,
         stickyness_type: StickynessType,
         lv_interpolator: VolatilityInterplator,
         name: String,
@@ -67,19 +74,110 @@ impl LocalVolatilitySurface {
             imvol_spot: 0.0,
             //
             evaluation_date,
+            forward_curve: EquityForwardCurve::new(
+                market_price.clone(),
+                collateral_curve,
+                borrowing_curve,
+            ),
             market_price,
-            collateral_curve,
-            borrowing_curve,
             //
             stickyness_type,
+            business_time: None,
+            time_interpolation: VolatilityTimeInterpolation::default(),
+            surface_interpolation: SurfaceInterpolation::default(),
             lv_interpolator,
-            local_volatility: BilinearInterpolator::default(),
+            local_volatility: None,
             //
             name,
             id,
         }
     }
 
+    /// the variances accumulate in the business time instead of the calendar time
+    pub fn with_business_time(mut self, business_time: BusinessTime) -> LocalVolatilitySurface {
+        self.business_time = Some(business_time);
+        self
+    }
+
+    pub fn get_business_time(&self) -> Option<&BusinessTime> {
+        self.business_time.as_ref()
+    }
+
+    /// interpolation scheme in the expiry dimension (total variance by default).
+    /// This must be set before with_market_surface since the market expiries are interpolated there.
+    pub fn with_time_interpolation(
+        mut self,
+        time_interpolation: VolatilityTimeInterpolation,
+    ) -> LocalVolatilitySurface {
+        self.time_interpolation = time_interpolation;
+        self
+    }
+
+    pub fn get_time_interpolation(&self) -> VolatilityTimeInterpolation {
+        self.time_interpolation
+    }
+
+    /// interpolation of the market quotes on (expiry, spot moneyness) (bilinear by default).
+    /// This must be set before with_market_surface since the market quotes are interpolated there.
+    pub fn with_surface_interpolation(
+        mut self,
+        surface_interpolation: SurfaceInterpolation,
+    ) -> LocalVolatilitySurface {
+        self.surface_interpolation = surface_interpolation;
+        self
+    }
+
+    pub fn get_surface_interpolation(&self) -> SurfaceInterpolation {
+        self.surface_interpolation
+    }
+
+    /// t_knots are the expiries of the interpolator, between which the total variance is interpolated
+    fn interpolate_in_time(
+        &self,
+        interpolator: &dyn Interpolator2D,
+        t_knots: &Array1<Time>,
+        t: Time,
+        moneyness: Real,
+    ) -> Result<Real> {
+        match self.time_interpolation {
+            VolatilityTimeInterpolation::Volatility => interpolator.interpolate(t, moneyness),
+            VolatilityTimeInterpolation::TotalVariance => {
+                interpolator.interpolate_total_variance_between(t_knots, t, moneyness)
+            }
+        }
+    }
+
+    fn interpolate_implied_volatility(&self, t: Time, forward_moneyness: Real) -> Result<Real> {
+        self.interpolate_in_time(
+            &self.forward_monenyess_imvol,
+            self.forward_monenyess_imvol.get_t_domain(),
+            t,
+            forward_moneyness,
+        )
+    }
+
+    /// the volatility of a surface flat in both the expiry and the moneyness in calendar time,
+    /// e.g., a surface made by with_constant_volatility without a business time
+    pub fn get_flat_volatility(&self) -> Option<Real> {
+        if self.business_time.is_some() {
+            return None;
+        }
+        let vol = *self.interpolated_imvol.first()?;
+        match self.interpolated_imvol.iter().all(|v| *v == vol) {
+            true => Some(vol),
+            false => None,
+        }
+    }
+
+    fn get_variance_time(&self, t: Time) -> Time {
+        match self.business_time.as_ref() {
+            Some(business_time) => {
+                business_time.get_business_time(self.evaluation_date.read().unwrap().get_date(), t)
+            }
+            None => t,
+        }
+    }
+
     pub fn with_market_surface(
         mut self,
         market_implied_volatility_surface: &SurfaceData,
@@ -87,7 +185,7 @@ impl LocalVolatilitySurface {
         vega_matrix_spot_moneyness: Array1<Real>,
     ) -> Result<LocalVolatilitySurface> {
         let given_dates = market_implied_volatility_surface.get_dates();
-        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let eval_date = self.evaluation_date.read().unwrap().get_date_clone();
 
         if given_dates.windows(2).any(|w| w[0] > w[1]) {
             let err = || {
@@ -146,15 +244,32 @@ impl LocalVolatilitySurface {
         let given_strikes = market_implied_volatility_surface.get_strike();
         let given_spot_moneyness = given_strikes / self.imvol_spot;
 
-        let bilinear_interpolator = BilinearInterpolator::new_from_rectangle_data(
-            given_times,
-            given_spot_moneyness,
-            market_implied_volatility_surface.get_value().to_owned(),
-            true,
-            ExtraPolationType::Flat,
-            true,
-            ExtraPolationType::Flat,
-        )?;
+        let given_values = market_implied_volatility_surface.get_value().to_owned();
+        let market_interpolator: Box<dyn Interpolator2D> = match self.surface_interpolation {
+            SurfaceInterpolation::Bilinear => Box::new(BilinearInterpolator::new_from_rectangle_data(
+                given_times.clone(),
+                given_spot_moneyness,
+                given_values,
+                true,
+                ExtraPolationType::Flat,
+                true,
+                ExtraPolationType::Flat,
+            )?),
+            SurfaceInterpolation::Bicubic => Box::new(BicubicInterpolator::new(
+                given_times.clone(),
+                given_spot_moneyness,
+                given_values,
+                true,
+            )?),
+            SurfaceInterpolation::ThinPlateSpline => {
+                Box::new(ThinPlateSplineInterpolator::new_from_rectangle_data(
+                    &given_times,
+                    &given_spot_moneyness,
+                    &given_values,
+                    0.0,
+                )?)
+            }
+        };
 
         self.imvol_maturity_dates = vega_structure_tenors
             .iter()
@@ -207,8 +322,12 @@ impl LocalVolatilitySurface {
         ));
         for i in 0..self.imvol_maturity_times.len() {
             for j in 0..self.imvol_spot_moneyness.len() {
-                self.interpolated_imvol[[i, j]] = bilinear_interpolator
-                    .interpolate(self.imvol_maturity_times[i], self.imvol_spot_moneyness[j])?;
+                self.interpolated_imvol[[i, j]] = self.interpolate_in_time(
+                    market_interpolator.as_ref(),
+                    &given_times,
+                    self.imvol_maturity_times[i],
+                    self.imvol_spot_moneyness[j],
+                )?;
             }
         }
 
@@ -221,7 +340,7 @@ impl LocalVolatilitySurface {
         vega_structure_tenors: Vec<Tenor>,
         vega_matrix_spot_moneyness: Array1<Real>,
     ) -> Result<LocalVolatilitySurface> {
-        let eval_date = self.evaluation_date.borrow().get_date_clone();
+        let eval_date = self.evaluation_date.read().unwrap().get_date_clone();
         let dates = vega_structure_tenors
             .iter()
             .map(|tenor| tenor.apply(&eval_date))
@@ -237,7 +356,7 @@ impl LocalVolatilitySurface {
         self.imvol_maturity_dates = dates;
         self.imvol_maturity_times = times;
         self.imvol_spot_moneyness = vega_matrix_spot_moneyness;
-        self.imvol_spot = self.market_price.borrow().get_value();
+        self.imvol_spot = self.market_price.read().unwrap().get_value();
         let vol = constant_volatility.get_value();
 
         self.interpolated_imvol = Array2::from_elem(
@@ -265,7 +384,7 @@ impl LocalVolatilitySurface {
                 let mut forward_vector: Vec<Real> = Vec::new();
                 for i in 0..self.imvol_maturity_dates.len() {
                     let fwd = self.get_forward(
-                        self.market_price.borrow().get_value(),
+                        self.market_price.read().unwrap().get_value(),
                         &self.imvol_maturity_dates[i],
                     )?;
                     forward_vector.push(fwd);
@@ -316,34 +435,90 @@ impl LocalVolatilitySurface {
             true,
             ExtraPolationType::Flat,
         )?;
+        self.local_volatility = self.dupire_local_volatility()?;
 
         Ok(())
     }
 
-    fn get_forward(&self, spot: Real, maturity: &OffsetDateTime) -> Result<Real> {
-        let collateral_discount = self
-            .collateral_curve
-            .borrow()
-            .get_discount_factor_at_date(maturity)
-            .with_context(|| {
-                anyhow!(
-                    "({}:{}) failed to get collateral discount factor\n\
-                    maturity: {}, name: {}, code: {}",
-                    file!(),
-                    line!(),
-                    maturity,
-                    self.name,
-                    self.id,
-                )
-            })?;
+    /// The Dupire local volatility in the forward log-moneyness y = ln k and the total variance w(t, y):
+    ///
+    /// σ_loc^2 = ∂w/∂t / (1 - y/w ∂w/∂y + 1/4 (-1/4 - 1/w + y^2/w^2) (∂w/∂y)^2 + 1/2 ∂^2w/∂y^2)
+    ///
+    /// on (imvol_maturity_times, imvol_spot_moneyness as forward moneyness), where w is the bicubic spline
+    /// of the total variances (w = 0 at t = 0) so that the derivatives are smooth.
+    /// None if the grid does not have two increasing positive expiries and moneyness
+    fn dupire_local_volatility(&self) -> Result<Option<BicubicInterpolator>> {
+        let times = &self.imvol_maturity_times;
+        let moneyness = &self.imvol_spot_moneyness;
+        let (n, m) = (times.len(), moneyness.len());
+        let is_grid = |domain: &Array1<Real>| {
+            domain.len() >= 2
+                && domain[0] > 0.0
+                && domain.windows(2).into_iter().all(|w| w[0] < w[1])
+        };
+        if !is_grid(times) || !is_grid(moneyness) {
+            return Ok(None);
+        }
 
-        let borrowing_discount = self
-            .borrowing_curve
-            .borrow()
-            .get_discount_factor_at_date(maturity)
+        let log_moneyness = moneyness.mapv(|k| k.ln());
+        let mut total_variance = Array2::zeros((n + 1, m));
+        for i in 0..n {
+            for j in 0..m {
+                total_variance[[i + 1, j]] = self.total_variance(times[i], moneyness[j])?;
+            }
+        }
+        let w = BicubicInterpolator::new(
+            Array1::from_iter(std::iter::once(0.0).chain(times.iter().copied())),
+            log_moneyness.clone(),
+            total_variance,
+            true,
+        )?;
+
+        let (dt, dy) = (1.0e-2, 1.0e-2);
+        let (y_min, y_max) = (log_moneyness[0], log_moneyness[m - 1]);
+        let mut local_volatility = Array2::zeros((n, m));
+        for i in 0..n {
+            for j in 0..m {
+                let (t, y) = (times[i], log_moneyness[j]);
+                let (t_down, t_up) = ((t - dt).max(0.0), (t + dt).min(times[n - 1]));
+                let w_t = (w.interpolate(t_up, y)? - w.interpolate(t_down, y)?) / (t_up - t_down);
+
+                let (y_down, y_up) = ((y - dy).max(y_min), (y + dy).min(y_max));
+                let (w_down, w_mid, w_up) = (
+                    w.interpolate(t, y_down)?,
+                    w.interpolate(t, y)?,
+                    w.interpolate(t, y_up)?,
+                );
+                let w_y = (w_up - w_down) / (y_up - y_down);
+                // the natural spline has no curvature on the boundary
+                let w_yy = if y_down < y && y < y_up {
+                    2.0 * (w_up * (y - y_down) - w_mid * (y_up - y_down) + w_down * (y_up - y))
+                        / ((y_up - y) * (y - y_down) * (y_up - y_down))
+                } else {
+                    0.0
+                };
+                let denominator = 1.0 - y / w_mid * w_y
+                    + 0.25 * (-0.25 - 1.0 / w_mid + y * y / (w_mid * w_mid)) * w_y * w_y
+                    + 0.5 * w_yy;
+                // the butterfly arbitrage makes the denominator non-positive
+                local_volatility[[i, j]] = (w_t.max(0.0) / denominator.max(1.0e-4)).sqrt();
+            }
+        }
+
+        Ok(Some(BicubicInterpolator::new(
+            times.clone(),
+            moneyness.clone(),
+            local_volatility,
+            true,
+        )?))
+    }
+
+    fn get_forward(&self, spot: Real, maturity: &OffsetDateTime) -> Result<Real> {
+        self.forward_curve
+            .get_forward_from_spot(spot, maturity)
             .with_context(|| {
                 anyhow!(
-                    "({}:{}) failed to get borrowing discount factor\n\
+                    "({}:{}) failed to get forward\n\
                     maturity: {}, name: {}, code: {}",
                     file!(),
                     line!(),
@@ -351,27 +526,7 @@ impl LocalVolatilitySurface {
                     self.name,
                     self.id,
                 )
-            })?;
-
-        let dividend_deduction_ratio = self
-            .market_price
-            .borrow()
-            .get_dividend_deduction_ratio(maturity)
-            .with_context(|| {
-                anyhow!(
-                    "({}:{}) failed to get dividend deduction ratio\n\
-                maturity: {}, name: {}, code: {}",
-                    file!(),
-                    line!(),
-                    maturity,
-                    self.name,
-                    self.id
-                )
-            })?;
-
-        let fwd: Real = spot * borrowing_discount / collateral_discount * dividend_deduction_ratio;
-
-        Ok(fwd)
+            })
     }
 
     pub fn get_imvol_matuirty_dates(&self) -> &Vec<OffsetDateTime> {
@@ -389,13 +544,16 @@ impl LocalVolatilitySurface {
 
 impl VolatilityTrait for LocalVolatilitySurface {
     fn get_value(&self, t: Time, forward_moneyness: Real) -> Real {
-        self.forward_monenyess_imvol
-            .interpolate(t, forward_moneyness)
+        self.interpolate_implied_volatility(t, forward_moneyness)
             .expect("Failed to interpolate implied volatility")
     }
 
-    fn get_local_volatility(&self, _t: Time, _forward_moneyness: Real) -> Real {
-        panic!("Not implemented")
+    fn get_local_volatility(&self, t: Time, forward_moneyness: Real) -> Real {
+        self.local_volatility
+            .as_ref()
+            .expect("the local volatility needs at least two expiries and two moneyness in build")
+            .interpolate(t, forward_moneyness)
+            .expect("Failed to interpolate local volatility")
     }
 
     fn get_name(&self) -> &String {
@@ -412,8 +570,7 @@ impl VolatilityTrait for LocalVolatilitySurface {
 
     fn total_variance(&self, t: Time, forward_moneyness: Real) -> Result<Real> {
         let iv = self
-            .forward_monenyess_imvol
-            .interpolate(t, forward_moneyness)
+            .interpolate_implied_volatility(t, forward_moneyness)
             .with_context(|| {
                 anyhow!(
                     "({}:{}) failed to interpolate implied volatility\n\
@@ -427,13 +584,12 @@ impl VolatilityTrait for LocalVolatilitySurface {
                 )
             })?;
 
-        Ok(iv * iv * t)
+        Ok(iv * iv * self.get_variance_time(t))
     }
 
     fn total_deviation(&self, t: Time, forward_moneyness: Real) -> Result<Real> {
         let iv = self
-            .forward_monenyess_imvol
-            .interpolate(t, forward_moneyness)
+            .interpolate_implied_volatility(t, forward_moneyness)
             .with_context(|| {
                 anyhow!(
                     "({}:{}) failed to interpolate implied volatility\n\
@@ -447,7 +603,7 @@ impl VolatilityTrait for LocalVolatilitySurface {
                 )
             })?;
 
-        Ok(iv * t.sqrt())
+        Ok(iv * self.get_variance_time(t).sqrt())
     }
 
     /// bump self.interpolated_imvol and remake forward_monenyess_imvol
@@ -496,13 +652,13 @@ mod tests {
         AndreasenHuge, VolatilityInterplator,
     };
     use crate::definitions::{Real, Time};
-    use crate::enums::StickynessType;
+    use crate::enums::{StickynessType, SurfaceInterpolation};
     use crate::evaluation_date::EvaluationDate;
     use crate::parameters::market_price::MarketPrice;
     use crate::parameters::{volatility::VolatilityTrait, zero_curve::ZeroCurve};
     use anyhow::Result;
     use ndarray::{prelude::*, Array1, Array2};
-    use std::{cell::RefCell, rc::Rc};
+    use std::sync::{Arc, RwLock};
     use time::macros::datetime;
     //
     #[test]
@@ -510,18 +666,18 @@ mod tests {
         let eval_date = datetime!(2024-01-02 00:00:00 +09:00);
         let spot = 350.0;
 
-        let equity = Rc::new(RefCell::new(MarketPrice::new(
+        let equity = Arc::new(RwLock::new(MarketPrice::new(
             spot,
-            eval_date.clone(),
+            eval_date,
             None,
             Currency::KRW,
             "KOSPI2".to_string(),
             static_id::static_id::StaticId::from_str("KOSPI2", "KRX"),
         )));
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date.clone())));
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_date)));
 
         let dummy_data = VectorData::test_curve_data(0.00, Currency::KRW)?;
-        let zero_curve = Rc::new(RefCell::new(ZeroCurve::new(
+        let zero_curve = Arc::new(RwLock::new(ZeroCurve::new(
             evaluation_date.clone(),
             &dummy_data,
             "KRWGOV".to_string(),
@@ -530,7 +686,7 @@ mod tests {
 
         let surface_data = SurfaceData::test_data(
             spot,
-            Some(eval_date.clone()),
+            Some(eval_date),
         )?;
 
         //println!("vector_data: {:?}", dummy_data);
@@ -564,8 +720,7 @@ mod tests {
         let times: Vec<Time> = dates
             .iter()
             .map(|date| time_calculator.get_time_difference(&eval_date, date))
-            .collect::<Vec<Time>>()
-            .into();
+            .collect::<Vec<Time>>();
 
         let vega_spot_moneyness = Array1::linspace(0.6, 1.4, 17);
 
@@ -669,6 +824,103 @@ mod tests {
 
         assert!(max_diff < 1.0e-6, "diff_last: {:?}", diff_last);
 
+        for t in [0.1, 0.5, 1.0, 2.5] {
+            for k in [0.7, 1.0, 1.3] {
+                let lv = local_volatility_surface.get_local_volatility(t, k);
+                assert!(lv.is_finite() && lv > 0.0, "t: {}, k: {}, lv: {}", t, k, lv);
+            }
+        }
+
+        // the smooth interpolations also give the market quotes on the grid
+        for surface_interpolation in [
+            SurfaceInterpolation::Bicubic,
+            SurfaceInterpolation::ThinPlateSpline,
+        ] {
+            let mut surface = LocalVolatilitySurface::initialize(
+                evaluation_date.clone(),
+                equity.clone(),
+                zero_curve.clone(),
+                zero_curve.clone(),
+                StickynessType::default(),
+                VolatilityInterplator::default(),
+                "local vol".to_string(),
+                vol_id,
+            )
+            .with_surface_interpolation(surface_interpolation)
+            .with_market_surface(
+                &surface_data,
+                vec![
+                    crate::Tenor::new_from_string("1M")?,
+                    crate::Tenor::new_from_string("1Y")?,
+                ],
+                vega_spot_moneyness.clone(),
+            )?;
+            surface.build()?;
+            for (i, j) in [(0, 4), (5, 12)] {
+                let v = surface.get_value(times[i], vega_spot_moneyness[j]);
+                assert!(
+                    (v - original_value_sliced[[i, j]]).abs() < 1.0e-4,
+                    "{:?}: {} != {}",
+                    surface_interpolation,
+                    v,
+                    original_value_sliced[[i, j]]
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_volatility_of_flat_surface() -> Result<()> {
+        let eval_date = datetime!(2024-01-02 00:00:00 +09:00);
+        let equity = Arc::new(RwLock::new(MarketPrice::new(
+            350.0,
+            eval_date,
+            None,
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            StaticId::from_str("KOSPI2", "KRX"),
+        )));
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_date)));
+        let zero_curve = Arc::new(RwLock::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &VectorData::test_curve_data(0.03, Currency::KRW)?,
+            "KRWGOV".to_string(),
+            StaticId::from_str("zero curve", "nil"),
+        )?));
+        let tenors = ["1M", "3M", "6M", "1Y", "2Y"]
+            .iter()
+            .map(|t| crate::Tenor::new_from_string(t))
+            .collect::<Result<Vec<_>>>()?;
+        let mut surface = LocalVolatilitySurface::initialize(
+            evaluation_date,
+            equity,
+            zero_curve.clone(),
+            zero_curve,
+            StickynessType::default(),
+            VolatilityInterplator::default(),
+            "flat vol".to_string(),
+            StaticId::from_str("flat vol", "KRX"),
+        )
+        .with_constant_volatility(
+            &ValueData::new(
+                0.2,
+                Some(eval_date),
+                Currency::KRW,
+                "flat".to_string(),
+                StaticId::from_str("flat", "KRX"),
+            )?,
+            tenors,
+            Array1::linspace(0.6, 1.4, 9),
+        )?;
+        surface.build()?;
+        for t in [0.05, 0.3, 1.0, 1.9] {
+            for k in [0.6, 0.85, 1.0, 1.25] {
+                let lv = surface.get_local_volatility(t, k);
+                assert!((lv - 0.2).abs() < 1.0e-4, "t: {}, k: {}, lv: {}", t, k, lv);
+            }
+        }
         Ok(())
     }
 }
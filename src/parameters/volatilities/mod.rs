@@ -1,3 +1,4 @@
+pub mod business_time;
 pub mod constant_volatility;
 pub mod local_volatility_surface;
 pub mod volatiltiy_interpolator;
@@ -34,6 +34,7 @@ pub trait VolatilityTrait {
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum Volatility {
     ConstantVolatility(ConstantVolatility),
     LocalVolatilitySurface(LocalVolatilitySurface),
@@ -72,6 +73,15 @@ impl Volatility {
         }
     }
 
+    /// Some if the volatility does not depend on the expiry and the moneyness,
+    /// so that the parallel bump is a shift of a single number
+    pub fn get_flat_volatility(&self) -> Option<Real> {
+        match self {
+            Volatility::ConstantVolatility(volatility) => Some(volatility.get_value(0.0, 1.0)),
+            Volatility::LocalVolatilitySurface(volatility) => volatility.get_flat_volatility(),
+        }
+    }
+
     pub fn total_variance(&self, t: Time, forward_moneyness: Real) -> Result<Real> {
         match self {
             Volatility::ConstantVolatility(volatility) => {
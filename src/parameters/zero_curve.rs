@@ -1,8 +1,9 @@
 use crate::currency::Currency;
 use crate::data::vector_data::VectorData;
 use crate::definitions::{Real, Time};
-use crate::enums::Compounding;
+use crate::enums::{Compounding, KeyRateBumpScheme};
 use crate::evaluation_date::EvaluationDate;
+use crate::math::dual::Dual;
 use crate::math::interpolator::ExtraPolationType;
 use crate::math::interpolator::Interpolator1D;
 use crate::math::interpolator::InterpolatorReal1D;
@@ -14,9 +15,10 @@ use time::{macros::datetime, OffsetDateTime};
 //
 use anyhow::{anyhow, Context, Result};
 use ndarray::{array, Array1};
-use std::cell::RefCell;
+use rustc_hash::FxHashMap;
+use std::sync::RwLock;
 use std::fmt::Debug;
-use std::rc::Rc;
+use std::sync::Arc;
 use static_id::static_id::StaticId;
 
 #[derive(Clone, Debug)]
@@ -28,15 +30,22 @@ enum ZeroCurveInterpolator {
 /// ZeroCurve is a curve of zero rates which implements Parameter (Observer) trait.
 /// Input is a vector of dates and a vector of zero rates of Data (observable) type.
 /// when the zero rates are updated, the zero curve will be updated.
+///
+/// If base_curves are given, the curve is a spread curve (e.g., z-spread over a government curve)
+/// and its own rates are the spreads layered on top of the base curves.
+/// The discount factor is then the product of the base discount factors and the spread discount factor,
+/// so bumps on the base curves are shared by the spread curve.
 #[derive(Clone, Debug)]
 pub struct ZeroCurve {
-    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    evaluation_date: Arc<RwLock<EvaluationDate>>,
     rate_interpolator: ZeroCurveInterpolator,
     interpolated_rates: Array1<Real>,
     discount_times: Array1<Time>,
     discount_factors: Array1<Real>,
     discount_interpolator: LinearInterpolator1D,
     time_calculator: NullCalendar,
+    base_curves: Vec<Arc<RwLock<ZeroCurve>>>,
+    currency: Currency,
     name: String,
     id: StaticId,
 }
@@ -57,9 +66,8 @@ impl ZeroCurve {
     ///
     /// This setup is chosen for afety and clean code but it is not the most efficient way.
     /// I leave the optimization for later.
-
     pub fn new(
-        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        evaluation_date: Arc<RwLock<EvaluationDate>>,
         data: &VectorData,
         name: String,
         id: StaticId,
@@ -119,8 +127,8 @@ impl ZeroCurve {
         //discount_times[0] = - 0.0001;
         for (i, period) in period_leteral.iter().enumerate() {
             discount_times[i] = time_calculator.get_time_difference(
-                &eval_date.borrow().get_date_clone(),
-                &add_period(&eval_date.borrow().get_date_clone(), period),
+                &eval_date.read().unwrap().get_date_clone(),
+                &add_period(&eval_date.read().unwrap().get_date_clone(), period),
             );
         }
 
@@ -151,12 +159,81 @@ impl ZeroCurve {
             discount_factors,
             discount_interpolator,
             time_calculator,
+            base_curves: vec![],
+            currency: data.currency,
             name,
             id,
         };
         Ok(res)
     }
 
+    /// Create a spread curve layered on top of base_curve.
+    /// spread_data is the term structure of spreads (continuous compounding) over the base curve.
+    /// The evaluation date is shared with the base curve.
+    pub fn new_spread_curve(
+        base_curve: Arc<RwLock<ZeroCurve>>,
+        spread_data: &VectorData,
+        name: String,
+        id: StaticId,
+    ) -> Result<ZeroCurve> {
+        if base_curve.read().unwrap().get_id() == id {
+            return Err(anyhow!(
+                "({}:{}) the spread curve ({}) can not be layered on itself",
+                file!(),
+                line!(),
+                name
+            ));
+        }
+        let evaluation_date = base_curve.read().unwrap().get_evaluation_date_clone();
+        let mut res = ZeroCurve::new(evaluation_date, spread_data, name, id)?;
+        res.base_curves = vec![base_curve];
+        Ok(res)
+    }
+
+    /// Create a curve whose discount factor is the product of the given curves' discount factors,
+    /// e.g., a discount curve with a counterparty funding spread curve on top of it.
+    /// The curves are shared, so the bumps on each curve are reflected in the composite curve.
+    pub fn new_composite_curve(
+        curves: Vec<Arc<RwLock<ZeroCurve>>>,
+        name: String,
+        id: StaticId,
+    ) -> Result<ZeroCurve> {
+        if curves.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) no curve is given for the composite curve ({})",
+                file!(),
+                line!(),
+                name
+            ));
+        }
+        let evaluation_date = curves[0].read().unwrap().get_evaluation_date_clone();
+        let zero_data = VectorData::new(
+            array![0.0],
+            None,
+            Some(array![0.0]),
+            None,
+            Currency::NIL,
+            name.clone(),
+            id,
+        )?;
+        let mut res = ZeroCurve::new(evaluation_date, &zero_data, name, id)?;
+        res.currency = curves[0].read().unwrap().get_currency();
+        res.base_curves = curves;
+        Ok(res)
+    }
+
+    pub fn get_base_curves(&self) -> &Vec<Arc<RwLock<ZeroCurve>>> {
+        &self.base_curves
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn is_spread_curve(&self) -> bool {
+        !self.base_curves.is_empty()
+    }
+
     /// For self.interpolated_rates in the time_interval (date1 < date <= date2)
     /// bump self.interpolated_rates by bump_val
     /// then reset
@@ -167,7 +244,7 @@ impl ZeroCurve {
         date2: Option<&OffsetDateTime>,
         bump_val: Real,
     ) -> Result<()> {
-        let dt = &self.evaluation_date.borrow().get_date_clone();
+        let dt = &self.evaluation_date.read().unwrap().get_date_clone();
 
         let t1 = match date1 {
             Some(d) => self.time_calculator.get_time_difference(dt, d),
@@ -183,7 +260,7 @@ impl ZeroCurve {
     }
 
     /// For self.interpolated_rates in the time_interval (t1 < t <= t2)
-    /// bump self.interpolated_rates by bump_val
+    /// bump self.interpolated_rates by bump_val (only the spread if it is a spread curve)
     /// then reset
     /// self.rate_interpolator, self.discount_factors, and self.discount_interpolator
     pub fn bump_time_interval(
@@ -218,6 +295,93 @@ impl ZeroCurve {
             .mapv(|x| if (x > t1) & (x <= t2) { 1.0 } else { 0.0 });
 
         self.interpolated_rates = &self.interpolated_rates + mask * bump_val;
+        self.reset_interpolators()
+    }
+
+    /// weight of the key-rate bump of key_times[index] on the zero rate at t
+    fn key_rate_weight(
+        key_times: &[Time],
+        index: usize,
+        scheme: KeyRateBumpScheme,
+        t: Time,
+    ) -> Real {
+        let key = key_times[index];
+        let last = key_times.len() - 1;
+        match scheme {
+            KeyRateBumpScheme::Triangle => {
+                if t <= key {
+                    if index == 0 {
+                        1.0
+                    } else if t <= key_times[index - 1] {
+                        0.0
+                    } else {
+                        (t - key_times[index - 1]) / (key - key_times[index - 1])
+                    }
+                } else if index == last {
+                    1.0
+                } else if t >= key_times[index + 1] {
+                    0.0
+                } else {
+                    (key_times[index + 1] - t) / (key_times[index + 1] - key)
+                }
+            }
+            KeyRateBumpScheme::FlatForward => {
+                // the forwards on (start, end] are bumped, and the zero rate is their average on (0, t]
+                let start = if index == 0 { 0.0 } else { key_times[index - 1] };
+                let end = if index == last { Time::MAX } else { key };
+                if t <= 0.0 {
+                    return if index == 0 { 1.0 } else { 0.0 };
+                }
+                let overlap = t.min(end) - start;
+                if overlap <= 0.0 {
+                    0.0
+                } else {
+                    overlap / t
+                }
+            }
+        }
+    }
+
+    /// Bump the zero rates by bump_val with the key-rate bump shape of key_times[index].
+    /// key_times must be increasing and positive.
+    /// The bumps of all the key times add up to the parallel bump of bump_val
+    pub fn bump_key_rate(
+        &mut self,
+        key_times: &[Time],
+        index: usize,
+        scheme: KeyRateBumpScheme,
+        bump_val: Real,
+    ) -> Result<()> {
+        if index >= key_times.len() {
+            return Err(anyhow!(
+                "({}:{}) key rate index {} is out of the key times {:?} in {}",
+                file!(),
+                line!(),
+                index,
+                key_times,
+                self.name,
+            ));
+        }
+        if key_times[0] <= 0.0 || key_times.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(anyhow!(
+                "({}:{}) key times {:?} must be positive and increasing in {}",
+                file!(),
+                line!(),
+                key_times,
+                self.name,
+            ));
+        }
+
+        let bumps = self
+            .discount_times
+            .mapv(|t| ZeroCurve::key_rate_weight(key_times, index, scheme, t) * bump_val);
+        self.interpolated_rates = &self.interpolated_rates + bumps;
+        self.reset_interpolators()
+    }
+
+    /// reset self.rate_interpolator, self.discount_factors, and self.discount_interpolator
+    /// from self.interpolated_rates
+    fn reset_interpolators(&mut self) -> Result<()> {
         // reset self.rate_interpolator
         if self.interpolated_rates.len() == 1 {
             self.rate_interpolator = ZeroCurveInterpolator::Constant(ConstantInterpolator1D::new(
@@ -250,13 +414,13 @@ impl ZeroCurve {
 
     pub fn dummy_curve() -> Result<ZeroCurve> {
         let dt = EvaluationDate::new(datetime!(1970-01-01 00:00:00 UTC));
-        let evaluation_date = Rc::new(RefCell::new(dt));
+        let evaluation_date = Arc::new(RwLock::new(dt));
         let name = "dummy curve in ZeroCurve::null_curve".to_string();
         let data = VectorData::new(
             array![0.0],
             Some(vec![datetime!(2080-01-01 00:00:00 UTC)]), // dummy date
             None,
-            Some(evaluation_date.borrow().get_date_clone()),
+            Some(evaluation_date.read().unwrap().get_date_clone()),
             Currency::NIL,
             name.clone(),
             StaticId::from_str(name.as_str(), "nil"),
@@ -271,21 +435,56 @@ impl ZeroCurve {
         )
     }
     pub fn get_discount_factor(&self, time: Time) -> Result<Real> {
-        self.discount_interpolator.interpolate(time)
+        let mut res = self.discount_interpolator.interpolate(time)?;
+        for base_curve in self.base_curves.iter() {
+            res *= base_curve.read().unwrap().get_discount_factor(time)?;
+        }
+        Ok(res)
+    }
+
+    /// discount factor as a dual number of the parallel shift of the zero rates (only the spread for a spread curve).
+    /// shift is the dual variable at 0, and the derivative is that of the interpolated discount factors
+    /// exp(-(r_i + shift) t_i), so it matches the bump by bump_time_interval(None, None, shift) in the limit.
+    pub fn get_discount_factor_dual<const N: usize>(
+        &self,
+        time: Time,
+        shift: Dual<N>,
+    ) -> Result<Dual<N>> {
+        let discount_factor = self.get_discount_factor(time)?;
+        let slopes = -(&self.discount_times * &self.discount_factors);
+        let mut slope = LinearInterpolator1D::new(
+            self.discount_times.clone(),
+            slopes,
+            ExtraPolationType::None,
+            false,
+        )?
+        .interpolate(time)?;
+        for base_curve in self.base_curves.iter() {
+            slope *= base_curve.read().unwrap().get_discount_factor(time)?;
+        }
+        Ok(shift * slope + discount_factor)
     }
 
     pub fn get_vectorized_discount_factor_for_sorted_time(
         &self,
         times: &Array1<Time>,
     ) -> Result<Array1<Real>> {
-        self.discount_interpolator
-            .vectorized_interpolate_for_sorted_ndarray(times)
+        let mut res = self
+            .discount_interpolator
+            .vectorized_interpolate_for_sorted_ndarray(times)?;
+        for base_curve in self.base_curves.iter() {
+            res = res
+                * base_curve
+                    .read().unwrap()
+                    .get_vectorized_discount_factor_for_sorted_time(times)?;
+        }
+        Ok(res)
     }
 
     pub fn get_discount_factor_at_date(&self, date: &OffsetDateTime) -> Result<Real> {
         let t = self
             .time_calculator
-            .get_time_difference(&self.evaluation_date.borrow().get_date_clone(), date);
+            .get_time_difference(&self.evaluation_date.read().unwrap().get_date_clone(), date);
         if t < 0.0 {
             Err(anyhow!(
                 "(ZeroCurve::get_discount_factor_at_date)\n\
@@ -293,7 +492,7 @@ impl ZeroCurve {
                 An action on negative time is not defined.\n\
                 If it is intentional, check {}:{}",
                 date,
-                self.evaluation_date.borrow().get_date_clone(),
+                self.evaluation_date.read().unwrap().get_date_clone(),
                 file!(),
                 line!()
             ))
@@ -302,6 +501,27 @@ impl ZeroCurve {
         }
     }
 
+    pub fn get_discount_factor_dual_at_date<const N: usize>(
+        &self,
+        date: &OffsetDateTime,
+        shift: Dual<N>,
+    ) -> Result<Dual<N>> {
+        let t = self
+            .time_calculator
+            .get_time_difference(&self.evaluation_date.read().unwrap().get_date_clone(), date);
+        if t < 0.0 {
+            return Err(anyhow!(
+                "({}:{}) date = {:?} is before the evaluation date = {:?} in {}",
+                file!(),
+                line!(),
+                date,
+                self.evaluation_date.read().unwrap().get_date_clone(),
+                self.name,
+            ));
+        }
+        self.get_discount_factor_dual(t, shift)
+    }
+
     pub fn get_vectorized_discount_factor_for_sorted_dates(
         &self,
         dates: &[OffsetDateTime],
@@ -312,6 +532,38 @@ impl ZeroCurve {
             .collect()
     }
 
+    /// discount factors at the dates in the given order.
+    /// The times are interpolated at once in the ascending order
+    /// instead of a binary search and a lock of the evaluation date per date.
+    pub fn get_discount_factors_at_dates(&self, dates: &[OffsetDateTime]) -> Result<Array1<Real>> {
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
+        let times: Vec<Time> = dates
+            .iter()
+            .map(|date| self.time_calculator.get_time_difference(&eval_dt, date))
+            .collect();
+        if let Some(i) = times.iter().position(|t| *t < 0.0) {
+            return Err(anyhow!(
+                "({}:{}) date = {:?} is before the evaluation date = {:?} in {}",
+                file!(),
+                line!(),
+                dates[i],
+                eval_dt,
+                self.name,
+            ));
+        }
+
+        let mut order: Vec<usize> = (0..times.len()).collect();
+        order.sort_by(|&i, &j| times[i].total_cmp(&times[j]));
+        let sorted_times: Array1<Time> = order.iter().map(|&i| times[i]).collect();
+        let sorted_discount_factors = self.get_vectorized_discount_factor_for_sorted_time(&sorted_times)?;
+
+        let mut res = Array1::<Real>::zeros(times.len());
+        for (k, &i) in order.iter().enumerate() {
+            res[i] = sorted_discount_factors[k];
+        }
+        Ok(res)
+    }
+
     pub fn get_discount_factor_between_times(&self, t1: Time, t2: Time) -> Result<Real> {
         match t1 <= t2 {
             true => Ok(self.get_discount_factor(t2)? / self.get_discount_factor(t1)?),
@@ -338,15 +590,9 @@ impl ZeroCurve {
                 let tau = t2 - t1;
 
                 let disc: Real = if tau.abs() > 1e-6 {
-                    match self.get_discount_factor_between_times(t1, t2) {
-                        Ok(d) => d,
-                        Err(e) => return Err(e),
-                    }
+                    self.get_discount_factor_between_times(t1, t2)?
                 } else {
-                    match self.get_discount_factor_between_times(t1, t2 + 1e-5) {
-                        Ok(d) => d,
-                        Err(e) => return Err(e),
-                    }
+                    self.get_discount_factor_between_times(t1, t2 + 1e-5)?
                 };
 
                 match compounding {
@@ -386,10 +632,10 @@ impl ZeroCurve {
 
         let t1 = self
             .time_calculator
-            .get_time_difference(&self.evaluation_date.borrow().get_date_clone(), date1);
+            .get_time_difference(&self.evaluation_date.read().unwrap().get_date_clone(), date1);
         let t2 = self
             .time_calculator
-            .get_time_difference(&self.evaluation_date.borrow().get_date_clone(), date2);
+            .get_time_difference(&self.evaluation_date.read().unwrap().get_date_clone(), date2);
         self.get_forward_rate_between_times(t1, t2, compounding)
     }
 
@@ -398,7 +644,7 @@ impl ZeroCurve {
         date: &OffsetDateTime,
         compounding: Compounding,
     ) -> Result<Real> {
-        let dt = self.evaluation_date.borrow().get_date_clone();
+        let dt = self.evaluation_date.read().unwrap().get_date_clone();
         if date < &dt {
             let error = anyhow!(
                 "({}:{}) date = {:?} < evaluation date = {:?} in ZeroCurve::get_forward_rate_from_evaluation_date", 
@@ -419,20 +665,89 @@ impl ZeroCurve {
     pub fn get_vectorized_short_rate_for_sorted_times(&self, times: &[Time]) -> Result<Vec<Real>> {
         let mut res = vec![0.0; times.len()];
         for i in 0..times.len() {
-            res[i] = match self.get_short_rate_at_time(times[i]) {
-                Ok(rate) => rate,
-                Err(e) => return Err(e),
-            }
+            res[i] = self.get_short_rate_at_time(times[i])?;
         }
         Ok(res)
     }
     pub fn get_instantaneous_forward_rate_from_date(&self, date: &OffsetDateTime) -> Result<Real> {
         let time = self
             .time_calculator
-            .get_time_difference(&self.evaluation_date.borrow().get_date_clone(), date);
+            .get_time_difference(&self.evaluation_date.read().unwrap().get_date_clone(), date);
         self.get_short_rate_at_time(time)
     }
 
+    /// (present value, forward value at horizon, value at horizon on the unchanged curve)
+    /// The cashflows paid in (evaluation_date, horizon_date] are added to both horizon values without reinvestment.
+    fn get_horizon_values(
+        &self,
+        cashflows: &FxHashMap<OffsetDateTime, Real>,
+        horizon_date: &OffsetDateTime,
+    ) -> Result<(Real, Real, Real)> {
+        let eval_date = self.evaluation_date.read().unwrap().get_date_clone();
+        if horizon_date.date() <= eval_date.date() {
+            return Err(anyhow!(
+                "({}:{}) horizon_date = {:?} <= evaluation date = {:?} in ZeroCurve ({})",
+                file!(),
+                line!(),
+                horizon_date,
+                eval_date,
+                self.name
+            ));
+        }
+
+        let horizon = self
+            .time_calculator
+            .get_time_difference(&eval_date, horizon_date);
+        let horizon_discount = self.get_discount_factor(horizon)?;
+
+        let mut present_value: Real = 0.0;
+        let mut forward_value: Real = 0.0;
+        let mut rolled_value: Real = 0.0;
+        for (payment_date, amount) in cashflows.iter() {
+            if payment_date.date() <= eval_date.date() {
+                continue;
+            }
+            let t = self
+                .time_calculator
+                .get_time_difference(&eval_date, payment_date);
+            let disc = self.get_discount_factor(t)?;
+            present_value += amount * disc;
+
+            if payment_date.date() <= horizon_date.date() {
+                forward_value += amount;
+                rolled_value += amount;
+            } else {
+                forward_value += amount * disc / horizon_discount;
+                rolled_value += amount * self.get_discount_factor(t - horizon)?;
+            }
+        }
+        Ok((present_value, forward_value, rolled_value))
+    }
+
+    /// Carry of the cashflows up to the horizon_date.
+    /// The forward rates of the curve are assumed to be realized,
+    /// so the carry is the forward value at the horizon minus the present value.
+    pub fn get_carry(
+        &self,
+        cashflows: &FxHashMap<OffsetDateTime, Real>,
+        horizon_date: &OffsetDateTime,
+    ) -> Result<Real> {
+        let (present_value, forward_value, _) = self.get_horizon_values(cashflows, horizon_date)?;
+        Ok(forward_value - present_value)
+    }
+
+    /// Roll-down of the cashflows up to the horizon_date.
+    /// The curve is assumed to be unchanged in tenor, i.e., the cashflows roll down the curve.
+    /// It is measured against the forward value so that carry + roll_down is the whole horizon pnl.
+    pub fn get_roll_down(
+        &self,
+        cashflows: &FxHashMap<OffsetDateTime, Real>,
+        horizon_date: &OffsetDateTime,
+    ) -> Result<Real> {
+        let (_, forward_value, rolled_value) = self.get_horizon_values(cashflows, horizon_date)?;
+        Ok(rolled_value - forward_value)
+    }
+
     pub fn get_cached_discount_factors_clone(&self) -> Array1<Real> {
         self.discount_factors.clone()
     }
@@ -449,7 +764,7 @@ impl ZeroCurve {
         self.name.clone()
     }
 
-    pub fn get_evaluation_date_clone(&self) -> Rc<RefCell<EvaluationDate>> {
+    pub fn get_evaluation_date_clone(&self) -> Arc<RwLock<EvaluationDate>> {
         self.evaluation_date.clone()
     }
 }
@@ -457,13 +772,13 @@ impl ZeroCurve {
 impl Default for ZeroCurve {
     fn default() -> Self {
         let dt = EvaluationDate::new(datetime!(1970-01-01 00:00:00 UTC));
-        let evaluation_date = Rc::new(RefCell::new(dt));
+        let evaluation_date = Arc::new(RwLock::new(dt));
         let name = "default curve in ZeroCurve::default".to_string();
         let data = VectorData::new(
             array![0.0],
             Some(vec![datetime!(2080-01-01 00:00:00 UTC)]), // dummy date
             None,
-            Some(evaluation_date.borrow().get_date_clone()),
+            Some(evaluation_date.read().unwrap().get_date_clone()),
             Currency::NIL,
             name.clone(),
             StaticId::from_str(name.as_str(), "nil"),
@@ -481,17 +796,17 @@ impl Default for ZeroCurve {
 }
 
 impl ZeroCurve {
-    pub fn new_dummy(eval_dt: Rc<RefCell<EvaluationDate>>) {
+    pub fn new_dummy(eval_dt: Arc<RwLock<EvaluationDate>>) {
         let name = "dummy".to_string();
         let tenor = "50Y".to_string();
         let date = crate::Tenor::new_from_string(&tenor)
             .expect("error in ZeroCurve::new_dummy")
-            .apply(&eval_dt.borrow().get_date_clone());
+            .apply(&eval_dt.read().unwrap().get_date_clone());
         let data = VectorData::new(
             array![0.0],
             Some(vec![date]),
             None,
-            Some(eval_dt.borrow().get_date_clone()),
+            Some(eval_dt.read().unwrap().get_date_clone()),
             Currency::NIL,
             name.clone(),
             StaticId::from_str(name.as_str(), "nil"),
@@ -517,7 +832,7 @@ mod tests {
     use crate::Tenor;
     use anyhow::Ok;
     use ndarray::array;
-    use std::rc::Rc;
+    use std::sync::Arc;
     use time::macros::datetime;
 
     #[test]
@@ -535,9 +850,9 @@ mod tests {
     #[test]
     fn test_dummy_curve() -> Result<()> {
         let eval_dt = datetime!(2021-01-01 00:00:00 UTC);
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_dt)));
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_dt)));
         let _zero_curve = ZeroCurve::dummy_curve()?;
-        let eval_date = evaluation_date.borrow().get_date_clone();
+        let eval_date = evaluation_date.read().unwrap().get_date_clone();
         let date = Tenor::new_from_string("3Y")?.apply(&eval_date);
         assert_eq!(_zero_curve.get_discount_factor_at_date(&date)?, 1.0);
         Ok(())
@@ -546,7 +861,7 @@ mod tests {
     #[test]
     fn test_zero_curve() -> Result<()> {
         let eval_dt = datetime!(2021-01-01 00:00:00 UTC);
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_dt)));
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_dt)));
 
         let param_dt = datetime!(2020-01-01 00:00:00 UTC);
         let dates = vec![
@@ -577,7 +892,7 @@ mod tests {
         )
         .expect("error in test_zero_curve");
 
-        let zero_curve = Rc::new(RefCell::new(_zero_curve));
+        let zero_curve = Arc::new(RwLock::new(_zero_curve));
 
         let cal = NullCalendar::default();
         let times: Vec<Time> = dates
@@ -585,26 +900,211 @@ mod tests {
             .map(|&date| cal.get_time_difference(&param_dt, &date))
             .collect();
 
-        let expected_discount_factors = vec![
-            1.0,
+        let expected_discount_factors = [1.0,
             (-0.02 * times[1]).exp(),
             (-0.025 * times[2]).exp(),
             (-0.03 * times[3]).exp(),
             (-0.035 * times[4]).exp(),
-            (-0.04 * times[5]).exp(),
-        ];
+            (-0.04 * times[5]).exp()];
 
         let allow_error = 1e-6;
         for i in 0..times.len() {
             assert!(
-                (zero_curve.borrow().get_discount_factor(times[i])? - expected_discount_factors[i]) < allow_error,
+                (zero_curve.read().unwrap().get_discount_factor(times[i])? - expected_discount_factors[i]) < allow_error,
                 "i: {}, zero_curve.get_discount_factor(times[i]): {}, expected_discount_factors[i]: {}",
                 i,
-                zero_curve.borrow().get_discount_factor(times[i])?,
+                zero_curve.read().unwrap().get_discount_factor(times[i])?,
                 expected_discount_factors[i]
                 );
         }
 
+        // the batch discount factors are in the given order and equal to the ones of the single dates
+        let zero_curve = zero_curve.read().unwrap();
+        let payment_dates = vec![
+            add_period(&eval_dt, "3Y"),
+            add_period(&eval_dt, "6M"),
+            eval_dt,
+            add_period(&eval_dt, "10Y"),
+            add_period(&eval_dt, "6M"),
+        ];
+        let discount_factors = zero_curve.get_discount_factors_at_dates(&payment_dates)?;
+        assert_eq!(discount_factors.len(), payment_dates.len());
+        for (date, discount_factor) in payment_dates.iter().zip(discount_factors.iter()) {
+            assert_eq!(*discount_factor, zero_curve.get_discount_factor_at_date(date)?);
+        }
+        assert!(zero_curve.get_discount_factors_at_dates(&[])?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_carry_and_roll_down() -> Result<()> {
+        let eval_dt = datetime!(2024-01-02 00:00:00 UTC);
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_dt)));
+        let data = VectorData::new(
+            array![0.03, 0.04],
+            None,
+            Some(array![1.0, 5.0]),
+            None,
+            Currency::KRW,
+            "upward curve".to_string(),
+            StaticId::from_str("upward curve", "test"),
+        )?;
+        let curve = ZeroCurve::new(
+            evaluation_date,
+            &data,
+            "upward curve".to_string(),
+            StaticId::from_str("upward curve", "test"),
+        )?;
+
+        let mut cashflows = FxHashMap::default();
+        cashflows.insert(datetime!(2025-01-02 00:00:00 UTC), 0.04);
+        cashflows.insert(datetime!(2026-01-02 00:00:00 UTC), 0.04);
+        cashflows.insert(datetime!(2027-01-02 00:00:00 UTC), 1.04);
+
+        let horizon = datetime!(2024-07-02 00:00:00 UTC);
+        let carry = curve.get_carry(&cashflows, &horizon)?;
+        let roll_down = curve.get_roll_down(&cashflows, &horizon)?;
+
+        // on a flat forward assumption, the carry is the accrual of the present value
+        let pv: Real = cashflows
+            .iter()
+            .map(|(d, a)| a * curve.get_discount_factor_at_date(d).unwrap())
+            .sum();
+        let horizon_df = curve.get_discount_factor_at_date(&horizon)?;
+        assert!((carry - pv * (1.0 / horizon_df - 1.0)).abs() < 1.0e-6);
+        // upward sloping curve gives a positive roll-down
+        assert!(roll_down > 0.0, "roll_down: {}", roll_down);
+        assert!(curve.get_carry(&cashflows, &eval_dt).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_rate_bump() -> Result<()> {
+        let eval_dt = datetime!(2024-01-02 00:00:00 UTC);
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_dt)));
+        let data = VectorData::new(
+            array![0.03, 0.04],
+            None,
+            Some(array![1.0, 5.0]),
+            None,
+            Currency::KRW,
+            "upward curve".to_string(),
+            StaticId::from_str("upward curve", "test"),
+        )?;
+        let curve = ZeroCurve::new(
+            evaluation_date,
+            &data,
+            "upward curve".to_string(),
+            StaticId::from_str("upward curve", "test"),
+        )?;
+        let key_times = [0.5, 2.0, 5.0, 10.0];
+        let bump = 0.0001;
+
+        let mut parallel = curve.clone();
+        parallel.bump_time_interval(None, None, bump)?;
+        for scheme in [KeyRateBumpScheme::Triangle, KeyRateBumpScheme::FlatForward] {
+            let mut bumped = curve.clone();
+            for i in 0..key_times.len() {
+                bumped.bump_key_rate(&key_times, i, scheme, bump)?;
+            }
+            // the key-rate bumps add up to the parallel bump
+            let diff = &bumped.get_interpolated_rates() - &parallel.get_interpolated_rates();
+            assert!(diff.iter().all(|x| x.abs() < 1.0e-7), "{:?}: {:?}", scheme, diff);
+        }
+
+        // a triangle bump on 2Y does not move the rates outside of (0.5, 5.0)
+        let mut bumped = curve.clone();
+        bumped.bump_key_rate(&key_times, 1, KeyRateBumpScheme::Triangle, bump)?;
+        let t_2y = curve.discount_times.iter().position(|t| (t - 2.0).abs() < 0.01).unwrap();
+        for (i, t) in curve.discount_times.iter().enumerate() {
+            let d = bumped.interpolated_rates[i] - curve.interpolated_rates[i];
+            if *t <= 0.5 || *t >= 5.0 {
+                assert!(d.abs() < 1.0e-9, "t: {}, d: {}", t, d);
+            }
+            if i == t_2y {
+                assert!((d - bump).abs() < 1.0e-5, "t: {}, d: {}", t, d);
+            }
+        }
+        assert!(bumped.bump_key_rate(&key_times, 4, KeyRateBumpScheme::Triangle, bump).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_spread_curve() -> Result<()> {
+        let eval_dt = datetime!(2024-01-02 00:00:00 UTC);
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_dt)));
+        let base_data = VectorData::test_curve_data(0.03, Currency::KRW)?;
+        let spread_data = VectorData::test_curve_data(0.01, Currency::KRW)?;
+        let base_curve = Arc::new(RwLock::new(ZeroCurve::new(
+            evaluation_date,
+            &base_data,
+            "KRWGOV".to_string(),
+            StaticId::from_str("KRWGOV", "test"),
+        )?));
+        let spread_curve = ZeroCurve::new_spread_curve(
+            base_curve.clone(),
+            &spread_data,
+            "KRW AA Spread".to_string(),
+            StaticId::from_str("KRW AA Spread", "test"),
+        )?;
+
+        assert!(spread_curve.is_spread_curve());
+        let t: Time = 2.0;
+        let expected = (-0.04 * t).exp();
+        assert!((spread_curve.get_discount_factor(t)? - expected).abs() < 1.0e-6);
+
+        // bump on the base curve is shared by the spread curve
+        base_curve.write().unwrap().bump_time_interval(None, None, 0.01)?;
+        let expected = (-0.05 * t).exp();
+        assert!((spread_curve.get_discount_factor(t)? - expected).abs() < 1.0e-6);
+
+        let spread_id = StaticId::from_str("KRW AA Spread", "test");
+        assert!(ZeroCurve::new_spread_curve(
+            Arc::new(RwLock::new(spread_curve)),
+            &spread_data,
+            "KRW AA Spread".to_string(),
+            spread_id,
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_composite_curve() -> Result<()> {
+        let eval_dt = datetime!(2024-01-02 00:00:00 UTC);
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_dt)));
+        let discount_data = VectorData::test_curve_data(0.03, Currency::KRW)?;
+        let funding_data = VectorData::test_curve_data(0.005, Currency::KRW)?;
+        let discount_curve = Arc::new(RwLock::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &discount_data,
+            "KRWIRS".to_string(),
+            StaticId::from_str("KRWIRS", "test"),
+        )?));
+        let funding_curve = Arc::new(RwLock::new(ZeroCurve::new(
+            evaluation_date,
+            &funding_data,
+            "CSA Funding Spread".to_string(),
+            StaticId::from_str("CSA Funding Spread", "test"),
+        )?));
+        let composite = ZeroCurve::new_composite_curve(
+            vec![discount_curve, funding_curve.clone()],
+            "KRWIRS + CSA".to_string(),
+            StaticId::from_str("KRWIRS + CSA", "test"),
+        )?;
+
+        let t: Time = 3.0;
+        assert!((composite.get_discount_factor(t)? - (-0.035 * t).exp()).abs() < 1.0e-6);
+
+        funding_curve.write().unwrap().bump_time_interval(None, None, 0.001)?;
+        assert!((composite.get_discount_factor(t)? - (-0.036 * t).exp()).abs() < 1.0e-6);
+        assert!(ZeroCurve::new_composite_curve(
+            vec![],
+            "empty".to_string(),
+            StaticId::from_str("empty", "test")
+        )
+        .is_err());
         Ok(())
     }
 }
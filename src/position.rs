@@ -0,0 +1,208 @@
+use crate::definitions::Real;
+use crate::instrument::{Instrument, InstrumentTrait, Instruments};
+use crate::pricing_engines::calculation_result::CalculationResult;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use static_id::static_id::StaticId;
+use std::sync::Arc;
+
+/// Signed holding of an instrument in a book, e.g., -3 contracts of KOSPI2 futures.
+/// The quantity multiplies the result on the unit notional of the instrument.
+#[derive(Debug, Clone)]
+pub struct Position {
+    instrument: Arc<Instrument>,
+    quantity: Real,
+    book: StaticId,
+}
+
+impl Position {
+    pub fn new(instrument: Arc<Instrument>, quantity: Real, book: StaticId) -> Position {
+        Position {
+            instrument,
+            quantity,
+            book,
+        }
+    }
+
+    pub fn get_instrument(&self) -> &Arc<Instrument> {
+        &self.instrument
+    }
+
+    pub fn get_instrument_id(&self) -> StaticId {
+        self.instrument.get_id()
+    }
+
+    pub fn get_quantity(&self) -> Real {
+        self.quantity
+    }
+
+    pub fn get_book(&self) -> StaticId {
+        self.book
+    }
+}
+
+/// Positions with a single position on an instrument in a book
+#[derive(Debug, Clone, Default)]
+pub struct Positions {
+    positions: Vec<Position>,
+}
+
+impl Positions {
+    pub fn new(positions: Vec<Position>) -> Result<Positions> {
+        for (i, position) in positions.iter().enumerate() {
+            if positions[..i].iter().any(|p| {
+                p.book == position.book && p.get_instrument_id() == position.get_instrument_id()
+            }) {
+                return Err(anyhow!(
+                    "({}:{}) {} has more than one position in the book {}",
+                    file!(),
+                    line!(),
+                    position.get_instrument_id(),
+                    position.book,
+                ));
+            }
+        }
+        Ok(Positions { positions })
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Position> {
+        self.positions.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// instruments to be priced, once for an instrument held in several books
+    pub fn get_instruments(&self) -> Instruments {
+        let mut res: Vec<Arc<Instrument>> = vec![];
+        for position in self.positions.iter() {
+            let inst_id = position.get_instrument_id();
+            if !res.iter().any(|inst| inst.get_id() == inst_id) {
+                res.push(position.instrument.clone());
+            }
+        }
+        Instruments::new(res)
+    }
+
+    /// books in the order of the positions
+    pub fn get_books(&self) -> Vec<StaticId> {
+        let mut res: Vec<StaticId> = vec![];
+        for position in self.positions.iter() {
+            if !res.contains(&position.book) {
+                res.push(position.book);
+            }
+        }
+        res
+    }
+
+    /// book -> instrument id -> result on the quantity of the position
+    pub fn get_position_results(
+        &self,
+        results: &FxHashMap<StaticId, CalculationResult>,
+    ) -> Result<FxHashMap<StaticId, FxHashMap<StaticId, CalculationResult>>> {
+        let mut res: FxHashMap<StaticId, FxHashMap<StaticId, CalculationResult>> =
+            FxHashMap::default();
+        for position in self.positions.iter() {
+            let inst_id = position.get_instrument_id();
+            let result = results.get(&inst_id).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) result is not calculated for {} in the book {}",
+                    file!(),
+                    line!(),
+                    inst_id,
+                    position.book,
+                )
+            })?;
+            res.entry(position.book)
+                .or_default()
+                .insert(inst_id, result.scaled_by_quantity(position.quantity));
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::instruments::cashflow::{Cashflow, CashflowLeg, CashflowType};
+    use crate::instruments::{futures::Futures, inst_info::InstInfo};
+    use crate::pricing_engines::npv_result::NpvResult;
+    use crate::InstType;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_positions() -> Result<()> {
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let inst_id = StaticId::from_str("KOSPI2 Fut", "KRX");
+        let inst_info = InstInfo {
+            id: inst_id,
+            name: "KOSPI2 Fut".to_string(),
+            inst_type: InstType::Futures,
+            currency: Currency::KRW,
+            unit_notional: 250_000.0,
+            maturity: Some(datetime!(2024-06-13 15:45:00 +09:00)),
+            ..Default::default()
+        };
+        let futures = Arc::new(Instrument::Futures(Futures::new(
+            inst_info.clone(),
+            300.0,
+            None,
+            Currency::KRW,
+            und_id,
+        )));
+        let (trading, hedge) = (
+            StaticId::from_str("Trading", "Book"),
+            StaticId::from_str("Hedge", "Book"),
+        );
+
+        let positions = Positions::new(vec![
+            Position::new(futures.clone(), 3.0, trading),
+            Position::new(futures.clone(), -2.0, hedge),
+        ])?;
+        assert_eq!(positions.get_instruments().len(), 1);
+        assert_eq!(positions.get_books(), vec![trading, hedge]);
+
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let pay_date = datetime!(2024-06-13 16:30:00 +09:00);
+        let mut result = CalculationResult::new(inst_info, dt);
+        result.set_npv(NpvResult::new_from_npv(301.0));
+        result.set_value()?;
+        result.set_single_delta(und_id, 750_000.0);
+        result.set_cashflows(vec![Cashflow::new(
+            pay_date,
+            1.0,
+            Currency::KRW,
+            CashflowLeg::Single,
+            CashflowType::Expected,
+        )]);
+        let mut results = FxHashMap::default();
+        results.insert(inst_id, result);
+
+        let position_results = positions.get_position_results(&results)?;
+        let short = &position_results[&hedge][&inst_id];
+        assert_eq!(short.get_value(), Some(-2.0 * 301.0 * 250_000.0));
+        assert_eq!(short.get_delta().unwrap()[&und_id], -1_500_000.0);
+        assert_eq!(short.get_cashflows().unwrap()[0].get_amount(), -2.0);
+        assert_eq!(
+            position_results[&trading][&inst_id].get_value(),
+            Some(3.0 * 301.0 * 250_000.0)
+        );
+
+        assert!(positions
+            .get_position_results(&FxHashMap::default())
+            .is_err());
+        assert!(Positions::new(vec![
+            Position::new(futures.clone(), 3.0, trading),
+            Position::new(futures, 1.0, trading),
+        ])
+        .is_err());
+        Ok(())
+    }
+}
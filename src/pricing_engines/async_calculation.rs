@@ -0,0 +1,77 @@
+use crate::pricing_engines::calculation_result::CalculationResult;
+use crate::utils::platform;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use static_id::static_id::StaticId;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+pub type GroupResult = Result<FxHashMap<StaticId, CalculationResult>>;
+
+#[derive(Default)]
+struct GroupState {
+    result: Option<GroupResult>,
+    waker: Option<Waker>,
+}
+
+/// The calculation of an instrument group running on the rayon thread pool
+/// (EngineGenerator::calculate_async). It resolves to the results of the instruments in the group,
+/// or to an error if the group failed or was cancelled. The future does not depend on any async runtime.
+/// Dropping the future does not stop the calculation; cancel the token for that.
+pub struct GroupCalculation {
+    group_id: usize,
+    state: Arc<Mutex<GroupState>>,
+}
+
+impl GroupCalculation {
+    pub(crate) fn spawn<F>(group_id: usize, calculation: F) -> GroupCalculation
+    where
+        F: FnOnce() -> GroupResult + Send + 'static,
+    {
+        let state = Arc::new(Mutex::new(GroupState::default()));
+        let shared_state = state.clone();
+        platform::spawn(move || {
+            let result = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(calculation)) {
+                Ok(result) => result,
+                Err(_) => Err(anyhow!(
+                    "({}:{}) the calculation of group-{} panicked",
+                    file!(),
+                    line!(),
+                    group_id
+                )),
+            };
+            let mut state = shared_state.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+        GroupCalculation { group_id, state }
+    }
+
+    pub fn get_group_id(&self) -> usize {
+        self.group_id
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().result.is_some()
+    }
+}
+
+impl Future for GroupCalculation {
+    type Output = GroupResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<GroupResult> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use crate::definitions::{Real, Time};
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::math::hull_white_lattice::HullWhiteTrinomialTree;
+use crate::math::solvers::{expand_bracket, newton_safeguarded, SolverOptions};
+use crate::parameters::{past_price::DailyClosePrice, zero_curve::ZeroCurve};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use time::OffsetDateTime;
+
+const YIELD_MAX_ITERATIONS: usize = 50;
+const YIELD_TOLERANCE: Real = 1.0e-6;
+const SOLVER_BRACKET_WIDTH: Real = 0.01;
+
+/// Price and yield measures of a bond on a unit notional at the pricing date.
+/// - dirty_price: npv, the cashflows after the pricing date discounted to the pricing date
+/// - clean_price: dirty_price - accrued_interest
+/// - yield_to_maturity: compounded on the coupon frequency (annually for zero coupon bonds)
+/// - modified_duration: -dP/dy / P on the yield_to_maturity
+/// - convexity: d^2P/dy^2 / P on the yield_to_maturity
+/// - z_spread: constant spread (continuous compounding) over the benchmark curve reproducing the dirty price
+/// - oas: option adjusted spread of a callable bond on the Hull-White lattice fitted to the benchmark curve
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct BondAnalytics {
+    clean_price: Real,
+    dirty_price: Real,
+    accrued_interest: Real,
+    yield_to_maturity: Real,
+    modified_duration: Real,
+    convexity: Real,
+    z_spread: Option<Real>,
+    oas: Option<Real>,
+}
+
+impl BondAnalytics {
+    pub fn new(
+        dirty_price: Real,
+        accrued_interest: Real,
+        yield_to_maturity: Real,
+        modified_duration: Real,
+        convexity: Real,
+    ) -> BondAnalytics {
+        BondAnalytics {
+            clean_price: dirty_price - accrued_interest,
+            dirty_price,
+            accrued_interest,
+            yield_to_maturity,
+            modified_duration,
+            convexity,
+            z_spread: None,
+            oas: None,
+        }
+    }
+
+    pub fn with_z_spread(mut self, z_spread: Real) -> BondAnalytics {
+        self.z_spread = Some(z_spread);
+        self
+    }
+
+    pub fn with_oas(mut self, oas: Real) -> BondAnalytics {
+        self.oas = Some(oas);
+        self
+    }
+
+    pub fn get_clean_price(&self) -> Real {
+        self.clean_price
+    }
+
+    pub fn get_dirty_price(&self) -> Real {
+        self.dirty_price
+    }
+
+    pub fn get_accrued_interest(&self) -> Real {
+        self.accrued_interest
+    }
+
+    pub fn get_yield_to_maturity(&self) -> Real {
+        self.yield_to_maturity
+    }
+
+    pub fn get_modified_duration(&self) -> Real {
+        self.modified_duration
+    }
+
+    pub fn get_convexity(&self) -> Real {
+        self.convexity
+    }
+
+    pub fn get_z_spread(&self) -> Option<Real> {
+        self.z_spread
+    }
+
+    pub fn get_oas(&self) -> Option<Real> {
+        self.oas
+    }
+}
+
+/// BondAnalytics of the bond at the dirty price on the pricing date.
+/// The yield is found from the dirty price if it is not given, e.g., by a yield pricer.
+pub fn calculate_bond_analytics(
+    instrument: &Instrument,
+    pricing_date: &OffsetDateTime,
+    dirty_price: Real,
+    bond_yield: Option<Real>,
+    forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+    past_data: Option<Arc<DailyClosePrice>>,
+) -> Result<BondAnalytics> {
+    let calendar = instrument.get_calendar()?;
+    let daycounter = instrument.get_daycounter()?;
+    let frequency = match instrument.get_coupon_frequency()?.as_real() {
+        x if x > 0.0 => x,
+        _ => 1.0,
+    };
+    let mut cashflows = Vec::<(Time, Real)>::new();
+    for (payment_date, amount) in instrument
+        .get_cashflows(pricing_date, forward_curve.clone(), past_data.clone())?
+        .iter()
+    {
+        if payment_date.date() > pricing_date.date() {
+            let t = calendar.year_fraction(pricing_date, payment_date, &daycounter)?;
+            cashflows.push((t, *amount));
+        }
+    }
+    cashflows.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let accrued_interest =
+        instrument.get_accrued_interest(pricing_date, forward_curve, past_data)?;
+    let yield_to_maturity = match bond_yield {
+        Some(bond_yield) => bond_yield,
+        None => find_yield(&cashflows, dirty_price, frequency, None)?,
+    };
+    let (_, modified_duration, convexity) =
+        yield_measures(&cashflows, yield_to_maturity, frequency);
+    Ok(BondAnalytics::new(
+        dirty_price,
+        accrued_interest,
+        yield_to_maturity,
+        modified_duration,
+        convexity,
+    ))
+}
+
+/// z-spread over the benchmark curve reproducing the dirty price of the cashflows (payment date, amount) on the pricing date.
+/// The cashflows are discounted to the pricing date by the benchmark discount factors and exp(-z (t - t_pricing)).
+pub fn find_z_spread(
+    cashflows: &[(OffsetDateTime, Real)],
+    pricing_date: &OffsetDateTime,
+    dirty_price: Real,
+    benchmark_curve: &ZeroCurve,
+) -> Result<Real> {
+    let eval_dt = benchmark_curve
+        .get_evaluation_date_clone()
+        .read().unwrap()
+        .get_date_clone();
+    let time_calculator = NullCalendar::default();
+    let pricing_time = time_calculator.get_time_difference(&eval_dt, pricing_date);
+    let pricing_discount = benchmark_curve.get_discount_factor_at_date(pricing_date)?;
+    let mut discounted = Vec::<(Time, Real)>::new();
+    for (payment_date, amount) in cashflows.iter() {
+        if payment_date.date() > pricing_date.date() {
+            let t = time_calculator.get_time_difference(&eval_dt, payment_date) - pricing_time;
+            let discount = benchmark_curve.get_discount_factor_at_date(payment_date)?;
+            discounted.push((t, amount * discount / pricing_discount));
+        }
+    }
+
+    let price_and_derivative = |z_spread: Real| {
+        let mut price: Real = -dirty_price;
+        let mut derivative: Real = 0.0;
+        for (t, pv) in discounted.iter() {
+            let spread_pv = pv * (-z_spread * t).exp();
+            price += spread_pv;
+            derivative -= t * spread_pv;
+        }
+        (price, derivative)
+    };
+    solve_price(price_and_derivative, 0.0, (Real::NEG_INFINITY, Real::INFINITY)).with_context(
+        || {
+            anyhow!(
+                "({}:{}) failed to find the z-spread reproducing the price {}",
+                file!(),
+                line!(),
+                dirty_price,
+            )
+        },
+    )
+}
+
+/// OAS: the spread on the short rates of the Hull-White lattice reproducing the price at the time 0 of the tree.
+/// cashflows and calls are on the steps of the tree, and the secant method starts from the init_guess, e.g., z-spread.
+pub fn find_oas(
+    tree: &HullWhiteTrinomialTree,
+    cashflows: &[(usize, Real)],
+    calls: &[(usize, Real)],
+    price: Real,
+    init_guess: Real,
+) -> Result<Real> {
+    let mut x0 = init_guess;
+    let mut x1 = init_guess + 0.001;
+    let mut f0 = tree.price(cashflows, calls, x0) - price;
+    for _ in 0..YIELD_MAX_ITERATIONS {
+        let f1 = tree.price(cashflows, calls, x1) - price;
+        if f1 == f0 {
+            break;
+        }
+        let x2 = x1 - f1 * (x1 - x0) / (f1 - f0);
+        if (x2 - x1).abs() < YIELD_TOLERANCE {
+            return Ok(x2);
+        }
+        (x0, f0, x1) = (x1, f1, x2);
+    }
+    Err(anyhow!(
+        "({}:{}) failed to find the OAS reproducing the price {}",
+        file!(),
+        line!(),
+        price,
+    ))
+}
+
+/// price, modified duration and convexity of the cashflows, (time from the pricing date, amount),
+/// on the yield compounded frequency times a year
+pub fn yield_measures(
+    cashflows: &[(Time, Real)],
+    bond_yield: Real,
+    frequency: Real,
+) -> (Real, Real, Real) {
+    let base = 1.0 + bond_yield / frequency;
+    let mut price: Real = 0.0;
+    let mut first_derivative: Real = 0.0;
+    let mut second_derivative: Real = 0.0;
+    for (t, amount) in cashflows.iter() {
+        let pv = amount * base.powf(-frequency * t);
+        price += pv;
+        first_derivative += t * pv / base;
+        second_derivative += t * (t + 1.0 / frequency) * pv / (base * base);
+    }
+    if price == 0.0 {
+        return (0.0, 0.0, 0.0);
+    }
+    (price, first_derivative / price, second_derivative / price)
+}
+
+/// yield reproducing the price of the cashflows by the safeguarded Newton's method on yield_measures
+pub fn find_yield(
+    cashflows: &[(Time, Real)],
+    price: Real,
+    frequency: Real,
+    init_guess: Option<Real>,
+) -> Result<Real> {
+    let price_and_derivative = |bond_yield: Real| {
+        let (model_price, modified_duration, _) = yield_measures(cashflows, bond_yield, frequency);
+        (model_price - price, -modified_duration * model_price)
+    };
+    solve_price(
+        price_and_derivative,
+        init_guess.unwrap_or(0.03),
+        (-frequency, Real::INFINITY),
+    )
+    .with_context(|| {
+        anyhow!(
+            "({}:{}) failed to find the yield reproducing the price {}",
+            file!(),
+            line!(),
+            price,
+        )
+    })
+}
+
+/// the root of the price residual, (value, derivative), by Newton's method safeguarded on the bracket
+/// expanded from the init_guess in the domain, so that it does not diverge near zero or negative rates
+fn solve_price<F: FnMut(Real) -> (Real, Real)>(
+    mut f: F,
+    init_guess: Real,
+    domain: (Real, Real),
+) -> Result<Real> {
+    let (lower, upper, _, _) = expand_bracket(
+        &mut |x| Ok(f(x).0),
+        init_guess - SOLVER_BRACKET_WIDTH,
+        init_guess + SOLVER_BRACKET_WIDTH,
+        domain,
+    )?;
+    let options = SolverOptions::default().with_tolerance(YIELD_TOLERANCE);
+    newton_safeguarded(&mut |x| Ok(f(x)), lower, upper, init_guess, &options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yield_measures() -> Result<()> {
+        // 2 year 4% semi-annual coupon bond on 4% yield is at par
+        let cashflows = vec![(0.5, 0.02), (1.0, 0.02), (1.5, 0.02), (2.0, 1.02)];
+        let (price, modified_duration, convexity) = yield_measures(&cashflows, 0.04, 2.0);
+        assert!((price - 1.0).abs() < 1.0e-5);
+        assert!((modified_duration - 1.9039).abs() < 1.0e-3);
+        assert!(convexity > modified_duration * modified_duration);
+
+        let bond_yield = find_yield(&cashflows, 0.98, 2.0, None)?;
+        let (price, _, _) = yield_measures(&cashflows, bond_yield, 2.0);
+        assert!((price - 0.98).abs() < 1.0e-5);
+        assert!(bond_yield > 0.04);
+        Ok(())
+    }
+}
@@ -1,115 +1,224 @@
-use crate::definitions::Real;
+use crate::definitions::{Real, Time};
 use crate::evaluation_date::EvaluationDate;
 use crate::instrument::Instrument;
 use crate::instrument::InstrumentTrait;
+use crate::instruments::cashflow::{CashflowCache, CashflowMap};
+use crate::math::hull_white_lattice::HullWhiteTrinomialTree;
 use crate::parameters::past_price::DailyClosePrice;
 use crate::parameters::zero_curve::ZeroCurve;
-use crate::pricing_engines::{npv_result::NpvResult, pricer::PricerTrait};
+use crate::pricing_engines::{
+    bond_analytics::{calculate_bond_analytics, find_oas, find_z_spread, BondAnalytics},
+    npv_result::NpvResult,
+    pricer::PricerTrait,
+};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
 //
 use anyhow::{Context, Result};
-use std::{cell::RefCell, rc::Rc};
+use std::sync::{Arc, RwLock};
 use time::OffsetDateTime;
 use rustc_hash::FxHashMap;
 
-/// forward_curve (Optional<Rc<RefCell<ZeroCurve>>>): forward curve for floating rate bond, so it is optional
-/// past_fixing_data (Optional<Rc<CloseData>>): past fixing data for floating rate bond, so it is optional
+const OAS_LATTICE_STEPS_PER_YEAR: Real = 50.0;
+
+/// forward_curve (Optional<Arc<RwLock<ZeroCurve>>>): forward curve for floating rate bond, so it is optional
+/// past_fixing_data (Optional<Arc<CloseData>>): past fixing data for floating rate bond, so it is optional
+/// hull_white_parameters ((Real, Real)): mean reversion and volatility of the lattice for the OAS of callable bonds
 pub struct BondPricer {
-    evaluation_date: Rc<RefCell<EvaluationDate>>,
-    discount_curve: Rc<RefCell<ZeroCurve>>,
-    forward_curve: Option<Rc<RefCell<ZeroCurve>>>,
-    past_fixing_data: Option<Rc<DailyClosePrice>>,
+    evaluation_date: Arc<RwLock<EvaluationDate>>,
+    discount_curve: Arc<RwLock<ZeroCurve>>,
+    forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+    past_fixing_data: Option<Arc<DailyClosePrice>>,
+    hull_white_parameters: (Real, Real),
+    cashflow_cache: CashflowCache,
 }
 
 impl BondPricer {
     pub fn new(
-        evaluation_date: Rc<RefCell<EvaluationDate>>,
-        discount_curve: Rc<RefCell<ZeroCurve>>,
-        forward_curve: Option<Rc<RefCell<ZeroCurve>>>,
-        past_fixing_data: Option<Rc<DailyClosePrice>>,
+        evaluation_date: Arc<RwLock<EvaluationDate>>,
+        discount_curve: Arc<RwLock<ZeroCurve>>,
+        forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        past_fixing_data: Option<Arc<DailyClosePrice>>,
     ) -> BondPricer {
         BondPricer {
             evaluation_date,
             discount_curve,
             forward_curve,
             past_fixing_data,
+            hull_white_parameters: (0.03, 0.01),
+            cashflow_cache: CashflowCache::default(),
         }
     }
+
+    pub fn with_hull_white_parameters(mut self, mean_reversion: Real, volatility: Real) -> BondPricer {
+        self.hull_white_parameters = (mean_reversion, volatility);
+        self
+    }
+
+    /// cashflows on the pricing date, which are cached for the bonds without a rate index
+    fn get_cashflows(
+        &self,
+        instrument: &Instrument,
+        pricing_date: &OffsetDateTime,
+    ) -> Result<Arc<CashflowMap>> {
+        let generate = || {
+            instrument.get_cashflows(
+                pricing_date,
+                self.forward_curve.clone(),
+                self.past_fixing_data.clone(),
+            )
+        };
+        match instrument.get_rate_index()? {
+            None => self
+                .cashflow_cache
+                .get_or_insert_with(instrument.get_id(), *pricing_date, generate),
+            Some(_) => Ok(Arc::new(generate()?)),
+        }
+    }
+
+    /// the base curve of the discount curve if it is a spread curve on a single base curve (e.g., government curve),
+    /// otherwise the discount curve itself
+    fn get_benchmark_curve(&self) -> Arc<RwLock<ZeroCurve>> {
+        let discount_curve = self.discount_curve.read().unwrap();
+        match discount_curve.get_base_curves().as_slice() {
+            [base_curve] => base_curve.clone(),
+            _ => self.discount_curve.clone(),
+        }
+    }
+
+    /// OAS on the Hull-White lattice fitted to the benchmark curve for the price at the pricing date
+    fn get_oas(
+        &self,
+        instrument: &Instrument,
+        pricing_date: &OffsetDateTime,
+        dirty_price: Real,
+        benchmark_curve: &ZeroCurve,
+        init_guess: Real,
+    ) -> Result<Real> {
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
+        let time_calculator = NullCalendar::default();
+        let cashflows: Vec<(Time, Real)> = self
+            .get_cashflows(instrument, pricing_date)?
+            .iter()
+            .filter(|(date, _)| date.date() > pricing_date.date())
+            .map(|(date, amount)| (time_calculator.get_time_difference(&eval_dt, date), *amount))
+            .collect();
+        let maturity = cashflows.iter().map(|(t, _)| *t).fold(0.0, Real::max);
+        let steps = (maturity * OAS_LATTICE_STEPS_PER_YEAR).ceil().max(1.0) as usize;
+        let (mean_reversion, volatility) = self.hull_white_parameters;
+        let tree = HullWhiteTrinomialTree::new(mean_reversion, volatility, maturity, steps, |t| {
+            benchmark_curve.get_discount_factor(t)
+        })?;
+
+        let cashflow_steps: Vec<(usize, Real)> = cashflows
+            .iter()
+            .map(|(t, amount)| (tree.get_step(*t), *amount))
+            .collect();
+        let call_steps: Vec<(usize, Real)> = instrument
+            .get_call_schedule()?
+            .iter()
+            .filter(|(date, _)| date.date() > pricing_date.date())
+            .map(|(date, price)| {
+                (tree.get_step(time_calculator.get_time_difference(&eval_dt, date)), *price)
+            })
+            .collect();
+        let price = dirty_price * benchmark_curve.get_discount_factor_at_date(pricing_date)?;
+        find_oas(&tree, &cashflow_steps, &call_steps, price, init_guess)
+    }
 }
 
 impl PricerTrait for BondPricer {
     fn npv(&self, instrument: &Instrument) -> Result<Real> {
         let mut res: Real = 0.0;
-        let mut disc_factor: Real;
-        let eval_dt = self.evaluation_date.borrow().get_date_clone();
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
         let pricing_date = instrument.get_pricing_date()?.unwrap_or(&eval_dt);
 
-        let cashflow = instrument
-            .get_cashflows(
-                pricing_date,
-                self.forward_curve.clone(),
-                self.past_fixing_data.clone(),
-            )
+        let cashflow = self
+            .get_cashflows(instrument, pricing_date)
             .context("Failed to get coupon cashflow in calculating Bond::npv")?;
 
-        for (payment_date, amount) in cashflow.iter() {
-            if payment_date.date() > pricing_date.date() {
-                disc_factor = self
-                    .discount_curve
-                    .borrow()
-                    .get_discount_factor_at_date(payment_date)?;
-                res += amount * disc_factor;
-            }
+        let discount_curve = self.discount_curve.read().unwrap();
+        let payment_dates: Vec<OffsetDateTime> = cashflow
+            .keys()
+            .filter(|payment_date| payment_date.date() > pricing_date.date())
+            .copied()
+            .collect();
+        let disc_factors = discount_curve.get_discount_factors_at_dates(&payment_dates)?;
+        for (payment_date, disc_factor) in payment_dates.iter().zip(disc_factors.iter()) {
+            res += cashflow[payment_date] * disc_factor;
         }
 
-        res /= self
-            .discount_curve
-            .borrow()
-            .get_discount_factor_at_date(pricing_date)?;
+        res /= discount_curve.get_discount_factor_at_date(pricing_date)?;
         Ok(res)
     }
 
     fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
-        let eval_dt = self.evaluation_date.borrow().get_date_clone();
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
         let pricing_date = instrument.get_pricing_date()?.unwrap_or(&eval_dt);
 
         let mut npv: Real = 0.0;
         let mut coupon_amounts: FxHashMap<usize, (OffsetDateTime, Real)> = FxHashMap::default();
         let mut coupon_payment_probability: FxHashMap<usize, (OffsetDateTime, Real)> = FxHashMap::default();
 
-        let mut disc_factor: Real;
-
-        let cashflow = instrument
-            .get_cashflows(
-                pricing_date,
-                self.forward_curve.clone(),
-                self.past_fixing_data.clone(),
-            )
+        let cashflow = self
+            .get_cashflows(instrument, pricing_date)
             .context("Failed to get coupon cashflow in calculating Bond::npv_result")?; // include evaluation date
 
-        for (i, (payment_date, amount)) in cashflow.iter().enumerate() {
-            if pricing_date.date() < payment_date.date() {
-                disc_factor = self
-                    .discount_curve
-                    .borrow()
-                    .get_discount_factor_at_date(payment_date)?;
-                npv += amount * disc_factor;
-            }
+        let discount_curve = self.discount_curve.read().unwrap();
+        let payment_dates: Vec<OffsetDateTime> = cashflow
+            .keys()
+            .filter(|payment_date| pricing_date.date() < payment_date.date())
+            .copied()
+            .collect();
+        let disc_factors = discount_curve.get_discount_factors_at_dates(&payment_dates)?;
+        for (payment_date, disc_factor) in payment_dates.iter().zip(disc_factors.iter()) {
+            npv += cashflow[payment_date] * disc_factor;
+        }
 
+        for (i, (payment_date, amount)) in cashflow.iter().enumerate() {
             if pricing_date.date() <= payment_date.date() {
                 coupon_amounts.insert(i, (*payment_date, *amount));
                 coupon_payment_probability.insert(i, (*payment_date, 1.0));
             }
         }
 
-        npv /= self
-            .discount_curve
-            .borrow()
-            .get_discount_factor_at_date(pricing_date)?;
+        npv /= discount_curve.get_discount_factor_at_date(pricing_date)?;
 
         let res = NpvResult::new(npv, coupon_amounts, coupon_payment_probability);
 
         Ok(res)
     }
+
+    /// The yield is found from npv, the dirty price on the pricing date.
+    /// z-spread is over the benchmark curve, the base of the discount spread curve,
+    /// and OAS is calculated only for callable bonds.
+    fn bond_analytics(&self, instrument: &Instrument, npv: Real) -> Result<Option<BondAnalytics>> {
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
+        let pricing_date = instrument.get_pricing_date()?.unwrap_or(&eval_dt);
+        let analytics = calculate_bond_analytics(
+            instrument,
+            pricing_date,
+            npv,
+            None,
+            self.forward_curve.clone(),
+            self.past_fixing_data.clone(),
+        )?;
+
+        let benchmark_curve = self.get_benchmark_curve();
+        let benchmark_curve = benchmark_curve.read().unwrap();
+        let cashflows: Vec<(OffsetDateTime, Real)> = self
+            .get_cashflows(instrument, pricing_date)?
+            .iter()
+            .map(|(date, amount)| (*date, *amount))
+            .collect();
+        let z_spread = find_z_spread(&cashflows, pricing_date, npv, &benchmark_curve)?;
+        let analytics = analytics.with_z_spread(z_spread);
+
+        if instrument.get_call_schedule()?.is_empty() {
+            return Ok(Some(analytics));
+        }
+        let oas = self.get_oas(instrument, pricing_date, npv, &benchmark_curve, z_spread)?;
+        Ok(Some(analytics.with_oas(oas)))
+    }
 }
 
 // please make a pricer test by refering crate::instruments::schedule,
@@ -144,29 +253,29 @@ mod tests {
     use static_id::static_id::StaticId;
     use anyhow::Result;
     use ndarray::array;
-    use std::{cell::RefCell, rc::Rc};
+    use std::sync::{Arc, RwLock};
     use time::{macros::datetime, Duration};
 
     #[test]
     fn test_fixed_coupon_bond_pricer() -> Result<()> {
         let dt = datetime!(2021-01-01 16:30:00 +09:00);
-        let bond_pricing_date = dt.clone();
+        let bond_pricing_date = dt;
         let name = "KRWGOV";
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(dt)));
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(dt)));
 
         // define a vector data 1Y = 0.03, 5Y = 0.04
         let curve_data = VectorData::new(
             array!(0.03, 0.03),
             None,
             Some(array!(1.0, 5.0)),
-            None, //evaluation_date.borrow().get_date_clone(),
+            None, //evaluation_date.read().unwrap().get_date_clone(),
             Currency::KRW,
             name.to_string(),
             StaticId::from_str(name, "KRX"),
         )?;
 
         // make a discount curve (ZeroCurve)
-        let discount_curve = Rc::new(RefCell::new(ZeroCurve::new(
+        let discount_curve = Arc::new(RwLock::new(ZeroCurve::new(
             evaluation_date.clone(),
             &curve_data,
             name.to_string(),
@@ -193,15 +302,15 @@ mod tests {
             InstType::Bond,
             Currency::KRW,
             10_000.0,
-            Some(issuedate.clone()),
-            Some(maturity.clone()),
+            Some(issuedate),
+            Some(maturity),
             crate::AccountingLevel::L1,
         );
 
         let bond_info = BondInfo {
             issuer_type: IssuerType::Government,
             credit_rating: CreditRating::None,
-            issuer_id: issuer_id,
+            issuer_id,
             rank: RankType::Undefined,
         };
 
@@ -274,6 +383,51 @@ mod tests {
             expected_npv
         );
 
+        // the fixed coupons are generated once on the pricing date and reused in the revaluations
+        assert_eq!(pricer.cashflow_cache.len(), 1);
+        assert_eq!(pricer.npv(&isntrument)?, pricer.npv(&isntrument)?);
+        assert_eq!(pricer.cashflow_cache.len(), 1);
+
+        // z-spread over the base curve of the discount spread curve is the flat spread
+        let spread_data = VectorData::new(
+            array!(0.01, 0.01),
+            None,
+            Some(array!(1.0, 5.0)),
+            None,
+            Currency::KRW,
+            "KRWGOV Spread".to_string(),
+            StaticId::from_str("KRWGOV Spread", "KRX"),
+        )?;
+        let spread_curve = Arc::new(RwLock::new(ZeroCurve::new_spread_curve(
+            discount_curve.clone(),
+            &spread_data,
+            "KRWGOV Spread".to_string(),
+            StaticId::from_str("KRWGOV Spread", "KRX"),
+        )?));
+        let spread_pricer = BondPricer::new(evaluation_date.clone(), spread_curve, None, None);
+        let spread_npv = spread_pricer.npv(&isntrument)?;
+        let analytics = spread_pricer.bond_analytics(&isntrument, spread_npv)?.unwrap();
+        let z_spread = analytics.get_z_spread().unwrap();
+        assert!((z_spread - 0.01).abs() < 1.0e-5, "z-spread: {}", z_spread);
+        assert!(analytics.get_oas().is_none());
+
+        // OAS of a bullet bond is the z-spread up to the discretization of the lattice
+        let benchmark_curve = spread_pricer.get_benchmark_curve();
+        let bullet_oas =
+            spread_pricer.get_oas(&isntrument, &dt, spread_npv, &benchmark_curve.read().unwrap(), 0.0)?;
+        assert!((bullet_oas - z_spread).abs() < 2.0e-4, "OAS: {}, z-spread: {}", bullet_oas, z_spread);
+
+        // the issuer's call at par costs the holder, so the OAS on the same price is lower
+        let callable = Instrument::Bond(
+            bond.with_call_schedule(vec![(datetime!(2022-01-01 16:30:00 +09:00), 1.0)])?,
+        );
+        let callable_oas = spread_pricer
+            .bond_analytics(&callable, spread_npv)?
+            .unwrap()
+            .get_oas()
+            .unwrap();
+        assert!(callable_oas < bullet_oas, "callable OAS: {}, bullet OAS: {}", callable_oas, bullet_oas);
+
         Ok(())
     }
 
@@ -284,21 +438,21 @@ mod tests {
         let dt = datetime!(2020-12-31 16:30:00 +09:00);
         let effective_date = datetime!(2021-01-01 16:30:00 +09:00);
         let name = "KRWGOV";
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(dt)));
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(dt)));
 
         // define a vector data 1Y = 0.03, 5Y = 0.04
         let curve_data = VectorData::new(
             array!(0.04, 0.04),
             None,
             Some(array!(1.0, 5.0)),
-            None, //evaluation_date.borrow().get_date_clone(),
+            None, //evaluation_date.read().unwrap().get_date_clone(),
             Currency::KRW,
             name.to_string(),
             StaticId::from_str(name, "KRX"),
         )?;
 
         // make a discount curve (ZeroCurve)
-        let discount_curve = Rc::new(RefCell::new(ZeroCurve::new(
+        let discount_curve = Arc::new(RwLock::new(ZeroCurve::new(
             evaluation_date.clone(),
             &curve_data,
             name.to_string(),
@@ -310,14 +464,14 @@ mod tests {
             array!(0.04, 0.04),
             None,
             Some(array!(1.0, 5.0)),
-            None, //evaluation_date.borrow().get_date_clone(),
+            None, //evaluation_date.read().unwrap().get_date_clone(),
             Currency::KRW,
             name.to_string(),
             StaticId::from_str(name, "KRX"),
         )?;
 
         // make a discount curve (ZeroCurve)
-        let forward_curve = Rc::new(RefCell::new(ZeroCurve::new(
+        let forward_curve = Arc::new(RwLock::new(ZeroCurve::new(
             evaluation_date.clone(),
             &forward_curve_data,
             "KRWIRS".to_string(),
@@ -357,8 +511,8 @@ mod tests {
             InstType::Bond,
             Currency::KRW,
             10_000.0,
-            Some(issuedate.clone()),
-            Some(maturity.clone()),
+            Some(issuedate),
+            Some(maturity),
             crate::AccountingLevel::L1,
         );
 
@@ -375,8 +529,8 @@ mod tests {
             //
             false,
             //
-            Some(effective_date.clone()),
-            Some(evaluation_date.borrow().get_date_clone()),
+            Some(effective_date),
+            Some(evaluation_date.read().unwrap().get_date_clone()),
             None,
             //
             Some(0.00),
@@ -413,6 +567,8 @@ mod tests {
             npv,
             expected_npv
         );
+        // the floating coupons depend on the forward curve, so they are not cached
+        assert!(pricer.cashflow_cache.is_empty());
         Ok(())
     }
 }
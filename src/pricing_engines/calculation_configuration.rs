@@ -1,10 +1,234 @@
+use crate::currency::Currency;
 use crate::definitions::{Integer, Real};
-use crate::enums::{StickynessType, VanillaOptionCalculationMethod};
+use crate::enums::{BumpType, FiniteDifferenceScheme, GreekMethod, KeyRateBumpScheme, MarkingSide, RiskFactorClass, StickynessType, ThetaDayMode, VanillaOptionCalculationMethod, VolatilityTimeInterpolation};
+use crate::parameters::volatilities::business_time::BusinessTime;
+use crate::time::calendar::Calendar;
+use crate::time::calendars::southkorea::{SouthKorea, SouthKoreaType};
+use crate::time::jointcalendar::JointCalendar;
 use crate::parameters::volatilities::volatiltiy_interpolator::VolatilityInterplator;
 use crate::Tenor;
 use anyhow::{anyhow, Result};
 use ndarray::Array1;
-use serde::{Deserialize, Serialize};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize, Serializer};
+use static_id::static_id::StaticId;
+fn serialize_sorted_ids<S: Serializer>(
+    ids: &FxHashSet<StaticId>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    let mut ids: Vec<&StaticId> = ids.iter().collect();
+    ids.sort_by_key(|id| id.to_string());
+    ids.serialize(serializer)
+}
+
+fn default_key_rate_tenors() -> Vec<Tenor> {
+    ["3M", "6M", "1Y", "2Y", "3Y", "5Y", "7Y", "10Y", "15Y", "20Y", "30Y"]
+        .iter()
+        .map(|x| Tenor::new_from_string(x).expect("failed to convert key rate tenor"))
+        .collect()
+}
+
+fn default_cs01_bump_value() -> Real {
+    0.0001
+}
+
+fn default_div_carry_calendar() -> Calendar {
+    Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Settlement))
+}
+
+fn default_ladder_spot_shifts() -> Vec<Real> {
+    vec![-0.2, -0.1, -0.05, 0.0, 0.05, 0.1, 0.2]
+}
+
+fn default_ladder_vol_shifts() -> Vec<Real> {
+    vec![-0.05, 0.0, 0.05]
+}
+
+fn default_gamma_structure_bump_value() -> Real {
+    0.001
+}
+
+fn default_fx_exposure_ladder_tenors() -> Vec<Tenor> {
+    ["1M", "3M", "6M", "1Y", "2Y", "3Y", "5Y", "10Y"]
+        .iter()
+        .map(|x| Tenor::new_from_string(x).expect("failed to convert fx exposure ladder tenor"))
+        .collect()
+}
+
+fn default_hull_white_mean_reversion() -> Real {
+    0.03
+}
+
+fn default_hull_white_volatility() -> Real {
+    0.01
+}
+
+fn default_delta_scheme() -> FiniteDifferenceScheme {
+    FiniteDifferenceScheme::Central
+}
+
+fn default_parallel_pricing_threshold() -> usize {
+    32
+}
+
+/// Bump size of a risk factor.
+/// A relative bump of 0.01 on the spot 350.0 moves it by 3.5,
+/// and the engine converts it to the bump of the other type at the level of the risk factor:
+/// - Spot, Fx: the price
+/// - Volatility: the one year at-the-money volatility
+/// - Rate: the average of the zero rates of the curve
+/// - Dividend: the average of the dividend amounts
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BumpSize {
+    value: Real,
+    bump_type: BumpType,
+}
+
+impl BumpSize {
+    pub fn new(value: Real, bump_type: BumpType) -> Result<BumpSize> {
+        if value <= 0.0 || !value.is_finite() {
+            return Err(anyhow!(
+                "({}:{}) bump size must be positive, got {}",
+                file!(),
+                line!(),
+                value
+            ));
+        }
+        Ok(BumpSize { value, bump_type })
+    }
+
+    pub fn relative(value: Real) -> Result<BumpSize> {
+        BumpSize::new(value, BumpType::Relative)
+    }
+
+    pub fn absolute(value: Real) -> Result<BumpSize> {
+        BumpSize::new(value, BumpType::Absolute)
+    }
+
+    pub fn get_value(&self) -> Real {
+        self.value
+    }
+
+    pub fn get_bump_type(&self) -> BumpType {
+        self.bump_type
+    }
+
+    /// bump added to the risk factor at the level
+    pub fn get_absolute_bump(&self, level: Real) -> Result<Real> {
+        let res = match self.bump_type {
+            BumpType::Absolute => self.value,
+            BumpType::Relative => self.value * level.abs(),
+        };
+        check_bump(res, level)
+    }
+
+    /// bump ratio to the level of the risk factor
+    pub fn get_relative_bump(&self, level: Real) -> Result<Real> {
+        let res = match self.bump_type {
+            BumpType::Absolute => self.value / level.abs(),
+            BumpType::Relative => self.value,
+        };
+        check_bump(res, level)
+    }
+}
+
+/// Smoothing of the discontinuous payoffs (digitals, barriers, autocall triggers) in the bumped delta and gamma,
+/// so that the pin risk near the discontinuity does not blow up the greeks.
+/// - CallSpread(width): the digital is replicated by the call spread of the relative width around the spot,
+///   i.e., the spot is bumped by at least width / 2 on each side
+/// - GaussianKernel(bandwidth): the value is averaged over the relative spot shifts N(0, bandwidth²)
+///   by the Gauss-Hermite quadrature before the differences are taken
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PayoffSmoothing {
+    CallSpread(Real),
+    GaussianKernel(Real),
+}
+
+impl PayoffSmoothing {
+    pub fn call_spread(width: Real) -> Result<PayoffSmoothing> {
+        check_smoothing_width(width)?;
+        Ok(PayoffSmoothing::CallSpread(width))
+    }
+
+    pub fn gaussian_kernel(bandwidth: Real) -> Result<PayoffSmoothing> {
+        check_smoothing_width(bandwidth)?;
+        Ok(PayoffSmoothing::GaussianKernel(bandwidth))
+    }
+}
+
+fn check_smoothing_width(width: Real) -> Result<()> {
+    if width <= 0.0 || width >= 1.0 || !width.is_finite() {
+        return Err(anyhow!(
+            "({}:{}) smoothing width must be in (0, 1) relative to the spot, got {}",
+            file!(),
+            line!(),
+            width
+        ));
+    }
+    Ok(())
+}
+
+/// Expiry buckets (rows) and spot moneyness buckets (columns) of vega_matrix.
+/// The bucket i is (tenors[i-1], tenors[i]] with the first one from the evaluation date,
+/// and the bucket j is (spot_moneyness[j-1], spot_moneyness[j]] with the first one from zero.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VegaMatrixGrid {
+    tenors: Vec<Tenor>,
+    spot_moneyness: Vec<Real>,
+}
+
+impl VegaMatrixGrid {
+    pub fn new(tenors: Vec<Tenor>, spot_moneyness: Vec<Real>) -> Result<VegaMatrixGrid> {
+        if tenors.is_empty() || spot_moneyness.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) vega matrix grid must have at least one tenor and one spot moneyness",
+                file!(),
+                line!(),
+            ));
+        }
+        if spot_moneyness.iter().any(|x| *x <= 0.0)
+            || spot_moneyness.windows(2).any(|w| w[0] >= w[1])
+        {
+            return Err(anyhow!(
+                "({}:{}) spot moneyness of vega matrix grid must be positive and increasing, got {:?}",
+                file!(),
+                line!(),
+                spot_moneyness,
+            ));
+        }
+        Ok(VegaMatrixGrid {
+            tenors,
+            spot_moneyness,
+        })
+    }
+
+    pub fn get_tenors(&self) -> &Vec<Tenor> {
+        &self.tenors
+    }
+
+    pub fn get_spot_moneyness(&self) -> &Vec<Real> {
+        &self.spot_moneyness
+    }
+
+    /// (number of tenors, number of spot moneyness), the shape of vega_matrix
+    pub fn shape(&self) -> (usize, usize) {
+        (self.tenors.len(), self.spot_moneyness.len())
+    }
+}
+
+fn check_bump(bump: Real, level: Real) -> Result<Real> {
+    if bump <= 0.0 || !bump.is_finite() {
+        return Err(anyhow!(
+            "({}:{}) invalid bump {} at the level {} of the risk factor",
+            file!(),
+            line!(),
+            bump,
+            level
+        ));
+    }
+    Ok(bump)
+}
+
 /// CalculationConfiguration is a struct that holds the configuration of the calculation.
 /// stickyness_type: StickynessType
 /// StickynessType is an enum that represents the stickyness of the calculation.
@@ -22,11 +246,81 @@ pub struct CalculationConfiguration {
     theta: bool,
     vega_strucure: bool,
     rho_structure: bool,
+    // second order rho of each bucket on rho_structure_tenors. It is expensive with two bumps on each bucket
+    #[serde(default)]
+    gamma_structure: bool,
+    // the second differences on 1bp are below the precision of Real, so the buckets are bumped by 10bp by default
+    #[serde(default = "default_gamma_structure_bump_value")]
+    gamma_structure_bump_value: Real,
     div_structure: bool,
     vega_matrix: bool,
+    carry_roll_down: bool,
+    #[serde(default)]
+    theta_decomposition: bool,
+    #[serde(default)]
+    bid_ask_adjustment: bool,
+    #[serde(default)]
+    bond_analytics: bool,
+    // Hull-White parameters of the lattice for the OAS of callable bonds
+    #[serde(default = "default_hull_white_mean_reversion")]
+    hull_white_mean_reversion: Real,
+    #[serde(default = "default_hull_white_volatility")]
+    hull_white_volatility: Real,
+    #[serde(default)]
+    marking_side: MarkingSide,
+    #[serde(default)]
+    key_rate_dv01: bool,
+    #[serde(default = "default_key_rate_tenors")]
+    key_rate_tenors: Vec<Tenor>,
+    #[serde(default)]
+    key_rate_bump_scheme: KeyRateBumpScheme,
+    #[serde(default)]
+    cs01: bool,
+    #[serde(default)]
+    cs01_structure: bool,
+    #[serde(default = "default_cs01_bump_value")]
+    cs01_bump_value: Real,
+    #[serde(default = "default_key_rate_tenors")]
+    cs01_structure_tenors: Vec<Tenor>,
+    // fx delta and fx gamma of the instruments priced with fx rates (e.g., fx futures and cross currency swaps)
+    #[serde(default)]
+    fx_delta: bool,
+    // fx_exposure on the settlement date buckets ending at fx_exposure_ladder_tenors
+    #[serde(default)]
+    fx_exposure_ladder: bool,
+    #[serde(default = "default_fx_exposure_ladder_tenors")]
+    fx_exposure_ladder_tenors: Vec<Tenor>,
+    // rho on the discount, forward, collateral and borrowing curves of each instrument
+    #[serde(default)]
+    rho_by_role: bool,
+    // cross sensitivity of delta on each underlying to the parallel bump of each curve
+    #[serde(default)]
+    delta_rho: bool,
+    // dividends going ex by the next business day in div_carry_calendar on the position of each underlying
+    #[serde(default)]
+    div_carry: bool,
+    #[serde(default = "default_div_carry_calendar")]
+    div_carry_calendar: Calendar,
+    // values (and deltas) of each underlying on the relative spot shifts x the absolute vol shifts
+    #[serde(default)]
+    ladder: bool,
+    #[serde(default)]
+    ladder_delta: bool,
+    #[serde(default = "default_ladder_spot_shifts")]
+    ladder_spot_shifts: Vec<Real>,
+    #[serde(default = "default_ladder_vol_shifts")]
+    ladder_vol_shifts: Vec<Real>,
+    // the results are also given in this currency, converted by the fx data of the engine
+    #[serde(default)]
+    reporting_currency: Option<Currency>,
     //
     stickyness_type: StickynessType,
     lv_interpolator: VolatilityInterplator,
+    #[serde(default)]
+    volatility_time_interpolation: VolatilityTimeInterpolation,
+    // underlying id -> business time in which the volatility surface accumulates the variance
+    #[serde(default)]
+    volatility_business_time_map: FxHashMap<StaticId, BusinessTime>,
     //
     delta_bump_ratio: Real,
     gamma_bump_ratio: Real,
@@ -36,13 +330,69 @@ pub struct CalculationConfiguration {
     rho_bump_value: Real,
     div_bump_value: Real,
     theta_day: Integer,
+    // theta_day in calendar days or in business days of the instrument calendars
+    #[serde(default)]
+    theta_day_mode: ThetaDayMode,
+    // calendar of the business day theta for the instruments without their own calendar
+    #[serde(default)]
+    theta_calendar: JointCalendar,
+    // the part of theta on the weekends and holidays in the theta period
+    #[serde(default)]
+    weekend_theta: bool,
+    carry_horizon_day: Integer,
     //
     rho_structure_tenors: Vec<Tenor>,
     vega_structure_tenors: Vec<Tenor>,
     div_structure_tenors: Vec<Tenor>,
     vega_matrix_spot_moneyness: Array1<Real>,
+    // expiry buckets of vega_matrix, vega_structure_tenors if None
+    #[serde(default)]
+    vega_matrix_tenors: Option<Vec<Tenor>>,
+    // underlying id -> vega_matrix grid overriding the above
+    #[serde(default)]
+    vega_matrix_grids: FxHashMap<StaticId, VegaMatrixGrid>,
     //
     vanilla_option_calculation_method: VanillaOptionCalculationMethod,
+    #[serde(default)]
+    greek_method: GreekMethod,
+    // bump sizes overriding the default bumps of each risk factor class
+    // and the bump sizes for each currency overriding both
+    #[serde(default)]
+    bump_sizes: FxHashMap<RiskFactorClass, BumpSize>,
+    #[serde(default)]
+    currency_bump_sizes: FxHashMap<Currency, FxHashMap<RiskFactorClass, BumpSize>>,
+    // finite difference schemes of the bumped greeks.
+    // gamma is the central second difference, optionally Richardson-extrapolated with the double bump
+    #[serde(default = "default_delta_scheme")]
+    delta_scheme: FiniteDifferenceScheme,
+    #[serde(default)]
+    vega_scheme: FiniteDifferenceScheme,
+    #[serde(default)]
+    rho_scheme: FiniteDifferenceScheme,
+    #[serde(default)]
+    div_delta_scheme: FiniteDifferenceScheme,
+    #[serde(default)]
+    gamma_richardson_extrapolation: bool,
+    // smoothing of the delta and gamma of the instruments with discontinuous payoffs,
+    // which are the ones in discontinuous_payoff_ids
+    #[serde(default)]
+    payoff_smoothing: Option<PayoffSmoothing>,
+    // sorted in the json so that the same ids give the same configuration hash
+    #[serde(default, serialize_with = "serialize_sorted_ids")]
+    discontinuous_payoff_ids: FxHashSet<StaticId>,
+    // an engine prices its instruments on the rayon thread pool
+    // when the number of instruments in action is at least this threshold
+    #[serde(default = "default_parallel_pricing_threshold")]
+    parallel_pricing_threshold: usize,
+    // bit-identical results across runs (see with_reproducibility)
+    #[serde(default)]
+    reproducible: bool,
+    // the seed of the Monte Carlo VaR scenarios whose generator has no seed of its own
+    #[serde(default)]
+    random_seed: u64,
+    // the engines record the elapsed time of each phase (see EngineGenerator::get_calculation_statistics)
+    #[serde(default)]
+    statistics_collection: bool,
     //
 }
 
@@ -102,10 +452,40 @@ impl Default for CalculationConfiguration {
             theta: false,
             vega_strucure: false,
             rho_structure: false,
+            gamma_structure: false,
+            gamma_structure_bump_value: default_gamma_structure_bump_value(),
             div_structure: false,
             vega_matrix: false,
+            carry_roll_down: false,
+            theta_decomposition: false,
+            bid_ask_adjustment: false,
+            bond_analytics: false,
+            hull_white_mean_reversion: default_hull_white_mean_reversion(),
+            hull_white_volatility: default_hull_white_volatility(),
+            marking_side: MarkingSide::default(),
+            key_rate_dv01: false,
+            key_rate_tenors: default_key_rate_tenors(),
+            key_rate_bump_scheme: KeyRateBumpScheme::default(),
+            cs01: false,
+            cs01_structure: false,
+            cs01_bump_value: default_cs01_bump_value(),
+            cs01_structure_tenors: default_key_rate_tenors(),
+            fx_delta: false,
+            fx_exposure_ladder: false,
+            fx_exposure_ladder_tenors: default_fx_exposure_ladder_tenors(),
+            rho_by_role: false,
+            delta_rho: false,
+            div_carry: false,
+            div_carry_calendar: default_div_carry_calendar(),
+            ladder: false,
+            ladder_delta: false,
+            ladder_spot_shifts: default_ladder_spot_shifts(),
+            ladder_vol_shifts: default_ladder_vol_shifts(),
+            reporting_currency: None,
             stickyness_type: StickynessType::StickyToMoneyness,
             lv_interpolator: VolatilityInterplator::default(),
+            volatility_time_interpolation: VolatilityTimeInterpolation::default(),
+            volatility_business_time_map: FxHashMap::default(),
             delta_bump_ratio: 0.01,
             gamma_bump_ratio: 0.01,
             vega_bump_value: 0.01,
@@ -114,11 +494,31 @@ impl Default for CalculationConfiguration {
             rho_bump_value: 0.0001,
             div_bump_value: 0.0001,
             theta_day: 1,
+            theta_day_mode: ThetaDayMode::default(),
+            theta_calendar: JointCalendar::default(),
+            weekend_theta: false,
+            carry_horizon_day: 90,
             rho_structure_tenors: rho_tenors,
             vega_structure_tenors: vega_tenors,
             div_structure_tenors: div_tenors,
             vega_matrix_spot_moneyness,
+            vega_matrix_tenors: None,
+            vega_matrix_grids: FxHashMap::default(),
             vanilla_option_calculation_method: VanillaOptionCalculationMethod::Analytic,
+            greek_method: GreekMethod::default(),
+            bump_sizes: FxHashMap::default(),
+            currency_bump_sizes: FxHashMap::default(),
+            delta_scheme: default_delta_scheme(),
+            vega_scheme: FiniteDifferenceScheme::default(),
+            rho_scheme: FiniteDifferenceScheme::default(),
+            div_delta_scheme: FiniteDifferenceScheme::default(),
+            gamma_richardson_extrapolation: false,
+            payoff_smoothing: None,
+            discontinuous_payoff_ids: FxHashSet::default(),
+            parallel_pricing_threshold: default_parallel_pricing_threshold(),
+            reproducible: false,
+            random_seed: 0,
+            statistics_collection: false,
         }
     }
 }
@@ -215,10 +615,40 @@ impl CalculationConfiguration {
             vega_strucure,
             div_structure,
             rho_structure,
+            gamma_structure: false,
+            gamma_structure_bump_value: default_gamma_structure_bump_value(),
             vega_matrix,
+            carry_roll_down: false,
+            theta_decomposition: false,
+            bid_ask_adjustment: false,
+            bond_analytics: false,
+            hull_white_mean_reversion: default_hull_white_mean_reversion(),
+            hull_white_volatility: default_hull_white_volatility(),
+            marking_side: MarkingSide::default(),
+            key_rate_dv01: false,
+            key_rate_tenors: default_key_rate_tenors(),
+            key_rate_bump_scheme: KeyRateBumpScheme::default(),
+            cs01: false,
+            cs01_structure: false,
+            cs01_bump_value: default_cs01_bump_value(),
+            cs01_structure_tenors: default_key_rate_tenors(),
+            fx_delta: false,
+            fx_exposure_ladder: false,
+            fx_exposure_ladder_tenors: default_fx_exposure_ladder_tenors(),
+            rho_by_role: false,
+            delta_rho: false,
+            div_carry: false,
+            div_carry_calendar: default_div_carry_calendar(),
+            ladder: false,
+            ladder_delta: false,
+            ladder_spot_shifts: default_ladder_spot_shifts(),
+            ladder_vol_shifts: default_ladder_vol_shifts(),
+            reporting_currency: None,
             //
             stickyness_type,
             lv_interpolator,
+            volatility_time_interpolation: VolatilityTimeInterpolation::default(),
+            volatility_business_time_map: FxHashMap::default(),
             //
             delta_bump_ratio,
             gamma_bump_ratio,
@@ -228,12 +658,32 @@ impl CalculationConfiguration {
             rho_bump_value,
             div_bump_value,
             theta_day,
+            theta_day_mode: ThetaDayMode::default(),
+            theta_calendar: JointCalendar::default(),
+            weekend_theta: false,
+            carry_horizon_day: 90,
             rho_structure_tenors,
             vega_structure_tenors,
             div_structure_tenors,
             vega_matrix_spot_moneyness,
+            vega_matrix_tenors: None,
+            vega_matrix_grids: FxHashMap::default(),
             //
             vanilla_option_calculation_method,
+            greek_method: GreekMethod::default(),
+            bump_sizes: FxHashMap::default(),
+            currency_bump_sizes: FxHashMap::default(),
+            delta_scheme: default_delta_scheme(),
+            vega_scheme: FiniteDifferenceScheme::default(),
+            rho_scheme: FiniteDifferenceScheme::default(),
+            div_delta_scheme: FiniteDifferenceScheme::default(),
+            gamma_richardson_extrapolation: false,
+            payoff_smoothing: None,
+            discontinuous_payoff_ids: FxHashSet::default(),
+            parallel_pricing_threshold: default_parallel_pricing_threshold(),
+            reproducible: false,
+            random_seed: 0,
+            statistics_collection: false,
         })
     }
 
@@ -249,6 +699,13 @@ impl CalculationConfiguration {
             .with_rho_structure_calculation(true)
             .with_div_structure_calculation(true)
             .with_vega_matrix_calculation(true)
+            .with_carry_roll_down_calculation(true)
+            .with_theta_decomposition_calculation(true)
+            .with_bid_ask_adjustment_calculation(true)
+            .with_bond_analytics_calculation(true)
+            .with_key_rate_dv01_calculation(true)
+            .with_cs01_calculation(true)
+            .with_cs01_structure_calculation(true)
     }
 
     pub fn with_theta_day(mut self, theta_day: Integer) -> CalculationConfiguration {
@@ -256,6 +713,226 @@ impl CalculationConfiguration {
         self
     }
 
+    pub fn with_theta_day_mode(mut self, theta_day_mode: ThetaDayMode) -> CalculationConfiguration {
+        self.theta_day_mode = theta_day_mode;
+        self
+    }
+
+    /// calendar of the business day theta for the instruments which do not have their own calendar
+    pub fn with_theta_calendar(mut self, theta_calendar: JointCalendar) -> CalculationConfiguration {
+        self.theta_calendar = theta_calendar;
+        self
+    }
+
+    /// theta per day times the weekends and holidays in the theta period,
+    /// e.g., two days of theta on Friday in the business day mode
+    pub fn with_weekend_theta_calculation(mut self, weekend_theta: bool) -> CalculationConfiguration {
+        self.weekend_theta = weekend_theta;
+        self
+    }
+
+    /// carry and roll-down are calculated over evaluation_date + carry_horizon_day
+    pub fn with_carry_horizon_day(mut self, carry_horizon_day: Integer) -> CalculationConfiguration {
+        self.carry_horizon_day = carry_horizon_day;
+        self
+    }
+
+    pub fn with_carry_roll_down_calculation(mut self, carry_roll_down: bool) -> CalculationConfiguration {
+        self.carry_roll_down = carry_roll_down;
+        self
+    }
+
+    /// decomposition of theta into carry, roll-down and decay. This is calculated only if theta is true
+    pub fn with_theta_decomposition_calculation(mut self, theta_decomposition: bool) -> CalculationConfiguration {
+        self.theta_decomposition = theta_decomposition;
+        self
+    }
+
+    /// exit-price valuation and bid-ask valuation adjustment (exit value - mid value)
+    pub fn with_bid_ask_adjustment_calculation(mut self, bid_ask_adjustment: bool) -> CalculationConfiguration {
+        self.bid_ask_adjustment = bid_ask_adjustment;
+        self
+    }
+
+    /// clean and dirty prices, accrued interest, yield to maturity, modified duration and convexity of bonds
+    pub fn with_bond_analytics_calculation(mut self, bond_analytics: bool) -> CalculationConfiguration {
+        self.bond_analytics = bond_analytics;
+        self
+    }
+
+    /// mean reversion and (normal) volatility of the Hull-White lattice on which the OAS of callable bonds is calculated
+    pub fn with_hull_white_parameters(mut self, mean_reversion: Real, volatility: Real) -> CalculationConfiguration {
+        self.hull_white_mean_reversion = mean_reversion;
+        self.hull_white_volatility = volatility;
+        self
+    }
+
+    pub fn with_marking_side(mut self, marking_side: MarkingSide) -> CalculationConfiguration {
+        self.marking_side = marking_side;
+        self
+    }
+
+    /// key-rate DV01 of bonds and swaps on key_rate_tenors with key_rate_bump_scheme
+    pub fn with_key_rate_dv01_calculation(mut self, key_rate_dv01: bool) -> CalculationConfiguration {
+        self.key_rate_dv01 = key_rate_dv01;
+        self
+    }
+
+    pub fn with_key_rate_tenors(mut self, key_rate_tenors: Vec<Tenor>) -> CalculationConfiguration {
+        self.key_rate_tenors = key_rate_tenors;
+        self
+    }
+
+    pub fn with_key_rate_bump_scheme(
+        mut self,
+        key_rate_bump_scheme: KeyRateBumpScheme,
+    ) -> CalculationConfiguration {
+        self.key_rate_bump_scheme = key_rate_bump_scheme;
+        self
+    }
+
+    /// CS01 on the credit spread curves (spread curves over base curves in MatchParameter)
+    pub fn with_cs01_calculation(mut self, cs01: bool) -> CalculationConfiguration {
+        self.cs01 = cs01;
+        self
+    }
+
+    /// bucketed CS01 on cs01_structure_tenors. This is calculated only if cs01 is true
+    pub fn with_cs01_structure_calculation(mut self, cs01_structure: bool) -> CalculationConfiguration {
+        self.cs01_structure = cs01_structure;
+        self
+    }
+
+    pub fn with_cs01_bump_value(mut self, cs01_bump_value: Real) -> Result<CalculationConfiguration> {
+        if cs01_bump_value <= 0.0 {
+            return Err(anyhow!(
+                "cs01_bump_value must be > 0.0, got {}",
+                cs01_bump_value
+            ));
+        }
+        self.cs01_bump_value = cs01_bump_value;
+        Ok(self)
+    }
+
+    pub fn with_cs01_structure_tenors(mut self, cs01_structure_tenors: Vec<Tenor>) -> CalculationConfiguration {
+        self.cs01_structure_tenors = cs01_structure_tenors;
+        self
+    }
+
+    /// fx_exposure of each currency on the buckets of the settlement dates:
+    /// (evaluation date, t_1], (t_1, t_2], ..., (t_n-1, t_n] on fx_exposure_ladder_tenors and the last one over t_n.
+    /// This is calculated only if fx_exposure is true.
+    pub fn with_fx_exposure_ladder_calculation(mut self, fx_exposure_ladder: bool) -> CalculationConfiguration {
+        self.fx_exposure_ladder = fx_exposure_ladder;
+        self
+    }
+
+    pub fn with_fx_exposure_ladder_tenors(mut self, fx_exposure_ladder_tenors: Vec<Tenor>) -> CalculationConfiguration {
+        self.fx_exposure_ladder_tenors = fx_exposure_ladder_tenors;
+        self
+    }
+
+    /// fx delta and fx gamma on each fx rate used in pricing, bumped by the Fx bump size with delta_scheme.
+    /// This is separate from fx_exposure which decomposes the value by currency.
+    pub fn with_fx_delta_calculation(mut self, fx_delta: bool) -> CalculationConfiguration {
+        self.fx_delta = fx_delta;
+        self
+    }
+
+    /// rho split by the roles of the curves (CurveRole), bumped by the Rate bump size in the instrument currency.
+    /// A curve in several roles, e.g., discounting and projecting an IRS, is shifted in one role at a time.
+    pub fn with_rho_by_role_calculation(mut self, rho_by_role: bool) -> CalculationConfiguration {
+        self.rho_by_role = rho_by_role;
+        self
+    }
+
+    /// delta change on the 1bp parallel bump of each curve, which is the same as the rho change on the 1% spot move,
+    /// for the instruments depending on both (e.g., quanto futures and equity-linked notes)
+    pub fn with_delta_rho_calculation(mut self, delta_rho: bool) -> CalculationConfiguration {
+        self.delta_rho = delta_rho;
+        self
+    }
+
+    /// spot/vol ladder of each underlying: values on the grid of the ladder spot and vol shifts
+    pub fn with_ladder_calculation(mut self, ladder: bool) -> CalculationConfiguration {
+        self.ladder = ladder;
+        self
+    }
+
+    /// deltas on each node of the ladder. This is calculated only if ladder is true
+    pub fn with_ladder_delta_calculation(mut self, ladder_delta: bool) -> CalculationConfiguration {
+        self.ladder_delta = ladder_delta;
+        self
+    }
+
+    /// relative spot shifts (e.g., -0.1 for the spot down 10%) and absolute vol shifts of the ladder
+    pub fn with_ladder_shifts(
+        mut self,
+        ladder_spot_shifts: Vec<Real>,
+        ladder_vol_shifts: Vec<Real>,
+    ) -> CalculationConfiguration {
+        self.ladder_spot_shifts = ladder_spot_shifts;
+        self.ladder_vol_shifts = ladder_vol_shifts;
+        self
+    }
+
+    /// dividend carry of each underlying: the dividends whose ex-dividend dates are
+    /// after the evaluation date and not after the next business day in div_carry_calendar,
+    /// multiplied by the position (dV/dS) in the underlying
+    pub fn with_div_carry_calculation(mut self, div_carry: bool) -> CalculationConfiguration {
+        self.div_carry = div_carry;
+        self
+    }
+
+    pub fn with_div_carry_calendar(mut self, div_carry_calendar: Calendar) -> CalculationConfiguration {
+        self.div_carry_calendar = div_carry_calendar;
+        self
+    }
+
+    /// each result also has its copy in the reporting currency (CalculationResult::get_reporting_result)
+    /// converted at the fx rates given to the engine
+    pub fn with_reporting_currency(mut self, reporting_currency: Currency) -> CalculationConfiguration {
+        self.reporting_currency = Some(reporting_currency);
+        self
+    }
+
+    pub fn with_npv_calculation(mut self, npv: bool) -> CalculationConfiguration {
+        self.npv = npv;
+        self
+    }
+
+    /// the same configuration (stickyness, interpolation, calculation method and so on)
+    /// only with npv and fx exposure, e.g., for the revaluations on scenarios
+    pub fn npv_only(mut self) -> CalculationConfiguration {
+        self.npv = true;
+        self.delta = false;
+        self.gamma = false;
+        self.vega = false;
+        self.rho = false;
+        self.div_delta = false;
+        self.theta = false;
+        self.vega_strucure = false;
+        self.rho_structure = false;
+        self.gamma_structure = false;
+        self.div_structure = false;
+        self.vega_matrix = false;
+        self.carry_roll_down = false;
+        self.theta_decomposition = false;
+        self.weekend_theta = false;
+        self.bid_ask_adjustment = false;
+        self.bond_analytics = false;
+        self.key_rate_dv01 = false;
+        self.cs01 = false;
+        self.cs01_structure = false;
+        self.fx_delta = false;
+        self.fx_exposure_ladder = false;
+        self.rho_by_role = false;
+        self.delta_rho = false;
+        self.ladder = false;
+        self.div_carry = false;
+        self
+    }
+
     pub fn with_delta_calculation(mut self, delta: bool) -> CalculationConfiguration {
         self.delta = delta;
         self
@@ -302,6 +979,24 @@ impl CalculationConfiguration {
         self
     }
 
+    /// bucketed gamma on rho_structure_tenors: the change of the rho of each bucket on the 1bp bump of the bucket
+    pub fn with_gamma_structure_calculation(
+        mut self,
+        gamma_structure: bool,
+    ) -> CalculationConfiguration {
+        self.gamma_structure = gamma_structure;
+        self
+    }
+
+    /// absolute bump of the rates in each bucket of gamma_structure
+    pub fn with_gamma_structure_bump_value(
+        mut self,
+        gamma_structure_bump_value: Real,
+    ) -> CalculationConfiguration {
+        self.gamma_structure_bump_value = gamma_structure_bump_value;
+        self
+    }
+
     pub fn with_stickyness_type(
         mut self,
         stickyness_type: StickynessType,
@@ -391,6 +1086,18 @@ impl CalculationConfiguration {
         self
     }
 
+    /// expiry buckets of vega_matrix which are vega_structure_tenors if not given
+    pub fn with_vega_matrix_tenors(mut self, vega_matrix_tenors: Vec<Tenor>) -> CalculationConfiguration {
+        self.vega_matrix_tenors = Some(vega_matrix_tenors);
+        self
+    }
+
+    /// vega_matrix grid of the underlying overriding vega_matrix_tenors and vega_matrix_spot_moneyness
+    pub fn with_vega_matrix_grid(mut self, und_id: StaticId, grid: VegaMatrixGrid) -> CalculationConfiguration {
+        self.vega_matrix_grids.insert(und_id, grid);
+        self
+    }
+
     pub fn with_vanilla_option_calculation_method(
         mut self,
         vanilla_option_calculation_method: VanillaOptionCalculationMethod,
@@ -399,6 +1106,190 @@ impl CalculationConfiguration {
         self
     }
 
+    /// BumpAndRevalue or AutomaticDifferentiation for delta, vega and rho
+    pub fn with_greek_method(mut self, greek_method: GreekMethod) -> CalculationConfiguration {
+        self.greek_method = greek_method;
+        self
+    }
+
+    /// bump size of a risk factor class in place of delta_bump_ratio (Spot), vega_bump_value (Volatility),
+    /// rho_bump_value (Rate), div_bump_value (Dividend), and the relative 1% (Fx)
+    pub fn with_bump_size(
+        mut self,
+        risk_factor_class: RiskFactorClass,
+        bump_size: BumpSize,
+    ) -> CalculationConfiguration {
+        self.bump_sizes.insert(risk_factor_class, bump_size);
+        self
+    }
+
+    /// bump size of a risk factor class for the risk factors in the currency
+    pub fn with_currency_bump_size(
+        mut self,
+        currency: Currency,
+        risk_factor_class: RiskFactorClass,
+        bump_size: BumpSize,
+    ) -> CalculationConfiguration {
+        self.currency_bump_sizes
+            .entry(currency)
+            .or_default()
+            .insert(risk_factor_class, bump_size);
+        self
+    }
+
+    /// The currency of a risk factor is the currency of the equity or the curve,
+    /// and the foreign currency (currency1) of an fx rate.
+    pub fn get_bump_size(
+        &self,
+        risk_factor_class: RiskFactorClass,
+        currency: Option<Currency>,
+    ) -> BumpSize {
+        if let Some(bump_size) = currency
+            .and_then(|ccy| self.currency_bump_sizes.get(&ccy))
+            .and_then(|bump_sizes| bump_sizes.get(&risk_factor_class))
+        {
+            return *bump_size;
+        }
+        if let Some(bump_size) = self.bump_sizes.get(&risk_factor_class) {
+            return *bump_size;
+        }
+        let (value, bump_type) = match risk_factor_class {
+            RiskFactorClass::Spot => (self.delta_bump_ratio, BumpType::Relative),
+            RiskFactorClass::Volatility => (self.vega_bump_value, BumpType::Absolute),
+            RiskFactorClass::Rate => (self.rho_bump_value, BumpType::Absolute),
+            RiskFactorClass::Dividend => (self.div_bump_value, BumpType::Absolute),
+            RiskFactorClass::Fx => (0.01, BumpType::Relative),
+        };
+        BumpSize { value, bump_type }
+    }
+
+    /// Central by default
+    pub fn with_delta_scheme(mut self, delta_scheme: FiniteDifferenceScheme) -> CalculationConfiguration {
+        self.delta_scheme = delta_scheme;
+        self
+    }
+
+    /// Forward by default
+    pub fn with_vega_scheme(mut self, vega_scheme: FiniteDifferenceScheme) -> CalculationConfiguration {
+        self.vega_scheme = vega_scheme;
+        self
+    }
+
+    /// Forward by default
+    pub fn with_rho_scheme(mut self, rho_scheme: FiniteDifferenceScheme) -> CalculationConfiguration {
+        self.rho_scheme = rho_scheme;
+        self
+    }
+
+    /// Forward by default
+    pub fn with_div_delta_scheme(
+        mut self,
+        div_delta_scheme: FiniteDifferenceScheme,
+    ) -> CalculationConfiguration {
+        self.div_delta_scheme = div_delta_scheme;
+        self
+    }
+
+    /// gamma = (4 Γ(h) - Γ(2h)) / 3 where Γ(h) is the central second difference on the spot bump h.
+    /// It cancels the h² error term at the cost of two more revaluations per underlying.
+    pub fn with_gamma_richardson_extrapolation(
+        mut self,
+        gamma_richardson_extrapolation: bool,
+    ) -> CalculationConfiguration {
+        self.gamma_richardson_extrapolation = gamma_richardson_extrapolation;
+        self
+    }
+
+    pub fn with_payoff_smoothing(mut self, payoff_smoothing: PayoffSmoothing) -> CalculationConfiguration {
+        self.payoff_smoothing = Some(payoff_smoothing);
+        self
+    }
+
+    /// instruments with discontinuous payoffs, e.g., digitals and barriers, whose delta and gamma are smoothed
+    pub fn with_discontinuous_payoff_ids(
+        mut self,
+        discontinuous_payoff_ids: Vec<StaticId>,
+    ) -> CalculationConfiguration {
+        self.discontinuous_payoff_ids.extend(discontinuous_payoff_ids);
+        self
+    }
+
+    /// usize::MAX keeps the pricing of an engine in the calling thread,
+    /// e.g., when the engines are already distributed over all cores
+    pub fn with_parallel_pricing_threshold(
+        mut self,
+        parallel_pricing_threshold: usize,
+    ) -> CalculationConfiguration {
+        self.parallel_pricing_threshold = parallel_pricing_threshold;
+        self
+    }
+
+    pub fn get_parallel_pricing_threshold(&self) -> usize {
+        self.parallel_pricing_threshold
+    }
+
+    /// In the reproducibility mode, the results are bit-identical across runs regardless of the rayon scheduling:
+    /// the engines price their instruments in the calling thread (parallel_pricing_threshold is ignored).
+    /// The instrument groups are still calculated in parallel, and the results in the order of the ids
+    /// are given by EngineGenerator::get_sorted_calculation_results.
+    pub fn with_reproducibility(mut self, reproducible: bool) -> CalculationConfiguration {
+        self.reproducible = reproducible;
+        self
+    }
+
+    pub fn is_reproducible(&self) -> bool {
+        self.reproducible
+    }
+
+    /// seed of the Monte Carlo VaR scenarios of a ScenarioGenerator without its own seed
+    pub fn with_random_seed(mut self, random_seed: u64) -> CalculationConfiguration {
+        self.random_seed = random_seed;
+        self
+    }
+
+    pub fn get_random_seed(&self) -> u64 {
+        self.random_seed
+    }
+
+    /// The engines record the elapsed time of the parameter build, the pricer initialization
+    /// and each risk measure, which are collected by EngineGenerator::get_calculation_statistics
+    pub fn with_statistics_collection(mut self, statistics_collection: bool) -> CalculationConfiguration {
+        self.statistics_collection = statistics_collection;
+        self
+    }
+
+    pub fn get_statistics_collection(&self) -> bool {
+        self.statistics_collection
+    }
+
+    pub fn get_payoff_smoothing(&self) -> Option<PayoffSmoothing> {
+        self.payoff_smoothing
+    }
+
+    pub fn is_discontinuous_payoff_id(&self, inst_id: &StaticId) -> bool {
+        self.discontinuous_payoff_ids.contains(inst_id)
+    }
+
+    pub fn get_delta_scheme(&self) -> FiniteDifferenceScheme {
+        self.delta_scheme
+    }
+
+    pub fn get_vega_scheme(&self) -> FiniteDifferenceScheme {
+        self.vega_scheme
+    }
+
+    pub fn get_rho_scheme(&self) -> FiniteDifferenceScheme {
+        self.rho_scheme
+    }
+
+    pub fn get_div_delta_scheme(&self) -> FiniteDifferenceScheme {
+        self.div_delta_scheme
+    }
+
+    pub fn get_gamma_richardson_extrapolation(&self) -> bool {
+        self.gamma_richardson_extrapolation
+    }
+
     pub fn with_lv_interpolator(
         mut self,
         lv_interpolator: VolatilityInterplator,
@@ -407,10 +1298,39 @@ impl CalculationConfiguration {
         self
     }
 
+    pub fn with_volatility_time_interpolation(
+        mut self,
+        volatility_time_interpolation: VolatilityTimeInterpolation,
+    ) -> CalculationConfiguration {
+        self.volatility_time_interpolation = volatility_time_interpolation;
+        self
+    }
+
+    pub fn get_volatility_time_interpolation(&self) -> VolatilityTimeInterpolation {
+        self.volatility_time_interpolation
+    }
+
+    /// underlying id -> business time (weekend and event weights) of its volatility surface
+    pub fn with_volatility_business_time_map(
+        mut self,
+        volatility_business_time_map: FxHashMap<StaticId, BusinessTime>,
+    ) -> CalculationConfiguration {
+        self.volatility_business_time_map = volatility_business_time_map;
+        self
+    }
+
+    pub fn get_volatility_business_time(&self, und_id: &StaticId) -> Option<&BusinessTime> {
+        self.volatility_business_time_map.get(und_id)
+    }
+
     pub fn get_vanilla_option_calculation_method(&self) -> VanillaOptionCalculationMethod {
         self.vanilla_option_calculation_method
     }
 
+    pub fn get_greek_method(&self) -> GreekMethod {
+        self.greek_method
+    }
+
     pub fn get_div_structure_tenors(&self) -> &Vec<Tenor> {
         &self.div_structure_tenors
     }
@@ -427,6 +1347,21 @@ impl CalculationConfiguration {
         &self.vega_matrix_spot_moneyness
     }
 
+    /// vega_matrix grid of the underlying: the override if any, otherwise
+    /// (vega_matrix_tenors or vega_structure_tenors, vega_matrix_spot_moneyness)
+    pub fn get_vega_matrix_grid(&self, und_id: &StaticId) -> VegaMatrixGrid {
+        match self.vega_matrix_grids.get(und_id) {
+            Some(grid) => grid.clone(),
+            None => VegaMatrixGrid {
+                tenors: self
+                    .vega_matrix_tenors
+                    .clone()
+                    .unwrap_or_else(|| self.vega_structure_tenors.clone()),
+                spot_moneyness: self.vega_matrix_spot_moneyness.to_vec(),
+            },
+        }
+    }
+
     pub fn get_vega_matrix_calculation(&self) -> bool {
         self.vega_matrix
     }
@@ -459,6 +1394,123 @@ impl CalculationConfiguration {
         self.theta_day
     }
 
+    pub fn get_theta_day_mode(&self) -> ThetaDayMode {
+        self.theta_day_mode
+    }
+
+    pub fn get_theta_calendar(&self) -> &JointCalendar {
+        &self.theta_calendar
+    }
+
+    pub fn get_weekend_theta_calculation(&self) -> bool {
+        self.weekend_theta
+    }
+
+    pub fn get_carry_horizon_day(&self) -> Integer {
+        self.carry_horizon_day
+    }
+
+    pub fn get_carry_roll_down_calculation(&self) -> bool {
+        self.carry_roll_down
+    }
+
+    pub fn get_theta_decomposition_calculation(&self) -> bool {
+        self.theta_decomposition
+    }
+
+    pub fn get_bid_ask_adjustment_calculation(&self) -> bool {
+        self.bid_ask_adjustment
+    }
+
+    pub fn get_bond_analytics_calculation(&self) -> bool {
+        self.bond_analytics
+    }
+
+    /// (mean reversion, volatility)
+    pub fn get_hull_white_parameters(&self) -> (Real, Real) {
+        (self.hull_white_mean_reversion, self.hull_white_volatility)
+    }
+
+    pub fn get_marking_side(&self) -> MarkingSide {
+        self.marking_side
+    }
+
+    pub fn get_key_rate_dv01_calculation(&self) -> bool {
+        self.key_rate_dv01
+    }
+
+    pub fn get_key_rate_tenors(&self) -> &Vec<Tenor> {
+        &self.key_rate_tenors
+    }
+
+    pub fn get_key_rate_bump_scheme(&self) -> KeyRateBumpScheme {
+        self.key_rate_bump_scheme
+    }
+
+    pub fn get_cs01_calculation(&self) -> bool {
+        self.cs01
+    }
+
+    pub fn get_cs01_structure_calculation(&self) -> bool {
+        self.cs01_structure
+    }
+
+    pub fn get_cs01_bump_value(&self) -> Real {
+        self.cs01_bump_value
+    }
+
+    pub fn get_cs01_structure_tenors(&self) -> &Vec<Tenor> {
+        &self.cs01_structure_tenors
+    }
+
+    pub fn get_fx_delta_calculation(&self) -> bool {
+        self.fx_delta
+    }
+
+    pub fn get_fx_exposure_ladder_calculation(&self) -> bool {
+        self.fx_exposure_ladder
+    }
+
+    pub fn get_fx_exposure_ladder_tenors(&self) -> &Vec<Tenor> {
+        &self.fx_exposure_ladder_tenors
+    }
+
+    pub fn get_rho_by_role_calculation(&self) -> bool {
+        self.rho_by_role
+    }
+
+    pub fn get_ladder_calculation(&self) -> bool {
+        self.ladder
+    }
+
+    pub fn get_ladder_delta_calculation(&self) -> bool {
+        self.ladder_delta
+    }
+
+    pub fn get_ladder_spot_shifts(&self) -> &Vec<Real> {
+        &self.ladder_spot_shifts
+    }
+
+    pub fn get_ladder_vol_shifts(&self) -> &Vec<Real> {
+        &self.ladder_vol_shifts
+    }
+
+    pub fn get_delta_rho_calculation(&self) -> bool {
+        self.delta_rho
+    }
+
+    pub fn get_div_carry_calculation(&self) -> bool {
+        self.div_carry
+    }
+
+    pub fn get_div_carry_calendar(&self) -> &Calendar {
+        &self.div_carry_calendar
+    }
+
+    pub fn get_reporting_currency(&self) -> Option<Currency> {
+        self.reporting_currency
+    }
+
     pub fn get_stickyness_type(&self) -> StickynessType {
         self.stickyness_type
     }
@@ -503,6 +1555,14 @@ impl CalculationConfiguration {
         self.rho_structure
     }
 
+    pub fn get_gamma_structure_calculation(&self) -> bool {
+        self.gamma_structure
+    }
+
+    pub fn get_gamma_structure_bump_value(&self) -> Real {
+        self.gamma_structure_bump_value
+    }
+
     pub fn get_fx_exposure_calculation(&self) -> bool {
         self.fx_exposure
     }
@@ -526,4 +1586,70 @@ mod tests {
         println!("deserialized = {:?}", deserialized);
         assert_eq!(config, deserialized);
     }
+
+    #[test]
+    fn test_bump_size() -> Result<()> {
+        let config = CalculationConfiguration::default()
+            .with_bump_size(RiskFactorClass::Volatility, BumpSize::relative(0.05)?)
+            .with_currency_bump_size(Currency::USD, RiskFactorClass::Spot, BumpSize::absolute(0.5)?);
+
+        // defaults from the legacy bump values
+        let spot_krw = config.get_bump_size(RiskFactorClass::Spot, Some(Currency::KRW));
+        assert_eq!(spot_krw, BumpSize::relative(0.01)?);
+        assert_eq!(
+            config.get_bump_size(RiskFactorClass::Rate, None),
+            BumpSize::absolute(0.0001)?
+        );
+        // class and currency overrides
+        assert_eq!(
+            config.get_bump_size(RiskFactorClass::Volatility, Some(Currency::USD)),
+            BumpSize::relative(0.05)?
+        );
+        let spot_usd = config.get_bump_size(RiskFactorClass::Spot, Some(Currency::USD));
+        assert_eq!(spot_usd.get_bump_type(), BumpType::Absolute);
+
+        // conversions at the level of the risk factor
+        assert!((spot_krw.get_absolute_bump(350.0)? - 3.5).abs() < 1.0e-5);
+        assert!((spot_usd.get_relative_bump(50.0)? - 0.01).abs() < 1.0e-7);
+        assert!(BumpSize::relative(0.01)?.get_absolute_bump(0.0).is_err());
+        assert!(BumpSize::absolute(-0.01).is_err());
+
+        let serialized = serde_json::to_string(&config)?;
+        let deserialized: CalculationConfiguration = serde_json::from_str(&serialized)?;
+        assert_eq!(config, deserialized);
+        Ok(())
+    }
+
+    #[test]
+    fn test_vega_matrix_grid() -> Result<()> {
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let other_id = StaticId::from_str("SPX", "CBOE");
+        let tenors: Vec<Tenor> = ["3M", "6M", "1Y"]
+            .iter()
+            .map(|x| Tenor::new_from_string(x))
+            .collect::<Result<_>>()?;
+        let grid = VegaMatrixGrid::new(tenors.clone(), vec![0.9, 1.0, 1.1])?;
+        let config = CalculationConfiguration::default()
+            .with_vega_matrix_tenors(tenors[..2].to_vec())
+            .with_vega_matrix_grid(und_id, grid.clone());
+
+        assert_eq!(config.get_vega_matrix_grid(&und_id), grid);
+        let default_grid = config.get_vega_matrix_grid(&other_id);
+        assert_eq!(default_grid.get_tenors(), &tenors[..2].to_vec());
+        assert_eq!(default_grid.shape(), (2, 17));
+        assert_eq!(
+            CalculationConfiguration::default()
+                .get_vega_matrix_grid(&other_id)
+                .get_tenors(),
+            CalculationConfiguration::default().get_vega_structure_tenors()
+        );
+
+        assert!(VegaMatrixGrid::new(tenors.clone(), vec![1.0, 0.9]).is_err());
+        assert!(VegaMatrixGrid::new(vec![], vec![1.0]).is_err());
+
+        let serialized = serde_json::to_string(&config)?;
+        let deserialized: CalculationConfiguration = serde_json::from_str(&serialized)?;
+        assert_eq!(config, deserialized);
+        Ok(())
+    }
 }
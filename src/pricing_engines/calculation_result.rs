@@ -1,6 +1,10 @@
-use crate::currency::Currency;
+use crate::currency::{Currency, FxCode};
 use crate::definitions::{Integer, Real};
+use crate::enums::CurveRole;
+use crate::instruments::cashflow::Cashflow;
 use crate::instruments::inst_info::InstInfo;
+use crate::pricing_engines::bond_analytics::BondAnalytics;
+use crate::pricing_engines::calculation_configuration::VegaMatrixGrid;
 use crate::pricing_engines::npv_result::NpvResult;
 use crate::utils::number_format::{formatted_number, write_number_with_commas};
 use anyhow::{anyhow, Result};
@@ -10,6 +14,123 @@ use rustc_hash::FxHashMap;
 use time::OffsetDateTime;
 use static_id::static_id::StaticId;
 
+/// Decomposition of theta in the same unit as theta.
+/// carry: coupon and dividend cashflows, and the accrual on the realized forward rates for bonds and swaps
+/// roll_down: the value change of bonds and swaps rolling down the unchanged curve
+/// decay: the rest, e.g., the time decay of the volatility (time value) of options
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+pub struct ThetaDecomposition {
+    carry: Real,
+    roll_down: Real,
+    decay: Real,
+}
+
+impl ThetaDecomposition {
+    pub fn new(carry: Real, roll_down: Real, decay: Real) -> ThetaDecomposition {
+        ThetaDecomposition {
+            carry,
+            roll_down,
+            decay,
+        }
+    }
+
+    pub fn get_carry(&self) -> Real {
+        self.carry
+    }
+
+    pub fn get_roll_down(&self) -> Real {
+        self.roll_down
+    }
+
+    pub fn get_decay(&self) -> Real {
+        self.decay
+    }
+
+    pub fn get_theta(&self) -> Real {
+        self.carry + self.roll_down + self.decay
+    }
+
+    fn scaled(&self, factor: Real) -> ThetaDecomposition {
+        ThetaDecomposition::new(self.carry * factor, self.roll_down * factor, self.decay * factor)
+    }
+}
+
+/// Spot/vol ladder (risk slide) of an instrument on an underlying.
+/// The rows are the relative spot shifts, e.g., -0.1 for the spot down 10%,
+/// and the columns are the absolute volatility shifts, e.g., 0.05 for the volatility up 5%p.
+/// values: value (npv * unit_notional) on each node
+/// deltas: delta (value change on 1% spot move) on each node, if calculated
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Ladder {
+    spot_shifts: Vec<Real>,
+    vol_shifts: Vec<Real>,
+    base_value: Real,
+    values: Array2<Real>,
+    deltas: Option<Array2<Real>>,
+}
+
+impl Ladder {
+    pub fn new(
+        spot_shifts: Vec<Real>,
+        vol_shifts: Vec<Real>,
+        base_value: Real,
+        values: Array2<Real>,
+        deltas: Option<Array2<Real>>,
+    ) -> Result<Ladder> {
+        let shape = (spot_shifts.len(), vol_shifts.len());
+        if values.dim() != shape || deltas.as_ref().is_some_and(|d| d.dim() != shape) {
+            return Err(anyhow!(
+                "({}:{}) ladder must be {:?} on the spot and vol shifts",
+                file!(),
+                line!(),
+                shape,
+            ));
+        }
+        Ok(Ladder {
+            spot_shifts,
+            vol_shifts,
+            base_value,
+            values,
+            deltas,
+        })
+    }
+
+    pub fn get_spot_shifts(&self) -> &Vec<Real> {
+        &self.spot_shifts
+    }
+
+    pub fn get_vol_shifts(&self) -> &Vec<Real> {
+        &self.vol_shifts
+    }
+
+    pub fn get_base_value(&self) -> Real {
+        self.base_value
+    }
+
+    pub fn get_values(&self) -> &Array2<Real> {
+        &self.values
+    }
+
+    pub fn get_deltas(&self) -> Option<&Array2<Real>> {
+        self.deltas.as_ref()
+    }
+
+    /// value changes from the base value on each node
+    pub fn get_pnls(&self) -> Array2<Real> {
+        &self.values - self.base_value
+    }
+
+    fn scaled(&self, factor: Real) -> Ladder {
+        Ladder {
+            spot_shifts: self.spot_shifts.clone(),
+            vol_shifts: self.vol_shifts.clone(),
+            base_value: self.base_value * factor,
+            values: &self.values * factor,
+            deltas: self.deltas.as_ref().map(|d| d * factor),
+        }
+    }
+}
+
 /// CalculationResult is a struct that holds the result of the calculation.
 /// It is used to store the result of the calculation of the pricing engine.
 /// instrument: InstrumentInfo
@@ -29,20 +150,42 @@ pub struct CalculationResult {
     npv_result: Option<NpvResult>,
     value: Option<Real>,
     fx_exposure: Option<FxHashMap<Currency, Real>>,
+    fx_exposure_ladder: Option<FxHashMap<Currency, Vec<Real>>>, // currency -> fx_exposure on the settlement date buckets
+    fx_delta: Option<FxHashMap<FxCode, Real>>, // fx code -> value change on the 1% rise of the fx rate
+    fx_gamma: Option<FxHashMap<FxCode, Real>>, // fx code -> delta change on the 1% rise of the fx rate
     delta: Option<FxHashMap<StaticId, Real>>,
     gamma: Option<FxHashMap<StaticId, Real>>,
     vega: Option<FxHashMap<StaticId, Real>>,
     vega_strucure: Option<FxHashMap<StaticId, Vec<Real>>>, // underlying code -> Vec::<Real> on vega_tenor in CalculationConfiguration
     vega_matrix: Option<FxHashMap<StaticId, Array2<Real>>>, // underlying code -> Vec<Vec<Real>> vega_matrix
+    vega_matrix_grid: Option<FxHashMap<StaticId, VegaMatrixGrid>>, // underlying code -> tenors (rows) and spot moneyness (columns) of vega_matrix
+    ladder: Option<FxHashMap<StaticId, Ladder>>, // underlying code -> spot/vol ladder
     theta: Option<Real>,
+    theta_decomposition: Option<ThetaDecomposition>,
+    weekend_theta: Option<Real>, // theta on the weekends and holidays in the theta period
     div_delta: Option<FxHashMap<StaticId, Real>>,
     div_structure: Option<FxHashMap<StaticId, Vec<Real>>>, // underlying code -> Vec::<Real> on div_tenor in CalculationConfiguration
+    div_carry: Option<FxHashMap<StaticId, Real>>, // underlying code -> dividends going ex by the next business day on the position
     rho: Option<FxHashMap<StaticId, Real>>,                // Curve Code -> rho
     rho_structure: Option<FxHashMap<StaticId, Vec<Real>>>, // curve code -> Vec::<Real> on rho_tenor in CalculationConfig
+    gamma_structure: Option<FxHashMap<StaticId, Vec<Real>>>, // curve code -> rho change on 1bp of each bucket on rho_tenor
+    rho_by_role: Option<FxHashMap<CurveRole, Real>>, // curve role -> rho on the curves in the role
+    delta_rho: Option<FxHashMap<StaticId, FxHashMap<StaticId, Real>>>, // underlying code -> curve code -> delta change on 1bp
+    key_rate_dv01: Option<FxHashMap<StaticId, Vec<Real>>>, // curve code -> Vec::<Real> on key_rate_tenors in CalculationConfig
+    cs01: Option<FxHashMap<StaticId, Real>>, // credit curve code -> cs01
+    cs01_structure: Option<FxHashMap<StaticId, Vec<Real>>>, // credit curve code -> Vec::<Real> on cs01_structure_tenors in CalculationConfig
     theta_day: Option<Integer>,
-    #[serde(skip)]
-    cashflows: Option<FxHashMap<OffsetDateTime, Real>>, //expected cashflow inbetween
+    carry: Option<Real>,     // forward value at the horizon - present value (bond and swap)
+    roll_down: Option<Real>, // value on the unchanged curve at the horizon - forward value (bond and swap)
+    exit_value: Option<Real>, // value with the quotes at the marking side
+    bid_ask_adjustment: Option<Real>, // exit_value - value with the quotes at mid
+    #[serde(default)]
+    short_exit_value: Option<Real>, // exit_value of a short position in the conservative marking
+    bond_analytics: Option<BondAnalytics>, // prices and yield measures on a unit notional
+    #[serde(default)]
+    cashflows: Option<Vec<Cashflow>>, // cashflows on a unit notional after the evaluation date
     representation_currency: Option<Currency>,
+    reporting_result: Option<Box<CalculationResult>>, // the same result in the reporting currency of CalculationConfiguration
 }
 
 impl std::fmt::Debug for CalculationResult {
@@ -71,6 +214,36 @@ impl std::fmt::Debug for CalculationResult {
             }
             writeln!(f)?;
         }
+        if let Some(ref fx_exposure_ladder) = self.fx_exposure_ladder {
+            writeln!(f, " * fx_exposure_ladder: ")?;
+            for (currency, value) in fx_exposure_ladder {
+                write!(f, "        {}: ", currency)?;
+                for v in value {
+                    write_number_with_commas(f, *v)?;
+                    write!(f, " | ")?;
+                }
+                writeln!(f)?;
+            }
+            writeln!(f)?;
+        }
+        if let Some(ref fx_delta) = self.fx_delta {
+            writeln!(f, " * fx_delta: ")?;
+            for (key, value) in fx_delta {
+                write!(f, "        {}: ", key)?;
+                write_number_with_commas(f, *value)?;
+                writeln!(f)?;
+            }
+            writeln!(f)?;
+        }
+        if let Some(ref fx_gamma) = self.fx_gamma {
+            writeln!(f, " * fx_gamma: ")?;
+            for (key, value) in fx_gamma {
+                write!(f, "        {}: ", key)?;
+                write_number_with_commas(f, *value)?;
+                writeln!(f)?;
+            }
+            writeln!(f)?;
+        }
         if let Some(ref delta) = self.delta {
             writeln!(f, " * delta: ")?;
             for (key, value) in delta {
@@ -96,8 +269,64 @@ impl std::fmt::Debug for CalculationResult {
             write_number_with_commas(f, *theta)?;
             writeln!(f)?;
         }
+
+        if let Some(ref theta_decomposition) = self.theta_decomposition {
+            write!(f, " * theta_decomposition: carry = ")?;
+            write_number_with_commas(f, theta_decomposition.get_carry())?;
+            write!(f, ", roll_down = ")?;
+            write_number_with_commas(f, theta_decomposition.get_roll_down())?;
+            write!(f, ", decay = ")?;
+            write_number_with_commas(f, theta_decomposition.get_decay())?;
+            writeln!(f)?;
+        }
+
+        if let Some(weekend_theta) = self.weekend_theta {
+            write!(f, " * weekend_theta: ")?;
+            write_number_with_commas(f, weekend_theta)?;
+            writeln!(f)?;
+        }
         writeln!(f)?;
 
+        if let Some(carry) = self.carry {
+            write!(f, " * carry: ")?;
+            write_number_with_commas(f, carry)?;
+            writeln!(f)?;
+        }
+
+        if let Some(roll_down) = self.roll_down {
+            write!(f, " * roll_down: ")?;
+            write_number_with_commas(f, roll_down)?;
+            writeln!(f)?;
+        }
+
+        if let Some(exit_value) = self.exit_value {
+            write!(f, " * exit_value: ")?;
+            write_number_with_commas(f, exit_value)?;
+            writeln!(f)?;
+        }
+
+        if let Some(bid_ask_adjustment) = self.bid_ask_adjustment {
+            write!(f, " * bid_ask_adjustment: ")?;
+            write_number_with_commas(f, bid_ask_adjustment)?;
+            writeln!(f)?;
+        }
+
+        if let Some(ref bond_analytics) = self.bond_analytics {
+            writeln!(f, " * bond_analytics: ")?;
+            writeln!(f, "        clean_price: {}", bond_analytics.get_clean_price())?;
+            writeln!(f, "        dirty_price: {}", bond_analytics.get_dirty_price())?;
+            writeln!(f, "        accrued_interest: {}", bond_analytics.get_accrued_interest())?;
+            writeln!(f, "        yield_to_maturity: {}", bond_analytics.get_yield_to_maturity())?;
+            writeln!(f, "        modified_duration: {}", bond_analytics.get_modified_duration())?;
+            writeln!(f, "        convexity: {}", bond_analytics.get_convexity())?;
+            if let Some(z_spread) = bond_analytics.get_z_spread() {
+                writeln!(f, "        z_spread: {}", z_spread)?;
+            }
+            if let Some(oas) = bond_analytics.get_oas() {
+                writeln!(f, "        oas: {}", oas)?;
+            }
+        }
+
         if let Some(ref vega) = self.vega {
             writeln!(f, " * vega: ")?;
             for (key, value) in vega {
@@ -135,6 +364,23 @@ impl std::fmt::Debug for CalculationResult {
             writeln!(f)?;
         }
 
+        if let Some(ref gamma_structure) = self.gamma_structure {
+            writeln!(f, " * gamma_structure: ")?;
+            for (key, value) in gamma_structure {
+                let vector_sum = value.iter().sum::<Real>();
+                write!(f, "        {} (sum = ", key)?;
+                write_number_with_commas(f, vector_sum)?;
+                write!(f, "): ")?;
+
+                for v in value {
+                    write_number_with_commas(f, *v)?;
+                    write!(f, " | ")?;
+                }
+                writeln!(f)?;
+            }
+            writeln!(f)?;
+        }
+
         if let Some(ref rho_structure) = self.rho_structure {
             writeln!(f, " * rho_structure: ")?;
             for (key, value) in rho_structure {
@@ -152,6 +398,72 @@ impl std::fmt::Debug for CalculationResult {
             writeln!(f)?;
         }
 
+        if let Some(ref key_rate_dv01) = self.key_rate_dv01 {
+            writeln!(f, " * key_rate_dv01: ")?;
+            for (key, value) in key_rate_dv01 {
+                let vector_sum = value.iter().sum::<Real>();
+                write!(f, "        {} (sum = ", key)?;
+                write_number_with_commas(f, vector_sum)?;
+                write!(f, "): ")?;
+
+                for v in value {
+                    write_number_with_commas(f, *v)?;
+                    write!(f, " | ")?;
+                }
+                writeln!(f)?;
+            }
+            writeln!(f)?;
+        }
+
+        if let Some(ref rho_by_role) = self.rho_by_role {
+            writeln!(f, " * rho_by_role: ")?;
+            for (role, value) in rho_by_role {
+                write!(f, "        {:?}: ", role)?;
+                write_number_with_commas(f, *value)?;
+                writeln!(f)?;
+            }
+            writeln!(f)?;
+        }
+
+        if let Some(ref delta_rho) = self.delta_rho {
+            writeln!(f, " * delta_rho: ")?;
+            for (und_code, curve_map) in delta_rho {
+                for (curve_code, value) in curve_map {
+                    write!(f, "        {:?} x {:?}: ", und_code, curve_code)?;
+                    write_number_with_commas(f, *value)?;
+                    writeln!(f)?;
+                }
+            }
+            writeln!(f)?;
+        }
+
+        if let Some(ref cs01) = self.cs01 {
+            writeln!(f, " * cs01: ")?;
+            for (key, value) in cs01 {
+                write!(f, "        {}: ", key)?;
+                write_number_with_commas(f, *value)?;
+                writeln!(f)?;
+            }
+            writeln!(f)?;
+        }
+
+        if let Some(ref cs01_structure) = self.cs01_structure {
+            writeln!(f, " * cs01_structure: ")?;
+            for (key, value) in cs01_structure {
+                let vector_sum = value.iter().sum::<Real>();
+                write!(f, "        {} (sum = ", key)?;
+                write_number_with_commas(f, vector_sum)?;
+                write!(f, "): ")?;
+
+                for v in value {
+                    write_number_with_commas(f, *v)?;
+                    write!(f, " | ")?;
+                }
+                writeln!(f)?;
+            }
+            writeln!(f)?;
+        }
+
         if let Some(div_delta) = self.div_delta.as_ref() {
             writeln!(f, " * div_delta: ")?;
             for (key, value) in div_delta {
@@ -179,6 +491,16 @@ impl std::fmt::Debug for CalculationResult {
             writeln!(f)?;
         }
 
+        if let Some(div_carry) = self.div_carry.as_ref() {
+            writeln!(f, " * div_carry: ")?;
+            for (key, value) in div_carry {
+                write!(f, "        {}: ", key)?;
+                write_number_with_commas(f, *value)?;
+                writeln!(f)?;
+            }
+            writeln!(f)?;
+        }
+
         if let Some(vega_matrix) = self.vega_matrix.as_ref() {
             writeln!(f, " * vega_matrix: ")?;
             for (key, value) in vega_matrix {
@@ -187,7 +509,13 @@ impl std::fmt::Debug for CalculationResult {
                 write_number_with_commas(f, matrix_sum)?;
                 writeln!(f, "): ")?;
 
-                let under_line = "-".repeat((9 + 3) * 17);
+                if let Some(grid) = self.vega_matrix_grid.as_ref().and_then(|g| g.get(key)) {
+                    let tenors: Vec<String> = grid.get_tenors().iter().map(|t| t.to_string()).collect();
+                    writeln!(f, "        tenors: {:?}", tenors)?;
+                    writeln!(f, "        spot moneyness: {:?}", grid.get_spot_moneyness())?;
+                }
+
+                let under_line = "-".repeat((9 + 3) * value.ncols());
                 writeln!(f, "{}", under_line)?;
                 for row in value.rows() {
                     for element in row {
@@ -200,9 +528,32 @@ impl std::fmt::Debug for CalculationResult {
             }
             writeln!(f)?;
         }
+        if let Some(ladder) = self.ladder.as_ref() {
+            writeln!(f, " * ladder: ")?;
+            for (key, value) in ladder {
+                writeln!(
+                    f,
+                    "        {} (spot shifts = {:?}, vol shifts = {:?}): ",
+                    key,
+                    value.get_spot_shifts(),
+                    value.get_vol_shifts()
+                )?;
+                for row in value.get_values().rows() {
+                    write!(f, "        ")?;
+                    for element in row {
+                        write!(f, "{:>12} | ", formatted_number(*element))?;
+                    }
+                    writeln!(f)?;
+                }
+            }
+            writeln!(f)?;
+        }
         if let Some(ref currency) = self.representation_currency {
             writeln!(f, " * representation_currency: {:?}", currency)?;
         }
+        if let Some(ref reporting_result) = self.reporting_result {
+            writeln!(f, " * reporting_result: {:?}", reporting_result)?;
+        }
         writeln!(
             f,
             "==========================================================="
@@ -222,19 +573,40 @@ impl CalculationResult {
             npv_result: None,
             value: None,
             fx_exposure: None,
+            fx_exposure_ladder: None,
+            fx_delta: None,
+            fx_gamma: None,
             delta: None,
             gamma: None,
             vega: None,
             vega_strucure: None,
             vega_matrix: None,
+            vega_matrix_grid: None,
+            ladder: None,
             theta: None,
+            theta_decomposition: None,
+            weekend_theta: None,
             div_delta: None,
             div_structure: None,
+            div_carry: None,
             rho: None,
             rho_structure: None,
+            gamma_structure: None,
+            rho_by_role: None,
+            delta_rho: None,
+            key_rate_dv01: None,
+            cs01: None,
+            cs01_structure: None,
             theta_day: None,
+            carry: None,
+            roll_down: None,
+            exit_value: None,
+            bid_ask_adjustment: None,
+            short_exit_value: None,
+            bond_analytics: None,
             cashflows: None,
             representation_currency: Some(representation_currency),
+            reporting_result: None,
         }
     }
 
@@ -269,6 +641,22 @@ impl CalculationResult {
         self.fx_exposure = Some(fx_exposure);
     }
 
+    pub fn set_fx_exposure_ladder(&mut self, fx_exposure_ladder: FxHashMap<Currency, Vec<Real>>) {
+        self.fx_exposure_ladder = Some(fx_exposure_ladder);
+    }
+
+    pub fn set_single_fx_delta(&mut self, fx_code: FxCode, v: Real) {
+        self.fx_delta
+            .get_or_insert_with(FxHashMap::default)
+            .insert(fx_code, v);
+    }
+
+    pub fn set_single_fx_gamma(&mut self, fx_code: FxCode, v: Real) {
+        self.fx_gamma
+            .get_or_insert_with(FxHashMap::default)
+            .insert(fx_code, v);
+    }
+
     /// insert delta to self.delta as und_code as its key
     /// if the key is already in the map, it will be updated
     pub fn set_single_delta(&mut self, und_id: StaticId, v: Real) {
@@ -336,6 +724,65 @@ impl CalculationResult {
         }
     }
 
+    pub fn set_single_rho_by_role(&mut self, role: CurveRole, v: Real) {
+        self.rho_by_role
+            .get_or_insert_with(FxHashMap::default)
+            .insert(role, v);
+    }
+
+    pub fn set_single_delta_rho(&mut self, und_id: StaticId, curve_id: StaticId, v: Real) {
+        self.delta_rho
+            .get_or_insert_with(FxHashMap::default)
+            .entry(und_id)
+            .or_default()
+            .insert(curve_id, v);
+    }
+
+    pub fn set_single_cs01(&mut self, curve_id: StaticId, cs01: Real) {
+        match &mut self.cs01 {
+            None => {
+                let mut cs01_map = FxHashMap::default();
+                cs01_map.insert(curve_id, cs01);
+                self.cs01 = Some(cs01_map);
+            }
+            Some(cs01_map) => {
+                cs01_map.insert(curve_id, cs01);
+            }
+        }
+    }
+
+    pub fn set_single_cs01_structure(&mut self, curve_id: StaticId, cs01_structure: Vec<Real>) {
+        match &mut self.cs01_structure {
+            None => {
+                let mut cs01_structure_map = FxHashMap::default();
+                cs01_structure_map.insert(curve_id, cs01_structure);
+                self.cs01_structure = Some(cs01_structure_map);
+            }
+            Some(cs01_structure_map) => {
+                cs01_structure_map.insert(curve_id, cs01_structure);
+            }
+        }
+    }
+
+    pub fn set_single_key_rate_dv01(&mut self, curve_id: StaticId, key_rate_dv01: Vec<Real>) {
+        match &mut self.key_rate_dv01 {
+            None => {
+                let mut key_rate_dv01_map = FxHashMap::default();
+                key_rate_dv01_map.insert(curve_id, key_rate_dv01);
+                self.key_rate_dv01 = Some(key_rate_dv01_map);
+            }
+            Some(key_rate_dv01_map) => {
+                key_rate_dv01_map.insert(curve_id, key_rate_dv01);
+            }
+        }
+    }
+
+    pub fn set_single_gamma_structure(&mut self, curve_id: StaticId, gamma_structure: Vec<Real>) {
+        self.gamma_structure
+            .get_or_insert_with(FxHashMap::default)
+            .insert(curve_id, gamma_structure);
+    }
+
     pub fn set_single_rho_structure(&mut self, curve_id: StaticId, rho_structure: Vec<Real>) {
         match &mut self.rho_structure {
             None => {
@@ -362,6 +809,12 @@ impl CalculationResult {
         }
     }
 
+    pub fn set_single_div_carry(&mut self, und_id: StaticId, v: Real) {
+        self.div_carry
+            .get_or_insert_with(FxHashMap::default)
+            .insert(und_id, v);
+    }
+
     pub fn set_single_div_structure(&mut self, und_id: StaticId, div_structure: Vec<Real>) {
         match &mut self.div_structure {
             None => {
@@ -383,7 +836,54 @@ impl CalculationResult {
         self.theta = Some(theta);
     }
 
-    pub fn set_cashflows(&mut self, cashflows: FxHashMap<OffsetDateTime, Real>) {
+    pub fn set_theta_decomposition(&mut self, theta_decomposition: ThetaDecomposition) {
+        self.theta_decomposition = Some(theta_decomposition);
+    }
+
+    pub fn get_theta_decomposition(&self) -> Option<&ThetaDecomposition> {
+        self.theta_decomposition.as_ref()
+    }
+
+    pub fn set_weekend_theta(&mut self, weekend_theta: Real) {
+        self.weekend_theta = Some(weekend_theta);
+    }
+
+    pub fn get_weekend_theta(&self) -> Option<Real> {
+        self.weekend_theta
+    }
+
+    /// calendar days of the theta period
+    pub fn get_theta_day(&self) -> Option<Integer> {
+        self.theta_day
+    }
+
+    pub fn set_carry(&mut self, carry: Real) {
+        self.carry = Some(carry);
+    }
+
+    pub fn set_roll_down(&mut self, roll_down: Real) {
+        self.roll_down = Some(roll_down);
+    }
+
+    pub fn set_exit_value(&mut self, exit_value: Real) {
+        self.exit_value = Some(exit_value);
+    }
+
+    pub fn set_bid_ask_adjustment(&mut self, bid_ask_adjustment: Real) {
+        self.bid_ask_adjustment = Some(bid_ask_adjustment);
+    }
+
+    /// the exit value if the instrument is held short, i.e., the other side of the quotes
+    /// in the conservative marking. This is used in scaled_by_quantity with a negative quantity
+    pub fn set_short_exit_value(&mut self, short_exit_value: Real) {
+        self.short_exit_value = Some(short_exit_value);
+    }
+
+    pub fn set_bond_analytics(&mut self, bond_analytics: BondAnalytics) {
+        self.bond_analytics = Some(bond_analytics);
+    }
+
+    pub fn set_cashflows(&mut self, cashflows: Vec<Cashflow>) {
         self.cashflows = Some(cashflows);
     }
 
@@ -399,6 +899,18 @@ impl CalculationResult {
         self.fx_exposure.as_ref()
     }
 
+    pub fn get_fx_exposure_ladder(&self) -> Option<&FxHashMap<Currency, Vec<Real>>> {
+        self.fx_exposure_ladder.as_ref()
+    }
+
+    pub fn get_fx_delta(&self) -> Option<&FxHashMap<FxCode, Real>> {
+        self.fx_delta.as_ref()
+    }
+
+    pub fn get_fx_gamma(&self) -> Option<&FxHashMap<FxCode, Real>> {
+        self.fx_gamma.as_ref()
+    }
+
     pub fn get_delta(&self) -> Option<&FxHashMap<StaticId, Real>> {
         self.delta.as_ref()
     }
@@ -423,15 +935,63 @@ impl CalculationResult {
         self.theta
     }
 
+    pub fn get_carry(&self) -> Option<Real> {
+        self.carry
+    }
+
+    pub fn get_roll_down(&self) -> Option<Real> {
+        self.roll_down
+    }
+
+    pub fn get_exit_value(&self) -> Option<Real> {
+        self.exit_value
+    }
+
+    pub fn get_bid_ask_adjustment(&self) -> Option<Real> {
+        self.bid_ask_adjustment
+    }
+
+    pub fn get_short_exit_value(&self) -> Option<Real> {
+        self.short_exit_value
+    }
+
+    pub fn get_bond_analytics(&self) -> Option<&BondAnalytics> {
+        self.bond_analytics.as_ref()
+    }
+
     pub fn get_rho(&self) -> Option<&FxHashMap<StaticId, Real>> {
         self.rho.as_ref()
     }
 
+    pub fn get_rho_by_role(&self) -> Option<&FxHashMap<CurveRole, Real>> {
+        self.rho_by_role.as_ref()
+    }
+
+    pub fn get_delta_rho(&self) -> Option<&FxHashMap<StaticId, FxHashMap<StaticId, Real>>> {
+        self.delta_rho.as_ref()
+    }
+
+    pub fn get_cs01(&self) -> Option<&FxHashMap<StaticId, Real>> {
+        self.cs01.as_ref()
+    }
+
+    pub fn get_cs01_structure(&self) -> Option<&FxHashMap<StaticId, Vec<Real>>> {
+        self.cs01_structure.as_ref()
+    }
+
+    pub fn get_key_rate_dv01(&self) -> Option<&FxHashMap<StaticId, Vec<Real>>> {
+        self.key_rate_dv01.as_ref()
+    }
+
     pub fn get_rho_structure(&self) -> Option<&FxHashMap<StaticId, Vec<Real>>> {
         self.rho_structure.as_ref()
     }
 
-    pub fn get_cashflows(&self) -> Option<&FxHashMap<OffsetDateTime, Real>> {
+    pub fn get_gamma_structure(&self) -> Option<&FxHashMap<StaticId, Vec<Real>>> {
+        self.gamma_structure.as_ref()
+    }
+
+    pub fn get_cashflows(&self) -> Option<&Vec<Cashflow>> {
         self.cashflows.as_ref()
     }
 
@@ -439,10 +999,27 @@ impl CalculationResult {
         self.div_delta.as_ref()
     }
 
+    pub fn get_div_carry(&self) -> Option<&FxHashMap<StaticId, Real>> {
+        self.div_carry.as_ref()
+    }
+
     pub fn get_div_structure(&self) -> Option<&FxHashMap<StaticId, Vec<Real>>> {
         self.div_structure.as_ref()
     }
 
+    pub fn get_representation_currency(&self) -> Option<Currency> {
+        self.representation_currency
+    }
+
+    /// the result converted to the reporting currency in the engine
+    pub fn set_reporting_result(&mut self, reporting_result: CalculationResult) {
+        self.reporting_result = Some(Box::new(reporting_result));
+    }
+
+    pub fn get_reporting_result(&self) -> Option<&CalculationResult> {
+        self.reporting_result.as_deref()
+    }
+
     pub fn set_representation_currency(&mut self, currency: Currency) {
         self.representation_currency = Some(currency);
     }
@@ -460,6 +1037,26 @@ impl CalculationResult {
         }
     }
 
+    pub fn set_single_vega_matrix_grid(&mut self, und_id: StaticId, grid: VegaMatrixGrid) {
+        self.vega_matrix_grid
+            .get_or_insert_with(FxHashMap::default)
+            .insert(und_id, grid);
+    }
+
+    pub fn get_vega_matrix_grid(&self) -> Option<&FxHashMap<StaticId, VegaMatrixGrid>> {
+        self.vega_matrix_grid.as_ref()
+    }
+
+    pub fn set_single_ladder(&mut self, und_id: StaticId, ladder: Ladder) {
+        self.ladder
+            .get_or_insert_with(FxHashMap::default)
+            .insert(und_id, ladder);
+    }
+
+    pub fn get_ladder(&self) -> Option<&FxHashMap<StaticId, Ladder>> {
+        self.ladder.as_ref()
+    }
+
     pub fn representation_currency_conversion(
         &self,
         currency: Currency,
@@ -469,37 +1066,94 @@ impl CalculationResult {
             return Ok(self.clone());
         }
 
+        let mut result = self.scaled(fx_rate);
+        result.representation_currency = Some(currency);
+        Ok(result)
+    }
+
+    /// result of holding the quantity of the instrument, where a short position has a negative quantity.
+    /// The expected cashflows are also multiplied by the quantity.
+    /// A short position takes the short exit value if it is set, i.e., in the conservative marking.
+    pub fn scaled_by_quantity(&self, quantity: Real) -> CalculationResult {
+        let mut result = self.scaled(quantity);
+        result.short_exit_value = None;
+        if quantity < 0.0 {
+            if let (Some(short_exit_value), Some(exit_value), Some(bid_ask_adjustment)) = (
+                self.short_exit_value,
+                self.exit_value,
+                self.bid_ask_adjustment,
+            ) {
+                let mid_value = exit_value - bid_ask_adjustment;
+                result.exit_value = Some(short_exit_value * quantity);
+                result.bid_ask_adjustment = Some((short_exit_value - mid_value) * quantity);
+            }
+        }
+        result.reporting_result = self
+            .reporting_result
+            .as_ref()
+            .map(|reporting_result| Box::new(reporting_result.scaled_by_quantity(quantity)));
+        result.cashflows = self.cashflows.as_ref().map(|cashflows| {
+            cashflows
+                .iter()
+                .map(|cashflow| cashflow.scaled(quantity))
+                .collect()
+        });
+        result
+    }
+
+    /// values and greeks multiplied by the ratio in the same representation currency
+    fn scaled(&self, ratio: Real) -> CalculationResult {
         let instrument_info = self.instrument_info.clone();
         let evaluation_date = self.evaluation_date;
         let npv_result = self.npv_result.clone();
-        let value = self.value.map(|x| x * fx_rate);
+        let value = self.value.map(|x| x * ratio);
         let fx_exposure: Option<FxHashMap<Currency, Real>> = match &self.fx_exposure {
             Some(exposure) => {
                 let mut new_exposure = FxHashMap::default();
                 for (c, v) in exposure {
-                    new_exposure.insert(*c, v * fx_rate);
+                    new_exposure.insert(*c, v * ratio);
                 }
                 Some(new_exposure)
             }
             None => None,
         };
-
-        let delta: Option<FxHashMap<StaticId, f32>> = match &self.delta {
+        let fx_exposure_ladder: Option<FxHashMap<Currency, Vec<Real>>> =
+            self.fx_exposure_ladder.as_ref().map(|fx_exposure_ladder| {
+                fx_exposure_ladder
+                    .iter()
+                    .map(|(currency, v)| (*currency, v.iter().map(|x| x * ratio).collect()))
+                    .collect()
+            });
+
+        let fx_delta: Option<FxHashMap<FxCode, Real>> = self.fx_delta.as_ref().map(|fx_delta| {
+            fx_delta
+                .iter()
+                .map(|(fx_code, v)| (*fx_code, v * ratio))
+                .collect()
+        });
+        let fx_gamma: Option<FxHashMap<FxCode, Real>> = self.fx_gamma.as_ref().map(|fx_gamma| {
+            fx_gamma
+                .iter()
+                .map(|(fx_code, v)| (*fx_code, v * ratio))
+                .collect()
+        });
+
+        let delta: Option<FxHashMap<StaticId, Real>> = match &self.delta {
             Some(delta) => {
                 let mut new_delta = FxHashMap::default();
                 for (und_code, v) in delta {
-                    new_delta.insert(*und_code, v * fx_rate);
+                    new_delta.insert(*und_code, v * ratio);
                 }
                 Some(new_delta)
             }
             None => None,
         };
 
-        let gamma: Option<FxHashMap<StaticId, f32>> = match &self.gamma {
+        let gamma: Option<FxHashMap<StaticId, Real>> = match &self.gamma {
             Some(gamma) => {
                 let mut new_gamma = FxHashMap::default();
                 for (und_code, v) in gamma {
-                    new_gamma.insert(*und_code, v * fx_rate);
+                    new_gamma.insert(*und_code, v * ratio);
                 }
                 Some(new_gamma)
             }
@@ -510,7 +1164,7 @@ impl CalculationResult {
             Some(vega) => {
                 let mut new_vega = FxHashMap::default();
                 for (und_code, v) in vega {
-                    new_vega.insert(*und_code, v * fx_rate);
+                    new_vega.insert(*und_code, v * ratio);
                 }
                 Some(new_vega)
             }
@@ -520,7 +1174,7 @@ impl CalculationResult {
             Some(vega_structure) => {
                 let mut new_vega_structure = FxHashMap::default();
                 for (und_code, v) in vega_structure {
-                    let new_v = v.iter().map(|x| x * fx_rate).collect();
+                    let new_v = v.iter().map(|x| x * ratio).collect();
                     new_vega_structure.insert(*und_code, new_v);
                 }
                 Some(new_vega_structure)
@@ -531,7 +1185,7 @@ impl CalculationResult {
             Some(vega_matrix) => {
                 let mut new_vega_matrix = FxHashMap::default();
                 for (und_code, v) in vega_matrix {
-                    let new_v = v.mapv(|x| x * fx_rate);
+                    let new_v = v.mapv(|x| x * ratio);
                     new_vega_matrix.insert(*und_code, new_v);
                 }
                 Some(new_vega_matrix)
@@ -539,12 +1193,21 @@ impl CalculationResult {
             None => None,
         };
 
-        let theta: Option<Real> = self.theta.map(|x| x * fx_rate);
+        let theta: Option<Real> = self.theta.map(|x| x * ratio);
+        let theta_decomposition: Option<ThetaDecomposition> =
+            self.theta_decomposition.as_ref().map(|x| x.scaled(ratio));
+        let weekend_theta: Option<Real> = self.weekend_theta.map(|x| x * ratio);
+        let ladder: Option<FxHashMap<StaticId, Ladder>> = self.ladder.as_ref().map(|ladder| {
+            ladder
+                .iter()
+                .map(|(und_code, l)| (*und_code, l.scaled(ratio)))
+                .collect()
+        });
         let div_delta: Option<FxHashMap<StaticId, Real>> = match &self.div_delta {
             Some(div_delta) => {
                 let mut new_div_delta = FxHashMap::default();
                 for (und_code, v) in div_delta {
-                    new_div_delta.insert(*und_code, v * fx_rate);
+                    new_div_delta.insert(*und_code, v * ratio);
                 }
                 Some(new_div_delta)
             }
@@ -552,20 +1215,26 @@ impl CalculationResult {
         };
         let div_structure: Option<FxHashMap<StaticId, Vec<Real>>> = match &self.div_structure {
             Some(div_structure) => {
-                let mut new_div_structure: FxHashMap<StaticId, Vec<f32>> = FxHashMap::default();
+                let mut new_div_structure: FxHashMap<StaticId, Vec<Real>> = FxHashMap::default();
                 for (und_code, v) in div_structure {
-                    let new_v = v.iter().map(|x| x * fx_rate).collect();
+                    let new_v = v.iter().map(|x| x * ratio).collect();
                     new_div_structure.insert(*und_code, new_v);
                 }
                 Some(new_div_structure)
             }
             None => None,
         };
+        let div_carry: Option<FxHashMap<StaticId, Real>> = self.div_carry.as_ref().map(|div_carry| {
+            div_carry
+                .iter()
+                .map(|(und_code, v)| (*und_code, v * ratio))
+                .collect()
+        });
         let rho: Option<FxHashMap<StaticId, Real>> = match &self.rho {
             Some(rho) => {
                 let mut new_rho = FxHashMap::default();
                 for (curve_code, v) in rho {
-                    new_rho.insert(*curve_code, v * fx_rate);
+                    new_rho.insert(*curve_code, v * ratio);
                 }
                 Some(new_rho)
             }
@@ -575,38 +1244,111 @@ impl CalculationResult {
             Some(rho_structure) => {
                 let mut new_rho_structure = FxHashMap::default();
                 for (curve_code, v) in rho_structure {
-                    let new_v = v.iter().map(|x| x * fx_rate).collect();
+                    let new_v = v.iter().map(|x| x * ratio).collect();
                     new_rho_structure.insert(*curve_code, new_v);
                 }
                 Some(new_rho_structure)
             }
             None => None,
         };
+        let gamma_structure: Option<FxHashMap<StaticId, Vec<Real>>> =
+            self.gamma_structure.as_ref().map(|gamma_structure| {
+                gamma_structure
+                    .iter()
+                    .map(|(curve_code, v)| (*curve_code, v.iter().map(|x| x * ratio).collect()))
+                    .collect()
+            });
+        let rho_by_role: Option<FxHashMap<CurveRole, Real>> =
+            self.rho_by_role.as_ref().map(|rho_by_role| {
+                rho_by_role
+                    .iter()
+                    .map(|(role, v)| (*role, v * ratio))
+                    .collect()
+            });
+        let delta_rho: Option<FxHashMap<StaticId, FxHashMap<StaticId, Real>>> =
+            self.delta_rho.as_ref().map(|delta_rho| {
+                delta_rho
+                    .iter()
+                    .map(|(und_code, curve_map)| {
+                        let curve_map = curve_map
+                            .iter()
+                            .map(|(curve_code, v)| (*curve_code, v * ratio))
+                            .collect();
+                        (*und_code, curve_map)
+                    })
+                    .collect()
+            });
+        let key_rate_dv01: Option<FxHashMap<StaticId, Vec<Real>>> =
+            self.key_rate_dv01.as_ref().map(|key_rate_dv01| {
+                key_rate_dv01
+                    .iter()
+                    .map(|(curve_code, v)| (*curve_code, v.iter().map(|x| x * ratio).collect()))
+                    .collect()
+            });
+        let cs01: Option<FxHashMap<StaticId, Real>> = self.cs01.as_ref().map(|cs01| {
+            cs01.iter()
+                .map(|(curve_code, v)| (*curve_code, v * ratio))
+                .collect()
+        });
+        let cs01_structure: Option<FxHashMap<StaticId, Vec<Real>>> =
+            self.cs01_structure.as_ref().map(|cs01_structure| {
+                cs01_structure
+                    .iter()
+                    .map(|(curve_code, v)| (*curve_code, v.iter().map(|x| x * ratio).collect()))
+                    .collect()
+            });
         let theta_day: Option<Integer> = self.theta_day;
-        let cashflows: Option<FxHashMap<OffsetDateTime, Real>> = self.cashflows.clone();
-        let representation_currency: Option<Currency> = Some(currency);
+        let carry: Option<Real> = self.carry.map(|x| x * ratio);
+        let roll_down: Option<Real> = self.roll_down.map(|x| x * ratio);
+        let exit_value: Option<Real> = self.exit_value.map(|x| x * ratio);
+        let bid_ask_adjustment: Option<Real> = self.bid_ask_adjustment.map(|x| x * ratio);
+        let short_exit_value: Option<Real> = self.short_exit_value.map(|x| x * ratio);
+        // prices and yield measures on a unit notional are not scaled
+        let bond_analytics: Option<BondAnalytics> = self.bond_analytics.clone();
+        let cashflows: Option<Vec<Cashflow>> = self.cashflows.clone();
+        let representation_currency: Option<Currency> = self.representation_currency;
 
-        let result = CalculationResult {
+        CalculationResult {
             instrument_info,
             evaluation_date,
             npv_result,
             value,
             fx_exposure,
+            fx_exposure_ladder,
+            fx_delta,
+            fx_gamma,
             delta,
             gamma,
             vega,
             vega_strucure,
             vega_matrix,
+            vega_matrix_grid: self.vega_matrix_grid.clone(),
+            ladder,
             theta,
+            theta_decomposition,
+            weekend_theta,
             div_delta,
             div_structure,
+            div_carry,
             rho,
             rho_structure,
+            gamma_structure,
+            rho_by_role,
+            delta_rho,
+            key_rate_dv01,
+            cs01,
+            cs01_structure,
             theta_day,
+            carry,
+            roll_down,
+            exit_value,
+            bid_ask_adjustment,
+            short_exit_value,
+            bond_analytics,
             cashflows,
             representation_currency,
-        };
-        Ok(result)
+            reporting_result: None,
+        }
     }
 }
 
@@ -647,8 +1389,8 @@ mod tests {
             inst_type: InstType::Futures,
             currency: Currency::KRW,
             unit_notional: 250_000.0,
-            issue_date: Some(issue_date.clone()),
-            maturity: Some(maturity.clone()),
+            issue_date: Some(issue_date),
+            maturity: Some(maturity),
             accounting_level: AccountingLevel::L1,
         };
 
@@ -676,8 +1418,8 @@ mod tests {
             inst_type: InstType::Futures,
             currency: fut_trait.get_currency(),
             unit_notional: fut_trait.get_unit_notional(),
-            issue_date: Some(issue_date.clone()),
-            maturity: Some(maturity.unwrap().clone()),
+            issue_date: Some(issue_date),
+            maturity: Some(*maturity.unwrap()),
             accounting_level: AccountingLevel::L1,
         };
 
@@ -0,0 +1,180 @@
+use crate::definitions::Real;
+//
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Elapsed times (sec) of an instrument group in EngineGenerator::calculate.
+/// The phases are "parameter build", "pricer init" and the risk measures of Engine::calculate,
+/// e.g., "npv", "delta", "vega", in the order they are calculated.
+/// elapsed is the whole time of the group including the engine build.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GroupStatistics {
+    group_id: usize,
+    instrument_number: usize,
+    elapsed: Real,
+    phase_timings: Vec<(String, Real)>,
+}
+
+impl GroupStatistics {
+    pub fn new(
+        group_id: usize,
+        instrument_number: usize,
+        elapsed: Real,
+        phase_timings: Vec<(String, Real)>,
+    ) -> GroupStatistics {
+        GroupStatistics {
+            group_id,
+            instrument_number,
+            elapsed,
+            phase_timings,
+        }
+    }
+
+    pub fn get_group_id(&self) -> usize {
+        self.group_id
+    }
+
+    pub fn get_instrument_number(&self) -> usize {
+        self.instrument_number
+    }
+
+    pub fn get_elapsed(&self) -> Real {
+        self.elapsed
+    }
+
+    pub fn get_phase_timings(&self) -> &Vec<(String, Real)> {
+        &self.phase_timings
+    }
+
+    pub fn get_phase_elapsed(&self, phase: &str) -> Real {
+        self.phase_timings
+            .iter()
+            .filter(|(name, _)| name == phase)
+            .map(|(_, elapsed)| elapsed)
+            .sum()
+    }
+}
+
+/// Timing of the last calculation of EngineGenerator, collected if the statistics collection is on
+/// (CalculationConfiguration::with_statistics_collection).
+/// The phase times are summed over the groups, so they are cpu times rather than wall times
+/// when the groups are calculated in parallel, while elapsed is the wall time of the calculation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CalculationStatistics {
+    elapsed: Real,
+    groups: Vec<GroupStatistics>,
+}
+
+impl CalculationStatistics {
+    pub fn new(elapsed: Real, mut groups: Vec<GroupStatistics>) -> CalculationStatistics {
+        groups.sort_by_key(|group| group.get_group_id());
+        CalculationStatistics { elapsed, groups }
+    }
+
+    pub fn get_elapsed(&self) -> Real {
+        self.elapsed
+    }
+
+    pub fn get_groups(&self) -> &Vec<GroupStatistics> {
+        &self.groups
+    }
+
+    pub fn get_instrument_number(&self) -> usize {
+        self.groups
+            .iter()
+            .map(|group| group.instrument_number)
+            .sum()
+    }
+
+    /// sum of the elapsed times of the phase over the groups
+    pub fn get_phase_elapsed(&self, phase: &str) -> Real {
+        self.groups
+            .iter()
+            .map(|group| group.get_phase_elapsed(phase))
+            .sum()
+    }
+
+    /// (phase, elapsed) summed over the groups in the order the phases first appear
+    pub fn get_phase_timings(&self) -> Vec<(String, Real)> {
+        let mut res: Vec<(String, Real)> = vec![];
+        for group in self.groups.iter() {
+            for (phase, elapsed) in group.phase_timings.iter() {
+                match res.iter_mut().find(|(name, _)| name == phase) {
+                    Some((_, total)) => *total += elapsed,
+                    None => res.push((phase.clone(), *elapsed)),
+                }
+            }
+        }
+        res
+    }
+
+    pub fn get_slowest_group(&self) -> Option<&GroupStatistics> {
+        self.groups
+            .iter()
+            .max_by(|a, b| a.elapsed.total_cmp(&b.elapsed))
+    }
+}
+
+impl fmt::Display for CalculationStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "calculation of {} instruments in {} groups: {:.6} sec",
+            self.get_instrument_number(),
+            self.groups.len(),
+            self.elapsed
+        )?;
+        let phase_timings = self.get_phase_timings();
+        let total: Real = phase_timings.iter().map(|(_, elapsed)| elapsed).sum();
+        for (phase, elapsed) in phase_timings.iter() {
+            let share = if total > 0.0 {
+                elapsed / total * 100.0
+            } else {
+                0.0
+            };
+            writeln!(f, "  {:<26} {:>12.6} sec {:>6.2} %", phase, elapsed, share)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_timings() {
+        let statistics = CalculationStatistics::new(
+            3.0,
+            vec![
+                GroupStatistics::new(
+                    1,
+                    2,
+                    2.0,
+                    vec![("pricer init".to_string(), 0.5), ("npv".to_string(), 1.0)],
+                ),
+                GroupStatistics::new(
+                    0,
+                    3,
+                    1.0,
+                    vec![("npv".to_string(), 0.25), ("delta".to_string(), 0.5)],
+                ),
+            ],
+        );
+
+        assert_eq!(statistics.get_groups()[0].get_group_id(), 0);
+        assert_eq!(statistics.get_instrument_number(), 5);
+        assert_eq!(statistics.get_phase_elapsed("npv"), 1.25);
+        assert_eq!(statistics.get_phase_elapsed("theta"), 0.0);
+        assert_eq!(
+            statistics.get_phase_timings(),
+            vec![
+                ("npv".to_string(), 1.25),
+                ("delta".to_string(), 0.5),
+                ("pricer init".to_string(), 0.5),
+            ]
+        );
+        assert_eq!(statistics.get_slowest_group().unwrap().get_group_id(), 1);
+        assert!(statistics.to_string().contains("5 instruments in 2 groups"));
+    }
+}
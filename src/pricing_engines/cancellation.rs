@@ -0,0 +1,55 @@
+use anyhow::{anyhow, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A flag shared between a running calculation and its caller for cooperative cancellation.
+/// The engine checks the flag between the risk calculations, so a cancelled engine stops
+/// at the next check with an error rather than immediately.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// all the clones of the token are cancelled
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// an error if the token is cancelled
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(anyhow!(
+                "({}:{}) the calculation is cancelled",
+                file!(),
+                line!()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancellation_token() {
+        let token = CancellationToken::new();
+        let cloned = token.clone();
+        assert!(!cloned.is_cancelled());
+        assert!(cloned.check().is_ok());
+
+        token.cancel();
+        assert!(cloned.is_cancelled());
+        assert!(cloned.check().is_err());
+    }
+}
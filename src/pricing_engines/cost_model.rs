@@ -0,0 +1,250 @@
+use crate::definitions::Real;
+use crate::instrument::{Instrument, InstrumentTrait};
+use crate::utils::platform::current_num_threads;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use std::sync::Arc;
+
+/// Expected compute cost of the instruments for EngineGenerator::distribute_instruments.
+/// The instrument groups of the categories whose cost exceeds the total cost over the group number
+/// are split so that a single heavy group does not dominate the wall time.
+///
+/// The cost of an instrument is
+/// - the measured time (sec) from the previous runs if the instrument has it
+/// - otherwise the average measured time of the instruments of the same type, or of all the instruments,
+///   if any time is measured
+/// - otherwise the weight of the instrument type (default_weight if the type has no weight)
+///
+/// The times are measured per group in EngineGenerator::calculate and shared equally by the instruments of the group.
+/// The model can be serialized to keep the times for the next run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostModel {
+    type_weights: FxHashMap<String, Real>,
+    default_weight: Real,
+    // None: the number of threads in the rayon thread pool (one on wasm32)
+    group_number: Option<usize>,
+    timings: FxHashMap<StaticId, (String, Real)>,
+}
+
+impl Default for CostModel {
+    fn default() -> CostModel {
+        CostModel {
+            type_weights: FxHashMap::default(),
+            default_weight: 1.0,
+            group_number: None,
+            timings: FxHashMap::default(),
+        }
+    }
+}
+
+fn check_weight(weight: Real) -> Result<()> {
+    if !weight.is_finite() || weight <= 0.0 {
+        return Err(anyhow!(
+            "({}:{}) cost weight must be positive, but {} is given",
+            file!(),
+            line!(),
+            weight
+        ));
+    }
+    Ok(())
+}
+
+impl CostModel {
+    pub fn new() -> CostModel {
+        CostModel::default()
+    }
+
+    /// type_name is as InstrumentTrait::get_type_name, e.g., "VanillaOption"
+    pub fn with_type_weight(mut self, type_name: &str, weight: Real) -> Result<CostModel> {
+        check_weight(weight)?;
+        self.type_weights.insert(type_name.to_string(), weight);
+        Ok(self)
+    }
+
+    pub fn with_default_weight(mut self, default_weight: Real) -> Result<CostModel> {
+        check_weight(default_weight)?;
+        self.default_weight = default_weight;
+        Ok(self)
+    }
+
+    /// the number of groups which the total cost is balanced over
+    pub fn with_group_number(mut self, group_number: usize) -> Result<CostModel> {
+        if group_number == 0 {
+            return Err(anyhow!(
+                "({}:{}) group number must be positive",
+                file!(),
+                line!()
+            ));
+        }
+        self.group_number = Some(group_number);
+        Ok(self)
+    }
+
+    pub fn get_group_number(&self) -> usize {
+        self.group_number
+            .unwrap_or_else(current_num_threads)
+            .max(1)
+    }
+
+    /// elapsed time (sec) of the calculation of an instrument
+    pub fn record_timing(&mut self, instrument: &Instrument, elapsed: Real) {
+        if elapsed.is_finite() && elapsed >= 0.0 {
+            self.timings.insert(
+                instrument.get_id(),
+                (instrument.get_type_name().to_string(), elapsed),
+            );
+        }
+    }
+
+    pub fn get_timing(&self, inst_id: &StaticId) -> Option<Real> {
+        self.timings.get(inst_id).map(|(_, elapsed)| *elapsed)
+    }
+
+    pub fn clear_timings(&mut self) {
+        self.timings.clear();
+    }
+
+    pub fn get_cost(&self, instrument: &Instrument) -> Real {
+        if let Some(elapsed) = self.get_timing(&instrument.get_id()) {
+            return elapsed;
+        }
+        if !self.timings.is_empty() {
+            let type_name = instrument.get_type_name();
+            let same_type: Vec<Real> = self
+                .timings
+                .values()
+                .filter(|(name, _)| name == type_name)
+                .map(|(_, elapsed)| *elapsed)
+                .collect();
+            if !same_type.is_empty() {
+                return same_type.iter().sum::<Real>() / same_type.len() as Real;
+            }
+            return self
+                .timings
+                .values()
+                .map(|(_, elapsed)| elapsed)
+                .sum::<Real>()
+                / self.timings.len() as Real;
+        }
+        self.type_weights
+            .get(instrument.get_type_name())
+            .copied()
+            .unwrap_or(self.default_weight)
+    }
+
+    pub fn get_group_cost(&self, instrument_group: &[Arc<Instrument>]) -> Real {
+        instrument_group
+            .iter()
+            .map(|instrument| self.get_cost(instrument))
+            .sum()
+    }
+
+    /// Splits the groups whose cost exceeds the total cost over the group number.
+    /// A group is split into the least number of subgroups below the bound,
+    /// and the instruments are assigned from the most expensive one to the least loaded subgroup.
+    /// The instruments keep their order in the subgroups.
+    pub fn balance(
+        &self,
+        instrument_groups: Vec<Vec<Arc<Instrument>>>,
+    ) -> Vec<Vec<Arc<Instrument>>> {
+        let costs: Vec<Vec<Real>> = instrument_groups
+            .iter()
+            .map(|group| group.iter().map(|inst| self.get_cost(inst)).collect())
+            .collect();
+        let total_cost: Real = costs.iter().flatten().sum();
+        if total_cost <= 0.0 {
+            return instrument_groups;
+        }
+        let bound = total_cost / self.get_group_number() as Real;
+
+        let mut res = Vec::<Vec<Arc<Instrument>>>::new();
+        for (group, group_costs) in instrument_groups.into_iter().zip(costs.iter()) {
+            let group_cost: Real = group_costs.iter().sum();
+            let split_number = ((group_cost / bound).ceil() as usize).clamp(1, group.len().max(1));
+            if split_number == 1 {
+                res.push(group);
+                continue;
+            }
+
+            let mut order: Vec<usize> = (0..group.len()).collect();
+            order.sort_by(|&i, &j| group_costs[j].total_cmp(&group_costs[i]).then(i.cmp(&j)));
+            let mut loads = vec![0.0 as Real; split_number];
+            let mut assigned = vec![Vec::<usize>::new(); split_number];
+            for i in order {
+                let mut lightest = 0;
+                for (k, load) in loads.iter().enumerate() {
+                    if *load < loads[lightest] {
+                        lightest = k;
+                    }
+                }
+                loads[lightest] += group_costs[i];
+                assigned[lightest].push(i);
+            }
+            for mut indices in assigned.into_iter().filter(|indices| !indices.is_empty()) {
+                indices.sort_unstable();
+                res.push(indices.iter().map(|&i| group[i].clone()).collect());
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::instruments::{futures::Futures, inst_info::InstInfo};
+    use crate::{AccountingLevel, InstType};
+    use time::macros::datetime;
+
+    fn futures(name: &str) -> Arc<Instrument> {
+        let futures = Futures::new(
+            InstInfo {
+                id: StaticId::from_str(name, "KRX"),
+                issue_date: Some(datetime!(2024-01-02 00:00:00 +09:00)),
+                maturity: Some(datetime!(2024-09-13 00:00:00 +09:00)),
+                currency: Currency::KRW,
+                inst_type: InstType::Futures,
+                unit_notional: 250_000.0,
+                name: name.to_string(),
+                accounting_level: AccountingLevel::L1,
+            },
+            350.0,
+            None,
+            Currency::KRW,
+            StaticId::from_str("KOSPI2", "KRX"),
+        );
+        Arc::new(Instrument::Futures(futures))
+    }
+
+    #[test]
+    fn test_cost_model() -> Result<()> {
+        let heavy: Vec<Arc<Instrument>> = (0..6).map(|i| futures(&format!("Fut{}", i))).collect();
+        let light = vec![futures("Fut6"), futures("Fut7")];
+        let model = CostModel::new().with_group_number(4)?;
+        assert_eq!(model.get_cost(&heavy[0]), 1.0);
+
+        // the bound is 8 / 4 = 2, so the heavy group is split into 3
+        let groups = model.balance(vec![heavy.clone(), light.clone()]);
+        assert_eq!(groups.len(), 4);
+        for group in groups.iter() {
+            assert_eq!(group.len(), 2);
+        }
+        assert_eq!(groups[0][0].get_id(), heavy[0].get_id());
+        assert_eq!(groups[0][1].get_id(), heavy[3].get_id());
+
+        // the measured time of an instrument and the average for the others
+        let mut model = model.with_type_weight("Futures", 3.0)?;
+        assert_eq!(model.get_cost(&heavy[0]), 3.0);
+        model.record_timing(&heavy[0], 0.5);
+        model.record_timing(&heavy[1], 1.5);
+        assert_eq!(model.get_cost(&heavy[0]), 0.5);
+        assert_eq!(model.get_cost(&heavy[2]), 1.0);
+        assert!(CostModel::new().with_type_weight("Futures", -1.0).is_err());
+        assert!(CostModel::new().with_group_number(0).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use crate::definitions::Real;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use time::OffsetDateTime;
+
+/// Valuation of the instruments over a vector of evaluation dates in one run (Engine::get_date_sweep),
+/// e.g., for theta profiles and aging analysis. Each vector is indexed as the dates.
+/// The values and the cashflows are in the instrument currency multiplied by the unit notional as CalculationResult::value.
+/// An instrument matured on or before a date has zero npv on the date, and its redemption is in the cashflows.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DateSweepResult {
+    base_date: Option<OffsetDateTime>,
+    dates: Vec<OffsetDateTime>,
+    base_values: FxHashMap<StaticId, Real>,
+    npvs: FxHashMap<StaticId, Vec<Real>>,
+    values: FxHashMap<StaticId, Vec<Real>>,
+    // the cashflows paid after the base date up to each date
+    cumulative_cashflows: FxHashMap<StaticId, Vec<Real>>,
+}
+
+impl DateSweepResult {
+    pub fn new(base_date: OffsetDateTime, dates: Vec<OffsetDateTime>) -> Result<DateSweepResult> {
+        if let Some(date) = dates.iter().find(|date| **date < base_date) {
+            return Err(anyhow!(
+                "({}:{}) the sweep date {} is before the evaluation date {}",
+                file!(),
+                line!(),
+                date,
+                base_date
+            ));
+        }
+        Ok(DateSweepResult {
+            base_date: Some(base_date),
+            dates,
+            ..Default::default()
+        })
+    }
+
+    pub fn insert(
+        &mut self,
+        inst_id: StaticId,
+        base_value: Real,
+        npvs: Vec<Real>,
+        values: Vec<Real>,
+        cumulative_cashflows: Vec<Real>,
+    ) -> Result<()> {
+        let n = self.dates.len();
+        if npvs.len() != n || values.len() != n || cumulative_cashflows.len() != n {
+            return Err(anyhow!(
+                "({}:{}) the sweep of {} does not match the {} dates",
+                file!(),
+                line!(),
+                inst_id,
+                n
+            ));
+        }
+        self.base_values.insert(inst_id, base_value);
+        self.npvs.insert(inst_id, npvs);
+        self.values.insert(inst_id, values);
+        self.cumulative_cashflows
+            .insert(inst_id, cumulative_cashflows);
+        Ok(())
+    }
+
+    /// merges the sweep of other instruments on the same dates, e.g., from the engines of other groups
+    pub fn extend(&mut self, other: DateSweepResult) -> Result<()> {
+        if self.base_date != other.base_date || self.dates != other.dates {
+            return Err(anyhow!(
+                "({}:{}) the sweeps on different dates can not be merged",
+                file!(),
+                line!()
+            ));
+        }
+        self.base_values.extend(other.base_values);
+        self.npvs.extend(other.npvs);
+        self.values.extend(other.values);
+        self.cumulative_cashflows.extend(other.cumulative_cashflows);
+        Ok(())
+    }
+
+    pub fn get_base_date(&self) -> Option<OffsetDateTime> {
+        self.base_date
+    }
+
+    pub fn get_dates(&self) -> &Vec<OffsetDateTime> {
+        &self.dates
+    }
+
+    /// sorted by the string of the ids
+    pub fn get_inst_ids(&self) -> Vec<StaticId> {
+        let mut ids: Vec<StaticId> = self.values.keys().copied().collect();
+        ids.sort_by_key(|id| id.to_string());
+        ids
+    }
+
+    pub fn get_base_value(&self, inst_id: &StaticId) -> Option<Real> {
+        self.base_values.get(inst_id).copied()
+    }
+
+    pub fn get_npvs(&self, inst_id: &StaticId) -> Option<&Vec<Real>> {
+        self.npvs.get(inst_id)
+    }
+
+    pub fn get_values(&self, inst_id: &StaticId) -> Option<&Vec<Real>> {
+        self.values.get(inst_id)
+    }
+
+    pub fn get_cumulative_cashflows(&self, inst_id: &StaticId) -> Option<&Vec<Real>> {
+        self.cumulative_cashflows.get(inst_id)
+    }
+
+    /// value + cumulative cashflows - base value on each date,
+    /// i.e., the pnl of the instrument by aging without any market move
+    pub fn get_aging_pnls(&self, inst_id: &StaticId) -> Option<Vec<Real>> {
+        let base_value = self.base_values.get(inst_id)?;
+        let values = self.values.get(inst_id)?;
+        let cashflows = self.cumulative_cashflows.get(inst_id)?;
+        Some(
+            values
+                .iter()
+                .zip(cashflows.iter())
+                .map(|(value, cashflow)| value + cashflow - base_value)
+                .collect(),
+        )
+    }
+
+    /// sum of the aging pnls of all the instruments on each date
+    pub fn get_total_aging_pnls(&self) -> Vec<Real> {
+        let mut total = vec![0.0; self.dates.len()];
+        for inst_id in self.get_inst_ids() {
+            if let Some(pnls) = self.get_aging_pnls(&inst_id) {
+                for (sum, pnl) in total.iter_mut().zip(pnls.iter()) {
+                    *sum += pnl;
+                }
+            }
+        }
+        total
+    }
+}
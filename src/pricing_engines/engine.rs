@@ -1,9 +1,14 @@
 use crate::currency::{Currency, FxCode};
 use crate::definitions::{
-    Real, Time, DELTA_PNL_UNIT, DIV_PNL_UNIT, RHO_PNL_UNIT, THETA_PNL_UNIT, VEGA_PNL_UNIT,
+    Integer, Real, Time, DELTA_PNL_UNIT, DIV_PNL_UNIT, RHO_PNL_UNIT, THETA_PNL_UNIT, VEGA_PNL_UNIT,
+};
+use crate::enums::{
+    FiniteDifferenceScheme, GreekMethod, KeyRateBumpScheme, MarkingSide, RiskFactorClass,
+    ThetaDayMode,
 };
 use crate::evaluation_date::EvaluationDate;
 use crate::instrument::{Instrument, InstrumentTrait, Instruments};
+use crate::math::quadrature::GaussHermite;
 
 use crate::parameters::volatilities::local_volatility_surface::LocalVolatilitySurface;
 use crate::parameters::{
@@ -18,23 +23,29 @@ use crate::data::{
     vector_data::VectorData,
 };
 use crate::pricing_engines::{
-    calculation_configuration::CalculationConfiguration,
-    calculation_result::CalculationResult,
+    calculation_configuration::{CalculationConfiguration, PayoffSmoothing},
+    calculation_result::{CalculationResult, Ladder, ThetaDecomposition},
+    cancellation::CancellationToken,
+    date_sweep::DateSweepResult,
     match_parameter::MatchParameter,
     npv_result::NpvResult,
     pricer::{Pricer, PricerTrait},
     pricer_factory::PricerFactory,
+    progress::{ProgressCallback, ProgressEvent},
+};
+use crate::time::{
+    calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar,
+    jointcalendar::JointCalendar,
 };
-use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
 use crate::util::format_duration;
+use crate::utils::platform::{get_unix_nano, log_info, log_warn};
+use crate::Tenor;
 //
 use anyhow::{anyhow, bail, Context, Result};
-use ndarray::Array2;
-use std::sync::Arc;
-use std::{
-    cell::RefCell,    
-    rc::Rc,
-};
+use ndarray::{Array1, Array2};
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+use std::sync::{Arc, RwLock};
 use rustc_hash::{
     FxHashMap,
     FxHashSet,
@@ -42,6 +53,8 @@ use rustc_hash::{
 use time::{Duration, OffsetDateTime};
 use static_id::static_id::StaticId;
 
+type PastData = Option<Arc<DailyClosePrice>>;
+
 /// Engine typically handles a bunch of instruments and calculate the pricing of the instruments.
 /// Therefore, the result of calculations is a hashmap with the key being the code of the instrument
 /// Engine is a struct that holds the calculation results of the instruments
@@ -49,24 +62,33 @@ pub struct Engine {
     engine_id: usize,
     msg_tag: String,
     //
-    calculation_results: FxHashMap<StaticId, RefCell<CalculationResult>>,
-    calculation_configuration: Rc<CalculationConfiguration>, // this should be cloned
+    calculation_results: FxHashMap<StaticId, RwLock<CalculationResult>>,
+    calculation_configuration: Arc<CalculationConfiguration>, // this should be cloned
     //
-    evaluation_date: Rc<RefCell<EvaluationDate>>,
-    fxs: FxHashMap<FxCode, Rc<RefCell<MarketPrice>>>,
-    equities: FxHashMap<StaticId, Rc<RefCell<MarketPrice>>>,
-    zero_curves: FxHashMap<StaticId, Rc<RefCell<ZeroCurve>>>,
-    dividends: FxHashMap<StaticId, Option<Rc<RefCell<DiscreteRatioDividend>>>>,
-    volatilities: FxHashMap<StaticId, Rc<RefCell<Volatility>>>,
-    quantos: FxHashMap<(StaticId, FxCode), Rc<RefCell<Quanto>>>,
-    past_daily_close_prices: FxHashMap<StaticId, Rc<DailyClosePrice>>,
+    evaluation_date: Arc<RwLock<EvaluationDate>>,
+    fxs: FxHashMap<FxCode, Arc<RwLock<MarketPrice>>>,
+    equities: FxHashMap<StaticId, Arc<RwLock<MarketPrice>>>,
+    zero_curves: FxHashMap<StaticId, Arc<RwLock<ZeroCurve>>>,
+    dividends: FxHashMap<StaticId, Option<Arc<RwLock<DiscreteRatioDividend>>>>,
+    volatilities: FxHashMap<StaticId, Arc<RwLock<Volatility>>>,
+    quantos: FxHashMap<(StaticId, FxCode), Arc<RwLock<Quanto>>>,
+    past_daily_close_prices: FxHashMap<StaticId, Arc<DailyClosePrice>>,
+    // instrument currency -> rate to the reporting currency in the configuration
+    reporting_fx_rates: FxHashMap<Currency, Real>,
     // instruments
     instruments: Instruments,         // all instruments
     pricers: FxHashMap<StaticId, Pricer>, // pricers for each instrument
     // selected instuments for calculation,
     // e.g., if we calcualte a delta of a single stock, we do not need calculate all instruments
-    instruments_in_action: Vec<Rc<Instrument>>,
-    match_parameter: Rc<MatchParameter>, // this must be cloned
+    instruments_in_action: Vec<Arc<Instrument>>,
+    // instruments whose delta, vega, and rho are from the automatic differentiation
+    ad_instrument_ids: FxHashSet<StaticId>,
+    match_parameter: Arc<MatchParameter>, // this must be cloned
+    // checked between the risk calculations and in their loops over the risk factors
+    cancellation_token: Option<CancellationToken>,
+    progress_callback: Option<ProgressCallback>,
+    // (phase, elapsed seconds) recorded if the statistics collection is on in the configuration
+    phase_timings: Vec<(&'static str, Real)>,
 }
 
 impl Engine {
@@ -80,8 +102,8 @@ impl Engine {
             engine_id,
             msg_tag: "".to_string(),
             calculation_results: FxHashMap::default(),
-            calculation_configuration: Rc::new(calculation_configuration),
-            evaluation_date: Rc::new(RefCell::new(EvaluationDate::new(evaluation_offsetdatetime))),
+            calculation_configuration: Arc::new(calculation_configuration),
+            evaluation_date: Arc::new(RwLock::new(EvaluationDate::new(evaluation_offsetdatetime))),
             fxs: FxHashMap::default(),
             equities: FxHashMap::default(),
             zero_curves: FxHashMap::default(),
@@ -89,10 +111,53 @@ impl Engine {
             volatilities: FxHashMap::default(),
             quantos: FxHashMap::default(),
             past_daily_close_prices: FxHashMap::default(),
+            reporting_fx_rates: FxHashMap::default(),
             instruments: Instruments::default(),
             instruments_in_action: vec![],
+            ad_instrument_ids: FxHashSet::default(),
             pricers: FxHashMap::default(),
-            match_parameter: Rc::new(match_parameter),
+            match_parameter: Arc::new(match_parameter),
+            cancellation_token: None,
+            progress_callback: None,
+            phase_timings: vec![],
+        }
+    }
+
+    /// The calculation stops with an error at the next check after the token is cancelled.
+    /// The token is checked before each risk measure in Engine::calculate
+    /// and for each bumped risk factor in the risk measures.
+    pub fn with_cancellation_token(mut self, cancellation_token: CancellationToken) -> Engine {
+        self.cancellation_token = Some(cancellation_token);
+        self
+    }
+
+    /// ProgressEvent::StageFinished is reported to the callback after each risk measure in Engine::calculate
+    pub fn with_progress_callback(mut self, progress_callback: ProgressCallback) -> Engine {
+        self.progress_callback = Some(progress_callback);
+        self
+    }
+
+    fn record_phase(&mut self, phase: &'static str, timer: u64) {
+        if self.calculation_configuration.get_statistics_collection() {
+            let elapsed = (get_unix_nano() - timer) as Real / 1_000_000_000.0;
+            self.phase_timings.push((phase, elapsed));
+        }
+    }
+
+    fn report_stage(&mut self, stage: &'static str, timer: u64) {
+        self.record_phase(stage, timer);
+        if let Some(callback) = &self.progress_callback {
+            callback(&ProgressEvent::StageFinished {
+                group_id: self.engine_id,
+                stage,
+            });
+        }
+    }
+
+    fn check_cancellation(&self) -> Result<()> {
+        match &self.cancellation_token {
+            Some(token) => token.check().with_context(|| format!("engine-{}", self.engine_id)),
+            None => Ok(()),
         }
     }
 
@@ -109,31 +174,38 @@ impl Engine {
         quanto_correlation_data: Arc<FxHashMap<(StaticId, FxCode), ValueData>>,
         past_daily_value_data: Arc<FxHashMap<StaticId, DailyValueData>>,
     ) -> Result<Engine> {
+        let timer = get_unix_nano();
         let fx_codes = self.instruments.get_all_fxcodes_for_pricing();
-        let mut fxs: FxHashMap<FxCode, Rc<RefCell<MarketPrice>>> = FxHashMap::default();
+        let mut fxs: FxHashMap<FxCode, Arc<RwLock<MarketPrice>>> = FxHashMap::default();
         for fx_code in fx_codes {
             if fx_data.contains_key(&fx_code) {
                 let data = fx_data.get(&fx_code).unwrap();
-                let rc = Rc::new(RefCell::new(MarketPrice::new(
+                let mut fx = MarketPrice::new(
                     data.get_value(),
-                    self.evaluation_date.borrow().get_date_clone(),
+                    self.evaluation_date.read().unwrap().get_date_clone(),
                     None,
                     fx_code.get_currency2(),
                     fx_code.to_string(),
                     fx_code.to_static_id(),
-                )));
-                fxs.insert(fx_code, rc);
+                );
+                if let (Some(bid), Some(ask)) = (data.get_bid(), data.get_ask()) {
+                    fx = fx.with_bid_ask(bid, ask)?;
+                }
+                fxs.insert(fx_code, Arc::new(RwLock::new(fx)));
             } else if fx_data.contains_key(&fx_code.reciprocal()) {
                 let data = fx_data.get(&fx_code.reciprocal()).unwrap();
-                let rc = Rc::new(RefCell::new(MarketPrice::new(
+                let mut fx = MarketPrice::new(
                     1.0 / data.get_value(),
-                    self.evaluation_date.borrow().get_date_clone(),
+                    self.evaluation_date.read().unwrap().get_date_clone(),
                     None,
                     fx_code.get_currency2(),
                     fx_code.to_string(),
                     fx_code.to_static_id(),
-                )));
-                fxs.insert(fx_code, rc);
+                );
+                if let (Some(bid), Some(ask)) = (data.get_bid(), data.get_ask()) {
+                    fx = fx.with_bid_ask(1.0 / ask, 1.0 / bid)?;
+                }
+                fxs.insert(fx_code, Arc::new(RwLock::new(fx)));
             } else if fx_data.contains_key(&FxCode::new(fx_code.get_currency1(), Currency::KRW))
                 && fx_data.contains_key(&FxCode::new(fx_code.get_currency2(), Currency::KRW))
             {
@@ -144,20 +216,25 @@ impl Engine {
                     .get(&FxCode::new(fx_code.get_currency2(), Currency::KRW))
                     .unwrap();
 
-                let rc = Rc::new(RefCell::new(MarketPrice::new(
+                let mut fx = MarketPrice::new(
                     data1.get_value() / data2.get_value(),
-                    self.evaluation_date.borrow().get_date_clone(),
+                    self.evaluation_date.read().unwrap().get_date_clone(),
                     None,
                     fx_code.get_currency2(),
                     fx_code.to_string(),
                     fx_code.to_static_id(),
-                )));
-                fxs.insert(fx_code, rc);
+                );
+                if let (Some(bid1), Some(ask1), Some(bid2), Some(ask2)) =
+                    (data1.get_bid(), data1.get_ask(), data2.get_bid(), data2.get_ask())
+                {
+                    fx = fx.with_bid_ask(bid1 / ask2, ask1 / bid2)?;
+                }
+                fxs.insert(fx_code, Arc::new(RwLock::new(fx)));
             } else {
                 bail!(
                     "({}:{}) failed to get fx data for {}.\n\
                      fx_data must have either of itself, reciprocal,\n\
-                    or both Curr1/KRW and Currr2/KRW",
+                    or both Curr1/KRW and Curr2/KRW",
                     file!(),
                     line!(),
                     fx_code
@@ -167,9 +244,15 @@ impl Engine {
         // curve data
         let mut zero_curves = FxHashMap::default();
         let all_curve_ids = self.instruments.get_all_curve_ids(&self.match_parameter)?;
+        let mut spread_curve_ids = vec![];
         for curve_id in all_curve_ids {
+            // spread curves are built after their base curves
+            if self.match_parameter.get_base_curve_id(&curve_id).is_some() {
+                spread_curve_ids.push(curve_id);
+                continue;
+            }
             if let Some(data) = curve_data.get(&curve_id) {
-                let zero_curve = Rc::new(RefCell::new(ZeroCurve::new(
+                let zero_curve = Arc::new(RwLock::new(ZeroCurve::new(
                     self.evaluation_date.clone(),
                     data,
                     data.name.clone(),
@@ -177,7 +260,7 @@ impl Engine {
                 )?));
                 zero_curves.insert(curve_id, zero_curve.clone());
             } else {
-                //let dummy_curve = Rc::new(RefCell::new(ZeroCurve::new_dummy(self.evaluation_date.clone())));
+                //let dummy_curve = Arc::new(RwLock::new(ZeroCurve::new_dummy(self.evaluation_date.clone())));
                 //zero_curves.insert(curve_id, dummy_curve.clone());
                 bail!(
                     "({}:{}) failed to get curve data for {}",
@@ -187,6 +270,34 @@ impl Engine {
                 );
             }
         }
+
+        for curve_id in spread_curve_ids {
+            let base_id = self.match_parameter.get_base_curve_id(&curve_id).unwrap();
+            let base_curve = zero_curves.get(&base_id).cloned().with_context(|| {
+                anyhow!(
+                    "({}:{}) base curve {} of spread curve {} is not built",
+                    file!(),
+                    line!(),
+                    base_id,
+                    curve_id
+                )
+            })?;
+            let data = curve_data.get(&curve_id).with_context(|| {
+                anyhow!(
+                    "({}:{}) failed to get spread curve data for {}",
+                    file!(),
+                    line!(),
+                    curve_id
+                )
+            })?;
+            let spread_curve = Arc::new(RwLock::new(ZeroCurve::new_spread_curve(
+                base_curve,
+                data,
+                data.name.clone(),
+                curve_id,
+            )?));
+            zero_curves.insert(curve_id, spread_curve);
+        }
         
         // dividend data
         let mut no_dividend_data_msg = format!(
@@ -210,7 +321,7 @@ impl Engine {
                         )
                     })?
                     .get_value();
-                let dividend = Some(Rc::new(RefCell::new(
+                let dividend = Some(Arc::new(RwLock::new(
                     DiscreteRatioDividend::new(
                         self.evaluation_date.clone(),
                         data,
@@ -241,7 +352,7 @@ impl Engine {
                     .collect::<Vec<&str>>()
                     .join(" | ")
             ));
-            flashlog::flash_warn!("NoData"; no_dividend = no_dividend_data_msg);
+            log_warn!("NoData"; no_dividend = no_dividend_data_msg);
         }
         //
         // borrowing curve parameter
@@ -249,7 +360,7 @@ impl Engine {
         for und_code in all_underlying_ids {
             if let Some(borrowing_curve_id) = self.match_parameter.get_borrowing_curve_map().get(&und_code) {
                 if let Some(data) = curve_data.get(borrowing_curve_id) {
-                    let zero_curve = Rc::new(RefCell::new(ZeroCurve::new(
+                    let zero_curve = Arc::new(RwLock::new(ZeroCurve::new(
                         self.evaluation_date.clone(),
                         data,
                         data.name.clone(),
@@ -278,15 +389,18 @@ impl Engine {
                     Some(div) => div.clone(),
                     None => None,
                 };
-                let rc = Rc::new(RefCell::new(MarketPrice::new(
+                let mut equity = MarketPrice::new(
                     data.get_value(),
-                    self.evaluation_date.borrow().get_date_clone(),
+                    self.evaluation_date.read().unwrap().get_date_clone(),
                     div,
                     data.get_currency(),
                     data.get_name().clone(),
                     underlying_id,
-                )));
-                equities.insert(underlying_id, rc);
+                );
+                if let (Some(bid), Some(ask)) = (data.get_bid(), data.get_ask()) {
+                    equity = equity.with_bid_ask(bid, ask)?;
+                }
+                equities.insert(underlying_id, Arc::new(RwLock::new(equity)));
             } else {
                 bail!(
                     "({}:{}) failed to get stock data for {}",
@@ -304,11 +418,8 @@ impl Engine {
             //if equity_constant_volatility_data.contains_key(&und_code) {
             if let Some(data) = equity_constant_volatility_data.get(&und_code) {
                 //let data = equity_constant_volatility_data.get(&und_code).unwrap();
-                let vega_matrix_spot_moneyness = self
-                    .calculation_configuration
-                    .get_vega_matrix_spot_moneyness();
-                let vega_structure_tenors =
-                    self.calculation_configuration.get_vega_structure_tenors();
+                let (vega_structure_tenors, vega_matrix_spot_moneyness) =
+                    self.get_volatility_grid(&und_code);
                 let market_price = equities
                     .get(&und_code)
                     .with_context(|| {
@@ -359,20 +470,28 @@ impl Engine {
                     data.name.clone(),
                     und_code,
                 )
+                .with_time_interpolation(
+                    self.calculation_configuration
+                        .get_volatility_time_interpolation(),
+                )
                 .with_constant_volatility(
                     data,
-                    vega_structure_tenors.clone(),
-                    vega_matrix_spot_moneyness.clone(),
+                    vega_structure_tenors,
+                    vega_matrix_spot_moneyness,
                 )?;
+                if let Some(business_time) = self
+                    .calculation_configuration
+                    .get_volatility_business_time(&und_code)
+                {
+                    lv = lv.with_business_time(business_time.clone());
+                }
                 lv.build()?;
-                let rc = Rc::new(RefCell::new(Volatility::LocalVolatilitySurface(lv)));
+                let rc = Arc::new(RwLock::new(Volatility::LocalVolatilitySurface(lv)));
                 volatilities.insert(und_code, rc);
             } else if equity_volatility_surface_data.contains_key(&und_code) {
                 let data = equity_volatility_surface_data.get(&und_code).unwrap();
-                let vega_matrix_spot_moneyness = self
-                    .calculation_configuration
-                    .get_vega_matrix_spot_moneyness();
-                let vega_structure_tenors = self.calculation_configuration.get_vega_structure_tenors();
+                let (vega_structure_tenors, vega_matrix_spot_moneyness) =
+                    self.get_volatility_grid(&und_code);
                 let market_price = equities
                     .get(&und_code)
                     .with_context(|| {
@@ -416,13 +535,23 @@ impl Engine {
                     data.name.clone(),
                     und_code,
                 )
+                .with_time_interpolation(
+                    self.calculation_configuration
+                        .get_volatility_time_interpolation(),
+                )
                 .with_market_surface(
                     data,
-                    vega_structure_tenors.clone(),
-                    vega_matrix_spot_moneyness.clone(),
+                    vega_structure_tenors,
+                    vega_matrix_spot_moneyness,
                 )?;
+                if let Some(business_time) = self
+                    .calculation_configuration
+                    .get_volatility_business_time(&und_code)
+                {
+                    lv = lv.with_business_time(business_time.clone());
+                }
                 lv.build()?;
-                let rc = Rc::new(RefCell::new(Volatility::LocalVolatilitySurface(lv)));
+                let rc = Arc::new(RwLock::new(Volatility::LocalVolatilitySurface(lv)));
                 volatilities.insert(und_code, rc);
             } else {
                 bail!(
@@ -445,7 +574,7 @@ impl Engine {
         for fx_code in unique_fxcodes {
             if fx_constant_volatility_data.contains_key(&fx_code) {
                 let data = fx_constant_volatility_data.get(&fx_code).unwrap();
-                let rc = Rc::new(RefCell::new(Volatility::ConstantVolatility(
+                let rc = Arc::new(RwLock::new(Volatility::ConstantVolatility(
                     ConstantVolatility::new(
                         data.get_value(),
                         fx_code.to_string(),
@@ -468,7 +597,7 @@ impl Engine {
         for (und_code, fxcode) in quanto_fx_und_pair {
             //if quanto_correlation_data.contains_key(&(und_code, *fxcode)) {
             if let Some(data) = quanto_correlation_data.get(&(und_code, fxcode)) {
-                let rc = Rc::new(RefCell::new(
+                let rc = Arc::new(RwLock::new(
                     Quanto::new(
                         fx_volatilities.get(&fxcode)
                             .with_context(|| anyhow!(
@@ -500,7 +629,7 @@ impl Engine {
                     key
                 )
             })?;
-            let rc = Rc::new(daily_close);
+            let rc = Arc::new(daily_close);
             past_daily_close_prices.insert(*key, rc);
         }
 
@@ -511,36 +640,79 @@ impl Engine {
         self.volatilities = volatilities;
         self.quantos = quantos;
         self.past_daily_close_prices = past_daily_close_prices;
+        self.check_instrument_expiries()?;
+
+        if let Some(reporting_currency) = self.calculation_configuration.get_reporting_currency() {
+            for inst in self.instruments.iter() {
+                let currency = inst.get_currency();
+                if self.reporting_fx_rates.contains_key(&currency) {
+                    continue;
+                }
+                let rate = get_fx_rate_from_data(&fx_data, FxCode::new(currency, reporting_currency))?;
+                self.reporting_fx_rates.insert(currency, rate);
+            }
+        }
 
         // add marketprice_observers
         for (_, fx) in self.fxs.iter() {
             self.evaluation_date
-                .borrow_mut()
+                .write().unwrap()
                 .add_marketprice_observer(fx.clone());
         }
         // add marketprice_observers
         for (_, equity) in self.equities.iter() {
             self.evaluation_date
-                .borrow_mut()
+                .write().unwrap()
                 .add_marketprice_observer(equity.clone());
         }
 
         for (_, dividend) in self.dividends.iter() {
             if let Some(div) = dividend {
                 self.evaluation_date
-                    .borrow_mut()
+                    .write().unwrap()
                     .add_dividend_observer(div.clone());
             }
         }
 
         let id = self.engine_id;
 
-        flashlog::flash_info!("Launch";"Engine {} is initialized with parameter data", id);
+        log_info!("Launch";"Engine {} is initialized with parameter data", id);
+        self.record_phase("parameter build", timer);
+        Ok(self)
+    }
 
+    /// dividend payment dates of the underlyings, which are matched in order to the ex-dividend dates of the dividend data.
+    /// This must be called after with_parameter_data.
+    /// Without payment dates, the dividends are paid on the ex-dividend dates.
+    pub fn with_dividend_payment_dates(
+        self,
+        dividend_payment_date_data: Arc<FxHashMap<StaticId, Vec<OffsetDateTime>>>,
+    ) -> Result<Engine> {
+        for (id, payment_dates) in dividend_payment_date_data.iter() {
+            if let Some(Some(dividend)) = self.dividends.get(id) {
+                dividend
+                    .write().unwrap()
+                    .set_payment_dates(payment_dates.clone())
+                    .with_context(|| {
+                        anyhow!(
+                            "({}:{}) failed to set dividend payment dates for {}",
+                            file!(),
+                            line!(),
+                            id
+                        )
+                    })?;
+            }
+        }
         Ok(self)
     }
     // initialize CalculationResult for each instrument
-    pub fn with_instruments(mut self, instrument_vec: Vec<Instrument>) -> Result<Engine> {
+    pub fn with_instruments(self, instrument_vec: Vec<Instrument>) -> Result<Engine> {
+        self.with_shared_instruments(instrument_vec.into_iter().map(Arc::new).collect())
+    }
+
+    /// same as with_instruments but the instruments are shared with the caller, e.g., EngineGenerator,
+    /// so that the engines in other threads do not clone them
+    pub fn with_shared_instruments(mut self, instrument_vec: Vec<Arc<Instrument>>) -> Result<Engine> {
         if instrument_vec.is_empty() {
             return Err(anyhow!(
                 "({}:{}) no instruments are given to initialize",
@@ -548,8 +720,19 @@ impl Engine {
                 line!()
             ));
         }
-        let vec_rc_inst: Vec<Rc<Instrument>> = instrument_vec.into_iter().map(Rc::new).collect();
-        self.instruments = Instruments::new(vec_rc_inst);
+        // the schedules generated before the latest holiday updates are regenerated on the copies
+        let instrument_vec = instrument_vec
+            .into_iter()
+            .map(|inst| {
+                if !inst.has_outdated_schedules() {
+                    return Ok(inst);
+                }
+                let mut inst = inst.as_ref().clone();
+                inst.refresh_schedules()?;
+                Ok(Arc::new(inst))
+            })
+            .collect::<Result<Vec<Arc<Instrument>>>>()?;
+        self.instruments = Instruments::new(instrument_vec);
         let all_types = self.instruments.get_all_type_names();
         let curr_str: Vec<&str> = self
             .instruments
@@ -572,73 +755,23 @@ impl Engine {
             all_und_codes,
         );
 
-        let dt = self.evaluation_date.borrow().get_date_clone();
-        let insts_over_maturity = self
-            .instruments
-            .instruments_with_maturity_upto(None, &dt, None);
-
-        if !insts_over_maturity.is_empty() {
-            let mut inst_codes = Vec::<StaticId>::new();
-            let mut inst_mat = Vec::<Option<OffsetDateTime>>::new();
-            for inst in insts_over_maturity {
-                inst_codes.push(inst.get_id());
-                let mat = inst.get_maturity();
-                match mat {
-                    Some(m) => inst_mat.push(Some(*m)),
-                    None => inst_mat.push(None),
-                }
-            }      
-            bail!(
-                "(Engine::initialize_instruments) There are instruments with maturity within the evaluation date\n\
-                evaluation date: {:?}\n\
-                {}\n",
-                dt, inst_codes.into_iter().map(|s| s.code_str().to_string()).collect::<Vec<String>>().join(" | "),
-            );
-        }
-
-        let insts_with_very_short_maturity =
-            self.instruments
-                .instruments_with_maturity_upto(None, &(dt + Duration::hours(6)), None);
-
-        if !insts_with_very_short_maturity.is_empty() {
-            let mut inst_codes = Vec::<StaticId>::new();
-            let mut inst_mat = Vec::<Option<OffsetDateTime>>::new();
-            for inst in insts_with_very_short_maturity {
-                inst_codes.push(inst.get_id());
-                let mat = inst.get_maturity();
-                match mat {
-                    Some(m) => inst_mat.push(Some(*m)),
-                    None => inst_mat.push(None),
-                }
-            }
-
-            println!(
-                "\n(Engine::initialize_instruments) There are instruments with a very short maturity (within 6 hours) \n\
-                Note that these products may produce numerical errors.
-                <LIST>\n{}\n{}\n",
-                inst_codes.into_iter().map(|s| s.code_str().to_string()).collect::<Vec<String>>().join(" | "),
-                self.msg_tag
-            );
-        }
-
         self.instruments_in_action = self.instruments.get_instruments_clone();
 
         for inst in self.instruments.iter() {
             let inst_info = inst.get_inst_info();
             let init_res = CalculationResult::new(
                 inst_info.clone(),
-                self.evaluation_date.borrow().get_date_clone(),
+                self.evaluation_date.read().unwrap().get_date_clone(),
             );
 
             self.calculation_results
-                .insert(inst.get_id(), RefCell::new(init_res));
+                .insert(inst.get_id(), RwLock::new(init_res));
         }
         Ok(self)
     }
 
-    pub fn initialize_pricers(&mut self) -> Result<()> {
-        let inst_vec = self.instruments.get_instruments_clone();
-        let pricer_factory = PricerFactory::new(
+    fn get_pricer_factory(&self) -> PricerFactory {
+        PricerFactory::new(
             self.evaluation_date.clone(),
             self.fxs.clone(),
             self.equities.clone(),
@@ -646,9 +779,15 @@ impl Engine {
             self.volatilities.clone(),
             self.quantos.clone(),
             self.past_daily_close_prices.clone(),
-            Rc::clone(&self.match_parameter),
-            Rc::clone(&self.calculation_configuration),
-        );
+            Arc::clone(&self.match_parameter),
+            Arc::clone(&self.calculation_configuration),
+        )
+    }
+
+    pub fn initialize_pricers(&mut self) -> Result<()> {
+        let timer = get_unix_nano();
+        let inst_vec = self.instruments.get_instruments_clone();
+        let pricer_factory = self.get_pricer_factory();
 
         for inst in inst_vec.iter() {
             let pricer = pricer_factory.create_pricer(inst).with_context(|| {
@@ -663,6 +802,7 @@ impl Engine {
             })?;
             self.pricers.insert(inst.get_id(), pricer);
         }
+        self.record_phase("pricer init", timer);
         Ok(())
     }
 
@@ -671,40 +811,176 @@ impl Engine {
         self.instruments_in_action = self.instruments.get_instruments_clone();
     }
 
-    pub fn get_npvs(&self) -> Result<FxHashMap<StaticId, Real>> {
-        let mut npvs = FxHashMap::default();
-        for inst in &self.instruments_in_action {
-            let inst_code = inst.get_id();
-            let pricer = self.pricers.get(&inst_code).with_context(|| {
-                anyhow!(
-                    "({}:{}) <Egnine::get_npvs> failed to get pricer for {}\n{}",
-                    file!(),
-                    line!(),
-                    inst_code,
-                    self.msg_tag
-                )
-            })?;
+    /// currency and level of a risk factor for the bump size in the calculation configuration
+    fn get_risk_factor_level(
+        &self,
+        risk_factor_class: RiskFactorClass,
+        id: &StaticId,
+    ) -> Result<(Option<Currency>, Real)> {
+        let equity_currency = self.equities.get(id).map(|eq| *eq.read().unwrap().get_currency());
+        let res = match risk_factor_class {
+            RiskFactorClass::Spot => {
+                let equity = self.equities.get(id).ok_or_else(|| {
+                    anyhow!("({}:{}) there is no equity {}", file!(), line!(), id)
+                })?;
+                let level = equity.read().unwrap().get_value();
+                (equity_currency, level)
+            }
+            RiskFactorClass::Volatility => {
+                let volatility = self.volatilities.get(id).ok_or_else(|| {
+                    anyhow!("({}:{}) volatility {} is not set", file!(), line!(), id)
+                })?;
+                let level = volatility.read().unwrap().get_value(1.0, 1.0);
+                (equity_currency, level)
+            }
+            RiskFactorClass::Rate => {
+                let curve = self.zero_curves.get(id).ok_or_else(|| {
+                    anyhow!("({}:{}) no zero curve: {}", file!(), line!(), id)
+                })?;
+                let curve = curve.read().unwrap();
+                let level = curve.get_interpolated_rates().mean().unwrap_or(0.0);
+                (Some(curve.get_currency()), level)
+            }
+            RiskFactorClass::Dividend => {
+                let level = match self.dividends.get(id) {
+                    Some(Some(dividend)) => {
+                        let amounts: Vec<Real> = dividend
+                            .read().unwrap()
+                            .get_dividend_payments()
+                            .iter()
+                            .map(|(_, amount)| *amount)
+                            .collect();
+                        match amounts.is_empty() {
+                            true => 0.0,
+                            false => amounts.iter().sum::<Real>() / amounts.len() as Real,
+                        }
+                    }
+                    _ => 0.0,
+                };
+                (equity_currency, level)
+            }
+            RiskFactorClass::Fx => {
+                let (fx_code, fx) = self
+                    .fxs
+                    .iter()
+                    .find(|(fx_code, _)| fx_code.to_static_id() == *id)
+                    .ok_or_else(|| anyhow!("({}:{}) there is no fx {}", file!(), line!(), id))?;
+                (Some(fx_code.get_currency1()), fx.read().unwrap().get_value())
+            }
+        };
+        Ok(res)
+    }
 
-            let npv = pricer.npv(inst).with_context(|| {
-                anyhow!(
-                    "({}:{}) <Egnine::get_npvs> failed to get npv for {}\n{}\n\
-                    inst: {:?}",
-                    file!(),
-                    line!(),
-                    inst_code,
-                    self.msg_tag,
-                    &inst
-                )
-            })?;
+    /// bump added to the risk factor
+    fn get_absolute_bump(&self, risk_factor_class: RiskFactorClass, id: &StaticId) -> Result<Real> {
+        let (currency, level) = self.get_risk_factor_level(risk_factor_class, id)?;
+        self.calculation_configuration
+            .get_bump_size(risk_factor_class, currency)
+            .get_absolute_bump(level)
+            .with_context(|| anyhow!("({}:{}) failed to get the bump of {}", file!(), line!(), id))
+    }
+
+    /// bump ratio to the level of the risk factor
+    fn get_relative_bump(&self, risk_factor_class: RiskFactorClass, id: &StaticId) -> Result<Real> {
+        let (currency, level) = self.get_risk_factor_level(risk_factor_class, id)?;
+        self.calculation_configuration
+            .get_bump_size(risk_factor_class, currency)
+            .get_relative_bump(level)
+            .with_context(|| anyhow!("({}:{}) failed to get the bump of {}", file!(), line!(), id))
+    }
+
+    /// The bump of an underlying without dividends does not move any price,
+    /// so the bump value is used as it is.
+    fn get_dividend_bump(&self, und_id: &StaticId) -> Result<Real> {
+        match self.dividends.get(und_id) {
+            Some(Some(dividend)) if !dividend.read().unwrap().get_dividend_payments().is_empty() => {
+                self.get_absolute_bump(RiskFactorClass::Dividend, und_id)
+            }
+            _ => {
+                let currency = self.equities.get(und_id).map(|eq| *eq.read().unwrap().get_currency());
+                Ok(self
+                    .calculation_configuration
+                    .get_bump_size(RiskFactorClass::Dividend, currency)
+                    .get_value())
+            }
+        }
+    }
+
+    /// npvs of the instruments in action on the up and down bumps that the scheme requires.
+    /// bump(1.0) applies the bump and bump(-1.0) puts it back.
+    fn get_bumped_npvs<F>(
+        &self,
+        scheme: FiniteDifferenceScheme,
+        bump: F,
+    ) -> Result<(FxHashMap<StaticId, Real>, FxHashMap<StaticId, Real>)>
+    where
+        F: Fn(Real) -> Result<()>,
+    {
+        let mut npvs_up = FxHashMap::default();
+        let mut npvs_down = FxHashMap::default();
+        if scheme != FiniteDifferenceScheme::Backward {
+            bump(1.0)?;
+            let npvs = self.get_npvs();
+            bump(-1.0)?;
+            npvs_up = npvs.context("failed to get npvs")?;
+        }
+        if scheme != FiniteDifferenceScheme::Forward {
+            bump(-1.0)?;
+            let npvs = self.get_npvs();
+            bump(1.0)?;
+            npvs_down = npvs.context("failed to get npvs")?;
+        }
+        Ok((npvs_up, npvs_down))
+    }
+
+    /// whether the instruments in action are priced on the rayon thread pool (never on wasm32).
+    /// The bumps of the risk factors stay sequential as they modify the shared parameters,
+    /// but the revaluation of the instruments on each bump is parallel.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn is_parallel_pricing(&self) -> bool {
+        !self.calculation_configuration.is_reproducible()
+            && self.instruments_in_action.len()
+                >= self.calculation_configuration.get_parallel_pricing_threshold()
+    }
+
+    fn get_npv(&self, inst: &Instrument) -> Result<Real> {
+        let inst_code = inst.get_id();
+        let pricer = self.pricers.get(&inst_code).with_context(|| {
+            anyhow!(
+                "({}:{}) <Egnine::get_npvs> failed to get pricer for {}\n{}",
+                file!(),
+                line!(),
+                inst_code,
+                self.msg_tag
+            )
+        })?;
+
+        pricer.npv(inst).with_context(|| {
+            anyhow!(
+                "({}:{}) <Egnine::get_npvs> failed to get npv for {}\n{}\n\
+                inst: {:?}",
+                file!(),
+                line!(),
+                inst_code,
+                self.msg_tag,
+                &inst
+            )
+        })
+    }
 
-            npvs.insert(inst_code, npv);
+    pub fn get_npvs(&self) -> Result<FxHashMap<StaticId, Real>> {
+        let npv_of = |inst: &Arc<Instrument>| -> Result<(StaticId, Real)> {
+            Ok((inst.get_id(), self.get_npv(inst)?))
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.is_parallel_pricing() {
+            return self.instruments_in_action.par_iter().map(npv_of).collect();
         }
-        Ok(npvs)
+        self.instruments_in_action.iter().map(npv_of).collect()
     }
 
     pub fn get_npv_results(&self) -> Result<FxHashMap<StaticId, NpvResult>> {
-        let mut npvs = FxHashMap::default();
-        for inst in &self.instruments_in_action {
+        let npv_result_of = |inst: &Arc<Instrument>| -> Result<(StaticId, NpvResult)> {
             let inst_code = inst.get_id();
             let pricer = self.pricers.get(&inst_code).with_context(|| {
                 anyhow!(
@@ -715,18 +991,20 @@ impl Engine {
                     self.msg_tag,
                 )
             })?;
-
-            let npv = pricer.npv_result(inst)?;
-            npvs.insert(inst.get_id(), npv);
+            Ok((inst_code, pricer.npv_result(inst)?))
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.is_parallel_pricing() {
+            return self.instruments_in_action.par_iter().map(npv_result_of).collect();
         }
-        Ok(npvs)
+        self.instruments_in_action.iter().map(npv_result_of).collect()
     }
 
     pub fn set_npv_results(&mut self) -> Result<()> {
         let npvs = self.get_npv_results()?;
 
         for (code, result) in self.calculation_results.iter() {
-            result.borrow_mut().set_npv(
+            result.write().unwrap().set_npv(
                 npvs.get(code)
                     .ok_or_else(|| anyhow!("npv is not set for {}\n{}", code, self.msg_tag,))?
                     .clone(),
@@ -735,24 +1013,51 @@ impl Engine {
         Ok(())
     }
 
+    /// cashflows of bonds and swaps from the schedules, and the probability weighted amounts of the pricer for the others
     pub fn set_cashflow_inbetween(&mut self) -> Result<()> {
-        for (code, result) in self.calculation_results.iter() {
-            let npv_res = result
-                .borrow()
-                .get_npv_result()
-                .ok_or_else(|| anyhow!("npv_result is not set for {}\n{}", code, self.msg_tag,))?
-                .clone();
-
-            let cashflow = npv_res
-                .get_expected_coupon_amount()
-                .with_context(|| anyhow!("failed to get expected coupon amount for {}", code))?;
-            (*result).borrow_mut().set_cashflows(cashflow);
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
+        for inst in &self.instruments_in_action {
+            let code = inst.get_id();
+            let result = self
+                .calculation_results
+                .get(&code)
+                .ok_or_else(|| anyhow!("result is not set for {}\n{}", code, self.msg_tag,))?;
+
+            let cashflows = match inst.as_ref() {
+                Instrument::Bond(_) | Instrument::PlainSwap(_) => {
+                    let (forward_curve, past_data) = self.get_forward_curve_and_past_data(inst)?;
+                    inst.get_cashflow_details(&eval_dt, forward_curve, past_data)
+                        .with_context(|| anyhow!("failed to get cashflow details for {}", code))?
+                }
+                _ => {
+                    let npv_res = result
+                        .read().unwrap()
+                        .get_npv_result()
+                        .ok_or_else(|| anyhow!("npv_result is not set for {}\n{}", code, self.msg_tag,))?
+                        .clone();
+                    npv_res
+                        .get_expected_cashflows(inst.get_currency())
+                        .with_context(|| anyhow!("failed to get expected cashflows for {}", code))?
+                }
+            };
+            (*result).write().unwrap().set_cashflows(cashflows);
         }
         Ok(())
     }
 
     pub fn set_fx_exposures(&mut self) -> Result<()> {
         let mut fx_exposures = FxHashMap::default();
+        let mut fx_exposure_ladders = FxHashMap::default();
+        let ladder_calculation = self
+            .calculation_configuration
+            .get_fx_exposure_ladder_calculation();
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
+        let ladder_dates = self
+            .calculation_configuration
+            .get_fx_exposure_ladder_tenors()
+            .iter()
+            .map(|tenor| tenor.apply(&eval_dt))
+            .collect::<Vec<_>>();
         for inst in &self.instruments_in_action {
             let inst_code = inst.get_id();
             let pricer = self.pricers.get(&inst_code).ok_or_else(|| {
@@ -768,7 +1073,7 @@ impl Engine {
                 .ok_or_else(|| {
                     anyhow!("failed to get npv for {} in getting fx-exposure", inst_code)
                 })?
-                .borrow()
+                .read().unwrap()
                 .get_npv_result()
                 .ok_or_else(|| anyhow!("npv is not set for {} in getting fx-exposure", inst_code))?
                 .get_npv();
@@ -777,16 +1082,196 @@ impl Engine {
                 .fx_exposure(inst, npv)
                 .context("failed to get fx exposure")?;
 
+            if ladder_calculation {
+                // the exposure without the cashflow dates settles on the maturity
+                let exposure_by_date = match pricer
+                    .fx_exposure_by_date(inst, npv)
+                    .context("failed to get fx exposure by date")?
+                {
+                    Some(exposure_by_date) => exposure_by_date,
+                    None => {
+                        let settlement_date = inst.get_maturity().cloned().unwrap_or(eval_dt);
+                        fx_exposure
+                            .iter()
+                            .map(|(currency, v)| (*currency, vec![(settlement_date, *v)]))
+                            .collect()
+                    }
+                };
+                let mut ladder: FxHashMap<Currency, Vec<Real>> = FxHashMap::default();
+                for (currency, exposures) in exposure_by_date.iter() {
+                    let buckets = ladder
+                        .entry(*currency)
+                        .or_insert_with(|| vec![0.0; ladder_dates.len() + 1]);
+                    for (date, v) in exposures.iter() {
+                        let idx = ladder_dates
+                            .iter()
+                            .position(|d| date <= d)
+                            .unwrap_or(ladder_dates.len());
+                        buckets[idx] += v;
+                    }
+                }
+                fx_exposure_ladders.insert(inst_code, ladder);
+            }
+
             fx_exposures.insert(inst.get_id(), fx_exposure);
         }
 
         for (code, result) in self.calculation_results.iter() {
-            (*result).borrow_mut().set_fx_exposure(
+            (*result).write().unwrap().set_fx_exposure(
                 fx_exposures
                     .get(code)
                     .ok_or_else(|| anyhow!("fx exposure is not set"))?
                     .clone(),
             );
+            if let Some(ladder) = fx_exposure_ladders.remove(code) {
+                (*result).write().unwrap().set_fx_exposure_ladder(ladder);
+            }
+        }
+        Ok(())
+    }
+
+    /// clean and dirty prices, accrued interest, yield, duration and convexity of the bonds in action
+    /// by the pricers supporting bond analytics (BondPricer and KrxYieldPricer)
+    pub fn set_bond_analytics(&mut self) -> Result<()> {
+        for inst in &self.instruments_in_action {
+            let inst_code = inst.get_id();
+            let pricer = self.pricers.get(&inst_code).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) failed to get pricer for {} in getting bond analytics",
+                    file!(),
+                    line!(),
+                    inst_code
+                )
+            })?;
+            let result = self.calculation_results.get(&inst_code).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) failed to get result for {} in getting bond analytics",
+                    file!(),
+                    line!(),
+                    inst_code
+                )
+            })?;
+            let npv = result
+                .read().unwrap()
+                .get_npv_result()
+                .ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) npv is not set for {} in getting bond analytics",
+                        file!(),
+                        line!(),
+                        inst_code
+                    )
+                })?
+                .get_npv();
+
+            if let Some(bond_analytics) = pricer
+                .bond_analytics(inst, npv)
+                .with_context(|| anyhow!("failed to get bond analytics of {}", inst_code))?
+            {
+                result.write().unwrap().set_bond_analytics(bond_analytics);
+            }
+        }
+        Ok(())
+    }
+
+    /// npvs of the instruments in action with the quotes moved to the marking side
+    /// The quotes are put back after the calculation
+    fn get_npvs_marked_at(
+        &self,
+        quotes: &[Arc<RwLock<MarketPrice>>],
+        marking_side: MarkingSide,
+    ) -> Result<FxHashMap<StaticId, Real>> {
+        let original_values: Vec<Real> = quotes.iter().map(|q| q.read().unwrap().get_value()).collect();
+        for quote in quotes.iter() {
+            let marked_value = quote.read().unwrap().get_marked_value(marking_side);
+            quote.write().unwrap().set_price(marked_value);
+        }
+        let npvs = self.get_npvs();
+        // put back
+        for (quote, value) in quotes.iter().zip(original_values) {
+            quote.write().unwrap().set_price(value);
+        }
+        npvs
+    }
+
+    /// Set the exit value (value with the quotes at the marking side) and
+    /// the bid-ask adjustment (exit value - value with the quotes at mid).
+    /// Only the equities and fxs given with bid and ask are moved.
+    /// In the conservative marking, the exit value is the lower of the values at bid and ask,
+    /// and the short exit value is the higher one which is taken by the short positions.
+    /// The results are represented in value (considering unit_notional).
+    pub fn set_bid_ask_adjustment(&mut self) -> Result<()> {
+        self.reset_instruments_in_action();
+        let quotes: Vec<Arc<RwLock<MarketPrice>>> = self
+            .equities
+            .values()
+            .chain(self.fxs.values())
+            .filter(|q| q.read().unwrap().has_bid_ask())
+            .cloned()
+            .collect();
+
+        let mid_npvs = self.get_npvs_marked_at(&quotes, MarkingSide::Mid)?;
+        let marking_side = self.calculation_configuration.get_marking_side();
+        let (exit_npvs, other_npvs) = match marking_side {
+            MarkingSide::Conservative => (
+                self.get_npvs_marked_at(&quotes, MarkingSide::Bid)?,
+                Some(self.get_npvs_marked_at(&quotes, MarkingSide::Ask)?),
+            ),
+            marking_side => (self.get_npvs_marked_at(&quotes, marking_side)?, None),
+        };
+
+        for inst in self.instruments_in_action.iter() {
+            let inst_id = inst.get_id();
+            let unitamt = inst.get_unit_notional();
+            let (exit_npv, mid_npv) = match (exit_npvs.get(&inst_id), mid_npvs.get(&inst_id)) {
+                (Some(exit_npv), Some(mid_npv)) => (*exit_npv, *mid_npv),
+                _ => {
+                    return Err(anyhow!(
+                        "({}:{}) exit npv is not set for {}\n{}",
+                        file!(),
+                        line!(),
+                        inst_id,
+                        self.msg_tag,
+                    ))
+                }
+            };
+            let mut result = self
+                .calculation_results
+                .get(&inst_id)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) result is not set for {}",
+                        file!(),
+                        line!(),
+                        inst_id,
+                    )
+                })?
+                .write().unwrap();
+            match &other_npvs {
+                Some(other_npvs) => {
+                    let other_npv = other_npvs.get(&inst_id).ok_or_else(|| {
+                        anyhow!(
+                            "({}:{}) npv at ask is not set for {}\n{}",
+                            file!(),
+                            line!(),
+                            inst_id,
+                            self.msg_tag,
+                        )
+                    })?;
+                    // the worse for the holder of the unit notional, which may be negative
+                    let (long_value, short_value) = {
+                        let (v1, v2) = (exit_npv * unitamt, other_npv * unitamt);
+                        (v1.min(v2), v1.max(v2))
+                    };
+                    result.set_exit_value(long_value);
+                    result.set_bid_ask_adjustment(long_value - mid_npv * unitamt);
+                    result.set_short_exit_value(short_value);
+                }
+                None => {
+                    result.set_exit_value(exit_npv * unitamt);
+                    result.set_bid_ask_adjustment((exit_npv - mid_npv) * unitamt);
+                }
+            }
         }
         Ok(())
     }
@@ -794,7 +1279,7 @@ impl Engine {
     /// Set the value of the instruments which means npv * unit_notional
     pub fn set_values(&mut self) -> Result<()> {
         for (_code, result) in self.calculation_results.iter() {
-            (*result).borrow_mut().set_value()?;
+            (*result).write().unwrap().set_value()?;
         }
         Ok(())
     }
@@ -819,7 +1304,7 @@ impl Engine {
                         inst.get_type_name(),
                     )
                 })?
-                .borrow()
+                .read().unwrap()
                 .get_npv_result()
                 .ok_or_else(|| {
                     anyhow!(
@@ -843,7 +1328,7 @@ impl Engine {
                     inst.get_type_name(),
                 )
             })?)
-            .borrow_mut()
+            .write().unwrap()
             .set_single_delta(inst_code, delta);
             (*self.calculation_results.get(&inst_code).ok_or_else(|| {
                 anyhow!(
@@ -854,7 +1339,7 @@ impl Engine {
                     inst.get_type_name(),
                 )
             })?)
-            .borrow_mut()
+            .write().unwrap()
             .set_single_gamma(inst_code, gamma);
         }
         Ok(())
@@ -864,7 +1349,7 @@ impl Engine {
         self.reset_instruments_in_action();
 
         let all_underlying_ids = self.instruments.get_all_underlying_ids();
-        let delta_bump_ratio = self.calculation_configuration.get_delta_bump_ratio();
+        let mut delta_bump_ratio: Real;
 
         let mut delta_up_map: FxHashMap<StaticId, Real>;
         let mut delta_down_map: FxHashMap<StaticId, Real>;
@@ -876,15 +1361,29 @@ impl Engine {
         let mut mid: Real;
         let mut original_price: Real;
 
-        let up_bump = 1.0 + delta_bump_ratio;
-        let down_bump = 1.0 - delta_bump_ratio;
+        let mut up_bump: Real;
+        let mut down_bump: Real;
+        let delta_scheme = self.calculation_configuration.get_delta_scheme();
+        let gamma_richardson = self
+            .calculation_configuration
+            .get_gamma_richardson_extrapolation();
+        let gamma_calculation = self.calculation_configuration.get_gamma_calculation();
         let exclude_type = vec!["Stock", "Futures"];
         let exclude_type_clone = exclude_type.clone();
         for und_code in all_underlying_ids.iter() {
+            self.check_cancellation()?;
             self.instruments_in_action = self
                 .instruments
                 .instruments_with_underlying(*und_code, Some(exclude_type_clone.clone()));
 
+            // the delta by automatic differentiation needs no bump unless gamma is required
+            if !gamma_calculation {
+                let instruments_in_action = std::mem::take(&mut self.instruments_in_action);
+                self.instruments_in_action = instruments_in_action
+                    .into_iter()
+                    .filter(|inst| !self.has_ad_delta(inst.as_ref()))
+                    .collect();
+            }
             if self.instruments_in_action.is_empty() {
                 continue;
             }
@@ -893,8 +1392,11 @@ impl Engine {
                 .equities
                 .get(und_code)
                 .ok_or_else(|| anyhow!("there is no equity {}", und_code))?
-                .borrow()
+                .read().unwrap()
                 .get_value();
+            delta_bump_ratio = self.get_relative_bump(RiskFactorClass::Spot, und_code)?;
+            up_bump = 1.0 + delta_bump_ratio;
+            down_bump = 1.0 - delta_bump_ratio;
 
             // set instruments that needs to be calculated
             {
@@ -902,7 +1404,7 @@ impl Engine {
                     anyhow!("({}:{}) there is no stock {}", file!(), line!(), und_code)
                 })?)
                 .as_ref()
-                .borrow_mut();
+                .write().unwrap();
 
                 *equity *= up_bump;
             }
@@ -913,7 +1415,7 @@ impl Engine {
                     anyhow!("({}:{}) there is no stock {}", file!(), line!(), und_code)
                 })?)
                 .as_ref()
-                .borrow_mut();
+                .write().unwrap();
 
                 equity.set_price(original_price);
                 *equity *= down_bump;
@@ -921,8 +1423,27 @@ impl Engine {
 
             delta_down_map = self.get_npvs().context("failed to get npvs")?;
 
-            for inst in &self.instruments_in_action {
-                let inst_code = inst.get_id();
+            // npvs on the double bumps for the Richardson extrapolation of gamma
+            let (double_up_map, double_down_map) = match gamma_richardson {
+                true => {
+                    let equity = self.equities.get(und_code).ok_or_else(|| {
+                        anyhow!("({}:{}) there is no stock {}", file!(), line!(), und_code)
+                    })?;
+                    equity
+                        .write().unwrap()
+                        .set_price(original_price * (1.0 + 2.0 * delta_bump_ratio));
+                    let double_up_map = self.get_npvs().context("failed to get npvs")?;
+                    equity
+                        .write().unwrap()
+                        .set_price(original_price * (1.0 - 2.0 * delta_bump_ratio));
+                    let double_down_map = self.get_npvs().context("failed to get npvs")?;
+                    (double_up_map, double_down_map)
+                }
+                false => (FxHashMap::default(), FxHashMap::default()),
+            };
+
+            for inst in &self.instruments_in_action {
+                let inst_code = inst.get_id();
                 let unitamt = inst.get_unit_notional();
                 delta_up = *delta_up_map
                     .get(&inst_code)
@@ -931,31 +1452,51 @@ impl Engine {
                     .get(&inst_code)
                     .ok_or_else(|| anyhow!("delta_down is not set"))?;
 
-                delta = (delta_up - delta_down) / (2.0 * delta_bump_ratio) * DELTA_PNL_UNIT;
-
-                (*self.calculation_results.get(&inst_code).ok_or_else(|| {
-                    anyhow!(
-                        "({}:{}) result is not set for {}",
-                        file!(),
-                        line!(),
-                        inst_code,
-                    )
-                })?)
-                .borrow_mut()
-                .set_single_delta(*und_code, delta * unitamt);
-
                 mid = self
                     .calculation_results
                     .get(&inst.get_id())
                     .ok_or_else(|| anyhow!("result is not set"))?
-                    .borrow()
+                    .read().unwrap()
                     .get_npv_result()
                     .ok_or_else(|| anyhow!("npv is not set"))?
                     .get_npv();
 
+                if !self.has_ad_delta(inst.as_ref()) {
+                    delta = finite_difference(
+                        delta_scheme,
+                        Some(delta_up),
+                        mid,
+                        Some(delta_down),
+                        delta_bump_ratio,
+                    )? * DELTA_PNL_UNIT;
+
+                    (*self.calculation_results.get(&inst_code).ok_or_else(|| {
+                        anyhow!(
+                            "({}:{}) result is not set for {}",
+                            file!(),
+                            line!(),
+                            inst_code,
+                        )
+                    })?)
+                    .write().unwrap()
+                    .set_single_delta(*und_code, delta * unitamt);
+                }
+
                 gamma = delta_up - mid + delta_down - mid;
                 gamma *= DELTA_PNL_UNIT / delta_bump_ratio;
                 gamma *= 0.5 * (DELTA_PNL_UNIT / delta_bump_ratio);
+                if gamma_richardson {
+                    let double_up = *double_up_map
+                        .get(&inst_code)
+                        .ok_or_else(|| anyhow!("double up npv is not set"))?;
+                    let double_down = *double_down_map
+                        .get(&inst_code)
+                        .ok_or_else(|| anyhow!("double down npv is not set"))?;
+                    let mut double_gamma = double_up - mid + double_down - mid;
+                    double_gamma *= DELTA_PNL_UNIT / (2.0 * delta_bump_ratio);
+                    double_gamma *= 0.5 * (DELTA_PNL_UNIT / (2.0 * delta_bump_ratio));
+                    gamma = (4.0 * gamma - double_gamma) / 3.0;
+                }
 
                 (*self
                     .calculation_results
@@ -968,7 +1509,7 @@ impl Engine {
                             inst.get_id(),
                         )
                     })?)
-                .borrow_mut()
+                .write().unwrap()
                 .set_single_gamma(*und_code, gamma * unitamt);
             }
 
@@ -977,7 +1518,7 @@ impl Engine {
                     anyhow!("({}:{}) there is no stock {}", file!(), line!(), und_code)
                 })?)
                 .as_ref()
-                .borrow_mut()
+                .write().unwrap()
                 .set_price(original_price);
             }
         }
@@ -985,58 +1526,333 @@ impl Engine {
         Ok(())
     }
 
+    /// the delta is set by set_first_order_greeks_by_ad, which keeps the smoothed deltas
+    fn has_ad_delta(&self, inst: &Instrument) -> bool {
+        self.ad_instrument_ids.contains(&inst.get_id()) && !self.is_payoff_smoothed(inst)
+    }
+
+    fn is_payoff_smoothed(&self, inst: &Instrument) -> bool {
+        self.calculation_configuration.get_payoff_smoothing().is_some()
+            && self
+                .calculation_configuration
+                .is_discontinuous_payoff_id(&inst.get_id())
+    }
+
+    /// npvs of the instruments in action on the relative shift of the spot, which is put back afterwards
+    fn get_npvs_on_spot_shift(
+        &self,
+        und_code: &StaticId,
+        original_price: Real,
+        shift: Real,
+    ) -> Result<FxHashMap<StaticId, Real>> {
+        let equity = self.equities.get(und_code).ok_or_else(|| {
+            anyhow!("({}:{}) there is no stock {}", file!(), line!(), und_code)
+        })?;
+        equity.write().unwrap().set_price(original_price * (1.0 + shift));
+        let npvs = self.get_npvs();
+        equity.write().unwrap().set_price(original_price);
+        npvs.context("failed to get npvs")
+    }
+
+    /// delta and gamma of the instruments with discontinuous payoffs under payoff_smoothing,
+    /// which overwrite the ones of set_delta_gamma. Both are the central differences on the relative bump h
+    /// of the smoothed value Ṽ in the same units as set_delta_gamma:
+    /// - CallSpread(w): Ṽ = V and h = max(w / 2, Spot bump), the delta of the call spread replicating the digital
+    /// - GaussianKernel(b): Ṽ(S) = Σ_i w_i V(S (1 + b z_i)) on the Gauss-Hermite nodes z_i and h is the Spot bump
+    pub fn set_smoothed_delta_gamma(&mut self) -> Result<()> {
+        let Some(smoothing) = self.calculation_configuration.get_payoff_smoothing() else {
+            return Ok(());
+        };
+        self.reset_instruments_in_action();
+
+        let exclude_type = vec!["Stock", "Futures"];
+        for und_code in self.instruments.get_all_underlying_ids().iter() {
+            self.check_cancellation()?;
+            let smoothed_instruments: Vec<Arc<Instrument>> = self
+                .instruments
+                .instruments_with_underlying(*und_code, Some(exclude_type.clone()))
+                .into_iter()
+                .filter(|inst| self.is_payoff_smoothed(inst.as_ref()))
+                .collect();
+            if smoothed_instruments.is_empty() {
+                continue;
+            }
+            self.instruments_in_action = smoothed_instruments;
+
+            let original_price = self
+                .equities
+                .get(und_code)
+                .ok_or_else(|| anyhow!("({}:{}) there is no equity {}", file!(), line!(), und_code))?
+                .read().unwrap()
+                .get_value();
+            let spot_bump = self.get_relative_bump(RiskFactorClass::Spot, und_code)?;
+            let (bump, kernel): (Real, Vec<(Real, Real)>) = match smoothing {
+                PayoffSmoothing::CallSpread(width) => (spot_bump.max(0.5 * width), vec![(0.0, 1.0)]),
+                PayoffSmoothing::GaussianKernel(bandwidth) => (
+                    spot_bump,
+                    GaussHermite::new(5)?
+                        .get_normal_nodes()
+                        .into_iter()
+                        .map(|(z, w)| (bandwidth * z, w))
+                        .collect(),
+                ),
+            };
+
+            // smoothed npvs on the down, mid and up spots
+            let mut smoothed_npvs: Vec<FxHashMap<StaticId, Real>> = vec![FxHashMap::default(); 3];
+            for (npvs, center) in smoothed_npvs.iter_mut().zip([-bump, 0.0, bump]) {
+                for (shift, weight) in kernel.iter() {
+                    let bumped = self.get_npvs_on_spot_shift(
+                        und_code,
+                        original_price,
+                        (1.0 + center) * (1.0 + shift) - 1.0,
+                    )?;
+                    for (inst_code, npv) in bumped.into_iter() {
+                        *npvs.entry(inst_code).or_insert(0.0) += weight * npv;
+                    }
+                }
+            }
+
+            for inst in &self.instruments_in_action {
+                let inst_code = inst.get_id();
+                let unitamt = inst.get_unit_notional();
+                let npv = |i: usize| -> Result<Real> {
+                    smoothed_npvs[i].get(&inst_code).copied().ok_or_else(|| {
+                        anyhow!(
+                            "({}:{}) smoothed npv is not set for {}",
+                            file!(),
+                            line!(),
+                            inst_code,
+                        )
+                    })
+                };
+                let (down, mid, up) = (npv(0)?, npv(1)?, npv(2)?);
+                let delta = (up - down) / (2.0 * bump) * DELTA_PNL_UNIT;
+                let gamma =
+                    (up - 2.0 * mid + down) * (DELTA_PNL_UNIT / bump) * 0.5 * (DELTA_PNL_UNIT / bump);
+
+                let mut result = self
+                    .calculation_results
+                    .get(&inst_code)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "({}:{}) result is not set for {}",
+                            file!(),
+                            line!(),
+                            inst_code,
+                        )
+                    })?
+                    .write().unwrap();
+                result.set_single_delta(*und_code, delta * unitamt);
+                result.set_single_gamma(*und_code, gamma * unitamt);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// fx delta and fx gamma of the instruments priced with each fx rate in self.fxs.
+    /// The fx rate is bumped relatively by the Fx bump size and the units are the same as delta and gamma,
+    /// i.e., the value change on the 1% rise of the fx rate. Delta follows delta_scheme and gamma is the central difference.
+    pub fn set_fx_delta_gamma(&mut self) -> Result<()> {
+        let delta_scheme = self.calculation_configuration.get_delta_scheme();
+        let mut fx_codes: Vec<FxCode> = self.fxs.keys().copied().collect();
+        fx_codes.sort_by_key(|fx_code| fx_code.to_string());
+
+        for fx_code in fx_codes.iter() {
+            self.check_cancellation()?;
+            self.instruments_in_action = self.instruments.instruments_using_fx(fx_code);
+            if self.instruments_in_action.is_empty() {
+                continue;
+            }
+
+            let fx = self.fxs.get(fx_code).cloned().ok_or_else(|| {
+                anyhow!("({}:{}) there is no fx {}", file!(), line!(), fx_code)
+            })?;
+            let bump_ratio = self.get_relative_bump(RiskFactorClass::Fx, &fx_code.to_static_id())?;
+            let original_rate = fx.read().unwrap().get_value();
+            // both sides are needed for gamma
+            fx.write().unwrap().set_price(original_rate * (1.0 + bump_ratio));
+            let npvs_up = self.get_npvs();
+            fx.write().unwrap().set_price(original_rate * (1.0 - bump_ratio));
+            let npvs_down = self.get_npvs();
+            fx.write().unwrap().set_price(original_rate);
+            let npvs_up = npvs_up.context("failed to get npvs")?;
+            let npvs_down = npvs_down.context("failed to get npvs")?;
+
+            for inst in &self.instruments_in_action {
+                let inst_id = inst.get_id();
+                let unitamt = inst.get_unit_notional();
+                let result = self.calculation_results.get(&inst_id).ok_or_else(|| {
+                    anyhow!("({}:{}) result is not set for {}", file!(), line!(), inst_id)
+                })?;
+                let mid = result
+                    .read().unwrap()
+                    .get_npv_result()
+                    .ok_or_else(|| anyhow!("({}:{}) npv is not set for {}", file!(), line!(), inst_id))?
+                    .get_npv();
+                let up = *npvs_up.get(&inst_id).ok_or_else(|| {
+                    anyhow!("({}:{}) up npv is not set for {}", file!(), line!(), inst_id)
+                })?;
+                let down = *npvs_down.get(&inst_id).ok_or_else(|| {
+                    anyhow!("({}:{}) down npv is not set for {}", file!(), line!(), inst_id)
+                })?;
+
+                let fx_delta =
+                    finite_difference(delta_scheme, Some(up), mid, Some(down), bump_ratio)?
+                        * DELTA_PNL_UNIT;
+                let fx_gamma = (up - 2.0 * mid + down)
+                    * (DELTA_PNL_UNIT / bump_ratio)
+                    * (DELTA_PNL_UNIT / bump_ratio)
+                    * 0.5;
+
+                let mut result = result.write().unwrap();
+                result.set_single_fx_delta(*fx_code, fx_delta * unitamt);
+                result.set_single_fx_gamma(*fx_code, fx_gamma * unitamt);
+            }
+        }
+        Ok(())
+    }
+
+    /// delta, vega, and rho from the automatic differentiation of the pricers in one pass.
+    /// This runs before the bumps: set_delta_gamma bumps the instruments done here only for gamma,
+    /// and set_vega and set_rho skip them. Gamma and the structures are still from bump and revalue.
+    /// Rho is reported on the curves that set_rho bumps for the instrument (e.g., not on the borrowing curve),
+    /// and an instrument is left to bump and revalue if the pricer does not differentiate all of them.
+    pub fn set_first_order_greeks_by_ad(&mut self) -> Result<()> {
+        self.ad_instrument_ids.clear();
+        let config = self.calculation_configuration.clone();
+        // instrument id -> curves bumped in set_rho
+        let mut rho_curve_ids: FxHashMap<StaticId, Vec<StaticId>> = FxHashMap::default();
+        if config.get_rho_calculation() {
+            for curve_id in self.instruments.get_all_curve_ids(&self.match_parameter)? {
+                for inst in self.instruments.instruments_using_curve(
+                    curve_id,
+                    &self.match_parameter,
+                    Some(vec!["Stock"]),
+                )? {
+                    let curve_ids = rho_curve_ids.entry(inst.get_id()).or_default();
+                    if !curve_ids.contains(&curve_id) {
+                        curve_ids.push(curve_id);
+                    }
+                }
+            }
+        }
+        for inst in self.instruments.get_instruments_clone() {
+            let inst_code = inst.get_id();
+            let Some(pricer) = self.pricers.get(&inst_code) else {
+                continue;
+            };
+            let Some(greeks) = pricer.first_order_greeks(&inst).with_context(|| {
+                anyhow!(
+                    "({}:{}) failed to get first order greeks for {}\n{}",
+                    file!(),
+                    line!(),
+                    inst_code,
+                    self.msg_tag,
+                )
+            })?
+            else {
+                continue;
+            };
+            let curve_ids = rho_curve_ids.get(&inst_code).cloned().unwrap_or_default();
+            if !curve_ids.iter().all(|id| greeks.rates.contains_key(id)) {
+                continue;
+            }
+
+            let unitamt = inst.get_unit_notional();
+            let mut result = self
+                .calculation_results
+                .get(&inst_code)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) result is not set for {}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                    )
+                })?
+                .write().unwrap();
+            // the smoothed deltas of the discontinuous payoffs are kept
+            if config.get_delta_calculation() && !self.is_payoff_smoothed(inst.as_ref()) {
+                for (und_code, delta) in greeks.spot.iter() {
+                    result.set_single_delta(*und_code, delta * DELTA_PNL_UNIT * unitamt);
+                }
+            }
+            if config.get_vega_calculation() {
+                for (vol_code, vega) in greeks.volatility.iter() {
+                    result.set_single_vega(*vol_code, vega * VEGA_PNL_UNIT * unitamt);
+                }
+            }
+            if config.get_rho_calculation() {
+                for curve_id in curve_ids.iter() {
+                    let rho = greeks.rates[curve_id];
+                    result.set_single_rho(*curve_id, rho * RHO_PNL_UNIT * unitamt);
+                }
+            }
+            self.ad_instrument_ids.insert(inst_code);
+        }
+        Ok(())
+    }
+
     pub fn set_rho(&mut self) -> Result<()> {
         let mut npvs_up: FxHashMap<StaticId, Real>;
+        let mut npvs_down: FxHashMap<StaticId, Real>;
+        let rho_scheme = self.calculation_configuration.get_rho_scheme();
         let all_curve_ids = self
             .instruments
             .get_all_curve_ids(&self.match_parameter)?;
-        let bump_val = self.calculation_configuration.get_rho_bump_value();
         let exclude_type = vec!["Stock"];
         let exclude_type_clone = exclude_type.clone();
 
         for curve_id in all_curve_ids {
+            self.check_cancellation()?;
             self.instruments_in_action = self.instruments.instruments_using_curve(
                 curve_id,
                 &self.match_parameter,
                 Some(exclude_type_clone.clone()),
             )?;
+            self.instruments_in_action
+                .retain(|inst| !self.ad_instrument_ids.contains(&inst.get_id()));
             if self.instruments_in_action.is_empty() {
                 continue;
             }
-            // bump the curve but limit the scope that the zero_curve ismutably borrowed
-            {
-                (*self.zero_curves.get(&curve_id).with_context(|| {
-                    anyhow!(
-                        "({}:{}) no zero curve: {}\n{}",
-                        file!(),
-                        line!(),
-                        curve_id,
-                        self.msg_tag,
-                    )
-                })?)
-                .as_ref()
-                .borrow_mut()
-                .bump_time_interval(None, None, bump_val)?;
-            }
-
-            npvs_up = self.get_npvs().context("failed to get npvs")?;
+            let bump_val = self.get_absolute_bump(RiskFactorClass::Rate, &curve_id)?;
+            let curve = self.zero_curves.get(&curve_id).with_context(|| {
+                anyhow!(
+                    "({}:{}) no zero curve: {}\n{}",
+                    file!(),
+                    line!(),
+                    curve_id,
+                    self.msg_tag,
+                )
+            })?;
+            (npvs_up, npvs_down) = self.get_bumped_npvs(rho_scheme, |sign| {
+                curve
+                    .write().unwrap()
+                    .bump_time_interval(None, None, sign * bump_val)
+            })?;
 
             for inst in &self.instruments_in_action {
                 let inst_code = inst.get_id();
                 let unitamt = inst.get_unit_notional();
-                let npv_up = npvs_up
-                    .get(&inst_code)
-                    .ok_or_else(|| anyhow!("npv_up is not set"))?;
                 let npv = self
                     .calculation_results
                     .get(&inst_code)
                     .ok_or_else(|| anyhow!("result is not set"))?
-                    .borrow()
+                    .read().unwrap()
                     .get_npv_result()
                     .ok_or_else(|| anyhow!("npv is not set"))?
                     .get_npv();
 
-                let rho = (npv_up - npv) / bump_val * RHO_PNL_UNIT * unitamt;
+                let rho = finite_difference(
+                    rho_scheme,
+                    npvs_up.get(&inst_code).copied(),
+                    npv,
+                    npvs_down.get(&inst_code).copied(),
+                    bump_val,
+                )? * RHO_PNL_UNIT
+                    * unitamt;
                 (*self
                     .calculation_results
                     .get(&inst.get_id())
@@ -1048,12 +1864,52 @@ impl Engine {
                             inst.get_id(),
                         )
                     })?)
-                .borrow_mut()
+                .write().unwrap()
                 .set_single_rho(curve_id, rho);
             }
-            // put back the bump value
-            {
-                (*self.zero_curves.get(&curve_id).with_context(|| {
+        }
+        Ok(())
+    }
+
+    /// cross sensitivity of delta and rho on each pair of an underlying and a curve used by the same instruments:
+    /// (V(S+, r+) - V(S+, r-) - V(S-, r+) + V(S-, r-)) / (4 ΔS Δr) in the units of delta (1% spot) and rho (1bp).
+    /// The bumps are the Spot (relative) and Rate (absolute) bump sizes as in delta and rho.
+    pub fn set_delta_rho(&mut self) -> Result<()> {
+        let all_curve_ids = self
+            .instruments
+            .get_all_curve_ids(&self.match_parameter)?;
+        let exclude_type = vec!["Stock", "Cash"];
+
+        for und_id in self.instruments.get_all_underlying_ids() {
+            let und_instruments = self
+                .instruments
+                .instruments_with_underlying(und_id, Some(exclude_type.clone()));
+            if und_instruments.is_empty() {
+                continue;
+            }
+            let equity = self.equities.get(&und_id).cloned().ok_or_else(|| {
+                anyhow!("({}:{}) there is no equity {}", file!(), line!(), und_id)
+            })?;
+            let original_price = equity.read().unwrap().get_value();
+            let spot_bump = self.get_relative_bump(RiskFactorClass::Spot, &und_id)?;
+
+            for curve_id in all_curve_ids.iter() {
+                self.check_cancellation()?;
+                let curve_instruments = self.instruments.instruments_using_curve(
+                    *curve_id,
+                    &self.match_parameter,
+                    Some(exclude_type.clone()),
+                )?;
+                self.instruments_in_action = und_instruments
+                    .iter()
+                    .filter(|inst| curve_instruments.iter().any(|c| c.get_id() == inst.get_id()))
+                    .cloned()
+                    .collect();
+                if self.instruments_in_action.is_empty() {
+                    continue;
+                }
+                let rate_bump = self.get_absolute_bump(RiskFactorClass::Rate, curve_id)?;
+                let curve = self.zero_curves.get(curve_id).cloned().with_context(|| {
                     anyhow!(
                         "({}:{}) no zero curve: {}\n{}",
                         file!(),
@@ -1061,10 +1917,218 @@ impl Engine {
                         curve_id,
                         self.msg_tag,
                     )
-                })?)
-                .as_ref()
-                .borrow_mut()
-                .bump_time_interval(None, None, -bump_val)?;
+                })?;
+
+                // (spot sign, rate sign) on the four corners
+                let corners: [(Real, Real); 4] = [(1.0, 1.0), (1.0, -1.0), (-1.0, 1.0), (-1.0, -1.0)];
+                let mut corner_npvs = Vec::with_capacity(corners.len());
+                for (spot_sign, rate_sign) in corners {
+                    equity
+                        .write().unwrap()
+                        .set_price(original_price * (1.0 + spot_sign * spot_bump));
+                    curve
+                        .write().unwrap()
+                        .bump_time_interval(None, None, rate_sign * rate_bump)?;
+                    let npvs = self.get_npvs();
+                    curve
+                        .write().unwrap()
+                        .bump_time_interval(None, None, -rate_sign * rate_bump)?;
+                    equity.write().unwrap().set_price(original_price);
+                    corner_npvs.push(npvs.context("failed to get npvs")?);
+                }
+
+                for inst in &self.instruments_in_action {
+                    let inst_id = inst.get_id();
+                    let mut cross = 0.0;
+                    for ((spot_sign, rate_sign), npvs) in corners.iter().zip(corner_npvs.iter()) {
+                        let npv = npvs.get(&inst_id).ok_or_else(|| {
+                            anyhow!("({}:{}) bumped npv is not set for {}", file!(), line!(), inst_id)
+                        })?;
+                        cross += spot_sign * rate_sign * npv;
+                    }
+                    cross *= DELTA_PNL_UNIT / (4.0 * spot_bump) * RHO_PNL_UNIT / rate_bump
+                        * inst.get_unit_notional();
+                    (*self.calculation_results.get(&inst_id).ok_or_else(|| {
+                        anyhow!("({}:{}) result is not set for {}", file!(), line!(), inst_id)
+                    })?)
+                    .write().unwrap()
+                    .set_single_delta_rho(und_id, *curve_id, cross);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Spot/vol ladder of each underlying: the values of the instruments on the underlying
+    /// with the spot moved by (1 + spot shift) and the volatility by + vol shift on each pair of the shifts.
+    /// The volatility is not shifted for the underlyings without volatility, e.g., of futures only.
+    /// The deltas on the nodes are the central differences on the Spot bump at the shifted spot.
+    pub fn set_ladder(&mut self) -> Result<()> {
+        let spot_shifts = self.calculation_configuration.get_ladder_spot_shifts().clone();
+        let vol_shifts = self.calculation_configuration.get_ladder_vol_shifts().clone();
+        let calc_delta = self.calculation_configuration.get_ladder_delta_calculation();
+        let shape = (spot_shifts.len(), vol_shifts.len());
+        let exclude_type = vec!["Cash"];
+
+        for und_id in self.instruments.get_all_underlying_ids() {
+            self.check_cancellation()?;
+            self.instruments_in_action = self
+                .instruments
+                .instruments_with_underlying(und_id, Some(exclude_type.clone()));
+            if self.instruments_in_action.is_empty() {
+                continue;
+            }
+            let equity = self.equities.get(&und_id).cloned().ok_or_else(|| {
+                anyhow!("({}:{}) there is no equity {}", file!(), line!(), und_id)
+            })?;
+            let original_price = equity.read().unwrap().get_value();
+            let delta_bump_ratio = self.get_relative_bump(RiskFactorClass::Spot, &und_id)?;
+            let volatility = self.volatilities.get(&und_id).cloned();
+
+            let mut values: FxHashMap<StaticId, Array2<Real>> = FxHashMap::default();
+            let mut deltas: FxHashMap<StaticId, Array2<Real>> = FxHashMap::default();
+            for inst in self.instruments_in_action.iter() {
+                values.insert(inst.get_id(), Array2::zeros(shape));
+                deltas.insert(inst.get_id(), Array2::zeros(shape));
+            }
+
+            for (j, vol_shift) in vol_shifts.iter().enumerate() {
+                if let Some(volatility) = volatility.as_ref() {
+                    volatility
+                        .write().unwrap()
+                        .bump_volatility(None, None, None, None, *vol_shift)?;
+                }
+                for (i, spot_shift) in spot_shifts.iter().enumerate() {
+                    let spot = original_price * (1.0 + spot_shift);
+                    equity.write().unwrap().set_price(spot);
+                    let npvs = self.get_npvs().context("failed to get npvs")?;
+                    let (npvs_up, npvs_down) = match calc_delta {
+                        true => {
+                            equity
+                                .write().unwrap()
+                                .set_price(spot * (1.0 + delta_bump_ratio));
+                            let npvs_up = self.get_npvs().context("failed to get npvs")?;
+                            equity
+                                .write().unwrap()
+                                .set_price(spot * (1.0 - delta_bump_ratio));
+                            let npvs_down = self.get_npvs().context("failed to get npvs")?;
+                            (npvs_up, npvs_down)
+                        }
+                        false => (FxHashMap::default(), FxHashMap::default()),
+                    };
+
+                    for inst in self.instruments_in_action.iter() {
+                        let inst_id = inst.get_id();
+                        let unitamt = inst.get_unit_notional();
+                        let npv = npvs.get(&inst_id).ok_or_else(|| {
+                            anyhow!("({}:{}) ladder npv is not set for {}", file!(), line!(), inst_id)
+                        })?;
+                        values.get_mut(&inst_id).unwrap()[[i, j]] = npv * unitamt;
+                        if calc_delta {
+                            let (up, down) = match (npvs_up.get(&inst_id), npvs_down.get(&inst_id)) {
+                                (Some(up), Some(down)) => (up, down),
+                                _ => bail!(
+                                    "({}:{}) ladder delta npv is not set for {}",
+                                    file!(),
+                                    line!(),
+                                    inst_id
+                                ),
+                            };
+                            deltas.get_mut(&inst_id).unwrap()[[i, j]] =
+                                (up - down) / (2.0 * delta_bump_ratio) * DELTA_PNL_UNIT * unitamt;
+                        }
+                    }
+                }
+                // put back
+                equity.write().unwrap().set_price(original_price);
+                if let Some(volatility) = volatility.as_ref() {
+                    volatility
+                        .write().unwrap()
+                        .bump_volatility(None, None, None, None, -vol_shift)?;
+                }
+            }
+
+            for inst in self.instruments_in_action.iter() {
+                let inst_id = inst.get_id();
+                let result = self.calculation_results.get(&inst_id).ok_or_else(|| {
+                    anyhow!("({}:{}) result is not set for {}", file!(), line!(), inst_id)
+                })?;
+                let base_value = result
+                    .read().unwrap()
+                    .get_npv_result()
+                    .ok_or_else(|| anyhow!("({}:{}) npv is not set for {}", file!(), line!(), inst_id))?
+                    .get_npv()
+                    * inst.get_unit_notional();
+                let ladder = Ladder::new(
+                    spot_shifts.clone(),
+                    vol_shifts.clone(),
+                    base_value,
+                    values.remove(&inst_id).unwrap(),
+                    deltas.remove(&inst_id).filter(|_| calc_delta),
+                )?;
+                result.write().unwrap().set_single_ladder(und_id, ladder);
+            }
+        }
+        Ok(())
+    }
+
+    /// rho of each instrument on the curves in each role (CurveRole), e.g., the discounting and the projection
+    /// of an IRS on a single curve are reported separately, while set_rho gives one number per curve id.
+    /// The curves in a role are shifted in the pricers made for the bump only, so the other roles are unchanged.
+    /// The shift is the Rate bump size in the instrument currency taken as an absolute bump.
+    pub fn set_rho_by_role(&mut self) -> Result<()> {
+        let rho_scheme = self.calculation_configuration.get_rho_scheme();
+        let pricer_factory = self.get_pricer_factory();
+        let exclude_type = ["Stock", "Cash"];
+
+        for inst in self.instruments.get_instruments_clone().iter() {
+            if exclude_type.contains(&inst.get_type_name()) {
+                continue;
+            }
+            let inst_id = inst.get_id();
+            let unitamt = inst.get_unit_notional();
+            let bump_val = self
+                .calculation_configuration
+                .get_bump_size(RiskFactorClass::Rate, Some(inst.get_currency()))
+                .get_value();
+            let result = self.calculation_results.get(&inst_id).ok_or_else(|| {
+                anyhow!("({}:{}) result is not set for {}", file!(), line!(), inst_id)
+            })?;
+            let npv = result
+                .read().unwrap()
+                .get_npv_result()
+                .ok_or_else(|| anyhow!("({}:{}) npv is not set for {}", file!(), line!(), inst_id))?
+                .get_npv();
+
+            for role in pricer_factory.get_curve_roles(inst)? {
+                let shifted_npv = |shift: Real| -> Result<Real> {
+                    self.get_pricer_factory()
+                        .with_curve_shift(role, shift)
+                        .create_pricer(inst)?
+                        .npv(inst)
+                        .with_context(|| {
+                            anyhow!(
+                                "({}:{}) failed to get npv of {} on the {:?} curve shift\n{}",
+                                file!(),
+                                line!(),
+                                inst_id,
+                                role,
+                                self.msg_tag,
+                            )
+                        })
+                };
+                let up = match rho_scheme {
+                    FiniteDifferenceScheme::Backward => None,
+                    _ => Some(shifted_npv(bump_val)?),
+                };
+                let down = match rho_scheme {
+                    FiniteDifferenceScheme::Forward => None,
+                    _ => Some(shifted_npv(-bump_val)?),
+                };
+                let rho = finite_difference(rho_scheme, up, npv, down, bump_val)?
+                    * RHO_PNL_UNIT
+                    * unitamt;
+                result.write().unwrap().set_single_rho_by_role(role, rho);
             }
         }
         Ok(())
@@ -1072,50 +2136,44 @@ impl Engine {
 
     pub fn set_vega(&mut self) -> Result<()> {
         let mut npvs_up: FxHashMap<StaticId, Real>;
+        let mut npvs_down: FxHashMap<StaticId, Real>;
+        let vega_scheme = self.calculation_configuration.get_vega_scheme();
         let all_underlying_ids = self.instruments.get_all_underlying_ids();
-        let bump_val = self.calculation_configuration.get_vega_bump_value();
         let mut npv: Real;
         let exclude_type = vec!["Futures", "Stock"];
         let exclude_type_clone = exclude_type.clone();
         for vol_code in all_underlying_ids {
+            self.check_cancellation()?;
             self.instruments_in_action = self
                 .instruments
                 .instruments_with_underlying(vol_code, Some(exclude_type_clone.clone()));
+            self.instruments_in_action
+                .retain(|inst| !self.ad_instrument_ids.contains(&inst.get_id()));
 
             if self.instruments_in_action.is_empty() {
                 continue;
             }
+            let bump_val = self.get_absolute_bump(RiskFactorClass::Volatility, &vol_code)?;
 
-            // bump the volatility but limit the scope that is mutably borrowed
-            {
-                (*self.volatilities.get(&vol_code).ok_or_else(|| {
-                    anyhow!(
-                        "({}:{}) volatility {} is not set\ntag:\n{}",
-                        file!(),
-                        line!(),
-                        vol_code,
-                        self.msg_tag
-                    )
-                })?)
-                .as_ref()
-                .borrow_mut()
-                .bump_volatility(None, None, None, None, bump_val)?;
-            }
-
-            npvs_up = self.get_npvs().context("failed to get npvs")?; // instrument code (StaticId) -> npv (Real
+            let volatility = self.volatilities.get(&vol_code).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) volatility {} is not set\ntag:\n{}",
+                    file!(),
+                    line!(),
+                    vol_code,
+                    self.msg_tag
+                )
+            })?;
+            // instrument code (StaticId) -> npv (Real)
+            (npvs_up, npvs_down) = self.get_bumped_npvs(vega_scheme, |sign| {
+                volatility
+                    .write().unwrap()
+                    .bump_volatility(None, None, None, None, sign * bump_val)
+            })?;
 
             for inst in &self.instruments_in_action {
                 let inst_code = inst.get_id();
                 let unitamt = inst.get_unit_notional();
-                let npv_up = npvs_up.get(&inst_code).ok_or_else(|| {
-                    anyhow!(
-                        "({}:{}) npv_up is not set for {}",
-                        file!(),
-                        line!(),
-                        inst_code
-                    )
-                })?;
-
                 npv = self
                     .calculation_results
                     .get(&inst.get_id())
@@ -1127,14 +2185,21 @@ impl Engine {
                             inst_code
                         )
                     })?
-                    .borrow()
+                    .read().unwrap()
                     .get_npv_result()
                     .ok_or_else(|| {
                         anyhow!("({}:{}) npv is not set for {}", file!(), line!(), inst_code)
                     })?
                     .get_npv();
 
-                let vega = (npv_up - npv) / bump_val * VEGA_PNL_UNIT * unitamt;
+                let vega = finite_difference(
+                    vega_scheme,
+                    npvs_up.get(&inst_code).copied(),
+                    npv,
+                    npvs_down.get(&inst_code).copied(),
+                    bump_val,
+                )? * VEGA_PNL_UNIT
+                    * unitamt;
                 (*self
                     .calculation_results
                     .get(&inst.get_id())
@@ -1146,24 +2211,9 @@ impl Engine {
                             inst.get_id()
                         )
                     })?)
-                .borrow_mut()
+                .write().unwrap()
                 .set_single_vega(vol_code, vega);
             }
-            // put back the bump
-            {
-                (*self.volatilities.get(&vol_code).ok_or_else(|| {
-                    anyhow!(
-                        "({}:{}) volatility {} is not set\ntag:\n{}",
-                        file!(),
-                        line!(),
-                        vol_code,
-                        self.msg_tag
-                    )
-                })?)
-                .as_ref()
-                .borrow_mut()
-                .bump_volatility(None, None, None, None, -bump_val)?;
-            }
         }
         Ok(())
     }
@@ -1176,7 +2226,7 @@ impl Engine {
     // vega_structure[N-1] = vega_structure_up[N-1] - npv
     pub fn set_vega_structure(&mut self) -> Result<()> {
         let all_underlying_ids = self.instruments.get_all_underlying_ids();
-        let eval_dt = self.evaluation_date.borrow().get_date_clone();
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
         let bump_val = self
             .calculation_configuration
             .get_vega_structure_bump_value();
@@ -1199,6 +2249,7 @@ impl Engine {
         let exclude_type_clone = exclude_type.clone();
 
         for und_code in all_underlying_ids {
+            self.check_cancellation()?;
             self.instruments_in_action = self
                 .instruments
                 .instruments_with_underlying(und_code, Some(exclude_type_clone.clone()));
@@ -1221,7 +2272,7 @@ impl Engine {
             single_vega_structure = inst_codes_in_action
                 .clone()
                 .into_iter()
-                .zip(init_vec.into_iter())
+                .zip(init_vec)
                 .collect();
             let mut prev_npvs_up: FxHashMap<StaticId, Real> = FxHashMap::default();
             for inst_code in inst_codes_in_action.iter() {
@@ -1237,7 +2288,7 @@ impl Engine {
                                 inst_code,
                             )
                         })?
-                        .borrow()
+                        .read().unwrap()
                         .get_npv_result()
                         .ok_or_else(|| {
                             anyhow!("({}:{}) npv is not set for {}", file!(), line!(), inst_code,)
@@ -1263,7 +2314,7 @@ impl Engine {
                         )
                     })?)
                     .as_ref()
-                    .borrow_mut()
+                    .write().unwrap()
                     .bump_volatility(bump_start, bump_end, None, None, bump_val)?;
                 }
                 if let Some(start) = bump_start {
@@ -1305,7 +2356,7 @@ impl Engine {
                             inst_code,
                         )
                     })?)
-                    .borrow_mut()
+                    .write().unwrap()
                     .set_single_vega_structure(und_code, vega_structure.clone());
                 }
             }
@@ -1321,32 +2372,36 @@ impl Engine {
                     )
                 })?)
                 .as_ref()
-                .borrow_mut()
+                .write().unwrap()
                 .bump_volatility(None, None, None, None, -bump_val)?;
             }
         }
         Ok(())
     }
 
+    /// Nodes of the equity volatility surface of the underlying:
+    /// the union of vega_structure_tenors and the vega_matrix grid tenors on the grid spot moneyness,
+    /// so that both vega_structure and vega_matrix buckets have the nodes to be bumped.
+    fn get_volatility_grid(&self, und_id: &StaticId) -> (Vec<Tenor>, Array1<Real>) {
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
+        let grid = self.calculation_configuration.get_vega_matrix_grid(und_id);
+        let mut tenors = self.calculation_configuration.get_vega_structure_tenors().clone();
+        for tenor in grid.get_tenors() {
+            if !tenors.iter().any(|t| t.apply(&eval_dt) == tenor.apply(&eval_dt)) {
+                tenors.push(tenor.clone());
+            }
+        }
+        tenors.sort_by_key(|tenor| tenor.apply(&eval_dt));
+        (tenors, Array1::from(grid.get_spot_moneyness().clone()))
+    }
+
     pub fn set_vega_matrix(&mut self) -> Result<()> {
         let all_underlying_ids = self.instruments.get_all_underlying_ids();
-        let eval_dt = self.evaluation_date.borrow().get_date_clone();
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
         let bump_val = self
             .calculation_configuration
             .get_vega_structure_bump_value();
-        let calc_tenors = self.calculation_configuration.get_vega_structure_tenors();
-
         let time_calculator = NullCalendar::default();
-        let calc_times = calc_tenors
-            .iter()
-            .map(|tenor| tenor.apply(&eval_dt))
-            .map(|dt| time_calculator.get_time_difference(&eval_dt, &dt))
-            .collect::<Vec<Time>>();
-
-        let spot_moneyness = self
-            .calculation_configuration
-            .get_vega_matrix_spot_moneyness()
-            .to_vec();
 
         // instrument code (StaticId) -> npv (Real)
         let mut current_npvs_up: FxHashMap<StaticId, Real>;
@@ -1358,6 +2413,18 @@ impl Engine {
         let exclude_type_clone = exclude_type.clone();
 
         for und_code in all_underlying_ids {
+            self.check_cancellation()?;
+            let grid = self.calculation_configuration.get_vega_matrix_grid(&und_code);
+            let calc_times = grid
+                .get_tenors()
+                .iter()
+                .map(|tenor| tenor.apply(&eval_dt))
+                .map(|dt| time_calculator.get_time_difference(&eval_dt, &dt))
+                .collect::<Vec<Time>>();
+            let spot_moneyness = grid.get_spot_moneyness().clone();
+            // the matrices are set on the underlying of the instruments only
+            single_vega_matrix.clear();
+
             self.instruments_in_action = self
                 .instruments
                 .instruments_with_underlying(und_code, Some(exclude_type_clone.clone()));
@@ -1377,8 +2444,13 @@ impl Engine {
                 .get_all_inst_id(Some(&self.instruments_in_action));
 
             for inst_code in inst_codes_in_action.iter() {
-                let init = Array2::zeros((calc_times.len(), spot_moneyness.len()));
+                let init = Array2::zeros(grid.shape());
                 single_vega_matrix.insert(*inst_code, init);
+                (*self.calculation_results.get(inst_code).ok_or_else(|| {
+                    anyhow!("({}:{}) result is not set for {}", file!(), line!(), inst_code)
+                })?)
+                .write().unwrap()
+                .set_single_vega_matrix_grid(und_code, grid.clone());
             }
 
             let mut prev_npvs_up: FxHashMap<StaticId, Real> = FxHashMap::default();
@@ -1396,7 +2468,7 @@ impl Engine {
                                 inst_code,
                             )
                         })?
-                        .borrow()
+                        .read().unwrap()
                         .get_npv_result()
                         .ok_or_else(|| {
                             anyhow!("({}:{}) npv is not set for {}", file!(), line!(), inst_code,)
@@ -1434,7 +2506,7 @@ impl Engine {
                             )
                         })?)
                         .as_ref()
-                        .borrow_mut()
+                        .write().unwrap()
                         .bump_volatility(
                             bump_tenor_start,
                             bump_tenor_end,
@@ -1481,7 +2553,7 @@ impl Engine {
                                 inst_code,
                             )
                         })?)
-                        .borrow_mut()
+                        .write().unwrap()
                         .set_single_vega_matrix(und_code, vega_matrix.clone());
                     }
                 }
@@ -1498,7 +2570,7 @@ impl Engine {
                     )
                 })?)
                 .as_ref()
-                .borrow_mut()
+                .write().unwrap()
                 .bump_volatility(None, None, None, None, -bump_val)?;
             }
         }
@@ -1507,13 +2579,15 @@ impl Engine {
 
     pub fn set_div_delta(&mut self) -> Result<()> {
         let mut npvs_up: FxHashMap<StaticId, Real>;
+        let mut npvs_down: FxHashMap<StaticId, Real>;
+        let div_delta_scheme = self.calculation_configuration.get_div_delta_scheme();
         let all_dividend_codes = self.instruments.get_all_underlying_ids();
-        let bump_val = self.calculation_configuration.get_div_bump_value();
         let mut npv: Real;
         let exclude_type = vec!["Stock", "Cash"];
         let exclude_type_clone = exclude_type.clone();
 
         for div_code in all_dividend_codes {
+            self.check_cancellation()?;
             self.instruments_in_action = self
                 .instruments
                 .instruments_with_underlying(div_code, Some(exclude_type_clone.clone()));
@@ -1521,44 +2595,123 @@ impl Engine {
             if self.instruments_in_action.is_empty() {
                 continue;
             }
-            // bump dividend but limit the scope that is mutably borrowed
-            {
-                if let Some(div) = self.dividends.get(&div_code) {
-                    div.clone().unwrap().borrow_mut().bump_date_interval(None, None, bump_val)?;
+            let bump_val = self.get_dividend_bump(&div_code)?;
+            let dividend = self.dividends.get(&div_code).cloned().flatten();
+            // instrument code (StaticId) -> npv (Real)
+            (npvs_up, npvs_down) = self.get_bumped_npvs(div_delta_scheme, |sign| {
+                match &dividend {
+                    Some(div) => div
+                        .write().unwrap()
+                        .bump_date_interval(None, None, sign * bump_val),
+                    None => Ok(()),
                 }
-            }
-
-            npvs_up = self.get_npvs().context("failed to get npvs")?; // instrument code (StaticId) -> npv (Real
+            })?;
 
             for inst in &self.instruments_in_action {
                 let inst_id = inst.get_id();
                 let unitamt = inst.get_unit_notional();
-                let npv_up = npvs_up
-                    .get(&inst_id)
-                    .ok_or_else(|| anyhow!("npv_up is not set"))?;
 
                 npv = self
                     .calculation_results
                     .get(&inst.get_id())
                     .ok_or_else(|| anyhow!("result is not set"))?
-                    .borrow()
+                    .read().unwrap()
                     .get_npv_result()
                     .ok_or_else(|| anyhow!("npv is not set"))?
                     .get_npv();
 
-                let div_delta = (npv_up - npv) / bump_val * DIV_PNL_UNIT * unitamt;
+                let div_delta = finite_difference(
+                    div_delta_scheme,
+                    npvs_up.get(&inst_id).copied(),
+                    npv,
+                    npvs_down.get(&inst_id).copied(),
+                    bump_val,
+                )? * DIV_PNL_UNIT
+                    * unitamt;
                 (*self
                     .calculation_results
                     .get(&inst.get_id())
                     .ok_or_else(|| anyhow!("result is not set for {}", inst.get_code_str()))?)
-                .borrow_mut()
+                .write().unwrap()
                 .set_single_div_delta(div_code, div_delta);
             }
-            // put back the bump
-            {
-                if let Some(div) = self.dividends.get(&div_code) {
-                    div.clone().unwrap().borrow_mut().bump_date_interval(None, None, -bump_val)?;
+        }
+        Ok(())
+    }
+
+    /// Dividend carry of each underlying: the dividends whose ex-dividend dates are in
+    /// (evaluation date, next business day in div_carry_calendar] on the position dV/dS in the underlying,
+    /// e.g., the dividend points the spot position of an index forward book accrues by the next business day.
+    /// dV/dS is the central difference on the Spot bump, so it does not depend on the delta configuration.
+    pub fn set_div_carry(&mut self) -> Result<()> {
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
+        let next_date = self
+            .calculation_configuration
+            .get_div_carry_calendar()
+            .add_business_days(&eval_dt, 1);
+        let exclude_type = vec!["Cash"];
+
+        for und_id in self.instruments.get_all_underlying_ids() {
+            self.check_cancellation()?;
+            self.instruments_in_action = self
+                .instruments
+                .instruments_with_underlying(und_id, Some(exclude_type.clone()));
+            if self.instruments_in_action.is_empty() {
+                continue;
+            }
+            let dividend_amount: Real = match self.dividends.get(&und_id).cloned().flatten() {
+                Some(dividend) => {
+                    let dividend = dividend.read().unwrap();
+                    dividend
+                        .get_ex_dividend_dates()
+                        .iter()
+                        .zip(dividend.get_dividend_payments().iter())
+                        .filter(|(ex_date, _)| {
+                            ex_date.date() > eval_dt.date() && ex_date.date() <= next_date.date()
+                        })
+                        .map(|(_, (_, amount))| *amount)
+                        .sum()
+                }
+                None => 0.0,
+            };
+
+            if dividend_amount == 0.0 {
+                for inst in &self.instruments_in_action {
+                    (*self.calculation_results.get(&inst.get_id()).ok_or_else(|| {
+                        anyhow!("({}:{}) result is not set for {}", file!(), line!(), inst.get_id())
+                    })?)
+                    .write().unwrap()
+                    .set_single_div_carry(und_id, 0.0);
                 }
+                continue;
+            }
+
+            let equity = self.equities.get(&und_id).cloned().ok_or_else(|| {
+                anyhow!("({}:{}) there is no equity {}", file!(), line!(), und_id)
+            })?;
+            let bump_val = equity.read().unwrap().get_value()
+                * self.get_relative_bump(RiskFactorClass::Spot, &und_id)?;
+            let (npvs_up, npvs_down) =
+                self.get_bumped_npvs(FiniteDifferenceScheme::Central, |sign| {
+                    let price = equity.read().unwrap().get_value() + sign * bump_val;
+                    equity.write().unwrap().set_price(price);
+                    Ok(())
+                })?;
+
+            for inst in &self.instruments_in_action {
+                let inst_id = inst.get_id();
+                let up = *npvs_up.get(&inst_id).ok_or_else(|| {
+                    anyhow!("({}:{}) up npv is not set for {}", file!(), line!(), inst_id)
+                })?;
+                let down = *npvs_down.get(&inst_id).ok_or_else(|| {
+                    anyhow!("({}:{}) down npv is not set for {}", file!(), line!(), inst_id)
+                })?;
+                let position = (up - down) / (2.0 * bump_val) * inst.get_unit_notional();
+                (*self.calculation_results.get(&inst_id).ok_or_else(|| {
+                    anyhow!("({}:{}) result is not set for {}", file!(), line!(), inst_id)
+                })?)
+                .write().unwrap()
+                .set_single_div_carry(und_id, position * dividend_amount);
             }
         }
         Ok(())
@@ -1577,24 +2730,240 @@ impl Engine {
                     inst.get_type_name(),
                 )
             })?)
-            .borrow_mut()
+            .write().unwrap()
             .set_theta(0.0);
         }
         Ok(())
     }
+
+    /// Calendar of the business day theta: the calendar of the instrument if any,
+    /// otherwise the theta calendar in the configuration
+    fn get_theta_calendar<'a>(&'a self, inst: &'a Instrument) -> &'a JointCalendar {
+        inst.get_calendar()
+            .unwrap_or(self.calculation_configuration.get_theta_calendar())
+    }
+
+    /// The end of the life of the instrument in pricing and theta: the expiry at the close of the underlying market
+    /// for the options with the maturity date only whose underlying has the daily close data (as in OptionAnalyticPricer),
+    /// otherwise the maturity
+    fn get_expiry(&self, inst: &Instrument) -> Option<OffsetDateTime> {
+        let maturity = inst.get_maturity()?;
+        match inst {
+            Instrument::VanillaOption(_) => match self
+                .past_daily_close_prices
+                .get(&inst.get_underlying_ids()[0])
+            {
+                Some(market_close) => Some(market_close.get_expiry_datetime(maturity)),
+                None => Some(*maturity),
+            },
+            _ => Some(*maturity),
+        }
+    }
+
+    /// Fails if an instrument has expired by the evaluation date and warns the instruments expiring within 6 hours.
+    /// The expiry follows get_expiry, so this is checked after the daily close data are given in with_parameter_data.
+    fn check_instrument_expiries(&self) -> Result<()> {
+        let dt = self.evaluation_date.read().unwrap().get_date_clone();
+        let expiring_upto = |date: &OffsetDateTime| {
+            self.instruments
+                .iter()
+                .filter(|inst| self.get_expiry(inst).is_some_and(|expiry| expiry <= *date))
+                .map(|inst| inst.get_code_str().to_string())
+                .collect::<Vec<String>>()
+        };
+
+        let expired = expiring_upto(&dt);
+        if !expired.is_empty() {
+            bail!(
+                "(Engine::check_instrument_expiries) There are instruments with maturity within the evaluation date\n\
+                evaluation date: {:?}\n\
+                {}\n",
+                dt, expired.join(" | "),
+            );
+        }
+
+        let very_short = expiring_upto(&(dt + Duration::hours(6)));
+        if !very_short.is_empty() {
+            println!(
+                "\n(Engine::check_instrument_expiries) There are instruments with a very short maturity (within 6 hours) \n\
+                Note that these products may produce numerical errors.
+                <LIST>\n{}\n{}\n",
+                very_short.join(" | "),
+                self.msg_tag
+            );
+        }
+        Ok(())
+    }
+
+    /// The instruments (all if None) expiring up to the bumped date and the others (including those without maturity)
+    fn split_by_theta_expiry(
+        &self,
+        instruments: Option<&Vec<Arc<Instrument>>>,
+        bumped_date: &OffsetDateTime,
+        exclude_type: &[&str],
+    ) -> (Vec<Arc<Instrument>>, Vec<Arc<Instrument>>) {
+        let instruments = match instruments {
+            Some(instruments) => instruments.clone(),
+            None => self.instruments.get_instruments_clone(),
+        };
+        instruments
+            .into_iter()
+            .filter(|inst| !exclude_type.contains(&inst.get_type_name()))
+            .partition(|inst| {
+                self.get_expiry(inst)
+                    .is_some_and(|expiry| expiry <= *bumped_date)
+            })
+    }
+
+    /// Dates to which the evaluation date is bumped in theta and the instruments on each date.
+    /// In ThetaDayMode::Fixed, all instruments (None) are on evaluation_date + theta_day.
+    /// In ThetaDayMode::BusinessDay, the instruments are grouped by their theta_day-th business day.
+    pub fn get_theta_dates(
+        &self,
+        exclude_type: Vec<&str>,
+    ) -> Vec<(OffsetDateTime, Option<Vec<Arc<Instrument>>>)> {
+        let evaluation_date = self.evaluation_date.read().unwrap().get_date_clone();
+        let theta_day = self.calculation_configuration.get_theta_day() as i64;
+        match self.calculation_configuration.get_theta_day_mode() {
+            ThetaDayMode::Fixed => vec![(evaluation_date + Duration::days(theta_day), None)],
+            ThetaDayMode::BusinessDay => {
+                let mut res: Vec<(OffsetDateTime, Option<Vec<Arc<Instrument>>>)> = vec![];
+                for inst in self.instruments.iter() {
+                    if exclude_type.contains(&inst.get_type_name()) {
+                        continue;
+                    }
+                    let bumped_date = self
+                        .get_theta_calendar(inst)
+                        .add_business_days(&evaluation_date, theta_day);
+                    match res.iter_mut().find(|(date, _)| *date == bumped_date) {
+                        Some((_, Some(insts))) => insts.push(inst.clone()),
+                        _ => res.push((bumped_date, Some(vec![inst.clone()]))),
+                    }
+                }
+                res.sort_by_key(|(date, _)| *date);
+                res
+            }
+        }
+    }
+
+    /// Calendar days of the theta period and, if configured, the weekend theta:
+    /// theta per day times the holidays (including weekends) in (evaluation date, bumped_date]
+    /// in the theta calendar of each instrument.
+    pub fn set_theta_period(
+        &mut self,
+        given_instruments: &[Arc<Instrument>],
+        bumped_date: OffsetDateTime,
+    ) -> Result<()> {
+        let evaluation_date = self.evaluation_date.read().unwrap().get_date_clone();
+        let theta_day = (bumped_date.date() - evaluation_date.date()).whole_days();
+        let calc_weekend_theta = self.calculation_configuration.get_weekend_theta_calculation();
+        for inst in given_instruments.iter() {
+            let inst_id = inst.get_id();
+            let result = self.calculation_results.get(&inst_id).ok_or_else(|| {
+                anyhow!("({}:{}) result is not set for {}", file!(), line!(), inst_id)
+            })?;
+            result.write().unwrap().set_theta_day(theta_day as Integer);
+            if !calc_weekend_theta {
+                continue;
+            }
+            let calendar = self.get_theta_calendar(inst);
+            let holidays = (1..=theta_day)
+                .filter(|d| calendar.is_holiday(&(evaluation_date + Duration::days(*d))))
+                .count();
+            let theta = result.read().unwrap().get_theta().unwrap_or(0.0);
+            result
+                .write().unwrap()
+                .set_weekend_theta(theta * holidays as Real);
+        }
+        Ok(())
+    }
+
+    /// Values all the instruments on each of the dates by shifting the evaluation date
+    /// with the parameters (and the pricers) of the engine reused. The evaluation date is restored afterwards.
+    /// The cashflows are the expected coupon amounts of the npv results on the evaluation date.
+    pub fn get_date_sweep(&mut self, dates: &[OffsetDateTime]) -> Result<DateSweepResult> {
+        let original_evaluation_date = self.evaluation_date.read().unwrap().get_date_clone();
+        let mut sweep = DateSweepResult::new(original_evaluation_date, dates.to_vec())?;
+        self.reset_instruments_in_action();
+        let all_instruments = self.instruments_in_action.clone();
+        let base_npv_results = self.get_npv_results()?;
+
+        let mut swept_npvs: FxHashMap<StaticId, Vec<Real>> = FxHashMap::default();
+        let sweep_res = (|| -> Result<()> {
+            for date in dates.iter() {
+                self.check_cancellation()?;
+                (*self.evaluation_date).write().unwrap().set_date(*date);
+                // the matured instruments are not priced
+                self.instruments_in_action = self.instruments.instruments_with_maturity_over(
+                    Some(&all_instruments),
+                    date,
+                    None,
+                );
+                let npvs = self.get_npvs()?;
+                for inst in all_instruments.iter() {
+                    let npv = npvs.get(&inst.get_id()).copied().unwrap_or(0.0);
+                    swept_npvs.entry(inst.get_id()).or_default().push(npv);
+                }
+            }
+            Ok(())
+        })();
+        (*self.evaluation_date).write().unwrap().set_date(original_evaluation_date);
+        self.reset_instruments_in_action();
+        sweep_res?;
+
+        for inst in all_instruments.iter() {
+            let inst_id = inst.get_id();
+            let unitamt = inst.get_unit_notional();
+            let base_npv_result = base_npv_results.get(&inst_id).with_context(|| {
+                anyhow!(
+                    "({}:{}) npv result is not set for {}\n{}",
+                    file!(),
+                    line!(),
+                    inst_id,
+                    self.msg_tag,
+                )
+            })?;
+            let coupons = base_npv_result.get_expected_coupon_amount()?;
+            let npvs = swept_npvs.remove(&inst_id).unwrap_or_default();
+            let values = npvs.iter().map(|npv| npv * unitamt).collect();
+            let cumulative_cashflows = dates
+                .iter()
+                .map(|date| {
+                    coupons
+                        .iter()
+                        .filter(|(payment_date, _)| {
+                            original_evaluation_date.date() < payment_date.date()
+                                && payment_date.date() <= date.date()
+                        })
+                        .map(|(_, amount)| amount)
+                        .sum::<Real>()
+                        * unitamt
+                })
+                .collect();
+            sweep.insert(
+                inst_id,
+                base_npv_result.get_npv() * unitamt,
+                npvs,
+                values,
+                cumulative_cashflows,
+            )?;
+        }
+        Ok(sweep)
+    }
+
     /// Set theta for the given instruments where the evaluation date is bumped to bumped_date.
     /// Note that the theta result is represented per day.
     /// Only self.set_theta has the inputs, given_instruments and bumped_dates.
     /// This is for handling instruments whose maturity is within the evaluation_date + theta_day.
     pub fn set_theta_for_given_instruments(
         &mut self,
-        given_instruments: Vec<Rc<Instrument>>,
+        given_instruments: Vec<Arc<Instrument>>,
         bumped_date: OffsetDateTime,
     ) -> Result<()> {
         //
         self.instruments_in_action = given_instruments;
         let time_calculator = NullCalendar::default();
-        let original_evaluation_date = self.evaluation_date.borrow().get_date_clone();
+        let original_evaluation_date = self.evaluation_date.read().unwrap().get_date_clone();
         let time_diff =
             time_calculator.get_time_difference(&original_evaluation_date, &bumped_date);
 
@@ -1603,79 +2972,460 @@ impl Engine {
             .get_npvs()
             .with_context(|| anyhow!("({}:{}) failed to get npvs", file!(), line!()))?;
 
+        // carry and roll-down of bonds and swaps on the discount curve over the theta period
+        // calculated before the evaluation date is bumped
+        let calc_decomposition = self.calculation_configuration.get_theta_decomposition_calculation();
+        let mut curve_carry_roll_downs: FxHashMap<StaticId, (Real, Real)> = FxHashMap::default();
+        if calc_decomposition {
+            for inst in self.instruments_in_action.iter() {
+                if !["Bond", "IRS"].contains(&inst.get_type_name()) {
+                    continue;
+                }
+                let curve_id = self.match_parameter.get_discount_curve_id(inst)?;
+                let curve = self.zero_curves.get(&curve_id).ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) discount curve {} is not found for {}\n{}",
+                        file!(),
+                        line!(),
+                        curve_id,
+                        inst.get_id(),
+                        self.msg_tag,
+                    )
+                })?;
+                let cashflows = self.get_discounted_cashflows(inst)?;
+                let carry = curve.read().unwrap().get_carry(&cashflows, &bumped_date)?;
+                let roll_down = curve.read().unwrap().get_roll_down(&cashflows, &bumped_date)?;
+                curve_carry_roll_downs.insert(inst.get_id(), (carry, roll_down));
+            }
+        }
+
         // limit the scope that the attribute is mutably borrowed
 
-        {
-            (*self.evaluation_date).borrow_mut().set_date(bumped_date);
+        {
+            (*self.evaluation_date).write().unwrap().set_date(bumped_date);
+        }
+
+        let npvs_theta = self
+            .get_npvs()
+            .with_context(|| anyhow!("({}:{}) failed to get npvs", file!(), line!()))?;
+
+        let continue_type = ["Stock", "Cash"];
+        for inst in self.instruments_in_action.iter() {
+            let inst_code = inst.get_id();
+            let inst_type = inst.get_type_name();
+            if continue_type.contains(&inst_type) {
+                continue;
+            };
+
+            let result = self
+                .calculation_results
+                .get(&inst_code)
+                .context("result is not set")?;
+
+            let unitamt = result
+                .read().unwrap()
+                .get_instrument_info()
+                .context("instrument_info is not set")?
+                .get_unit_notional();
+
+            let npv = npvs.get(&inst_code).context("npv is not set")?;
+
+            let npv_theta = npvs_theta.get(&inst_code).context("npv_theta is not set")?;
+
+            // deduct the cashflow inbetween
+            // the scope bound is for borrowing the result
+            {
+                let result_borrow_clone = result.read().unwrap().clone();
+                let cashflows = match result_borrow_clone.get_npv_result() {
+                    Some(npv_result) => npv_result.get_expected_coupon_amount()?,
+                    None => {
+                        return Err(anyhow!(
+                            "npv_result is not set for {} ({})",
+                            inst_code,
+                            inst_type,
+                        ))
+                    }
+                };
+
+                cash_sum = 0.0;
+                let cashflows_cloned = cashflows.clone();
+                for (date, cash) in cashflows_cloned.into_iter() {
+                    if (original_evaluation_date.date() < date.date())
+                        && (date.date() <= bumped_date.date())
+                    {
+                        cash_sum += cash;
+                        log_info!("Cashflow"; "\n### {} ({}) has a cashflow: {} at {}\n", inst_code, inst_type, cash, date);
+                    }
+                }
+            }
+
+            let theta = (npv_theta - npv + cash_sum) * unitamt / time_diff / 365.0 * THETA_PNL_UNIT;
+            {
+                result.write().unwrap().set_theta(theta);
+            }
+
+            if calc_decomposition {
+                // the cashflows are the carry of the instruments off the curves
+                let (carry, roll_down) = curve_carry_roll_downs
+                    .get(&inst_code)
+                    .copied()
+                    .unwrap_or((cash_sum, 0.0));
+                let scale = unitamt / time_diff / 365.0 * THETA_PNL_UNIT;
+                let carry = carry * scale;
+                let roll_down = roll_down * scale;
+                result.write().unwrap().set_theta_decomposition(ThetaDecomposition::new(
+                    carry,
+                    roll_down,
+                    theta - carry - roll_down,
+                ));
+            }
+        }
+        // put back
+        {
+            (*self.evaluation_date)
+                .write().unwrap()
+                .set_date(original_evaluation_date);
+        }
+
+        Ok(())
+    }
+
+    /// forward curve and past fixings of the rate index of a bond or a swap to project the floating coupons
+    fn get_forward_curve_and_past_data(
+        &self,
+        inst: &Instrument,
+    ) -> Result<(Option<Arc<RwLock<ZeroCurve>>>, PastData)> {
+        let rate_index_curve_id = self.match_parameter.get_rate_index_curve_id(inst)?;
+        let forward_curve = self.zero_curves.get(&rate_index_curve_id).cloned();
+        let past_data = match inst.get_rate_index()? {
+            Some(rate_index) => self.past_daily_close_prices.get(&rate_index.get_id()).cloned(),
+            None => None,
+        };
+        Ok((forward_curve, past_data))
+    }
+
+    /// cashflows of a bond or an IRS to be discounted by the discount curve of the instrument
+    /// In the IRS case, the fixed and floating cashflows are merged (both legs are discounted by the same curve)
+    fn get_discounted_cashflows(&self, inst: &Instrument) -> Result<FxHashMap<OffsetDateTime, Real>> {
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
+        let (forward_curve, past_data) = self.get_forward_curve_and_past_data(inst)?;
+
+        match inst {
+            Instrument::Bond(_) => inst.get_cashflows(&eval_dt, forward_curve, past_data),
+            Instrument::PlainSwap(_) => {
+                let mut res = inst.get_fixed_cashflows(&eval_dt)?;
+                let floating_cashflows = inst.get_floating_cashflows(&eval_dt, forward_curve, past_data)?;
+                for (date, amount) in floating_cashflows.into_iter() {
+                    res.entry(date)
+                        .and_modify(|e| *e += amount)
+                        .or_insert(amount);
+                }
+                Ok(res)
+            }
+            _ => Err(anyhow!(
+                "({}:{}) {} ({}) is not supported in carry and roll-down",
+                file!(),
+                line!(),
+                inst.get_code_str(),
+                inst.get_type_name()
+            )),
+        }
+    }
+
+    /// Set carry and roll-down for bonds and IRS over evaluation_date + carry_horizon_day.
+    /// The results are represented in value (considering unit_notional).
+    pub fn set_carry_roll_down(&mut self) -> Result<()> {
+        let horizon_date = self.evaluation_date.read().unwrap().get_date_clone()
+            + Duration::days(self.calculation_configuration.get_carry_horizon_day() as i64);
+
+        let insts = self.instruments.instruments_with_types(vec!["Bond", "IRS"]);
+        for inst in insts.iter() {
+            let inst_id = inst.get_id();
+            let curve_id = self.match_parameter.get_discount_curve_id(inst)?;
+            let curve = self.zero_curves.get(&curve_id).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) discount curve {} is not found for {} ({})\n{}",
+                    file!(),
+                    line!(),
+                    curve_id,
+                    inst_id,
+                    inst.get_type_name(),
+                    self.msg_tag,
+                )
+            })?;
+
+            let cashflows = self.get_discounted_cashflows(inst)?;
+            let unitamt = inst.get_unit_notional();
+            let carry = curve.read().unwrap().get_carry(&cashflows, &horizon_date)?;
+            let roll_down = curve.read().unwrap().get_roll_down(&cashflows, &horizon_date)?;
+
+            let mut result = self
+                .calculation_results
+                .get(&inst_id)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) result is not set for {}",
+                        file!(),
+                        line!(),
+                        inst_id,
+                    )
+                })?
+                .write().unwrap();
+            result.set_carry(carry * unitamt);
+            result.set_roll_down(roll_down * unitamt);
+        }
+        Ok(())
+    }
+
+    /// value changes per 1bp down of the instruments in action from the npvs with the bump of bump_val up
+    /// represented in value (considering unit_notional)
+    fn get_value_changes_per_bp_down(
+        &self,
+        npvs_up: &FxHashMap<StaticId, Real>,
+        bump_val: Real,
+    ) -> Result<FxHashMap<StaticId, Real>> {
+        let mut res = FxHashMap::default();
+        for inst in &self.instruments_in_action {
+            let inst_code = inst.get_id();
+            let npv_up = npvs_up.get(&inst_code).with_context(|| {
+                anyhow!(
+                    "({}:{}) npv_up is not set for {}\n{}",
+                    file!(),
+                    line!(),
+                    inst_code,
+                    self.msg_tag,
+                )
+            })?;
+            let npv = self
+                .calculation_results
+                .get(&inst_code)
+                .with_context(|| anyhow!("({}:{}) result is not set for {}", file!(), line!(), inst_code))?
+                .read().unwrap()
+                .get_npv_result()
+                .with_context(|| anyhow!("({}:{}) npv is not set for {}", file!(), line!(), inst_code))?
+                .get_npv();
+            res.insert(
+                inst_code,
+                -(npv_up - npv) / bump_val * RHO_PNL_UNIT * inst.get_unit_notional(),
+            );
+        }
+        Ok(res)
+    }
+
+    /// Set CS01 (and CS01 structure if configured) on the credit spread curves,
+    /// which are the spread curves layered on base curves (spread_curve_base_map in MatchParameter).
+    /// Only the spreads are bumped by cs01_bump_value, so the base curves are unchanged.
+    /// The structure bumps the spreads in the buckets (tenor[i-1] < t <= tenor[i]) of cs01_structure_tenors.
+    /// The results are the value changes for 1bp spread tightening, i.e., positive for a long credit position,
+    /// represented in value (considering unit_notional).
+    pub fn set_cs01(&mut self) -> Result<()> {
+        let calc_structure = self.calculation_configuration.get_cs01_structure_calculation();
+        let bump_val = self.calculation_configuration.get_cs01_bump_value();
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
+        let time_calculator = NullCalendar::default();
+        let calc_dates = self
+            .calculation_configuration
+            .get_cs01_structure_tenors()
+            .iter()
+            .map(|tenor| tenor.apply(&eval_dt))
+            .collect::<Vec<_>>();
+        let calc_times = calc_dates
+            .iter()
+            .map(|date| time_calculator.get_time_difference(&eval_dt, date))
+            .collect::<Vec<Time>>();
+        let exclude_type = vec!["Stock", "Cash"];
+
+        let credit_curve_ids: Vec<StaticId> = self
+            .match_parameter
+            .get_spread_curve_base_map()
+            .keys()
+            .filter(|id| self.zero_curves.contains_key(id))
+            .copied()
+            .collect();
+
+        for curve_id in credit_curve_ids {
+            self.check_cancellation()?;
+            self.instruments_in_action = self.instruments.instruments_using_curve(
+                curve_id,
+                &self.match_parameter,
+                Some(exclude_type.clone()),
+            )?;
+            if self.instruments_in_action.is_empty() {
+                continue;
+            }
+            let curve = self.zero_curves.get(&curve_id).cloned().with_context(|| {
+                anyhow!(
+                    "({}:{}) no zero curve: {}\n{}",
+                    file!(),
+                    line!(),
+                    curve_id,
+                    self.msg_tag,
+                )
+            })?;
+
+            curve.write().unwrap().bump_time_interval(None, None, bump_val)?;
+            let npvs_up = self.get_npvs();
+            // put back
+            curve.write().unwrap().bump_time_interval(None, None, -bump_val)?;
+            let npvs_up = npvs_up.context("failed to get npvs in cs01 calculation")?;
+            let cs01s = self.get_value_changes_per_bp_down(&npvs_up, bump_val)?;
+            for (inst_code, cs01) in cs01s.into_iter() {
+                (*self.calculation_results.get(&inst_code).with_context(|| {
+                    anyhow!("({}:{}) failed to get result of {}", file!(), line!(), inst_code)
+                })?)
+                .write().unwrap()
+                .set_single_cs01(curve_id, cs01);
+            }
+
+            if !calc_structure {
+                continue;
+            }
+
+            let mut single_cs01_structure: FxHashMap<StaticId, Vec<Real>> = self
+                .instruments_in_action
+                .iter()
+                .map(|inst| (inst.get_id(), vec![0.0; calc_times.len()]))
+                .collect();
+            for i in 0..calc_times.len() {
+                let bump_start = match i {
+                    0 => None,
+                    _ => Some(calc_times[i - 1]),
+                };
+                let bump_end = Some(calc_times[i]);
+                curve
+                    .write().unwrap()
+                    .bump_time_interval(bump_start, bump_end, bump_val)?;
+                let npvs_up = self.get_npvs();
+                // put back
+                curve
+                    .write().unwrap()
+                    .bump_time_interval(bump_start, bump_end, -bump_val)?;
+                let npvs_up = npvs_up.context("failed to get npvs in cs01 structure calculation")?;
+                let cs01s = self.get_value_changes_per_bp_down(&npvs_up, bump_val)?;
+                for (inst_code, cs01) in cs01s.into_iter() {
+                    single_cs01_structure
+                        .get_mut(&inst_code)
+                        .context("failed to get single_cs01_structure")?[i] = cs01;
+                }
+
+                // if there is no instrument over the calc_tenors, we do not need to calculate the next bump
+                let inst_over_bump_end = self.instruments.instruments_with_maturity_over(
+                    Some(&self.instruments_in_action),
+                    &calc_dates[i],
+                    Some(exclude_type.clone()),
+                );
+                if inst_over_bump_end.is_empty() {
+                    break;
+                }
+            }
+
+            for (inst_code, cs01_structure) in single_cs01_structure.into_iter() {
+                (*self.calculation_results.get(&inst_code).with_context(|| {
+                    anyhow!("({}:{}) failed to get result of {}", file!(), line!(), inst_code)
+                })?)
+                .write().unwrap()
+                .set_single_cs01_structure(curve_id, cs01_structure);
+            }
         }
+        Ok(())
+    }
 
-        let npvs_theta = self
-            .get_npvs()
-            .with_context(|| anyhow!("({}:{}) failed to get npvs", file!(), line!()))?;
-
-        let continue_type = ["Stock", "Cash"];
-        for inst in self.instruments_in_action.iter() {
-            let inst_code = inst.get_id();
-            let inst_type = inst.get_type_name();
-            if continue_type.contains(&inst_type) {
-                continue;
-            };
+    /// Set key-rate DV01 of bonds and swaps for each curve on key_rate_tenors.
+    /// The curve is bumped by rho_bump_value with the key-rate bump shape (triangle or flat-forward),
+    /// and the result is the value change for 1bp down, i.e., positive for a long bond,
+    /// represented in value (considering unit_notional).
+    pub fn set_key_rate_dv01(&mut self) -> Result<()> {
+        let all_curve_codes = self
+            .instruments
+            .get_all_curve_ids(&self.match_parameter)?;
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
+        let bump_scheme: KeyRateBumpScheme = self.calculation_configuration.get_key_rate_bump_scheme();
+        let key_dates = self
+            .calculation_configuration
+            .get_key_rate_tenors()
+            .iter()
+            .map(|tenor| tenor.apply(&eval_dt))
+            .collect::<Vec<_>>();
+        let time_calculator = NullCalendar::default();
+        let key_times = key_dates
+            .iter()
+            .map(|date| time_calculator.get_time_difference(&eval_dt, date))
+            .collect::<Vec<Time>>();
+        let dv01_types = ["Bond", "IRS"];
 
-            let result = self
-                .calculation_results
-                .get(&inst_code)
-                .context("result is not set")?
-                .clone();
+        for curve_code in all_curve_codes {
+            self.check_cancellation()?;
+            self.instruments_in_action = self
+                .instruments
+                .instruments_using_curve(curve_code, &self.match_parameter, None)?
+                .into_iter()
+                .filter(|inst| dv01_types.contains(&inst.get_type_name()))
+                .collect();
 
-            let unitamt = result
-                .borrow()
-                .get_instrument_info()
-                .context("instrument_info is not set")?
-                .get_unit_notional();
+            if self.instruments_in_action.is_empty() {
+                continue;
+            }
+            let bump_val = self.get_absolute_bump(RiskFactorClass::Rate, &curve_code)?;
 
-            let npv = npvs.get(&inst_code).context("npv is not set")?;
+            let mut single_key_rate_dv01: FxHashMap<StaticId, Vec<Real>> = self
+                .instruments_in_action
+                .iter()
+                .map(|inst| (inst.get_id(), vec![0.0; key_times.len()]))
+                .collect();
 
-            let npv_theta = npvs_theta.get(&inst_code).context("npv_theta is not set")?;
+            let curve = self.zero_curves.get(&curve_code).cloned().with_context(|| {
+                anyhow!(
+                    "({}:{}) no zero curve: {}\n{}",
+                    file!(),
+                    line!(),
+                    curve_code,
+                    self.msg_tag,
+                )
+            })?;
 
-            // deduct the cashflow inbetween
-            // the scope bound is for borrowing the result
-            {
-                let result_borrow_clone = result.borrow().clone();
-                let cashflows = match result_borrow_clone.get_cashflows().cloned() {
-                    Some(r) => r,
-                    None => {
-                        return Err(anyhow!(
-                            "cashflows is not set for {} ({})",
-                            inst_code,
-                            inst_type,
-                        ))
+            for i in 0..key_times.len() {
+                // the bump of the i-th key rate does not move the rates before the previous key date
+                if i > 0 {
+                    let inst_over_bump_start = self.instruments.instruments_with_maturity_over(
+                        Some(&self.instruments_in_action),
+                        &key_dates[i - 1],
+                        None,
+                    );
+                    if inst_over_bump_start.is_empty() {
+                        break;
                     }
-                };
+                }
 
-                cash_sum = 0.0;
-                let cashflows_cloned = cashflows.clone();
-                for (date, cash) in cashflows_cloned.into_iter() {
-                    if (original_evaluation_date.date() < date.date())
-                        && (date.date() <= bumped_date.date())
-                    {
-                        cash_sum += cash;
-                        flashlog::flash_info!("Cashflow"; "\n### {} ({}) has a cashflow: {} at {}\n", inst_code, inst_type, cash, date);
-                    }
+                curve
+                    .write().unwrap()
+                    .bump_key_rate(&key_times, i, bump_scheme, bump_val)?;
+                let npvs_up = self.get_npvs();
+                // put back
+                curve
+                    .write().unwrap()
+                    .bump_key_rate(&key_times, i, bump_scheme, -bump_val)?;
+                let npvs_up = npvs_up.context("failed to get npvs in key-rate dv01 calculation")?;
+                let dv01s = self.get_value_changes_per_bp_down(&npvs_up, bump_val)?;
+                for (inst_code, dv01) in dv01s.into_iter() {
+                    single_key_rate_dv01
+                        .get_mut(&inst_code)
+                        .context("failed to get single_key_rate_dv01")?[i] = dv01;
                 }
             }
 
-            let theta = (npv_theta - npv + cash_sum) * unitamt / time_diff / 365.0 * THETA_PNL_UNIT;
-            {
-                result.borrow_mut().set_theta(theta);
+            for (inst_code, key_rate_dv01) in single_key_rate_dv01.into_iter() {
+                (*self.calculation_results.get(&inst_code).with_context(|| {
+                    anyhow!(
+                        "({}:{}) failed to get result of {}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                    )
+                })?)
+                .write().unwrap()
+                .set_single_key_rate_dv01(curve_code, key_rate_dv01);
             }
         }
-        // put back
-        {
-            (*self.evaluation_date)
-                .borrow_mut()
-                .set_date(original_evaluation_date);
-        }
-
         Ok(())
     }
 
@@ -1683,8 +3433,7 @@ impl Engine {
         let all_curve_codes = self
             .instruments
             .get_all_curve_ids(&self.match_parameter)?;
-        let eval_dt = self.evaluation_date.borrow().get_date_clone();
-        let bump_val = self.calculation_configuration.get_rho_bump_value();
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
         let calc_tenors = self.calculation_configuration.get_rho_structure_tenors();
         let tenor_length = calc_tenors.len();
         let time_calculator = NullCalendar::default();
@@ -1708,6 +3457,7 @@ impl Engine {
         let exclude_type_clone = exclude_type.clone();
 
         for curve_code in all_curve_codes {
+            self.check_cancellation()?;
             self.instruments_in_action = self.instruments.instruments_using_curve(
                 curve_code,
                 &self.match_parameter,
@@ -1717,6 +3467,7 @@ impl Engine {
             if self.instruments_in_action.is_empty() {
                 continue;
             }
+            let bump_val = self.get_absolute_bump(RiskFactorClass::Rate, &curve_code)?;
 
             let inst_codes_in_action = self
                 .instruments
@@ -1725,7 +3476,7 @@ impl Engine {
                 vec![vec![0.0; tenor_length]; inst_codes_in_action.len()];
             single_rho_structure = inst_codes_in_action
                 .into_iter()
-                .zip(init_vec.into_iter())
+                .zip(init_vec)
                 .collect();
             // bump zero_curve by bump_date_interval where calc_dates[i] < date <= calc_dates[i+1]
             for i in 0..calc_times.len() {
@@ -1746,7 +3497,7 @@ impl Engine {
                         )
                     })?)
                     .as_ref()
-                    .borrow_mut()
+                    .write().unwrap()
                     .bump_time_interval(bump_start, bump_end, bump_val)?;
                 }
                 //
@@ -1761,7 +3512,7 @@ impl Engine {
                         .calculation_results
                         .get(&inst_code)
                         .context("failed to get npv in rho-structure calculation")?
-                        .borrow()
+                        .read().unwrap()
                         .get_npv_result()
                         .context("failed to get npv_result in rho-structure calculation")?
                         .get_npv();
@@ -1783,7 +3534,7 @@ impl Engine {
                         )
                     })?)
                     .as_ref()
-                    .borrow_mut()
+                    .write().unwrap()
                     .bump_time_interval(bump_start, bump_end, -bump_val)?;
                 }
 
@@ -1807,23 +3558,138 @@ impl Engine {
                         inst_code,
                     )
                 })?)
-                .borrow_mut()
+                .write().unwrap()
                 .set_single_rho_structure(curve_code, rho_structure.clone());
             }
         }
         Ok(())
     }
 
+    /// Bucketed gamma on rho_structure_tenors: the central second difference on the bump h of each bucket,
+    /// (V(+h) - 2V + V(-h)) / h^2, in the unit of the rho change on 1bp.
+    /// h is gamma_structure_bump_value in CalculationConfiguration.
+    /// The cross terms between the buckets are not calculated.
+    pub fn set_gamma_structure(&mut self) -> Result<()> {
+        let all_curve_codes = self
+            .instruments
+            .get_all_curve_ids(&self.match_parameter)?;
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
+        let time_calculator = NullCalendar::default();
+        let calc_dates = self
+            .calculation_configuration
+            .get_rho_structure_tenors()
+            .iter()
+            .map(|tenor| tenor.apply(&eval_dt))
+            .collect::<Vec<_>>();
+        let calc_times = calc_dates
+            .iter()
+            .map(|date| time_calculator.get_time_difference(&eval_dt, date))
+            .collect::<Vec<Time>>();
+        let exclude_type = vec!["Stock", "Cash"];
+        let bump_val = self
+            .calculation_configuration
+            .get_gamma_structure_bump_value();
+
+        for curve_code in all_curve_codes {
+            self.check_cancellation()?;
+            self.instruments_in_action = self.instruments.instruments_using_curve(
+                curve_code,
+                &self.match_parameter,
+                Some(exclude_type.clone()),
+            )?;
+            if self.instruments_in_action.is_empty() {
+                continue;
+            }
+            let curve = self.zero_curves.get(&curve_code).cloned().with_context(|| {
+                anyhow!(
+                    "({}:{}) no zero curve: {}\n{}",
+                    file!(),
+                    line!(),
+                    curve_code,
+                    self.msg_tag,
+                )
+            })?;
+
+            let mut single_gamma_structure: FxHashMap<StaticId, Vec<Real>> = self
+                .instruments_in_action
+                .iter()
+                .map(|inst| (inst.get_id(), vec![0.0; calc_times.len()]))
+                .collect();
+            for i in 0..calc_times.len() {
+                let bump_start = match i {
+                    0 => None,
+                    _ => Some(calc_times[i - 1]),
+                };
+                let bump_end = Some(calc_times[i]);
+                let (npvs_up, npvs_down) =
+                    self.get_bumped_npvs(FiniteDifferenceScheme::Central, |sign| {
+                        curve
+                            .write().unwrap()
+                            .bump_time_interval(bump_start, bump_end, sign * bump_val)
+                    })?;
+
+                for inst in &self.instruments_in_action {
+                    let inst_code = inst.get_id();
+                    let npv = self
+                        .calculation_results
+                        .get(&inst_code)
+                        .context("failed to get npv in gamma-structure calculation")?
+                        .read().unwrap()
+                        .get_npv_result()
+                        .context("failed to get npv_result in gamma-structure calculation")?
+                        .get_npv();
+                    let npv_up = npvs_up
+                        .get(&inst_code)
+                        .context("failed to get npv_up in gamma-structure calculation")?;
+                    let npv_down = npvs_down
+                        .get(&inst_code)
+                        .context("failed to get npv_down in gamma-structure calculation")?;
+
+                    single_gamma_structure
+                        .get_mut(&inst_code)
+                        .context("failed to get single_gamma_structure")?[i] =
+                        (npv_up - 2.0 * npv + npv_down) / (bump_val * bump_val)
+                            * RHO_PNL_UNIT
+                            * RHO_PNL_UNIT
+                            * inst.get_unit_notional();
+                }
+
+                // if there is no instrument over the calc_tenors, we do not need to calculate the next bump
+                let inst_over_bump_end = self.instruments.instruments_with_maturity_over(
+                    Some(&self.instruments_in_action),
+                    &calc_dates[i],
+                    Some(exclude_type.clone()),
+                );
+                if inst_over_bump_end.is_empty() {
+                    break;
+                }
+            }
+
+            for (inst_code, gamma_structure) in single_gamma_structure.into_iter() {
+                (*self.calculation_results.get(&inst_code).with_context(|| {
+                    anyhow!(
+                        "({}:{}) failed to get result of {}",
+                        file!(),
+                        line!(),
+                        inst_code,
+                    )
+                })?)
+                .write().unwrap()
+                .set_single_gamma_structure(curve_code, gamma_structure);
+            }
+        }
+        Ok(())
+    }
+
     pub fn set_div_structure(&mut self) -> Result<()> {
         //let all_dividend_codes = self.instruments.get_all_underlying_ids();
         let all_dividend_codes = self.dividends.keys().collect::<Vec<&StaticId>>();
-        let bump_val = self.calculation_configuration.get_div_bump_value();
         let calc_tenors = self.calculation_configuration.get_div_structure_tenors();
         let tenor_length = calc_tenors.len();
         let calc_dates = calc_tenors
             .iter()
             .map(|tenor| {
-                tenor.apply(self.evaluation_date.borrow().get_date())  
+                tenor.apply(self.evaluation_date.read().unwrap().get_date())  
             })
             .collect::<Vec<_>>();
 
@@ -1838,6 +3704,7 @@ impl Engine {
         let exclude_type_clone = exclude_type.clone();
 
         for div_code in all_dividend_codes {
+            self.check_cancellation()?;
             // reset instruments
             self.instruments_in_action = self
                 .instruments
@@ -1846,6 +3713,7 @@ impl Engine {
             if self.instruments_in_action.is_empty() {
                 continue;
             }
+            let bump_val = self.get_dividend_bump(div_code)?;
             // initialize the single_div_structure. insert the inst code and zero vector
             let inst_codes_in_action = self
                 .instruments
@@ -1855,7 +3723,7 @@ impl Engine {
                 vec![vec![0.0; tenor_length]; inst_codes_in_action.len()];
             single_div_structure = inst_codes_in_action
                 .into_iter()
-                .zip(init_vec.into_iter())
+                .zip(init_vec)
                 .collect();
 
             // bump dividend by bump_date_interval where calc_dates[i] < date <= calc_dates[i+1]
@@ -1867,7 +3735,7 @@ impl Engine {
                 let bump_end = Some(&calc_dates[i]);
                 {
                     if let Some(div) = self.dividends.get(div_code) {
-                        div.clone().unwrap().borrow_mut().bump_date_interval(bump_start, bump_end, bump_val)?;
+                        div.clone().unwrap().write().unwrap().bump_date_interval(bump_start, bump_end, bump_val)?;
                     }
                 }
 
@@ -1879,7 +3747,7 @@ impl Engine {
                     npv_up = *npvs_up.get(&inst_code).context("failed to get npv_up in div-structure calculation")?;
                     npv = self.calculation_results
                         .get(&inst_code).context("failed to get npv in div-structure calculation")?
-                        .borrow()
+                        .read().unwrap()
                         .get_npv_result().context("failed to get npv_result in div-structure calculation")?
                         .get_npv();
 
@@ -1891,7 +3759,7 @@ impl Engine {
 
                 {
                     if let Some(div) = self.dividends.get(div_code) {
-                        div.clone().unwrap().borrow_mut().bump_date_interval(bump_start, bump_end, -bump_val)?;
+                        div.clone().unwrap().write().unwrap().bump_date_interval(bump_start, bump_end, -bump_val)?;
                     }
                 }
 
@@ -1910,7 +3778,7 @@ impl Engine {
                 self.calculation_results
                     .get(inst_code)
                     .context("failed to get result")?
-                    .borrow_mut()
+                    .write().unwrap()
                     .set_single_div_structure(*div_code, div_structure.clone());
             }
         }
@@ -1918,210 +3786,471 @@ impl Engine {
     }
 
     pub fn calculate(&mut self) -> Result<()> {
-        let mut timer = flashlog::get_unix_nano();
-        let start_time = flashlog::get_unix_nano();
+        let mut timer = get_unix_nano();
+        let start_time = get_unix_nano();
 
         if !self.instruments_in_action.is_empty() {
             let id = self.engine_id;
             
-            flashlog::flash_warn!("NoInst"; " * instruments to calculate in engine-{}\n", id);
+            log_warn!("NoInst"; " * instruments to calculate in engine-{}\n", id);
         }
 
         if self.calculation_configuration.get_npv_calculation() {
+            self.check_cancellation()?;
             self.set_npv_results()?;
             self.set_values()?;
             self.set_cashflow_inbetween()?;
 
             let eng_id = self.engine_id;
-            let elapsed_sec1 = format_duration((flashlog::get_unix_nano() - timer) as f64 / 1_000_000_000.0);
-            let elapsed_sec2 = format_duration((flashlog::get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
             
-            flashlog::flash_info!("Timer"; "* npv calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            log_info!("Timer"; "* npv calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("npv", timer);
         }
 
         if self.calculation_configuration.get_fx_exposure_calculation() {
-            timer = flashlog::get_unix_nano();
+            self.check_cancellation()?;
+            timer = get_unix_nano();
             self.set_fx_exposures()?;
             let eng_id = self.engine_id;
-            let elapsed_sec1 = format_duration((flashlog::get_unix_nano() - timer) as f64 / 1_000_000_000.0);
-            let elapsed_sec2 = format_duration((flashlog::get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
             
-            flashlog::flash_info!("Timer"; "* fx exposure calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            log_info!("Timer"; "* fx exposure calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("fx exposure", timer);
+        }
+
+        if self.calculation_configuration.get_bond_analytics_calculation() {
+            self.check_cancellation()?;
+            timer = get_unix_nano();
+            self.set_bond_analytics()?;
+            let eng_id = self.engine_id;
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+
+            log_info!("Timer"; "* bond analytics calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("bond analytics", timer);
+        }
+
+        if self.calculation_configuration.get_bid_ask_adjustment_calculation() {
+            self.check_cancellation()?;
+            timer = get_unix_nano();
+            self.set_bid_ask_adjustment()?;
+            let eng_id = self.engine_id;
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+
+            log_info!("Timer"; "* bid-ask adjustment calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("bid-ask adjustment", timer);
+        }
+
+        // before the bumps so that the greeks given by automatic differentiation are not bumped
+        if self.calculation_configuration.get_greek_method() == GreekMethod::AutomaticDifferentiation
+            && (self.calculation_configuration.get_delta_calculation()
+                || self.calculation_configuration.get_vega_calculation()
+                || self.calculation_configuration.get_rho_calculation())
+        {
+            self.check_cancellation()?;
+            timer = get_unix_nano();
+            self.set_first_order_greeks_by_ad()?;
+            let eng_id = self.engine_id;
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+
+            log_info!("Timer"; "* automatic differentiation of greeks is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("automatic differentiation", timer);
         }
 
         if self.calculation_configuration.get_delta_calculation() {
-            timer = flashlog::get_unix_nano();
+            self.check_cancellation()?;
+            timer = get_unix_nano();
             self.preprocess_delta_gamma()?;
             self.set_delta_gamma()?;
+            self.set_smoothed_delta_gamma()?;
 
             let eng_id = self.engine_id;
-            let elapsed_sec1 = format_duration((flashlog::get_unix_nano() - timer) as f64 / 1_000_000_000.0);
-            let elapsed_sec2 = format_duration((flashlog::get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
             
-            flashlog::flash_info!("Timer"; "* delta calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            log_info!("Timer"; "* delta calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("delta", timer);
+        }
+
+        if self.calculation_configuration.get_fx_delta_calculation() {
+            self.check_cancellation()?;
+            timer = get_unix_nano();
+            self.set_fx_delta_gamma()?;
+
+            let eng_id = self.engine_id;
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+
+            log_info!("Timer"; "* fx delta calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("fx delta", timer);
         }
 
         if self.calculation_configuration.get_theta_calculation() {
-            timer = flashlog::get_unix_nano();
+            self.check_cancellation()?;
+            timer = get_unix_nano();
             let exclude_type = vec!["Cash", "Stock"];
             let exclude_type_clone = exclude_type.clone();
             self.preprocess_theta(exclude_type_clone.clone())?;
-            // we separate instruments by
-            // 1) instruments whose maturity is within the evaluation_date + theta_day
-            // 2) instruments whose maturity is not within the evaluation_date + theta_day
-            let bumped_day = self.evaluation_date.borrow().get_date_clone()
-                + Duration::days(self.calculation_configuration.get_theta_day() as i64);
-
-            let insts_upto_bumped_day = self.instruments.instruments_with_maturity_upto(
-                None,
-                &bumped_day,
-                Some(exclude_type.clone()),
-            );
-
-            let insts_over_bumped_day = self.instruments.instruments_with_maturity_over(
-                None,
-                &bumped_day,
-                Some(exclude_type),
-            );
-
-            if !insts_upto_bumped_day.is_empty() {
-                let shortest_maturity = self
-                    .instruments
-                    .get_shortest_maturity(Some(&insts_upto_bumped_day))
-                    .unwrap();
+            for (bumped_day, theta_instruments) in self.get_theta_dates(exclude_type.clone()) {
+                // we separate instruments by
+                // 1) instruments whose maturity is within the evaluation_date + theta_day
+                // 2) instruments whose maturity is not within the evaluation_date + theta_day
+                let (insts_upto_bumped_day, insts_over_bumped_day) = self.split_by_theta_expiry(
+                    theta_instruments.as_ref(),
+                    &bumped_day,
+                    &exclude_type,
+                );
 
-                let mut name_mat_pair_list = String::new();
-                for inst in insts_upto_bumped_day.iter() {
-                    name_mat_pair_list.push_str(&format!(
-                        "{} ({}): {}\n",
-                        inst.get_name(),
-                        inst.get_code_str(),
-                        inst.get_maturity().unwrap()
-                    ));
-                }
-                let msg_tag = self.msg_tag.clone();
+                if !insts_upto_bumped_day.is_empty() {
+                    let shortest_maturity = insts_upto_bumped_day
+                        .iter()
+                        .filter_map(|inst| self.get_expiry(inst))
+                        .min()
+                        .unwrap();
+
+                    let mut name_mat_pair_list = String::new();
+                    for inst in insts_upto_bumped_day.iter() {
+                        name_mat_pair_list.push_str(&format!(
+                            "{} ({}): {}\n",
+                            inst.get_name(),
+                            inst.get_code_str(),
+                            self.get_expiry(inst).unwrap()
+                        ));
+                    }
+                    let msg_tag = self.msg_tag.clone();
                 
-                flashlog::flash_warn!(
-                    "UnstableTheta";
-                    "{}\n\
-                        (Engine::calculate -> theta calculation)\n\
-                        There are instruments whose maturity is within the evaluation_date + theta_day (= {:?}) \n\
-                        \n\
-                        The instruments are as follows:\n\
-                        {}\n\
-                        For the theta calculation for the above instruments, \n\
-                        the evaluation date is bumped to {:?} which is the shortest maturity of the above instruments. \n\
-                        Note that the theta calculation period may be too small to get accurate theta.\n",
-                        msg_tag.as_str(),
-                        &bumped_day,
-                        name_mat_pair_list,
-                        &shortest_maturity,
-                    );
-                self.set_theta_for_given_instruments(insts_upto_bumped_day, shortest_maturity)?;
-            }
+                    log_warn!(
+                        "UnstableTheta";
+                        "{}\n\
+                            (Engine::calculate -> theta calculation)\n\
+                            There are instruments whose maturity is within the evaluation_date + theta_day (= {:?}) \n\
+                            \n\
+                            The instruments are as follows:\n\
+                            {}\n\
+                            For the theta calculation for the above instruments, \n\
+                            the evaluation date is bumped to {:?} which is the shortest maturity of the above instruments. \n\
+                            Note that the theta calculation period may be too small to get accurate theta.\n",
+                            msg_tag.as_str(),
+                            &bumped_day,
+                            name_mat_pair_list,
+                            &shortest_maturity,
+                        );
+                    self.set_theta_for_given_instruments(insts_upto_bumped_day.clone(), shortest_maturity)?;
+                    self.set_theta_period(&insts_upto_bumped_day, shortest_maturity)?;
+                }
 
-            if !insts_over_bumped_day.is_empty() {
-                self.set_theta_for_given_instruments(insts_over_bumped_day, bumped_day)?;
+                if !insts_over_bumped_day.is_empty() {
+                    self.set_theta_for_given_instruments(insts_over_bumped_day.clone(), bumped_day)?;
+                    self.set_theta_period(&insts_over_bumped_day, bumped_day)?;
+                }
             }
             let eng_id = self.engine_id;
-            let elapsed_sec1 = format_duration((flashlog::get_unix_nano() - timer) as f64 / 1_000_000_000.0);
-            let elapsed_sec2 = format_duration((flashlog::get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
             
-            flashlog::flash_info!("Timer"; "* theta calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            log_info!("Timer"; "* theta calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("theta", timer);
+        }
+
+        if self.calculation_configuration.get_carry_roll_down_calculation() {
+            self.check_cancellation()?;
+            timer = get_unix_nano();
+            self.set_carry_roll_down()?;
+            let eng_id = self.engine_id;
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+
+            log_info!("Timer"; "* carry and roll-down calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("carry and roll-down", timer);
+        }
+
+        if self.calculation_configuration.get_key_rate_dv01_calculation() {
+            self.check_cancellation()?;
+            timer = get_unix_nano();
+            self.set_key_rate_dv01()?;
+            let eng_id = self.engine_id;
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+
+            log_info!("Timer"; "* key-rate dv01 calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("key-rate dv01", timer);
+        }
+
+        if self.calculation_configuration.get_cs01_calculation() {
+            self.check_cancellation()?;
+            timer = get_unix_nano();
+            self.set_cs01()?;
+            let eng_id = self.engine_id;
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+
+            log_info!("Timer"; "* cs01 calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("cs01", timer);
         }
 
         if self.calculation_configuration.get_vega_calculation() {
-            timer = flashlog::get_unix_nano();
+            self.check_cancellation()?;
+            timer = get_unix_nano();
             self.set_vega()?;
             let eng_id = self.engine_id;
-            let elapsed_sec1 = format_duration((flashlog::get_unix_nano() - timer) as f64 / 1_000_000_000.0);
-            let elapsed_sec2 = format_duration((flashlog::get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
             
-            flashlog::flash_info!("Timer"; "* vega calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            log_info!("Timer"; "* vega calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("vega", timer);
         }
 
         if self.calculation_configuration.get_rho_calculation() {
-            timer = flashlog::get_unix_nano();
+            self.check_cancellation()?;
+            timer = get_unix_nano();
             self.set_rho()?;
             let eng_id = self.engine_id;
-            let elapsed_sec1 = format_duration((flashlog::get_unix_nano() - timer) as f64 / 1_000_000_000.0);
-            let elapsed_sec2 = format_duration((flashlog::get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
             
-            flashlog::flash_info!("Timer"; "* rho calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            log_info!("Timer"; "* rho calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("rho", timer);
+        }
+
+        if self.calculation_configuration.get_rho_by_role_calculation() {
+            self.check_cancellation()?;
+            timer = get_unix_nano();
+            self.set_rho_by_role()?;
+
+            let eng_id = self.engine_id;
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+
+            log_info!("Timer"; "* rho by curve role calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("rho by curve role", timer);
+        }
+
+        if self.calculation_configuration.get_delta_rho_calculation() {
+            self.check_cancellation()?;
+            timer = get_unix_nano();
+            self.set_delta_rho()?;
+
+            let eng_id = self.engine_id;
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+
+            log_info!("Timer"; "* delta-rho calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("delta-rho", timer);
+        }
+
+        if self.calculation_configuration.get_ladder_calculation() {
+            self.check_cancellation()?;
+            timer = get_unix_nano();
+            self.set_ladder()?;
+
+            let eng_id = self.engine_id;
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+
+            log_info!("Timer"; "* ladder calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("ladder", timer);
         }
 
         if self.calculation_configuration.get_div_delta_calculation() {
-            timer = flashlog::get_unix_nano();
+            self.check_cancellation()?;
+            timer = get_unix_nano();
             self.set_div_delta()?;
             let eng_id = self.engine_id;
-            let elapsed_sec1 = format_duration((flashlog::get_unix_nano() - timer) as f64 / 1_000_000_000.0);
-            let elapsed_sec2 = format_duration((flashlog::get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
             
-            flashlog::flash_info!("Timer"; "* div_delta calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            log_info!("Timer"; "* div_delta calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("div delta", timer);
+        }
+
+        if self.calculation_configuration.get_div_carry_calculation() {
+            self.check_cancellation()?;
+            timer = get_unix_nano();
+            self.set_div_carry()?;
+            let eng_id = self.engine_id;
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+
+            log_info!("Timer"; "* div_carry calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("div carry", timer);
         }
 
         if self
             .calculation_configuration
             .get_vega_structure_calculation()
         {
-            timer = flashlog::get_unix_nano();
+            self.check_cancellation()?;
+            timer = get_unix_nano();
             self.set_vega_structure()?;
             let eng_id = self.engine_id;
-            let elapsed_sec1 = format_duration((flashlog::get_unix_nano() - timer) as f64 / 1_000_000_000.0);
-            let elapsed_sec2 = format_duration((flashlog::get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
             
-            flashlog::flash_info!("Timer"; "* vega-structure calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            log_info!("Timer"; "* vega-structure calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("vega-structure", timer);
         }
 
         if self
             .calculation_configuration
             .get_rho_structure_calculation()
         {
-            timer = flashlog::get_unix_nano();
+            self.check_cancellation()?;
+            timer = get_unix_nano();
             self.set_rho_structure()?;
             let eng_id = self.engine_id;
-            let elapsed_sec1 = format_duration((flashlog::get_unix_nano() - timer) as f64 / 1_000_000_000.0);
-            let elapsed_sec2 = format_duration((flashlog::get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
             
-            flashlog::flash_info!("Timer"; "* rho-structure calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            log_info!("Timer"; "* rho-structure calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("rho-structure", timer);
+        }
+
+        if self
+            .calculation_configuration
+            .get_gamma_structure_calculation()
+        {
+            self.check_cancellation()?;
+            timer = get_unix_nano();
+            self.set_gamma_structure()?;
+            let eng_id = self.engine_id;
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+
+            log_info!("Timer"; "* gamma-structure calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("gamma-structure", timer);
         }
 
         if self
             .calculation_configuration
             .get_div_structure_calculation()
         {
-            timer = flashlog::get_unix_nano();
+            self.check_cancellation()?;
+            timer = get_unix_nano();
             self.set_div_structure()?;
             let eng_id = self.engine_id;
-            let elapsed_sec1 = format_duration((flashlog::get_unix_nano() - timer) as f64 / 1_000_000_000.0);
-            let elapsed_sec2 = format_duration((flashlog::get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
-            flashlog::flash_info!(
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+            log_info!(
                 "Timer";
                 "* div-structure calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", 
                 eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("div-structure", timer);
         }
 
         if self.calculation_configuration.get_vega_matrix_calculation() {
-            timer = flashlog::get_unix_nano();
+            self.check_cancellation()?;
+            timer = get_unix_nano();
             self.set_vega_matrix()?;
             let eng_id = self.engine_id;
-            let elapsed_sec1 = format_duration((flashlog::get_unix_nano() - timer) as f64 / 1_000_000_000.0);
-            let elapsed_sec2 = format_duration((flashlog::get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+            let elapsed_sec1 = format_duration((get_unix_nano() - timer) as f64 / 1_000_000_000.0);
+            let elapsed_sec2 = format_duration((get_unix_nano() - start_time) as f64 / 1_000_000_000.0);
+
+            log_info!("Timer"; "* vega-matrix calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+            self.report_stage("vega-matrix", timer);
+        }
+
+        if let Some(reporting_currency) = self.calculation_configuration.get_reporting_currency() {
+            self.set_reporting_results(reporting_currency)?;
+        }
+        Ok(())
+    }
 
-            flashlog::flash_info!("Timer"; "* vega-matrix calculation is done (engine id: {}, time = {} sec whole time elapsed: {})\n", eng_id, elapsed_sec1, elapsed_sec2);
+    /// copies of the results in the reporting currency of the configuration
+    pub fn set_reporting_results(&mut self, reporting_currency: Currency) -> Result<()> {
+        for (code, result) in self.calculation_results.iter() {
+            let currency = result.read().unwrap().get_representation_currency().ok_or_else(|| {
+                anyhow!("({}:{}) representation currency is not set for {}", file!(), line!(), code)
+            })?;
+            let fx_rate = *self.reporting_fx_rates.get(&currency).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) no fx rate from {} to the reporting currency {} for {}",
+                    file!(),
+                    line!(),
+                    currency,
+                    reporting_currency,
+                    code,
+                )
+            })?;
+            let reporting_result = result
+                .read().unwrap()
+                .representation_currency_conversion(reporting_currency, fx_rate)?;
+            result.write().unwrap().set_reporting_result(reporting_result);
         }
         Ok(())
     }
 
-    pub fn get_calculation_result(&self) -> &FxHashMap<StaticId, RefCell<CalculationResult>> {
+    /// (phase, elapsed seconds) in the order of the phases,
+    /// which is empty unless the statistics collection is on in the configuration
+    pub fn get_phase_timings(&self) -> &Vec<(&'static str, Real)> {
+        &self.phase_timings
+    }
+
+    pub fn get_calculation_result(&self) -> &FxHashMap<StaticId, RwLock<CalculationResult>> {
         &self.calculation_results
     }
 
     pub fn get_calculation_result_clone(&self) -> FxHashMap<StaticId, CalculationResult> {
         let mut result = FxHashMap::default();
         for (key, value) in self.calculation_results.iter() {
-            result.insert(*key, value.borrow().clone());
+            result.insert(*key, value.read().unwrap().clone());
         }
         result
     }
 }
+
+/// first order finite difference of the values on the up and down bumps by the scheme
+fn finite_difference(
+    scheme: FiniteDifferenceScheme,
+    up: Option<Real>,
+    mid: Real,
+    down: Option<Real>,
+    bump: Real,
+) -> Result<Real> {
+    let err = || {
+        anyhow!(
+            "({}:{}) the bumped value is not set for the {:?} difference",
+            file!(),
+            line!(),
+            scheme
+        )
+    };
+    match scheme {
+        FiniteDifferenceScheme::Forward => Ok((up.ok_or_else(err)? - mid) / bump),
+        FiniteDifferenceScheme::Backward => Ok((mid - down.ok_or_else(err)?) / bump),
+        FiniteDifferenceScheme::Central => {
+            Ok((up.ok_or_else(err)? - down.ok_or_else(err)?) / (2.0 * bump))
+        }
+    }
+}
+
+/// mid rate of the fx code from the fx data: itself, its reciprocal, or the cross over KRW
+/// as the fx rates for pricing in Engine::with_parameter_data
+fn get_fx_rate_from_data(fx_data: &FxHashMap<FxCode, ValueData>, fx_code: FxCode) -> Result<Real> {
+    if fx_code.get_currency1() == fx_code.get_currency2() {
+        return Ok(1.0);
+    }
+    if let Some(data) = fx_data.get(&fx_code) {
+        return Ok(data.get_value());
+    }
+    if let Some(data) = fx_data.get(&fx_code.reciprocal()) {
+        return Ok(1.0 / data.get_value());
+    }
+    let krw1 = fx_data.get(&FxCode::new(fx_code.get_currency1(), Currency::KRW));
+    let krw2 = fx_data.get(&FxCode::new(fx_code.get_currency2(), Currency::KRW));
+    if let (Some(data1), Some(data2)) = (krw1, krw2) {
+        return Ok(data1.get_value() / data2.get_value());
+    }
+    bail!(
+        "({}:{}) failed to get fx data for {}.\n\
+         fx_data must have either of itself, reciprocal,\n\
+         or both Curr1/KRW and Curr2/KRW",
+        file!(),
+        line!(),
+        fx_code
+    )
+}
@@ -3,20 +3,36 @@ use crate::data::{
     daily_value_data::DailyValueData, surface_data::SurfaceData, value_data::ValueData,
     vector_data::VectorData,
 };
+use crate::definitions::Real;
 use crate::evaluation_date::EvaluationDate;
 use crate::instrument::{Instrument, InstrumentTrait, Instruments};
+use crate::limits::{LimitChecker, LimitReport};
+use crate::position::Positions;
 use crate::pricing_engines::{
     calculation_configuration::CalculationConfiguration, calculation_result::CalculationResult,
-    engine::Engine, match_parameter::MatchParameter,
+    calculation_statistics::{CalculationStatistics, GroupStatistics},
+    cancellation::CancellationToken,
+    cost_model::CostModel,
+    date_sweep::DateSweepResult,
+    engine::Engine,
+    greek_diagnostics::{DiagnosticsReport, GreekDiagnostics},
+    match_parameter::MatchParameter,
+    progress::{ProgressCallback, ProgressEvent},
 };
+#[cfg(feature = "async")]
+use crate::pricing_engines::async_calculation::GroupCalculation;
+use crate::utils::platform::get_unix_nano;
 //
 use anyhow::{anyhow, Result};
+#[cfg(not(target_arch = "wasm32"))]
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use time::OffsetDateTime;
 use static_id::static_id::StaticId;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct InstrumentCategory {
@@ -69,15 +85,118 @@ impl InstrumentCategory {
     }
 }
 
+/// A single market data to replace in a built EngineGenerator.
+/// Only the instruments using the data are recalculated by EngineGenerator::update_data.
+#[derive(Clone)]
+pub enum MarketDataUpdate {
+    Fx(FxCode, ValueData),
+    Stock(StaticId, ValueData),
+    Curve(StaticId, VectorData),
+    Dividend(StaticId, VectorData),
+    EquityConstantVolatility(StaticId, ValueData),
+    EquityVolatilitySurface(StaticId, SurfaceData),
+    FxConstantVolatility(FxCode, ValueData),
+    QuantoCorrelation((StaticId, FxCode), ValueData),
+}
+
+/// The market data of a MarketDataUpdate without the data,
+/// e.g., to subscribe to a MarketDataSource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MarketDataKey {
+    Fx(FxCode),
+    Stock(StaticId),
+    Curve(StaticId),
+    Dividend(StaticId),
+    EquityConstantVolatility(StaticId),
+    EquityVolatilitySurface(StaticId),
+    FxConstantVolatility(FxCode),
+    QuantoCorrelation((StaticId, FxCode)),
+}
+
+impl MarketDataUpdate {
+    pub fn get_key(&self) -> MarketDataKey {
+        match self {
+            MarketDataUpdate::Fx(fx_code, _) => MarketDataKey::Fx(*fx_code),
+            MarketDataUpdate::Stock(id, _) => MarketDataKey::Stock(*id),
+            MarketDataUpdate::Curve(id, _) => MarketDataKey::Curve(*id),
+            MarketDataUpdate::Dividend(id, _) => MarketDataKey::Dividend(*id),
+            MarketDataUpdate::EquityConstantVolatility(id, _) => {
+                MarketDataKey::EquityConstantVolatility(*id)
+            }
+            MarketDataUpdate::EquityVolatilitySurface(id, _) => {
+                MarketDataKey::EquityVolatilitySurface(*id)
+            }
+            MarketDataUpdate::FxConstantVolatility(fx_code, _) => {
+                MarketDataKey::FxConstantVolatility(*fx_code)
+            }
+            MarketDataUpdate::QuantoCorrelation(pair, _) => MarketDataKey::QuantoCorrelation(*pair),
+        }
+    }
+
+    /// whether the instrument is priced with the data.
+    /// Curves are matched by MatchParameter including the base curves of spread curves.
+    pub fn is_used_by(
+        &self,
+        instrument: &Arc<Instrument>,
+        match_parameter: &MatchParameter,
+    ) -> Result<bool> {
+        let res = match self {
+            MarketDataUpdate::Fx(fx_code, _) => {
+                let inverse = FxCode::new(fx_code.get_currency2(), fx_code.get_currency1());
+                instrument
+                    .get_all_fxcodes_for_pricing()
+                    .iter()
+                    .chain(instrument.get_quanto_fxcode_und_pair().iter().map(|(_, fx)| fx))
+                    .any(|code| *code == *fx_code || *code == inverse)
+            }
+            MarketDataUpdate::Stock(id, _) | MarketDataUpdate::Dividend(id, _) => {
+                instrument.get_underlying_ids().contains(id)
+            }
+            MarketDataUpdate::Curve(id, _) => Instruments::new(vec![instrument.clone()])
+                .get_all_curve_ids(match_parameter)?
+                .contains(id),
+            MarketDataUpdate::EquityConstantVolatility(id, _)
+            | MarketDataUpdate::EquityVolatilitySurface(id, _) => instrument
+                .get_underlying_ids_requiring_volatility()
+                .contains(id),
+            MarketDataUpdate::FxConstantVolatility(fx_code, _) => instrument
+                .get_quanto_fxcode_und_pair()
+                .iter()
+                .any(|(_, code)| code == fx_code),
+            MarketDataUpdate::QuantoCorrelation(pair, _) => {
+                instrument.get_quanto_fxcode_und_pair().contains(pair)
+            }
+        };
+        Ok(res)
+    }
+}
+
 pub struct EngineGenerator {
     instruments: Instruments,
-    instrument_group_vec: Vec<Vec<Instrument>>,
+    positions: Positions,
+    instrument_group_vec: Vec<Vec<Arc<Instrument>>>,
     instrument_categories: Vec<InstrumentCategory>,
     //
     calculation_configuration: CalculationConfiguration,
     match_parameter: MatchParameter,
     //
     calculation_results: FxHashMap<StaticId, CalculationResult>,
+    position_results: FxHashMap<StaticId, FxHashMap<StaticId, CalculationResult>>,
+    limit_checker: Option<LimitChecker>,
+    limit_report: Option<LimitReport>,
+    diagnostics: Option<GreekDiagnostics>,
+    diagnostics_report: Option<DiagnosticsReport>,
+    calculation_statistics: Option<CalculationStatistics>,
+    // balances the groups in distribute_instruments
+    cost_model: Option<CostModel>,
+    cancellation_token: Option<CancellationToken>,
+    progress_callback: Option<ProgressCallback>,
+    // warm start: calculate() recalculates only the stale instruments
+    // unless a full recalculation is required by the invalidation rules (see with_warm_start)
+    warm_start: bool,
+    full_recalculation: bool,
+    stale_ids: FxHashSet<StaticId>,
+    recalculated_ids: Vec<StaticId>,
     // evaluation date
     evaluation_date: EvaluationDate,
     // data
@@ -90,12 +209,103 @@ pub struct EngineGenerator {
     fx_constant_volatility_data: Arc<FxHashMap<FxCode, ValueData>>,
     quanto_correlation_data: Arc<FxHashMap<(StaticId, FxCode), ValueData>>,
     past_daily_value_data: Arc<FxHashMap<StaticId, DailyValueData>>,
+    dividend_payment_date_data: Arc<FxHashMap<StaticId, Vec<OffsetDateTime>>>,
+}
+
+// everything an engine of a group needs, cloned from the generator so that
+// the groups can be calculated away from it (calculate_async)
+struct GroupInputs {
+    calculation_configuration: CalculationConfiguration,
+    evaluation_offsetdatetime: OffsetDateTime,
+    match_parameter: MatchParameter,
+    fx_data: Arc<FxHashMap<FxCode, ValueData>>,
+    stock_data: Arc<FxHashMap<StaticId, ValueData>>,
+    curve_data: Arc<FxHashMap<StaticId, VectorData>>,
+    dividend_data: Arc<FxHashMap<StaticId, VectorData>>,
+    equity_constant_volatility_data: Arc<FxHashMap<StaticId, ValueData>>,
+    equity_volatility_surface_data: Arc<FxHashMap<StaticId, SurfaceData>>,
+    fx_constant_volatility_data: Arc<FxHashMap<FxCode, ValueData>>,
+    quanto_correlation_data: Arc<FxHashMap<(StaticId, FxCode), ValueData>>,
+    past_daily_value_data: Arc<FxHashMap<StaticId, DailyValueData>>,
+    dividend_payment_date_data: Arc<FxHashMap<StaticId, Vec<OffsetDateTime>>>,
+    cancellation_token: Option<CancellationToken>,
+    progress_callback: Option<ProgressCallback>,
+}
+
+impl GroupInputs {
+    fn build_engine(
+        &self,
+        group_id: usize,
+        instrument_group: Vec<Arc<Instrument>>,
+        cancellation_token: Option<CancellationToken>,
+    ) -> Result<Engine> {
+        let mut engine = Engine::builder(
+            group_id,
+            self.calculation_configuration.clone(),
+            self.evaluation_offsetdatetime,
+            self.match_parameter.clone(),
+        );
+        if let Some(token) = cancellation_token.or_else(|| self.cancellation_token.clone()) {
+            token.check()?;
+            engine = engine.with_cancellation_token(token);
+        }
+        if let Some(progress_callback) = &self.progress_callback {
+            engine = engine.with_progress_callback(progress_callback.clone());
+        }
+
+        let mut engine = engine
+            .with_shared_instruments(instrument_group)?
+            .with_parameter_data(
+                self.fx_data.clone(),
+                self.stock_data.clone(),
+                self.curve_data.clone(),
+                self.dividend_data.clone(),
+                self.equity_constant_volatility_data.clone(),
+                self.equity_volatility_surface_data.clone(),
+                self.fx_constant_volatility_data.clone(),
+                self.quanto_correlation_data.clone(),
+                self.past_daily_value_data.clone(),
+            )?
+            .with_dividend_payment_dates(self.dividend_payment_date_data.clone())?;
+        engine.initialize_pricers()?;
+        Ok(engine)
+    }
+
+    fn calculate(
+        &self,
+        group_id: usize,
+        instrument_group: Vec<Arc<Instrument>>,
+        cancellation_token: Option<CancellationToken>,
+    ) -> Result<(FxHashMap<StaticId, CalculationResult>, GroupStatistics)> {
+        let timer = get_unix_nano();
+        let instrument_number = instrument_group.len();
+        let mut engine = self.build_engine(group_id, instrument_group, cancellation_token)?;
+        engine.calculate()?;
+
+        let results = engine
+            .get_calculation_result()
+            .iter()
+            .map(|(key, value)| (*key, value.read().unwrap().clone()))
+            .collect();
+        let statistics = GroupStatistics::new(
+            group_id,
+            instrument_number,
+            (get_unix_nano() - timer) as Real / 1_000_000_000.0,
+            engine
+                .get_phase_timings()
+                .iter()
+                .map(|(phase, elapsed)| (phase.to_string(), *elapsed))
+                .collect(),
+        );
+        Ok((results, statistics))
+    }
 }
 
 impl Default for EngineGenerator {
     fn default() -> Self {
         EngineGenerator {
             instruments: Instruments::default(),
+            positions: Positions::default(),
             instrument_group_vec: vec![],
             instrument_categories: vec![],
             //
@@ -103,6 +313,20 @@ impl Default for EngineGenerator {
             match_parameter: MatchParameter::default(),
             //
             calculation_results: FxHashMap::default(),
+            position_results: FxHashMap::default(),
+            limit_checker: None,
+            limit_report: None,
+            diagnostics: None,
+            diagnostics_report: None,
+            calculation_statistics: None,
+            cost_model: None,
+            cancellation_token: None,
+            progress_callback: None,
+            //
+            warm_start: false,
+            full_recalculation: true,
+            stale_ids: FxHashSet::default(),
+            recalculated_ids: vec![],
             //
             evaluation_date: EvaluationDate::default(),
             //
@@ -115,6 +339,7 @@ impl Default for EngineGenerator {
             fx_constant_volatility_data: Arc::new(FxHashMap::default()),
             quanto_correlation_data: Arc::new(FxHashMap::default()),
             past_daily_value_data: Arc::new(FxHashMap::default()),
+            dividend_payment_date_data: Arc::new(FxHashMap::default()),
         }
     }
 }
@@ -133,11 +358,61 @@ impl EngineGenerator {
         self.calculation_configuration = calculation_configuration;
         self.evaluation_date = EvaluationDate::new(evalutation_datetime);
         self.match_parameter = match_parameter;
+        self.full_recalculation = true;
         Ok(self)
     }
 
     pub fn with_instruments(&mut self, instruments: Instruments) -> Result<&mut Self> {
         self.instruments = instruments;
+        self.full_recalculation = true;
+        Ok(self)
+    }
+
+    /// the instruments in the positions are priced,
+    /// and the results are also given on the quantities of the positions in each book
+    pub fn with_positions(&mut self, positions: Positions) -> Result<&mut Self> {
+        self.instruments = positions.get_instruments();
+        self.positions = positions;
+        self.full_recalculation = true;
+        Ok(self)
+    }
+
+    /// the limits are checked on the position results after each calculation
+    pub fn with_limit_checker(&mut self, limit_checker: LimitChecker) -> Result<&mut Self> {
+        self.limit_checker = Some(limit_checker);
+        Ok(self)
+    }
+
+    /// the greeks are checked by the diagnostics after each calculation
+    pub fn with_diagnostics(&mut self, diagnostics: GreekDiagnostics) -> Result<&mut Self> {
+        self.diagnostics = Some(diagnostics);
+        Ok(self)
+    }
+
+    /// The groups of the categories are split by the expected cost in distribute_instruments (CostModel::balance),
+    /// so it must be given before distribute_instruments. The model records the times measured in calculate,
+    /// which can be kept by get_cost_model for the next run.
+    pub fn with_cost_model(&mut self, cost_model: CostModel) -> Result<&mut Self> {
+        self.cost_model = Some(cost_model);
+        Ok(self)
+    }
+
+    /// The engines stop at the next check after the token is cancelled (Engine::with_cancellation_token),
+    /// and calculate returns the error. The results of the previous calculation are kept.
+    pub fn with_cancellation_token(
+        &mut self,
+        cancellation_token: CancellationToken,
+    ) -> Result<&mut Self> {
+        self.cancellation_token = Some(cancellation_token);
+        Ok(self)
+    }
+
+    /// The callback is called with the ProgressEvent of the groups from the threads calculating them
+    pub fn with_progress_callback<F>(&mut self, progress_callback: F) -> Result<&mut Self>
+    where
+        F: Fn(&ProgressEvent) + Send + Sync + 'static,
+    {
+        self.progress_callback = Some(Arc::new(progress_callback));
         Ok(self)
     }
 
@@ -146,6 +421,7 @@ impl EngineGenerator {
         instrument_categories: Vec<InstrumentCategory>,
     ) -> Result<&mut Self> {
         self.instrument_categories = instrument_categories;
+        self.full_recalculation = true;
         Ok(self)
     }
 
@@ -162,6 +438,41 @@ impl EngineGenerator {
         quanto_correlation_data: FxHashMap<(StaticId, FxCode), ValueData>,
         past_daily_value_data: FxHashMap<StaticId, DailyValueData>,
     ) -> Result<&mut Self> {
+        if self.is_warm() {
+            let mut changes = get_changed_data(&self.fx_data, &fx_data, MarketDataUpdate::Fx);
+            changes.extend(get_changed_data(&self.stock_data, &stock_data, MarketDataUpdate::Stock));
+            changes.extend(get_changed_data(&self.curve_data, &curve_data, MarketDataUpdate::Curve));
+            changes.extend(get_changed_data(
+                &self.dividend_data,
+                &dividend_data,
+                MarketDataUpdate::Dividend,
+            ));
+            changes.extend(get_changed_data(
+                &self.equity_constant_volatility_data,
+                &equity_constant_volatility_data,
+                MarketDataUpdate::EquityConstantVolatility,
+            ));
+            changes.extend(get_changed_data(
+                &self.equity_volatility_surface_data,
+                &equity_volatility_surface_data,
+                MarketDataUpdate::EquityVolatilitySurface,
+            ));
+            changes.extend(get_changed_data(
+                &self.fx_constant_volatility_data,
+                &fx_constant_volatility_data,
+                MarketDataUpdate::FxConstantVolatility,
+            ));
+            changes.extend(get_changed_data(
+                &self.quanto_correlation_data,
+                &quanto_correlation_data,
+                MarketDataUpdate::QuantoCorrelation,
+            ));
+            // the fixings are not matched to the instruments
+            if *self.past_daily_value_data != past_daily_value_data {
+                self.full_recalculation = true;
+            }
+            self.invalidate(&changes)?;
+        }
         self.fx_data = Arc::new(fx_data);
         self.stock_data = Arc::new(stock_data);
         self.curve_data = Arc::new(curve_data);
@@ -174,15 +485,25 @@ impl EngineGenerator {
         Ok(self)
     }
 
+    /// payment dates matched to the ex-dividend dates of dividend_data
+    pub fn with_dividend_payment_dates(
+        &mut self,
+        dividend_payment_date_data: FxHashMap<StaticId, Vec<OffsetDateTime>>,
+    ) -> Result<&mut Self> {
+        self.dividend_payment_date_data = Arc::new(dividend_payment_date_data);
+        self.full_recalculation = true;
+        Ok(self)
+    }
+
     pub fn distribute_instruments(&mut self) -> Result<()> {
         let mut distribution_checker: Vec<bool> = vec![false; self.instruments.len()];
 
-        let mut instrument_group_vec: Vec<Vec<Instrument>> = vec![];
+        let mut instrument_group_vec: Vec<Vec<Arc<Instrument>>> = vec![];
         for instrument_category in &self.instrument_categories {
-            let mut instrument_group: Vec<Instrument> = vec![];
+            let mut instrument_group: Vec<Arc<Instrument>> = vec![];
             for (inst_id, instrument) in self.instruments.iter().enumerate() {
                 if !distribution_checker[inst_id] && instrument_category.contains(instrument)? {
-                    instrument_group.push(instrument.as_ref().clone());
+                    instrument_group.push(instrument.clone());
                     distribution_checker[inst_id] = true;
                 }
             }
@@ -217,72 +538,434 @@ impl EngineGenerator {
             ));
         }
 
+        if let Some(cost_model) = &self.cost_model {
+            instrument_group_vec = cost_model.balance(instrument_group_vec);
+        }
         self.instrument_group_vec = instrument_group_vec;
+        self.full_recalculation = true;
+
+        Ok(())
+    }
+
+    /// Warm start keeps the results of the previous calculate() and recalculates only the instruments
+    /// using the data changed by with_data since then. The invalidation rules are
+    /// - with_configuration, with_instruments, with_positions, with_instrument_categories,
+    ///   distribute_instruments, and with_dividend_payment_dates require a full recalculation
+    /// - in with_data, an added, removed, or changed data invalidates the instruments using it
+    ///   (MarketDataUpdate::is_used_by), and a change of the past daily values requires a full recalculation
+    /// - update_data recalculates the instruments at once, so it does not leave stale instruments
+    pub fn with_warm_start(&mut self, warm_start: bool) -> Result<&mut Self> {
+        self.warm_start = warm_start;
+        Ok(self)
+    }
 
+    fn is_warm(&self) -> bool {
+        self.warm_start && !self.full_recalculation && !self.calculation_results.is_empty()
+    }
+
+    fn invalidate(&mut self, changes: &[MarketDataUpdate]) -> Result<()> {
+        for instrument in self.instruments.iter() {
+            for change in changes.iter() {
+                if change.is_used_by(instrument, &self.match_parameter)? {
+                    self.stale_ids.insert(instrument.get_id());
+                    break;
+                }
+            }
+        }
         Ok(())
     }
 
+    /// the instruments of the ids in their groups
+    fn get_groups_of(&self, ids: &FxHashSet<StaticId>) -> Vec<Vec<Arc<Instrument>>> {
+        self.instrument_group_vec
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .filter(|inst| ids.contains(&inst.get_id()))
+                    .cloned()
+                    .collect::<Vec<Arc<Instrument>>>()
+            })
+            .filter(|group| !group.is_empty())
+            .collect()
+    }
+
     /// spawn threads to create engine and calculate
     pub fn calculate(&mut self) -> Result<()> {
-        let shared_results = Arc::new(Mutex::new(FxHashMap::<StaticId, CalculationResult>::default()));
-        let dt = self.evaluation_date.get_date_clone();
-        let calc_res: Result<()> = self
-            .instrument_group_vec
-            .par_iter()
-            .enumerate()
-            .map(|(group_id, instrument_group)| {
-                let engine = Engine::builder(
-                    group_id,
-                    self.calculation_configuration.clone(),
-                    dt,
-                    self.match_parameter.clone(),
-                );
+        let mut recalculated_ids: Vec<StaticId>;
+        if self.is_warm() {
+            let stale_groups = self.get_groups_of(&self.stale_ids);
+            let results = self.calculate_groups(stale_groups)?;
+            self.calculation_results.extend(results);
+            recalculated_ids = self.stale_ids.iter().copied().collect();
+        } else {
+            let results = self.calculate_groups(self.instrument_group_vec.clone());
+            self.calculation_results = results?;
+            recalculated_ids = self.calculation_results.keys().copied().collect();
+        }
+        recalculated_ids.sort_by_key(|id| id.to_string());
+        self.recalculated_ids = recalculated_ids;
+        self.full_recalculation = false;
+        self.stale_ids.clear();
+        self.set_position_results()?;
+        self.set_diagnostics_report()
+    }
+
+    /// Replaces the data and recalculates only the instruments using it (MarketDataUpdate::is_used_by)
+    /// in their groups. The other results are kept. It returns the ids of the recalculated instruments.
+    pub fn update_data(&mut self, update: MarketDataUpdate) -> Result<Vec<StaticId>> {
+        if self.calculation_results.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) the data can be updated after calculate",
+                file!(),
+                line!()
+            ));
+        }
+
+        let mut updated_ids = Vec::<StaticId>::new();
+        for instrument in self.instruments.iter() {
+            if update.is_used_by(instrument, &self.match_parameter)? {
+                updated_ids.push(instrument.get_id());
+            }
+        }
+
+        match update {
+            MarketDataUpdate::Fx(fx_code, data) => {
+                Arc::make_mut(&mut self.fx_data).insert(fx_code, data);
+            }
+            MarketDataUpdate::Stock(id, data) => {
+                Arc::make_mut(&mut self.stock_data).insert(id, data);
+            }
+            MarketDataUpdate::Curve(id, data) => {
+                Arc::make_mut(&mut self.curve_data).insert(id, data);
+            }
+            MarketDataUpdate::Dividend(id, data) => {
+                Arc::make_mut(&mut self.dividend_data).insert(id, data);
+            }
+            MarketDataUpdate::EquityConstantVolatility(id, data) => {
+                Arc::make_mut(&mut self.equity_constant_volatility_data).insert(id, data);
+            }
+            MarketDataUpdate::EquityVolatilitySurface(id, data) => {
+                Arc::make_mut(&mut self.equity_volatility_surface_data).insert(id, data);
+            }
+            MarketDataUpdate::FxConstantVolatility(fx_code, data) => {
+                Arc::make_mut(&mut self.fx_constant_volatility_data).insert(fx_code, data);
+            }
+            MarketDataUpdate::QuantoCorrelation(pair, data) => {
+                Arc::make_mut(&mut self.quanto_correlation_data).insert(pair, data);
+            }
+        }
+
+        let updated_groups = self.get_groups_of(&updated_ids.iter().copied().collect());
+        let results = self.calculate_groups(updated_groups)?;
+        self.calculation_results.extend(results);
+        self.set_position_results()?;
+        self.set_diagnostics_report()?;
+
+        updated_ids.sort_by_key(|id| id.to_string());
+        self.recalculated_ids = updated_ids.clone();
+        Ok(updated_ids)
+    }
+
+    /// the keys of the market data given to the generator, which can be updated by update_data
+    pub fn get_market_data_keys(&self) -> Vec<MarketDataKey> {
+        self.fx_data
+            .keys()
+            .map(|fx_code| MarketDataKey::Fx(*fx_code))
+            .chain(self.stock_data.keys().map(|id| MarketDataKey::Stock(*id)))
+            .chain(self.curve_data.keys().map(|id| MarketDataKey::Curve(*id)))
+            .chain(self.dividend_data.keys().map(|id| MarketDataKey::Dividend(*id)))
+            .chain(
+                self.equity_constant_volatility_data
+                    .keys()
+                    .map(|id| MarketDataKey::EquityConstantVolatility(*id)),
+            )
+            .chain(
+                self.equity_volatility_surface_data
+                    .keys()
+                    .map(|id| MarketDataKey::EquityVolatilitySurface(*id)),
+            )
+            .chain(
+                self.fx_constant_volatility_data
+                    .keys()
+                    .map(|fx_code| MarketDataKey::FxConstantVolatility(*fx_code)),
+            )
+            .chain(
+                self.quanto_correlation_data
+                    .keys()
+                    .map(|pair| MarketDataKey::QuantoCorrelation(*pair)),
+            )
+            .collect()
+    }
 
-                let engine = match engine.with_instruments(instrument_group.clone()) {
-                    Ok(engine) => engine,
-                    Err(e) => return Err(e),
-                };
-
-                let mut engine = match engine.with_parameter_data(
-                    self.fx_data.clone(),
-                    self.stock_data.clone(),
-                    self.curve_data.clone(),
-                    self.dividend_data.clone(),
-                    self.equity_constant_volatility_data.clone(),
-                    self.equity_volatility_surface_data.clone(),
-                    self.fx_constant_volatility_data.clone(),
-                    self.quanto_correlation_data.clone(),
-                    self.past_daily_value_data.clone(),
-                ) {
-                    Ok(engine) => engine,
-                    Err(e) => return Err(e),
-                };
-
-                engine.initialize_pricers()?;
-                engine.calculate()?;
-
-                let result = engine.get_calculation_result();
-                let mut mut_res = shared_results.lock().unwrap();
-
-                for (key, value) in result.iter() {
-                    mut_res.insert(*key, value.borrow().clone());
+    fn calculate_groups(
+        &mut self,
+        instrument_groups: Vec<Vec<Arc<Instrument>>>,
+    ) -> Result<FxHashMap<StaticId, CalculationResult>> {
+        let group_inputs = self.get_group_inputs();
+        let progress_callback = self.progress_callback.clone();
+        let instrument_number: usize = instrument_groups.iter().map(|group| group.len()).sum();
+        let finished_instrument_number = AtomicUsize::new(0);
+        if let Some(callback) = &progress_callback {
+            callback(&ProgressEvent::Started {
+                group_number: instrument_groups.len(),
+                instrument_number,
+            });
+        }
+        // the groups are calculated sequentially on wasm32
+        #[cfg(not(target_arch = "wasm32"))]
+        let groups = instrument_groups.par_iter();
+        #[cfg(target_arch = "wasm32")]
+        let groups = instrument_groups.iter();
+        let timer = get_unix_nano();
+        let group_results: Result<Vec<(FxHashMap<StaticId, CalculationResult>, GroupStatistics)>> =
+            groups
+                .enumerate()
+                .map(|(group_id, instrument_group)| {
+                    let results = group_inputs.calculate(group_id, instrument_group.clone(), None)?;
+                    if let Some(callback) = &progress_callback {
+                        let finished = finished_instrument_number
+                            .fetch_add(instrument_group.len(), Ordering::SeqCst)
+                            + instrument_group.len();
+                        callback(&ProgressEvent::GroupFinished {
+                            group_id,
+                            instrument_ids: instrument_group
+                                .iter()
+                                .map(|inst| inst.get_id())
+                                .collect(),
+                            finished_instrument_number: finished,
+                            instrument_number,
+                        });
+                    }
+                    Ok(results)
+                })
+                .collect();
+        let elapsed = (get_unix_nano() - timer) as Real / 1_000_000_000.0;
+
+        let mut results = FxHashMap::<StaticId, CalculationResult>::default();
+        let mut group_statistics = Vec::<GroupStatistics>::with_capacity(instrument_groups.len());
+        for ((group_result, statistics), instrument_group) in
+            group_results?.into_iter().zip(instrument_groups.iter())
+        {
+            // the time of a group is shared equally by its instruments
+            if let Some(cost_model) = self.cost_model.as_mut() {
+                let elapsed = statistics.get_elapsed() / instrument_group.len().max(1) as Real;
+                for instrument in instrument_group.iter() {
+                    cost_model.record_timing(instrument, elapsed);
                 }
+            }
+            results.extend(group_result);
+            group_statistics.push(statistics);
+        }
+        if self.calculation_configuration.get_statistics_collection() {
+            self.calculation_statistics = Some(CalculationStatistics::new(elapsed, group_statistics));
+        }
+        Ok(results)
+    }
 
-                Ok(())
+    /// Values the instruments over the evaluation dates (Engine::get_date_sweep) in the engines of the groups,
+    /// e.g., for theta profiles and aging analysis. The calculation results of the generator are not changed.
+    pub fn get_date_sweep(&self, dates: &[OffsetDateTime]) -> Result<DateSweepResult> {
+        let group_inputs = self.get_group_inputs();
+        #[cfg(not(target_arch = "wasm32"))]
+        let groups = self.instrument_group_vec.par_iter();
+        #[cfg(target_arch = "wasm32")]
+        let groups = self.instrument_group_vec.iter();
+        let group_sweeps: Result<Vec<DateSweepResult>> = groups
+            .enumerate()
+            .map(|(group_id, instrument_group)| {
+                let mut engine = group_inputs.build_engine(group_id, instrument_group.clone(), None)?;
+                engine.get_date_sweep(dates)
             })
             .collect();
 
-        //self.calculation_results = shared_results.lock().unwrap().clone();
-        self.calculation_results
-            .clone_from(&shared_results.lock().unwrap());
+        let mut sweep = DateSweepResult::new(self.evaluation_date.get_date_clone(), dates.to_vec())?;
+        for group_sweep in group_sweeps? {
+            sweep.extend(group_sweep)?;
+        }
+        Ok(sweep)
+    }
 
-        match calc_res {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e),
+    // the engines share the instruments and the market data with the generator
+    fn get_group_inputs(&self) -> GroupInputs {
+        GroupInputs {
+            calculation_configuration: self.calculation_configuration.clone(),
+            evaluation_offsetdatetime: self.evaluation_date.get_date_clone(),
+            match_parameter: self.match_parameter.clone(),
+            fx_data: self.fx_data.clone(),
+            stock_data: self.stock_data.clone(),
+            curve_data: self.curve_data.clone(),
+            dividend_data: self.dividend_data.clone(),
+            equity_constant_volatility_data: self.equity_constant_volatility_data.clone(),
+            equity_volatility_surface_data: self.equity_volatility_surface_data.clone(),
+            fx_constant_volatility_data: self.fx_constant_volatility_data.clone(),
+            quanto_correlation_data: self.quanto_correlation_data.clone(),
+            past_daily_value_data: self.past_daily_value_data.clone(),
+            dividend_payment_date_data: self.dividend_payment_date_data.clone(),
+            cancellation_token: self.cancellation_token.clone(),
+            progress_callback: self.progress_callback.clone(),
         }
     }
 
+    /// Starts the calculation of each instrument group on the rayon thread pool
+    /// (on the calling thread on wasm32), and returns a future per group, e.g., for a server to time-box a risk run with a timeout.
+    /// The groups stop at the next check of the engine after the token is cancelled.
+    /// The token replaces that of with_cancellation_token.
+    /// The results are not stored in the generator until they are given to set_calculation_results.
+    #[cfg(feature = "async")]
+    pub fn calculate_async(&self, cancellation_token: &CancellationToken) -> Vec<GroupCalculation> {
+        let group_inputs = Arc::new(self.get_group_inputs());
+        self.instrument_group_vec
+            .iter()
+            .enumerate()
+            .map(|(group_id, instrument_group)| {
+                let group_inputs = group_inputs.clone();
+                let instrument_group = instrument_group.clone();
+                let cancellation_token = cancellation_token.clone();
+                GroupCalculation::spawn(group_id, move || {
+                    group_inputs
+                        .calculate(group_id, instrument_group, Some(cancellation_token))
+                        .map(|(results, _)| results)
+                })
+            })
+            .collect()
+    }
+
+    /// Stores the results of the groups from calculate_async, and updates the position results,
+    /// the limit report and the diagnostics report.
+    #[cfg(feature = "async")]
+    pub fn set_calculation_results(
+        &mut self,
+        group_results: Vec<FxHashMap<StaticId, CalculationResult>>,
+    ) -> Result<()> {
+        let mut results = FxHashMap::<StaticId, CalculationResult>::default();
+        for group_result in group_results {
+            results.extend(group_result);
+        }
+        let mut recalculated_ids: Vec<StaticId> = results.keys().copied().collect();
+        recalculated_ids.sort_by_key(|id| id.to_string());
+        self.calculation_results = results;
+        self.recalculated_ids = recalculated_ids;
+        self.full_recalculation = false;
+        self.stale_ids.clear();
+        self.set_position_results()?;
+        self.set_diagnostics_report()
+    }
+
+    fn set_position_results(&mut self) -> Result<()> {
+        if !self.positions.is_empty() {
+            self.position_results = self
+                .positions
+                .get_position_results(&self.calculation_results)?;
+        }
+        if let Some(limit_checker) = &self.limit_checker {
+            let fx_map: FxHashMap<FxCode, Real> = self
+                .fx_data
+                .iter()
+                .map(|(code, data)| (*code, data.get_value()))
+                .collect();
+            let curve_currencies: FxHashMap<StaticId, Currency> = self
+                .curve_data
+                .iter()
+                .map(|(id, data)| (*id, data.currency))
+                .collect();
+            self.limit_report = Some(limit_checker.check(
+                &self.positions,
+                &self.calculation_results,
+                &fx_map,
+                &curve_currencies,
+            )?);
+        }
+        Ok(())
+    }
+
+    fn set_diagnostics_report(&mut self) -> Result<()> {
+        if let Some(diagnostics) = &self.diagnostics {
+            self.diagnostics_report = Some(diagnostics.run(
+                &self.evaluation_date.get_date_clone(),
+                &self.instruments,
+                &self.calculation_results,
+                &self.match_parameter,
+                &self.curve_data,
+                &self.equity_constant_volatility_data,
+                &self.equity_volatility_surface_data,
+            )?);
+        }
+        Ok(())
+    }
+
+    pub fn get_cost_model(&self) -> Option<&CostModel> {
+        self.cost_model.as_ref()
+    }
+
+    /// Timing of the groups calculated by the last calculate or update_data
+    /// (only the recalculated groups in a warm start or an update),
+    /// None unless the statistics collection is on in the configuration
+    pub fn get_calculation_statistics(&self) -> Option<&CalculationStatistics> {
+        self.calculation_statistics.as_ref()
+    }
+
+    pub fn get_instrument_groups(&self) -> &Vec<Vec<Arc<Instrument>>> {
+        &self.instrument_group_vec
+    }
+
+    /// ids of the instruments calculated by the last calculate or update_data
+    pub fn get_recalculated_ids(&self) -> &Vec<StaticId> {
+        &self.recalculated_ids
+    }
+
     pub fn get_calculation_results(&self) -> &FxHashMap<StaticId, CalculationResult> {
         &self.calculation_results
     }
+
+    /// the results in the order of the instrument ids,
+    /// which does not depend on the groups or the recalculations that gave them
+    pub fn get_sorted_calculation_results(&self) -> Vec<(&StaticId, &CalculationResult)> {
+        let mut results: Vec<(&StaticId, &CalculationResult)> =
+            self.calculation_results.iter().collect();
+        results.sort_by_key(|(id, _)| id.to_string());
+        results
+    }
+
+    /// book -> instrument id -> result on the quantity of the position, empty without positions
+    pub fn get_position_results(
+        &self,
+    ) -> &FxHashMap<StaticId, FxHashMap<StaticId, CalculationResult>> {
+        &self.position_results
+    }
+
+    /// None without a limit checker
+    pub fn get_limit_report(&self) -> Option<&LimitReport> {
+        self.limit_report.as_ref()
+    }
+
+    /// None without diagnostics
+    pub fn get_diagnostics_report(&self) -> Option<&DiagnosticsReport> {
+        self.diagnostics_report.as_ref()
+    }
+}
+
+/// updates of the data added, removed, or changed from old to new.
+/// A removed data is given with the old data as it is matched to the instruments only by the key.
+fn get_changed_data<K, V>(
+    old: &FxHashMap<K, V>,
+    new: &FxHashMap<K, V>,
+    to_update: fn(K, V) -> MarketDataUpdate,
+) -> Vec<MarketDataUpdate>
+where
+    K: Eq + Hash + Copy,
+    V: PartialEq + Clone,
+{
+    let mut res = vec![];
+    for (key, data) in new.iter() {
+        if old.get(key) != Some(data) {
+            res.push(to_update(*key, data.clone()));
+        }
+    }
+    for (key, data) in old.iter() {
+        if !new.contains_key(key) {
+            res.push(to_update(*key, data.clone()));
+        }
+    }
+    res
 }
@@ -2,66 +2,50 @@ use crate::currency::Currency;
 use crate::definitions::Real;
 use crate::instrument::Instrument;
 use crate::instrument::InstrumentTrait;
+use crate::parameters::equity_forward_curve::EquityForwardCurve;
 use crate::parameters::market_price::MarketPrice;
 use crate::parameters::zero_curve::ZeroCurve;
 use crate::pricing_engines::npv_result::NpvResult;
 use crate::pricing_engines::pricer::PricerTrait;
 //
 use anyhow::{anyhow, Context, Result};
-use std::{cell::RefCell, rc::Rc};
+use std::sync::{Arc, RwLock};
 use time::OffsetDateTime;
 use rustc_hash::FxHashMap;
 
 #[derive(Debug, Clone)]
 pub struct FuturesPricer {
-    // evaluation_date: Rc<RefCell<EvaluationDate>>, not used
-    market_price: Rc<RefCell<MarketPrice>>,
-    collateral_curve: Rc<RefCell<ZeroCurve>>, // if you use implied dividend, this will be risk-free rate (or you can think of it as benchmark rate)
-    borrowing_curve: Rc<RefCell<ZeroCurve>>,  // or repo
+    // evaluation_date: Arc<RwLock<EvaluationDate>>, not used
+    // collateral_curve: if you use implied dividend, this will be risk-free rate (or you can think of it as benchmark rate)
+    // borrowing_curve: or repo
+    forward_curve: EquityForwardCurve,
 }
 
 impl FuturesPricer {
     pub fn new(
-        //evaluation_date: Rc<RefCell<EvaluationDate>>,
-        market_price: Rc<RefCell<MarketPrice>>,
-        collateral_curve: Rc<RefCell<ZeroCurve>>,
-        borrowing_curve: Rc<RefCell<ZeroCurve>>,
+        //evaluation_date: Arc<RwLock<EvaluationDate>>,
+        market_price: Arc<RwLock<MarketPrice>>,
+        collateral_curve: Arc<RwLock<ZeroCurve>>,
+        borrowing_curve: Arc<RwLock<ZeroCurve>>,
     ) -> FuturesPricer {
         FuturesPricer {
             //evaluation_date,
-            market_price,
-            collateral_curve,
-            borrowing_curve,
+            forward_curve: EquityForwardCurve::new(market_price, collateral_curve, borrowing_curve),
         }
     }
 
-    pub fn fair_forward(&self, datetime: &OffsetDateTime) -> Result<Real> {
-        let market_price_price = self.market_price.borrow().get_value();
-        let collateral_discount = self
-            .collateral_curve
-            .borrow()
-            .get_discount_factor_at_date(datetime)
-            .context(
-                "(FuturesPricer::fair_forward) failed to get collateral discount factor at date",
-            )?;
-        let borrowing_discount = self
-            .borrowing_curve
-            .borrow()
-            .get_discount_factor_at_date(datetime)
-            .context(
-                "(FuturesPricer::fair_forward) failed to get borrowing discount factor at date",
-            )?;
-        let dividend_deduction_ratio = self
-            .market_price
-            .borrow()
-            .get_dividend_deduction_ratio(datetime)
-            .context(
-                "(FuturesPricer::fair_forward) failed to get dividend deduction ratio at date",
-            )?;
+    pub fn new_from_forward_curve(forward_curve: EquityForwardCurve) -> FuturesPricer {
+        FuturesPricer { forward_curve }
+    }
 
-        let fwd: Real = market_price_price * borrowing_discount / collateral_discount
-            * dividend_deduction_ratio;
-        Ok(fwd)
+    pub fn get_forward_curve(&self) -> &EquityForwardCurve {
+        &self.forward_curve
+    }
+
+    pub fn fair_forward(&self, datetime: &OffsetDateTime) -> Result<Real> {
+        self.forward_curve
+            .get_forward(datetime)
+            .context("(FuturesPricer::fair_forward) failed to get forward from the equity forward curve")
     }
 }
 
@@ -137,7 +121,7 @@ mod tests {
     #[test]
     fn test_futures_engine() -> Result<()> {
         let market_datetime = datetime!(2024-01-02 00:00:00 +09:00);
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(market_datetime.clone())));
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(market_datetime)));
 
         let spot: Real = 350.0;
         let name = "KOSPI2";
@@ -166,10 +150,10 @@ mod tests {
         .expect("failed to make a discrete ratio dividend");
 
         // make a equity
-        let equity = Rc::new(RefCell::new(MarketPrice::new(
+        let equity = Arc::new(RwLock::new(MarketPrice::new(
             spot,
-            market_datetime.clone(),
-            Some(Rc::new(RefCell::new(dividend))),
+            market_datetime,
+            Some(Arc::new(RwLock::new(dividend))),
             Currency::KRW,
             name.to_string(),
             StaticId::from_str(name, "KRX"),
@@ -189,7 +173,7 @@ mod tests {
             StaticId::from_str("KSD", "NIL"),
         ).expect("failed to make a vector data for KSD curve");
 
-        let ksd_curve = Rc::new(RefCell::new(
+        let ksd_curve = Arc::new(RwLock::new(
             ZeroCurve::new(
                 evaluation_date.clone(),
                 &ksd_data,
@@ -201,7 +185,7 @@ mod tests {
 
         //ksd_data.add_observer(ksd_curve.clone());
 
-        let dummy_curve = Rc::new(RefCell::new(
+        let dummy_curve = Arc::new(RwLock::new(
             ZeroCurve::dummy_curve().expect("failed to make a dummy curve"),
         ));
 
@@ -254,7 +238,7 @@ mod tests {
             "ksd compound: {:?}",
             spot * (1.0
                 / ksd_curve
-                    .borrow()
+                    .read().unwrap()
                     .get_discount_factor_at_date(futures.get_maturity().unwrap())?
                 - 1.0)
         );
@@ -262,7 +246,7 @@ mod tests {
             "dividend deduction: {:?}",
             spot * (1.0
                 - (equity
-                    .borrow()
+                    .read().unwrap()
                     .get_dividend_deduction_ratio(futures.get_maturity().unwrap()))?)
         );
         println!("npv: {}", res);
@@ -8,27 +8,24 @@ use crate::pricing_engines::npv_result::NpvResult;
 use crate::pricing_engines::pricer::PricerTrait;
 //
 use anyhow::{anyhow, Result};
-use std::{
-    cell::RefCell, 
-    rc::Rc,
-};
+use std::sync::{Arc, RwLock};
 use rustc_hash::FxHashMap;
 
 /// evaluation date is not needed for this pricer
-/// all parameters have the evaluation date (shared in the form of Rc<RefCell<EvaluationDate>>)
+/// all parameters have the evaluation date (shared in the form of Arc<RwLock<EvaluationDate>>)
 pub struct FxFuturesPricer {
-    //evaluation_date: Rc<RefCell<EvaluationDate>>, //not used
-    fx: Rc<RefCell<MarketPrice>>, // floationg to fixed fx as in PlainSwapPricer.
-    underlying_currency_curve: Rc<RefCell<ZeroCurve>>, // if you use implied dividend, this will be risk-free rate (or you can think of it as benchmark rate)
-    futures_currency_curve: Rc<RefCell<ZeroCurve>>,    // or repo
+    //evaluation_date: Arc<RwLock<EvaluationDate>>, //not used
+    fx: Arc<RwLock<MarketPrice>>, // floationg to fixed fx as in PlainSwapPricer.
+    underlying_currency_curve: Arc<RwLock<ZeroCurve>>, // if you use implied dividend, this will be risk-free rate (or you can think of it as benchmark rate)
+    futures_currency_curve: Arc<RwLock<ZeroCurve>>,    // or repo
 }
 
 impl FxFuturesPricer {
     pub fn new(
-        //evaluation_date: Rc<RefCell<EvaluationDate>>,
-        fx: Rc<RefCell<MarketPrice>>,
-        underlying_currency_curve: Rc<RefCell<ZeroCurve>>,
-        futures_currency_curve: Rc<RefCell<ZeroCurve>>,
+        //evaluation_date: Arc<RwLock<EvaluationDate>>,
+        fx: Arc<RwLock<MarketPrice>>,
+        underlying_currency_curve: Arc<RwLock<ZeroCurve>>,
+        futures_currency_curve: Arc<RwLock<ZeroCurve>>,
     ) -> Self {
         FxFuturesPricer {
             //evaluation_date,
@@ -39,9 +36,10 @@ impl FxFuturesPricer {
     }
 }
 
-impl PricerTrait for FxFuturesPricer {
-    fn npv(&self, instrument: &Instrument) -> Result<Real> {
-        let fx_rate = self.fx.borrow().get_value();
+impl FxFuturesPricer {
+    /// discount factors of the underlying and futures currency curves from the spot date to the maturity.
+    /// The spot date is the evaluation date if the spot convention of the instrument is not given.
+    fn get_discount_factors(&self, instrument: &Instrument) -> Result<(Real, Real)> {
         let maturity = match instrument.get_maturity() {
             Some(maturity) => maturity,
             None => {
@@ -55,14 +53,30 @@ impl PricerTrait for FxFuturesPricer {
             }
         };
 
-        let underlying_discount = self
-            .underlying_currency_curve
-            .borrow()
-            .get_discount_factor_at_date(maturity)?;
-        let futures_discount = self
-            .futures_currency_curve
-            .borrow()
-            .get_discount_factor_at_date(maturity)?;
+        let underlying_curve = self.underlying_currency_curve.read().unwrap();
+        let futures_curve = self.futures_currency_curve.read().unwrap();
+        let mut underlying_discount = underlying_curve.get_discount_factor_at_date(maturity)?;
+        let mut futures_discount = futures_curve.get_discount_factor_at_date(maturity)?;
+
+        if let Some(spot_convention) = instrument.get_fx_spot_convention()? {
+            let evaluation_date = self
+                .fx
+                .read()
+                .unwrap()
+                .get_market_datetime()
+                .to_owned();
+            let spot_date = spot_convention.get_settlement_date(&evaluation_date);
+            underlying_discount /= underlying_curve.get_discount_factor_at_date(&spot_date)?;
+            futures_discount /= futures_curve.get_discount_factor_at_date(&spot_date)?;
+        }
+        Ok((underlying_discount, futures_discount))
+    }
+}
+
+impl PricerTrait for FxFuturesPricer {
+    fn npv(&self, instrument: &Instrument) -> Result<Real> {
+        let fx_rate = self.fx.read().unwrap().get_value();
+        let (underlying_discount, futures_discount) = self.get_discount_factors(instrument)?;
 
         let npv = fx_rate * underlying_discount / futures_discount;
         Ok(npv)
@@ -77,27 +91,7 @@ impl PricerTrait for FxFuturesPricer {
         let average_trade_price = instrument.get_average_trade_price();
         let futures_currency = instrument.get_currency();
         let underlying_currency = instrument.get_underlying_currency()?;
-        let maturity = match instrument.get_maturity() {
-            Some(maturity) => maturity,
-            None => {
-                return Err(anyhow!(
-                    "({}:{}) Maturity of {} ({}) is not set",
-                    file!(),
-                    line!(),
-                    instrument.get_name(),
-                    instrument.get_id(),
-                ))
-            }
-        };
-
-        let underlying_discount = self
-            .underlying_currency_curve
-            .borrow()
-            .get_discount_factor_at_date(maturity)?;
-        let futures_discount = self
-            .futures_currency_curve
-            .borrow()
-            .get_discount_factor_at_date(maturity)?;
+        let (underlying_discount, futures_discount) = self.get_discount_factors(instrument)?;
 
         let mut res: FxHashMap<Currency, Real> = FxHashMap::default();
         res.insert(futures_currency, -futures_discount * average_trade_price);
@@ -118,24 +112,26 @@ mod tests {
     use crate::parameters::zero_curve::ZeroCurve;
     use crate::pricing_engines::fx_futures_pricer::FxFuturesPricer;
     use crate::pricing_engines::pricer::PricerTrait;
+    use crate::time::jointcalendar::JointCalendar;
+    use crate::time::settlement::SettlementConvention;
     use crate::{
         InstInfo,
         InstType,
     };
     use anyhow::Result;
     use ndarray::array;
-    use std::cell::RefCell;
-    use std::rc::Rc;
+    use std::sync::RwLock;
+    use std::sync::Arc;
     use time::macros::datetime;
     use static_id::static_id::StaticId;
 
     #[test]
     fn test_fx_futures_pricer() -> Result<()> {
         let eval_date = datetime!(2024-01-02 00:00:00 UTC);
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date.clone())));
-        let fx = Rc::new(RefCell::new(MarketPrice::new(
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_date)));
+        let fx = Arc::new(RwLock::new(MarketPrice::new(
             1300.0,
-            eval_date.clone(),
+            eval_date,
             None,
             Currency::KRW,
             "USDKRW".to_string(),
@@ -152,7 +148,7 @@ mod tests {
             StaticId::from_str("USDOIS", "KAP"),
         )?;
 
-        let usdois_curve = Rc::new(RefCell::new(ZeroCurve::new(
+        let usdois_curve = Arc::new(RwLock::new(ZeroCurve::new(
             evaluation_date.clone(),
             &underlying_curve_data,
             "USDOIS".to_string(),
@@ -169,7 +165,7 @@ mod tests {
             StaticId::from_str("KRWCRS", "KAP"),
         )?;
 
-        let krwcrs_curve = Rc::new(RefCell::new(ZeroCurve::new(
+        let krwcrs_curve = Arc::new(RwLock::new(ZeroCurve::new(
             evaluation_date.clone(),
             &futures_curve_data,
             "KRWCRS".to_string(),
@@ -191,8 +187,8 @@ mod tests {
             id: inst_id,
             inst_type: InstType::FxFutures,
             name: "USDKRW Futures".to_string(),
-            issue_date: Some(issue_date.clone()),
-            maturity: Some(last_trade_date.clone()),
+            issue_date: Some(issue_date),
+            maturity: Some(last_trade_date),
             currency: Currency::KRW,
             unit_notional: 10_000.0,
             accounting_level: crate::AccountingLevel::L1,
@@ -205,7 +201,7 @@ mod tests {
             Currency::USD,
         );
 
-        let inst = Instrument::FxFutures(fxfutures);
+        let inst = Instrument::FxFutures(fxfutures.clone());
         let npv = pricer.npv_result(&inst)?;
         let fx_exporsure = pricer.fx_exposure(&inst, npv.get_npv())?;
 
@@ -220,9 +216,10 @@ mod tests {
             npv.get_npv(),
         );
 
-        let expected_krw_fx_exposure = -1251.4957;
+        let expected_krw_fx_exposure: Real = -1_251.495_769;
         assert!(
-            (fx_exporsure.get(&Currency::KRW).unwrap() - expected_krw_fx_exposure).abs() < 1e-6,
+            (fx_exporsure.get(&Currency::KRW).unwrap() - expected_krw_fx_exposure).abs()
+                < 1e-6 * expected_krw_fx_exposure.abs(),
             "KRW fx exposure is not correct: expected {}, got {}",
             expected_krw_fx_exposure,
             fx_exporsure.get(&Currency::KRW).unwrap(),
@@ -235,6 +232,22 @@ mod tests {
             expected_usd_fx_exposure,
             fx_exporsure.get(&Currency::USD).unwrap(),
         );
+
+        // the fx rate is for the spot date (T+2), so the discount factors are taken from the spot date
+        let spot_convention = SettlementConvention::new(2, JointCalendar::default())?;
+        let inst = Instrument::FxFutures(fxfutures.with_spot_convention(spot_convention));
+        let npv = pricer.npv(&inst)?;
+        assert!((npv - expected_npv).abs() < 1e-6);
+        let spot_discount = usdois_curve
+            .read()
+            .unwrap()
+            .get_discount_factor_at_date(&datetime!(2024-01-04 00:00:00 UTC))?;
+        let fx_exporsure = pricer.fx_exposure(&inst, npv)?;
+        assert!(
+            (fx_exporsure.get(&Currency::USD).unwrap() - expected_usd_fx_exposure / spot_discount)
+                .abs()
+                < 1e-6
+        );
         Ok(())
     }
 }
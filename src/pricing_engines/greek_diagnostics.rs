@@ -0,0 +1,353 @@
+use crate::currency::Currency;
+use crate::data::{surface_data::SurfaceData, value_data::ValueData, vector_data::VectorData};
+use crate::definitions::{Real, Time, DELTA_PNL_UNIT};
+use crate::enums::{OptionExerciseType, OptionType};
+use crate::instrument::{Instrument, InstrumentTrait, Instruments};
+use crate::pricing_engines::{
+    calculation_result::CalculationResult, match_parameter::MatchParameter,
+};
+use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
+//
+use crate::utils::platform::log_warn;
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use time::OffsetDateTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DiagnosticCheck {
+    ThetaGammaCarry,
+    PutCallParity,
+}
+
+/// An inconsistency found by GreekDiagnostics, where expected is implied by the other results
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticWarning {
+    check: DiagnosticCheck,
+    inst_ids: Vec<StaticId>,
+    expected: Real,
+    actual: Real,
+    message: String,
+}
+
+impl DiagnosticWarning {
+    pub fn get_check(&self) -> DiagnosticCheck {
+        self.check
+    }
+
+    pub fn get_inst_ids(&self) -> &Vec<StaticId> {
+        &self.inst_ids
+    }
+
+    pub fn get_expected(&self) -> Real {
+        self.expected
+    }
+
+    pub fn get_actual(&self) -> Real {
+        self.actual
+    }
+
+    pub fn get_message(&self) -> &String {
+        &self.message
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticsReport {
+    // number of instruments (theta) and pairs (parity) checked
+    checked: FxHashMap<DiagnosticCheck, usize>,
+    warnings: Vec<DiagnosticWarning>,
+}
+
+impl DiagnosticsReport {
+    pub fn get_checked_number(&self, check: DiagnosticCheck) -> usize {
+        self.checked.get(&check).copied().unwrap_or(0)
+    }
+
+    pub fn get_warnings(&self) -> &Vec<DiagnosticWarning> {
+        &self.warnings
+    }
+
+    pub fn get_warnings_of(&self, check: DiagnosticCheck) -> Vec<&DiagnosticWarning> {
+        self.warnings.iter().filter(|w| w.check == check).collect()
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Consistency checks of the greeks of the vanilla options after an engine run.
+///
+/// - ThetaGammaCarry: theta is compared with the Black-Scholes equation
+///   Θ = r_d V - (r_c - r_b) S ∂V/∂S - ½ σ² S² ∂²V/∂S² per year, i.e., in the units of the results
+///   (value - delta / 0.01 (r_c - r_b) / r_d - σ² gamma / 0.01²) / 365 per day.
+///   r_d, r_c and r_b are the zero rates of the discount, collateral and borrowing curves at the maturity,
+///   and σ is the constant volatility or the nearest node of the volatility surface.
+///   It requires delta, gamma and theta, and warns when the difference exceeds theta_tolerance
+///   relative to the larger of the two.
+/// - PutCallParity: C - P = DF (F - K) on the European call and put of the same underlying, strike and maturity.
+///   The forward is proportional to the spot, so DF F = S ∂(C - P)/∂S is taken from the deltas,
+///   and DF is discounted by r_d. It warns when the residual per unit exceeds parity_tolerance * strike.
+///
+/// The dividends and the smile dynamics are not in the equations, so the tolerances are loose by default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GreekDiagnostics {
+    theta_tolerance: Real,
+    parity_tolerance: Real,
+}
+
+impl Default for GreekDiagnostics {
+    fn default() -> GreekDiagnostics {
+        GreekDiagnostics {
+            theta_tolerance: 0.2,
+            parity_tolerance: 0.001,
+        }
+    }
+}
+
+impl GreekDiagnostics {
+    pub fn new() -> GreekDiagnostics {
+        GreekDiagnostics::default()
+    }
+
+    pub fn with_theta_tolerance(mut self, theta_tolerance: Real) -> Result<GreekDiagnostics> {
+        check_tolerance(theta_tolerance)?;
+        self.theta_tolerance = theta_tolerance;
+        Ok(self)
+    }
+
+    pub fn with_parity_tolerance(mut self, parity_tolerance: Real) -> Result<GreekDiagnostics> {
+        check_tolerance(parity_tolerance)?;
+        self.parity_tolerance = parity_tolerance;
+        Ok(self)
+    }
+
+    pub fn get_theta_tolerance(&self) -> Real {
+        self.theta_tolerance
+    }
+
+    pub fn get_parity_tolerance(&self) -> Real {
+        self.parity_tolerance
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn run(
+        &self,
+        evaluation_datetime: &OffsetDateTime,
+        instruments: &Instruments,
+        results: &FxHashMap<StaticId, CalculationResult>,
+        match_parameter: &MatchParameter,
+        curve_data: &FxHashMap<StaticId, VectorData>,
+        equity_constant_volatility_data: &FxHashMap<StaticId, ValueData>,
+        equity_volatility_surface_data: &FxHashMap<StaticId, SurfaceData>,
+    ) -> Result<DiagnosticsReport> {
+        let mut report = DiagnosticsReport::default();
+        let time_calculator = NullCalendar::default();
+        let zero_rate = |curve_id: Option<StaticId>, t: Time| -> Result<Real> {
+            match curve_id.filter(|id| *id != StaticId::default()) {
+                Some(id) => {
+                    let data = curve_data.get(&id).ok_or_else(|| {
+                        anyhow!("({}:{}) no curve data of {}", file!(), line!(), id)
+                    })?;
+                    Ok(get_zero_rate(data, t))
+                }
+                None => Ok(0.0),
+            }
+        };
+
+        // (underlying, strike, maturity, currency) -> (call, put) of the European options
+        // the strike as the bits of f64 for both precisions of Real
+        type ParityKey = (StaticId, u64, OffsetDateTime, Currency);
+        let mut parity_pairs: FxHashMap<ParityKey, (Option<StaticId>, Option<StaticId>)> =
+            FxHashMap::default();
+        let mut parity_keys: Vec<ParityKey> = vec![];
+        // instrument id -> (time to maturity, discount rate)
+        let mut option_rates: FxHashMap<StaticId, (Time, Real)> = FxHashMap::default();
+
+        for inst in instruments.iter() {
+            let Instrument::VanillaOption(option) = inst.as_ref() else {
+                continue;
+            };
+            let inst_id = inst.get_id();
+            let Some(result) = results.get(&inst_id) else {
+                continue;
+            };
+            let Some(maturity) = inst.get_maturity() else {
+                continue;
+            };
+            let und_id = option.underlying_ids[0];
+            let t = time_calculator.get_time_difference(evaluation_datetime, maturity);
+            if t <= 0.0 {
+                continue;
+            }
+            let r_d = zero_rate(Some(match_parameter.get_discount_curve_id(inst)?), t)?;
+            option_rates.insert(inst_id, (t, r_d));
+
+            if option.exercise_type == OptionExerciseType::European {
+                let key = (
+                    und_id,
+                    (option.strike as f64).to_bits(),
+                    *maturity,
+                    inst.get_currency(),
+                );
+                let pair = parity_pairs.entry(key).or_insert_with(|| {
+                    parity_keys.push(key);
+                    (None, None)
+                });
+                match option.option_type {
+                    OptionType::Call => pair.0 = pair.0.or(Some(inst_id)),
+                    OptionType::Put => pair.1 = pair.1.or(Some(inst_id)),
+                }
+            }
+
+            // theta-gamma-carry
+            let greek = |greeks: Option<&FxHashMap<StaticId, Real>>| {
+                greeks.and_then(|g| g.get(&und_id)).copied()
+            };
+            let (Some(value), Some(delta), Some(gamma), Some(theta)) = (
+                result.get_value(),
+                greek(result.get_delta()),
+                greek(result.get_gamma()),
+                result.get_theta(),
+            ) else {
+                continue;
+            };
+            let sigma = match equity_constant_volatility_data.get(&und_id) {
+                Some(data) => data.get_value(),
+                None => match equity_volatility_surface_data.get(&und_id) {
+                    Some(data) => get_nearest_volatility(data, maturity, option.strike),
+                    None => continue,
+                },
+            };
+            let r_c = zero_rate(
+                Some(match_parameter.get_collateral_curve_id(inst, und_id)?),
+                t,
+            )?;
+            let r_b = zero_rate(
+                match_parameter
+                    .get_borrowing_curve_ids(inst)?
+                    .first()
+                    .copied(),
+                t,
+            )?;
+            let expected = (r_d * value
+                - (r_c - r_b) * delta / DELTA_PNL_UNIT
+                - sigma * sigma * gamma / (DELTA_PNL_UNIT * DELTA_PNL_UNIT))
+                / 365.0;
+            *report
+                .checked
+                .entry(DiagnosticCheck::ThetaGammaCarry)
+                .or_insert(0) += 1;
+            if (theta - expected).abs() > self.theta_tolerance * theta.abs().max(expected.abs()) {
+                report.warnings.push(DiagnosticWarning {
+                    check: DiagnosticCheck::ThetaGammaCarry,
+                    inst_ids: vec![inst_id],
+                    expected,
+                    actual: theta,
+                    message: format!(
+                        "theta {} of {} is off the theta {} from gamma and carry (vol = {}, r_d = {}, r_c - r_b = {})",
+                        theta,
+                        inst_id,
+                        expected,
+                        sigma,
+                        r_d,
+                        r_c - r_b,
+                    ),
+                });
+            }
+        }
+
+        // put-call parity
+        for key in parity_keys.iter() {
+            let (Some(call_id), Some(put_id)) = parity_pairs[key] else {
+                continue;
+            };
+            let (und_id, strike, _, _) = key;
+            let strike = f64::from_bits(*strike) as Real;
+            let per_unit = |inst_id: &StaticId| -> Option<(Real, Real)> {
+                let result = results.get(inst_id)?;
+                let unit = result.get_instrument_info()?.get_unit_notional();
+                let npv = result.get_npv_result()?.get_npv();
+                let delta = *result.get_delta()?.get(und_id)?;
+                Some((npv, delta / unit / DELTA_PNL_UNIT))
+            };
+            let (Some((call_npv, call_delta)), Some((put_npv, put_delta))) =
+                (per_unit(&call_id), per_unit(&put_id))
+            else {
+                continue;
+            };
+            let (t, r_d) = option_rates[&call_id];
+            let expected = (call_delta - put_delta) - strike * (-r_d * t).exp();
+            let actual = call_npv - put_npv;
+            *report
+                .checked
+                .entry(DiagnosticCheck::PutCallParity)
+                .or_insert(0) += 1;
+            if (actual - expected).abs() > self.parity_tolerance * strike {
+                report.warnings.push(DiagnosticWarning {
+                    check: DiagnosticCheck::PutCallParity,
+                    inst_ids: vec![call_id, put_id],
+                    expected,
+                    actual,
+                    message: format!(
+                        "call {} - put {} = {} per unit, but DF (F - K) = {} at the strike {}",
+                        call_id, put_id, actual, expected, strike,
+                    ),
+                });
+            }
+        }
+
+        for warning in report.warnings.iter() {
+            let msg = warning.message.clone();
+            log_warn!("Diagnostics"; diagnostics = msg);
+        }
+        Ok(report)
+    }
+}
+
+fn check_tolerance(tolerance: Real) -> Result<()> {
+    if tolerance <= 0.0 || !tolerance.is_finite() {
+        return Err(anyhow!(
+            "({}:{}) tolerance must be positive, got {}",
+            file!(),
+            line!(),
+            tolerance
+        ));
+    }
+    Ok(())
+}
+
+/// zero rate linearly interpolated on the times of the data with the flat extrapolation as in ZeroCurve
+fn get_zero_rate(data: &VectorData, t: Time) -> Real {
+    let (times, rates) = (&data.times, &data.value);
+    let n = times.len();
+    if n == 0 {
+        return 0.0;
+    }
+    if t <= times[0] {
+        return rates[0];
+    }
+    if t >= times[n - 1] {
+        return rates[n - 1];
+    }
+    let i = times.iter().position(|x| *x >= t).unwrap_or(n - 1);
+    let w = (t - times[i - 1]) / (times[i] - times[i - 1]);
+    rates[i - 1] * (1.0 - w) + rates[i] * w
+}
+
+/// volatility on the node nearest to the maturity and the strike
+fn get_nearest_volatility(data: &SurfaceData, maturity: &OffsetDateTime, strike: Real) -> Real {
+    let i = (0..data.dates.len())
+        .min_by_key(|i| (data.dates[*i] - *maturity).whole_seconds().abs())
+        .unwrap_or(0);
+    let j = (0..data.strikes.len())
+        .min_by(|a, b| {
+            (data.strikes[*a] - strike)
+                .abs()
+                .total_cmp(&(data.strikes[*b] - strike).abs())
+        })
+        .unwrap_or(0);
+    data.value[[i, j]]
+}
@@ -1,28 +1,136 @@
 use crate::definitions::Real;
+use crate::evaluation_date::EvaluationDate;
 use crate::instrument::Instrument;
 use crate::parameters::market_price::MarketPrice;
 use crate::pricing_engines::npv_result::NpvResult;
 use crate::pricing_engines::pricer::PricerTrait;
 //
 use anyhow::Result;
-use std::{cell::RefCell, rc::Rc};
+use rustc_hash::FxHashMap;
+use std::sync::{Arc, RwLock};
+use time::OffsetDateTime;
 
 pub struct IdentityPricer {
-    market_price: Rc<RefCell<MarketPrice>>,
+    market_price: Arc<RwLock<MarketPrice>>,
+    evaluation_date: Arc<RwLock<EvaluationDate>>,
 }
 
 impl IdentityPricer {
-    pub fn new(market_price: Rc<RefCell<MarketPrice>>) -> IdentityPricer {
-        IdentityPricer { market_price }
+    pub fn new(
+        market_price: Arc<RwLock<MarketPrice>>,
+        evaluation_date: Arc<RwLock<EvaluationDate>>,
+    ) -> IdentityPricer {
+        IdentityPricer {
+            market_price,
+            evaluation_date,
+        }
     }
 }
 
 impl PricerTrait for IdentityPricer {
     fn npv(&self, _instrument: &Instrument) -> Result<Real> {
-        Ok(self.market_price.borrow().get_value())
+        Ok(self.market_price.read().unwrap().get_value())
     }
 
+    /// The dividends are the cashflows of the holder on the payment dates.
+    /// Only the dividends with ex_date <= evaluation date < payment_date are included
+    /// since the value has already dropped but the cash is not received yet.
+    /// The dividends with a future ex-date are still in the value.
     fn npv_result(&self, instrument: &Instrument) -> Result<NpvResult> {
-        Ok(NpvResult::new_from_npv(self.npv(instrument)?))
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
+        let market_price = self.market_price.read().unwrap();
+        let payments = market_price.get_unpaid_dividend_payments(&eval_dt);
+        if payments.is_empty() {
+            return Ok(NpvResult::new_from_npv(self.npv(instrument)?));
+        }
+
+        let mut cashflow_amounts: FxHashMap<usize, (OffsetDateTime, Real)> = FxHashMap::default();
+        let mut cashflow_probabilities: FxHashMap<usize, (OffsetDateTime, Real)> = FxHashMap::default();
+        for (i, (payment_date, amount)) in payments.into_iter().enumerate() {
+            cashflow_amounts.insert(i, (payment_date, amount));
+            cashflow_probabilities.insert(i, (payment_date, 1.0));
+        }
+        Ok(NpvResult::new(
+            market_price.get_value(),
+            cashflow_amounts,
+            cashflow_probabilities,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::data::vector_data::VectorData;
+    use crate::instruments::stock::Stock;
+    use crate::parameters::discrete_ratio_dividend::DiscreteRatioDividend;
+    use crate::{AccountingLevel, InstInfo, InstType};
+    use ndarray::array;
+    use static_id::static_id::StaticId;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_unpaid_dividends() -> Result<()> {
+        let eval_dt = datetime!(2024-04-01 16:00:00 +09:00);
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_dt)));
+        let id = StaticId::from_str("005930", "KRX");
+        // ex-dates: 2024-03-28 (passed) and 2024-06-27 (ahead)
+        let data = VectorData::new(
+            array![1.0, 2.0],
+            Some(vec![
+                datetime!(2024-03-28 00:00:00 +09:00),
+                datetime!(2024-06-27 00:00:00 +09:00),
+            ]),
+            None,
+            Some(eval_dt),
+            Currency::KRW,
+            "dividend".to_string(),
+            id,
+        )?;
+        let dividend = DiscreteRatioDividend::new(
+            evaluation_date.clone(),
+            &data,
+            100.0,
+            "dividend".to_string(),
+            id,
+        )?
+        .with_payment_dates(vec![
+            datetime!(2024-04-19 00:00:00 +09:00),
+            datetime!(2024-08-16 00:00:00 +09:00),
+        ])?;
+        let market_price = Arc::new(RwLock::new(MarketPrice::new(
+            99.0,
+            eval_dt,
+            Some(Arc::new(RwLock::new(dividend))),
+            Currency::KRW,
+            "Samsung".to_string(),
+            id,
+        )));
+        let inst_info = InstInfo::new(
+            id,
+            "Samsung".to_string(),
+            InstType::Stock,
+            Currency::KRW,
+            1.0,
+            None,
+            None,
+            AccountingLevel::L1,
+        );
+        let stock = Instrument::Stock(Stock::new(inst_info, id, None));
+        let pricer = IdentityPricer::new(market_price, evaluation_date.clone());
+
+        // the dividend with the future ex-date is still in the spot
+        let npv_result = pricer.npv_result(&stock)?;
+        assert_eq!(npv_result.get_npv(), 99.0);
+        let cashflows: Vec<(OffsetDateTime, Real)> =
+            npv_result.get_cashflow_amounts().values().copied().collect();
+        assert_eq!(cashflows, vec![(datetime!(2024-04-19 00:00:00 +09:00), 1.0)]);
+
+        // paid on 2024-04-19 and the next ex-date is not reached
+        *evaluation_date.write().unwrap() = EvaluationDate::new(datetime!(2024-04-19 16:00:00 +09:00));
+        let npv_result = pricer.npv_result(&stock)?;
+        assert!(npv_result.get_cashflow_amounts().is_empty());
+        Ok(())
     }
 }
@@ -2,34 +2,35 @@ use crate::definitions::Real;
 use crate::evaluation_date::EvaluationDate;
 use crate::instrument::{Instrument, InstrumentTrait};
 use crate::instruments::bond::Bond;
+use crate::math::solvers::{brent, expand_bracket, SolverOptions};
 use crate::parameters::{past_price::DailyClosePrice, zero_curve::ZeroCurve};
+use crate::pricing_engines::bond_analytics::{calculate_bond_analytics, BondAnalytics};
 use crate::pricing_engines::npv_result::NpvResult;
 use crate::pricing_engines::pricer::PricerTrait;
 use crate::time::{calendar_trait::CalendarTrait, conventions::DayCountConvention};
 //
 use anyhow::{anyhow, Context, Result};
-use argmin::core::{CostFunction, Error, Executor, Gradient};
-use argmin::solver::gradientdescent::SteepestDescent;
-use argmin::solver::linesearch::MoreThuenteLineSearch;
-use std::{cell::RefCell, rc::Rc};
+use std::sync::{Arc, RwLock};
+
+const BOND_YIELD_BRACKET_WIDTH: Real = 0.01;
 
 /// 금융투자회사의 영업 및 업무에 관한 규정 별표 14
 /// https://law.kofia.or.kr/service/law/lawFullScreenContent.do?seq=136&historySeq=263
 #[derive(Debug, Clone)]
 pub struct KrxYieldPricer {
-    evaluation_date: Rc<RefCell<EvaluationDate>>,
+    evaluation_date: Arc<RwLock<EvaluationDate>>,
     bond_yield: Real,
     daycount: DayCountConvention,
-    forward_curve: Option<Rc<RefCell<ZeroCurve>>>,
-    past_fixing_data: Option<Rc<DailyClosePrice>>,
+    forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+    past_fixing_data: Option<Arc<DailyClosePrice>>,
 }
 
 impl KrxYieldPricer {
     pub fn new(
-        evaluation_date: Rc<RefCell<EvaluationDate>>,
+        evaluation_date: Arc<RwLock<EvaluationDate>>,
         bond_yield: Real,
-        forward_curve: Option<Rc<RefCell<ZeroCurve>>>,
-        past_fixing_data: Option<Rc<DailyClosePrice>>,
+        forward_curve: Option<Arc<RwLock<ZeroCurve>>>,
+        past_fixing_data: Option<Arc<DailyClosePrice>>,
     ) -> KrxYieldPricer {
         KrxYieldPricer {
             evaluation_date,
@@ -48,61 +49,35 @@ impl KrxYieldPricer {
         self.bond_yield = bond_yield;
     }
 
+    /// The yield reproducing the npv, by Brent's method on the bracket expanded from the init_guess (0.02 by default).
+    /// The yield is in (-frequency, inf) where the discount factors are defined, including the negative yields
     pub fn find_bond_yield(&self, bond: Bond, npv: Real, init_guess: Option<Real>) -> Result<Real> {
-        let pricer = self.clone();
-        let problem = KrxYieldPricerCostFunction::new(bond, npv, pricer);
-        let linesearch = MoreThuenteLineSearch::new();
-
-        let solver = SteepestDescent::new(linesearch);
-        let init_param = init_guess.unwrap_or(0.02);
-        let executor = Executor::new(problem, solver)
-            .configure(|state| state.param(init_param).max_iters(30).target_cost(1.0e-9));
-
-        let res = executor.run()?;
-        match res.state.best_param {
-            Some(param) => Ok(param),
-            None => Err(anyhow!("Failed to find bond yield")),
-        }
-    }
-}
-
-pub struct KrxYieldPricerCostFunction {
-    bond: Instrument,
-    npv: Real,
-    pricer: RefCell<KrxYieldPricer>,
-}
-
-impl KrxYieldPricerCostFunction {
-    pub fn new(bond: Bond, npv: Real, pricer: KrxYieldPricer) -> KrxYieldPricerCostFunction {
-        KrxYieldPricerCostFunction {
-            bond: Instrument::Bond(bond),
-            npv,
-            pricer: RefCell::new(pricer),
-        }
-    }
-}
-
-impl CostFunction for KrxYieldPricerCostFunction {
-    type Param = Real;
-    type Output = Real;
-
-    fn cost(&self, param: &Self::Param) -> Result<Self::Output, Error> {
-        {
-            self.pricer.borrow_mut().set_bond_yield(*param);
-        }
-        let npv = self.pricer.borrow().npv(&self.bond)?;
-        Ok((npv - self.npv).powf(2.0))
-    }
-}
-
-impl Gradient for KrxYieldPricerCostFunction {
-    type Param = Real;
-    type Gradient = Real;
+        let bond = Instrument::Bond(bond);
+        let freq = bond.get_coupon_frequency()?.as_real();
+        let mut pricer = self.clone();
+        let mut residual = |bond_yield: Real| {
+            pricer.set_bond_yield(bond_yield);
+            Ok(pricer.npv(&bond)? - npv)
+        };
 
-    fn gradient(&self, param: &Self::Param) -> Result<Self::Gradient, Error> {
-        let h = 5.0e-5;
-        let grad = (self.cost(&(param + h))? - self.cost(&(param - h))?) / (2.0 * h);
-        Ok(grad)
+        let init_guess = init_guess.unwrap_or(0.02);
+        let (lower, upper, _, _) = expand_bracket(
+            &mut residual,
+            init_guess - BOND_YIELD_BRACKET_WIDTH,
+            init_guess + BOND_YIELD_BRACKET_WIDTH,
+            (-freq, Real::INFINITY),
+        )
+        .with_context(|| {
+            anyhow!(
+                "({}:{}) failed to find the yield of {} ({}) reproducing the npv {}",
+                file!(),
+                line!(),
+                bond.get_name(),
+                bond.get_code_str(),
+                npv
+            )
+        })?;
+        brent(&mut residual, lower, upper, &SolverOptions::default())
     }
 }
 
@@ -110,7 +85,7 @@ impl PricerTrait for KrxYieldPricer {
     fn npv(&self, bond: &Instrument) -> Result<Real> {
         let mut res: Real = 0.0;
         let mut disc_factor: Real;
-        let eval_dt = self.evaluation_date.borrow().get_date_clone();
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
         let pricing_date = bond.get_pricing_date()?.unwrap_or(&eval_dt);
         let freq = bond.get_coupon_frequency()?.as_real();
         let effective_yield = self.bond_yield / freq;
@@ -178,6 +153,21 @@ impl PricerTrait for KrxYieldPricer {
         let npv = self.npv(instrument)?;
         Ok(NpvResult::new_from_npv(npv))
     }
+
+    /// the yield is the given bond_yield, and the duration and convexity are on the yield
+    fn bond_analytics(&self, instrument: &Instrument, npv: Real) -> Result<Option<BondAnalytics>> {
+        let eval_dt = self.evaluation_date.read().unwrap().get_date_clone();
+        let pricing_date = instrument.get_pricing_date()?.unwrap_or(&eval_dt);
+        let analytics = calculate_bond_analytics(
+            instrument,
+            pricing_date,
+            npv,
+            Some(self.bond_yield),
+            self.forward_curve.clone(),
+            self.past_fixing_data.clone(),
+        )?;
+        Ok(Some(analytics))
+    }
 }
 
 #[cfg(test)]
@@ -200,7 +190,7 @@ mod tests {
     fn test_krx_yield_pricer() -> Result<()> {
         let dt = datetime!(2024-03-18 16:30:00 +09:00);
         let eval_date = evaluation_date::EvaluationDate::new(dt);
-        let eval_date_rc = Rc::new(RefCell::new(eval_date));
+        let eval_date_rc = Arc::new(RwLock::new(eval_date));
         let pricing_date = dt + Duration::days(1);
         //
         let issuedate2 = datetime!(2022-12-10 16:30:00 +09:00);
@@ -223,8 +213,8 @@ mod tests {
             currency: bond_currency2,
             unit_notional: 10_000.0,
             accounting_level: crate::AccountingLevel::L1,
-            issue_date: Some(issuedate2.clone()),
-            maturity: Some(maturity2.clone()),
+            issue_date: Some(issuedate2),
+            maturity: Some(maturity2),
         };
 
         let bond_info = BondInfo {
@@ -240,7 +230,7 @@ mod tests {
             false,
             //
             None,
-            Some(pricing_date.clone()),
+            Some(pricing_date),
             None,
             //
             Some(0.0425),
@@ -291,6 +281,33 @@ mod tests {
             expected_yield
         );
 
+        // accrued interest from 2023-12-10 to 2024-03-19 (99 days in the street convention)
+        // in the coupon period to 2024-06-10
+        let analytics = pricer.bond_analytics(&inst, npv)?.unwrap();
+        let expected_accrued = 0.0425 / 2.0 * 99.0 / 180.0;
+        assert!(
+            (analytics.get_accrued_interest() - expected_accrued).abs() < 1.0e-5,
+            "accrued interest: {}, expected: {}",
+            analytics.get_accrued_interest(),
+            expected_accrued
+        );
+        assert_eq!(analytics.get_yield_to_maturity(), bond_yield);
+        assert!((analytics.get_clean_price() - (npv - expected_accrued)).abs() < 1.0e-5);
+        assert!((analytics.get_dirty_price() - npv).abs() < 1.0e-6);
+        // modified duration against the central difference of the price in the yield
+        let bump = 0.0001;
+        let up = KrxYieldPricer::new(eval_date_rc.clone(), bond_yield + bump, None, None).npv(&inst)?;
+        let down = KrxYieldPricer::new(eval_date_rc.clone(), bond_yield - bump, None, None).npv(&inst)?;
+        let expected_duration = -(up - down) / (2.0 * bump * npv);
+        assert!(
+            (analytics.get_modified_duration() - expected_duration).abs() < 1.0e-2,
+            "modified duration: {}, expected: {}",
+            analytics.get_modified_duration(),
+            expected_duration
+        );
+        assert!(analytics.get_convexity() > 0.0);
+        assert!(analytics.get_z_spread().is_none() && analytics.get_oas().is_none());
+
         Ok(())
     }
 }
@@ -9,26 +9,40 @@ use crate::pricing_engines::{
 };
 //
 use anyhow::Result;
-use std::{cell::RefCell, rc::Rc};
+use static_id::static_id::StaticId;
+use rustc_hash::FxHashMap;
+use std::sync::{Arc, RwLock};
 
+/// repo_curves (bond id -> repo curve): the underlying bonds in this map are carried
+/// to the futures maturity on their repo curves instead of the discount curve
 pub struct KtbfPricer {
-    evaluation_date: Rc<RefCell<EvaluationDate>>,
-    discount_curve: Rc<RefCell<ZeroCurve>>,
-    borrowing_curve: Rc<RefCell<ZeroCurve>>,
+    evaluation_date: Arc<RwLock<EvaluationDate>>,
+    discount_curve: Arc<RwLock<ZeroCurve>>,
+    borrowing_curve: Arc<RwLock<ZeroCurve>>,
+    repo_curves: FxHashMap<StaticId, Arc<RwLock<ZeroCurve>>>,
 }
 
 impl KtbfPricer {
     pub fn new(
-        evaluation_date: Rc<RefCell<EvaluationDate>>,
-        discount_curve: Rc<RefCell<ZeroCurve>>,
-        borrowing_curve: Rc<RefCell<ZeroCurve>>,
+        evaluation_date: Arc<RwLock<EvaluationDate>>,
+        discount_curve: Arc<RwLock<ZeroCurve>>,
+        borrowing_curve: Arc<RwLock<ZeroCurve>>,
     ) -> KtbfPricer {
         KtbfPricer {
             evaluation_date,
             discount_curve,
             borrowing_curve,
+            repo_curves: FxHashMap::default(),
         }
     }
+
+    pub fn with_repo_curves(
+        mut self,
+        repo_curves: FxHashMap<StaticId, Arc<RwLock<ZeroCurve>>>,
+    ) -> KtbfPricer {
+        self.repo_curves = repo_curves;
+        self
+    }
 }
 
 impl PricerTrait for KtbfPricer {
@@ -48,7 +62,7 @@ impl PricerTrait for KtbfPricer {
 
         let init_guess = self
             .discount_curve
-            .borrow()
+            .read().unwrap()
             .get_forward_rate_from_evaluation_date(
                 underlying_bonds[0].get_maturity().unwrap(),
                 Compounding::Simple,
@@ -56,7 +70,17 @@ impl PricerTrait for KtbfPricer {
 
         for bond in underlying_bonds.iter() {
             let inst = Instrument::Bond(bond.clone());
-            let npv = bond_pricer.npv(&inst)?;
+            let mut npv = bond_pricer.npv(&inst)?;
+            // the forward price from bond_pricer is carried on the discount curve.
+            // If the bond has a repo curve, it is carried on the repo curve instead.
+            if let Some(repo_curve) = self.repo_curves.get(&bond.get_id()) {
+                let maturity = instrument.get_maturity().unwrap();
+                npv *= self
+                    .discount_curve
+                    .read().unwrap()
+                    .get_discount_factor_at_date(maturity)?
+                    / repo_curve.read().unwrap().get_discount_factor_at_date(maturity)?;
+            }
             let yield_ = krx_yield_pricer.find_bond_yield(bond.clone(), npv, Some(init_guess))?;
             bond_yields.push(yield_);
         }
@@ -67,7 +91,7 @@ impl PricerTrait for KtbfPricer {
 
         let borrowing_cost = self
             .borrowing_curve
-            .borrow()
+            .read().unwrap()
             .get_discount_factor_at_date(instrument.get_maturity().unwrap())?;
 
         ktbf_price *= borrowing_cost;
@@ -109,16 +133,17 @@ mod tests {
     //
     use anyhow::Result;
     use ndarray::array;
-    use std::cell::RefCell;
-    use std::rc::Rc;
+    use std::sync::RwLock;
+    use std::sync::Arc;
     use time::macros::datetime;
     use time::Duration;
     use static_id::static_id::StaticId;
+    use rustc_hash::FxHashMap;
 
     #[test]
     fn test_ktbf_pricer() -> Result<()> {
         let eval_date = datetime!(2024-01-02 00:00:00 UTC);
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date)));
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_date)));
         let curve_data = VectorData::new(
             array![0.030, 0.040],
             None,
@@ -166,8 +191,8 @@ mod tests {
             inst_type: InstType::Bond,
             currency: Currency::KRW,
             unit_notional: 10_000.0,
-            issue_date: Some(bond1_issue_date.clone()),
-            maturity: Some(bond1_maturity.clone()),
+            issue_date: Some(bond1_issue_date),
+            maturity: Some(bond1_maturity),
             accounting_level: crate::AccountingLevel::L1,
         };
 
@@ -185,8 +210,8 @@ mod tests {
             
             false,
             //
-            Some(bond1_issue_date.clone()),
-            Some(ktbf_maturity.clone()),            
+            Some(bond1_issue_date),
+            Some(ktbf_maturity),            
             None,
             //
             Some(0.03),
@@ -213,8 +238,8 @@ mod tests {
             inst_type: InstType::Bond,
             currency: Currency::KRW,
             unit_notional: 10_000.0,
-            issue_date: Some(bond2_issue_date.clone()),
-            maturity: Some(bond2_maturity.clone()),
+            issue_date: Some(bond2_issue_date),
+            maturity: Some(bond2_maturity),
             accounting_level: crate::AccountingLevel::L1,
         };
 
@@ -230,8 +255,8 @@ mod tests {
             //
             false,
             //
-            Some(bond2_issue_date.clone()),
-            Some(ktbf_maturity.clone()),
+            Some(bond2_issue_date),
+            Some(ktbf_maturity),
             None,
             //
             Some(0.03),
@@ -259,29 +284,60 @@ mod tests {
             inst_type: InstType::KTBF,
             currency: Currency::KRW,
             unit_notional: 1_000_000.0,
-            issue_date: Some(ktbf_issue_date.clone()),
-            maturity: Some(ktbf_maturity.clone()),
+            issue_date: Some(ktbf_issue_date),
+            maturity: Some(ktbf_maturity),
             accounting_level: crate::AccountingLevel::L1,
         };
 
-        let ktbf = KTBF::new(
+        let ktbf = Instrument::KTBF(KTBF::new(
             ktbf_info,
-            Some(ktbf_issue_date.clone()),
+            Some(ktbf_issue_date),
             virtual_bond,
             vec![bond1, bond2],
             StaticId::from_str("KTBF3Y", "KRX"),
-        )?;
+        )?);
 
+        let discount_curve = Arc::new(RwLock::new(discount_curve));
+        let borrowing_curve = Arc::new(RwLock::new(borrowing_curve));
         let ktbf_pricer = KtbfPricer::new(
             evaluation_date.clone(),
-            Rc::new(RefCell::new(discount_curve)),
-            Rc::new(RefCell::new(borrowing_curve)),
+            discount_curve.clone(),
+            borrowing_curve.clone(),
         );
 
         let pricer = Pricer::KtbfPricer(ktbf_pricer);
-        let npv = pricer.npv(&Instrument::KTBF(ktbf))?;
+        let npv = pricer.npv(&ktbf)?;
         println!("KTBF NPV: {}", npv);
 
+        // carrying the bonds on a repo curve above the discount curve
+        // raises the forward bond prices, i.e., lowers the yields and raises the futures price
+        let repo_curve_data = VectorData::new(
+            array![0.045],
+            None,
+            Some(array![0.5]),
+            None,
+            Currency::KRW,
+            "KRW Repo".to_string(),
+            StaticId::from_str("KRW Repo", "test"),
+        )?;
+        let repo_curve = Arc::new(RwLock::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &repo_curve_data,
+            "KRW Repo".to_string(),
+            StaticId::from_str("KRW Repo", "test"),
+        )?));
+        let mut repo_curves = FxHashMap::default();
+        repo_curves.insert(inst_id, repo_curve.clone());
+        repo_curves.insert(bond_id2, repo_curve);
+
+        let repo_pricer = KtbfPricer::new(
+            evaluation_date.clone(),
+            discount_curve,
+            borrowing_curve,
+        ).with_repo_curves(repo_curves);
+        let repo_npv = repo_pricer.npv(&ktbf)?;
+        assert!(repo_npv > npv, "repo npv: {}, npv: {}", repo_npv, npv);
+
         Ok(())
     }
 }
@@ -0,0 +1,87 @@
+use crate::pricing_engines::engine_generator::{MarketDataKey, MarketDataUpdate};
+//
+use anyhow::Result;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::sync::mpsc::{Receiver, Sender};
+
+/// A feed of the market data, e.g., a Bloomberg or KRX feed handler, driving a PricingSession.
+/// The handlers convert their ticks into MarketDataUpdate, and the session
+/// subscribes to the data of its engine generator (PricingSession::connect).
+pub trait MarketDataSource {
+    /// starts the updates of the data.
+    /// An error is returned if the source does not serve the data.
+    fn subscribe(&mut self, key: MarketDataKey) -> Result<()>;
+
+    fn unsubscribe(&mut self, key: &MarketDataKey) -> Result<()>;
+
+    /// the last data of the subscribed keys which have been received
+    fn snapshot(&self) -> Result<Vec<MarketDataUpdate>>;
+
+    /// a channel of the updates of the subscribed keys from now on,
+    /// which ends when the source is closed
+    fn stream(&mut self) -> Result<Receiver<MarketDataUpdate>>;
+
+    fn subscribe_all(&mut self, keys: &[MarketDataKey]) -> Result<()> {
+        for key in keys.iter() {
+            self.subscribe(*key)?;
+        }
+        Ok(())
+    }
+}
+
+/// MarketDataSource of the data published in the process, e.g., for tests and replays.
+/// The streams end when the source is dropped.
+#[derive(Default)]
+pub struct InMemoryMarketDataSource {
+    last_data: FxHashMap<MarketDataKey, MarketDataUpdate>,
+    subscriptions: FxHashSet<MarketDataKey>,
+    streams: Vec<Sender<MarketDataUpdate>>,
+}
+
+impl InMemoryMarketDataSource {
+    pub fn new() -> InMemoryMarketDataSource {
+        InMemoryMarketDataSource::default()
+    }
+
+    /// keeps the data as the last data of its key,
+    /// and sends it to the streams if the key is subscribed
+    pub fn publish(&mut self, update: MarketDataUpdate) {
+        let key = update.get_key();
+        if self.subscriptions.contains(&key) {
+            self.streams
+                .retain(|sender| sender.send(update.clone()).is_ok());
+        }
+        self.last_data.insert(key, update);
+    }
+
+    pub fn is_subscribed(&self, key: &MarketDataKey) -> bool {
+        self.subscriptions.contains(key)
+    }
+}
+
+impl MarketDataSource for InMemoryMarketDataSource {
+    fn subscribe(&mut self, key: MarketDataKey) -> Result<()> {
+        self.subscriptions.insert(key);
+        Ok(())
+    }
+
+    fn unsubscribe(&mut self, key: &MarketDataKey) -> Result<()> {
+        self.subscriptions.remove(key);
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Result<Vec<MarketDataUpdate>> {
+        Ok(self
+            .last_data
+            .iter()
+            .filter(|(key, _)| self.subscriptions.contains(key))
+            .map(|(_, update)| update.clone())
+            .collect())
+    }
+
+    fn stream(&mut self) -> Result<Receiver<MarketDataUpdate>> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.streams.push(sender);
+        Ok(receiver)
+    }
+}
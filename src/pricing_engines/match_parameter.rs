@@ -9,10 +9,51 @@ use crate::instrument::{Instrument, InstrumentTrait};
 use crate::instruments::plain_swap::PlainSwapType;
 //
 use static_id::static_id::StaticId;
+use crate::utils::platform::log_warn;
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 use rustc_hash::FxHashMap;
 //
+// The maps on tuple keys are written as lists of [key, value] since json keys must be strings.
+// An empty map ({}) written by the older versions is also read.
+pub(crate) mod tuple_key_map {
+    use rustc_hash::FxHashMap;
+    use serde::de::{DeserializeOwned, Error, IgnoredAny};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::hash::Hash;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr<K, V> {
+        Entries(Vec<(K, V)>),
+        Map(FxHashMap<String, IgnoredAny>),
+    }
+
+    pub fn serialize<K, V, S>(map: &FxHashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<FxHashMap<K, V>, D::Error>
+    where
+        K: DeserializeOwned + Eq + Hash,
+        V: DeserializeOwned,
+        D: Deserializer<'de>,
+    {
+        match Repr::<K, V>::deserialize(deserializer)? {
+            Repr::Entries(entries) => Ok(entries.into_iter().collect()),
+            Repr::Map(map) if map.is_empty() => Ok(FxHashMap::default()),
+            Repr::Map(_) => Err(D::Error::custom(
+                "a map on tuple keys must be a list of [key, value]",
+            )),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MatchParameter {
     // Underlying asset code: StaticId -> curve_id: StaticId
@@ -25,6 +66,7 @@ pub struct MatchParameter {
     //  issuer_type: IssuerType,
     //  credit_rating: CreditRating,
     //  currency: Currency) -> StaticId
+    #[serde(with = "tuple_key_map")]
     bond_discount_curve_map: FxHashMap<(StaticId, IssuerType, CreditRating, Currency), StaticId>,
     // index code: RateIndexCode -> StaticId
     rate_index_forward_curve_map: FxHashMap<StaticId, StaticId>,
@@ -33,7 +75,25 @@ pub struct MatchParameter {
     crs_curve_map: FxHashMap<Currency, StaticId>,
     //
     funding_cost_map: FxHashMap<Currency, StaticId>,
-    //
+    // spread curve id: StaticId -> base curve id: StaticId
+    // e.g., KRW AA corporate z-spread curve -> KRWGOV
+    #[serde(default)]
+    spread_curve_base_map: FxHashMap<StaticId, StaticId>,
+    // instrument id: StaticId -> counterparty or CSA id: StaticId
+    // instruments not in this map are discounted by the discount curve only
+    #[serde(default)]
+    counterparty_map: FxHashMap<StaticId, StaticId>,
+    // (counterparty or CSA id: StaticId, currency: Currency) -> funding spread curve id: StaticId
+    // the funding spread curve is applied on top of the discount curve (e.g., uncollateralized trades)
+    #[serde(default, with = "tuple_key_map")]
+    funding_spread_curve_map: FxHashMap<(StaticId, Currency), StaticId>,
+    // bond id: StaticId -> repo curve id: StaticId
+    // used for the carry of the underlying bonds in bond forward/futures pricing
+    #[serde(default)]
+    repo_curve_map: FxHashMap<StaticId, StaticId>,
+    // Currency -> repo curve id: StaticId, the default for the bonds not in repo_curve_map
+    #[serde(default)]
+    repo_default_curve_map: FxHashMap<Currency, StaticId>,
 }
 
 impl Default for MatchParameter {
@@ -53,6 +113,11 @@ impl Default for MatchParameter {
             rate_index_forward_curve_map,
             crs_curve_map,
             funding_cost_map,
+            spread_curve_base_map: FxHashMap::default(),
+            counterparty_map: FxHashMap::default(),
+            funding_spread_curve_map: FxHashMap::default(),
+            repo_curve_map: FxHashMap::default(),
+            repo_default_curve_map: FxHashMap::default(),
         }
     }
 }
@@ -73,9 +138,24 @@ impl MatchParameter {
             rate_index_forward_curve_map,
             crs_curve_map,
             funding_cost_map,
+            spread_curve_base_map: FxHashMap::default(),
+            counterparty_map: FxHashMap::default(),
+            funding_spread_curve_map: FxHashMap::default(),
+            repo_curve_map: FxHashMap::default(),
+            repo_default_curve_map: FxHashMap::default(),
         }
     }
 
+    /// spread curve id -> base curve id.
+    /// The spread curves are built on top of their base curves in Engine
+    pub fn with_spread_curve_base_map(
+        mut self,
+        spread_curve_base_map: FxHashMap<StaticId, StaticId>,
+    ) -> MatchParameter {
+        self.spread_curve_base_map = spread_curve_base_map;
+        self
+    }
+
     /// In the cases of crs, fx products, etc, this means the base_curve
     /// For example, if the undrlying fx is usdkrw, then crs_curve is krwcrs
     pub fn get_crs_curve_id(&self, instrument: &Instrument) -> Result<StaticId> {
@@ -158,7 +238,7 @@ impl MatchParameter {
                     Some(curve_id) => Ok(*curve_id),
                     None => {
                         let msg = format!("id: {:?}, issuer_type: {:?}, credit_rating: {:?}, currency: {:?}", id, issuer_type, credit_rating, instrument.get_currency());
-                        flashlog::flash_warn!("CurveNotFound"; info = msg);
+                        log_warn!("CurveNotFound"; info = msg);
                         Ok(StaticId::default())
                     },
                 }
@@ -317,6 +397,122 @@ impl MatchParameter {
     pub fn get_borrowing_curve_map(&self) -> &FxHashMap<StaticId, StaticId> {
         &self.borrowing_curve_map
     }
+
+    /// counterparty_map: instrument id -> counterparty (or CSA) id
+    /// funding_spread_curve_map: (counterparty (or CSA) id, currency) -> funding spread curve id
+    pub fn with_counterparty_funding_spread(
+        mut self,
+        counterparty_map: FxHashMap<StaticId, StaticId>,
+        funding_spread_curve_map: FxHashMap<(StaticId, Currency), StaticId>,
+    ) -> MatchParameter {
+        self.counterparty_map = counterparty_map;
+        self.funding_spread_curve_map = funding_spread_curve_map;
+        self
+    }
+
+    pub fn get_counterparty_id(&self, instrument: &Instrument) -> Option<StaticId> {
+        self.counterparty_map.get(&instrument.get_id()).copied()
+    }
+
+    /// funding spread curve of the instrument's counterparty in the given currency.
+    /// If the instrument has no counterparty or the counterparty has no funding spread in the currency,
+    /// StaticId::default() is returned, i.e., the discount curve is used as it is.
+    pub fn get_funding_spread_curve_id(
+        &self,
+        instrument: &Instrument,
+        currency: Currency,
+    ) -> StaticId {
+        match self.get_counterparty_id(instrument) {
+            Some(counterparty_id) => self
+                .funding_spread_curve_map
+                .get(&(counterparty_id, currency))
+                .copied()
+                .unwrap_or_default(),
+            None => StaticId::default(),
+        }
+    }
+
+    /// funding spread curves in all the currencies discounted in the instrument
+    pub fn get_funding_spread_curve_ids(&self, instrument: &Instrument) -> Result<Vec<StaticId>> {
+        let currencies = match instrument {
+            Instrument::PlainSwap(_) => vec![
+                instrument.get_fixed_leg_currency()?,
+                instrument.get_floating_leg_currency()?,
+            ],
+            _ => vec![instrument.get_currency()],
+        };
+        let mut res = vec![];
+        for currency in currencies {
+            let id = self.get_funding_spread_curve_id(instrument, currency);
+            if id != StaticId::default() && !res.contains(&id) {
+                res.push(id);
+            }
+        }
+        Ok(res)
+    }
+
+    pub fn get_funding_spread_curve_map(&self) -> &FxHashMap<(StaticId, Currency), StaticId> {
+        &self.funding_spread_curve_map
+    }
+
+    /// repo_curve_map: bond id -> repo curve id
+    /// repo_default_curve_map: currency -> repo curve id for the bonds not in repo_curve_map
+    pub fn with_repo_curve_map(
+        mut self,
+        repo_curve_map: FxHashMap<StaticId, StaticId>,
+        repo_default_curve_map: FxHashMap<Currency, StaticId>,
+    ) -> MatchParameter {
+        self.repo_curve_map = repo_curve_map;
+        self.repo_default_curve_map = repo_default_curve_map;
+        self
+    }
+
+    /// repo curve of the bond. The bond-level mapping precedes the currency-level default.
+    /// If neither is given, StaticId::default() is returned,
+    /// i.e., the bond is carried on its discount curve.
+    pub fn get_repo_curve_id(&self, bond_id: &StaticId, currency: Currency) -> StaticId {
+        self.repo_curve_map
+            .get(bond_id)
+            .or_else(|| self.repo_default_curve_map.get(&currency))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// repo curves of the underlying bonds of bond futures (e.g., KTBF)
+    pub fn get_repo_curve_ids(&self, instrument: &Instrument) -> Vec<StaticId> {
+        let mut res = vec![];
+        if let Instrument::KTBF(ktbf) = instrument {
+            for bond in ktbf.get_underlying_bonds().iter() {
+                let id = self.get_repo_curve_id(&bond.get_id(), bond.get_currency());
+                if id != StaticId::default() && !res.contains(&id) {
+                    res.push(id);
+                }
+            }
+        }
+        res
+    }
+
+    pub fn get_repo_curve_map(&self) -> &FxHashMap<StaticId, StaticId> {
+        &self.repo_curve_map
+    }
+
+    pub fn get_repo_default_curve_map(&self) -> &FxHashMap<Currency, StaticId> {
+        &self.repo_default_curve_map
+    }
+
+    pub fn get_spread_curve_base_map(&self) -> &FxHashMap<StaticId, StaticId> {
+        &self.spread_curve_base_map
+    }
+
+    /// base curve id if curve_id is a spread curve
+    pub fn get_base_curve_id(&self, curve_id: &StaticId) -> Option<StaticId> {
+        self.spread_curve_base_map.get(curve_id).copied()
+    }
+
+    /// true if curve_id is target_id itself or a spread curve layered on target_id
+    pub fn is_curve_or_spread_over(&self, curve_id: &StaticId, target_id: &StaticId) -> bool {
+        curve_id == target_id || self.get_base_curve_id(curve_id).as_ref() == Some(target_id)
+    }
 }
 
 #[cfg(test)]
@@ -353,7 +549,7 @@ mod tests {
             inst_type: InstType::Futures,
             currency: Currency::USD,
             unit_notional: 50.0,
-            maturity: Some(maturity_date.clone()),
+            maturity: Some(maturity_date),
             issue_date: Some(datetime!(2021-01-01 00:00:00 +00:00)),
             accounting_level: crate::AccountingLevel::L1,
         };
@@ -361,7 +557,7 @@ mod tests {
         let stock_futures = Futures::new(
             inst_info,
             100.0,
-            Some(maturity_date.clone()),
+            Some(maturity_date),
             Currency::USD,
             stock_id,
         );
@@ -394,8 +590,8 @@ mod tests {
             inst_type: InstType::PlainSwap,
             currency: Currency::KRW,
             unit_notional: 10_000_000_000.0,
-            maturity: Some(maturity_date.clone()),
-            issue_date: Some(issue_date.clone()),
+            maturity: Some(maturity_date),
+            issue_date: Some(issue_date),
             accounting_level: crate::AccountingLevel::L2,
         };
 
@@ -408,7 +604,7 @@ mod tests {
             None,
             None,
             //
-            issue_date.clone(),
+            issue_date,
             //
             Some(0.02),
             Some(cd.clone()),
@@ -486,4 +682,105 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_spread_curve_base_map() {
+        let spread_id = StaticId::from_str("KRW AA Spread", "KAP");
+        let base_id = StaticId::from_str("KRWGOV", "KAP");
+        let mut spread_curve_base_map = FxHashMap::default();
+        spread_curve_base_map.insert(spread_id, base_id);
+
+        let match_parameter =
+            MatchParameter::default().with_spread_curve_base_map(spread_curve_base_map);
+
+        assert_eq!(match_parameter.get_base_curve_id(&spread_id), Some(base_id));
+        assert_eq!(match_parameter.get_base_curve_id(&base_id), None);
+        assert!(match_parameter.is_curve_or_spread_over(&spread_id, &base_id));
+        assert!(match_parameter.is_curve_or_spread_over(&base_id, &base_id));
+        assert!(!match_parameter.is_curve_or_spread_over(&base_id, &spread_id));
+    }
+
+    #[test]
+    fn test_counterparty_funding_spread() -> Result<()> {
+        let inst_id = StaticId::from_str("KOSPI2 Call OTC", "test");
+        let counterparty_id = StaticId::from_str("Counterparty A", "test");
+        let spread_curve_id = StaticId::from_str("Counterparty A KRW Funding", "test");
+
+        let inst_info = crate::InstInfo {
+            id: inst_id,
+            name: "KOSPI2 Fut OTC".to_string(),
+            inst_type: InstType::Futures,
+            currency: Currency::KRW,
+            unit_notional: 250_000.0,
+            maturity: Some(datetime!(2025-01-01 00:00:00 +09:00)),
+            issue_date: None,
+            accounting_level: crate::AccountingLevel::L2,
+        };
+        let futures = Instrument::Futures(Futures::new(
+            inst_info,
+            350.0,
+            None,
+            Currency::KRW,
+            StaticId::from_str("KOSPI2", "KRX"),
+        ));
+
+        let mut counterparty_map = FxHashMap::default();
+        counterparty_map.insert(inst_id, counterparty_id);
+        let mut funding_spread_curve_map = FxHashMap::default();
+        funding_spread_curve_map.insert((counterparty_id, Currency::KRW), spread_curve_id);
+
+        let match_parameter = MatchParameter::default();
+        assert_eq!(match_parameter.get_funding_spread_curve_ids(&futures)?, vec![]);
+
+        let match_parameter = match_parameter
+            .with_counterparty_funding_spread(counterparty_map, funding_spread_curve_map);
+        assert_eq!(match_parameter.get_counterparty_id(&futures), Some(counterparty_id));
+        assert_eq!(
+            match_parameter.get_funding_spread_curve_id(&futures, Currency::KRW),
+            spread_curve_id
+        );
+        assert_eq!(
+            match_parameter.get_funding_spread_curve_id(&futures, Currency::USD),
+            StaticId::default()
+        );
+        assert_eq!(
+            match_parameter.get_funding_spread_curve_ids(&futures)?,
+            vec![spread_curve_id]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_repo_curve_map() {
+        let bond_id = StaticId::from_str("KTB 3.25 2727", "KRX");
+        let other_bond_id = StaticId::from_str("KTB 3.00 2909", "KRX");
+        let special_repo_id = StaticId::from_str("KTB 3.25 2727 Repo", "test");
+        let general_repo_id = StaticId::from_str("KRW GC Repo", "test");
+
+        let mut repo_curve_map = FxHashMap::default();
+        repo_curve_map.insert(bond_id, special_repo_id);
+        let mut repo_default_curve_map = FxHashMap::default();
+        repo_default_curve_map.insert(Currency::KRW, general_repo_id);
+
+        let match_parameter = MatchParameter::default();
+        assert_eq!(
+            match_parameter.get_repo_curve_id(&bond_id, Currency::KRW),
+            StaticId::default()
+        );
+
+        let match_parameter =
+            match_parameter.with_repo_curve_map(repo_curve_map, repo_default_curve_map);
+        assert_eq!(
+            match_parameter.get_repo_curve_id(&bond_id, Currency::KRW),
+            special_repo_id
+        );
+        assert_eq!(
+            match_parameter.get_repo_curve_id(&other_bond_id, Currency::KRW),
+            general_repo_id
+        );
+        assert_eq!(
+            match_parameter.get_repo_curve_id(&other_bond_id, Currency::USD),
+            StaticId::default()
+        );
+    }
 }
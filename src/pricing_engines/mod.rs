@@ -1,18 +1,35 @@
 pub mod calculation_configuration;
 pub mod calculation_result;
+pub mod calculation_statistics;
+pub mod cost_model;
+pub mod date_sweep;
 pub mod engine;
 pub mod option_analytic_pricer;
 pub mod pricer;
+#[cfg(feature = "async")]
+pub mod async_calculation;
+pub mod bond_analytics;
 pub mod bond_pricer;
+pub mod cancellation;
 pub mod cash_pricer;
 pub mod engine_generator;
 pub mod futures_pricer;
 pub mod fx_futures_pricer;
+pub mod greek_diagnostics;
 pub mod identity_pricer;
 pub mod krx_yield_pricer;
 pub mod ktbf_pricer;
+pub mod market_data_source;
 pub mod match_parameter;
+pub mod netting_set;
 pub mod npv_result;
+pub mod par_rate_risk;
 pub mod plain_swap_pricer;
+pub mod pnl_explain;
+pub mod pnl_predictor;
+pub mod portfolio_result;
 pub mod pricer_factory;
+pub mod progress;
+pub mod pricing_session;
+pub mod result_diff;
 pub mod unit_pricer;
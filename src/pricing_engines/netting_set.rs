@@ -0,0 +1,263 @@
+use crate::currency::{Currency, FxCode};
+use crate::definitions::Real;
+use crate::pricing_engines::calculation_result::CalculationResult;
+use crate::pricing_engines::portfolio_result::PortfolioResult;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+
+/// Counterparty and netting set (e.g., an ISDA master agreement with a CSA) of an instrument.
+/// A netting set belongs to a single counterparty, while a counterparty can have several netting sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NettingSetInfo {
+    counterparty: StaticId,
+    netting_set: StaticId,
+}
+
+impl NettingSetInfo {
+    pub fn new(counterparty: StaticId, netting_set: StaticId) -> NettingSetInfo {
+        NettingSetInfo {
+            counterparty,
+            netting_set,
+        }
+    }
+
+    pub fn get_counterparty(&self) -> StaticId {
+        self.counterparty
+    }
+
+    pub fn get_netting_set(&self) -> StaticId {
+        self.netting_set
+    }
+}
+
+/// Instrument id -> NettingSetInfo kept aside of the instruments,
+/// so that the results can be grouped for XVA, SA-CCR and margin.
+/// Every instrument in the results to be grouped must be assigned.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NettingSets {
+    assignments: FxHashMap<StaticId, NettingSetInfo>,
+}
+
+impl NettingSets {
+    pub fn new() -> NettingSets {
+        NettingSets::default()
+    }
+
+    pub fn with_instrument(
+        mut self,
+        inst_id: StaticId,
+        counterparty: StaticId,
+        netting_set: StaticId,
+    ) -> Result<NettingSets> {
+        if let Some(owner) = self
+            .assignments
+            .values()
+            .find(|info| info.netting_set == netting_set && info.counterparty != counterparty)
+        {
+            return Err(anyhow!(
+                "({}:{}) netting set {} belongs to {}, not to {}",
+                file!(),
+                line!(),
+                netting_set,
+                owner.counterparty,
+                counterparty,
+            ));
+        }
+        if let Some(info) = self.assignments.get(&inst_id) {
+            return Err(anyhow!(
+                "({}:{}) {} is already in the netting set {}",
+                file!(),
+                line!(),
+                inst_id,
+                info.netting_set,
+            ));
+        }
+        self.assignments
+            .insert(inst_id, NettingSetInfo::new(counterparty, netting_set));
+        Ok(self)
+    }
+
+    pub fn get_netting_set_info(&self, inst_id: &StaticId) -> Option<&NettingSetInfo> {
+        self.assignments.get(inst_id)
+    }
+
+    /// sorted netting set ids
+    pub fn get_netting_set_ids(&self) -> Vec<StaticId> {
+        let mut res: Vec<StaticId> = self
+            .assignments
+            .values()
+            .map(|info| info.netting_set)
+            .collect();
+        res.sort_by_key(|id| id.to_string());
+        res.dedup();
+        res
+    }
+
+    /// sorted counterparty ids
+    pub fn get_counterparty_ids(&self) -> Vec<StaticId> {
+        let mut res: Vec<StaticId> = self
+            .assignments
+            .values()
+            .map(|info| info.counterparty)
+            .collect();
+        res.sort_by_key(|id| id.to_string());
+        res.dedup();
+        res
+    }
+
+    /// sorted instrument ids in the netting set
+    pub fn instruments_in_netting_set(&self, netting_set: &StaticId) -> Vec<StaticId> {
+        self.instruments_where(|info| info.netting_set == *netting_set)
+    }
+
+    /// sorted instrument ids of the counterparty over all its netting sets
+    pub fn instruments_of_counterparty(&self, counterparty: &StaticId) -> Vec<StaticId> {
+        self.instruments_where(|info| info.counterparty == *counterparty)
+    }
+
+    /// netting set id -> aggregated result of the instruments in the netting set
+    pub fn aggregate_by_netting_set(
+        &self,
+        results: &FxHashMap<StaticId, CalculationResult>,
+        target_currency: Currency,
+        fx_map: &FxHashMap<FxCode, Real>,
+    ) -> Result<FxHashMap<StaticId, PortfolioResult>> {
+        self.aggregate_by(results, target_currency, fx_map, |info| info.netting_set)
+    }
+
+    /// counterparty id -> aggregated result of the instruments with the counterparty
+    pub fn aggregate_by_counterparty(
+        &self,
+        results: &FxHashMap<StaticId, CalculationResult>,
+        target_currency: Currency,
+        fx_map: &FxHashMap<FxCode, Real>,
+    ) -> Result<FxHashMap<StaticId, PortfolioResult>> {
+        self.aggregate_by(results, target_currency, fx_map, |info| info.counterparty)
+    }
+
+    fn instruments_where(&self, predicate: impl Fn(&NettingSetInfo) -> bool) -> Vec<StaticId> {
+        let mut res: Vec<StaticId> = self
+            .assignments
+            .iter()
+            .filter(|(_, info)| predicate(info))
+            .map(|(inst_id, _)| *inst_id)
+            .collect();
+        res.sort_by_key(|id| id.to_string());
+        res
+    }
+
+    fn aggregate_by(
+        &self,
+        results: &FxHashMap<StaticId, CalculationResult>,
+        target_currency: Currency,
+        fx_map: &FxHashMap<FxCode, Real>,
+        key: impl Fn(&NettingSetInfo) -> StaticId,
+    ) -> Result<FxHashMap<StaticId, PortfolioResult>> {
+        let mut inst_ids: Vec<&StaticId> = results.keys().collect();
+        inst_ids.sort_by_key(|id| id.to_string());
+
+        let mut res: FxHashMap<StaticId, PortfolioResult> = FxHashMap::default();
+        for inst_id in inst_ids {
+            let info = self.assignments.get(inst_id).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) {} is not assigned to any netting set",
+                    file!(),
+                    line!(),
+                    inst_id,
+                )
+            })?;
+            res.entry(key(info))
+                .or_insert_with(|| PortfolioResult::new(target_currency))
+                .add_result(&results[inst_id], fx_map)?;
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruments::inst_info::InstInfo;
+    use crate::pricing_engines::npv_result::NpvResult;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_netting_sets() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let (bank_a, bank_b) = (
+            StaticId::from_str("Bank A", "Counterparty"),
+            StaticId::from_str("Bank B", "Counterparty"),
+        );
+        let (isda_a1, isda_a2, isda_b) = (
+            StaticId::from_str("ISDA A1", "NettingSet"),
+            StaticId::from_str("ISDA A2", "NettingSet"),
+            StaticId::from_str("ISDA B", "NettingSet"),
+        );
+
+        let mut results = FxHashMap::default();
+        let mut netting_sets = NettingSets::new();
+        for (name, npv, counterparty, netting_set) in [
+            ("IRS1", 100.0, bank_a, isda_a1),
+            ("IRS2", -30.0, bank_a, isda_a1),
+            ("IRS3", 50.0, bank_a, isda_a2),
+            ("IRS4", -20.0, bank_b, isda_b),
+        ] {
+            let inst_id = StaticId::from_str(name, "OTC");
+            let inst_info = InstInfo {
+                id: inst_id,
+                name: name.to_string(),
+                currency: Currency::KRW,
+                ..Default::default()
+            };
+            let mut result = CalculationResult::new(inst_info, dt);
+            result.set_npv(NpvResult::new_from_npv(npv));
+            result.set_value()?;
+            results.insert(inst_id, result);
+            netting_sets = netting_sets.with_instrument(inst_id, counterparty, netting_set)?;
+        }
+
+        assert_eq!(
+            netting_sets.get_netting_set_ids(),
+            vec![isda_a1, isda_a2, isda_b]
+        );
+        assert_eq!(netting_sets.get_counterparty_ids(), vec![bank_a, bank_b]);
+        assert_eq!(netting_sets.instruments_in_netting_set(&isda_a1).len(), 2);
+        assert_eq!(netting_sets.instruments_of_counterparty(&bank_a).len(), 3);
+        let irs3 = StaticId::from_str("IRS3", "OTC");
+        assert_eq!(
+            netting_sets.get_netting_set_info(&irs3),
+            Some(&NettingSetInfo::new(bank_a, isda_a2))
+        );
+
+        let fx_map = FxHashMap::default();
+        let by_netting_set =
+            netting_sets.aggregate_by_netting_set(&results, Currency::KRW, &fx_map)?;
+        assert_eq!(by_netting_set[&isda_a1].get_value(), 70.0);
+        assert_eq!(by_netting_set[&isda_a2].get_value(), 50.0);
+        assert_eq!(by_netting_set[&isda_b].get_value(), -20.0);
+
+        let by_counterparty =
+            netting_sets.aggregate_by_counterparty(&results, Currency::KRW, &fx_map)?;
+        assert_eq!(by_counterparty[&bank_a].get_value(), 120.0);
+        assert_eq!(by_counterparty[&bank_a].get_instrument_ids().len(), 3);
+
+        // a netting set has a single counterparty, and an instrument is in a single netting set
+        assert!(netting_sets
+            .clone()
+            .with_instrument(StaticId::from_str("IRS5", "OTC"), bank_b, isda_a1)
+            .is_err());
+        assert!(netting_sets
+            .clone()
+            .with_instrument(irs3, bank_a, isda_a1)
+            .is_err());
+
+        let unassigned = NettingSets::new();
+        assert!(unassigned
+            .aggregate_by_netting_set(&results, Currency::KRW, &fx_map)
+            .is_err());
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
+use crate::currency::Currency;
 use crate::definitions::Real;
+use crate::instruments::cashflow::{Cashflow, CashflowLeg, CashflowType};
 use crate::utils::number_format::write_number_with_commas;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -74,6 +76,8 @@ impl NpvResult {
         self.npv
     }
 
+    /// probability weighted amounts summed on the payment dates,
+    /// e.g., a coupon and the redemption of an autocallable on the same observation
     pub fn get_expected_coupon_amount(&self) -> Result<FxHashMap<OffsetDateTime, Real>> {
         let mut res = FxHashMap::default();
         for (id, (datetime, amount)) in self.cashflow_amounts.iter() {
@@ -81,14 +85,38 @@ impl NpvResult {
                 .cashflow_probabilities
                 .get(id)
                 .ok_or_else(|| anyhow::anyhow!("No probability found for coupon id {}", id))?;
-            res.insert(*datetime, *amount * prob.1);
+            *res.entry(*datetime).or_insert(0.0) += *amount * prob.1;
         }
         Ok(res)
     }
 
+    /// expected amounts in the currency sorted by the payment date,
+    /// which are the cashflows of the instruments priced on the payment probabilities, e.g., by simulation
+    pub fn get_expected_cashflows(&self, currency: Currency) -> Result<Vec<Cashflow>> {
+        let mut res = self
+            .get_expected_coupon_amount()?
+            .into_iter()
+            .map(|(datetime, amount)| {
+                Cashflow::new(
+                    datetime,
+                    amount,
+                    currency,
+                    CashflowLeg::Single,
+                    CashflowType::Expected,
+                )
+            })
+            .collect::<Vec<Cashflow>>();
+        res.sort_by_key(|cashflow| *cashflow.get_payment_date());
+        Ok(res)
+    }
+
     pub fn get_cashflow_amounts(&self) -> &FxHashMap<usize, (OffsetDateTime, Real)> {
         &self.cashflow_amounts
     }
+
+    pub fn get_cashflow_probabilities(&self) -> &FxHashMap<usize, (OffsetDateTime, Real)> {
+        &self.cashflow_probabilities
+    }
 }
 
 impl Default for NpvResult {
@@ -166,3 +194,34 @@ impl Div<Real> for NpvResult {
         self.npv / rhs
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_expected_cashflows() -> Result<()> {
+        // an autocallable redeemed at 6M with 60% and at 1Y otherwise
+        let early = datetime!(2024-09-13 16:30:00 +09:00);
+        let maturity = datetime!(2025-03-13 16:30:00 +09:00);
+        let mut amounts = FxHashMap::default();
+        let mut probabilities = FxHashMap::default();
+        amounts.insert(0, (early, 1.03));
+        probabilities.insert(0, (early, 0.6));
+        amounts.insert(1, (maturity, 0.06));
+        probabilities.insert(1, (maturity, 0.4));
+        amounts.insert(2, (maturity, 1.0));
+        probabilities.insert(2, (maturity, 0.4));
+        let npv_result = NpvResult::new(1.0, amounts, probabilities);
+
+        let cashflows = npv_result.get_expected_cashflows(Currency::KRW)?;
+        assert_eq!(cashflows.len(), 2);
+        assert_eq!(cashflows[0].get_payment_date(), &early);
+        assert!((cashflows[0].get_amount() - 0.618).abs() < 1.0e-6);
+        assert_eq!(cashflows[1].get_payment_date(), &maturity);
+        assert!((cashflows[1].get_amount() - 0.424).abs() < 1.0e-6);
+        assert_eq!(cashflows[1].get_cashflow_type(), CashflowType::Expected);
+        Ok(())
+    }
+}
@@ -1,38 +1,45 @@
 use crate::definitions::Real;
 use crate::enums::OptionType;
 use crate::evaluation_date::EvaluationDate;
+use crate::math::dual::Dual;
 use crate::instrument::Instrument;
 use crate::instrument::InstrumentTrait;
 use crate::parameters::market_price::MarketPrice;
+use crate::parameters::past_price::DailyClosePrice;
 use crate::parameters::{quanto::Quanto, volatility::Volatility, zero_curve::ZeroCurve};
-use crate::pricing_engines::pricer::PricerTrait;
+use crate::pricing_engines::pricer::{FirstOrderGreeks, PricerTrait};
 use crate::pricing_engines::{futures_pricer::FuturesPricer, npv_result::NpvResult};
 use crate::time::{calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar};
 //
 use anyhow::{anyhow, Context, Result};
-
+use rustc_hash::FxHashMap;
 use statrs::distribution::{ContinuousCDF, Normal};
-use std::{cell::RefCell, rc::Rc};
+use std::sync::{Arc, RwLock};
+use time::OffsetDateTime;
 
+/// If market_close is given, the option booked with the maturity date only (at midnight) expires
+/// at the close of the underlying market on the date, so that the time to expiry is counted in hours on the expiry date.
+/// A maturity with time, e.g., 15:20 KST of KOSPI200 options by KrxExpiryRule, is kept.
 pub struct OptionAnalyticPricer {
-    evaluation_date: Rc<RefCell<EvaluationDate>>,
-    market_price: Rc<RefCell<MarketPrice>>,
+    evaluation_date: Arc<RwLock<EvaluationDate>>,
+    market_price: Arc<RwLock<MarketPrice>>,
     futures_helper: FuturesPricer,
-    discount_curve: Rc<RefCell<ZeroCurve>>,
-    volatility: Rc<RefCell<Volatility>>,
-    quanto: Option<Rc<RefCell<Quanto>>>,
+    discount_curve: Arc<RwLock<ZeroCurve>>,
+    volatility: Arc<RwLock<Volatility>>,
+    quanto: Option<Arc<RwLock<Quanto>>>,
+    market_close: Option<Arc<DailyClosePrice>>,
     time_calculator: NullCalendar,
 }
 
 impl OptionAnalyticPricer {
     pub fn new(
-        evaluation_date: Rc<RefCell<EvaluationDate>>,
-        market_price: Rc<RefCell<MarketPrice>>,
-        collateral_curve: Rc<RefCell<ZeroCurve>>,
-        borrowing_curve: Rc<RefCell<ZeroCurve>>,
-        discount_curve: Rc<RefCell<ZeroCurve>>,
-        volatility: Rc<RefCell<Volatility>>,
-        quanto: Option<Rc<RefCell<Quanto>>>,
+        evaluation_date: Arc<RwLock<EvaluationDate>>,
+        market_price: Arc<RwLock<MarketPrice>>,
+        collateral_curve: Arc<RwLock<ZeroCurve>>,
+        borrowing_curve: Arc<RwLock<ZeroCurve>>,
+        discount_curve: Arc<RwLock<ZeroCurve>>,
+        volatility: Arc<RwLock<Volatility>>,
+        quanto: Option<Arc<RwLock<Quanto>>>,
     ) -> OptionAnalyticPricer {
         let futures_helper = FuturesPricer::new(
             //evaluation_date.clone(),
@@ -48,30 +55,138 @@ impl OptionAnalyticPricer {
             discount_curve,
             volatility,
             quanto,
+            market_close: None,
             time_calculator: NullCalendar::new(),
         }
     }
+
+    pub fn with_market_close(mut self, market_close: Arc<DailyClosePrice>) -> OptionAnalyticPricer {
+        self.market_close = Some(market_close);
+        self
+    }
+
+    /// the maturity at the close time of the underlying market if given and the maturity is a date only
+    fn get_expiry(&self, instrument: &Instrument) -> Result<OffsetDateTime> {
+        let maturity = instrument.get_maturity().with_context(|| {
+            anyhow!(
+                "({}:{}) Failed to get maturity of {} ({})",
+                file!(),
+                line!(),
+                instrument.get_name(),
+                instrument.get_code_str(),
+            )
+        })?;
+        Ok(match &self.market_close {
+            Some(market_close) => market_close.get_expiry_datetime(maturity),
+            None => *maturity,
+        })
+    }
+}
+
+impl OptionAnalyticPricer {
+    /// The automatic differentiation is applied only for a flat volatility and the curves without base curves,
+    /// where the parallel shifts of the engine are the shifts of the dual variables below.
+    fn supports_automatic_differentiation(&self) -> bool {
+        let forward_curve = self.futures_helper.get_forward_curve();
+        self.volatility.read().unwrap().get_flat_volatility().is_some()
+            && [
+                forward_curve.get_collateral_curve(),
+                forward_curve.get_borrowing_curve(),
+                &self.discount_curve,
+            ]
+            .iter()
+            .all(|curve| curve.read().unwrap().get_base_curves().is_empty())
+    }
 }
 
 impl PricerTrait for OptionAnalyticPricer {
+    /// Black formula on dual numbers of (spot, volatility, collateral rate, borrowing rate, discount rate).
+    /// The quanto adjustment is differentiated in the volatility of the underlying only
+    /// and its dependence on the forward moneyness is held fixed.
+    fn first_order_greeks(&self, instrument: &Instrument) -> Result<Option<FirstOrderGreeks>> {
+        if !self.supports_automatic_differentiation() {
+            return Ok(None);
+        }
+        let maturity = &self.get_expiry(instrument)?;
+        let t = self
+            .time_calculator
+            .get_time_difference(self.evaluation_date.read().unwrap().get_date(), maturity);
+        if t <= 0.0 {
+            return Ok(None);
+        }
+
+        let forward_curve = self.futures_helper.get_forward_curve();
+        let spot = self.market_price.read().unwrap().get_value();
+        let zero = Dual::<5>::constant(0.0);
+        let fwd = forward_curve.get_forward_dual(
+            Dual::variable(spot, 0),
+            Dual::variable(0.0, 2),
+            Dual::variable(0.0, 3),
+            maturity,
+        )?;
+        let strike = instrument.get_strike()?;
+        let forward_moneyness = strike / fwd.get_value();
+
+        let vol = match self.volatility.read().unwrap().get_flat_volatility() {
+            Some(vol) => Dual::variable(vol, 1),
+            None => return Ok(None),
+        };
+        let total_deviation = vol * t.sqrt();
+        let total_variance = total_deviation * total_deviation;
+        let quanto_adjustment = match &self.quanto {
+            Some(quanto) => vol * t * quanto.read().unwrap().quanto_adjust(t, forward_moneyness),
+            None => zero,
+        };
+
+        let y = (strike / fwd).ln();
+        let dsc = self
+            .discount_curve
+            .read().unwrap()
+            .get_discount_factor_dual(t, Dual::variable(0.0, 4))?;
+
+        let d1 = (-y + total_variance / 2.0 - quanto_adjustment) / total_deviation;
+        let d2 = d1 - total_deviation;
+        let (nd1, nd2) = (d1.norm_cdf(), d2.norm_cdf());
+
+        let npv = match instrument.get_option_type()? {
+            OptionType::Call => dsc * (fwd * nd1 - nd2 * strike),
+            OptionType::Put => dsc * ((1.0 - nd2) * strike - fwd * (1.0 - nd1)),
+        };
+
+        let underlying_id = self.market_price.read().unwrap().get_id();
+        let mut rates = FxHashMap::default();
+        for (curve, index) in [
+            (forward_curve.get_collateral_curve(), 2),
+            (forward_curve.get_borrowing_curve(), 3),
+            (&self.discount_curve, 4),
+        ] {
+            *rates.entry(curve.read().unwrap().get_id()).or_insert(0.0) += npv.get_derivative(index);
+        }
+
+        Ok(Some(FirstOrderGreeks {
+            npv: npv.get_value(),
+            spot: FxHashMap::from_iter([(underlying_id, npv.get_derivative(0) * spot)]),
+            volatility: FxHashMap::from_iter([(underlying_id, npv.get_derivative(1))]),
+            rates,
+        }))
+    }
+
     fn npv(&self, instrument: &Instrument) -> Result<Real> {
-        let maturity = instrument
-            .get_maturity()
-            .context("(OptionAnalyticPricer:npv) Failed to get maturity")?;
+        let maturity = &self.get_expiry(instrument)?;
         let fwd = self.futures_helper.fair_forward(maturity)?;
         let strike = instrument.get_strike()?;
         let forward_moneyness = strike / fwd;
         let t = self
             .time_calculator
-            .get_time_difference(self.evaluation_date.borrow().get_date(), maturity);
+            .get_time_difference(self.evaluation_date.read().unwrap().get_date(), maturity);
 
         let total_variance = self
             .volatility
-            .borrow()
+            .read().unwrap()
             .total_variance(t, forward_moneyness)?;
         let total_deviation = self
             .volatility
-            .borrow()
+            .read().unwrap()
             .total_deviation(t, forward_moneyness)?;
 
         if instrument.get_currency() != instrument.get_underlying_currency()?
@@ -84,23 +199,23 @@ impl PricerTrait for OptionAnalyticPricer {
                     line!(),
                     instrument.get_name(),
                     instrument.get_code_str(),
-                    self.market_price.borrow().get_name(),
+                    self.market_price.read().unwrap().get_name(),
                 )
             };
 
             return Err(err());
         }
 
-        let vol = self.volatility.borrow().get_value(t, forward_moneyness);
+        let vol = self.volatility.read().unwrap().get_value(t, forward_moneyness);
         let quanto_adjustment = match &self.quanto {
-            Some(quanto) => vol * t * quanto.borrow().quanto_adjust(t, forward_moneyness),
+            Some(quanto) => vol * t * quanto.read().unwrap().quanto_adjust(t, forward_moneyness),
             None => 0.0,
         };
 
         let y = forward_moneyness.ln();
         let option_type = instrument.get_option_type()?;
 
-        let dsc = self.discount_curve.borrow().get_discount_factor(t)?;
+        let dsc = self.discount_curve.read().unwrap().get_discount_factor(t)?;
 
         let d1 = (-y + total_variance / 2.0 - quanto_adjustment) / total_deviation;
         let d2 = d1 - total_deviation;
@@ -125,10 +240,14 @@ impl PricerTrait for OptionAnalyticPricer {
 pub mod test {
     use super::*;
     use crate::currency::Currency;
-    use crate::enums::{OptionDailySettlementType, OptionExerciseType, OptionType, StickynessType};
+    use crate::enums::{
+        OptionDailySettlementType, OptionExerciseType, OptionType, StickynessType,
+        VolatilityTimeInterpolation,
+    };
     use crate::instrument::Instrument;
     use crate::instruments::vanilla_option::VanillaOption;
     use crate::parameters::market_price::MarketPrice;
+    use crate::parameters::volatilities::constant_volatility::ConstantVolatility;
     use crate::parameters::volatilities::local_volatility_surface::LocalVolatilitySurface;
     use crate::parameters::{
         quanto::Quanto, volatilities::volatiltiy_interpolator::VolatilityInterplator,
@@ -136,6 +255,7 @@ pub mod test {
     use crate::data::{
         vector_data::VectorData,
         surface_data::SurfaceData,
+        value_data::ValueData,
     };
     use crate::{
         InstType,
@@ -143,7 +263,7 @@ pub mod test {
     };
     use anyhow::Result;
     use ndarray::Array1;
-    use std::{cell::RefCell, rc::Rc};
+    use std::sync::{Arc, RwLock};
     use time::macros::datetime;
     use static_id::static_id::StaticId;
     use crate::Tenor;
@@ -151,12 +271,12 @@ pub mod test {
     #[test]
     fn test_option_analytic_pricer_npv() -> Result<()> {
         let eval_date = datetime!(2024-01-02 16:30:00 +09:00);
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(eval_date.clone())));
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_date)));
         let spot = 357.38;
         let id = StaticId::from_str("KOSPI2", "KRX");
-        let market_price = Rc::new(RefCell::new(MarketPrice::new(
+        let market_price = Arc::new(RwLock::new(MarketPrice::new(
             spot,
-            eval_date.clone(),
+            eval_date,
             None,
             Currency::KRW,
             "KOSPI2".to_string(),
@@ -165,25 +285,23 @@ pub mod test {
 
         let discount_curve_data = VectorData::test_curve_data(0.03, Currency::KRW)?;
         
-        let discount_curve = Rc::new(RefCell::new(ZeroCurve::new(
+        let discount_curve = Arc::new(RwLock::new(ZeroCurve::new(
             evaluation_date.clone(),
             &discount_curve_data,
             "Option Test Curve".to_string(),
             StaticId::from_str("Option Test Curve", "test"),
         )?));
 
-        let surface_data = SurfaceData::test_data(spot, Some(eval_date.clone()))?;
+        let surface_data = SurfaceData::test_data(spot, Some(eval_date))?;
         
-        let vega_structure_tenors = vec![
-            String::from("1M"),
+        let vega_structure_tenors = [String::from("1M"),
             String::from("2M"),
             String::from("3M"),
             String::from("6M"),
             String::from("9M"),
             String::from("1Y"),
             String::from("2Y"),
-            String::from("3Y"),
-        ];
+            String::from("3Y")];
 
         let vega_structure_tenors = vega_structure_tenors
             .iter()
@@ -201,20 +319,28 @@ pub mod test {
             VolatilityInterplator::default(),
             "KOSPI2 Local Volatility".to_string(),
             StaticId::from_str("KOSPI2 Local Volatility", "KRX"),
-        )
-        .with_market_surface(
+        );
+        // the expected npv below is from the linear interpolation of volatility in the expiry dimension
+        let total_variance_volatility = local_volatility.clone().with_market_surface(
             &surface_data,
             vega_structure_tenors.clone(),
             vega_matrix_spot_moneyness.clone(),
         )?;
+        let local_volatility = local_volatility
+            .with_time_interpolation(VolatilityTimeInterpolation::Volatility)
+            .with_market_surface(
+                &surface_data,
+                vega_structure_tenors.clone(),
+                vega_matrix_spot_moneyness.clone(),
+            )?;
 
         let vol = Volatility::LocalVolatilitySurface(local_volatility);
 
-        let volatility = Rc::new(RefCell::new(vol));
+        let volatility = Arc::new(RwLock::new(vol));
 
-        volatility.borrow_mut().build()?;
+        volatility.write().unwrap().build()?;
 
-        let quanto = Rc::new(RefCell::new(Quanto::default()));
+        let quanto = Arc::new(RwLock::new(Quanto::default()));
 
         let pricer = OptionAnalyticPricer::new(
             evaluation_date.clone(),
@@ -235,8 +361,8 @@ pub mod test {
             inst_type: InstType::VanillaOption,
             accounting_level: crate::AccountingLevel::L1,
             currency: Currency::KRW,
-            issue_date: Some(issue_date.clone()),
-            maturity: Some(maturity.clone()),
+            issue_date: Some(issue_date),
+            maturity: Some(maturity),
             unit_notional: 250_000.0,
         };
 
@@ -262,6 +388,244 @@ pub mod test {
             expected_npv
         );
 
+        // total variance interpolation between the expiries moves the price only slightly
+        *volatility.write().unwrap() = Volatility::LocalVolatilitySurface(total_variance_volatility);
+        volatility.write().unwrap().build()?;
+        let total_variance_npv = pricer.npv(&inst)?;
+        assert!(
+            (total_variance_npv - npv).abs() / npv < 0.01,
+            "total variance npv: {}, npv: {}",
+            total_variance_npv,
+            npv
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_option_analytic_pricer_first_order_greeks() -> Result<()> {
+        let eval_date = datetime!(2024-01-02 16:30:00 +09:00);
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_date)));
+        let spot = 357.38;
+        let id = StaticId::from_str("KOSPI2", "KRX");
+        let market_price = Arc::new(RwLock::new(MarketPrice::new(
+            spot,
+            eval_date,
+            None,
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            id,
+        )));
+        let mut curves = vec![];
+        for (rate, name) in [(0.035, "KSD"), (0.01, "KOSPI2 Borrowing"), (0.04, "KRW OIS")] {
+            let data = VectorData::test_curve_data(rate, Currency::KRW)?;
+            curves.push(Arc::new(RwLock::new(ZeroCurve::new(
+                evaluation_date.clone(),
+                &data,
+                name.to_string(),
+                StaticId::from_str(name, "test"),
+            )?)));
+        }
+        let volatility = Arc::new(RwLock::new(Volatility::ConstantVolatility(
+            ConstantVolatility::new(0.2, "KOSPI2 Volatility".to_string(), id),
+        )));
+        let pricer = OptionAnalyticPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            curves[0].clone(),
+            curves[1].clone(),
+            curves[2].clone(),
+            volatility.clone(),
+            None,
+        );
+
+        let option = VanillaOption::new(
+            InstInfo {
+                id: StaticId::from_str("KOSPI2 Call Option", "KRX"),
+                name: "KOSPI2 Call Option".to_string(),
+                inst_type: InstType::VanillaOption,
+                accounting_level: crate::AccountingLevel::L1,
+                currency: Currency::KRW,
+                issue_date: Some(datetime!(2023-09-15 16:30:00 +09:00)),
+                maturity: Some(datetime!(2024-09-15 16:30:00 +09:00)),
+                unit_notional: 250_000.0,
+            },
+            spot * 1.05,
+            None,
+            id,
+            Currency::KRW,
+            OptionType::Call,
+            OptionExerciseType::European,
+            OptionDailySettlementType::NotSettled,
+        );
+        let inst = Instrument::VanillaOption(option);
+        let npv = pricer.npv(&inst)?;
+        let greeks = pricer
+            .first_order_greeks(&inst)?
+            .expect("constant volatility supports the automatic differentiation");
+        assert!((greeks.npv - npv).abs() < 1.0e-4);
+
+        // central differences
+        let h = 0.001;
+        market_price.write().unwrap().set_price(spot * (1.0 + h));
+        let npv_up = pricer.npv(&inst)?;
+        market_price.write().unwrap().set_price(spot * (1.0 - h));
+        let npv_down = pricer.npv(&inst)?;
+        market_price.write().unwrap().set_price(spot);
+        let delta = (npv_up - npv_down) / (2.0 * h);
+        assert!((greeks.spot[&id] - delta).abs() < 1.0e-3 * delta.abs());
+
+        volatility.write().unwrap().bump_volatility(None, None, None, None, h)?;
+        let npv_up = pricer.npv(&inst)?;
+        volatility.write().unwrap().bump_volatility(None, None, None, None, -2.0 * h)?;
+        let npv_down = pricer.npv(&inst)?;
+        volatility.write().unwrap().bump_volatility(None, None, None, None, h)?;
+        let vega = (npv_up - npv_down) / (2.0 * h);
+        assert!((greeks.volatility[&id] - vega).abs() < 1.0e-3 * vega.abs());
+
+        for curve in curves.iter() {
+            curve.write().unwrap().bump_time_interval(None, None, h)?;
+            let npv_up = pricer.npv(&inst)?;
+            curve.write().unwrap().bump_time_interval(None, None, -2.0 * h)?;
+            let npv_down = pricer.npv(&inst)?;
+            curve.write().unwrap().bump_time_interval(None, None, h)?;
+            let rho = (npv_up - npv_down) / (2.0 * h);
+            let ad_rho = greeks.rates[&curve.read().unwrap().get_id()];
+            assert!(
+                (ad_rho - rho).abs() < 1.0e-2 * rho.abs(),
+                "curve: {}, ad rho: {}, fd rho: {}",
+                curve.read().unwrap().get_id(),
+                ad_rho,
+                rho
+            );
+        }
+
+        // a flat local volatility surface is differentiated as the constant volatility,
+        // but the others are left to bump and revalue
+        let surface = LocalVolatilitySurface::initialize(
+            evaluation_date.clone(),
+            market_price.clone(),
+            curves[0].clone(),
+            curves[1].clone(),
+            StickynessType::StickyToMoneyness,
+            VolatilityInterplator::default(),
+            "KOSPI2 Local Volatility".to_string(),
+            id,
+        );
+        let vega_structure_tenors = ["1M", "3M", "6M", "1Y", "2Y"]
+            .iter()
+            .map(|x| Tenor::new_from_string(x).unwrap())
+            .collect::<Vec<Tenor>>();
+        let flat_surface = surface.clone().with_constant_volatility(
+            &ValueData::new(0.2, Some(eval_date), Currency::KRW, "KOSPI2".to_string(), id)?,
+            vega_structure_tenors,
+            Array1::linspace(0.6, 1.4, 17),
+        )?;
+        *volatility.write().unwrap() = Volatility::LocalVolatilitySurface(flat_surface);
+        volatility.write().unwrap().build()?;
+        let flat_greeks = pricer
+            .first_order_greeks(&inst)?
+            .expect("flat surface supports the automatic differentiation");
+        assert!((flat_greeks.volatility[&id] - greeks.volatility[&id]).abs() < 1.0e-2);
+
+        *volatility.write().unwrap() = Volatility::LocalVolatilitySurface(surface.with_market_surface(
+            &SurfaceData::test_data(spot, Some(eval_date))?,
+            vec![Tenor::new_from_string("1Y")?],
+            Array1::linspace(0.6, 1.4, 17),
+        )?);
+        volatility.write().unwrap().build()?;
+        assert!(pricer.first_order_greeks(&inst)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_option_analytic_pricer_market_close() -> Result<()> {
+        // evaluated at 10:00 on the expiry date of KOSPI2 options
+        let eval_date = datetime!(2024-06-13 10:00:00 +09:00);
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(eval_date)));
+        let spot = 357.38;
+        let id = StaticId::from_str("KOSPI2", "KRX");
+        let market_price = Arc::new(RwLock::new(MarketPrice::new(
+            spot,
+            eval_date,
+            None,
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            id,
+        )));
+        let curve = Arc::new(RwLock::new(ZeroCurve::new(
+            evaluation_date.clone(),
+            &VectorData::test_curve_data(0.03, Currency::KRW)?,
+            "KRW OIS".to_string(),
+            StaticId::from_str("KRW OIS", "test"),
+        )?));
+        let volatility = Arc::new(RwLock::new(Volatility::ConstantVolatility(
+            ConstantVolatility::new(0.2, "KOSPI2 Volatility".to_string(), id),
+        )));
+        let pricer = OptionAnalyticPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            curve.clone(),
+            curve.clone(),
+            curve.clone(),
+            volatility.clone(),
+            None,
+        );
+        let market_close = Arc::new(DailyClosePrice::new(
+            FxHashMap::default(),
+            time::Time::from_hms(15, 45, 0)?,
+            time::UtcOffset::from_hms(9, 0, 0)?,
+            crate::time::calendar::Calendar::default(),
+            "KOSPI2".to_string(),
+            id,
+        ));
+        let pricer_with_close = OptionAnalyticPricer::new(
+            evaluation_date.clone(),
+            market_price.clone(),
+            curve.clone(),
+            curve.clone(),
+            curve.clone(),
+            volatility.clone(),
+            None,
+        )
+        .with_market_close(market_close);
+
+        let make_option = |maturity| {
+            Instrument::VanillaOption(VanillaOption::new(
+                InstInfo {
+                    id: StaticId::from_str("KOSPI2 Call Option", "KRX"),
+                    name: "KOSPI2 Call Option".to_string(),
+                    inst_type: InstType::VanillaOption,
+                    accounting_level: crate::AccountingLevel::L1,
+                    currency: Currency::KRW,
+                    issue_date: None,
+                    maturity: Some(maturity),
+                    unit_notional: 250_000.0,
+                },
+                spot,
+                None,
+                id,
+                Currency::KRW,
+                OptionType::Call,
+                OptionExerciseType::European,
+                OptionDailySettlementType::NotSettled,
+            ))
+        };
+        // the maturity booked without time expires at the close, 5 hours and 45 minutes later
+        let npv = pricer_with_close.npv(&make_option(datetime!(2024-06-13 00:00:00 +09:00)))?;
+        let expected = pricer.npv(&make_option(datetime!(2024-06-13 15:45:00 +09:00)))?;
+        assert!((npv - expected).abs() < 1.0e-8, "npv: {}, expected: {}", npv, expected);
+
+        // at the money: 0.4 * spot * vol * sqrt(t)
+        let t: Real = 5.75 / 24.0 / 365.0;
+        let approx = 0.4 * spot * 0.2 * t.sqrt();
+        assert!((npv - approx).abs() < 0.01 * approx, "npv: {}, approx: {}", npv, approx);
+
+        // the maturity with time, e.g., 15:20 on the last trading day by KrxExpiryRule, is kept
+        let krx_expiry = make_option(datetime!(2024-06-13 15:20:00 +09:00));
+        let npv = pricer_with_close.npv(&krx_expiry)?;
+        let expected = pricer.npv(&krx_expiry)?;
+        assert!((npv - expected).abs() < 1.0e-8, "npv: {}, expected: {}", npv, expected);
         Ok(())
     }
 }
@@ -0,0 +1,339 @@
+use crate::definitions::{Real, Time};
+use crate::math::linalg::solve_linear_system;
+use crate::parameters::zero_curve::ZeroCurve;
+use crate::pricing_engines::calculation_result::CalculationResult;
+use crate::time::{
+    calendar_trait::CalendarTrait, calendars::nullcalendar::NullCalendar,
+    conventions::PaymentFrequency,
+};
+use crate::Tenor;
+//
+use anyhow::{anyhow, Result};
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use time::OffsetDateTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ParInstrumentType {
+    Deposit,
+    Futures,
+    Swap,
+}
+
+/// An instrument bootstrapping a zero curve, quoted by its par rate on the curve:
+/// - Deposit: (1 / P(T) - 1) / T
+/// - Futures: (P(T1) / P(T2) - 1) / (T2 - T1), the forward rate without the convexity adjustment
+/// - Swap: (1 - P(T)) / Σ τ_k P(t_k), the fixed leg paid on the frequency backward from the maturity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParInstrument {
+    name: String,
+    instrument_type: ParInstrumentType,
+    start: Option<Tenor>,
+    maturity: Tenor,
+    frequency: PaymentFrequency,
+}
+
+impl ParInstrument {
+    pub fn deposit(name: &str, maturity: Tenor) -> ParInstrument {
+        ParInstrument {
+            name: name.to_string(),
+            instrument_type: ParInstrumentType::Deposit,
+            start: None,
+            maturity,
+            frequency: PaymentFrequency::None,
+        }
+    }
+
+    pub fn futures(name: &str, start: Tenor, end: Tenor) -> ParInstrument {
+        ParInstrument {
+            name: name.to_string(),
+            instrument_type: ParInstrumentType::Futures,
+            start: Some(start),
+            maturity: end,
+            frequency: PaymentFrequency::None,
+        }
+    }
+
+    pub fn swap(name: &str, maturity: Tenor, frequency: PaymentFrequency) -> Result<ParInstrument> {
+        if frequency == PaymentFrequency::None {
+            return Err(anyhow!(
+                "({}:{}) the fixed leg frequency of the swap {} is not given",
+                file!(),
+                line!(),
+                name,
+            ));
+        }
+        Ok(ParInstrument {
+            name: name.to_string(),
+            instrument_type: ParInstrumentType::Swap,
+            start: None,
+            maturity,
+            frequency,
+        })
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_instrument_type(&self) -> ParInstrumentType {
+        self.instrument_type
+    }
+
+    pub fn get_par_rate(&self, curve: &ZeroCurve, eval_dt: &OffsetDateTime) -> Result<Real> {
+        let time_calculator = NullCalendar::default();
+        let maturity = time_calculator.get_time_difference(eval_dt, &self.maturity.apply(eval_dt));
+        let start = match &self.start {
+            Some(start) => time_calculator.get_time_difference(eval_dt, &start.apply(eval_dt)),
+            None => 0.0,
+        };
+        if maturity <= start {
+            return Err(anyhow!(
+                "({}:{}) {} ends at {} before it starts at {}",
+                file!(),
+                line!(),
+                self.name,
+                maturity,
+                start,
+            ));
+        }
+        match self.instrument_type {
+            ParInstrumentType::Deposit | ParInstrumentType::Futures => {
+                let df = curve.get_discount_factor_between_times(start, maturity)?;
+                Ok((1.0 / df - 1.0) / (maturity - start))
+            }
+            ParInstrumentType::Swap => {
+                let period = 1.0 / self.frequency.as_real();
+                let mut payment_times: Vec<Time> = vec![];
+                let mut t = maturity;
+                while t > 1.0e-4 {
+                    payment_times.push(t);
+                    t -= period;
+                }
+                payment_times.reverse();
+                let mut annuity: Real = 0.0;
+                let mut previous: Time = 0.0;
+                for t in payment_times.iter() {
+                    annuity += (t - previous) * curve.get_discount_factor(*t)?;
+                    previous = *t;
+                }
+                Ok((1.0 - curve.get_discount_factor(maturity)?) / annuity)
+            }
+        }
+    }
+}
+
+/// Jacobian dq_i / dz_k of the par rates q_i of the bootstrapping instruments to the zero rates z_k
+/// bumped by the buckets of rho_structure, i.e., (t_{k-1}, t_k] on the bucket tenors.
+///
+/// The zero-rate rho_structure is rho_k = Σ_i s_i dq_i / dz_k where s_i is the sensitivity to the par rate
+/// of the instrument i, so the par-rate risk s solves J^T s = rho, exactly if J is square,
+/// otherwise in the least squares (J J^T) s = J rho. Both are in the units of rho_structure (1bp).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParRateJacobian {
+    curve_id: StaticId,
+    instruments: Vec<ParInstrument>,
+    bucket_tenors: Vec<Tenor>,
+    par_rates: Vec<Real>,
+    jacobian: Array2<Real>,
+}
+
+impl ParRateJacobian {
+    /// The buckets of the curve are bumped by bump_val as in rho_structure and put back.
+    /// bucket_tenors must be the rho_structure_tenors of the CalculationConfiguration generating the results.
+    pub fn new(
+        curve: &mut ZeroCurve,
+        instruments: Vec<ParInstrument>,
+        bucket_tenors: Vec<Tenor>,
+        bump_val: Real,
+    ) -> Result<ParRateJacobian> {
+        if instruments.is_empty() || bucket_tenors.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) no instrument or bucket is given for the par-rate jacobian of {}",
+                file!(),
+                line!(),
+                curve.get_id(),
+            ));
+        }
+        if bump_val <= 0.0 {
+            return Err(anyhow!(
+                "({}:{}) bump must be positive, but {} is given",
+                file!(),
+                line!(),
+                bump_val,
+            ));
+        }
+        let eval_dt = curve.get_evaluation_date_clone().read().unwrap().get_date_clone();
+        let time_calculator = NullCalendar::default();
+        let bucket_times: Vec<Time> = bucket_tenors
+            .iter()
+            .map(|tenor| time_calculator.get_time_difference(&eval_dt, &tenor.apply(&eval_dt)))
+            .collect();
+
+        let get_par_rates = |curve: &ZeroCurve| -> Result<Vec<Real>> {
+            instruments
+                .iter()
+                .map(|inst| inst.get_par_rate(curve, &eval_dt))
+                .collect()
+        };
+        let par_rates = get_par_rates(curve)?;
+        let mut jacobian = Array2::zeros((instruments.len(), bucket_times.len()));
+        for k in 0..bucket_times.len() {
+            let bump_start = match k {
+                0 => None,
+                _ => Some(bucket_times[k - 1]),
+            };
+            let bump_end = Some(bucket_times[k]);
+            curve.bump_time_interval(bump_start, bump_end, bump_val)?;
+            let bumped = get_par_rates(curve);
+            // put back
+            curve.bump_time_interval(bump_start, bump_end, -bump_val)?;
+            for (i, rate) in bumped?.into_iter().enumerate() {
+                jacobian[[i, k]] = (rate - par_rates[i]) / bump_val;
+            }
+        }
+
+        Ok(ParRateJacobian {
+            curve_id: curve.get_id(),
+            instruments,
+            bucket_tenors,
+            par_rates,
+            jacobian,
+        })
+    }
+
+    pub fn get_curve_id(&self) -> StaticId {
+        self.curve_id
+    }
+
+    pub fn get_instruments(&self) -> &Vec<ParInstrument> {
+        &self.instruments
+    }
+
+    pub fn get_bucket_tenors(&self) -> &Vec<Tenor> {
+        &self.bucket_tenors
+    }
+
+    pub fn get_par_rates(&self) -> &Vec<Real> {
+        &self.par_rates
+    }
+
+    /// rows are the instruments and columns are the buckets
+    pub fn get_jacobian(&self) -> &Array2<Real> {
+        &self.jacobian
+    }
+
+    /// par-rate risk of each instrument from the zero-rate rho_structure on the bucket tenors
+    pub fn transform(&self, rho_structure: &[Real]) -> Result<Vec<Real>> {
+        let (n, m) = self.jacobian.dim();
+        if rho_structure.len() != m {
+            return Err(anyhow!(
+                "({}:{}) rho_structure of {} buckets is given, but the jacobian of {} has {}",
+                file!(),
+                line!(),
+                rho_structure.len(),
+                self.curve_id,
+                m,
+            ));
+        }
+        let jacobian = self.jacobian.mapv(|x| x as f64);
+        let rho: Vec<f64> = rho_structure.iter().map(|x| *x as f64).collect();
+        let (matrix, rhs) = if n == m {
+            (jacobian.t().to_owned(), rho)
+        } else {
+            let rhs = (0..n)
+                .map(|i| (0..m).map(|k| jacobian[[i, k]] * rho[k]).sum())
+                .collect();
+            (jacobian.dot(&jacobian.t()), rhs)
+        };
+        let res = solve_linear_system(matrix, rhs).map_err(|e| {
+            anyhow!(
+                "({}:{}) par-rate risk of {} is not determined by the buckets: {}",
+                file!(),
+                line!(),
+                self.curve_id,
+                e,
+            )
+        })?;
+        Ok(res.into_iter().map(|x| x as Real).collect())
+    }
+
+    /// par-rate risk of the result on the curve, None if it has no rho_structure on the curve
+    pub fn transform_result(&self, result: &CalculationResult) -> Result<Option<Vec<Real>>> {
+        match result
+            .get_rho_structure()
+            .and_then(|rho_structure| rho_structure.get(&self.curve_id))
+        {
+            Some(rho_structure) => Ok(Some(self.transform(rho_structure)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::data::vector_data::VectorData;
+    use crate::evaluation_date::EvaluationDate;
+    use ndarray::array;
+    use std::sync::{Arc, RwLock};
+    use time::macros::datetime;
+
+    #[test]
+    fn test_par_rate_jacobian() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(dt)));
+        let curve_id = StaticId::from_str("KRWIRS", "KAP");
+        let data = VectorData::new(
+            array![0.035, 0.034, 0.033, 0.032],
+            None,
+            Some(array![0.25, 1.0, 3.0, 5.0]),
+            Some(dt),
+            Currency::KRW,
+            "KRWIRS".to_string(),
+            curve_id,
+        )?;
+        let mut curve = ZeroCurve::new(evaluation_date, &data, "KRWIRS".to_string(), curve_id)?;
+
+        let tenor = |x: &str| Tenor::new_from_string(x).unwrap();
+        let buckets = vec![tenor("3M"), tenor("1Y"), tenor("3Y"), tenor("5Y")];
+        let instruments = vec![
+            ParInstrument::deposit("CD91", tenor("3M")),
+            ParInstrument::futures("3M-1Y FRA", tenor("3M"), tenor("1Y")),
+            ParInstrument::swap("IRS3Y", tenor("3Y"), PaymentFrequency::Quarterly)?,
+            ParInstrument::swap("IRS5Y", tenor("5Y"), PaymentFrequency::Quarterly)?,
+        ];
+        let jacobian = ParRateJacobian::new(&mut curve, instruments, buckets, 0.001)?;
+        // the curve is put back
+        let rates = curve.get_interpolated_rates();
+        assert!((rates[rates.len() - 1] - 0.032).abs() < 1.0e-6);
+
+        // the deposit moves only with the first bucket
+        let j = jacobian.get_jacobian();
+        assert!(j[[0, 0]] > 0.9 && j[[0, 1]].abs() < 1.0e-3);
+        // the forward of 3M-1Y falls on the rise of the 3M zero rate
+        assert!(j[[1, 0]] < 0.0 && j[[1, 1]] > 1.0);
+        assert!(jacobian.get_par_rates()[3] < jacobian.get_par_rates()[0]);
+
+        // the rho of the 3Y par swap itself is the unit risk on the 3Y swap
+        let rho: Vec<Real> = j.row(2).to_vec();
+        let par_risk = jacobian.transform(&rho)?;
+        assert!((par_risk[2] - 1.0).abs() < 1.0e-3);
+        for i in [0, 1, 3] {
+            assert!(par_risk[i].abs() < 1.0e-3);
+        }
+        // the least squares on fewer instruments
+        let short = ParRateJacobian::new(
+            &mut curve,
+            vec![ParInstrument::deposit("CD91", tenor("3M"))],
+            vec![tenor("3M"), tenor("1Y")],
+            0.001,
+        )?;
+        let par_risk = short.transform(&[2.0 * short.get_jacobian()[[0, 0]], 0.0])?;
+        assert!((par_risk[0] - 2.0).abs() < 1.0e-3);
+        assert!(short.transform(&[1.0]).is_err());
+        Ok(())
+    }
+}
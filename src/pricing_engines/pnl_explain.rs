@@ -0,0 +1,622 @@
+use crate::currency::{Currency, FxCode};
+use crate::data::{
+    daily_value_data::DailyValueData, surface_data::SurfaceData, value_data::ValueData,
+    vector_data::VectorData,
+};
+use crate::definitions::{Real, Time};
+use crate::instrument::{Instrument, InstrumentTrait, Instruments};
+use crate::pricing_engines::{
+    calculation_configuration::CalculationConfiguration,
+    calculation_result::CalculationResult,
+    engine_generator::{EngineGenerator, InstrumentCategory},
+    match_parameter::MatchParameter,
+    pnl_predictor::{MarketShock, TaylorPnlPredictor},
+};
+//
+use anyhow::{anyhow, Context, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use time::OffsetDateTime;
+
+/// Market data of a date in the form given to EngineGenerator::with_data.
+/// In json, the data which are not used may be omitted,
+/// and the quanto correlations are a list of [[und_id, fx_code], data].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketDataSet {
+    evaluation_datetime: OffsetDateTime,
+    #[serde(default)]
+    fx_data: FxHashMap<FxCode, ValueData>,
+    #[serde(default)]
+    stock_data: FxHashMap<StaticId, ValueData>,
+    #[serde(default)]
+    curve_data: FxHashMap<StaticId, VectorData>,
+    #[serde(default)]
+    dividend_data: FxHashMap<StaticId, VectorData>,
+    #[serde(default)]
+    equity_constant_volatility_data: FxHashMap<StaticId, ValueData>,
+    #[serde(default)]
+    equity_volatility_surface_data: FxHashMap<StaticId, SurfaceData>,
+    #[serde(default)]
+    fx_constant_volatility_data: FxHashMap<FxCode, ValueData>,
+    #[serde(default, with = "crate::pricing_engines::match_parameter::tuple_key_map")]
+    quanto_correlation_data: FxHashMap<(StaticId, FxCode), ValueData>,
+    #[serde(default)]
+    past_daily_value_data: FxHashMap<StaticId, DailyValueData>,
+}
+
+impl MarketDataSet {
+    pub fn new(evaluation_datetime: OffsetDateTime) -> MarketDataSet {
+        MarketDataSet {
+            evaluation_datetime,
+            fx_data: FxHashMap::default(),
+            stock_data: FxHashMap::default(),
+            curve_data: FxHashMap::default(),
+            dividend_data: FxHashMap::default(),
+            equity_constant_volatility_data: FxHashMap::default(),
+            equity_volatility_surface_data: FxHashMap::default(),
+            fx_constant_volatility_data: FxHashMap::default(),
+            quanto_correlation_data: FxHashMap::default(),
+            past_daily_value_data: FxHashMap::default(),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_data(
+        mut self,
+        fx_data: FxHashMap<FxCode, ValueData>,
+        stock_data: FxHashMap<StaticId, ValueData>,
+        curve_data: FxHashMap<StaticId, VectorData>,
+        dividend_data: FxHashMap<StaticId, VectorData>,
+        equity_constant_volatility_data: FxHashMap<StaticId, ValueData>,
+        equity_volatility_surface_data: FxHashMap<StaticId, SurfaceData>,
+        fx_constant_volatility_data: FxHashMap<FxCode, ValueData>,
+        quanto_correlation_data: FxHashMap<(StaticId, FxCode), ValueData>,
+        past_daily_value_data: FxHashMap<StaticId, DailyValueData>,
+    ) -> MarketDataSet {
+        self.fx_data = fx_data;
+        self.stock_data = stock_data;
+        self.curve_data = curve_data;
+        self.dividend_data = dividend_data;
+        self.equity_constant_volatility_data = equity_constant_volatility_data;
+        self.equity_volatility_surface_data = equity_volatility_surface_data;
+        self.fx_constant_volatility_data = fx_constant_volatility_data;
+        self.quanto_correlation_data = quanto_correlation_data;
+        self.past_daily_value_data = past_daily_value_data;
+        self
+    }
+
+    pub fn get_evaluation_datetime(&self) -> OffsetDateTime {
+        self.evaluation_datetime
+    }
+
+    pub fn get_stock_data(&self) -> &FxHashMap<StaticId, ValueData> {
+        &self.stock_data
+    }
+
+    pub fn get_curve_data(&self) -> &FxHashMap<StaticId, VectorData> {
+        &self.curve_data
+    }
+
+    pub fn get_equity_constant_volatility_data(&self) -> &FxHashMap<StaticId, ValueData> {
+        &self.equity_constant_volatility_data
+    }
+
+    pub fn get_equity_volatility_surface_data(&self) -> &FxHashMap<StaticId, SurfaceData> {
+        &self.equity_volatility_surface_data
+    }
+
+    /// volatility level of the underlying: the constant volatility if given,
+    /// otherwise the average of the surface
+    pub fn get_volatility_level(&self, und_id: &StaticId) -> Option<Real> {
+        if let Some(vol) = self.equity_constant_volatility_data.get(und_id) {
+            return Some(vol.get_value());
+        }
+        self.equity_volatility_surface_data
+            .get(und_id)
+            .and_then(|surface| surface.get_value().mean())
+    }
+
+    /// average zero rate of the curve
+    fn get_rate_level(&self, curve_id: &StaticId) -> Option<Real> {
+        self.curve_data
+            .get(curve_id)
+            .and_then(|curve| curve.get_value_clone().mean())
+    }
+
+    /// moves of the spots, volatility levels and average zero rates to the target data
+    pub fn get_market_shock(&self, target: &MarketDataSet) -> MarketShock {
+        let mut shock = MarketShock::new().with_days(
+            (target.evaluation_datetime - self.evaluation_datetime).whole_seconds() as Real
+                / 86_400.0,
+        );
+        for (und_id, spot0) in self.stock_data.iter() {
+            if let Some(spot1) = target.stock_data.get(und_id) {
+                shock = shock.with_spot_return(*und_id, spot1.get_value() / spot0.get_value() - 1.0);
+            }
+        }
+
+        let vol_ids = self
+            .equity_constant_volatility_data
+            .keys()
+            .chain(self.equity_volatility_surface_data.keys());
+        for und_id in vol_ids {
+            let vol0 = self.get_volatility_level(und_id);
+            let vol1 = target.get_volatility_level(und_id);
+            if let (Some(vol0), Some(vol1)) = (vol0, vol1) {
+                shock = shock.with_volatility_change(*und_id, vol1 - vol0);
+            }
+        }
+
+        for curve_id in self.curve_data.keys() {
+            let rate0 = self.get_rate_level(curve_id);
+            let rate1 = target.get_rate_level(curve_id);
+            if let (Some(rate0), Some(rate1)) = (rate0, rate1) {
+                shock = shock.with_rate_change(*curve_id, rate1 - rate0);
+            }
+        }
+
+        for (fx_code, fx0) in self.fx_data.iter() {
+            if let Some(fx1) = target.fx_data.get(fx_code) {
+                shock = shock.with_fx_return(*fx_code, fx1.get_value() / fx0.get_value() - 1.0);
+            }
+        }
+        shock
+    }
+
+    /// market data moved by the shock on the same evaluation date.
+    /// The spots and fx rates (with their bid and ask) are scaled by the returns,
+    /// the volatilities and the zero rates are shifted in parallel and then on the nodes.
+    /// The days in the shock are not applied.
+    pub fn get_shocked(&self, shock: &MarketShock) -> MarketDataSet {
+        let mut res = self.clone();
+        for (und_id, spot_return) in shock.get_spot_returns().iter() {
+            if let Some(spot) = res.stock_data.get_mut(und_id) {
+                scale_value_data(spot, 1.0 + spot_return);
+            }
+        }
+
+        for (und_id, change) in shock.get_volatility_changes().iter() {
+            if let Some(vol) = res.equity_constant_volatility_data.get_mut(und_id) {
+                vol.value += change;
+            }
+            if let Some(surface) = res.equity_volatility_surface_data.get_mut(und_id) {
+                surface.value += *change;
+            }
+        }
+
+        for (curve_id, change) in shock.get_rate_changes().iter() {
+            if let Some(curve) = res.curve_data.get_mut(curve_id) {
+                curve.value += *change;
+            }
+        }
+
+        for (fx_code, fx_return) in shock.get_fx_returns().iter() {
+            if let Some(fx) = res.fx_data.get_mut(fx_code) {
+                scale_value_data(fx, 1.0 + fx_return);
+            }
+        }
+
+        for (curve_id, nodes) in shock.get_rate_node_changes().iter() {
+            if let Some(curve) = res.curve_data.get_mut(curve_id) {
+                for (value, t) in curve.value.iter_mut().zip(curve.times.iter()) {
+                    *value += interpolate_node_changes(nodes, *t);
+                }
+            }
+        }
+
+        // the node changes of the surfaces are on the nearest maturity and strike
+        for (und_id, nodes) in shock.get_volatility_node_changes().iter() {
+            if let Some(surface) = res.equity_volatility_surface_data.get_mut(und_id) {
+                let maturities: Vec<Real> = surface
+                    .dates
+                    .iter()
+                    .map(|dt| {
+                        (*dt - self.evaluation_datetime).whole_seconds() as Real / 86_400.0 / 365.0
+                    })
+                    .collect();
+                for (maturity, strike, change) in nodes.iter() {
+                    let row = get_nearest_index(&maturities, *maturity);
+                    let col = get_nearest_index(surface.strikes.as_slice().unwrap_or(&[]), *strike);
+                    if let (Some(row), Some(col)) = (row, col) {
+                        surface.value[[row, col]] += *change;
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    /// the amount of `to` currency for a unit of `from` currency
+    pub fn get_fx_rate(&self, from: Currency, to: Currency) -> Result<Real> {
+        if from == to {
+            return Ok(1.0);
+        }
+        if let Some(fx) = self.fx_data.get(&FxCode::new(from, to)) {
+            return Ok(fx.get_value());
+        }
+        if let Some(fx) = self.fx_data.get(&FxCode::new(to, from)) {
+            return Ok(1.0 / fx.get_value());
+        }
+        Err(anyhow!(
+            "({}:{}) no fx rate for {}/{} at {}",
+            file!(),
+            line!(),
+            from,
+            to,
+            self.evaluation_datetime,
+        ))
+    }
+
+    /// calculation results of the instruments on this data set
+    pub(crate) fn run_engine(
+        &self,
+        calculation_configuration: CalculationConfiguration,
+        match_parameter: MatchParameter,
+        instruments: Instruments,
+        instrument_categories: Vec<InstrumentCategory>,
+    ) -> Result<FxHashMap<StaticId, CalculationResult>> {
+        let mut engine_builder = EngineGenerator::builder();
+        let engine_generator = engine_builder
+            .with_configuration(
+                calculation_configuration,
+                self.evaluation_datetime,
+                match_parameter,
+            )?
+            .with_instruments(instruments)?
+            .with_instrument_categories(instrument_categories)?
+            .with_data(
+                self.fx_data.clone(),
+                self.stock_data.clone(),
+                self.curve_data.clone(),
+                self.dividend_data.clone(),
+                self.equity_constant_volatility_data.clone(),
+                self.equity_volatility_surface_data.clone(),
+                self.fx_constant_volatility_data.clone(),
+                self.quanto_correlation_data.clone(),
+                self.past_daily_value_data.clone(),
+            )?;
+
+        engine_generator.distribute_instruments()?;
+        engine_generator.calculate()?;
+        Ok(engine_generator.get_calculation_results().clone())
+    }
+}
+
+/// changes on the sorted tenors interpolated linearly with the flat extrapolation
+fn interpolate_node_changes(nodes: &[(Time, Real)], t: Time) -> Real {
+    match nodes.iter().position(|(tenor, _)| *tenor >= t) {
+        None => nodes.last().map(|(_, change)| *change).unwrap_or(0.0),
+        Some(0) => nodes[0].1,
+        Some(i) => {
+            let (t0, c0) = nodes[i - 1];
+            let (t1, c1) = nodes[i];
+            c0 + (c1 - c0) * (t - t0) / (t1 - t0)
+        }
+    }
+}
+
+/// index of the value nearest to the target, None if the values are empty
+pub(crate) fn get_nearest_index(values: &[Real], target: Real) -> Option<usize> {
+    values
+        .iter()
+        .enumerate()
+        .min_by(|(_, x), (_, y)| (*x - target).abs().total_cmp(&(*y - target).abs()))
+        .map(|(i, _)| i)
+}
+
+fn scale_value_data(data: &mut ValueData, ratio: Real) {
+    data.value *= ratio;
+    data.bid = data.bid.map(|bid| bid * ratio);
+    data.ask = data.ask.map(|ask| ask * ratio);
+}
+
+/// P&L of an instrument (or a portfolio) between two dates explained by the greeks at the first date.
+/// unexplained = actual - (delta + gamma + vega + theta + rho + new_trades)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PnlWaterfall {
+    actual: Real,
+    delta: Real,
+    gamma: Real,
+    vega: Real,
+    theta: Real,
+    rho: Real,
+    new_trades: Real,
+    unexplained: Real,
+}
+
+impl PnlWaterfall {
+    pub fn get_actual(&self) -> Real {
+        self.actual
+    }
+
+    pub fn get_delta(&self) -> Real {
+        self.delta
+    }
+
+    pub fn get_gamma(&self) -> Real {
+        self.gamma
+    }
+
+    pub fn get_vega(&self) -> Real {
+        self.vega
+    }
+
+    pub fn get_theta(&self) -> Real {
+        self.theta
+    }
+
+    pub fn get_rho(&self) -> Real {
+        self.rho
+    }
+
+    pub fn get_new_trades(&self) -> Real {
+        self.new_trades
+    }
+
+    pub fn get_unexplained(&self) -> Real {
+        self.unexplained
+    }
+
+    pub fn get_explained(&self) -> Real {
+        self.delta + self.gamma + self.vega + self.theta + self.rho + self.new_trades
+    }
+
+    fn set_unexplained(&mut self) {
+        self.unexplained = self.actual - self.get_explained();
+    }
+
+    fn accumulate(&mut self, other: &PnlWaterfall) {
+        self.actual += other.actual;
+        self.delta += other.delta;
+        self.gamma += other.gamma;
+        self.vega += other.vega;
+        self.theta += other.theta;
+        self.rho += other.rho;
+        self.new_trades += other.new_trades;
+        self.unexplained += other.unexplained;
+    }
+}
+
+/// P&L explain between the base and the target market data.
+///
+/// The portfolio is valued with greeks on the base data and valued again on the target data.
+/// The actual P&L of an instrument held at both dates is the value change plus the cashflows in between.
+/// It is explained by TaylorPnlPredictor on the greeks of the base date
+/// with the market shock between the two data sets (MarketDataSet::get_market_shock):
+/// * delta, gamma: relative spot moves
+/// * vega: change of the volatility level (constant volatility or average of the surface)
+/// * rho: change of the average zero rate of each curve
+/// * theta: calendar days elapsed
+///
+/// Instruments only in the target portfolio are new trades whose P&L is
+/// the target value minus the trade cost (average trade price times unit notional).
+/// Instruments only in the base portfolio are not explained.
+pub struct PnlExplain {
+    calculation_configuration: CalculationConfiguration,
+    match_parameter: MatchParameter,
+    instrument_categories: Vec<InstrumentCategory>,
+    base_instruments: Instruments,
+    target_instruments: Instruments,
+    base_data: MarketDataSet,
+    target_data: MarketDataSet,
+    // None until the base results are calculated or given by with_base_results
+    base_results: Option<FxHashMap<StaticId, CalculationResult>>,
+    target_results: FxHashMap<StaticId, CalculationResult>,
+    waterfalls: FxHashMap<StaticId, PnlWaterfall>,
+    aggregated_waterfalls: FxHashMap<Currency, PnlWaterfall>,
+}
+
+impl PnlExplain {
+    pub fn new(
+        calculation_configuration: CalculationConfiguration,
+        match_parameter: MatchParameter,
+        instrument_categories: Vec<InstrumentCategory>,
+        instruments: Instruments,
+        base_data: MarketDataSet,
+        target_data: MarketDataSet,
+    ) -> PnlExplain {
+        PnlExplain {
+            calculation_configuration,
+            match_parameter,
+            instrument_categories,
+            base_instruments: instruments.clone(),
+            target_instruments: instruments,
+            base_data,
+            target_data,
+            base_results: None,
+            target_results: FxHashMap::default(),
+            waterfalls: FxHashMap::default(),
+            aggregated_waterfalls: FxHashMap::default(),
+        }
+    }
+
+    /// portfolio at the target date if it differs from the base portfolio
+    pub fn with_target_instruments(mut self, target_instruments: Instruments) -> PnlExplain {
+        self.target_instruments = target_instruments;
+        self
+    }
+
+    /// results on the base data calculated before with the greeks, e.g., loaded from a ResultStore,
+    /// which are used instead of running the engine on the base data.
+    /// Empty results mean an empty base portfolio, i.e., all the target instruments are new trades.
+    pub fn with_base_results(
+        mut self,
+        base_results: FxHashMap<StaticId, CalculationResult>,
+    ) -> PnlExplain {
+        self.base_results = Some(base_results);
+        self
+    }
+
+    pub fn calculate(&mut self) -> Result<()> {
+        let base_dt = self.base_data.get_evaluation_datetime();
+        let target_dt = self.target_data.get_evaluation_datetime();
+        if target_dt < base_dt {
+            return Err(anyhow!(
+                "({}:{}) target datetime {} is before the base datetime {}",
+                file!(),
+                line!(),
+                target_dt,
+                base_dt,
+            ));
+        }
+
+        let base_configuration = self
+            .calculation_configuration
+            .clone()
+            .with_npv_calculation(true)
+            .with_delta_calculation(true)
+            .with_gamma_calculation(true)
+            .with_vega_calculation(true)
+            .with_theta_calculation(true)
+            .with_rho_calculation(true);
+
+        let target_configuration = self.calculation_configuration.clone().npv_only();
+
+        if self.base_results.is_none() {
+            let base_results = self
+                .base_data
+                .run_engine(
+                    base_configuration,
+                    self.match_parameter.clone(),
+                    self.base_instruments.clone(),
+                    self.instrument_categories.clone(),
+                )
+                .with_context(|| {
+                    anyhow!("({}:{}) failed to calculate the base results", file!(), line!())
+                })?;
+            self.base_results = Some(base_results);
+        }
+
+        self.target_results = self
+            .target_data
+            .run_engine(
+                target_configuration,
+                self.match_parameter.clone(),
+                self.target_instruments.clone(),
+                self.instrument_categories.clone(),
+            )
+            .with_context(|| anyhow!("({}:{}) failed to calculate the target results", file!(), line!()))?;
+
+        let predictor = TaylorPnlPredictor::new(self.base_data.get_market_shock(&self.target_data));
+        let mut waterfalls = FxHashMap::default();
+        let mut aggregated_waterfalls: FxHashMap<Currency, PnlWaterfall> = FxHashMap::default();
+
+        for inst in self.target_instruments.iter() {
+            let inst_id = inst.get_id();
+            let target_value = self
+                .target_results
+                .get(&inst_id)
+                .and_then(|res| res.get_value())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "({}:{}) target value is not set for {}",
+                        file!(),
+                        line!(),
+                        inst_id,
+                    )
+                })?;
+
+            let base_result = self
+                .base_results
+                .as_ref()
+                .and_then(|base_results| base_results.get(&inst_id));
+            let waterfall = match base_result {
+                Some(base_result) => {
+                    self.explain(inst.as_ref(), base_result, target_value, &predictor)?
+                }
+                None => {
+                    let new_trades = target_value
+                        - inst.get_average_trade_price() * inst.get_unit_notional();
+                    PnlWaterfall {
+                        actual: new_trades,
+                        new_trades,
+                        ..Default::default()
+                    }
+                }
+            };
+
+            aggregated_waterfalls
+                .entry(inst.get_currency())
+                .or_default()
+                .accumulate(&waterfall);
+            waterfalls.insert(inst_id, waterfall);
+        }
+
+        self.waterfalls = waterfalls;
+        self.aggregated_waterfalls = aggregated_waterfalls;
+        Ok(())
+    }
+
+    fn explain(
+        &self,
+        inst: &Instrument,
+        base_result: &CalculationResult,
+        target_value: Real,
+        predictor: &TaylorPnlPredictor,
+    ) -> Result<PnlWaterfall> {
+        let inst_info = base_result.get_instrument_info().ok_or_else(|| {
+            anyhow!("({}:{}) instrument info is not set in the base result", file!(), line!())
+        })?;
+        let base_value = base_result.get_value().ok_or_else(|| {
+            anyhow!(
+                "({}:{}) base value is not set for {}",
+                file!(),
+                line!(),
+                inst_info.id,
+            )
+        })?;
+
+        let base_dt = self.base_data.get_evaluation_datetime();
+        let target_dt = self.target_data.get_evaluation_datetime();
+        // the expected amounts of the pricer are in the currency of the value
+        let cash_sum: Real = match base_result.get_npv_result() {
+            Some(npv_result) => npv_result
+                .get_expected_coupon_amount()?
+                .iter()
+                .filter(|(date, _)| {
+                    base_dt.date() < date.date() && date.date() <= target_dt.date()
+                })
+                .map(|(_, cash)| *cash)
+                .sum(),
+            None => 0.0,
+        };
+
+        let mut waterfall = PnlWaterfall {
+            actual: target_value - base_value + cash_sum * inst_info.unit_notional,
+            ..Default::default()
+        };
+
+        let prediction = predictor.predict(inst, base_result);
+        waterfall.delta = prediction.get_delta();
+        waterfall.gamma = prediction.get_gamma();
+        waterfall.vega = prediction.get_vega();
+        waterfall.theta = prediction.get_theta();
+        waterfall.rho = prediction.get_rho();
+
+        waterfall.set_unexplained();
+        Ok(waterfall)
+    }
+
+    /// None before calculate unless the base results are given by with_base_results
+    pub fn get_base_results(&self) -> Option<&FxHashMap<StaticId, CalculationResult>> {
+        self.base_results.as_ref()
+    }
+
+    pub fn get_target_results(&self) -> &FxHashMap<StaticId, CalculationResult> {
+        &self.target_results
+    }
+
+    pub fn get_waterfalls(&self) -> &FxHashMap<StaticId, PnlWaterfall> {
+        &self.waterfalls
+    }
+
+    pub fn get_waterfall(&self, inst_id: &StaticId) -> Option<&PnlWaterfall> {
+        self.waterfalls.get(inst_id)
+    }
+
+    /// waterfalls summed by the currency of the instruments
+    pub fn get_aggregated_waterfalls(&self) -> &FxHashMap<Currency, PnlWaterfall> {
+        &self.aggregated_waterfalls
+    }
+}
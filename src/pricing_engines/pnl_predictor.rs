@@ -0,0 +1,318 @@
+use crate::currency::FxCode;
+use crate::definitions::{
+    Real, Time, DELTA_PNL_UNIT, GAMMA_PNL_UNIT, RHO_PNL_UNIT, THETA_PNL_UNIT, VEGA_PNL_UNIT,
+};
+use crate::instrument::{Instrument, InstrumentTrait, Instruments};
+use crate::pricing_engines::calculation_result::CalculationResult;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+
+/// Market shock keyed by the same risk factor ids as the greeks in CalculationResult:
+/// underlying ids for the spot and volatility, curve ids for the rates.
+/// * spot_returns: relative move, e.g., 0.01 for +1%
+/// * volatility_changes: absolute move, e.g., 0.01 for +1%p
+/// * rate_changes: parallel move, e.g., 0.0001 for +1bp
+/// * fx_returns: relative move of the fx rates, e.g., 0.01 for +1%
+/// * days: calendar days elapsed
+/// * rate_node_changes: absolute moves on the tenors in years, interpolated linearly on the curve nodes
+/// * volatility_node_changes: absolute moves on the (maturity in years, strike) points of the surfaces
+///
+/// The node changes are added to the parallel changes in revaluation,
+/// but TaylorPnlPredictor takes only the parallel changes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketShock {
+    spot_returns: FxHashMap<StaticId, Real>,
+    volatility_changes: FxHashMap<StaticId, Real>,
+    rate_changes: FxHashMap<StaticId, Real>,
+    #[serde(default)]
+    fx_returns: FxHashMap<FxCode, Real>,
+    days: Real,
+    #[serde(default)]
+    rate_node_changes: FxHashMap<StaticId, Vec<(Time, Real)>>,
+    #[serde(default)]
+    volatility_node_changes: FxHashMap<StaticId, Vec<(Time, Real, Real)>>,
+}
+
+impl MarketShock {
+    pub fn new() -> MarketShock {
+        MarketShock::default()
+    }
+
+    pub fn with_spot_return(mut self, und_id: StaticId, spot_return: Real) -> MarketShock {
+        self.spot_returns.insert(und_id, spot_return);
+        self
+    }
+
+    pub fn with_volatility_change(mut self, und_id: StaticId, change: Real) -> MarketShock {
+        self.volatility_changes.insert(und_id, change);
+        self
+    }
+
+    pub fn with_rate_change(mut self, curve_id: StaticId, change: Real) -> MarketShock {
+        self.rate_changes.insert(curve_id, change);
+        self
+    }
+
+    pub fn with_fx_return(mut self, fx_code: FxCode, fx_return: Real) -> MarketShock {
+        self.fx_returns.insert(fx_code, fx_return);
+        self
+    }
+
+    pub fn with_days(mut self, days: Real) -> MarketShock {
+        self.days = days;
+        self
+    }
+
+    pub fn with_rate_node_change(
+        mut self,
+        curve_id: StaticId,
+        tenor: Time,
+        change: Real,
+    ) -> MarketShock {
+        let nodes = self.rate_node_changes.entry(curve_id).or_default();
+        nodes.push((tenor, change));
+        nodes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self
+    }
+
+    pub fn with_volatility_node_change(
+        mut self,
+        und_id: StaticId,
+        maturity: Time,
+        strike: Real,
+        change: Real,
+    ) -> MarketShock {
+        self.volatility_node_changes
+            .entry(und_id)
+            .or_default()
+            .push((maturity, strike, change));
+        self
+    }
+
+    pub fn get_spot_returns(&self) -> &FxHashMap<StaticId, Real> {
+        &self.spot_returns
+    }
+
+    pub fn get_volatility_changes(&self) -> &FxHashMap<StaticId, Real> {
+        &self.volatility_changes
+    }
+
+    pub fn get_rate_changes(&self) -> &FxHashMap<StaticId, Real> {
+        &self.rate_changes
+    }
+
+    pub fn get_fx_returns(&self) -> &FxHashMap<FxCode, Real> {
+        &self.fx_returns
+    }
+
+    pub fn get_days(&self) -> Real {
+        self.days
+    }
+
+    /// (tenor, change) sorted by the tenor
+    pub fn get_rate_node_changes(&self) -> &FxHashMap<StaticId, Vec<(Time, Real)>> {
+        &self.rate_node_changes
+    }
+
+    /// (maturity, strike, change)
+    pub fn get_volatility_node_changes(&self) -> &FxHashMap<StaticId, Vec<(Time, Real, Real)>> {
+        &self.volatility_node_changes
+    }
+}
+
+/// the underlying id of a delta key in CalculationResult.
+/// The delta of stocks and futures is keyed by the instrument itself.
+pub fn get_delta_underlying_id(inst: &Instrument, delta_id: &StaticId) -> StaticId {
+    match *delta_id == inst.get_id() {
+        true => inst.get_underlying_ids().first().copied().unwrap_or(*delta_id),
+        false => *delta_id,
+    }
+}
+
+/// P&L estimated from the greeks, in the currency of the instrument
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PnlPrediction {
+    delta: Real,
+    gamma: Real,
+    vega: Real,
+    theta: Real,
+    rho: Real,
+}
+
+impl PnlPrediction {
+    pub fn get_delta(&self) -> Real {
+        self.delta
+    }
+
+    pub fn get_gamma(&self) -> Real {
+        self.gamma
+    }
+
+    pub fn get_vega(&self) -> Real {
+        self.vega
+    }
+
+    pub fn get_theta(&self) -> Real {
+        self.theta
+    }
+
+    pub fn get_rho(&self) -> Real {
+        self.rho
+    }
+
+    pub fn get_total(&self) -> Real {
+        self.delta + self.gamma + self.vega + self.theta + self.rho
+    }
+}
+
+/// Second order Taylor expansion of the value on the greeks without revaluation:
+///
+/// dV = delta * r / 1% + gamma * (r / 1%)^2 + vega * dσ / 1%p + rho * dr / 1bp + theta * days
+///
+/// where the gamma in CalculationResult already has the factor 0.5.
+/// The greeks which are not in the result or the factors which are not in the shock contribute nothing.
+/// The fx returns are not applied since the instrument values are in their own currencies.
+#[derive(Debug, Clone, Default)]
+pub struct TaylorPnlPredictor {
+    shock: MarketShock,
+}
+
+impl TaylorPnlPredictor {
+    pub fn new(shock: MarketShock) -> TaylorPnlPredictor {
+        TaylorPnlPredictor { shock }
+    }
+
+    pub fn get_shock(&self) -> &MarketShock {
+        &self.shock
+    }
+
+    pub fn predict(&self, inst: &Instrument, result: &CalculationResult) -> PnlPrediction {
+        let mut res = PnlPrediction::default();
+
+        if let Some(delta) = result.get_delta() {
+            let gamma = result.get_gamma();
+            for (delta_id, delta_value) in delta.iter() {
+                let und_id = get_delta_underlying_id(inst, delta_id);
+                if let Some(spot_return) = self.shock.spot_returns.get(&und_id) {
+                    res.delta += delta_value * spot_return / DELTA_PNL_UNIT;
+                    if let Some(gamma_value) = gamma.and_then(|g| g.get(delta_id)) {
+                        let move_in_unit = spot_return / GAMMA_PNL_UNIT;
+                        res.gamma += gamma_value * move_in_unit * move_in_unit;
+                    }
+                }
+            }
+        }
+
+        if let Some(vega) = result.get_vega() {
+            for (und_id, vega_value) in vega.iter() {
+                if let Some(change) = self.shock.volatility_changes.get(und_id) {
+                    res.vega += vega_value * change / VEGA_PNL_UNIT;
+                }
+            }
+        }
+
+        if let Some(rho) = result.get_rho() {
+            for (curve_id, rho_value) in rho.iter() {
+                if let Some(change) = self.shock.rate_changes.get(curve_id) {
+                    res.rho += rho_value * change / RHO_PNL_UNIT;
+                }
+            }
+        }
+
+        if let Some(theta) = result.get_theta() {
+            res.theta = theta * self.shock.days / THETA_PNL_UNIT;
+        }
+
+        res
+    }
+
+    pub fn predict_instruments(
+        &self,
+        instruments: &Instruments,
+        results: &FxHashMap<StaticId, CalculationResult>,
+    ) -> Result<FxHashMap<StaticId, PnlPrediction>> {
+        let mut res = FxHashMap::default();
+        for inst in instruments.iter() {
+            let inst_id = inst.get_id();
+            let result = results.get(&inst_id).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) calculation result is not given for {} ({})",
+                    file!(),
+                    line!(),
+                    inst_id,
+                    inst.get_type_name(),
+                )
+            })?;
+            res.insert(inst_id, self.predict(inst.as_ref(), result));
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::instruments::futures::Futures;
+    use crate::{InstInfo, InstType};
+    use time::macros::datetime;
+
+    #[test]
+    fn test_taylor_pnl_predictor() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let curve_id = StaticId::from_str("KSD", "DataProvider");
+        let futures_id = StaticId::from_str("165XXX1", "KRX");
+        let inst_info = InstInfo {
+            id: futures_id,
+            currency: Currency::KRW,
+            inst_type: InstType::Futures,
+            unit_notional: 250_000.0,
+            maturity: Some(datetime!(2024-06-14 00:00:00 +09:00)),
+            ..Default::default()
+        };
+        let futures = Instrument::Futures(Futures::new(
+            inst_info.clone(),
+            350.0,
+            None,
+            Currency::KRW,
+            und_id,
+        ));
+
+        let mut result = CalculationResult::new(inst_info, dt);
+        result.set_single_delta(futures_id, 875_000.0);
+        result.set_single_gamma(futures_id, 0.0);
+        result.set_single_vega(und_id, 1_000.0);
+        result.set_single_rho(curve_id, 2_000.0);
+        result.set_theta(-100.0);
+
+        let shock = MarketShock::new()
+            .with_spot_return(und_id, -0.02)
+            .with_volatility_change(und_id, 0.005)
+            .with_rate_change(curve_id, -0.0003)
+            .with_days(3.0);
+        let predictor = TaylorPnlPredictor::new(shock);
+        let prediction = predictor.predict(&futures, &result);
+
+        assert!((prediction.get_delta() + 1_750_000.0).abs() < 1.0e-1);
+        assert_eq!(prediction.get_gamma(), 0.0);
+        assert!((prediction.get_vega() - 500.0).abs() < 1.0e-3);
+        assert!((prediction.get_rho() + 6_000.0).abs() < 1.0e-2);
+        assert!((prediction.get_theta() + 300.0).abs() < 1.0e-3);
+        assert!(
+            (prediction.get_total() + 1_755_800.0).abs() < 1.0,
+            "{:?}",
+            prediction
+        );
+
+        let mut results = FxHashMap::default();
+        results.insert(futures_id, result);
+        let instruments = Instruments::new(vec![std::sync::Arc::new(futures)]);
+        let predictions = predictor.predict_instruments(&instruments, &results)?;
+        assert_eq!(predictions.get(&futures_id), Some(&prediction));
+        Ok(())
+    }
+}
@@ -0,0 +1,487 @@
+use crate::currency::{Currency, FxCode};
+use crate::definitions::Real;
+use crate::enums::CurveRole;
+use crate::pricing_engines::calculation_result::CalculationResult;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use std::fmt::Display;
+use std::hash::Hash;
+use time::OffsetDateTime;
+
+/// Sum of the CalculationResults of a portfolio in a target currency.
+///
+/// Each result is converted by CalculationResult::representation_currency_conversion
+/// before it is added, so the values and greeks are in the target currency.
+/// The exceptions are
+/// - fx_exposure and fx_exposure_ladder: keyed by the exposed currency and summed in the amount of that currency
+/// - cashflows: the amounts on a unit are multiplied by the unit notional and converted from the currencies of the cashflows
+/// - cashflow_ladder: the same amounts summed on the payment dates in each currency without the conversion
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortfolioResult {
+    currency: Currency,
+    evaluation_date: Option<OffsetDateTime>,
+    instrument_ids: Vec<StaticId>,
+    value: Real,
+    fx_exposure: FxHashMap<Currency, Real>,
+    fx_exposure_ladder: FxHashMap<Currency, Vec<Real>>,
+    fx_delta: FxHashMap<FxCode, Real>,
+    fx_gamma: FxHashMap<FxCode, Real>,
+    delta: FxHashMap<StaticId, Real>,
+    gamma: FxHashMap<StaticId, Real>,
+    vega: FxHashMap<StaticId, Real>,
+    vega_structure: FxHashMap<StaticId, Vec<Real>>,
+    theta: Real,
+    div_delta: FxHashMap<StaticId, Real>,
+    div_structure: FxHashMap<StaticId, Vec<Real>>,
+    div_carry: FxHashMap<StaticId, Real>,
+    rho: FxHashMap<StaticId, Real>,
+    rho_structure: FxHashMap<StaticId, Vec<Real>>,
+    rho_by_role: FxHashMap<CurveRole, Real>,
+    delta_rho: FxHashMap<StaticId, FxHashMap<StaticId, Real>>,
+    gamma_structure: FxHashMap<StaticId, Vec<Real>>,
+    key_rate_dv01: FxHashMap<StaticId, Vec<Real>>,
+    cs01: FxHashMap<StaticId, Real>,
+    cs01_structure: FxHashMap<StaticId, Vec<Real>>,
+    carry: Real,
+    roll_down: Real,
+    cashflows: FxHashMap<OffsetDateTime, Real>,
+    cashflow_ladder: FxHashMap<Currency, Vec<(OffsetDateTime, Real)>>,
+}
+
+impl PortfolioResult {
+    pub fn new(currency: Currency) -> PortfolioResult {
+        PortfolioResult {
+            currency,
+            evaluation_date: None,
+            instrument_ids: vec![],
+            value: 0.0,
+            fx_exposure: FxHashMap::default(),
+            fx_exposure_ladder: FxHashMap::default(),
+            fx_delta: FxHashMap::default(),
+            fx_gamma: FxHashMap::default(),
+            delta: FxHashMap::default(),
+            gamma: FxHashMap::default(),
+            vega: FxHashMap::default(),
+            vega_structure: FxHashMap::default(),
+            theta: 0.0,
+            div_delta: FxHashMap::default(),
+            div_structure: FxHashMap::default(),
+            div_carry: FxHashMap::default(),
+            rho: FxHashMap::default(),
+            rho_structure: FxHashMap::default(),
+            rho_by_role: FxHashMap::default(),
+            delta_rho: FxHashMap::default(),
+            gamma_structure: FxHashMap::default(),
+            key_rate_dv01: FxHashMap::default(),
+            cs01: FxHashMap::default(),
+            cs01_structure: FxHashMap::default(),
+            carry: 0.0,
+            roll_down: 0.0,
+            cashflows: FxHashMap::default(),
+            cashflow_ladder: FxHashMap::default(),
+        }
+    }
+
+    /// fx_map has the fx rates to the target currency, e.g., USDKRW -> 1300.0 for KRW.
+    /// The inverse code (KRWUSD -> 1/1300.0) is also looked up.
+    pub fn aggregate(
+        results: &FxHashMap<StaticId, CalculationResult>,
+        target_currency: Currency,
+        fx_map: &FxHashMap<FxCode, Real>,
+    ) -> Result<PortfolioResult> {
+        let mut res = PortfolioResult::new(target_currency);
+        let mut inst_ids: Vec<&StaticId> = results.keys().collect();
+        inst_ids.sort_by_key(|id| id.to_string());
+        for inst_id in inst_ids {
+            res.add_result(&results[inst_id], fx_map)?;
+        }
+        Ok(res)
+    }
+
+    /// adds a result converted to the currency of the portfolio
+    pub fn add_result(
+        &mut self,
+        result: &CalculationResult,
+        fx_map: &FxHashMap<FxCode, Real>,
+    ) -> Result<()> {
+        let inst_info = result.get_instrument_info().ok_or_else(|| {
+            anyhow!(
+                "({}:{}) instrument info is not set in the result",
+                file!(),
+                line!()
+            )
+        })?;
+        let inst_id = inst_info.id;
+        if self.instrument_ids.contains(&inst_id) {
+            return Err(anyhow!(
+                "({}:{}) {} is already aggregated",
+                file!(),
+                line!(),
+                inst_id,
+            ));
+        }
+        match (self.evaluation_date, result.get_evaluation_date()) {
+            (Some(dt), Some(result_dt)) if dt != *result_dt => {
+                return Err(anyhow!(
+                    "({}:{}) evaluation date of {} ({}) is different from the portfolio ({})",
+                    file!(),
+                    line!(),
+                    inst_id,
+                    result_dt,
+                    dt,
+                ));
+            }
+            (None, Some(result_dt)) => self.evaluation_date = Some(*result_dt),
+            _ => {}
+        }
+
+        let from = result.get_representation_currency().ok_or_else(|| {
+            anyhow!(
+                "({}:{}) representation currency is not set in {}",
+                file!(),
+                line!(),
+                inst_id,
+            )
+        })?;
+        let fx_rate = get_fx_rate(fx_map, from, self.currency)?;
+        let converted = result.representation_currency_conversion(self.currency, fx_rate)?;
+
+        self.value += converted.get_value().unwrap_or(0.0);
+        self.theta += converted.get_theta().unwrap_or(0.0);
+        self.carry += converted.get_carry().unwrap_or(0.0);
+        self.roll_down += converted.get_roll_down().unwrap_or(0.0);
+
+        add_map(&mut self.fx_exposure, result.get_fx_exposure());
+        add_structure_map(
+            &mut self.fx_exposure_ladder,
+            result.get_fx_exposure_ladder(),
+        )?;
+        add_map(&mut self.fx_delta, converted.get_fx_delta());
+        add_map(&mut self.fx_gamma, converted.get_fx_gamma());
+        add_map(&mut self.delta, converted.get_delta());
+        add_map(&mut self.gamma, converted.get_gamma());
+        add_map(&mut self.vega, converted.get_vega());
+        add_map(&mut self.div_delta, converted.get_div_delta());
+        add_map(&mut self.div_carry, converted.get_div_carry());
+        add_map(&mut self.rho, converted.get_rho());
+        add_map(&mut self.rho_by_role, converted.get_rho_by_role());
+        add_map(&mut self.cs01, converted.get_cs01());
+        if let Some(delta_rho) = converted.get_delta_rho() {
+            for (und_id, curve_map) in delta_rho.iter() {
+                add_map(self.delta_rho.entry(*und_id).or_default(), Some(curve_map));
+            }
+        }
+        add_structure_map(&mut self.vega_structure, converted.get_vega_structure())?;
+        add_structure_map(&mut self.div_structure, converted.get_div_structure())?;
+        add_structure_map(&mut self.rho_structure, converted.get_rho_structure())?;
+        add_structure_map(&mut self.gamma_structure, converted.get_gamma_structure())?;
+        add_structure_map(&mut self.key_rate_dv01, converted.get_key_rate_dv01())?;
+        add_structure_map(&mut self.cs01_structure, converted.get_cs01_structure())?;
+
+        if let Some(cashflows) = result.get_cashflows() {
+            let unit = inst_info.get_unit_notional();
+            for cashflow in cashflows.iter() {
+                let date = *cashflow.get_payment_date();
+                let currency = cashflow.get_currency();
+                let amount = cashflow.get_amount() * unit;
+                let cashflow_fx_rate = get_fx_rate(fx_map, currency, self.currency)?;
+                *self.cashflows.entry(date).or_insert(0.0) += amount * cashflow_fx_rate;
+
+                let ladder = self.cashflow_ladder.entry(currency).or_default();
+                match ladder.binary_search_by_key(&date, |(d, _)| *d) {
+                    Ok(idx) => ladder[idx].1 += amount,
+                    Err(idx) => ladder.insert(idx, (date, amount)),
+                }
+            }
+        }
+
+        self.instrument_ids.push(inst_id);
+        Ok(())
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn get_evaluation_date(&self) -> Option<&OffsetDateTime> {
+        self.evaluation_date.as_ref()
+    }
+
+    /// instruments in the order of aggregation
+    pub fn get_instrument_ids(&self) -> &Vec<StaticId> {
+        &self.instrument_ids
+    }
+
+    pub fn get_value(&self) -> Real {
+        self.value
+    }
+
+    pub fn get_fx_exposure(&self) -> &FxHashMap<Currency, Real> {
+        &self.fx_exposure
+    }
+
+    pub fn get_fx_exposure_ladder(&self) -> &FxHashMap<Currency, Vec<Real>> {
+        &self.fx_exposure_ladder
+    }
+
+    pub fn get_fx_delta(&self) -> &FxHashMap<FxCode, Real> {
+        &self.fx_delta
+    }
+
+    pub fn get_fx_gamma(&self) -> &FxHashMap<FxCode, Real> {
+        &self.fx_gamma
+    }
+
+    pub fn get_delta(&self) -> &FxHashMap<StaticId, Real> {
+        &self.delta
+    }
+
+    pub fn get_gamma(&self) -> &FxHashMap<StaticId, Real> {
+        &self.gamma
+    }
+
+    pub fn get_vega(&self) -> &FxHashMap<StaticId, Real> {
+        &self.vega
+    }
+
+    pub fn get_vega_structure(&self) -> &FxHashMap<StaticId, Vec<Real>> {
+        &self.vega_structure
+    }
+
+    pub fn get_theta(&self) -> Real {
+        self.theta
+    }
+
+    pub fn get_div_delta(&self) -> &FxHashMap<StaticId, Real> {
+        &self.div_delta
+    }
+
+    pub fn get_div_structure(&self) -> &FxHashMap<StaticId, Vec<Real>> {
+        &self.div_structure
+    }
+
+    pub fn get_div_carry(&self) -> &FxHashMap<StaticId, Real> {
+        &self.div_carry
+    }
+
+    pub fn get_rho(&self) -> &FxHashMap<StaticId, Real> {
+        &self.rho
+    }
+
+    pub fn get_rho_structure(&self) -> &FxHashMap<StaticId, Vec<Real>> {
+        &self.rho_structure
+    }
+
+    pub fn get_rho_by_role(&self) -> &FxHashMap<CurveRole, Real> {
+        &self.rho_by_role
+    }
+
+    /// underlying id -> curve id -> delta change on 1bp
+    pub fn get_delta_rho(&self) -> &FxHashMap<StaticId, FxHashMap<StaticId, Real>> {
+        &self.delta_rho
+    }
+
+    pub fn get_gamma_structure(&self) -> &FxHashMap<StaticId, Vec<Real>> {
+        &self.gamma_structure
+    }
+
+    pub fn get_key_rate_dv01(&self) -> &FxHashMap<StaticId, Vec<Real>> {
+        &self.key_rate_dv01
+    }
+
+    pub fn get_cs01(&self) -> &FxHashMap<StaticId, Real> {
+        &self.cs01
+    }
+
+    pub fn get_cs01_structure(&self) -> &FxHashMap<StaticId, Vec<Real>> {
+        &self.cs01_structure
+    }
+
+    pub fn get_carry(&self) -> Real {
+        self.carry
+    }
+
+    pub fn get_roll_down(&self) -> Real {
+        self.roll_down
+    }
+
+    /// expected cashflows of the portfolio in its currency
+    pub fn get_cashflows(&self) -> &FxHashMap<OffsetDateTime, Real> {
+        &self.cashflows
+    }
+
+    /// currency -> (payment date, amount) sorted by the payment date
+    pub fn get_cashflow_ladder(&self) -> &FxHashMap<Currency, Vec<(OffsetDateTime, Real)>> {
+        &self.cashflow_ladder
+    }
+}
+
+/// the amount of `to` currency for a unit of `from` currency
+fn get_fx_rate(fx_map: &FxHashMap<FxCode, Real>, from: Currency, to: Currency) -> Result<Real> {
+    if from == to {
+        return Ok(1.0);
+    }
+    if let Some(rate) = fx_map.get(&FxCode::new(from, to)) {
+        return Ok(*rate);
+    }
+    if let Some(rate) = fx_map.get(&FxCode::new(to, from)) {
+        return Ok(1.0 / rate);
+    }
+    Err(anyhow!(
+        "({}:{}) no fx rate for {}/{} in the fx map",
+        file!(),
+        line!(),
+        from,
+        to,
+    ))
+}
+
+fn add_map<K: Copy + Eq + Hash>(acc: &mut FxHashMap<K, Real>, map: Option<&FxHashMap<K, Real>>) {
+    if let Some(map) = map {
+        for (key, v) in map.iter() {
+            *acc.entry(*key).or_insert(0.0) += v;
+        }
+    }
+}
+
+/// structures on the same key must be on the same tenors
+fn add_structure_map<K: Copy + Eq + Hash + Display>(
+    acc: &mut FxHashMap<K, Vec<Real>>,
+    map: Option<&FxHashMap<K, Vec<Real>>>,
+) -> Result<()> {
+    if let Some(map) = map {
+        for (key, structure) in map.iter() {
+            let sum = acc
+                .entry(*key)
+                .or_insert_with(|| vec![0.0; structure.len()]);
+            if sum.len() != structure.len() {
+                return Err(anyhow!(
+                    "({}:{}) structure length of {} is {}, but {} is aggregated",
+                    file!(),
+                    line!(),
+                    key,
+                    structure.len(),
+                    sum.len(),
+                ));
+            }
+            for (s, v) in sum.iter_mut().zip(structure.iter()) {
+                *s += v;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruments::cashflow::{Cashflow, CashflowLeg, CashflowType};
+    use crate::instruments::inst_info::InstInfo;
+    use crate::pricing_engines::npv_result::NpvResult;
+    use crate::InstType;
+    use time::macros::datetime;
+
+    fn result(
+        name: &str,
+        currency: Currency,
+        unit_notional: Real,
+        npv: Real,
+        dt: OffsetDateTime,
+    ) -> CalculationResult {
+        let inst_info = InstInfo {
+            id: StaticId::from_str(name, "KRX"),
+            name: name.to_string(),
+            inst_type: InstType::Futures,
+            currency,
+            unit_notional,
+            ..Default::default()
+        };
+        let mut result = CalculationResult::new(inst_info, dt);
+        result.set_npv(NpvResult::new_from_npv(npv));
+        result.set_value().unwrap();
+        result
+    }
+
+    #[test]
+    fn test_portfolio_result() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let curve_id = StaticId::from_str("KRWGOV", "DataProvider");
+        let pay_date = datetime!(2024-06-13 16:30:00 +09:00);
+
+        let mut krw = result("KRW Fut", Currency::KRW, 10.0, 100.0, dt);
+        krw.set_single_delta(und_id, 50.0);
+        krw.set_single_rho_structure(curve_id, vec![1.0, 2.0]);
+        let mut krw_fx_exposure = FxHashMap::default();
+        krw_fx_exposure.insert(Currency::KRW, 1000.0);
+        krw.set_fx_exposure(krw_fx_exposure);
+        krw.set_cashflows(vec![Cashflow::new(
+            pay_date,
+            1.0,
+            Currency::KRW,
+            CashflowLeg::Single,
+            CashflowType::Expected,
+        )]);
+
+        let mut usd = result("USD Fut", Currency::USD, 2.0, 5.0, dt);
+        usd.set_single_delta(und_id, 1.0);
+        usd.set_single_rho_structure(curve_id, vec![0.1, 0.2]);
+        let mut usd_fx_exposure = FxHashMap::default();
+        usd_fx_exposure.insert(Currency::USD, 10.0);
+        usd.set_fx_exposure(usd_fx_exposure);
+        usd.set_cashflows(vec![Cashflow::new(
+            pay_date,
+            1.0,
+            Currency::USD,
+            CashflowLeg::Single,
+            CashflowType::Expected,
+        )]);
+
+        let mut results = FxHashMap::default();
+        results.insert(StaticId::from_str("KRW Fut", "KRX"), krw);
+        results.insert(StaticId::from_str("USD Fut", "KRX"), usd.clone());
+
+        let mut fx_map = FxHashMap::default();
+        fx_map.insert(FxCode::new(Currency::USD, Currency::KRW), 1300.0);
+
+        let res = PortfolioResult::aggregate(&results, Currency::KRW, &fx_map)?;
+        assert_eq!(res.get_instrument_ids().len(), 2);
+        assert_eq!(res.get_evaluation_date(), Some(&dt));
+        // 100 * 10 + 5 * 2 * 1300
+        assert!((res.get_value() - 14_000.0).abs() < 1.0e-2);
+        assert!((res.get_delta()[&und_id] - 1_350.0).abs() < 1.0e-2);
+        let rho_structure = &res.get_rho_structure()[&curve_id];
+        assert!((rho_structure[0] - 131.0).abs() < 1.0e-3);
+        assert!((rho_structure[1] - 262.0).abs() < 1.0e-3);
+        assert_eq!(res.get_fx_exposure()[&Currency::KRW], 1000.0);
+        assert_eq!(res.get_fx_exposure()[&Currency::USD], 10.0);
+        // 1 * 10 + 1 * 2 * 1300
+        assert!((res.get_cashflows()[&pay_date] - 2_610.0).abs() < 1.0e-2);
+        assert_eq!(res.get_cashflow_ladder()[&Currency::KRW], vec![(pay_date, 10.0)]);
+        assert_eq!(res.get_cashflow_ladder()[&Currency::USD], vec![(pay_date, 2.0)]);
+
+        // the inverse fx code is also used
+        let mut inverse_fx_map = FxHashMap::default();
+        inverse_fx_map.insert(FxCode::new(Currency::KRW, Currency::USD), 1.0 / 1300.0);
+        let inverse = PortfolioResult::aggregate(&results, Currency::USD, &inverse_fx_map)?;
+        assert!((inverse.get_value() - 14_000.0 / 1300.0).abs() < 1.0e-3);
+
+        assert!(
+            PortfolioResult::aggregate(&results, Currency::KRW, &FxHashMap::default()).is_err()
+        );
+
+        // a result on another date or with a different structure length is not aggregated
+        let mut res = PortfolioResult::new(Currency::USD);
+        res.add_result(&usd, &fx_map)?;
+        assert!(res.add_result(&usd, &fx_map).is_err());
+        let other_date = result("Other Fut", Currency::USD, 1.0, 1.0, pay_date);
+        assert!(res.add_result(&other_date, &fx_map).is_err());
+        let mut other_tenor = result("Other Fut", Currency::USD, 1.0, 1.0, dt);
+        other_tenor.set_single_rho_structure(curve_id, vec![0.1]);
+        assert!(res.add_result(&other_tenor, &fx_map).is_err());
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use crate::currency::Currency;
 use crate::definitions::Real;
 use crate::instrument::{Instrument, InstrumentTrait};
+use crate::pricing_engines::bond_analytics::BondAnalytics;
 use crate::pricing_engines::npv_result::NpvResult;
 use crate::pricing_engines::{
     bond_pricer::BondPricer, futures_pricer::FuturesPricer, fx_futures_pricer::FxFuturesPricer,
@@ -12,6 +13,25 @@ use crate::pricing_engines::{
 use anyhow::Result;
 use enum_dispatch::enum_dispatch;
 use rustc_hash::FxHashMap;
+use static_id::StaticId;
+use time::OffsetDateTime;
+
+/// currency -> (settlement date, fx_exposure settled on the date)
+pub type FxExposureByDate = FxHashMap<Currency, Vec<(OffsetDateTime, Real)>>;
+
+/// npv and its exact first order derivatives from the automatic differentiation of a pricer
+/// - spot: dV/dS * S for each underlying id
+/// - volatility: dV/dσ for a parallel shift of the volatility of each underlying id
+/// - rates: dV/dr for a parallel shift of the zero rates of each curve id
+///
+/// The values are for a unit of the instrument, i.e., unit_notional is not considered.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FirstOrderGreeks {
+    pub npv: Real,
+    pub spot: FxHashMap<StaticId, Real>,
+    pub volatility: FxHashMap<StaticId, Real>,
+    pub rates: FxHashMap<StaticId, Real>,
+}
 
 #[enum_dispatch]
 pub trait PricerTrait {
@@ -28,6 +48,28 @@ pub trait PricerTrait {
         );
         Ok(map)
     }
+
+    /// fx_exposure on the settlement dates of the cashflows, unit_notional is considered.
+    /// None if the whole fx_exposure settles on the maturity of the instrument.
+    fn fx_exposure_by_date(
+        &self,
+        _instrument: &Instrument,
+        _npv: Real,
+    ) -> Result<Option<FxExposureByDate>> {
+        Ok(None)
+    }
+
+    /// clean and dirty prices, accrued interest, yield, duration and convexity on a unit notional.
+    /// None if the pricer does not price bonds.
+    fn bond_analytics(&self, _instrument: &Instrument, _npv: Real) -> Result<Option<BondAnalytics>> {
+        Ok(None)
+    }
+
+    /// None if the pricer does not support the automatic differentiation for the instrument.
+    /// Then the greeks are calculated by bump and revalue.
+    fn first_order_greeks(&self, _instrument: &Instrument) -> Result<Option<FirstOrderGreeks>> {
+        Ok(None)
+    }
 }
 
 #[enum_dispatch(PricerTrait)]
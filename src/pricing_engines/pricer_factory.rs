@@ -1,5 +1,7 @@
-use crate::currency::FxCode;
-use crate::enums::VanillaOptionCalculationMethod;
+use crate::currency::{Currency, FxCode};
+use crate::definitions::Real;
+use crate::data::vector_data::VectorData;
+use crate::enums::{CurveRole, VanillaOptionCalculationMethod};
 use crate::evaluation_date::EvaluationDate;
 use crate::instrument::{Instrument, InstrumentTrait};
 use crate::parameters::{market_price::MarketPrice, past_price::DailyClosePrice};
@@ -15,7 +17,8 @@ use crate::pricing_engines::{
 };
 //
 use static_id::static_id::StaticId;
-use std::{cell::RefCell, rc::Rc};
+use ndarray::array;
+use std::sync::{Arc, RwLock};
 use rustc_hash::FxHashMap;
 
 use anyhow::{anyhow, Result};
@@ -23,29 +26,31 @@ use anyhow::{anyhow, Result};
 /// dividend is not needed for this pricer factory
 /// dividend is in herent in equities
 pub struct PricerFactory {
-    evaluation_date: Rc<RefCell<EvaluationDate>>,
-    fxs: FxHashMap<FxCode, Rc<RefCell<MarketPrice>>>,
-    equities: FxHashMap<StaticId, Rc<RefCell<MarketPrice>>>,
-    zero_curves: FxHashMap<StaticId, Rc<RefCell<ZeroCurve>>>,
-    underlying_volatilities: FxHashMap<StaticId, Rc<RefCell<Volatility>>>,
-    quantos: FxHashMap<(StaticId, FxCode), Rc<RefCell<Quanto>>>, // (underlying_code, fx_code) -> Quanto
-    past_close_data: FxHashMap<StaticId, Rc<DailyClosePrice>>,
-    match_parameter: Rc<MatchParameter>,
-    calculation_configuration: Rc<CalculationConfiguration>,
+    evaluation_date: Arc<RwLock<EvaluationDate>>,
+    fxs: FxHashMap<FxCode, Arc<RwLock<MarketPrice>>>,
+    equities: FxHashMap<StaticId, Arc<RwLock<MarketPrice>>>,
+    zero_curves: FxHashMap<StaticId, Arc<RwLock<ZeroCurve>>>,
+    underlying_volatilities: FxHashMap<StaticId, Arc<RwLock<Volatility>>>,
+    quantos: FxHashMap<(StaticId, FxCode), Arc<RwLock<Quanto>>>, // (underlying_code, fx_code) -> Quanto
+    past_close_data: FxHashMap<StaticId, Arc<DailyClosePrice>>,
+    match_parameter: Arc<MatchParameter>,
+    calculation_configuration: Arc<CalculationConfiguration>,
+    // parallel shift of the curves in the role, which is applied only to the pricers made by this factory
+    curve_shift: Option<(CurveRole, Real)>,
 }
 
 impl PricerFactory {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        evaluation_date: Rc<RefCell<EvaluationDate>>,
-        fxs: FxHashMap<FxCode, Rc<RefCell<MarketPrice>>>,
-        equities: FxHashMap<StaticId, Rc<RefCell<MarketPrice>>>,
-        zero_curves: FxHashMap<StaticId, Rc<RefCell<ZeroCurve>>>,
-        underlying_volatilities: FxHashMap<StaticId, Rc<RefCell<Volatility>>>,
-        quantos: FxHashMap<(StaticId, FxCode), Rc<RefCell<Quanto>>>,
-        past_close_data: FxHashMap<StaticId, Rc<DailyClosePrice>>,
-        match_parameter: Rc<MatchParameter>,
-        calculation_configuration: Rc<CalculationConfiguration>,
+        evaluation_date: Arc<RwLock<EvaluationDate>>,
+        fxs: FxHashMap<FxCode, Arc<RwLock<MarketPrice>>>,
+        equities: FxHashMap<StaticId, Arc<RwLock<MarketPrice>>>,
+        zero_curves: FxHashMap<StaticId, Arc<RwLock<ZeroCurve>>>,
+        underlying_volatilities: FxHashMap<StaticId, Arc<RwLock<Volatility>>>,
+        quantos: FxHashMap<(StaticId, FxCode), Arc<RwLock<Quanto>>>,
+        past_close_data: FxHashMap<StaticId, Arc<DailyClosePrice>>,
+        match_parameter: Arc<MatchParameter>,
+        calculation_configuration: Arc<CalculationConfiguration>,
     ) -> PricerFactory {
         PricerFactory {
             evaluation_date,
@@ -57,11 +62,79 @@ impl PricerFactory {
             past_close_data,
             match_parameter,
             calculation_configuration,
+            curve_shift: None,
         }
     }
 
-    pub fn create_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
-        let pricer = match Rc::as_ref(instrument) {
+    /// The curves in the role are shifted in parallel by shift (continuous compounding) in the pricers,
+    /// while the other roles take the same curves unshifted even if the curve ids are the same.
+    /// This separates, e.g., the rho on the discounting from that on the projection of a single curve.
+    pub fn with_curve_shift(mut self, role: CurveRole, shift: Real) -> PricerFactory {
+        self.curve_shift = Some((role, shift));
+        self
+    }
+
+    /// roles of the curves in the pricer of the instrument
+    pub fn get_curve_roles(&self, instrument: &Instrument) -> Result<Vec<CurveRole>> {
+        let res = match instrument {
+            Instrument::Bond(_) | Instrument::PlainSwap(_) => {
+                match instrument.get_rate_index()? {
+                    Some(_) => vec![CurveRole::Discount, CurveRole::Forward],
+                    None => vec![CurveRole::Discount],
+                }
+            }
+            Instrument::Futures(_) => vec![CurveRole::Collateral, CurveRole::Borrowing],
+            Instrument::VanillaOption(_) => vec![
+                CurveRole::Discount,
+                CurveRole::Collateral,
+                CurveRole::Borrowing,
+            ],
+            Instrument::KTBF(_) => vec![CurveRole::Discount, CurveRole::Collateral],
+            Instrument::FxFutures(_) => vec![CurveRole::Discount],
+            _ => vec![],
+        };
+        Ok(res)
+    }
+
+    /// the curve shifted by self.curve_shift if the role is shifted, otherwise the curve itself
+    fn curve_in_role(
+        &self,
+        role: CurveRole,
+        curve: Arc<RwLock<ZeroCurve>>,
+    ) -> Result<Arc<RwLock<ZeroCurve>>> {
+        let shift = match self.curve_shift {
+            Some((shifted_role, shift)) if shifted_role == role => shift,
+            _ => return Ok(curve),
+        };
+        let (name, id, currency) = {
+            let curve = curve.read().unwrap();
+            (
+                format!("{} + {:?} shift", curve.get_name_clone(), role),
+                curve.get_id(),
+                curve.get_currency(),
+            )
+        };
+        let shift_data = VectorData::new(
+            array![shift],
+            None,
+            Some(array![1.0]),
+            None,
+            currency,
+            name.clone(),
+            id,
+        )?;
+        let shift_curve = Arc::new(RwLock::new(ZeroCurve::new(
+            self.evaluation_date.clone(),
+            &shift_data,
+            name.clone(),
+            id,
+        )?));
+        let res = ZeroCurve::new_composite_curve(vec![curve, shift_curve], name, id)?;
+        Ok(Arc::new(RwLock::new(res)))
+    }
+
+    pub fn create_pricer(&self, instrument: &Arc<Instrument>) -> Result<Pricer> {
+        let pricer = match Arc::as_ref(instrument) {
             Instrument::Futures(_) => self.get_futures_pricer(instrument)?,
             Instrument::VanillaOption(_) => self.get_vanilla_option_pricer(instrument)?,
             Instrument::Bond(_) => self.get_bond_pricer(instrument)?,
@@ -85,7 +158,46 @@ impl PricerFactory {
         Ok(pricer)
     }
 
-    fn get_bond_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+    /// If the counterparty of the instrument has a funding spread curve in the currency,
+    /// the discount curve is layered with the funding spread curve.
+    /// Otherwise, the discount curve is returned as it is.
+    fn apply_funding_spread(
+        &self,
+        instrument: &Arc<Instrument>,
+        discount_curve: Arc<RwLock<ZeroCurve>>,
+        currency: Currency,
+    ) -> Result<Arc<RwLock<ZeroCurve>>> {
+        let spread_curve_id = self
+            .match_parameter
+            .get_funding_spread_curve_id(instrument, currency);
+        if spread_curve_id == StaticId::default() {
+            return Ok(discount_curve);
+        }
+
+        let spread_curve = self
+            .zero_curves
+            .get(&spread_curve_id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) failed to get funding spread curve of {}.\nself.zero_curves does not have {}",
+                    file!(),
+                    line!(),
+                    instrument.get_id(),
+                    spread_curve_id,
+                )
+            })?
+            .clone();
+        let name = format!(
+            "{} + {}",
+            discount_curve.read().unwrap().get_name_clone(),
+            spread_curve.read().unwrap().get_name_clone()
+        );
+        let id = discount_curve.read().unwrap().get_id();
+        let res = ZeroCurve::new_composite_curve(vec![discount_curve, spread_curve], name, id)?;
+        Ok(Arc::new(RwLock::new(res)))
+    }
+
+    fn get_bond_pricer(&self, instrument: &Arc<Instrument>) -> Result<Pricer> {
         let discount_curve_id = self.match_parameter.get_discount_curve_id(instrument)?;
         let discount_curve = self
             .zero_curves
@@ -102,6 +214,9 @@ impl PricerFactory {
                 )
             })?
             .clone();
+        let discount_curve =
+            self.apply_funding_spread(instrument, discount_curve, instrument.get_currency())?;
+        let discount_curve = self.curve_in_role(CurveRole::Discount, discount_curve)?;
 
         let rate_index: Option<&RateIndex> = instrument.get_rate_index()?;
         let forward_curve = match rate_index {
@@ -122,7 +237,7 @@ impl PricerFactory {
                         )
                     })?
                     .clone();
-                Some(res)
+                Some(self.curve_in_role(CurveRole::Forward, res)?)
             }
         }; // the end of the forward curve construction which is optional
 
@@ -140,48 +255,54 @@ impl PricerFactory {
             }
         }; // the end of the past fixing data construction which is optional
 
+        let (mean_reversion, volatility) = self.calculation_configuration.get_hull_white_parameters();
         let core = BondPricer::new(
             self.evaluation_date.clone(),
             discount_curve,
             forward_curve,
             past_fixing_data,
-        );
+        )
+        .with_hull_white_parameters(mean_reversion, volatility);
         Ok(Pricer::BondPricer(core))
     }
     
-    fn get_futures_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+    fn get_futures_pricer(&self, instrument: &Arc<Instrument>) -> Result<Pricer> {
         let underlying_ids = instrument.get_underlying_ids();
         let equity = self.equities.get(&underlying_ids[0]).unwrap().clone();
         let collatral_curve_id = self.match_parameter.get_collateral_curve_ids(instrument)?[0];
         let borrowing_curve_id = self.match_parameter.get_borrowing_curve_ids(instrument)?[0];
+        let collateral_curve = self
+            .zero_curves
+            .get(&collatral_curve_id)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "failed to get collateral curve of {}.\nself.zero_curves does not have {}",
+                    instrument.get_id(),
+                    collatral_curve_id,
+                )
+            })?
+            .clone();
+        let borrowing_curve = self
+            .zero_curves
+            .get(&borrowing_curve_id)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "failed to get borrowing curve of {}.\nself.zero_curves does not have {}",
+                    instrument.get_id(),
+                    borrowing_curve_id,
+                )
+            })?
+            .clone();
         let core = FuturesPricer::new(
             //self.evaluation_date.clone(),
             equity,
-            self.zero_curves
-                .get(&collatral_curve_id)
-                .ok_or_else(|| {
-                    anyhow::anyhow!(
-                        "failed to get collateral curve of {}.\nself.zero_curves does not have {}",
-                        instrument.get_id(),
-                        collatral_curve_id,
-                    )
-                })?
-                .clone(),
-            self.zero_curves
-                .get(&borrowing_curve_id)
-                .ok_or_else(|| {
-                    anyhow::anyhow!(
-                        "failed to get borrowing curve of {}.\nself.zero_curves does not have {}",
-                        instrument.get_id(),
-                        borrowing_curve_id,
-                    )
-                })?
-                .clone(),
+            self.curve_in_role(CurveRole::Collateral, collateral_curve)?,
+            self.curve_in_role(CurveRole::Borrowing, borrowing_curve)?,
         );
         Ok(Pricer::FuturesPricer(core))
     }
 
-    fn get_vanilla_option_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+    fn get_vanilla_option_pricer(&self, instrument: &Arc<Instrument>) -> Result<Pricer> {
         let equity = self
             .equities
             .get(&instrument.get_underlying_ids()[0])
@@ -210,6 +331,9 @@ impl PricerFactory {
             )
             })?
             .clone();
+        let discount_curve =
+            self.apply_funding_spread(instrument, discount_curve, instrument.get_currency())?;
+        let discount_curve = self.curve_in_role(CurveRole::Discount, discount_curve)?;
 
         let collateral_curve_id = self
             .match_parameter
@@ -224,6 +348,7 @@ impl PricerFactory {
             )
             })?
             .clone();
+        let collatral_curve = self.curve_in_role(CurveRole::Collateral, collatral_curve)?;
         let borrowing_curve_id = self.match_parameter.get_borrowing_curve_ids(instrument)?[0];
         let borrowing_curve = self
             .zero_curves
@@ -235,6 +360,7 @@ impl PricerFactory {
             )
             })?
             .clone();
+        let borrowing_curve = self.curve_in_role(CurveRole::Borrowing, borrowing_curve)?;
 
         let curr = instrument.get_currency();
         let und_curr = instrument.get_underlying_currency()?;
@@ -272,10 +398,15 @@ impl PricerFactory {
             ),
             _ => return Err(anyhow::Error::msg("Unsupported calculation method")),
         };
+        // the option expires at the close of the underlying market
+        let core = match self.past_close_data.get(&instrument.get_underlying_ids()[0]) {
+            Some(market_close) => core.with_market_close(market_close.clone()),
+            None => core,
+        };
         Ok(Pricer::OptionAnalyticPricer(core))
     }
 
-    fn get_ktbf_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+    fn get_ktbf_pricer(&self, instrument: &Arc<Instrument>) -> Result<Pricer> {
         let discount_curve_id = StaticId::from_str("KRWGOV", "KAP");
         let discount_curve = self
             .zero_curves
@@ -294,16 +425,32 @@ impl PricerFactory {
                 "({}:{}) failed to get collateral curve of {}.\nself.zero_curves does not have {}",
                 file!(), line!(), instrument.get_id(), collateral_curve_id,
             )})?.clone();
+        let mut repo_curves = FxHashMap::default();
+        for bond in instrument.get_underlying_bonds()?.iter() {
+            let repo_curve_id = self
+                .match_parameter
+                .get_repo_curve_id(&bond.get_id(), bond.get_currency());
+            if repo_curve_id == StaticId::default() {
+                continue;
+            }
+            let repo_curve = self.zero_curves.get(&repo_curve_id)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                    "({}:{}) failed to get repo curve of {} in {}.\nself.zero_curves does not have {}",
+                    file!(), line!(), bond.get_id(), instrument.get_id(), repo_curve_id,
+                )})?.clone();
+            repo_curves.insert(bond.get_id(), repo_curve);
+        }
         let core = KtbfPricer::new(
             self.evaluation_date.clone(),
-            discount_curve,
-            collateral_curve,
-        );
+            self.curve_in_role(CurveRole::Discount, discount_curve)?,
+            self.curve_in_role(CurveRole::Collateral, collateral_curve)?,
+        ).with_repo_curves(repo_curves);
 
         Ok(Pricer::KtbfPricer(core))
     }
 
-    fn get_fx_futures_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+    fn get_fx_futures_pricer(&self, instrument: &Arc<Instrument>) -> Result<Pricer> {
         let fx_code = instrument.get_fxfutres_und_fxcode()?;
 
         let fx = self
@@ -335,13 +482,13 @@ impl PricerFactory {
         let core = FxFuturesPricer::new(
             //self.evaluation_date.clone(),
             fx,
-            underlying_currency_curve,
-            futures_currency_curve,
+            self.curve_in_role(CurveRole::Discount, underlying_currency_curve)?,
+            self.curve_in_role(CurveRole::Discount, futures_currency_curve)?,
         );
         Ok(Pricer::FxFuturesPricer(core))
     }
 
-    fn get_plain_swap_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+    fn get_plain_swap_pricer(&self, instrument: &Arc<Instrument>) -> Result<Pricer> {
         let fixed_leg_discount_curve_id = self.match_parameter.get_crs_curve_id(instrument)?;
         let fixed_leg_discount_curve = self.zero_curves.get(&fixed_leg_discount_curve_id)
             .ok_or_else(|| anyhow::anyhow!(
@@ -358,6 +505,21 @@ impl PricerFactory {
                 file!(), line!(), instrument.get_id(), floating_leg_discount_curve_id,
             ))?.clone();
 
+        let fixed_leg_discount_curve = self.apply_funding_spread(
+            instrument,
+            fixed_leg_discount_curve,
+            instrument.get_fixed_leg_currency()?,
+        )?;
+        let floating_leg_discount_curve = self.apply_funding_spread(
+            instrument,
+            floating_leg_discount_curve,
+            instrument.get_floating_leg_currency()?,
+        )?;
+        let fixed_leg_discount_curve =
+            self.curve_in_role(CurveRole::Discount, fixed_leg_discount_curve)?;
+        let floating_leg_discount_curve =
+            self.curve_in_role(CurveRole::Discount, floating_leg_discount_curve)?;
+
         let rate_index = instrument.get_rate_index()?;
         let forward_curve = match rate_index {
             Some(_) => {
@@ -367,7 +529,7 @@ impl PricerFactory {
                         "({}:{}) failed to get forward curve of {}.\nself.zero_curves does not have {}",
                         file!(), line!(), instrument.get_id(), forward_curve_id,
                     ))?.clone();
-                Some(res)
+                Some(self.curve_in_role(CurveRole::Forward, res)?)
             }
             None => None,
         };
@@ -417,7 +579,7 @@ impl PricerFactory {
         Ok(Pricer::PlainSwapPricer(core))
     }
 
-    fn get_stock_pricer(&self, instrument: &Rc<Instrument>) -> Result<Pricer> {
+    fn get_stock_pricer(&self, instrument: &Arc<Instrument>) -> Result<Pricer> {
         let equity = self
             .equities
             .get(&instrument.get_id())
@@ -430,11 +592,11 @@ impl PricerFactory {
                 )
             })?
             .clone();
-        let core = IdentityPricer::new(equity);
+        let core = IdentityPricer::new(equity, self.evaluation_date.clone());
         Ok(Pricer::IdentityPricer(core))
     }
 
-    fn get_cash_pricer(&self, _instrument: &Rc<Instrument>) -> Result<Pricer> {
+    fn get_cash_pricer(&self, _instrument: &Arc<Instrument>) -> Result<Pricer> {
         let core = UnitPricer::new();
         Ok(Pricer::UnitPricer(core))
     }
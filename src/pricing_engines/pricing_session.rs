@@ -0,0 +1,143 @@
+use crate::pricing_engines::{
+    calculation_result::CalculationResult,
+    engine_generator::{EngineGenerator, MarketDataUpdate},
+    market_data_source::MarketDataSource,
+};
+//
+use anyhow::Result;
+use rustc_hash::FxHashMap;
+use static_id::static_id::StaticId;
+use std::sync::mpsc::{Receiver, Sender};
+
+/// Results of the instruments recalculated on a market data update in a PricingSession.
+/// sequence is the number of the updates processed in the session including this one.
+#[derive(Debug, Clone)]
+pub struct SessionUpdate {
+    sequence: u64,
+    updated_ids: Vec<StaticId>,
+    results: FxHashMap<StaticId, CalculationResult>,
+}
+
+impl SessionUpdate {
+    pub fn get_sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// sorted by the string of the ids
+    pub fn get_updated_ids(&self) -> &Vec<StaticId> {
+        &self.updated_ids
+    }
+
+    pub fn get_results(&self) -> &FxHashMap<StaticId, CalculationResult> {
+        &self.results
+    }
+}
+
+type SessionCallback = Box<dyn FnMut(&SessionUpdate) + Send>;
+
+/// A long-lived EngineGenerator to which the market data updates (fx tick, spot tick, curve re-mark, etc.)
+/// are pushed. Each update recalculates only the instruments using the data (EngineGenerator::update_data),
+/// and the new results are emitted to the callbacks and the subscribed channels, e.g., for intraday risk monitoring.
+pub struct PricingSession {
+    engine_generator: EngineGenerator,
+    callbacks: Vec<SessionCallback>,
+    subscribers: Vec<Sender<SessionUpdate>>,
+    sequence: u64,
+}
+
+impl PricingSession {
+    /// the engine generator is calculated here if it has no results yet
+    pub fn new(mut engine_generator: EngineGenerator) -> Result<PricingSession> {
+        if engine_generator.get_calculation_results().is_empty() {
+            engine_generator.calculate()?;
+        }
+        Ok(PricingSession {
+            engine_generator,
+            callbacks: vec![],
+            subscribers: vec![],
+            sequence: 0,
+        })
+    }
+
+    pub fn with_callback<F>(mut self, callback: F) -> PricingSession
+    where
+        F: FnMut(&SessionUpdate) + Send + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// a channel receiving the updates from now on.
+    /// The channel is dropped from the session when the receiver is dropped.
+    pub fn subscribe(&mut self) -> Receiver<SessionUpdate> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// applies the update, and emits the results of the recalculated instruments
+    pub fn push(&mut self, update: MarketDataUpdate) -> Result<SessionUpdate> {
+        let updated_ids = self.engine_generator.update_data(update)?;
+        let all_results = self.engine_generator.get_calculation_results();
+        let results = updated_ids
+            .iter()
+            .filter_map(|id| all_results.get(id).map(|result| (*id, result.clone())))
+            .collect();
+        self.sequence += 1;
+        let session_update = SessionUpdate {
+            sequence: self.sequence,
+            updated_ids,
+            results,
+        };
+
+        for callback in self.callbacks.iter_mut() {
+            callback(&session_update);
+        }
+        self.subscribers
+            .retain(|sender| sender.send(session_update.clone()).is_ok());
+        Ok(session_update)
+    }
+
+    /// pushes the updates from the channel until all the senders are dropped,
+    /// so that the session can run in its own thread fed by the market data handlers.
+    /// It returns the number of the processed updates, and stops at the first failed update.
+    pub fn run(&mut self, updates: Receiver<MarketDataUpdate>) -> Result<u64> {
+        let mut count = 0;
+        for update in updates.iter() {
+            self.push(update)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// subscribes the source to the market data of the engine generator, pushes its snapshot,
+    /// and returns its stream to be run, e.g., in the thread of the session
+    pub fn connect<S: MarketDataSource + ?Sized>(
+        &mut self,
+        source: &mut S,
+    ) -> Result<Receiver<MarketDataUpdate>> {
+        source.subscribe_all(&self.engine_generator.get_market_data_keys())?;
+        let stream = source.stream()?;
+        for update in source.snapshot()? {
+            self.push(update)?;
+        }
+        Ok(stream)
+    }
+
+    pub fn get_sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    pub fn get_engine_generator(&self) -> &EngineGenerator {
+        &self.engine_generator
+    }
+
+    /// e.g., to add limits or diagnostics to the session
+    pub fn get_engine_generator_mut(&mut self) -> &mut EngineGenerator {
+        &mut self.engine_generator
+    }
+
+    pub fn into_engine_generator(self) -> EngineGenerator {
+        self.engine_generator
+    }
+}
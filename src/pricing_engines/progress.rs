@@ -0,0 +1,29 @@
+use static_id::static_id::StaticId;
+use std::sync::Arc;
+
+/// Progress of the calculation reported to the callback of EngineGenerator::with_progress_callback,
+/// e.g., for a GUI or a batch scheduler to show the progress of a risk run.
+/// The events of the groups are reported from the threads calculating them, so they may interleave.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// the calculation of the groups started
+    Started {
+        group_number: usize,
+        instrument_number: usize,
+    },
+    /// a risk measure, e.g., "delta", is calculated for the instruments of a group
+    StageFinished {
+        group_id: usize,
+        stage: &'static str,
+    },
+    /// all the risk measures of the instruments of a group are calculated.
+    /// finished_instrument_number is the number of the instruments in the finished groups so far
+    GroupFinished {
+        group_id: usize,
+        instrument_ids: Vec<StaticId>,
+        finished_instrument_number: usize,
+        instrument_number: usize,
+    },
+}
+
+pub type ProgressCallback = Arc<dyn Fn(&ProgressEvent) + Send + Sync>;
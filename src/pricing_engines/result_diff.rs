@@ -0,0 +1,403 @@
+use crate::definitions::Real;
+use crate::export::result_table::get_greek_rows;
+use crate::pricing_engines::calculation_result::CalculationResult;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+
+/// metrics compared by ResultComparator, named as the fields of CalculationResult
+pub const DIFF_METRICS: [&str; 28] = [
+    "npv",
+    "value",
+    "fx_exposure",
+    "fx_exposure_ladder",
+    "fx_delta",
+    "fx_gamma",
+    "delta",
+    "gamma",
+    "vega",
+    "vega_structure",
+    "vega_matrix",
+    "theta",
+    "weekend_theta",
+    "div_delta",
+    "div_structure",
+    "div_carry",
+    "rho",
+    "rho_structure",
+    "gamma_structure",
+    "rho_by_role",
+    "delta_rho",
+    "key_rate_dv01",
+    "cs01",
+    "cs01_structure",
+    "carry",
+    "roll_down",
+    "exit_value",
+    "bid_ask_adjustment",
+];
+
+/// Two numbers are the same if |base - target| <= absolute + relative * max(|base|, |target|)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DiffTolerance {
+    absolute: Real,
+    relative: Real,
+}
+
+impl Default for DiffTolerance {
+    fn default() -> DiffTolerance {
+        DiffTolerance {
+            absolute: 1.0e-6,
+            relative: 1.0e-5,
+        }
+    }
+}
+
+impl DiffTolerance {
+    pub fn new(absolute: Real, relative: Real) -> Result<DiffTolerance> {
+        if absolute < 0.0 || relative < 0.0 || !absolute.is_finite() || !relative.is_finite() {
+            return Err(anyhow!(
+                "({}:{}) tolerances must be non-negative, got absolute = {} and relative = {}",
+                file!(),
+                line!(),
+                absolute,
+                relative,
+            ));
+        }
+        Ok(DiffTolerance { absolute, relative })
+    }
+
+    pub fn get_absolute(&self) -> Real {
+        self.absolute
+    }
+
+    pub fn get_relative(&self) -> Real {
+        self.relative
+    }
+
+    pub fn is_close(&self, base: Real, target: Real) -> bool {
+        if base.is_nan() || target.is_nan() {
+            return base.is_nan() && target.is_nan();
+        }
+        (base - target).abs() <= self.absolute + self.relative * base.abs().max(target.abs())
+    }
+}
+
+/// A number of a metric differing beyond the tolerance, or set on only one side.
+/// key is the risk factor of the metric (e.g., the underlying of delta) followed by the bucket index if any,
+/// and it is empty for the scalar metrics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricDifference {
+    inst_id: StaticId,
+    metric: String,
+    key: String,
+    base: Option<Real>,
+    target: Option<Real>,
+}
+
+impl MetricDifference {
+    pub fn get_inst_id(&self) -> StaticId {
+        self.inst_id
+    }
+
+    pub fn get_metric(&self) -> &String {
+        &self.metric
+    }
+
+    pub fn get_key(&self) -> &String {
+        &self.key
+    }
+
+    pub fn get_base(&self) -> Option<Real> {
+        self.base
+    }
+
+    pub fn get_target(&self) -> Option<Real> {
+        self.target
+    }
+
+    /// target - base, None if either side is not set
+    pub fn get_difference(&self) -> Option<Real> {
+        Some(self.target? - self.base?)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResultDiffReport {
+    compared_number: usize,
+    missing_in_base: Vec<StaticId>,
+    missing_in_target: Vec<StaticId>,
+    differences: Vec<MetricDifference>,
+}
+
+impl ResultDiffReport {
+    /// number of instruments in both sets
+    pub fn get_compared_number(&self) -> usize {
+        self.compared_number
+    }
+
+    pub fn get_missing_in_base(&self) -> &Vec<StaticId> {
+        &self.missing_in_base
+    }
+
+    pub fn get_missing_in_target(&self) -> &Vec<StaticId> {
+        &self.missing_in_target
+    }
+
+    pub fn get_differences(&self) -> &Vec<MetricDifference> {
+        &self.differences
+    }
+
+    pub fn get_differences_of(&self, inst_id: &StaticId) -> Vec<&MetricDifference> {
+        self.differences
+            .iter()
+            .filter(|d| d.inst_id == *inst_id)
+            .collect()
+    }
+
+    /// no instrument is missing and all metrics are within the tolerances
+    pub fn is_identical(&self) -> bool {
+        self.missing_in_base.is_empty()
+            && self.missing_in_target.is_empty()
+            && self.differences.is_empty()
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Compares two sets of results by the instrument id and the metric,
+/// e.g., the results of two library versions or two data snapshots on the same portfolio.
+/// The reporting results are not compared, so the sets are expected in the same currencies.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResultComparator {
+    default_tolerance: DiffTolerance,
+    metric_tolerances: FxHashMap<String, DiffTolerance>,
+}
+
+impl ResultComparator {
+    pub fn new(default_tolerance: DiffTolerance) -> ResultComparator {
+        ResultComparator {
+            default_tolerance,
+            metric_tolerances: FxHashMap::default(),
+        }
+    }
+
+    /// tolerance of a metric in DIFF_METRICS overriding the default tolerance
+    pub fn with_metric_tolerance(
+        mut self,
+        metric: &str,
+        tolerance: DiffTolerance,
+    ) -> Result<ResultComparator> {
+        if !DIFF_METRICS.contains(&metric) {
+            return Err(anyhow!(
+                "({}:{}) unknown metric {}, which must be one of {:?}",
+                file!(),
+                line!(),
+                metric,
+                DIFF_METRICS,
+            ));
+        }
+        self.metric_tolerances.insert(metric.to_string(), tolerance);
+        Ok(self)
+    }
+
+    pub fn get_tolerance(&self, metric: &str) -> DiffTolerance {
+        self.metric_tolerances
+            .get(metric)
+            .copied()
+            .unwrap_or(self.default_tolerance)
+    }
+
+    pub fn compare(
+        &self,
+        base: &FxHashMap<StaticId, CalculationResult>,
+        target: &FxHashMap<StaticId, CalculationResult>,
+    ) -> ResultDiffReport {
+        let mut report = ResultDiffReport::default();
+        let mut inst_ids: Vec<StaticId> = base.keys().chain(target.keys()).copied().collect();
+        inst_ids.sort_by_key(|id| id.to_string());
+        inst_ids.dedup();
+
+        for inst_id in inst_ids {
+            let (base_result, target_result) = match (base.get(&inst_id), target.get(&inst_id)) {
+                (Some(b), Some(t)) => (b, t),
+                (None, _) => {
+                    report.missing_in_base.push(inst_id);
+                    continue;
+                }
+                (_, None) => {
+                    report.missing_in_target.push(inst_id);
+                    continue;
+                }
+            };
+            report.compared_number += 1;
+            report
+                .differences
+                .extend(self.compare_result(inst_id, base_result, target_result));
+        }
+        report
+    }
+
+    /// differences of the metrics of a single instrument
+    pub fn compare_result(
+        &self,
+        inst_id: StaticId,
+        base: &CalculationResult,
+        target: &CalculationResult,
+    ) -> Vec<MetricDifference> {
+        let base_values = get_metric_values(base);
+        let mut target_values = get_metric_values(target);
+        let mut res = vec![];
+        for ((metric, key), base_value) in base_values.into_iter() {
+            let target_value = target_values.remove(&(metric, key.clone()));
+            let is_same = match target_value {
+                Some(t) => self.get_tolerance(metric).is_close(base_value, t),
+                None => false,
+            };
+            if !is_same {
+                res.push(MetricDifference {
+                    inst_id,
+                    metric: metric.to_string(),
+                    key,
+                    base: Some(base_value),
+                    target: target_value,
+                });
+            }
+        }
+        for ((metric, key), target_value) in target_values.into_iter() {
+            res.push(MetricDifference {
+                inst_id,
+                metric: metric.to_string(),
+                key,
+                base: None,
+                target: Some(target_value),
+            });
+        }
+        let order = |metric: &str| DIFF_METRICS.iter().position(|m| *m == metric);
+        res.sort_by(|a, b| {
+            order(&a.metric)
+                .cmp(&order(&b.metric))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        res
+    }
+}
+
+type MetricValues = FxHashMap<(&'static str, String), Real>;
+
+/// the numbers of the metrics in DIFF_METRICS keyed by (metric, key),
+/// where the greeks are flattened as in the result table
+fn get_metric_values(result: &CalculationResult) -> MetricValues {
+    let mut res: MetricValues = FxHashMap::default();
+    let scalars = [
+        ("npv", result.get_npv_result().map(|npv| npv.get_npv())),
+        ("value", result.get_value()),
+        ("theta", result.get_theta()),
+        ("weekend_theta", result.get_weekend_theta()),
+        ("carry", result.get_carry()),
+        ("roll_down", result.get_roll_down()),
+        ("exit_value", result.get_exit_value()),
+        ("bid_ask_adjustment", result.get_bid_ask_adjustment()),
+    ];
+    for (metric, value) in scalars {
+        if let Some(v) = value {
+            res.insert((metric, String::new()), v);
+        }
+    }
+    for row in get_greek_rows("", result) {
+        let key = match (row.sub_key, row.row, row.column) {
+            (Some(sub_key), _, _) => format!("{}/{}", row.key, sub_key),
+            (None, Some(i), Some(j)) => format!("{}[{},{}]", row.key, i, j),
+            (None, Some(i), None) => format!("{}[{}]", row.key, i),
+            (None, None, _) => row.key,
+        };
+        res.insert((row.greek, key), row.value);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing_engines::npv_result::NpvResult;
+    use crate::InstInfo;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_result_comparator() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let curve_id = StaticId::from_str("KSD", "DataProvider");
+        let result = |id: &str, npv: Real, delta: Real, rho_structure: Vec<Real>| {
+            let inst_info = InstInfo {
+                id: StaticId::from_str(id, "KRX"),
+                unit_notional: 250_000.0,
+                ..Default::default()
+            };
+            let mut result = CalculationResult::new(inst_info.clone(), dt);
+            result.set_npv(NpvResult::new_from_npv(npv));
+            result.set_value().unwrap();
+            result.set_single_delta(und_id, delta);
+            result.set_single_rho_structure(curve_id, rho_structure);
+            (inst_info.id, result)
+        };
+        let base: FxHashMap<StaticId, CalculationResult> = [
+            result("A", 10.0, 100.0, vec![1.0, 2.0]),
+            result("B", 5.0, -50.0, vec![0.5, 0.5]),
+            result("C", 1.0, 0.0, vec![]),
+        ]
+        .into_iter()
+        .collect();
+        let target: FxHashMap<StaticId, CalculationResult> = [
+            result("A", 10.0, 100.0001, vec![1.0, 2.5]),
+            result("B", 5.0, -50.0, vec![0.5, 0.5]),
+            result("D", 1.0, 0.0, vec![]),
+        ]
+        .into_iter()
+        .collect();
+
+        let comparator = ResultComparator::new(DiffTolerance::default());
+        let report = comparator.compare(&base, &target);
+        assert!(!report.is_identical());
+        assert_eq!(report.get_compared_number(), 2);
+        assert_eq!(
+            report.get_missing_in_target(),
+            &vec![StaticId::from_str("C", "KRX")]
+        );
+        assert_eq!(
+            report.get_missing_in_base(),
+            &vec![StaticId::from_str("D", "KRX")]
+        );
+        // delta within the relative tolerance, and the second bucket of rho_structure
+        let differences = report.get_differences();
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].get_metric(), "rho_structure");
+        assert_eq!(differences[0].get_key(), &format!("{}[1]", curve_id));
+        assert_eq!(differences[0].get_difference(), Some(0.5));
+
+        // a tighter tolerance on delta
+        let comparator =
+            comparator.with_metric_tolerance("delta", DiffTolerance::new(0.0, 0.0)?)?;
+        let report = comparator.compare(&base, &target);
+        let differences = report.get_differences_of(&StaticId::from_str("A", "KRX"));
+        assert_eq!(differences.len(), 2);
+        assert_eq!(differences[0].get_metric(), "delta");
+        assert!(report
+            .get_differences_of(&StaticId::from_str("B", "KRX"))
+            .is_empty());
+        assert!(comparator
+            .clone()
+            .with_metric_tolerance("unknown", DiffTolerance::default())
+            .is_err());
+
+        // the report is machine-readable
+        let json = report.to_json()?;
+        let parsed: ResultDiffReport = serde_json::from_str(&json)?;
+        assert_eq!(parsed, report);
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+//! Persistence of the calculation runs, e.g., the end of day results kept in a database,
+//! which are reloaded for the P&L explain (`PnlExplain::with_base_results`)
+//! and the backtests (`VarBacktest::from_snapshots`).
+//!
+//! - `ResultStore`: the storage of the runs, implemented for other databases by the integrators
+//! - `InMemoryResultStore`: a store in memory, e.g., for tests and short sessions
+//! - `sqlite::SqliteResultStore`: a store in a sqlite file (feature `sqlite`)
+//!
+//! # Examples
+//! ```
+//! use rustmetrics::pricing_engines::calculation_configuration::CalculationConfiguration;
+//! use rustmetrics::storage::{CalculationRun, InMemoryResultStore, ResultStore};
+//! use rustc_hash::FxHashMap;
+//! use time::macros::datetime;
+//!
+//! let configuration = CalculationConfiguration::default();
+//! let run = CalculationRun::new(
+//!     "eod-20240313".to_string(),
+//!     datetime!(2024-03-13 16:30:00 +09:00),
+//!     &configuration,
+//!     FxHashMap::default(),
+//! ).unwrap();
+//!
+//! let mut store = InMemoryResultStore::default();
+//! store.save_run(&run).unwrap();
+//! let loaded = store.load_run("eod-20240313").unwrap().unwrap();
+//! assert_eq!(loaded.get_configuration_hash(), run.get_configuration_hash());
+//! ```
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+use crate::pricing_engines::calculation_configuration::CalculationConfiguration;
+use crate::var::backtest::ResultSnapshot;
+//
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
+
+/// The hash of the calculation configuration, to tell the runs on the same settings.
+/// It is the SHA-256 of the json of the configuration, whose object keys are sorted,
+/// so that it is the same across the processes, the platforms and the versions of the crate.
+pub fn get_configuration_hash(configuration: &CalculationConfiguration) -> Result<String> {
+    let value = serde_json::to_value(configuration).with_context(|| {
+        anyhow!(
+            "({}:{}) failed to serialize the calculation configuration",
+            file!(),
+            line!()
+        )
+    })?;
+    let digest = Sha256::digest(value.to_string().as_bytes());
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// The header of a run without the results
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunInfo {
+    run_id: String,
+    evaluation_date: OffsetDateTime,
+    configuration_hash: String,
+}
+
+impl RunInfo {
+    pub fn new(
+        run_id: String,
+        evaluation_date: OffsetDateTime,
+        configuration_hash: String,
+    ) -> RunInfo {
+        RunInfo {
+            run_id,
+            evaluation_date,
+            configuration_hash,
+        }
+    }
+
+    pub fn get_run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    pub fn get_evaluation_date(&self) -> &OffsetDateTime {
+        &self.evaluation_date
+    }
+
+    pub fn get_configuration_hash(&self) -> &str {
+        &self.configuration_hash
+    }
+}
+
+/// A calculation run: the results of the instruments on an evaluation date
+/// with the hash of the configuration they are calculated on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalculationRun {
+    info: RunInfo,
+    results: ResultSnapshot,
+}
+
+impl CalculationRun {
+    pub fn new(
+        run_id: String,
+        evaluation_date: OffsetDateTime,
+        configuration: &CalculationConfiguration,
+        results: ResultSnapshot,
+    ) -> Result<CalculationRun> {
+        let configuration_hash = get_configuration_hash(configuration)?;
+        Ok(CalculationRun {
+            info: RunInfo::new(run_id, evaluation_date, configuration_hash),
+            results,
+        })
+    }
+
+    /// a run of which the header is given, e.g., loaded from a store
+    pub fn from_info(info: RunInfo, results: ResultSnapshot) -> CalculationRun {
+        CalculationRun { info, results }
+    }
+
+    pub fn get_info(&self) -> &RunInfo {
+        &self.info
+    }
+
+    pub fn get_run_id(&self) -> &str {
+        self.info.get_run_id()
+    }
+
+    pub fn get_evaluation_date(&self) -> &OffsetDateTime {
+        self.info.get_evaluation_date()
+    }
+
+    pub fn get_configuration_hash(&self) -> &str {
+        self.info.get_configuration_hash()
+    }
+
+    pub fn get_results(&self) -> &ResultSnapshot {
+        &self.results
+    }
+
+    pub fn into_results(self) -> ResultSnapshot {
+        self.results
+    }
+}
+
+/// Storage of the calculation runs keyed by the run ids
+pub trait ResultStore {
+    /// saves the run, replacing the run of the same id
+    fn save_run(&mut self, run: &CalculationRun) -> Result<()>;
+
+    fn load_run(&self, run_id: &str) -> Result<Option<CalculationRun>>;
+
+    /// returns false if there is no run of the id
+    fn delete_run(&mut self, run_id: &str) -> Result<bool>;
+
+    /// the runs evaluated in [from, to], in the order of the evaluation dates
+    fn get_runs(&self, from: &OffsetDateTime, to: &OffsetDateTime) -> Result<Vec<RunInfo>>;
+
+    /// The results of the runs evaluated in [from, to] in the order of the evaluation dates,
+    /// e.g., for VarBacktest::from_snapshots.
+    /// Only the runs on the configuration are loaded if its hash is given.
+    fn load_snapshots(
+        &self,
+        from: &OffsetDateTime,
+        to: &OffsetDateTime,
+        configuration_hash: Option<&str>,
+    ) -> Result<Vec<ResultSnapshot>> {
+        let mut snapshots = Vec::new();
+        for info in self.get_runs(from, to)? {
+            if configuration_hash.is_some_and(|hash| hash != info.get_configuration_hash()) {
+                continue;
+            }
+            let run = self.load_run(info.get_run_id())?.ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) run {} is listed but not loaded",
+                    file!(),
+                    line!(),
+                    info.get_run_id()
+                )
+            })?;
+            snapshots.push(run.into_results());
+        }
+        Ok(snapshots)
+    }
+}
+
+/// ResultStore in memory
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryResultStore {
+    runs: BTreeMap<String, CalculationRun>,
+}
+
+impl InMemoryResultStore {
+    pub fn len(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+}
+
+impl ResultStore for InMemoryResultStore {
+    fn save_run(&mut self, run: &CalculationRun) -> Result<()> {
+        self.runs.insert(run.get_run_id().to_string(), run.clone());
+        Ok(())
+    }
+
+    fn load_run(&self, run_id: &str) -> Result<Option<CalculationRun>> {
+        Ok(self.runs.get(run_id).cloned())
+    }
+
+    fn delete_run(&mut self, run_id: &str) -> Result<bool> {
+        Ok(self.runs.remove(run_id).is_some())
+    }
+
+    fn get_runs(&self, from: &OffsetDateTime, to: &OffsetDateTime) -> Result<Vec<RunInfo>> {
+        let mut res: Vec<RunInfo> = self
+            .runs
+            .values()
+            .filter(|run| from <= run.get_evaluation_date() && run.get_evaluation_date() <= to)
+            .map(|run| run.get_info().clone())
+            .collect();
+        res.sort_by_key(|info| info.evaluation_date);
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::pricing_engines::calculation_result::CalculationResult;
+    use crate::pricing_engines::npv_result::NpvResult;
+    use crate::InstInfo;
+    use rustc_hash::FxHashMap;
+    use static_id::static_id::StaticId;
+    use time::{macros::datetime, Duration};
+
+    /// runs of an instrument worth 100, 95 and 97 on three days
+    pub(crate) fn runs() -> Result<Vec<CalculationRun>> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let inst_info = InstInfo {
+            id: StaticId::from_str("A", "KRX"),
+            currency: Currency::KRW,
+            unit_notional: 1.0,
+            ..Default::default()
+        };
+        let mut res = vec![];
+        for (i, value) in [100.0, 95.0, 97.0].into_iter().enumerate() {
+            let date = dt + Duration::days(i as i64);
+            let mut result = CalculationResult::new(inst_info.clone(), date);
+            result.set_npv(NpvResult::new_from_npv(value));
+            result.set_value()?;
+            let mut results = FxHashMap::default();
+            results.insert(inst_info.id, result);
+            res.push(CalculationRun::new(
+                format!("eod-{}", i),
+                date,
+                &CalculationConfiguration::default(),
+                results,
+            )?);
+        }
+        Ok(res)
+    }
+
+    #[test]
+    fn test_in_memory_store() -> Result<()> {
+        let runs = runs()?;
+        let mut store = InMemoryResultStore::default();
+        for run in runs.iter().rev() {
+            store.save_run(run)?;
+        }
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.load_run("eod-1")?.as_ref(), Some(&runs[1]));
+        assert!(store.load_run("eod-3")?.is_none());
+
+        let from = *runs[1].get_evaluation_date();
+        let to = *runs[2].get_evaluation_date();
+        let ids: Vec<String> = store
+            .get_runs(&from, &to)?
+            .iter()
+            .map(|info| info.get_run_id().to_string())
+            .collect();
+        assert_eq!(ids, vec!["eod-1", "eod-2"]);
+
+        let hash = get_configuration_hash(&CalculationConfiguration::default())?;
+        let snapshots = store.load_snapshots(&from, &to, Some(&hash))?;
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(&snapshots[0], runs[1].get_results());
+        assert!(store.load_snapshots(&from, &to, Some("other"))?.is_empty());
+
+        assert!(store.delete_run("eod-1")?);
+        assert!(!store.delete_run("eod-1")?);
+        assert_eq!(store.get_runs(&from, &to)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_configuration_hash() -> Result<()> {
+        let configuration = CalculationConfiguration::default();
+        let hash = get_configuration_hash(&configuration)?;
+        assert_eq!(hash, get_configuration_hash(&configuration.clone())?);
+        assert_eq!(hash.len(), 64);
+        let configuration = configuration.with_gamma_calculation(true);
+        assert_ne!(hash, get_configuration_hash(&configuration)?);
+
+        // the same ids in any order give the same hash
+        let ids: Vec<StaticId> = (0..20)
+            .map(|i| StaticId::from_str(&format!("Digital{}", i), "KRX"))
+            .collect();
+        let forward = configuration.clone().with_discontinuous_payoff_ids(ids.clone());
+        let backward = configuration
+            .with_discontinuous_payoff_ids(ids.into_iter().rev().collect());
+        assert_eq!(
+            get_configuration_hash(&forward)?,
+            get_configuration_hash(&backward)?
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+//! ResultStore in a sqlite database (feature `sqlite`).
+//!
+//! The tables are created if they do not exist:
+//! - `calculation_runs(run_id, evaluation_date, evaluation_timestamp, configuration_hash)`:
+//!   the evaluation date in RFC3339 and its unix timestamp in nanoseconds for the ordering
+//! - `calculation_results(run_id, instrument_id, result)`: the json of each CalculationResult
+//!
+//! The sql is plain enough to be ported to other databases, e.g., postgres.
+use crate::pricing_engines::calculation_result::CalculationResult;
+use crate::storage::{CalculationRun, ResultStore, RunInfo};
+use crate::var::backtest::ResultSnapshot;
+//
+use anyhow::{anyhow, Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use static_id::static_id::StaticId;
+use std::path::Path;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS calculation_runs (
+    run_id TEXT PRIMARY KEY,
+    evaluation_date TEXT NOT NULL,
+    evaluation_timestamp INTEGER NOT NULL,
+    configuration_hash TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS calculation_runs_evaluation_timestamp
+    ON calculation_runs (evaluation_timestamp);
+CREATE TABLE IF NOT EXISTS calculation_results (
+    run_id TEXT NOT NULL,
+    instrument_id TEXT NOT NULL,
+    result TEXT NOT NULL,
+    PRIMARY KEY (run_id, instrument_id)
+);
+";
+
+pub struct SqliteResultStore {
+    connection: Connection,
+}
+
+impl SqliteResultStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<SqliteResultStore> {
+        let connection = Connection::open(path.as_ref()).with_context(|| {
+            anyhow!(
+                "({}:{}) failed to open {}",
+                file!(),
+                line!(),
+                path.as_ref().display()
+            )
+        })?;
+        SqliteResultStore::new(connection)
+    }
+
+    pub fn open_in_memory() -> Result<SqliteResultStore> {
+        SqliteResultStore::new(Connection::open_in_memory()?)
+    }
+
+    /// the store on the connection, creating the tables if they do not exist
+    pub fn new(connection: Connection) -> Result<SqliteResultStore> {
+        connection
+            .execute_batch(SCHEMA)
+            .with_context(|| anyhow!("({}:{}) failed to create the tables", file!(), line!()))?;
+        Ok(SqliteResultStore { connection })
+    }
+
+    pub fn get_connection(&self) -> &Connection {
+        &self.connection
+    }
+}
+
+fn to_timestamp(dt: &OffsetDateTime) -> Result<i64> {
+    i64::try_from(dt.unix_timestamp_nanos()).map_err(|_| {
+        anyhow!(
+            "({}:{}) {} is out of the range of the timestamp",
+            file!(),
+            line!(),
+            dt
+        )
+    })
+}
+
+fn parse_evaluation_date(run_id: &str, date: &str) -> Result<OffsetDateTime> {
+    OffsetDateTime::parse(date, &Rfc3339).with_context(|| {
+        anyhow!(
+            "({}:{}) invalid evaluation date {} of run {}",
+            file!(),
+            line!(),
+            date,
+            run_id
+        )
+    })
+}
+
+impl ResultStore for SqliteResultStore {
+    fn save_run(&mut self, run: &CalculationRun) -> Result<()> {
+        let run_id = run.get_run_id();
+        let evaluation_date = run
+            .get_evaluation_date()
+            .format(&Rfc3339)
+            .with_context(|| {
+                anyhow!(
+                    "({}:{}) failed to format the evaluation date of run {}",
+                    file!(),
+                    line!(),
+                    run_id
+                )
+            })?;
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "DELETE FROM calculation_results WHERE run_id = ?1",
+            params![run_id],
+        )?;
+        transaction.execute(
+            "INSERT OR REPLACE INTO calculation_runs
+                (run_id, evaluation_date, evaluation_timestamp, configuration_hash)
+                VALUES (?1, ?2, ?3, ?4)",
+            params![
+                run_id,
+                evaluation_date,
+                to_timestamp(run.get_evaluation_date())?,
+                run.get_configuration_hash()
+            ],
+        )?;
+        {
+            let mut statement = transaction.prepare(
+                "INSERT INTO calculation_results (run_id, instrument_id, result)
+                    VALUES (?1, ?2, ?3)",
+            )?;
+            for (inst_id, result) in run.get_results().iter() {
+                let json = serde_json::to_string(result).with_context(|| {
+                    anyhow!(
+                        "({}:{}) failed to serialize the result of {} in run {}",
+                        file!(),
+                        line!(),
+                        inst_id,
+                        run_id
+                    )
+                })?;
+                statement.execute(params![run_id, inst_id.to_string(), json])?;
+            }
+        }
+        transaction
+            .commit()
+            .with_context(|| anyhow!("({}:{}) failed to save run {}", file!(), line!(), run_id))
+    }
+
+    fn load_run(&self, run_id: &str) -> Result<Option<CalculationRun>> {
+        let header = self
+            .connection
+            .query_row(
+                "SELECT evaluation_date, configuration_hash FROM calculation_runs
+                    WHERE run_id = ?1",
+                params![run_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()?;
+        let Some((evaluation_date, configuration_hash)) = header else {
+            return Ok(None);
+        };
+        let info = RunInfo::new(
+            run_id.to_string(),
+            parse_evaluation_date(run_id, &evaluation_date)?,
+            configuration_hash,
+        );
+
+        let mut statement = self
+            .connection
+            .prepare("SELECT instrument_id, result FROM calculation_results WHERE run_id = ?1")?;
+        let rows = statement.query_map(params![run_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut results = ResultSnapshot::default();
+        for row in rows {
+            let (inst_id, json) = row?;
+            let result: CalculationResult = serde_json::from_str(&json).with_context(|| {
+                anyhow!(
+                    "({}:{}) invalid result of {} in run {}",
+                    file!(),
+                    line!(),
+                    inst_id,
+                    run_id
+                )
+            })?;
+            results.insert(StaticId::from_combined_str(&inst_id), result);
+        }
+        Ok(Some(CalculationRun::from_info(info, results)))
+    }
+
+    fn delete_run(&mut self, run_id: &str) -> Result<bool> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "DELETE FROM calculation_results WHERE run_id = ?1",
+            params![run_id],
+        )?;
+        let deleted = transaction.execute(
+            "DELETE FROM calculation_runs WHERE run_id = ?1",
+            params![run_id],
+        )?;
+        transaction.commit()?;
+        Ok(deleted > 0)
+    }
+
+    fn get_runs(&self, from: &OffsetDateTime, to: &OffsetDateTime) -> Result<Vec<RunInfo>> {
+        let mut statement = self.connection.prepare(
+            "SELECT run_id, evaluation_date, configuration_hash FROM calculation_runs
+                WHERE evaluation_timestamp BETWEEN ?1 AND ?2
+                ORDER BY evaluation_timestamp, run_id",
+        )?;
+        let rows = statement.query_map(params![to_timestamp(from)?, to_timestamp(to)?], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        let mut res = Vec::new();
+        for row in rows {
+            let (run_id, evaluation_date, configuration_hash) = row?;
+            let evaluation_date = parse_evaluation_date(&run_id, &evaluation_date)?;
+            res.push(RunInfo::new(run_id, evaluation_date, configuration_hash));
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::storage::tests::runs;
+    use crate::var::backtest::VarBacktest;
+
+    #[test]
+    fn test_sqlite_store() -> Result<()> {
+        let runs = runs()?;
+        let path = std::env::temp_dir().join(format!("rustmetrics_runs_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let mut store = SqliteResultStore::open(&path)?;
+            for run in runs.iter() {
+                store.save_run(run)?;
+            }
+            // saving again replaces the run
+            store.save_run(&runs[0])?;
+        }
+
+        let mut store = SqliteResultStore::open(&path)?;
+        assert_eq!(store.load_run("eod-0")?.as_ref(), Some(&runs[0]));
+        assert!(store.load_run("eod-3")?.is_none());
+
+        let from = *runs[0].get_evaluation_date();
+        let to = *runs[2].get_evaluation_date();
+        let infos = store.get_runs(&from, &to)?;
+        assert_eq!(infos.len(), 3);
+        assert_eq!(&infos[2], runs[2].get_info());
+
+        // the backtest on the reloaded results: P&Ls -5 and 2
+        let snapshots = store.load_snapshots(&from, &to, Some(runs[0].get_configuration_hash()))?;
+        let backtest = VarBacktest::from_snapshots(0.99, Currency::KRW, &snapshots, &[3.0, 3.0])?;
+        assert_eq!(backtest.get_observations()[0].get_realized_pnl(), -5.0);
+        assert_eq!(backtest.get_observations()[1].get_realized_pnl(), 2.0);
+
+        assert!(store.delete_run("eod-1")?);
+        assert!(!store.delete_run("eod-1")?);
+        assert_eq!(store.get_runs(&from, &to)?.len(), 2);
+        drop(store);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
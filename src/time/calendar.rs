@@ -1,5 +1,6 @@
 use crate::time::calendars::{
-    nullcalendar::NullCalendar, southkorea::SouthKorea, unitedstates::UnitedStates,
+    china::China, custom::CustomCalendar, hongkong::HongKong, nullcalendar::NullCalendar,
+    singapore::Singapore, southkorea::SouthKorea, target::Target, unitedstates::UnitedStates,
 };
 use enum_dispatch;
 use serde::{Deserialize, Serialize};
@@ -10,6 +11,11 @@ pub enum Calendar {
     NullCalendar(NullCalendar),
     SouthKorea(SouthKorea),
     UnitedStates(UnitedStates),
+    Target(Target),
+    China(China),
+    HongKong(HongKong),
+    Singapore(Singapore),
+    Custom(CustomCalendar),
 }
 
 impl Default for Calendar {
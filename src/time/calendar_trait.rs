@@ -1,12 +1,20 @@
 use crate::definitions::Time;
 use crate::time::calendar::Calendar;
+use crate::time::calendars::china::China;
+use crate::time::calendars::custom::CustomCalendar;
+use crate::time::calendars::hongkong::HongKong;
 use crate::time::calendars::nullcalendar::NullCalendar;
+use crate::time::calendars::singapore::Singapore;
 use crate::time::calendars::southkorea::SouthKorea;
+use crate::time::calendars::target::Target;
 use crate::time::calendars::unitedstates::UnitedStates;
 use crate::time::conventions::BusinessDayConvention;
 use crate::time::conventions::DayCountConvention;
+use crate::time::holiday_updates::get_holiday_update;
+use crate::time::time_cache::{get_cached_time_difference, get_cached_year_fraction};
 use anyhow::{anyhow, Result};
 use enum_dispatch;
+use std::ops::RangeInclusive;
 use time::{Date, Month, OffsetDateTime, Weekday};
 
 #[enum_dispatch::enum_dispatch]
@@ -62,13 +70,42 @@ pub trait CalendarTrait {
     fn remove_holidays(&mut self, date: &Date) -> Result<()>;
 
     fn is_holiday(&self, date: &OffsetDateTime) -> bool;
+
+    /// The years of the hard coded holidays, e.g., the announced ones, None if all the holidays are given by rules.
+    /// Out of the years, is_holiday gives only the holidays of the rules.
+    fn get_holiday_years(&self) -> Option<RangeInclusive<i32>> {
+        None
+    }
+
+    /// is_holiday which fails out of the years of the hard coded holidays (get_holiday_years)
+    fn try_is_holiday(&self, date: &OffsetDateTime) -> Result<bool> {
+        if let Some(years) = self.get_holiday_years() {
+            if !years.contains(&date.year()) {
+                return Err(anyhow!(
+                    "({}:{}) the holidays of {} are given for {}-{}, but {} is given",
+                    file!(),
+                    line!(),
+                    self.calendar_name(),
+                    years.start(),
+                    years.end(),
+                    date,
+                ));
+            }
+        }
+        Ok(self.is_holiday(date))
+    }
+
     fn _is_holiday(&self, date: &OffsetDateTime) -> bool {
         // check order is as follows:
-        // weekend => removed holiday => base holiday following specific calendar => added holiday
+        // weekend => runtime update => removed holiday => base holiday following specific calendar => added holiday
         // 1) weekend
         if self.is_weekend(date) {
             return true;
         }
+        // holidays updated at runtime for all calendars of the name
+        if let Some(is_holiday) = get_holiday_update(self.calendar_name(), &date.date()) {
+            return is_holiday;
+        }
         // 2) removed holiday
         if self.is_removed_holiday(date) {
             return false;
@@ -136,6 +173,20 @@ pub trait CalendarTrait {
         }
     }
 
+    /// shift the date by the number of business days (backward if days < 0)
+    fn add_business_days(&self, date: &OffsetDateTime, days: i64) -> OffsetDateTime {
+        let step = if days < 0 { -1 } else { 1 };
+        let mut res = *date;
+        let mut count = 0;
+        while count < days.abs() {
+            res += time::Duration::days(step);
+            if !self.is_holiday(&res) {
+                count += 1;
+            }
+        }
+        res
+    }
+
     fn adjust(
         &self,
         date: &OffsetDateTime,
@@ -161,6 +212,18 @@ pub trait CalendarTrait {
         start_date: &OffsetDateTime,
         end_date: &OffsetDateTime,
         day_count: &DayCountConvention,
+    ) -> Result<Time> {
+        get_cached_year_fraction(start_date, end_date, day_count, || {
+            self.uncached_year_fraction(start_date, end_date, day_count)
+        })
+    }
+
+    /// year_fraction without the cache
+    fn uncached_year_fraction(
+        &self,
+        start_date: &OffsetDateTime,
+        end_date: &OffsetDateTime,
+        day_count: &DayCountConvention,
     ) -> Result<Time> {
         // sanity check
         if start_date > end_date {
@@ -290,7 +353,15 @@ pub trait CalendarTrait {
     /// then, calculate the time from start_date to the end of year of start_date as Act365 fasion
     /// at the second, calculate the time from the start of the end_date to the end_date as Act366 fasion
     /// then, sum up the two times
+    /// The times are cached per thread (time_cache), since the same dates are repeated in a run
     fn get_time_difference(&self, start_date: &OffsetDateTime, end_date: &OffsetDateTime) -> Time {
+        get_cached_time_difference(start_date, end_date, || {
+            self.get_uncached_time_difference(start_date, end_date)
+        })
+    }
+
+    /// get_time_difference without the cache
+    fn get_uncached_time_difference(&self, start_date: &OffsetDateTime, end_date: &OffsetDateTime) -> Time {
         //let midnight_last_day_of_year_start = OffsetDateTime::new_in_offset(last_day_of_year_start, time::Time::MIDNIGHT, start_date_offset);
         let mut frac: Time = 0.0;
         let start_year = start_date.year();
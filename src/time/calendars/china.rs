@@ -0,0 +1,335 @@
+use crate::time::calendar_trait::CalendarTrait;
+use crate::time::holiday::Holidays;
+//
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use time::{Date, Month, OffsetDateTime, UtcOffset};
+
+/// The holidays of China are announced by the State Council every year,
+/// and the weekdays lost to the long holidays are made up on "working weekends".
+/// The announced holidays are hard coded for 2018-2026 (CalendarTrait::try_is_holiday fails out of the years).
+///
+/// * Sse: Shanghai Stock Exchange, closed on all the weekends
+/// * InterbankMarket: China interbank market (CNY bonds, CNY swaps), open on the working weekends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub enum ChinaType {
+    Sse,
+    InterbankMarket,
+}
+
+impl ChinaType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChinaType::Sse => "SSE",
+            ChinaType::InterbankMarket => "Interbank Market",
+        }
+    }
+
+    /// the weekdays closed by the announcements of the State Council
+    fn is_announced_holiday(&self, date: &OffsetDateTime) -> bool {
+        let (y, m, d, _, _) = self.unpack(date);
+        matches!(
+            (y, m, d),
+            // 2018
+            (2018, Month::February, 15..=16)
+                | (2018, Month::February, 19..=21)
+                | (2018, Month::April, 5..=6)
+                | (2018, Month::April, 30)
+                | (2018, Month::May, 1)
+                | (2018, Month::June, 18)
+                | (2018, Month::September, 24)
+                | (2018, Month::October, 1..=5)
+                | (2018, Month::December, 31)
+                // 2019
+                | (2019, Month::February, 4..=8)
+                | (2019, Month::April, 5)
+                | (2019, Month::May, 1..=3)
+                | (2019, Month::June, 7)
+                | (2019, Month::September, 13)
+                | (2019, Month::October, 1..=4)
+                | (2019, Month::October, 7)
+                // 2020
+                | (2020, Month::January, 24)
+                | (2020, Month::January, 27..=31)
+                | (2020, Month::April, 6)
+                | (2020, Month::May, 1)
+                | (2020, Month::May, 4..=5)
+                | (2020, Month::June, 25..=26)
+                | (2020, Month::October, 1..=2)
+                | (2020, Month::October, 5..=8)
+                // 2021
+                | (2021, Month::February, 11..=12)
+                | (2021, Month::February, 15..=17)
+                | (2021, Month::April, 5)
+                | (2021, Month::May, 3..=5)
+                | (2021, Month::June, 14)
+                | (2021, Month::September, 20..=21)
+                | (2021, Month::October, 1)
+                | (2021, Month::October, 4..=7)
+                // 2022
+                | (2022, Month::January, 3)
+                | (2022, Month::January, 31)
+                | (2022, Month::February, 1..=4)
+                | (2022, Month::April, 4..=5)
+                | (2022, Month::May, 2..=4)
+                | (2022, Month::June, 3)
+                | (2022, Month::September, 12)
+                | (2022, Month::October, 3..=7)
+                // 2023
+                | (2023, Month::January, 2)
+                | (2023, Month::January, 23..=27)
+                | (2023, Month::April, 5)
+                | (2023, Month::May, 1..=3)
+                | (2023, Month::June, 22..=23)
+                | (2023, Month::September, 29)
+                | (2023, Month::October, 2..=6)
+                // 2024
+                | (2024, Month::February, 9)
+                | (2024, Month::February, 12..=16)
+                | (2024, Month::April, 4..=5)
+                | (2024, Month::May, 1..=3)
+                | (2024, Month::June, 10)
+                | (2024, Month::September, 16..=17)
+                | (2024, Month::October, 1..=4)
+                | (2024, Month::October, 7)
+                // 2025
+                | (2025, Month::January, 28..=31)
+                | (2025, Month::February, 3..=4)
+                | (2025, Month::April, 4)
+                | (2025, Month::May, 1..=2)
+                | (2025, Month::May, 5)
+                | (2025, Month::June, 2)
+                | (2025, Month::October, 1..=3)
+                | (2025, Month::October, 6..=8)
+                // 2026
+                | (2026, Month::January, 1..=2)
+                | (2026, Month::February, 16..=20)
+                | (2026, Month::February, 23)
+                | (2026, Month::April, 6)
+                | (2026, Month::May, 1)
+                | (2026, Month::May, 4..=5)
+                | (2026, Month::June, 19)
+                | (2026, Month::September, 25)
+                | (2026, Month::October, 1..=2)
+                | (2026, Month::October, 5..=7)
+        )
+    }
+
+    /// the weekends which are working days by the announcements of the State Council
+    pub fn is_working_weekend(&self, date: &OffsetDateTime) -> bool {
+        let (y, m, d, _, _) = self.unpack(date);
+        matches!(
+            (y, m, d),
+            (2018, Month::February, 11)
+                | (2018, Month::February, 24)
+                | (2018, Month::April, 8)
+                | (2018, Month::April, 28)
+                | (2018, Month::September, 29..=30)
+                | (2018, Month::December, 29)
+                | (2019, Month::February, 2..=3)
+                | (2019, Month::April, 28)
+                | (2019, Month::May, 5)
+                | (2019, Month::September, 29)
+                | (2019, Month::October, 12)
+                | (2020, Month::January, 19)
+                | (2020, Month::April, 26)
+                | (2020, Month::May, 9)
+                | (2020, Month::June, 28)
+                | (2020, Month::September, 27)
+                | (2020, Month::October, 10)
+                | (2021, Month::February, 7)
+                | (2021, Month::February, 20)
+                | (2021, Month::April, 25)
+                | (2021, Month::May, 8)
+                | (2021, Month::September, 18)
+                | (2021, Month::September, 26)
+                | (2021, Month::October, 9)
+                | (2022, Month::January, 29..=30)
+                | (2022, Month::April, 2)
+                | (2022, Month::April, 24)
+                | (2022, Month::May, 7)
+                | (2022, Month::October, 8..=9)
+                | (2023, Month::January, 28..=29)
+                | (2023, Month::April, 23)
+                | (2023, Month::May, 6)
+                | (2023, Month::June, 25)
+                | (2023, Month::October, 7..=8)
+                | (2024, Month::February, 4)
+                | (2024, Month::February, 18)
+                | (2024, Month::April, 7)
+                | (2024, Month::April, 28)
+                | (2024, Month::May, 11)
+                | (2024, Month::September, 14)
+                | (2024, Month::September, 29)
+                | (2024, Month::October, 12)
+                | (2025, Month::January, 26)
+                | (2025, Month::February, 8)
+                | (2025, Month::April, 27)
+                | (2025, Month::September, 28)
+                | (2025, Month::October, 11)
+                | (2026, Month::January, 4)
+                | (2026, Month::February, 14)
+                | (2026, Month::February, 28)
+                | (2026, Month::May, 9)
+                | (2026, Month::September, 20)
+                | (2026, Month::October, 10)
+        )
+    }
+}
+
+impl Holidays for ChinaType {
+    #[allow(unused_variables)]
+    fn is_temporary_holiday(&self, date: &OffsetDateTime) -> bool {
+        false
+    }
+
+    fn is_holiday(&self, date: &OffsetDateTime) -> bool {
+        let (_, m, d, _, _) = self.unpack(date);
+
+        if (d == 1 && (m == Month::January || m == Month::May)) // New Year's Day and Labour Day
+        || (m == Month::October && (1..=3).contains(&d))
+        // National Day
+        {
+            return true;
+        }
+
+        if self.is_temporary_holiday(date) {
+            return true;
+        }
+
+        self.is_announced_holiday(date)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct China {
+    name: String,
+    utc_offset: UtcOffset,
+    specific_type: ChinaType,
+    holiday_adder: Vec<Date>,
+    holiday_remover: Vec<Date>,
+}
+
+impl China {
+    pub fn new(specific_type: ChinaType) -> Self {
+        let name = format!("China ({})", specific_type.as_str());
+        Self {
+            name,
+            utc_offset: UtcOffset::from_hms(8, 0, 0).expect("valid offset"),
+            specific_type,
+            holiday_adder: Vec::new(),
+            holiday_remover: Vec::new(),
+        }
+    }
+}
+
+impl CalendarTrait for China {
+    fn calendar_name(&self) -> &String {
+        &self.name
+    }
+
+    fn add_holidays(&mut self, date: &Date) -> Result<()> {
+        self.holiday_adder.push(*date);
+        Ok(())
+    }
+
+    fn remove_holidays(&mut self, date: &Date) -> Result<()> {
+        self.holiday_remover.push(*date);
+        Ok(())
+    }
+
+    fn is_removed_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.date();
+        self.holiday_remover.contains(&date)
+    }
+
+    fn is_added_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.date();
+        self.holiday_adder.contains(&date)
+    }
+
+    fn is_base_holiday(&self, date: &OffsetDateTime) -> bool {
+        self.specific_type.is_holiday(date)
+    }
+
+    fn is_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.to_offset(self.utc_offset);
+        self._is_holiday(&date)
+    }
+
+    fn get_holiday_years(&self) -> Option<RangeInclusive<i32>> {
+        Some(2018..=2026)
+    }
+
+    /// The working weekends are not weekends in the interbank market
+    fn is_weekend(&self, date: &OffsetDateTime) -> bool {
+        let date = date.to_offset(self.utc_offset);
+        match self.specific_type {
+            ChinaType::Sse => self._is_weekend(&date),
+            ChinaType::InterbankMarket => {
+                self._is_weekend(&date) && !self.specific_type.is_working_weekend(&date)
+            }
+        }
+    }
+
+    fn display_holidays(
+        &self,
+        start_date: &OffsetDateTime,
+        end_date: &OffsetDateTime,
+        include_weekend: bool,
+    ) {
+        let start_date = start_date.to_offset(self.utc_offset);
+        let end_date = end_date.to_offset(self.utc_offset);
+
+        self._display_holidays(&start_date, &end_date, include_weekend);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_china_holidays() {
+        let sse = China::new(ChinaType::Sse);
+        let interbank = China::new(ChinaType::InterbankMarket);
+        assert_eq!(sse.calendar_name(), "China (SSE)");
+
+        // spring festival of 2024 and the working sundays around it
+        for d in [9, 12, 13, 14, 15, 16] {
+            let date = datetime!(2024-02-01 10:00:00 +08:00)
+                .replace_day(d)
+                .unwrap();
+            assert!(sse.is_holiday(&date));
+            assert!(interbank.is_holiday(&date));
+        }
+        let working_sunday = datetime!(2024-02-18 10:00:00 +08:00);
+        assert!(sse.is_holiday(&working_sunday));
+        assert!(interbank.is_business_day(&working_sunday));
+        assert!(!interbank.is_weekend(&working_sunday));
+
+        // national day on the weekdays
+        assert!(sse.is_holiday(&datetime!(2025-10-08 10:00:00 +08:00)));
+        assert!(sse.is_business_day(&datetime!(2025-10-09 10:00:00 +08:00)));
+
+        // the next business day of the national day holidays in the interbank market
+        let next = interbank.adjust_following(&datetime!(2025-10-01 10:00:00 +08:00));
+        assert_eq!(next, datetime!(2025-10-09 10:00:00 +08:00));
+        // friday after the holidays is a business day, and the next business day is the working saturday
+        let next = interbank.adjust_following(&datetime!(2025-10-10 10:00:00 +08:00));
+        assert_eq!(next, datetime!(2025-10-10 10:00:00 +08:00));
+        let next = interbank.add_business_days(&datetime!(2025-10-10 10:00:00 +08:00), 1);
+        assert_eq!(next, datetime!(2025-10-11 10:00:00 +08:00));
+
+        // spring festival of 2026 and the working saturdays around it
+        assert!(sse.is_holiday(&datetime!(2026-02-23 10:00:00 +08:00)));
+        assert!(interbank.is_business_day(&datetime!(2026-02-14 10:00:00 +08:00)));
+        assert!(interbank.is_business_day(&datetime!(2026-02-28 10:00:00 +08:00)));
+
+        // the holidays are not announced beyond the table
+        assert!(sse.try_is_holiday(&datetime!(2026-10-07 10:00:00 +08:00)).unwrap());
+        assert!(sse.try_is_holiday(&datetime!(2027-02-08 10:00:00 +08:00)).is_err());
+    }
+}
@@ -0,0 +1,272 @@
+use crate::time::calendar_trait::CalendarTrait;
+//
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+use time::macros::format_description;
+use time::{Date, OffsetDateTime, UtcOffset, Weekday};
+
+/// A calendar of an explicit list of holidays and a weekend mask,
+/// e.g., for the exchanges and the counterparties which have no calendar in the crate.
+///
+/// The holidays are loaded from
+/// * json: {"name": "DIFC", "utc_offset": "+04:00", "weekend": ["Saturday", "Sunday"], "holidays": ["2024-04-10", ...]}
+///   where utc_offset (UTC by default) and weekend (Saturday and Sunday by default) are optional
+/// * csv: a date (YYYY-MM-DD) in the first column of each row with an optional header
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomCalendar {
+    name: String,
+    utc_offset: UtcOffset,
+    weekend: Vec<Weekday>,
+    holidays: BTreeSet<Date>,
+    holiday_adder: Vec<Date>,
+    holiday_remover: Vec<Date>,
+}
+
+#[derive(Deserialize)]
+struct CustomCalendarFile {
+    name: String,
+    #[serde(default)]
+    utc_offset: Option<String>,
+    #[serde(default)]
+    weekend: Option<Vec<String>>,
+    holidays: Vec<String>,
+}
+
+fn parse_date(s: &str) -> Result<Date> {
+    Date::parse(s.trim(), format_description!("[year]-[month]-[day]"))
+        .map_err(|e| anyhow!("({}:{}) invalid date {}: {}", file!(), line!(), s, e))
+}
+
+fn parse_utc_offset(s: &str) -> Result<UtcOffset> {
+    UtcOffset::parse(
+        s.trim(),
+        format_description!("[offset_hour sign:mandatory]:[offset_minute]"),
+    )
+    .map_err(|e| anyhow!("({}:{}) invalid utc offset {}: {}", file!(), line!(), s, e))
+}
+
+fn parse_weekday(s: &str) -> Result<Weekday> {
+    let weekday = match s.trim().to_ascii_lowercase().as_str() {
+        "monday" | "mon" => Weekday::Monday,
+        "tuesday" | "tue" => Weekday::Tuesday,
+        "wednesday" | "wed" => Weekday::Wednesday,
+        "thursday" | "thu" => Weekday::Thursday,
+        "friday" | "fri" => Weekday::Friday,
+        "saturday" | "sat" => Weekday::Saturday,
+        "sunday" | "sun" => Weekday::Sunday,
+        _ => return Err(anyhow!("({}:{}) invalid weekday {}", file!(), line!(), s)),
+    };
+    Ok(weekday)
+}
+
+impl CustomCalendar {
+    /// a calendar of the holidays with the weekend on Saturday and Sunday in UTC
+    pub fn new(name: String, holidays: Vec<Date>) -> CustomCalendar {
+        CustomCalendar {
+            name,
+            utc_offset: UtcOffset::UTC,
+            weekend: vec![Weekday::Saturday, Weekday::Sunday],
+            holidays: holidays.into_iter().collect(),
+            holiday_adder: vec![],
+            holiday_remover: vec![],
+        }
+    }
+
+    /// e.g., Friday and Saturday for the gulf markets before 2022
+    pub fn with_weekend(mut self, weekend: Vec<Weekday>) -> CustomCalendar {
+        self.weekend = weekend;
+        self
+    }
+
+    pub fn with_utc_offset(mut self, utc_offset: UtcOffset) -> CustomCalendar {
+        self.utc_offset = utc_offset;
+        self
+    }
+
+    pub fn from_json(json: &str) -> Result<CustomCalendar> {
+        let file: CustomCalendarFile = serde_json::from_str(json)
+            .with_context(|| anyhow!("({}:{}) invalid json of CustomCalendar", file!(), line!()))?;
+        let holidays = file
+            .holidays
+            .iter()
+            .map(|s| parse_date(s))
+            .collect::<Result<Vec<Date>>>()?;
+        let mut calendar = CustomCalendar::new(file.name, holidays);
+        if let Some(utc_offset) = file.utc_offset {
+            calendar.utc_offset = parse_utc_offset(&utc_offset)?;
+        }
+        if let Some(weekend) = file.weekend {
+            calendar.weekend = weekend
+                .iter()
+                .map(|s| parse_weekday(s))
+                .collect::<Result<Vec<Weekday>>>()?;
+        }
+        Ok(calendar)
+    }
+
+    /// the holidays in the first column of csv. The first row is skipped if it is not a date (header).
+    pub fn from_csv(name: String, csv: &str) -> Result<CustomCalendar> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(csv.as_bytes());
+        let mut holidays = Vec::new();
+        for (i, record) in reader.records().enumerate() {
+            let record = record?;
+            let s = match record.get(0) {
+                Some(s) if !s.is_empty() => s,
+                _ => continue,
+            };
+            match parse_date(s) {
+                Ok(date) => holidays.push(date),
+                Err(_) if i == 0 => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(CustomCalendar::new(name, holidays))
+    }
+
+    /// Loads a json or csv file. The name of a csv calendar is the file name without the extension.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<CustomCalendar> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| anyhow!("({}:{}) failed to read {:?}", file!(), line!(), path))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => CustomCalendar::from_json(&text),
+            Some("csv") => {
+                let name = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or_default()
+                    .to_string();
+                CustomCalendar::from_csv(name, &text)
+            }
+            _ => Err(anyhow!(
+                "({}:{}) the file of CustomCalendar must be csv or json: {:?}",
+                file!(),
+                line!(),
+                path
+            )),
+        }
+    }
+
+    pub fn get_holidays(&self) -> &BTreeSet<Date> {
+        &self.holidays
+    }
+
+    pub fn get_weekend(&self) -> &Vec<Weekday> {
+        &self.weekend
+    }
+}
+
+impl CalendarTrait for CustomCalendar {
+    fn calendar_name(&self) -> &String {
+        &self.name
+    }
+
+    fn add_holidays(&mut self, date: &Date) -> Result<()> {
+        self.holiday_adder.push(*date);
+        Ok(())
+    }
+
+    fn remove_holidays(&mut self, date: &Date) -> Result<()> {
+        self.holiday_remover.push(*date);
+        Ok(())
+    }
+
+    fn is_removed_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.date();
+        self.holiday_remover.contains(&date)
+    }
+
+    fn is_added_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.date();
+        self.holiday_adder.contains(&date)
+    }
+
+    fn is_base_holiday(&self, date: &OffsetDateTime) -> bool {
+        self.holidays.contains(&date.date())
+    }
+
+    fn is_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.to_offset(self.utc_offset);
+        self._is_holiday(&date)
+    }
+
+    fn is_weekend(&self, date: &OffsetDateTime) -> bool {
+        let date = date.to_offset(self.utc_offset);
+        self.weekend.contains(&date.weekday())
+    }
+
+    fn display_holidays(
+        &self,
+        start_date: &OffsetDateTime,
+        end_date: &OffsetDateTime,
+        include_weekend: bool,
+    ) {
+        let start_date = start_date.to_offset(self.utc_offset);
+        let end_date = end_date.to_offset(self.utc_offset);
+
+        self._display_holidays(&start_date, &end_date, include_weekend);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::calendar::Calendar;
+    use crate::time::jointcalendar::JointCalendar;
+    use time::macros::{date, datetime};
+
+    #[test]
+    fn test_custom_calendar() -> Result<()> {
+        let json = r#"{
+            "name": "Dubai",
+            "utc_offset": "+04:00",
+            "weekend": ["Friday", "Sat"],
+            "holidays": ["2021-05-13", "2021-07-20"]
+        }"#;
+        let dubai = CustomCalendar::from_json(json)?;
+        assert_eq!(dubai.calendar_name(), "Dubai");
+        assert!(dubai.is_holiday(&datetime!(2021-05-13 10:00:00 +04:00)));
+        assert!(dubai.is_weekend(&datetime!(2021-05-14 10:00:00 +04:00)));
+        assert!(dubai.is_business_day(&datetime!(2021-05-16 10:00:00 +04:00)));
+        // Thursday 23:00 in UTC is Friday in Dubai
+        assert!(dubai.is_weekend(&datetime!(2021-05-20 23:00:00 UTC)));
+        assert_eq!(
+            dubai.adjust_following(&datetime!(2021-05-13 10:00:00 +04:00)),
+            datetime!(2021-05-16 10:00:00 +04:00)
+        );
+        assert!(CustomCalendar::from_json(r#"{"name": "X", "holidays": ["2021-13-01"]}"#).is_err());
+
+        let csv = "date,name\n2024-01-01,New Year\n2024-12-25,Christmas\n";
+        let calendar = CustomCalendar::from_csv("Counterparty".to_string(), csv)?;
+        assert_eq!(calendar.get_holidays().len(), 2);
+        assert!(calendar.is_holiday(&datetime!(2024-12-25 10:00:00 UTC)));
+        assert!(calendar.is_weekend(&datetime!(2024-12-28 10:00:00 UTC)));
+        assert!(CustomCalendar::from_csv("X".to_string(), "2024-01-01\n2024-02-30\n").is_err());
+
+        let directory =
+            std::env::temp_dir().join(format!("rustmetrics_calendar_{}", std::process::id()));
+        std::fs::create_dir_all(&directory)?;
+        std::fs::write(directory.join("counterparty.csv"), csv)?;
+        let loaded = CustomCalendar::from_file(directory.join("counterparty.csv"))?;
+        assert_eq!(loaded.calendar_name(), "counterparty");
+        std::fs::remove_dir_all(&directory)?;
+
+        // a variant of Calendar
+        let calendar = Calendar::Custom(
+            CustomCalendar::new("Holiday".to_string(), vec![date!(2024 - 03 - 13)])
+                .with_utc_offset(UtcOffset::from_hms(9, 0, 0)?),
+        );
+        let json = serde_json::to_string(&calendar)?;
+        let loaded: Calendar = serde_json::from_str(&json)?;
+        assert_eq!(loaded, calendar);
+        let joint = JointCalendar::new(vec![calendar, Calendar::Custom(dubai)])?;
+        assert!(joint.is_holiday(&datetime!(2024-03-13 10:00:00 +09:00)));
+        Ok(())
+    }
+}
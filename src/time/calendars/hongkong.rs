@@ -0,0 +1,227 @@
+use crate::time::calendar_trait::CalendarTrait;
+use crate::time::holiday::Holidays;
+//
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use time::{Date, Month, OffsetDateTime, UtcOffset, Weekday};
+
+/// holidays of Hong Kong Exchanges (HKEX), also used for HKD and CNH settlements.
+/// The holidays of the lunar calendar are hard coded for 2018-2026 (CalendarTrait::try_is_holiday fails out of the years).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct HongKongHolidays;
+
+impl HongKongHolidays {
+    /// the holidays of the lunar calendar (lunar new year, ching ming, buddha's birthday,
+    /// tuen ng, the day after mid-autumn festival, chung yeung) which fall on weekdays,
+    /// including the days moved from sundays
+    fn is_lunar_holiday(&self, date: &OffsetDateTime) -> bool {
+        let (y, m, d, _, _) = self.unpack(date);
+        matches!(
+            (y, m, d),
+            // 2018
+            (2018, Month::February, 16)
+                | (2018, Month::February, 19)
+                | (2018, Month::April, 5)
+                | (2018, Month::May, 22)
+                | (2018, Month::June, 18)
+                | (2018, Month::September, 25)
+                | (2018, Month::October, 17)
+                // 2019
+                | (2019, Month::February, 5..=7)
+                | (2019, Month::April, 5)
+                | (2019, Month::May, 13)
+                | (2019, Month::June, 7)
+                | (2019, Month::October, 7)
+                // 2020
+                | (2020, Month::January, 27..=28)
+                | (2020, Month::April, 30)
+                | (2020, Month::June, 25)
+                | (2020, Month::October, 2)
+                | (2020, Month::October, 26)
+                // 2021
+                | (2021, Month::February, 12)
+                | (2021, Month::February, 15)
+                | (2021, Month::April, 6)
+                | (2021, Month::May, 19)
+                | (2021, Month::June, 14)
+                | (2021, Month::September, 22)
+                | (2021, Month::October, 14)
+                // 2022
+                | (2022, Month::February, 1..=3)
+                | (2022, Month::April, 5)
+                | (2022, Month::May, 9)
+                | (2022, Month::June, 3)
+                | (2022, Month::September, 12)
+                | (2022, Month::October, 4)
+                // 2023
+                | (2023, Month::January, 23..=25)
+                | (2023, Month::April, 5)
+                | (2023, Month::May, 26)
+                | (2023, Month::June, 22)
+                | (2023, Month::October, 23)
+                // 2024
+                | (2024, Month::February, 12..=13)
+                | (2024, Month::April, 4)
+                | (2024, Month::May, 15)
+                | (2024, Month::June, 10)
+                | (2024, Month::September, 18)
+                | (2024, Month::October, 11)
+                // 2025
+                | (2025, Month::January, 29..=31)
+                | (2025, Month::April, 4)
+                | (2025, Month::May, 5)
+                | (2025, Month::October, 7)
+                | (2025, Month::October, 29)
+                // 2026
+                | (2026, Month::February, 17..=19)
+                | (2026, Month::April, 7)
+                | (2026, Month::May, 25)
+                | (2026, Month::June, 19)
+                | (2026, Month::October, 19)
+        )
+    }
+}
+
+impl Holidays for HongKongHolidays {
+    #[allow(unused_variables)]
+    fn is_temporary_holiday(&self, date: &OffsetDateTime) -> bool {
+        false
+    }
+
+    fn is_holiday(&self, date: &OffsetDateTime) -> bool {
+        let (_, m, d, w, _) = self.unpack(date);
+
+        if ((d == 1 || (d == 2 && w == Weekday::Monday)) && m == Month::January) // New Year's Day (Monday if Sunday)
+        || self.is_good_friday(date, false) // Good Friday
+        || self.is_easter_monday(date, false) // Easter Monday
+        || ((d == 1 || (d == 2 && w == Weekday::Monday)) && m == Month::May) // Labour Day (Monday if Sunday)
+        || ((d == 1 || (d == 2 && w == Weekday::Monday)) && m == Month::July) // HKSAR Establishment Day (Monday if Sunday)
+        || ((d == 1 || (d == 2 && w == Weekday::Monday)) && m == Month::October) // National Day (Monday if Sunday)
+        || ((d == 25 || d == 26) && m == Month::December) // Christmas and Boxing Day
+        || (d == 27 && (w == Weekday::Monday || w == Weekday::Tuesday) && m == Month::December)
+        // Christmas or Boxing Day on Sunday
+        {
+            return true;
+        }
+
+        if self.is_temporary_holiday(date) {
+            return true;
+        }
+
+        self.is_lunar_holiday(date)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HongKong {
+    name: String,
+    utc_offset: UtcOffset,
+    holiday_adder: Vec<Date>,
+    holiday_remover: Vec<Date>,
+}
+
+impl Default for HongKong {
+    fn default() -> HongKong {
+        HongKong {
+            name: "Hong Kong".to_string(),
+            utc_offset: UtcOffset::from_hms(8, 0, 0).unwrap(),
+            holiday_adder: vec![],
+            holiday_remover: vec![],
+        }
+    }
+}
+
+impl HongKong {
+    pub fn new() -> HongKong {
+        HongKong::default()
+    }
+}
+
+impl CalendarTrait for HongKong {
+    fn calendar_name(&self) -> &String {
+        &self.name
+    }
+
+    fn add_holidays(&mut self, date: &Date) -> Result<()> {
+        self.holiday_adder.push(*date);
+        Ok(())
+    }
+
+    fn remove_holidays(&mut self, date: &Date) -> Result<()> {
+        self.holiday_remover.push(*date);
+        Ok(())
+    }
+
+    fn is_removed_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.date();
+        self.holiday_remover.contains(&date)
+    }
+
+    fn is_added_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.date();
+        self.holiday_adder.contains(&date)
+    }
+
+    fn is_base_holiday(&self, date: &OffsetDateTime) -> bool {
+        HongKongHolidays.is_holiday(date)
+    }
+
+    fn is_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.to_offset(self.utc_offset);
+        self._is_holiday(&date)
+    }
+
+    fn get_holiday_years(&self) -> Option<RangeInclusive<i32>> {
+        Some(2018..=2026)
+    }
+
+    fn is_weekend(&self, date: &OffsetDateTime) -> bool {
+        let date = date.to_offset(self.utc_offset);
+        self._is_weekend(&date)
+    }
+
+    fn display_holidays(
+        &self,
+        start_date: &OffsetDateTime,
+        end_date: &OffsetDateTime,
+        include_weekend: bool,
+    ) {
+        let start_date = start_date.to_offset(self.utc_offset);
+        let end_date = end_date.to_offset(self.utc_offset);
+
+        self._display_holidays(&start_date, &end_date, include_weekend);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_hong_kong_holidays() {
+        let hk = HongKong::new();
+        let holidays = [
+            datetime!(2024-01-01 10:00:00 +08:00), // New Year's Day
+            datetime!(2024-02-12 10:00:00 +08:00), // Lunar New Year
+            datetime!(2024-03-29 10:00:00 +08:00), // Good Friday
+            datetime!(2024-04-01 10:00:00 +08:00), // Easter Monday
+            datetime!(2024-07-01 10:00:00 +08:00), // HKSAR Establishment Day
+            datetime!(2024-12-26 10:00:00 +08:00), // Boxing Day
+            datetime!(2023-10-02 10:00:00 +08:00), // National Day on Sunday
+            datetime!(2022-12-27 10:00:00 +08:00), // Christmas on Sunday
+            datetime!(2026-02-17 10:00:00 +08:00), // Lunar New Year
+            datetime!(2026-04-07 10:00:00 +08:00), // the day following Easter Monday
+            datetime!(2026-10-19 10:00:00 +08:00), // Chung Yeung on Sunday
+        ];
+        for date in holidays.iter() {
+            assert!(hk.is_holiday(date), "{} is a Hong Kong holiday", date);
+        }
+        assert!(hk.is_business_day(&datetime!(2024-02-14 10:00:00 +08:00)));
+        assert!(hk.is_business_day(&datetime!(2024-12-27 10:00:00 +08:00)));
+
+        assert!(hk.try_is_holiday(&datetime!(2026-02-20 10:00:00 +08:00)).is_ok());
+        assert!(hk.try_is_holiday(&datetime!(2027-01-04 10:00:00 +08:00)).is_err());
+    }
+}
@@ -81,8 +81,8 @@ mod tests {
         let weekday = datetime!(2021-12-3 0:0:0 UTC); // Friday, 3rd December 2021
         let weekend = datetime!(2021-12-4 0:0:0 UTC); // Saturday, 4th December 2021
 
-        assert_eq!(calendar.is_weekend(&weekday), false);
-        assert_eq!(calendar.is_weekend(&weekend), true);
+        assert!(!calendar.is_weekend(&weekday));
+        assert!(calendar.is_weekend(&weekend));
     }
 
     #[test]
@@ -90,15 +90,15 @@ mod tests {
         let calendar = NullCalendar::default();
         let date = datetime!(2021-12-3 0:0:0 UTC); // Friday, 3rd December 2021
 
-        assert_eq!(calendar.is_holiday(&date), false);
+        assert!(!calendar.is_holiday(&date));
     }
 
     #[test]
     fn test_is_leap_year() {
         let calendar = NullCalendar::default();
 
-        assert_eq!(calendar.is_leap_year(2000), true); // 2000 is a leap year
-        assert_eq!(calendar.is_leap_year(2021), false); // 2021 is not a leap year
+        assert!(calendar.is_leap_year(2000)); // 2000 is a leap year
+        assert!(!calendar.is_leap_year(2021)); // 2021 is not a leap year
     }
 
     #[test]
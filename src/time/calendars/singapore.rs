@@ -0,0 +1,212 @@
+use crate::time::calendar_trait::CalendarTrait;
+use crate::time::holiday::Holidays;
+//
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use time::{Date, Month, OffsetDateTime, UtcOffset, Weekday};
+
+/// holidays of Singapore Exchange (SGX), also used for SGD settlements.
+/// The holidays of the lunar and religious calendars are hard coded for 2018-2026 (CalendarTrait::try_is_holiday fails out of the years).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SingaporeHolidays;
+
+impl SingaporeHolidays {
+    /// chinese new year, hari raya puasa, vesak day, hari raya haji, deepavali and polling days
+    /// which fall on weekdays, including the days moved from sundays
+    fn is_lunar_holiday(&self, date: &OffsetDateTime) -> bool {
+        let (y, m, d, _, _) = self.unpack(date);
+        matches!(
+            (y, m, d),
+            // 2018
+            (2018, Month::February, 16)
+                | (2018, Month::May, 29)
+                | (2018, Month::June, 15)
+                | (2018, Month::August, 22)
+                | (2018, Month::November, 6)
+                // 2019
+                | (2019, Month::February, 5..=6)
+                | (2019, Month::May, 20)
+                | (2019, Month::June, 5)
+                | (2019, Month::August, 12)
+                | (2019, Month::October, 28)
+                // 2020
+                | (2020, Month::January, 27)
+                | (2020, Month::May, 7)
+                | (2020, Month::May, 25)
+                | (2020, Month::July, 10)
+                | (2020, Month::July, 31)
+                // 2021
+                | (2021, Month::February, 12)
+                | (2021, Month::May, 13)
+                | (2021, Month::May, 26)
+                | (2021, Month::July, 20)
+                | (2021, Month::November, 4)
+                // 2022
+                | (2022, Month::February, 1..=2)
+                | (2022, Month::May, 3)
+                | (2022, Month::May, 16)
+                | (2022, Month::July, 11)
+                | (2022, Month::October, 24)
+                // 2023
+                | (2023, Month::January, 23..=24)
+                | (2023, Month::June, 2)
+                | (2023, Month::June, 29)
+                | (2023, Month::September, 1)
+                | (2023, Month::November, 13)
+                // 2024
+                | (2024, Month::February, 12)
+                | (2024, Month::April, 10)
+                | (2024, Month::May, 22)
+                | (2024, Month::June, 17)
+                | (2024, Month::October, 31)
+                // 2025
+                | (2025, Month::January, 29..=30)
+                | (2025, Month::March, 31)
+                | (2025, Month::May, 12)
+                | (2025, Month::October, 20)
+                // 2026
+                | (2026, Month::February, 17..=18)
+                | (2026, Month::May, 27)
+                | (2026, Month::June, 1)
+                | (2026, Month::November, 9)
+        )
+    }
+}
+
+impl Holidays for SingaporeHolidays {
+    #[allow(unused_variables)]
+    fn is_temporary_holiday(&self, date: &OffsetDateTime) -> bool {
+        false
+    }
+
+    fn is_holiday(&self, date: &OffsetDateTime) -> bool {
+        let (_, m, d, w, _) = self.unpack(date);
+
+        if ((d == 1 || (d == 2 && w == Weekday::Monday)) && m == Month::January) // New Year's Day (Monday if Sunday)
+        || self.is_good_friday(date, false) // Good Friday
+        || ((d == 1 || (d == 2 && w == Weekday::Monday)) && m == Month::May) // Labour Day (Monday if Sunday)
+        || ((d == 9 || (d == 10 && w == Weekday::Monday)) && m == Month::August) // National Day (Monday if Sunday)
+        || ((d == 25 || (d == 26 && w == Weekday::Monday)) && m == Month::December)
+        // Christmas (Monday if Sunday)
+        {
+            return true;
+        }
+
+        if self.is_temporary_holiday(date) {
+            return true;
+        }
+
+        self.is_lunar_holiday(date)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Singapore {
+    name: String,
+    utc_offset: UtcOffset,
+    holiday_adder: Vec<Date>,
+    holiday_remover: Vec<Date>,
+}
+
+impl Default for Singapore {
+    fn default() -> Singapore {
+        Singapore {
+            name: "Singapore".to_string(),
+            utc_offset: UtcOffset::from_hms(8, 0, 0).unwrap(),
+            holiday_adder: vec![],
+            holiday_remover: vec![],
+        }
+    }
+}
+
+impl Singapore {
+    pub fn new() -> Singapore {
+        Singapore::default()
+    }
+}
+
+impl CalendarTrait for Singapore {
+    fn calendar_name(&self) -> &String {
+        &self.name
+    }
+
+    fn add_holidays(&mut self, date: &Date) -> Result<()> {
+        self.holiday_adder.push(*date);
+        Ok(())
+    }
+
+    fn remove_holidays(&mut self, date: &Date) -> Result<()> {
+        self.holiday_remover.push(*date);
+        Ok(())
+    }
+
+    fn is_removed_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.date();
+        self.holiday_remover.contains(&date)
+    }
+
+    fn is_added_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.date();
+        self.holiday_adder.contains(&date)
+    }
+
+    fn is_base_holiday(&self, date: &OffsetDateTime) -> bool {
+        SingaporeHolidays.is_holiday(date)
+    }
+
+    fn is_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.to_offset(self.utc_offset);
+        self._is_holiday(&date)
+    }
+
+    fn get_holiday_years(&self) -> Option<RangeInclusive<i32>> {
+        Some(2018..=2026)
+    }
+
+    fn is_weekend(&self, date: &OffsetDateTime) -> bool {
+        let date = date.to_offset(self.utc_offset);
+        self._is_weekend(&date)
+    }
+
+    fn display_holidays(
+        &self,
+        start_date: &OffsetDateTime,
+        end_date: &OffsetDateTime,
+        include_weekend: bool,
+    ) {
+        let start_date = start_date.to_offset(self.utc_offset);
+        let end_date = end_date.to_offset(self.utc_offset);
+
+        self._display_holidays(&start_date, &end_date, include_weekend);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_singapore_holidays() {
+        let sg = Singapore::new();
+        let holidays = [
+            datetime!(2024-02-12 10:00:00 +08:00), // Chinese New Year on Sunday
+            datetime!(2024-03-29 10:00:00 +08:00), // Good Friday
+            datetime!(2024-04-10 10:00:00 +08:00), // Hari Raya Puasa
+            datetime!(2024-08-09 10:00:00 +08:00), // National Day
+            datetime!(2022-12-26 10:00:00 +08:00), // Christmas on Sunday
+            datetime!(2026-02-17 10:00:00 +08:00), // Chinese New Year
+            datetime!(2026-06-01 10:00:00 +08:00), // Vesak Day on Sunday
+            datetime!(2026-11-09 10:00:00 +08:00), // Deepavali on Sunday
+        ];
+        for date in holidays.iter() {
+            assert!(sg.is_holiday(date), "{} is a Singapore holiday", date);
+        }
+        // Easter Monday is not a holiday
+        assert!(sg.is_business_day(&datetime!(2024-04-01 10:00:00 +08:00)));
+
+        assert!(sg.try_is_holiday(&datetime!(2026-12-31 10:00:00 +08:00)).is_ok());
+        assert!(sg.try_is_holiday(&datetime!(2027-01-04 10:00:00 +08:00)).is_err());
+    }
+}
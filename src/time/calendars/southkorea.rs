@@ -1,10 +1,14 @@
 use crate::time::calendar_trait::CalendarTrait;
-use crate::time::constants::{FIRST_LUNAR_NEWYEAR, KOREAN_LUNAR_NEWYEARS, LAST_LUNAR_NEWYEAR};
 use crate::time::holiday::Holidays;
+use crate::time::korean_lunar::{self, FIRST_LUNAR_YEAR, LAST_LUNAR_YEAR};
 //
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
-use time::{Date, Duration, Month, OffsetDateTime, UtcOffset};
+use std::ops::RangeInclusive;
+use time::macros::date;
+use time::{Date, Duration, Month, OffsetDateTime, UtcOffset, Weekday};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub enum SouthKoreaType {
@@ -20,26 +24,15 @@ impl SouthKoreaType {
         }
     }
     
+    /// None out of FIRST_LUNAR_YEAR..=LAST_LUNAR_YEAR (see SouthKorea::get_holiday_years)
+    fn get_korean_holidays(&self, year: i32) -> Option<&'static KoreanHolidays> {
+        KOREAN_HOLIDAYS.get(&year)
+    }
+
     fn is_lunar_new_year(&self, date: &OffsetDateTime) -> bool {
         let date = date.date();
-        let year = date.year();
-
-        if (year as usize > LAST_LUNAR_NEWYEAR) || (FIRST_LUNAR_NEWYEAR > year as usize) {
-            println!("(WARN) Lunar New Year is not available for the year {}", year);
-            return false;
-        }
-
-        let solar_ordinal = KOREAN_LUNAR_NEWYEARS[year as usize - FIRST_LUNAR_NEWYEAR];
-
-        let lunar_new_year_day = Date::from_ordinal_date(year, solar_ordinal).unwrap();
-
-        if (lunar_new_year_day == date)
-            || (lunar_new_year_day + Duration::days(1) == date)
-            || (lunar_new_year_day - Duration::days(1) == date)
-        {
-            return true;
-        }
-        false
+        self.get_korean_holidays(date.year())
+            .is_some_and(|holidays| is_in_period(&date, &holidays.seollal))
     }
 
     fn is_labour_day(&self, date: &OffsetDateTime) -> bool {
@@ -54,433 +47,195 @@ impl SouthKoreaType {
 
     fn is_buddha_birthday(&self, date: &OffsetDateTime) -> bool {
         let date = date.date();
-        let year = date.year();
-        let month = date.month();
-        let day = date.day();
+        self.get_korean_holidays(date.year())
+            .is_some_and(|holidays| holidays.buddha_birthday == date)
+    }
 
-        if !(1951..=2050).contains(&year) {
-            println!("(WARN) Buddha's Birthday is not implemented for the year {}", year);
-            return false;
+    fn is_chuseok(&self, date: &OffsetDateTime) -> bool {
+        let date = date.date();
+        self.get_korean_holidays(date.year())
+            .is_some_and(|holidays| is_in_period(&date, &holidays.chuseok))
+    }
+
+    /// the substitute holidays of the year, Err if the year is out of the supported range of the lunar calendar
+    pub fn get_substitute_holidays(&self, year: i32) -> Result<Vec<Date>> {
+        match self.get_korean_holidays(year) {
+            Some(holidays) => Ok(holidays.substitutes.clone()),
+            None => Err(anyhow!(
+                "({}:{}) the substitute holidays are supported in {} ~ {}, but {} is requested",
+                file!(),
+                line!(),
+                FIRST_LUNAR_YEAR,
+                LAST_LUNAR_YEAR,
+                year
+            )),
         }
+    }
 
-        matches!(
-            (year, month, day),
-            (1951, Month::May, 13)
-                | (1952, Month::May, 1)
-                | (1953, Month::May, 20)
-                | (1954, Month::May, 10)
-                | (1955, Month::May, 29)
-                | (1956, Month::May, 17)
-                | (1957, Month::May, 7)
-                | (1958, Month::May, 26)
-                | (1959, Month::May, 15)
-                | (1960, Month::May, 3)
-                | (1961, Month::May, 22)
-                | (1962, Month::May, 11)
-                | (1963, Month::May, 1)
-                | (1964, Month::May, 19)
-                | (1965, Month::May, 8)
-                | (1966, Month::May, 27)
-                | (1967, Month::May, 16)
-                | (1968, Month::May, 5)
-                | (1969, Month::May, 23)
-                | (1970, Month::May, 12)
-                | (1971, Month::May, 2)
-                | (1972, Month::May, 20)
-                | (1973, Month::May, 10)
-                | (1974, Month::April, 29)
-                | (1975, Month::May, 18)
-                | (1976, Month::May, 6)
-                | (1977, Month::May, 25)
-                | (1978, Month::May, 14)
-                | (1979, Month::May, 3)
-                | (1980, Month::May, 21)
-                | (1981, Month::May, 11)
-                | (1982, Month::May, 1)
-                | (1983, Month::May, 20)
-                | (1984, Month::May, 8)
-                | (1985, Month::May, 27)
-                | (1986, Month::May, 16)
-                | (1987, Month::May, 5)
-                | (1988, Month::May, 23)
-                | (1989, Month::May, 12)
-                | (1990, Month::May, 2)
-                | (1991, Month::May, 21)
-                | (1992, Month::May, 10)
-                | (1993, Month::May, 28)
-                | (1994, Month::May, 18)
-                | (1995, Month::May, 7)
-                | (1996, Month::May, 24)
-                | (1997, Month::May, 14)
-                | (1998, Month::May, 3)
-                | (1999, Month::May, 22)
-                | (2000, Month::May, 11)
-                | (2001, Month::May, 1)
-                | (2002, Month::May, 19)
-                | (2003, Month::May, 8)
-                | (2004, Month::May, 26)
-                | (2005, Month::May, 15)
-                | (2006, Month::May, 5)
-                | (2007, Month::May, 24)
-                | (2008, Month::May, 12)
-                | (2009, Month::May, 2)
-                | (2010, Month::May, 21)
-                | (2011, Month::May, 10)
-                | (2012, Month::May, 28)
-                | (2013, Month::May, 17)
-                | (2014, Month::May, 6)
-                | (2015, Month::May, 25)
-                | (2016, Month::May, 14)
-                | (2017, Month::May, 3)
-                | (2018, Month::May, 22)
-                | (2019, Month::May, 12)
-                | (2020, Month::April, 30)
-                | (2021, Month::May, 19)
-                | (2022, Month::May, 8)
-                | (2023, Month::May, 27)
-                | (2024, Month::May, 15)
-                | (2025, Month::May, 5)
-                | (2026, Month::May, 24)
-                | (2027, Month::May, 13)
-                | (2028, Month::May, 2)
-                | (2029, Month::May, 20)
-                | (2030, Month::May, 9)
-                | (2031, Month::May, 28)
-                | (2032, Month::May, 16)
-                | (2033, Month::May, 6)
-                | (2034, Month::May, 25)
-                | (2035, Month::May, 15)
-                | (2036, Month::May, 3)
-                | (2037, Month::May, 22)
-                | (2038, Month::May, 11)
-                | (2039, Month::April, 30)
-                | (2040, Month::May, 18)
-                | (2041, Month::May, 7)
-                | (2042, Month::May, 26)
-                | (2043, Month::May, 16)
-                | (2044, Month::May, 5)
-                | (2045, Month::May, 24)
-                | (2046, Month::May, 13)
-                | (2047, Month::May, 2)
-                | (2048, Month::May, 20)
-                | (2049, Month::May, 9)
-                | (2050, Month::May, 28)
-        )
+    fn is_substitute_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.date();
+        self.get_korean_holidays(date.year())
+            .is_some_and(|holidays| holidays.substitutes.contains(&date))
     }
+}
 
-    fn is_chuseok(&self, date: &OffsetDateTime) -> bool {
-        let year = date.year();
-        let month = date.month();
-        let day = date.day();
+/// The public holidays having substitute holidays (대체공휴일).
+/// The lunar holidays are the three days periods around the days
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PublicHoliday {
+    NewYear,
+    Seollal,
+    Independence,
+    Children,
+    Buddha,
+    Memorial,
+    Liberation,
+    Chuseok,
+    Foundation,
+    Hangul,
+    Christmas,
+}
 
-        if !(1951..=2050).contains(&year) {
-            println!("Chuseok is not implemented for the year {}", year);
-            return false;
+impl PublicHoliday {
+    /// the first date from which the holiday is substituted
+    /// * Seollal, Chuseok and Children's day: 2013-11-05 (관공서의 공휴일에 관한 규정)
+    /// * 3.1, 8.15, 10.3 and 10.9: 2021-08-04 (공휴일에 관한 법률)
+    /// * Buddha's birthday and Christmas: 2023-05-04
+    fn substituted_from(&self) -> Option<Date> {
+        match self {
+            PublicHoliday::Seollal | PublicHoliday::Chuseok | PublicHoliday::Children => {
+                Some(date!(2013 - 11 - 05))
+            }
+            PublicHoliday::Independence
+            | PublicHoliday::Liberation
+            | PublicHoliday::Foundation
+            | PublicHoliday::Hangul => Some(date!(2021 - 08 - 04)),
+            PublicHoliday::Buddha | PublicHoliday::Christmas => Some(date!(2023 - 05 - 04)),
+            PublicHoliday::NewYear | PublicHoliday::Memorial => None,
         }
+    }
 
-        matches!(
-            (year, month, day),
-            (1951, Month::September, 14)
-                | (1951, Month::September, 15)
-                | (1951, Month::September, 16)
-                | (1952, Month::October, 2)
-                | (1952, Month::October, 3)
-                | (1952, Month::October, 4)
-                | (1953, Month::September, 21)
-                | (1953, Month::September, 22)
-                | (1953, Month::September, 23)
-                | (1954, Month::September, 10)
-                | (1954, Month::September, 11)
-                | (1954, Month::September, 12)
-                | (1955, Month::September, 29)
-                | (1955, Month::September, 30)
-                | (1955, Month::October, 1)
-                | (1956, Month::September, 18)
-                | (1956, Month::September, 19)
-                | (1956, Month::September, 20)
-                | (1957, Month::September, 7)
-                | (1957, Month::September, 8)
-                | (1957, Month::September, 9)
-                | (1958, Month::September, 26)
-                | (1958, Month::September, 27)
-                | (1958, Month::September, 28)
-                | (1959, Month::September, 16)
-                | (1959, Month::September, 17)
-                | (1959, Month::September, 18)
-                | (1960, Month::October, 4)
-                | (1960, Month::October, 5)
-                | (1960, Month::October, 6)
-                | (1961, Month::September, 23)
-                | (1961, Month::September, 24)
-                | (1961, Month::September, 25)
-                | (1962, Month::September, 12)
-                | (1962, Month::September, 13)
-                | (1962, Month::September, 14)
-                | (1963, Month::October, 1)
-                | (1963, Month::October, 2)
-                | (1963, Month::October, 3)
-                | (1964, Month::September, 19)
-                | (1964, Month::September, 20)
-                | (1964, Month::September, 21)
-                | (1965, Month::September, 9)
-                | (1965, Month::September, 10)
-                | (1965, Month::September, 11)
-                | (1966, Month::September, 28)
-                | (1966, Month::September, 29)
-                | (1966, Month::September, 30)
-                | (1967, Month::September, 17)
-                | (1967, Month::September, 18)
-                | (1967, Month::September, 19)
-                | (1968, Month::October, 5)
-                | (1968, Month::October, 6)
-                | (1968, Month::October, 7)
-                | (1969, Month::September, 25)
-                | (1969, Month::September, 26)
-                | (1969, Month::September, 27)
-                | (1970, Month::September, 14)
-                | (1970, Month::September, 15)
-                | (1970, Month::September, 16)
-                | (1971, Month::October, 2)
-                | (1971, Month::October, 3)
-                | (1971, Month::October, 4)
-                | (1972, Month::September, 21)
-                | (1972, Month::September, 22)
-                | (1972, Month::September, 23)
-                | (1973, Month::September, 10)
-                | (1973, Month::September, 11)
-                | (1973, Month::September, 12)
-                | (1974, Month::September, 29)
-                | (1974, Month::September, 30)
-                | (1974, Month::October, 1)
-                | (1975, Month::September, 19)
-                | (1975, Month::September, 20)
-                | (1975, Month::September, 21)
-                | (1976, Month::September, 7)
-                | (1976, Month::September, 8)
-                | (1976, Month::September, 9)
-                | (1977, Month::September, 26)
-                | (1977, Month::September, 27)
-                | (1977, Month::September, 28)
-                | (1978, Month::September, 16)
-                | (1978, Month::September, 17)
-                | (1978, Month::September, 18)
-                | (1979, Month::October, 4)
-                | (1979, Month::October, 5)
-                | (1979, Month::October, 6)
-                | (1980, Month::September, 22)
-                | (1980, Month::September, 23)
-                | (1980, Month::September, 24)
-                | (1981, Month::September, 11)
-                | (1981, Month::September, 12)
-                | (1981, Month::September, 13)
-                | (1982, Month::September, 30)
-                | (1982, Month::October, 1)
-                | (1982, Month::October, 2)
-                | (1983, Month::September, 20)
-                | (1983, Month::September, 21)
-                | (1983, Month::September, 22)
-                | (1984, Month::September, 9)
-                | (1984, Month::September, 10)
-                | (1984, Month::September, 11)
-                | (1985, Month::September, 28)
-                | (1985, Month::September, 29)
-                | (1985, Month::September, 30)
-                | (1986, Month::September, 17)
-                | (1986, Month::September, 18)
-                | (1986, Month::September, 19)
-                | (1987, Month::October, 6)
-                | (1987, Month::October, 7)
-                | (1987, Month::October, 8)
-                | (1988, Month::September, 24)
-                | (1988, Month::September, 25)
-                | (1988, Month::September, 26)
-                | (1989, Month::September, 13)
-                | (1989, Month::September, 14)
-                | (1989, Month::September, 15)
-                | (1990, Month::October, 2)
-                | (1990, Month::October, 3)
-                | (1990, Month::October, 4)
-                | (1991, Month::September, 21)
-                | (1991, Month::September, 22)
-                | (1991, Month::September, 23)
-                | (1992, Month::September, 10)
-                | (1992, Month::September, 11)
-                | (1992, Month::September, 12)
-                | (1993, Month::September, 29)
-                | (1993, Month::September, 30)
-                | (1993, Month::October, 1)
-                | (1994, Month::September, 19)
-                | (1994, Month::September, 20)
-                | (1994, Month::September, 21)
-                | (1995, Month::September, 8)
-                | (1995, Month::September, 9)
-                | (1995, Month::September, 10)
-                | (1996, Month::September, 26)
-                | (1996, Month::September, 27)
-                | (1996, Month::September, 28)
-                | (1997, Month::September, 15)
-                | (1997, Month::September, 16)
-                | (1997, Month::September, 17)
-                | (1998, Month::October, 4)
-                | (1998, Month::October, 5)
-                | (1998, Month::October, 6)
-                | (1999, Month::September, 23)
-                | (1999, Month::September, 24)
-                | (1999, Month::September, 25)
-                | (2000, Month::September, 11)
-                | (2000, Month::September, 12)
-                | (2000, Month::September, 13)
-                | (2001, Month::September, 30)
-                | (2001, Month::October, 1)
-                | (2001, Month::October, 2)
-                | (2002, Month::September, 20)
-                | (2002, Month::September, 21)
-                | (2002, Month::September, 22)
-                | (2003, Month::September, 10)
-                | (2003, Month::September, 11)
-                | (2003, Month::September, 12)
-                | (2004, Month::September, 27)
-                | (2004, Month::September, 28)
-                | (2004, Month::September, 29)
-                | (2005, Month::September, 17)
-                | (2005, Month::September, 18)
-                | (2005, Month::September, 19)
-                | (2006, Month::October, 5)
-                | (2006, Month::October, 6)
-                | (2006, Month::October, 7)
-                | (2007, Month::September, 24)
-                | (2007, Month::September, 25)
-                | (2007, Month::September, 26)
-                | (2008, Month::September, 13)
-                | (2008, Month::September, 14)
-                | (2008, Month::September, 15)
-                | (2009, Month::October, 2)
-                | (2009, Month::October, 3)
-                | (2009, Month::October, 4)
-                | (2010, Month::September, 21)
-                | (2010, Month::September, 22)
-                | (2010, Month::September, 23)
-                | (2011, Month::September, 11)
-                | (2011, Month::September, 12)
-                | (2011, Month::September, 13)
-                | (2012, Month::September, 29)
-                | (2012, Month::September, 30)
-                | (2012, Month::October, 1)
-                | (2013, Month::September, 18)
-                | (2013, Month::September, 19)
-                | (2013, Month::September, 20)
-                | (2014, Month::September, 7)
-                | (2014, Month::September, 8)
-                | (2014, Month::September, 9)
-                | (2015, Month::September, 26)
-                | (2015, Month::September, 27)
-                | (2015, Month::September, 28)
-                | (2016, Month::September, 14)
-                | (2016, Month::September, 15)
-                | (2016, Month::September, 16)
-                | (2017, Month::October, 3)
-                | (2017, Month::October, 4)
-                | (2017, Month::October, 5)
-                | (2018, Month::September, 23)
-                | (2018, Month::September, 24)
-                | (2018, Month::September, 25)
-                | (2019, Month::September, 12)
-                | (2019, Month::September, 13)
-                | (2019, Month::September, 14)
-                | (2020, Month::September, 30)
-                | (2020, Month::October, 1)
-                | (2020, Month::October, 2)
-                | (2021, Month::September, 20)
-                | (2021, Month::September, 21)
-                | (2021, Month::September, 22)
-                | (2022, Month::September, 9)
-                | (2022, Month::September, 10)
-                | (2022, Month::September, 11)
-                | (2023, Month::September, 28)
-                | (2023, Month::September, 29)
-                | (2023, Month::September, 30)
-                | (2024, Month::September, 16)
-                | (2024, Month::September, 17)
-                | (2024, Month::September, 18)
-                | (2025, Month::October, 5)
-                | (2025, Month::October, 6)
-                | (2025, Month::October, 7)
-                | (2026, Month::September, 24)
-                | (2026, Month::September, 25)
-                | (2026, Month::September, 26)
-                | (2027, Month::September, 14)
-                | (2027, Month::September, 15)
-                | (2027, Month::September, 16)
-                | (2028, Month::October, 2)
-                | (2028, Month::October, 3)
-                | (2028, Month::October, 4)
-                | (2029, Month::September, 21)
-                | (2029, Month::September, 22)
-                | (2029, Month::September, 23)
-                | (2030, Month::September, 11)
-                | (2030, Month::September, 12)
-                | (2030, Month::September, 13)
-                | (2031, Month::September, 30)
-                | (2031, Month::October, 1)
-                | (2031, Month::October, 2)
-                | (2032, Month::September, 18)
-                | (2032, Month::September, 19)
-                | (2032, Month::September, 20)
-                | (2033, Month::September, 7)
-                | (2033, Month::September, 8)
-                | (2033, Month::September, 9)
-                | (2034, Month::September, 26)
-                | (2034, Month::September, 27)
-                | (2034, Month::September, 28)
-                | (2035, Month::September, 15)
-                | (2035, Month::September, 16)
-                | (2035, Month::September, 17)
-                | (2036, Month::October, 3)
-                | (2036, Month::October, 4)
-                | (2036, Month::October, 5)
-                | (2037, Month::September, 23)
-                | (2037, Month::September, 24)
-                | (2037, Month::September, 25)
-                | (2038, Month::September, 12)
-                | (2038, Month::September, 13)
-                | (2038, Month::September, 14)
-                | (2039, Month::October, 1)
-                | (2039, Month::October, 2)
-                | (2039, Month::October, 3)
-                | (2040, Month::September, 20)
-                | (2040, Month::September, 21)
-                | (2040, Month::September, 22)
-                | (2041, Month::September, 9)
-                | (2041, Month::September, 10)
-                | (2041, Month::September, 11)
-                | (2042, Month::September, 27)
-                | (2042, Month::September, 28)
-                | (2042, Month::September, 29)
-                | (2043, Month::September, 16)
-                | (2043, Month::September, 17)
-                | (2043, Month::September, 18)
-                | (2044, Month::October, 4)
-                | (2044, Month::October, 5)
-                | (2044, Month::October, 6)
-                | (2045, Month::September, 24)
-                | (2045, Month::September, 25)
-                | (2045, Month::September, 26)
-                | (2046, Month::September, 14)
-                | (2046, Month::September, 15)
-                | (2046, Month::September, 16)
-                | (2047, Month::October, 3)
-                | (2047, Month::October, 4)
-                | (2047, Month::October, 5)
-                | (2048, Month::September, 21)
-                | (2048, Month::September, 22)
-                | (2048, Month::September, 23)
-                | (2049, Month::September, 10)
-                | (2049, Month::September, 11)
-                | (2049, Month::September, 12)
-                | (2050, Month::September, 29)
-                | (2050, Month::September, 30)
-                | (2050, Month::October, 1)
-        )
+    /// the lunar holidays are substituted on Sunday but not on Saturday
+    fn is_substituted_on(&self, weekday: Weekday) -> bool {
+        match self {
+            PublicHoliday::Seollal | PublicHoliday::Chuseok => weekday == Weekday::Sunday,
+            _ => weekday == Weekday::Saturday || weekday == Weekday::Sunday,
+        }
+    }
+}
+
+/// the lunar holidays and the substitute holidays of a year
+#[derive(Debug, Clone)]
+struct KoreanHolidays {
+    seollal: [Date; 3],
+    buddha_birthday: Date,
+    chuseok: [Date; 3],
+    substitutes: Vec<Date>,
+}
+
+/// The lunar dates of all the years in the range are verified in the tests of korean_lunar
+static KOREAN_HOLIDAYS: Lazy<FxHashMap<i32, KoreanHolidays>> = Lazy::new(|| {
+    (FIRST_LUNAR_YEAR..=LAST_LUNAR_YEAR)
+        .map(|year| {
+            let holidays = KoreanHolidays::new(year)
+                .expect("the lunar calendar is supported in FIRST_LUNAR_YEAR..=LAST_LUNAR_YEAR");
+            (year, holidays)
+        })
+        .collect()
+});
+
+fn is_in_period(date: &Date, period: &[Date; 3]) -> bool {
+    period.contains(date)
+}
+
+fn three_days_period(date: Date) -> [Date; 3] {
+    [date - Duration::days(1), date, date + Duration::days(1)]
+}
+
+impl KoreanHolidays {
+    fn new(year: i32) -> Result<KoreanHolidays> {
+        let seollal = three_days_period(korean_lunar::seollal(year)?);
+        let buddha_birthday = korean_lunar::buddha_birthday(year)?;
+        let chuseok = three_days_period(korean_lunar::chuseok(year)?);
+
+        let mut holidays = vec![
+            (Date::from_calendar_date(year, Month::January, 1)?, PublicHoliday::NewYear),
+            (Date::from_calendar_date(year, Month::March, 1)?, PublicHoliday::Independence),
+            (Date::from_calendar_date(year, Month::May, 5)?, PublicHoliday::Children),
+            (buddha_birthday, PublicHoliday::Buddha),
+            (Date::from_calendar_date(year, Month::June, 6)?, PublicHoliday::Memorial),
+            (Date::from_calendar_date(year, Month::August, 15)?, PublicHoliday::Liberation),
+            (Date::from_calendar_date(year, Month::October, 3)?, PublicHoliday::Foundation),
+            (Date::from_calendar_date(year, Month::October, 9)?, PublicHoliday::Hangul),
+            (Date::from_calendar_date(year, Month::December, 25)?, PublicHoliday::Christmas),
+        ];
+        holidays.extend(seollal.iter().map(|d| (*d, PublicHoliday::Seollal)));
+        holidays.extend(chuseok.iter().map(|d| (*d, PublicHoliday::Chuseok)));
+        holidays.sort_by_key(|(d, _)| *d);
+
+        let substitutes = KoreanHolidays::get_substitutes(&holidays, &seollal, &chuseok);
+        Ok(KoreanHolidays {
+            seollal,
+            buddha_birthday,
+            chuseok,
+            substitutes,
+        })
+    }
+
+    /// A substitute holiday is the first non-holiday weekday after the holiday (or the lunar holiday period)
+    /// for each substituted holiday on the weekend or overlapping another public holiday
+    fn get_substitutes(
+        holidays: &[(Date, PublicHoliday)],
+        seollal: &[Date; 3],
+        chuseok: &[Date; 3],
+    ) -> Vec<Date> {
+        let is_holiday = |date: &Date| holidays.iter().any(|(d, _)| d == date);
+        let mut res: Vec<Date> = Vec::new();
+
+        let mut dates = holidays.iter().map(|(d, _)| *d).collect::<Vec<Date>>();
+        dates.dedup();
+        for date in dates {
+            let on_date = holidays
+                .iter()
+                .filter(|(d, _)| *d == date)
+                .map(|(_, h)| *h)
+                .collect::<Vec<PublicHoliday>>();
+            let substituted = on_date
+                .iter()
+                .filter(|h| h.substituted_from().is_some_and(|from| date >= from))
+                .collect::<Vec<&PublicHoliday>>();
+            if substituted.is_empty() {
+                continue;
+            }
+
+            let on_weekend = substituted
+                .iter()
+                .any(|h| h.is_substituted_on(date.weekday()));
+            let count = if on_weekend {
+                substituted.len()
+            } else {
+                substituted.len().min(on_date.len() - 1)
+            };
+
+            let mut next = if is_in_period(&date, seollal) {
+                seollal[2]
+            } else if is_in_period(&date, chuseok) {
+                chuseok[2]
+            } else {
+                date
+            };
+            for _ in 0..count {
+                next += Duration::days(1);
+                while matches!(next.weekday(), Weekday::Saturday | Weekday::Sunday)
+                    || is_holiday(&next)
+                    || res.contains(&next)
+                {
+                    next += Duration::days(1);
+                }
+                res.push(next);
+            }
+        }
+        res
     }
 }
 
@@ -491,11 +246,8 @@ impl Holidays for SouthKoreaType {
         let month = date.month();
         let day = date.day();
 
-        if !(1951..=2050).contains(&year) {
-            println!(
-                "(WARN) Last business day of the year is not implemented for the year {}",
-                year
-            );
+        // out of the years, try_is_holiday of SouthKorea fails
+        if !(FIRST_LUNAR_YEAR..=LAST_LUNAR_YEAR).contains(&year) {
             return false;
         }
 
@@ -661,13 +413,8 @@ impl Holidays for SouthKoreaType {
 
         match (year, month, day) {
             // 2023
-            (2023, Month::January, 24) => true,
             (2023, Month::May, 1) => true,
-            (2023, Month::May, 29) => true,
             (2023, Month::October, 2) => true,
-            // 2024
-            (2024, Month::February, 12) => true,
-            (2024, Month::May, 6) => true,
             _ => false,
         }
     }
@@ -689,7 +436,7 @@ impl Holidays for SouthKoreaType {
             return true;
         }
 
-        if self.is_temporary_holiday(date) {
+        if self.is_substitute_holiday(date) || self.is_temporary_holiday(date) {
             return true;
         }
 
@@ -760,6 +507,11 @@ impl CalendarTrait for SouthKorea {
         self._is_holiday(&date)
     }
 
+    /// the years of the lunar holidays, the substitute holidays and the last business days of KRX
+    fn get_holiday_years(&self) -> Option<RangeInclusive<i32>> {
+        Some(FIRST_LUNAR_YEAR..=LAST_LUNAR_YEAR)
+    }
+
     fn is_weekend(&self, date: &OffsetDateTime) -> bool {
         let date = date.to_offset(self.utc_offset);
         self._is_weekend(&date)
@@ -783,7 +535,7 @@ mod tests {
     use super::*;
     use crate::time::conventions::BusinessDayConvention;
     use anyhow::Result;
-    use time::macros::datetime;
+    use time::macros::{date, datetime};
 
     #[test]
     fn test_south_korea_name() {
@@ -823,6 +575,66 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_south_korea_substitute_holidays() -> Result<()> {
+        let krx = SouthKoreaType::Krx;
+        let expected = [
+            (2014, vec![date!(2014 - 09 - 10)]),
+            (2015, vec![date!(2015 - 09 - 29)]),
+            (2016, vec![date!(2016 - 02 - 10)]),
+            (2017, vec![date!(2017 - 01 - 30), date!(2017 - 10 - 06)]),
+            (2018, vec![date!(2018 - 05 - 07), date!(2018 - 09 - 26)]),
+            (2019, vec![date!(2019 - 05 - 06)]),
+            (2020, vec![date!(2020 - 01 - 27)]),
+            (
+                2021,
+                vec![date!(2021 - 08 - 16), date!(2021 - 10 - 04), date!(2021 - 10 - 11)],
+            ),
+            (2022, vec![date!(2022 - 09 - 12), date!(2022 - 10 - 10)]),
+            (2023, vec![date!(2023 - 01 - 24), date!(2023 - 05 - 29)]),
+            (2024, vec![date!(2024 - 02 - 12), date!(2024 - 05 - 06)]),
+            (
+                2025,
+                vec![date!(2025 - 03 - 03), date!(2025 - 05 - 06), date!(2025 - 10 - 08)],
+            ),
+            (
+                2026,
+                vec![
+                    date!(2026 - 03 - 02),
+                    date!(2026 - 05 - 25),
+                    date!(2026 - 08 - 17),
+                    date!(2026 - 10 - 05),
+                ],
+            ),
+        ];
+        for (year, dates) in expected {
+            assert_eq!(krx.get_substitute_holidays(year)?, dates, "{}", year);
+        }
+        assert!(krx.get_substitute_holidays(2013)?.is_empty());
+        assert!(krx.get_substitute_holidays(1950).is_err());
+
+        let calendar = SouthKorea::new(SouthKoreaType::Settlement);
+        // chuseok in the leap year of the lunar calendar and its substitute holiday
+        assert!(calendar.is_holiday(&datetime!(2025-10-06 0:0:0 +09:00)));
+        assert!(calendar.is_holiday(&datetime!(2025-10-08 0:0:0 +09:00)));
+        assert!(!calendar.is_holiday(&datetime!(2025-10-10 0:0:0 +09:00)));
+        assert!(calendar.is_holiday(&datetime!(2050-05-28 0:0:0 +09:00)));
+        assert!(calendar.is_holiday(&datetime!(2050-09-30 0:0:0 +09:00)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_south_korea_holiday_years() -> Result<()> {
+        let calendar = SouthKorea::new(SouthKoreaType::Krx);
+        assert_eq!(calendar.get_holiday_years(), Some(1951..=2050));
+        assert!(calendar.try_is_holiday(&datetime!(2050-12-30 0:0:0 +09:00))?);
+        assert!(!calendar.try_is_holiday(&datetime!(1951-06-01 0:0:0 +09:00))?);
+        // the lunar holidays are not available out of the years
+        assert!(calendar.try_is_holiday(&datetime!(2051-02-11 0:0:0 +09:00)).is_err());
+        assert!(calendar.try_is_holiday(&datetime!(1950-02-17 0:0:0 +09:00)).is_err());
+        Ok(())
+    }
+
     //unittest for adjusted business day
 
     #[test]
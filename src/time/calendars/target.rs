@@ -0,0 +1,162 @@
+use crate::time::calendar_trait::CalendarTrait;
+use crate::time::holiday::Holidays;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use time::{Date, Month, OffsetDateTime, UtcOffset};
+
+/// holidays of TARGET (Trans-European Automated Real-time Gross settlement Express Transfer system),
+/// the settlement calendar of EUR, e.g., for EUR discounting, EURIBOR and ESTR fixings, and EUR bonds.
+/// See <https://www.ecb.europa.eu/paym/target/target2/profuse/calendar/html/index.en.html>
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TargetHolidays;
+
+impl Holidays for TargetHolidays {
+    #[allow(unused_variables)]
+    fn is_temporary_holiday(&self, date: &OffsetDateTime) -> bool {
+        false
+    }
+
+    fn is_holiday(&self, date: &OffsetDateTime) -> bool {
+        let (y, m, d, _, _) = self.unpack(date);
+
+        if (d == 1 && m == Month::January) // New Year's Day
+        || (y >= 2000 && self.is_good_friday(date, false)) // Good Friday
+        || (y >= 2000 && self.is_easter_monday(date, false)) // Easter Monday
+        || (y >= 2000 && d == 1 && m == Month::May) // Labour Day
+        || (d == 25 && m == Month::December) // Christmas
+        || (y >= 2000 && d == 26 && m == Month::December) // Day of Goodwill
+        || (d == 31 && m == Month::December && (y == 1998 || y == 1999 || y == 2001))
+        // December 31st, 1998, 1999, and 2001 only
+        {
+            return true;
+        }
+        false
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Target {
+    name: String,
+    utc_offset: UtcOffset,
+    holiday_adder: Vec<Date>,
+    holiday_remover: Vec<Date>,
+}
+
+impl Default for Target {
+    fn default() -> Target {
+        Target {
+            name: "TARGET".to_string(),
+            // CET
+            utc_offset: UtcOffset::from_hms(1, 0, 0).unwrap(),
+            holiday_adder: vec![],
+            holiday_remover: vec![],
+        }
+    }
+}
+
+impl Target {
+    pub fn new() -> Target {
+        Target::default()
+    }
+}
+
+impl CalendarTrait for Target {
+    fn calendar_name(&self) -> &String {
+        &self.name
+    }
+
+    fn add_holidays(&mut self, date: &Date) -> Result<()> {
+        self.holiday_adder.push(*date);
+        Ok(())
+    }
+
+    fn remove_holidays(&mut self, date: &Date) -> Result<()> {
+        self.holiday_remover.push(*date);
+        Ok(())
+    }
+
+    fn is_removed_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.date();
+        self.holiday_remover.contains(&date)
+    }
+
+    fn is_added_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.date();
+        self.holiday_adder.contains(&date)
+    }
+
+    fn is_base_holiday(&self, date: &OffsetDateTime) -> bool {
+        TargetHolidays.is_holiday(date)
+    }
+
+    fn is_holiday(&self, date: &OffsetDateTime) -> bool {
+        let date = date.to_offset(self.utc_offset);
+
+        self._is_holiday(&date)
+    }
+
+    fn is_weekend(&self, date: &OffsetDateTime) -> bool {
+        let date = date.to_offset(self.utc_offset);
+        self._is_weekend(&date)
+    }
+
+    fn display_holidays(
+        &self,
+        start_date: &OffsetDateTime,
+        end_date: &OffsetDateTime,
+        include_weekend: bool,
+    ) {
+        let start_date = start_date.to_offset(self.utc_offset);
+        let end_date = end_date.to_offset(self.utc_offset);
+
+        self._display_holidays(&start_date, &end_date, include_weekend);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_target_holidays() {
+        let target = Target::new();
+        assert_eq!(target.calendar_name(), "TARGET");
+
+        let holidays = [
+            datetime!(2024-01-01 12:00:00 +01:00), // New Year's Day
+            datetime!(2024-03-29 12:00:00 +01:00), // Good Friday
+            datetime!(2024-04-01 12:00:00 +01:00), // Easter Monday
+            datetime!(2024-05-01 12:00:00 +01:00), // Labour Day
+            datetime!(2024-12-25 12:00:00 +01:00), // Christmas
+            datetime!(2024-12-26 12:00:00 +01:00), // Day of Goodwill
+            datetime!(2025-04-18 12:00:00 +01:00), // Good Friday
+            datetime!(2025-04-21 12:00:00 +01:00), // Easter Monday
+            datetime!(2001-12-31 12:00:00 +01:00),
+        ];
+        for date in holidays.iter() {
+            assert!(target.is_holiday(date), "{} is a TARGET holiday", date);
+            assert!(!target.is_business_day(date));
+        }
+
+        let business_days = [
+            datetime!(2024-03-28 12:00:00 +01:00),
+            datetime!(2024-04-02 12:00:00 +01:00),
+            datetime!(2024-05-09 12:00:00 +01:00), // Ascension Day is not a TARGET holiday
+            datetime!(2024-12-24 12:00:00 +01:00),
+            datetime!(2024-12-31 12:00:00 +01:00),
+        ];
+        for date in business_days.iter() {
+            assert!(target.is_business_day(date), "{} is a business day", date);
+        }
+
+        // the Good Friday, Easter Monday, Labour Day and Day of Goodwill since 2000
+        assert!(!target.is_holiday(&datetime!(1998-05-01 12:00:00 +01:00)));
+        assert!(target.is_holiday(&datetime!(1999-12-31 12:00:00 +01:00)));
+        assert!(!target.is_holiday(&datetime!(2002-12-31 12:00:00 +01:00)));
+
+        // weekend in CET
+        assert!(target.is_weekend(&datetime!(2024-03-30 12:00:00 +01:00)));
+        assert!(!target.is_weekend(&datetime!(2024-03-30 01:00:00 +09:00)));
+    }
+}
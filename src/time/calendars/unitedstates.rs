@@ -329,8 +329,8 @@ mod tests {
         let us = UnitedStates::new(UnitedStatesType::Settlement);
         let date = datetime!(2022-1-1 00:00:00 -5:00);
 
-        assert_eq!(us.is_base_holiday(&date), true);
-        assert_eq!(us.is_removed_holiday(&date), false);
-        assert_eq!(us.is_added_holiday(&date), false);
+        assert!(us.is_base_holiday(&date));
+        assert!(!us.is_removed_holiday(&date));
+        assert!(!us.is_added_holiday(&date));
     }
 }
@@ -13,7 +13,7 @@ pub enum BusinessDayConvention {
     Dummy,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Copy)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Copy)]
 pub enum DayCountConvention {
     Actual365Fixed,
     Actual360,
@@ -81,7 +81,6 @@ impl std::fmt::Display for PaymentFrequency {
 }
 
 #[cfg(test)]
-
 mod tests {
     use super::*;
     #[test]
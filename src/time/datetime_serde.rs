@@ -0,0 +1,281 @@
+//! Serde of the datetimes in the instruments and the data in the formats of the downstream systems.
+//!
+//! The fields with `#[serde(with = "crate::time::datetime_serde::datetime")]` (and option_datetime, vec_datetime, option_vec_datetime)
+//! are deserialized from any of
+//! * the default format of the time crate, e.g., [2024,73,16,30,0,0,9,0,0]
+//! * RFC 3339, e.g., "2024-03-13T16:30:00+09:00"
+//! * a datetime without offset, e.g., "2024-03-13 16:30:00", in the offset of the options
+//! * a date, e.g., "2024-03-13", at the midnight in the offset of the options
+//! * epoch milliseconds, e.g., 1710315000000, in the offset of the options
+//!
+//! and serialized in the format of the options of the thread (DateTimeFormat::Default if not set).
+use crate::util::parse_offsetdatetime;
+//
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::Cell;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{OffsetDateTime, UtcOffset};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DateTimeFormat {
+    /// the format of the time crate, which is the format before the options
+    #[default]
+    Default,
+    /// e.g., "2024-03-13T16:30:00+09:00"
+    Rfc3339,
+    /// e.g., "2024-03-13", the date in the offset of the datetime
+    DateOnly,
+    /// e.g., "2024-03-13 16:30:00", the local datetime without the offset
+    WithoutOffset,
+    /// milliseconds from 1970-01-01T00:00:00Z
+    EpochMillis,
+}
+
+/// * format: the format of the serialization
+/// * utc_offset: the offset given to the deserialized datetimes without offset (date only, without offset and epoch millis)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DateTimeSerdeOptions {
+    format: DateTimeFormat,
+    utc_offset: UtcOffset,
+}
+
+impl Default for DateTimeSerdeOptions {
+    fn default() -> DateTimeSerdeOptions {
+        DateTimeSerdeOptions {
+            format: DateTimeFormat::Default,
+            utc_offset: UtcOffset::UTC,
+        }
+    }
+}
+
+impl DateTimeSerdeOptions {
+    pub fn new(format: DateTimeFormat, utc_offset: UtcOffset) -> DateTimeSerdeOptions {
+        DateTimeSerdeOptions { format, utc_offset }
+    }
+
+    pub fn get_format(&self) -> DateTimeFormat {
+        self.format
+    }
+
+    pub fn get_utc_offset(&self) -> UtcOffset {
+        self.utc_offset
+    }
+}
+
+// per thread as the time cache, so that the threads writing to different systems do not interfere
+thread_local! {
+    static DATETIME_SERDE_OPTIONS: Cell<DateTimeSerdeOptions> = Cell::new(DateTimeSerdeOptions::default());
+}
+
+pub fn get_datetime_serde_options() -> DateTimeSerdeOptions {
+    DATETIME_SERDE_OPTIONS.with(|options| options.get())
+}
+
+/// set the options of the current thread
+pub fn set_datetime_serde_options(options: DateTimeSerdeOptions) {
+    DATETIME_SERDE_OPTIONS.with(|cell| cell.set(options));
+}
+
+/// run f with the options in the current thread, and restore the previous options
+pub fn with_datetime_serde_options<T, F: FnOnce() -> T>(options: DateTimeSerdeOptions, f: F) -> T {
+    let previous = get_datetime_serde_options();
+    set_datetime_serde_options(options);
+    let res = f();
+    set_datetime_serde_options(previous);
+    res
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DateTimeRepr {
+    Default(OffsetDateTime),
+    EpochMillis(i64),
+    Text(String),
+}
+
+struct SerdeDateTime(OffsetDateTime);
+
+impl Serialize for SerdeDateTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let dt = &self.0;
+        let text = match get_datetime_serde_options().format {
+            DateTimeFormat::Default => return dt.serialize(serializer),
+            DateTimeFormat::EpochMillis => {
+                return serializer.serialize_i64((dt.unix_timestamp_nanos() / 1_000_000) as i64)
+            }
+            DateTimeFormat::Rfc3339 => dt.format(&Rfc3339),
+            DateTimeFormat::DateOnly => dt.format(format_description!("[year]-[month]-[day]")),
+            DateTimeFormat::WithoutOffset => dt.format(format_description!(
+                "[year]-[month]-[day] [hour]:[minute]:[second]"
+            )),
+        };
+        serializer.serialize_str(&text.map_err(serde::ser::Error::custom)?)
+    }
+}
+
+impl<'de> Deserialize<'de> for SerdeDateTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SerdeDateTime, D::Error> {
+        let utc_offset = get_datetime_serde_options().utc_offset;
+        let dt = match DateTimeRepr::deserialize(deserializer)? {
+            DateTimeRepr::Default(dt) => dt,
+            DateTimeRepr::EpochMillis(millis) => {
+                OffsetDateTime::from_unix_timestamp_nanos(millis as i128 * 1_000_000)
+                    .map_err(de::Error::custom)?
+                    .to_offset(utc_offset)
+            }
+            DateTimeRepr::Text(s) => {
+                parse_offsetdatetime(&s, utc_offset).map_err(de::Error::custom)?
+            }
+        };
+        Ok(SerdeDateTime(dt))
+    }
+}
+
+pub mod datetime {
+    use super::SerdeDateTime;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use time::OffsetDateTime;
+
+    pub fn serialize<S: Serializer>(dt: &OffsetDateTime, serializer: S) -> Result<S::Ok, S::Error> {
+        SerdeDateTime(*dt).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<OffsetDateTime, D::Error> {
+        Ok(SerdeDateTime::deserialize(deserializer)?.0)
+    }
+}
+
+pub mod option_datetime {
+    use super::SerdeDateTime;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use time::OffsetDateTime;
+
+    pub fn serialize<S: Serializer>(
+        dt: &Option<OffsetDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        dt.map(SerdeDateTime).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<OffsetDateTime>, D::Error> {
+        Ok(Option::<SerdeDateTime>::deserialize(deserializer)?.map(|dt| dt.0))
+    }
+}
+
+pub mod vec_datetime {
+    use super::SerdeDateTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use time::OffsetDateTime;
+
+    pub fn serialize<S: Serializer>(
+        dts: &[OffsetDateTime],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(dts.iter().map(|dt| SerdeDateTime(*dt)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<OffsetDateTime>, D::Error> {
+        let dts = Vec::<SerdeDateTime>::deserialize(deserializer)?;
+        Ok(dts.into_iter().map(|dt| dt.0).collect())
+    }
+}
+
+pub mod option_vec_datetime {
+    use super::SerdeDateTime;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use time::OffsetDateTime;
+
+    pub fn serialize<S: Serializer>(
+        dts: &Option<Vec<OffsetDateTime>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        dts.as_ref()
+            .map(|dts| dts.iter().map(|dt| SerdeDateTime(*dt)).collect::<Vec<_>>())
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<OffsetDateTime>>, D::Error> {
+        let dts = Option::<Vec<SerdeDateTime>>::deserialize(deserializer)?;
+        Ok(dts.map(|dts| dts.into_iter().map(|dt| dt.0).collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use time::macros::datetime;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Dates {
+        #[serde(with = "datetime")]
+        date: OffsetDateTime,
+        #[serde(default, with = "option_datetime")]
+        maturity: Option<OffsetDateTime>,
+        #[serde(with = "vec_datetime")]
+        dates: Vec<OffsetDateTime>,
+    }
+
+    #[test]
+    fn test_datetime_serde() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let dates = Dates {
+            date: dt,
+            maturity: None,
+            dates: vec![dt],
+        };
+        // the default format is not changed
+        assert_eq!(
+            serde_json::to_string(&dates)?,
+            format!(
+                r#"{{"date":{0},"maturity":null,"dates":[{0}]}}"#,
+                serde_json::to_string(&dt)?
+            )
+        );
+
+        let kst = UtcOffset::from_hms(9, 0, 0)?;
+        let options = |format| DateTimeSerdeOptions::new(format, kst);
+        let expected = [
+            (DateTimeFormat::Rfc3339, r#""2024-03-13T16:30:00+09:00""#),
+            (DateTimeFormat::DateOnly, r#""2024-03-13""#),
+            (DateTimeFormat::WithoutOffset, r#""2024-03-13 16:30:00""#),
+            (DateTimeFormat::EpochMillis, "1710315000000"),
+        ];
+        for (format, text) in expected {
+            let s = with_datetime_serde_options(options(format), || serde_json::to_string(&dates))?;
+            assert_eq!(
+                s,
+                format!(r#"{{"date":{0},"maturity":null,"dates":[{0}]}}"#, text)
+            );
+            let res: Dates =
+                with_datetime_serde_options(options(format), || serde_json::from_str(&s))?;
+            let expected_dt = match format {
+                DateTimeFormat::DateOnly => datetime!(2024-03-13 00:00:00 +09:00),
+                _ => dt,
+            };
+            assert_eq!(res.date, expected_dt);
+            assert_eq!(res.date.offset(), kst);
+        }
+        assert_eq!(
+            get_datetime_serde_options(),
+            DateTimeSerdeOptions::default()
+        );
+
+        // without offset in UTC by default, and the missing option is None
+        let res: Dates = serde_json::from_str(r#"{"date":"2024-03-13","dates":[1710315000000]}"#)?;
+        assert_eq!(res.date, datetime!(2024-03-13 00:00:00 UTC));
+        assert_eq!(res.maturity, None);
+        assert_eq!(res.dates, vec![dt]);
+        assert!(serde_json::from_str::<Dates>(r#"{"date":"20240313","dates":[]}"#).is_err());
+        Ok(())
+    }
+}
@@ -26,10 +26,26 @@ pub trait Holidays {
             return false;
         }
 
+        // EASTER_MONDAYS[0] is western and EASTER_MONDAYS[1] is orthodox
         if is_orthodox {
+            dd == EASTER_MONDAYS[1][year as usize - FIRST_EASTER_MONDAY] - 3
+        } else {
             dd == EASTER_MONDAYS[0][year as usize - FIRST_EASTER_MONDAY] - 3
+        }
+    }
+
+    /// false out of the years of the Easter Monday table (FIRST_EASTER_MONDAY to LAST_EASTER_MONDAY)
+    fn is_easter_monday(&self, date: &OffsetDateTime, is_orthodox: bool) -> bool {
+        let (year, _, _, _, dd) = self.unpack(date);
+
+        if (year < FIRST_EASTER_MONDAY as i32) || (year as usize > LAST_EASTER_MONDAY) {
+            return false;
+        }
+
+        if is_orthodox {
+            dd == EASTER_MONDAYS[1][year as usize - FIRST_EASTER_MONDAY]
         } else {
-            dd == EASTER_MONDAYS[1][year as usize - FIRST_EASTER_MONDAY] - 3
+            dd == EASTER_MONDAYS[0][year as usize - FIRST_EASTER_MONDAY]
         }
     }
 }
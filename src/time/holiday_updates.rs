@@ -0,0 +1,156 @@
+//! Holidays amended at runtime, e.g., ad-hoc market closures and temporary holidays
+//! proclaimed by the government, without waiting for a new release of the crate.
+//!
+//! The updates are kept by the calendar name (CalendarTrait::calendar_name, e.g., "South Korea (KRX)")
+//! in a process-wide registry, and every calendar of the name checks it on is_holiday.
+//! Thus the calendars already held by the instruments, the schedules and the pricers pick up the updates.
+//! The dates of the schedules already generated are fixed, but a schedule made by build_schedule keeps
+//! the version of the updates (get_holiday_updates_version), so that Schedule::is_outdated detects the later updates.
+//! InstrumentTrait::refresh_schedules regenerates the outdated schedules of an instrument,
+//! and Engine::with_instruments does it on the copies of the instruments given.
+use crate::time::calendar_trait::CalendarTrait;
+//
+use once_cell::sync::Lazy;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use time::Date;
+
+#[derive(Debug, Clone, Default)]
+struct HolidayUpdate {
+    added: FxHashSet<Date>,
+    removed: FxHashSet<Date>,
+}
+
+static HOLIDAY_UPDATES: Lazy<RwLock<FxHashMap<String, HolidayUpdate>>> =
+    Lazy::new(|| RwLock::new(FxHashMap::default()));
+
+/// increased on each update, so that the users caching business days can detect the updates
+static HOLIDAY_UPDATES_VERSION: AtomicU64 = AtomicU64::new(0);
+
+fn update<F: FnOnce(&mut FxHashMap<String, HolidayUpdate>)>(f: F) {
+    let mut updates = HOLIDAY_UPDATES.write().unwrap_or_else(|e| e.into_inner());
+    f(&mut updates);
+    HOLIDAY_UPDATES_VERSION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Make the date a holiday in all calendars of the name. This cancels a removal of the date
+pub fn add_holiday(calendar_name: &str, date: &Date) {
+    update(|updates| {
+        let update = updates.entry(calendar_name.to_string()).or_default();
+        update.removed.remove(date);
+        update.added.insert(*date);
+    });
+}
+
+/// Make the date a business day (unless weekend) in all calendars of the name. This cancels an addition of the date
+pub fn remove_holiday(calendar_name: &str, date: &Date) {
+    update(|updates| {
+        let update = updates.entry(calendar_name.to_string()).or_default();
+        update.added.remove(date);
+        update.removed.insert(*date);
+    });
+}
+
+pub fn add_calendar_holiday<C: CalendarTrait>(calendar: &C, date: &Date) {
+    add_holiday(calendar.calendar_name(), date);
+}
+
+pub fn remove_calendar_holiday<C: CalendarTrait>(calendar: &C, date: &Date) {
+    remove_holiday(calendar.calendar_name(), date);
+}
+
+/// Clear the updates of the calendar name, or all updates if None
+pub fn clear_holiday_updates(calendar_name: Option<&str>) {
+    update(|updates| match calendar_name {
+        Some(name) => {
+            updates.remove(name);
+        }
+        None => updates.clear(),
+    });
+}
+
+/// (added holidays, removed holidays) of the calendar name in ascending order
+pub fn get_holiday_updates(calendar_name: &str) -> (Vec<Date>, Vec<Date>) {
+    let updates = HOLIDAY_UPDATES.read().unwrap_or_else(|e| e.into_inner());
+    match updates.get(calendar_name) {
+        Some(update) => {
+            let mut added = update.added.iter().copied().collect::<Vec<Date>>();
+            let mut removed = update.removed.iter().copied().collect::<Vec<Date>>();
+            added.sort();
+            removed.sort();
+            (added, removed)
+        }
+        None => (vec![], vec![]),
+    }
+}
+
+pub fn get_holiday_updates_version() -> u64 {
+    HOLIDAY_UPDATES_VERSION.load(Ordering::SeqCst)
+}
+
+/// Some(true) if added, Some(false) if removed, and None if the date is not updated
+pub(crate) fn get_holiday_update(calendar_name: &str, date: &Date) -> Option<bool> {
+    // skip the lock if nothing has been updated
+    if get_holiday_updates_version() == 0 {
+        return None;
+    }
+    let updates = HOLIDAY_UPDATES.read().unwrap_or_else(|e| e.into_inner());
+    let update = updates.get(calendar_name)?;
+    if update.added.contains(date) {
+        Some(true)
+    } else if update.removed.contains(date) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::calendar::Calendar;
+    use crate::time::calendars::custom::CustomCalendar;
+    use crate::time::jointcalendar::JointCalendar;
+    use anyhow::Result;
+    use time::macros::{date, datetime};
+
+    #[test]
+    fn test_holiday_updates() -> Result<()> {
+        let name = "Holiday Updates Test";
+        let custom = CustomCalendar::new(name.to_string(), vec![date!(2024 - 10 - 09)]);
+        // a joint calendar made before the updates, as held by the instruments
+        let calendar = JointCalendar::new(vec![Calendar::Custom(custom.clone())])?;
+
+        let closure = datetime!(2024-10-01 12:00:00 UTC);
+        assert!(!calendar.is_holiday(&closure));
+        assert_eq!(
+            calendar.adjust_following(&closure),
+            datetime!(2024-10-01 12:00:00 UTC)
+        );
+
+        add_calendar_holiday(&custom, &closure.date());
+        remove_holiday(name, &date!(2024 - 10 - 09));
+        assert!(calendar.is_holiday(&closure));
+        assert!(!calendar.is_holiday(&datetime!(2024-10-09 12:00:00 UTC)));
+        assert_eq!(
+            calendar.adjust_following(&closure),
+            datetime!(2024-10-02 12:00:00 UTC)
+        );
+        assert_eq!(
+            get_holiday_updates(name),
+            (vec![date!(2024 - 10 - 01)], vec![date!(2024 - 10 - 09)])
+        );
+
+        // the weekend is not changed by the removal
+        remove_holiday(name, &date!(2024 - 10 - 05));
+        assert!(calendar.is_holiday(&datetime!(2024-10-05 12:00:00 UTC)));
+
+        let version = get_holiday_updates_version();
+        clear_holiday_updates(Some(name));
+        assert!(get_holiday_updates_version() > version);
+        assert!(!calendar.is_holiday(&closure));
+        assert!(calendar.is_holiday(&datetime!(2024-10-09 12:00:00 UTC)));
+        Ok(())
+    }
+}
@@ -4,12 +4,26 @@ use crate::time::calendar_trait::CalendarTrait;
 use crate::time::calendars::southkorea::{SouthKorea, SouthKoreaType};
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
 use time::OffsetDateTime;
 
+/// How the calendars are joined
+/// * JoinHolidays: a holiday if it is a holiday in any calendar (business day if open in all markets)
+/// * JoinBusinessDays: a business day if it is a business day in any calendar (holiday if closed in all markets),
+///   e.g., the payment dates of some CRS confirmations
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum JointCalendarRule {
+    #[default]
+    JoinHolidays,
+    JoinBusinessDays,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct JointCalendar {
     name: String,
     calendars: Vec<Calendar>,
+    #[serde(default)]
+    rule: JointCalendarRule,
 }
 
 impl Default for JointCalendar {
@@ -19,6 +33,7 @@ impl Default for JointCalendar {
         JointCalendar {
             name: sk.calendar_name().clone(),
             calendars: vec![sk_cal],
+            rule: JointCalendarRule::JoinHolidays,
         }
     }
 }
@@ -41,7 +56,20 @@ impl JointCalendar {
             }
         }
 
-        Ok(JointCalendar { name, calendars })
+        Ok(JointCalendar {
+            name,
+            calendars,
+            rule: JointCalendarRule::JoinHolidays,
+        })
+    }
+
+    pub fn with_rule(mut self, rule: JointCalendarRule) -> JointCalendar {
+        self.rule = rule;
+        self
+    }
+
+    pub fn get_rule(&self) -> JointCalendarRule {
+        self.rule
     }
 
     pub fn calendars(&self) -> &Vec<Calendar> {
@@ -49,7 +77,22 @@ impl JointCalendar {
     }
 
     pub fn is_business_day(&self, date: &OffsetDateTime) -> bool {
-        self.calendars.iter().all(|c| c.is_business_day(date))
+        match self.rule {
+            JointCalendarRule::JoinHolidays => {
+                self.calendars.iter().all(|c| c.is_business_day(date))
+            }
+            JointCalendarRule::JoinBusinessDays => {
+                self.calendars.iter().any(|c| c.is_business_day(date))
+            }
+        }
+    }
+
+    /// true if the predicate holds in any calendar (JoinHolidays) or in all calendars (JoinBusinessDays)
+    fn join<F: Fn(&Calendar) -> bool>(&self, f: F) -> bool {
+        match self.rule {
+            JointCalendarRule::JoinHolidays => self.calendars.iter().any(f),
+            JointCalendarRule::JoinBusinessDays => self.calendars.iter().all(f),
+        }
     }
 }
 
@@ -68,7 +111,7 @@ impl CalendarTrait for JointCalendar {
     }
 
     fn is_weekend(&self, date: &OffsetDateTime) -> bool {
-        self.calendars.iter().any(|c| c.is_weekend(date))
+        self.join(|c| c.is_weekend(date))
     }
 
     fn display_holidays(
@@ -91,11 +134,11 @@ impl CalendarTrait for JointCalendar {
     }
 
     fn is_added_holiday(&self, date: &OffsetDateTime) -> bool {
-        self.calendars.iter().any(|c| c.is_added_holiday(date))
+        self.join(|c| c.is_added_holiday(date))
     }
 
     fn is_base_holiday(&self, date: &OffsetDateTime) -> bool {
-        self.calendars.iter().any(|c| c.is_base_holiday(date))
+        self.join(|c| c.is_base_holiday(date))
     }
 
     fn add_holidays(&mut self, _date: &time::Date) -> Result<()> {
@@ -111,7 +154,15 @@ impl CalendarTrait for JointCalendar {
     }
 
     fn is_holiday(&self, date: &OffsetDateTime) -> bool {
-        self.calendars.iter().any(|c| c.is_holiday(date))
+        self.join(|c| c.is_holiday(date))
+    }
+
+    /// the years in which the holidays of all the calendars are given
+    fn get_holiday_years(&self) -> Option<RangeInclusive<i32>> {
+        self.calendars
+            .iter()
+            .filter_map(|c| c.get_holiday_years())
+            .reduce(|a, b| *a.start().max(b.start())..=*a.end().min(b.end()))
     }
 }
 
@@ -119,6 +170,8 @@ impl CalendarTrait for JointCalendar {
 mod tests {
     use super::*;
     use crate::time::calendar::Calendar;
+    use crate::time::calendars::china::{China, ChinaType};
+    use crate::time::calendars::hongkong::HongKong;
     use crate::time::calendars::southkorea::{SouthKorea, SouthKoreaType};
     use crate::time::calendars::unitedstates::{UnitedStates, UnitedStatesType};
     use time::macros::datetime;
@@ -134,8 +187,48 @@ mod tests {
         let joint_calendar = JointCalendar::new(vec![us_cal, sk_cal])?;
 
         let date = datetime!(2021-05-05 00:00:00 +09:00);
-        assert_eq!(joint_calendar.is_holiday(&date), true);
-        assert_eq!(joint_calendar.is_business_day(&date), false);
+        assert!(joint_calendar.is_holiday(&date));
+        assert!(!joint_calendar.is_business_day(&date));
+        assert_eq!(joint_calendar.get_holiday_years(), Some(1951..=2050));
+        Ok(())
+    }
+
+    #[test]
+    fn test_joint_calendar_holiday_years() -> Result<()> {
+        let cn_cal = Calendar::China(China::new(ChinaType::Sse));
+        let hk_cal = Calendar::HongKong(HongKong::new());
+        let joint_calendar = JointCalendar::new(vec![cn_cal, hk_cal])?;
+        assert_eq!(joint_calendar.get_holiday_years(), Some(2018..=2026));
+        assert!(joint_calendar.try_is_holiday(&datetime!(2026-05-05 10:00:00 +08:00))?);
+        assert!(joint_calendar.try_is_holiday(&datetime!(2017-05-05 10:00:00 +08:00)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_joint_calendar_join_business_days() -> Result<()> {
+        let us_cal = Calendar::UnitedStates(UnitedStates::new(UnitedStatesType::Settlement));
+        let sk_cal = Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Settlement));
+        let joint_calendar = JointCalendar::new(vec![us_cal, sk_cal])?
+            .with_rule(JointCalendarRule::JoinBusinessDays);
+
+        // children's day in Korea is open in New York
+        let date = datetime!(2021-05-05 12:00:00 +09:00);
+        assert!(!joint_calendar.is_holiday(&date));
+        assert!(joint_calendar.is_business_day(&date));
+        // christmas in both (in the local times of the calendars)
+        let date = datetime!(2024-12-25 16:00:00 +09:00);
+        assert!(joint_calendar.is_holiday(&date));
+        assert_eq!(
+            joint_calendar.adjust_following(&date),
+            datetime!(2024-12-26 16:00:00 +09:00)
+        );
+        assert!(joint_calendar.is_weekend(&datetime!(2024-12-28 16:00:00 +09:00)));
+
+        // the rule is JoinHolidays in the json written before the rule
+        let mut value = serde_json::to_value(&joint_calendar)?;
+        value.as_object_mut().unwrap().remove("rule");
+        let loaded: JointCalendar = serde_json::from_value(value)?;
+        assert_eq!(loaded.get_rule(), JointCalendarRule::JoinHolidays);
         Ok(())
     }
 }
@@ -0,0 +1,306 @@
+//! Korean lunisolar calendar computed from the new moons and the principal solar terms in KST (UTC+9),
+//! following the rules of the Chinese calendar:
+//! - a lunar month starts on the day of a new moon
+//! - the month containing the winter solstice is the 11th month
+//! - if there are 13 months between two 11th months, the first month without a principal solar term
+//!   (the solar longitude of a multiple of 30°) is the leap month, numbered as the previous month
+//!
+//! The new moons and the solar longitudes are from Meeus, Astronomical Algorithms (2nd ed.), ch. 25 and 49.
+//! The lunar holidays (Seollal, Buddha's birthday and Chuseok) are computed in
+//! [FIRST_LUNAR_YEAR, LAST_LUNAR_YEAR], where every year is verified against the KASI tables,
+//! and the requests out of the range are errors.
+use anyhow::{anyhow, Result};
+use time::Date;
+
+pub const FIRST_LUNAR_YEAR: i32 = 1951;
+pub const LAST_LUNAR_YEAR: i32 = 2050;
+
+const KST_IN_DAYS: f64 = 9.0 / 24.0;
+
+/// ΔT = TT - UT in seconds (Espenak and Meeus polynomials)
+fn delta_t(year: f64) -> f64 {
+    if year < 1920.0 {
+        let t = year - 1900.0;
+        -2.79 + 1.494119 * t - 0.0598939 * t.powi(2) + 0.0061966 * t.powi(3) - 0.000197 * t.powi(4)
+    } else if year < 1941.0 {
+        let t = year - 1920.0;
+        21.20 + 0.84493 * t - 0.076100 * t.powi(2) + 0.0020936 * t.powi(3)
+    } else if year < 1961.0 {
+        let t = year - 1950.0;
+        29.07 + 0.407 * t - t.powi(2) / 233.0 + t.powi(3) / 2547.0
+    } else if year < 1986.0 {
+        let t = year - 1975.0;
+        45.45 + 1.067 * t - t.powi(2) / 260.0 - t.powi(3) / 718.0
+    } else if year < 2005.0 {
+        let t = year - 2000.0;
+        63.86 + 0.3345 * t - 0.060374 * t.powi(2)
+            + 0.0017275 * t.powi(3)
+            + 0.000651814 * t.powi(4)
+            + 0.00002373599 * t.powi(5)
+    } else if year < 2050.0 {
+        let t = year - 2000.0;
+        62.92 + 0.32217 * t + 0.005589 * t.powi(2)
+    } else {
+        let u = (year - 1820.0) / 100.0;
+        -20.0 + 32.0 * u.powi(2) - 0.5628 * (2150.0 - year)
+    }
+}
+
+/// the julian day (TT) to the date in KST
+fn jde_to_kst_date(jde: f64) -> Date {
+    let year = 2000.0 + (jde - 2451545.0) / 365.25;
+    let jd = jde - delta_t(year) / 86400.0 + KST_IN_DAYS;
+    Date::from_julian_day((jd + 0.5).floor() as i32).unwrap()
+}
+
+fn sin_deg(x: f64) -> f64 {
+    x.to_radians().sin()
+}
+
+/// the julian day (TT) of the k-th new moon from 2000-01-06 (Meeus ch. 49)
+fn new_moon_jde(k: i64) -> f64 {
+    let k = k as f64;
+    let t = k / 1236.85;
+    let jde = 2451550.09766 + 29.530588861 * k + 0.00015437 * t.powi(2) - 0.000000150 * t.powi(3)
+        + 0.00000000073 * t.powi(4);
+    let e = 1.0 - 0.002516 * t - 0.0000074 * t.powi(2);
+    let m = 2.5534 + 29.10535670 * k - 0.0000014 * t.powi(2) - 0.00000011 * t.powi(3);
+    let mp = 201.5643 + 385.81693528 * k + 0.0107582 * t.powi(2) + 0.00001238 * t.powi(3)
+        - 0.000000058 * t.powi(4);
+    let f = 160.7108 + 390.67050284 * k - 0.0016118 * t.powi(2) - 0.00000227 * t.powi(3)
+        + 0.000000011 * t.powi(4);
+    let omega = 124.7746 - 1.56375588 * k + 0.0020672 * t.powi(2) + 0.00000215 * t.powi(3);
+
+    let correction = -0.40720 * sin_deg(mp)
+        + 0.17241 * e * sin_deg(m)
+        + 0.01608 * sin_deg(2.0 * mp)
+        + 0.01039 * sin_deg(2.0 * f)
+        + 0.00739 * e * sin_deg(mp - m)
+        - 0.00514 * e * sin_deg(mp + m)
+        + 0.00208 * e * e * sin_deg(2.0 * m)
+        - 0.00111 * sin_deg(mp - 2.0 * f)
+        - 0.00057 * sin_deg(mp + 2.0 * f)
+        + 0.00056 * e * sin_deg(2.0 * mp + m)
+        - 0.00042 * sin_deg(3.0 * mp)
+        + 0.00042 * e * sin_deg(m + 2.0 * f)
+        + 0.00038 * e * sin_deg(m - 2.0 * f)
+        - 0.00024 * e * sin_deg(2.0 * mp - m)
+        - 0.00017 * sin_deg(omega)
+        - 0.00007 * sin_deg(mp + 2.0 * m)
+        + 0.00004 * sin_deg(2.0 * mp - 2.0 * f)
+        + 0.00004 * sin_deg(3.0 * m)
+        + 0.00003 * sin_deg(mp + m - 2.0 * f)
+        + 0.00003 * sin_deg(2.0 * mp + 2.0 * f)
+        - 0.00003 * sin_deg(mp + m + 2.0 * f)
+        + 0.00003 * sin_deg(mp - m + 2.0 * f)
+        - 0.00002 * sin_deg(mp - m - 2.0 * f)
+        - 0.00002 * sin_deg(3.0 * mp + m)
+        + 0.00002 * sin_deg(4.0 * mp);
+
+    let planetary = [
+        (0.000325, 299.77 + 0.107408 * k - 0.009173 * t.powi(2)),
+        (0.000165, 251.88 + 0.016321 * k),
+        (0.000164, 251.83 + 26.651886 * k),
+        (0.000126, 349.42 + 36.412478 * k),
+        (0.000110, 84.66 + 18.206239 * k),
+        (0.000062, 141.74 + 53.303771 * k),
+        (0.000060, 207.14 + 2.453732 * k),
+        (0.000056, 154.84 + 7.306860 * k),
+        (0.000047, 34.52 + 27.261239 * k),
+        (0.000042, 207.19 + 0.121824 * k),
+        (0.000040, 291.34 + 1.844379 * k),
+        (0.000037, 161.72 + 24.198154 * k),
+        (0.000035, 239.56 + 25.513099 * k),
+        (0.000023, 331.55 + 3.592518 * k),
+    ]
+    .iter()
+    .map(|(c, a)| c * sin_deg(*a))
+    .sum::<f64>();
+
+    jde + correction + planetary
+}
+
+/// apparent longitude of the sun in degrees (Meeus ch. 25, about 0.01° accuracy)
+fn solar_longitude(jde: f64) -> f64 {
+    let t = (jde - 2451545.0) / 36525.0;
+    let l0 = 280.46646 + 36000.76983 * t + 0.0003032 * t.powi(2);
+    let m = 357.52911 + 35999.05029 * t - 0.0001537 * t.powi(2);
+    let c = (1.914602 - 0.004817 * t - 0.000014 * t.powi(2)) * sin_deg(m)
+        + (0.019993 - 0.000101 * t) * sin_deg(2.0 * m)
+        + 0.000289 * sin_deg(3.0 * m);
+    let omega = 125.04 - 1934.136 * t;
+    (l0 + c - 0.00569 - 0.00478 * sin_deg(omega)).rem_euclid(360.0)
+}
+
+/// the julian day (TT) when the solar longitude is the given degrees, near the given julian day
+fn solar_term_jde(longitude: f64, jde_guess: f64) -> f64 {
+    let mut jde = jde_guess;
+    for _ in 0..50 {
+        let correction = 58.0 * sin_deg(longitude - solar_longitude(jde));
+        jde += correction;
+        if correction.abs() < 1.0e-7 {
+            break;
+        }
+    }
+    jde
+}
+
+/// the date (KST) of the winter solstice of the year
+fn winter_solstice(year: i32) -> Date {
+    let guess = Date::from_calendar_date(year, time::Month::December, 21)
+        .unwrap()
+        .to_julian_day() as f64;
+    jde_to_kst_date(solar_term_jde(270.0, guess))
+}
+
+/// the index of the new moon starting the lunar month containing the date
+fn new_moon_index_on_or_before(date: Date) -> i64 {
+    let jd = date.to_julian_day() as f64;
+    let mut k = ((jd - 2451550.09766) / 29.530588861).floor() as i64 + 1;
+    while jde_to_kst_date(new_moon_jde(k)) > date {
+        k -= 1;
+    }
+    k
+}
+
+/// true if a principal solar term is in [start, end)
+fn has_principal_term(start: Date, end: Date) -> bool {
+    // the principal term is the first multiple of 30° from the solar longitude at the start
+    let start_jde = start.to_julian_day() as f64;
+    let longitude = (solar_longitude(start_jde) / 30.0).ceil() * 30.0;
+    let term = jde_to_kst_date(solar_term_jde(longitude % 360.0, start_jde + 15.0));
+    let term = if term < start {
+        // the longitude was just below the multiple at the start in KST
+        let next = (longitude + 30.0) % 360.0;
+        jde_to_kst_date(solar_term_jde(next, start_jde + 45.0))
+    } else {
+        term
+    };
+    start <= term && term < end
+}
+
+/// (the month number, is leap month, the first day) of the lunar months
+/// from the 11th month of the previous year to the 11th month of the year (exclusive)
+fn lunar_months(year: i32) -> Vec<(u8, bool, Date)> {
+    let k_start = new_moon_index_on_or_before(winter_solstice(year - 1));
+    let k_end = new_moon_index_on_or_before(winter_solstice(year));
+    let starts = (k_start..=k_end)
+        .map(|k| jde_to_kst_date(new_moon_jde(k)))
+        .collect::<Vec<Date>>();
+    let is_leap_year = k_end - k_start == 13;
+
+    let mut res = Vec::with_capacity(13);
+    let mut month = 11;
+    let mut leap_found = false;
+    for i in 0..starts.len() - 1 {
+        let is_leap =
+            is_leap_year && !leap_found && i > 0 && !has_principal_term(starts[i], starts[i + 1]);
+        if is_leap {
+            leap_found = true;
+        } else if i > 0 {
+            month = month % 12 + 1;
+        }
+        res.push((month, is_leap, starts[i]));
+    }
+    res
+}
+
+fn check_range(year: i32) -> Result<()> {
+    if !(FIRST_LUNAR_YEAR..=LAST_LUNAR_YEAR).contains(&year) {
+        return Err(anyhow!(
+            "({}:{}) the lunar calendar is supported in {} ~ {}, but {} is requested",
+            file!(),
+            line!(),
+            FIRST_LUNAR_YEAR,
+            LAST_LUNAR_YEAR,
+            year
+        ));
+    }
+    Ok(())
+}
+
+/// the solar date of the lunar date (month, day) of the year, where the month is not a leap month
+pub fn lunar_to_solar(year: i32, month: u8, day: u8) -> Result<Date> {
+    check_range(year)?;
+    if !(1..=12).contains(&month) || !(1..=30).contains(&day) {
+        return Err(anyhow!(
+            "({}:{}) invalid lunar date {}-{}-{}",
+            file!(),
+            line!(),
+            year,
+            month,
+            day
+        ));
+    }
+    let (_, _, start) = lunar_months(year)
+        .into_iter()
+        .skip(2) // the 11th and 12th months of the previous year
+        .find(|(m, is_leap, _)| *m == month && !is_leap)
+        .ok_or_else(|| {
+            anyhow!(
+                "({}:{}) no lunar month {} in {}",
+                file!(),
+                line!(),
+                month,
+                year
+            )
+        })?;
+    Ok(start + time::Duration::days(day as i64 - 1))
+}
+
+/// lunar new year's day (1/1)
+pub fn seollal(year: i32) -> Result<Date> {
+    lunar_to_solar(year, 1, 1)
+}
+
+/// Buddha's birthday (4/8)
+pub fn buddha_birthday(year: i32) -> Result<Date> {
+    lunar_to_solar(year, 4, 8)
+}
+
+/// Chuseok (8/15)
+pub fn chuseok(year: i32) -> Result<Date> {
+    lunar_to_solar(year, 8, 15)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::constants::{FIRST_LUNAR_NEWYEAR, KOREAN_LUNAR_NEWYEARS};
+    use korean_lunar_calendar::convert::lunar_to_gregorian;
+    use korean_lunar_calendar::date::LunarDate;
+    use time::macros::date;
+
+    fn from_table(year: i32, month: u8, day: u8) -> Date {
+        let date = lunar_to_gregorian(&LunarDate {
+            year,
+            month: (month, false),
+            day,
+        })
+        .unwrap();
+        Date::from_julian_day(
+            (date - chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()).num_days() as i32
+                + date!(2000 - 01 - 01).to_julian_day(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_lunar_holidays() -> Result<()> {
+        assert_eq!(seollal(2024)?, date!(2024 - 02 - 10));
+        assert_eq!(buddha_birthday(2024)?, date!(2024 - 05 - 15));
+        assert_eq!(chuseok(2024)?, date!(2024 - 09 - 17));
+        // leap 6th month in 2025
+        assert_eq!(chuseok(2025)?, date!(2025 - 10 - 06));
+        assert!(seollal(1950).is_err());
+        assert!(chuseok(LAST_LUNAR_YEAR + 1).is_err());
+
+        for year in FIRST_LUNAR_YEAR..=LAST_LUNAR_YEAR {
+            let ordinal = KOREAN_LUNAR_NEWYEARS[year as usize - FIRST_LUNAR_NEWYEAR];
+            assert_eq!(seollal(year)?, Date::from_ordinal_date(year, ordinal)?);
+            assert_eq!(buddha_birthday(year)?, from_table(year, 4, 8), "{}", year);
+            assert_eq!(chuseok(year)?, from_table(year, 8, 15), "{}", year);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use crate::time::calendar_trait::CalendarTrait;
+use crate::time::jointcalendar::JointCalendar;
+//
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use time::{Date, Month, OffsetDateTime, Time, UtcOffset, Weekday};
+
+/// The last trading day rules of the KRX listed derivatives (파생상품시장 업무규정)
+/// * EquityDerivative: KOSPI200, KOSDAQ150, sector index and single stock futures/options.
+///   The second Thursday of the contract month, settled on the next business day
+/// * Ktbf: 3Y, 5Y, 10Y and 30Y KTB futures.
+///   The third Tuesday of the contract month, settled on the next business day
+/// * CurrencyFutures: USD, JPY, EUR and CNH futures.
+///   The third Monday of the contract month, settled on the second business day after
+///
+/// If the day is a KRX holiday, the last trading day is the preceding business day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KrxExpiryRule {
+    EquityDerivative,
+    Ktbf,
+    CurrencyFutures,
+}
+
+/// the n-th (1-based) weekday of the month, e.g., the second Thursday of March 2024 is 2024-03-14
+pub fn nth_weekday_of_month(year: i32, month: Month, weekday: Weekday, n: u8) -> Result<Date> {
+    let first = Date::from_calendar_date(year, month, 1)?;
+    let offset = (weekday.number_days_from_monday() as i64
+        - first.weekday().number_days_from_monday() as i64)
+        .rem_euclid(7);
+    let res = first + time::Duration::days(offset + 7 * (n as i64 - 1));
+    if n == 0 || res.month() != month {
+        return Err(anyhow!(
+            "({}:{}) there is no {}-th {:?} in {:?} {}",
+            file!(),
+            line!(),
+            n,
+            weekday,
+            month,
+            year
+        ));
+    }
+    Ok(res)
+}
+
+impl KrxExpiryRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KrxExpiryRule::EquityDerivative => "EquityDerivative",
+            KrxExpiryRule::Ktbf => "Ktbf",
+            KrxExpiryRule::CurrencyFutures => "CurrencyFutures",
+        }
+    }
+
+    /// the trading hours end earlier on the last trading day
+    pub fn closing_time(&self) -> Time {
+        match self {
+            KrxExpiryRule::EquityDerivative => Time::from_hms(15, 20, 0).unwrap(),
+            KrxExpiryRule::Ktbf | KrxExpiryRule::CurrencyFutures => {
+                Time::from_hms(11, 30, 0).unwrap()
+            }
+        }
+    }
+
+    pub fn settlement_lag(&self) -> i64 {
+        match self {
+            KrxExpiryRule::EquityDerivative | KrxExpiryRule::Ktbf => 1,
+            KrxExpiryRule::CurrencyFutures => 2,
+        }
+    }
+
+    fn unadjusted_last_trading_date(&self, year: i32, month: Month) -> Result<Date> {
+        match self {
+            KrxExpiryRule::EquityDerivative => {
+                nth_weekday_of_month(year, month, Weekday::Thursday, 2)
+            }
+            KrxExpiryRule::Ktbf => nth_weekday_of_month(year, month, Weekday::Tuesday, 3),
+            KrxExpiryRule::CurrencyFutures => nth_weekday_of_month(year, month, Weekday::Monday, 3),
+        }
+    }
+
+    /// The last trading day (the maturity) of the contract month at the closing time in KST
+    pub fn get_maturity(
+        &self,
+        year: i32,
+        month: Month,
+        calendar: &JointCalendar,
+    ) -> Result<OffsetDateTime> {
+        let date = self.unadjusted_last_trading_date(year, month)?;
+        let kst = UtcOffset::from_hms(9, 0, 0)?;
+        let maturity = OffsetDateTime::new_in_offset(date, self.closing_time(), kst);
+        Ok(calendar.adjust_preceding(&maturity))
+    }
+
+    pub fn get_settlement_date(
+        &self,
+        year: i32,
+        month: Month,
+        calendar: &JointCalendar,
+    ) -> Result<OffsetDateTime> {
+        let maturity = self.get_maturity(year, month, calendar)?;
+        Ok(calendar.add_business_days(&maturity, self.settlement_lag()))
+    }
+
+    /// Err if the date of the maturity is not the last trading day of its month.
+    /// The time of the maturity is not checked since the instruments are booked at various times
+    pub fn validate_maturity(
+        &self,
+        maturity: &OffsetDateTime,
+        calendar: &JointCalendar,
+    ) -> Result<()> {
+        let expected = self.get_maturity(maturity.year(), maturity.month(), calendar)?;
+        if expected.date() != maturity.date() {
+            return Err(anyhow!(
+                "({}:{}) the maturity {} is not the last trading day ({}) of {:?} {} under the KRX rule {}",
+                file!(),
+                line!(),
+                maturity.date(),
+                expected.date(),
+                maturity.month(),
+                maturity.year(),
+                self.as_str(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::calendar::Calendar;
+    use crate::time::calendars::southkorea::{SouthKorea, SouthKoreaType};
+    use time::macros::{date, datetime};
+
+    #[test]
+    fn test_krx_expiry() -> Result<()> {
+        let calendar = JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+            SouthKoreaType::Krx,
+        ))])?;
+
+        assert_eq!(
+            nth_weekday_of_month(2024, Month::March, Weekday::Thursday, 2)?,
+            date!(2024 - 03 - 14)
+        );
+        assert!(nth_weekday_of_month(2024, Month::February, Weekday::Friday, 5).is_err());
+
+        let rule = KrxExpiryRule::EquityDerivative;
+        assert_eq!(
+            rule.get_maturity(2024, Month::March, &calendar)?,
+            datetime!(2024-03-14 15:20:00 +09:00)
+        );
+        assert_eq!(
+            rule.get_settlement_date(2024, Month::March, &calendar)?,
+            datetime!(2024-03-15 15:20:00 +09:00)
+        );
+        // 2021-02-11 is the lunar new year's day
+        assert_eq!(
+            rule.get_maturity(2021, Month::February, &calendar)?.date(),
+            date!(2021 - 02 - 10)
+        );
+
+        let rule = KrxExpiryRule::Ktbf;
+        assert_eq!(
+            rule.get_maturity(2024, Month::March, &calendar)?,
+            datetime!(2024-03-19 11:30:00 +09:00)
+        );
+
+        // USD futures of June 2024 settle on the third Wednesday
+        let rule = KrxExpiryRule::CurrencyFutures;
+        assert_eq!(
+            rule.get_settlement_date(2024, Month::June, &calendar)?.date(),
+            date!(2024 - 06 - 19)
+        );
+
+        let rule = KrxExpiryRule::EquityDerivative;
+        rule.validate_maturity(&datetime!(2024-06-13 15:45:00 +09:00), &calendar)?;
+        assert!(rule
+            .validate_maturity(&datetime!(2024-06-14 15:45:00 +09:00), &calendar)
+            .is_err());
+        Ok(())
+    }
+}
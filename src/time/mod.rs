@@ -2,11 +2,22 @@ pub mod calendar;
 pub mod calendar_trait;
 pub mod constants;
 pub mod conventions;
+pub mod datetime_serde;
 pub mod jointcalendar;
+pub mod korean_lunar;
+pub mod krx_expiry;
 pub mod calendars {
+    pub mod china;
+    pub mod custom;
+    pub mod hongkong;
     pub mod nullcalendar;
+    pub mod singapore;
     pub mod southkorea;
+    pub mod target;
     pub mod unitedstates;
 }
 pub mod holiday;
-pub mod period;
\ No newline at end of file
+pub mod holiday_updates;
+pub mod period;
+pub mod settlement;
+pub mod time_cache;
\ No newline at end of file
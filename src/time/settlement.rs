@@ -0,0 +1,137 @@
+use crate::currency::{Currency, FxCode};
+use crate::time::calendar_trait::CalendarTrait;
+use crate::time::jointcalendar::JointCalendar;
+//
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// The markets whose trades settle after a lag of business days from the trade date
+/// * Fx: T+2 spot, but T+1 for USD/CAD
+/// * KrxStock: T+2 for the stocks and ETFs listed in KRX
+/// * KrxKtb: T+1 for the KTBs traded in the KRX KTB market
+/// * KrOtcBond: T+1 for the OTC bonds in Korea (T+0 to T+30 are allowed by agreement)
+/// * UsTreasury: T+1
+/// * UsStock: T+1 (since 2024-05-28)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SettlementMarket {
+    Fx(FxCode),
+    KrxStock,
+    KrxKtb,
+    KrOtcBond,
+    UsTreasury,
+    UsStock,
+}
+
+impl SettlementMarket {
+    pub fn default_lag(&self) -> i64 {
+        match self {
+            SettlementMarket::Fx(fx_code) => fx_spot_lag(fx_code),
+            SettlementMarket::KrxStock => 2,
+            SettlementMarket::KrxKtb
+            | SettlementMarket::KrOtcBond
+            | SettlementMarket::UsTreasury
+            | SettlementMarket::UsStock => 1,
+        }
+    }
+}
+
+/// the spot lag of the currency pair in business days
+pub fn fx_spot_lag(fx_code: &FxCode) -> i64 {
+    match (fx_code.get_currency1(), fx_code.get_currency2()) {
+        (Currency::USD, Currency::CAD) | (Currency::CAD, Currency::USD) => 1,
+        _ => 2,
+    }
+}
+
+/// The settlement (value) date is lag business days after the trade date in the calendar.
+/// The trade date is moved to the following business day first, e.g., a trade on Saturday is settled as on Monday.
+/// For FX spot, the calendar is usually the joint calendar of the two currencies.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SettlementConvention {
+    lag: i64,
+    calendar: JointCalendar,
+}
+
+impl SettlementConvention {
+    pub fn new(lag: i64, calendar: JointCalendar) -> Result<SettlementConvention> {
+        if lag < 0 {
+            return Err(anyhow!(
+                "({}:{}) settlement lag ({}) must be non-negative",
+                file!(),
+                line!(),
+                lag
+            ));
+        }
+        Ok(SettlementConvention { lag, calendar })
+    }
+
+    pub fn new_from_market(
+        market: SettlementMarket,
+        calendar: JointCalendar,
+    ) -> SettlementConvention {
+        SettlementConvention {
+            lag: market.default_lag(),
+            calendar,
+        }
+    }
+
+    pub fn get_lag(&self) -> i64 {
+        self.lag
+    }
+
+    pub fn get_calendar(&self) -> &JointCalendar {
+        &self.calendar
+    }
+
+    pub fn get_settlement_date(&self, trade_date: &OffsetDateTime) -> OffsetDateTime {
+        let trade_date = self.calendar.adjust_following(trade_date);
+        self.calendar.add_business_days(&trade_date, self.lag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::calendar::Calendar;
+    use crate::time::calendars::southkorea::{SouthKorea, SouthKoreaType};
+    use crate::time::calendars::unitedstates::{UnitedStates, UnitedStatesType};
+    use time::macros::datetime;
+
+    #[test]
+    fn test_settlement_date() -> Result<()> {
+        let kr = Calendar::SouthKorea(SouthKorea::new(SouthKoreaType::Settlement));
+        let us = Calendar::UnitedStates(UnitedStates::new(UnitedStatesType::Settlement));
+
+        // USDKRW traded on 2024-07-02 (Tue) is settled on 07-05 (Fri) skipping 07-04, the independence day in the US
+        let usdkrw = SettlementConvention::new_from_market(
+            SettlementMarket::Fx(FxCode::new(Currency::USD, Currency::KRW)),
+            JointCalendar::new(vec![kr.clone(), us.clone()])?,
+        );
+        assert_eq!(usdkrw.get_lag(), 2);
+        assert_eq!(
+            usdkrw.get_settlement_date(&datetime!(2024-07-02 16:00:00 +09:00)),
+            datetime!(2024-07-05 16:00:00 +09:00)
+        );
+        assert_eq!(
+            SettlementMarket::Fx(FxCode::new(Currency::USD, Currency::CAD)).default_lag(),
+            1
+        );
+
+        // KTB traded on Friday is settled on Monday, and a trade on Saturday on Tuesday
+        let ktb = SettlementConvention::new_from_market(
+            SettlementMarket::KrxKtb,
+            JointCalendar::new(vec![kr])?,
+        );
+        assert_eq!(
+            ktb.get_settlement_date(&datetime!(2024-03-08 10:00:00 +09:00)),
+            datetime!(2024-03-11 10:00:00 +09:00)
+        );
+        assert_eq!(
+            ktb.get_settlement_date(&datetime!(2024-03-09 10:00:00 +09:00)),
+            datetime!(2024-03-12 10:00:00 +09:00)
+        );
+        assert!(SettlementConvention::new(-1, JointCalendar::default()).is_err());
+        Ok(())
+    }
+}
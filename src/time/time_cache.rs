@@ -0,0 +1,193 @@
+use crate::definitions::Time;
+use crate::time::conventions::DayCountConvention;
+//
+use anyhow::Result;
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use time::{OffsetDateTime, UtcOffset};
+
+/// the cache of a thread is cleared when it has this number of times
+const TIME_CACHE_CAPACITY: usize = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TimeKind {
+    TimeDifference,
+    YearFraction(DayCountConvention),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeCacheStatistics {
+    pub hits: u64,
+    pub misses: u64,
+    pub len: usize,
+}
+
+#[derive(Default)]
+struct TimeCache {
+    // OffsetDateTime compares the instants, but the times depend on the local dates,
+    // so the offsets are in the key
+    times: FxHashMap<
+        (
+            OffsetDateTime,
+            UtcOffset,
+            OffsetDateTime,
+            UtcOffset,
+            TimeKind,
+        ),
+        Time,
+    >,
+    hits: u64,
+    misses: u64,
+}
+
+// The cache is per thread so that the engines pricing in parallel do not contend for a lock.
+// The times only depend on the dates and the day count convention, not on the calendar.
+thread_local! {
+    static TIME_CACHE: RefCell<TimeCache> = RefCell::new(TimeCache::default());
+}
+
+fn get_or_insert_with<F>(
+    start_date: &OffsetDateTime,
+    end_date: &OffsetDateTime,
+    kind: TimeKind,
+    calculate: F,
+) -> Result<Time>
+where
+    F: FnOnce() -> Result<Time>,
+{
+    let key = (
+        *start_date,
+        start_date.offset(),
+        *end_date,
+        end_date.offset(),
+        kind,
+    );
+    let cached = TIME_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let time = cache.times.get(&key).copied();
+        if time.is_some() {
+            cache.hits += 1;
+        }
+        time
+    });
+    if let Some(time) = cached {
+        return Ok(time);
+    }
+
+    // calculated out of the borrow, and the errors are not cached
+    let time = calculate()?;
+    TIME_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.times.len() >= TIME_CACHE_CAPACITY {
+            cache.times.clear();
+        }
+        cache.times.insert(key, time);
+        cache.misses += 1;
+    });
+    Ok(time)
+}
+
+/// CalendarTrait::get_time_difference looked up in the cache of the thread
+pub fn get_cached_time_difference<F>(
+    start_date: &OffsetDateTime,
+    end_date: &OffsetDateTime,
+    calculate: F,
+) -> Time
+where
+    F: FnOnce() -> Time,
+{
+    get_or_insert_with(start_date, end_date, TimeKind::TimeDifference, || {
+        Ok(calculate())
+    })
+    .expect("time difference does not fail")
+}
+
+/// CalendarTrait::year_fraction looked up in the cache of the thread
+pub fn get_cached_year_fraction<F>(
+    start_date: &OffsetDateTime,
+    end_date: &OffsetDateTime,
+    day_count: &DayCountConvention,
+    calculate: F,
+) -> Result<Time>
+where
+    F: FnOnce() -> Result<Time>,
+{
+    get_or_insert_with(
+        start_date,
+        end_date,
+        TimeKind::YearFraction(*day_count),
+        calculate,
+    )
+}
+
+/// statistics of the cache of the current thread
+pub fn get_time_cache_statistics() -> TimeCacheStatistics {
+    TIME_CACHE.with(|cache| {
+        let cache = cache.borrow();
+        TimeCacheStatistics {
+            hits: cache.hits,
+            misses: cache.misses,
+            len: cache.times.len(),
+        }
+    })
+}
+
+/// clears the cache of the current thread and its statistics
+pub fn clear_time_cache() {
+    TIME_CACHE.with(|cache| {
+        *cache.borrow_mut() = TimeCache::default();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::calendar_trait::CalendarTrait;
+    use crate::time::calendars::nullcalendar::NullCalendar;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_time_cache() -> Result<()> {
+        clear_time_cache();
+        let calendar = NullCalendar::default();
+        let start = datetime!(2024-03-13 16:30:00 +09:00);
+        let end = datetime!(2025-06-13 16:30:00 +09:00);
+
+        let time = calendar.get_time_difference(&start, &end);
+        assert_eq!(calendar.get_time_difference(&start, &end), time);
+        let fraction = calendar.year_fraction(&start, &end, &DayCountConvention::Actual365Fixed)?;
+        assert_eq!(
+            calendar.year_fraction(&start, &end, &DayCountConvention::Actual365Fixed)?,
+            fraction
+        );
+        assert_ne!(
+            calendar.year_fraction(&start, &end, &DayCountConvention::Actual360)?,
+            fraction
+        );
+        assert!(calendar
+            .year_fraction(&end, &start, &DayCountConvention::Actual365Fixed)
+            .is_err());
+
+        let statistics = get_time_cache_statistics();
+        assert_eq!(statistics.hits, 2);
+        assert_eq!(statistics.misses, 3);
+        assert_eq!(statistics.len, 3);
+
+        // the same instant in another offset is another local date
+        let utc_end = datetime!(2024-12-31 20:00:00 +00:00);
+        let kst_end = datetime!(2025-01-01 05:00:00 +09:00);
+        assert_eq!(utc_end, kst_end);
+        assert_eq!(
+            calendar.get_time_difference(&start, &kst_end),
+            calendar.get_uncached_time_difference(&start, &kst_end)
+        );
+        assert_eq!(
+            calendar.get_time_difference(&start, &utc_end),
+            calendar.get_uncached_time_difference(&start, &utc_end)
+        );
+
+        clear_time_cache();
+        assert_eq!(get_time_cache_statistics(), TimeCacheStatistics::default());
+        Ok(())
+    }
+}
@@ -1,6 +1,12 @@
 use crate::definitions::Integer;
+use anyhow::{anyhow, Result};
 use ndarray::Array1;
-use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{Date, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+pub mod market_data_loader;
+pub mod portfolio_loader;
 
 pub fn min_offsetdatetime(d1: &OffsetDateTime, d2: &OffsetDateTime) -> OffsetDateTime {
     if d1 < d2 {
@@ -59,6 +65,23 @@ pub fn format_duration(secs: f64) -> String {
     }
 }
 
+/// Parses a datetime in files, e.g., "2024-03-13T16:30:00+09:00" (RFC 3339), "2024-03-13 16:30:00" or "2024-03-13".
+/// The offset is given to the datetimes without offset, and the dates are at the midnight.
+pub fn parse_offsetdatetime(s: &str, offset: UtcOffset) -> Result<OffsetDateTime> {
+    let s = s.trim();
+    if let Ok(dt) = OffsetDateTime::parse(s, &Rfc3339) {
+        return Ok(dt);
+    }
+    let datetime_format = format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+    if let Ok(dt) = PrimitiveDateTime::parse(s, datetime_format) {
+        return Ok(dt.assume_offset(offset));
+    }
+    if let Ok(date) = Date::parse(s, format_description!("[year]-[month]-[day]")) {
+        return Ok(date.midnight().assume_offset(offset));
+    }
+    Err(anyhow!("({}:{}) invalid datetime: {}", file!(), line!(), s))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,12 +108,31 @@ mod tests {
         assert_eq!(type_name(&s), "MockStruct");
     }
 
+    #[test]
+    fn test_parse_offsetdatetime() -> Result<()> {
+        let offset = UtcOffset::from_hms(9, 0, 0)?;
+        assert_eq!(
+            parse_offsetdatetime("2024-03-13T16:30:00+09:00", UtcOffset::UTC)?,
+            datetime!(2024-03-13 16:30:00 +09:00)
+        );
+        assert_eq!(
+            parse_offsetdatetime("2024-03-13 16:30:00", offset)?,
+            datetime!(2024-03-13 16:30:00 +09:00)
+        );
+        assert_eq!(
+            parse_offsetdatetime(" 2024-03-13", offset)?,
+            datetime!(2024-03-13 00:00:00 +09:00)
+        );
+        assert!(parse_offsetdatetime("20240313", offset).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_is_ndarray_sorted() {
         let arr = array![1, 2, 3, 4, 5];
-        assert_eq!(is_ndarray_sorted(&arr), true);
+        assert!(is_ndarray_sorted(&arr));
         let arr = array![1, 2, 3, 4, 3];
-        assert_eq!(is_ndarray_sorted(&arr), false);
+        assert!(!is_ndarray_sorted(&arr));
     }
 
     #[test]
@@ -0,0 +1,965 @@
+//! Loads the market data (ValueData, VectorData, SurfaceData and DailyValueData) from csv files,
+//! or from parquet files with the feature "parquet".
+//!
+//! A file has a data point per row, and the rows of the same id (code, venue) make a data.
+//! The columns may be in any order. A parquet file has the same columns
+//! in strings, numbers, dates (Date32) or timestamps.
+//!
+//! # Values (`load_values`, `load_fx_values`)
+//! Stock prices, fx rates, constant volatilities, etc.
+//!
+//! | column | required | description |
+//! |---|---|---|
+//! | code, venue | yes | data id, e.g., "KOSPI2", "KRX". The code of fx rates is the fx code, e.g., "USDKRW" |
+//! | currency | yes | e.g., KRW |
+//! | value | yes | |
+//! | bid, ask | | both or none |
+//! | name | | the code if empty |
+//! | market_datetime | | the market datetime of the loader if empty |
+//!
+//! # Curves (`load_curves`)
+//! Zero curves, spread curves, dividends, etc. A row is a point of the curve,
+//! and the points are sorted by their dates (or times) in the curve.
+//!
+//! | column | required | description |
+//! |---|---|---|
+//! | code, venue, currency, name, market_datetime | | as in the values (code, venue and currency are required) |
+//! | date or time | yes | the date of the point, or the time in years from the market datetime |
+//! | value | yes | |
+//!
+//! The points of a curve are all given by date or all by time,
+//! and the curves given by date need the market datetime.
+//!
+//! # Surfaces (`load_surfaces`)
+//! Implied volatility surfaces. A row is a volatility of a date and a strike,
+//! and the rows of a surface must fill the grid of its dates and strikes.
+//!
+//! | column | required | description |
+//! |---|---|---|
+//! | code, venue, currency, name, market_datetime | | as in the values (code, venue and currency are required) |
+//! | date, strike, value | yes | |
+//! | spot | | |
+//!
+//! # Daily values (`load_daily_values`)
+//! Past closes and fixings. The close time, the utc offset and the calendar are given by the loader
+//! (15:40:00, +09:00 and KRX by default as in `DailyValueData::default()`).
+//!
+//! | column | required | description |
+//! |---|---|---|
+//! | code, venue, name | | as in the values (code and venue are required) |
+//! | date, value | yes | |
+//!
+//! The datetimes without offset are in the utc offset of the loader, and the rows are numbered
+//! by their lines in csv files (the first data point is on the row 2) and from 1 in parquet files.
+//! A data with an invalid row is not loaded.
+//!
+//! # Example
+//! ```
+//! use rustmetrics::util::market_data_loader::MarketDataLoader;
+//! use static_id::static_id::StaticId;
+//! use time::macros::datetime;
+//!
+//! let file = std::env::temp_dir().join(format!("rustmetrics_doc_curves_{}.csv", std::process::id()));
+//! std::fs::write(
+//!     &file,
+//!     "code,venue,currency,date,value\n\
+//!     KRWGOV,KAP,KRW,2025-03-13,0.030\n\
+//!     KRWGOV,KAP,KRW,2024-09-13,0.032\n",
+//! )
+//! .unwrap();
+//!
+//! let loaded = MarketDataLoader::new()
+//!     .with_market_datetime(datetime!(2024-03-13 16:30:00 +09:00))
+//!     .load_curves(&file)
+//!     .unwrap();
+//! std::fs::remove_file(&file).unwrap();
+//!
+//! let curves = loaded.into_data().unwrap();
+//! let curve = &curves[&StaticId::from_str("KRWGOV", "KAP")];
+//! assert_eq!(curve.get_value_clone().to_vec(), vec![0.032, 0.030]);
+//! ```
+use crate::currency::{Currency, FxCode};
+use crate::data::{
+    daily_value_data::DailyValueData, surface_data::SurfaceData, value_data::ValueData,
+    vector_data::VectorData,
+};
+use crate::definitions::{Real, Time};
+use crate::time::calendar::Calendar;
+use crate::util::parse_offsetdatetime;
+use crate::util::portfolio_loader::{CsvRow, LoadError};
+//
+use anyhow::{anyhow, Result};
+use ndarray::{Array1, Array2};
+use rustc_hash::FxHashMap;
+use serde_json::Value;
+use static_id::static_id::StaticId;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use time::{OffsetDateTime, UtcOffset};
+
+/// The data read without errors, keyed by their ids (fx codes for the fx rates), and the errors
+#[derive(Debug, Clone)]
+pub struct LoadedMarketData<K, T> {
+    data: FxHashMap<K, T>,
+    errors: Vec<LoadError>,
+}
+
+impl<K, T> LoadedMarketData<K, T> {
+    pub fn get_data(&self) -> &FxHashMap<K, T> {
+        &self.data
+    }
+
+    pub fn get_errors(&self) -> &Vec<LoadError> {
+        &self.errors
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// the data if there is no error, otherwise an error listing all the errors
+    pub fn into_data(self) -> Result<FxHashMap<K, T>> {
+        if self.errors.is_empty() {
+            return Ok(self.data);
+        }
+        let messages: Vec<String> = self.errors.iter().map(|e| e.to_string()).collect();
+        Err(anyhow!(
+            "({}:{}) {} errors in loading the market data\n{}",
+            file!(),
+            line!(),
+            self.errors.len(),
+            messages.join("\n")
+        ))
+    }
+}
+
+// the rows of a data
+struct Group<K, P> {
+    key: K,
+    id: StaticId,
+    row: Option<usize>,
+    name: Option<String>,
+    currency: Option<Currency>,
+    market_datetime: Option<OffsetDateTime>,
+    points: Vec<P>,
+    failed: bool,
+}
+
+// value and (bid, ask)
+type ValuePoint = (Real, Option<(Real, Real)>);
+// date, strike, value and spot
+type SurfacePoint = (OffsetDateTime, Real, Real, Option<Real>);
+// the header and the rows with their row numbers
+type Records = (Vec<String>, Vec<(Option<usize>, csv::StringRecord)>);
+
+enum CurvePoint {
+    Date(OffsetDateTime, Real),
+    Time(Time, Real),
+}
+
+#[derive(Debug, Clone)]
+pub struct MarketDataLoader {
+    utc_offset: UtcOffset,
+    market_datetime: Option<OffsetDateTime>,
+    close_time: time::Time,
+    calendar: Calendar,
+}
+
+impl Default for MarketDataLoader {
+    fn default() -> MarketDataLoader {
+        let daily_value_data = DailyValueData::default();
+        MarketDataLoader {
+            utc_offset: daily_value_data.utc_offset,
+            market_datetime: None,
+            close_time: daily_value_data.close_time,
+            calendar: daily_value_data.calendar,
+        }
+    }
+}
+
+impl MarketDataLoader {
+    pub fn new() -> MarketDataLoader {
+        MarketDataLoader::default()
+    }
+
+    /// offset of the datetimes without offset in the files, and of the daily values
+    pub fn with_utc_offset(mut self, utc_offset: UtcOffset) -> MarketDataLoader {
+        self.utc_offset = utc_offset;
+        self
+    }
+
+    /// market datetime of the rows without market_datetime
+    pub fn with_market_datetime(mut self, market_datetime: OffsetDateTime) -> MarketDataLoader {
+        self.market_datetime = Some(market_datetime);
+        self
+    }
+
+    /// close time of the daily values
+    pub fn with_close_time(mut self, close_time: time::Time) -> MarketDataLoader {
+        self.close_time = close_time;
+        self
+    }
+
+    /// calendar of the daily values
+    pub fn with_calendar(mut self, calendar: Calendar) -> MarketDataLoader {
+        self.calendar = calendar;
+        self
+    }
+
+    pub fn load_values<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<LoadedMarketData<StaticId, ValueData>> {
+        self.load(
+            path.as_ref(),
+            &["value"],
+            |_, id| Ok(id),
+            MarketDataLoader::value_point,
+            |group| self.build_value(group),
+        )
+    }
+
+    /// Loads the fx rates keyed by the fx codes in the code column, e.g., "USDKRW"
+    pub fn load_fx_values<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<LoadedMarketData<FxCode, ValueData>> {
+        self.load(
+            path.as_ref(),
+            &["value"],
+            |row, _| {
+                let code = row.required("code")?;
+                serde_json::from_value::<FxCode>(Value::String(code.to_string()))
+                    .map_err(|_| anyhow!("({}:{}) invalid fx code: {}", file!(), line!(), code))
+            },
+            MarketDataLoader::value_point,
+            |group| self.build_value(group),
+        )
+    }
+
+    pub fn load_curves<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<LoadedMarketData<StaticId, VectorData>> {
+        self.load(
+            path.as_ref(),
+            &["value"],
+            |_, id| Ok(id),
+            |loader, row| {
+                let value = row.parse_required::<Real>("value")?;
+                match (loader.parse_date(row, "date")?, row.parse::<Time>("time")?) {
+                    (Some(date), None) => Ok(CurvePoint::Date(date, value)),
+                    (None, Some(time)) => Ok(CurvePoint::Time(time, value)),
+                    _ => Err(anyhow!(
+                        "({}:{}) either date or time is required",
+                        file!(),
+                        line!()
+                    )),
+                }
+            },
+            MarketDataLoader::build_curve,
+        )
+    }
+
+    pub fn load_surfaces<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<LoadedMarketData<StaticId, SurfaceData>> {
+        self.load(
+            path.as_ref(),
+            &["date", "strike", "value"],
+            |_, id| Ok(id),
+            |loader, row| {
+                let date = loader
+                    .parse_date(row, "date")?
+                    .ok_or_else(|| anyhow!("({}:{}) date is required", file!(), line!()))?;
+                Ok((
+                    date,
+                    row.parse_required::<Real>("strike")?,
+                    row.parse_required::<Real>("value")?,
+                    row.parse::<Real>("spot")?,
+                ))
+            },
+            MarketDataLoader::build_surface,
+        )
+    }
+
+    pub fn load_daily_values<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<LoadedMarketData<StaticId, DailyValueData>> {
+        self.load(
+            path.as_ref(),
+            &["date", "value"],
+            |_, id| Ok(id),
+            |loader, row| {
+                let date = loader
+                    .parse_date(row, "date")?
+                    .ok_or_else(|| anyhow!("({}:{}) date is required", file!(), line!()))?;
+                Ok((date.date(), row.parse_required::<Real>("value")?))
+            },
+            |group| {
+                let mut value = FxHashMap::default();
+                for (date, v) in group.points.iter() {
+                    if value.insert(*date, *v).is_some() {
+                        return Err(anyhow!(
+                            "({}:{}) {} is given more than once in {}",
+                            file!(),
+                            line!(),
+                            date,
+                            group.id
+                        ));
+                    }
+                }
+                Ok(DailyValueData::new(
+                    value,
+                    self.close_time,
+                    self.utc_offset,
+                    self.calendar.clone(),
+                    group
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| group.id.code_str().to_string()),
+                    group.id,
+                ))
+            },
+        )
+    }
+
+    fn parse_date(&self, row: &CsvRow, column: &str) -> Result<Option<OffsetDateTime>> {
+        row.get(column)
+            .map(|s| parse_offsetdatetime(s, self.utc_offset))
+            .transpose()
+    }
+
+    fn value_point(_loader: &MarketDataLoader, row: &CsvRow) -> Result<ValuePoint> {
+        let value = row.parse_required::<Real>("value")?;
+        match (row.parse::<Real>("bid")?, row.parse::<Real>("ask")?) {
+            (Some(bid), Some(ask)) => Ok((value, Some((bid, ask)))),
+            (None, None) => Ok((value, None)),
+            _ => Err(anyhow!(
+                "({}:{}) bid and ask must be given together",
+                file!(),
+                line!()
+            )),
+        }
+    }
+
+    fn build_value<K>(&self, group: &Group<K, ValuePoint>) -> Result<ValueData> {
+        if group.points.len() > 1 {
+            return Err(anyhow!(
+                "({}:{}) {} is given in {} rows",
+                file!(),
+                line!(),
+                group.id,
+                group.points.len()
+            ));
+        }
+        let (value, bid_ask) = group.points[0];
+        let data = ValueData::new(
+            value,
+            group.market_datetime,
+            MarketDataLoader::currency(group)?,
+            MarketDataLoader::name(group),
+            group.id,
+        )?;
+        match bid_ask {
+            Some((bid, ask)) => data.with_bid_ask(bid, ask),
+            None => Ok(data),
+        }
+    }
+
+    fn build_curve<K>(group: &Group<K, CurvePoint>) -> Result<VectorData> {
+        let mut dates: Vec<(OffsetDateTime, Real)> = Vec::new();
+        let mut times: Vec<(Time, Real)> = Vec::new();
+        for point in group.points.iter() {
+            match point {
+                CurvePoint::Date(date, value) => dates.push((*date, *value)),
+                CurvePoint::Time(time, value) => times.push((*time, *value)),
+            }
+        }
+        if !dates.is_empty() && !times.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) the points of {} are given by both dates and times",
+                file!(),
+                line!(),
+                group.id
+            ));
+        }
+        let currency = MarketDataLoader::currency(group)?;
+        let name = MarketDataLoader::name(group);
+        if !dates.is_empty() {
+            dates.sort_by_key(|(date, _)| *date);
+            if dates.windows(2).any(|w| w[0].0 == w[1].0) {
+                return Err(anyhow!(
+                    "({}:{}) a date is given more than once in {}",
+                    file!(),
+                    line!(),
+                    group.id
+                ));
+            }
+            VectorData::new(
+                dates.iter().map(|(_, value)| *value).collect(),
+                Some(dates.iter().map(|(date, _)| *date).collect()),
+                None,
+                group.market_datetime,
+                currency,
+                name,
+                group.id,
+            )
+        } else {
+            times.sort_by(|a, b| a.0.total_cmp(&b.0));
+            if times.windows(2).any(|w| w[0].0 == w[1].0) {
+                return Err(anyhow!(
+                    "({}:{}) a time is given more than once in {}",
+                    file!(),
+                    line!(),
+                    group.id
+                ));
+            }
+            VectorData::new(
+                times.iter().map(|(_, value)| *value).collect(),
+                None,
+                Some(times.iter().map(|(time, _)| *time).collect()),
+                group.market_datetime,
+                currency,
+                name,
+                group.id,
+            )
+        }
+    }
+
+    fn build_surface<K>(group: &Group<K, SurfacePoint>) -> Result<SurfaceData> {
+        let mut dates: Vec<OffsetDateTime> = group.points.iter().map(|p| p.0).collect();
+        dates.sort();
+        dates.dedup();
+        let mut strikes: Vec<Real> = group.points.iter().map(|p| p.1).collect();
+        strikes.sort_by(|a, b| a.total_cmp(b));
+        strikes.dedup();
+
+        let mut value = Array2::<Real>::from_elem((dates.len(), strikes.len()), Real::NAN);
+        let mut spot: Option<Real> = None;
+        for (date, strike, v, point_spot) in group.points.iter() {
+            let i = dates.binary_search(date).unwrap();
+            let j = strikes.binary_search_by(|s| s.total_cmp(strike)).unwrap();
+            if !value[[i, j]].is_nan() {
+                return Err(anyhow!(
+                    "({}:{}) ({}, {}) is given more than once in {}",
+                    file!(),
+                    line!(),
+                    date,
+                    strike,
+                    group.id
+                ));
+            }
+            value[[i, j]] = *v;
+            spot = spot.or(*point_spot);
+        }
+        if value.iter().any(|v| v.is_nan()) {
+            return Err(anyhow!(
+                "({}:{}) {} has {} points, which do not fill the grid of {} dates and {} strikes",
+                file!(),
+                line!(),
+                group.id,
+                group.points.len(),
+                dates.len(),
+                strikes.len()
+            ));
+        }
+        Ok(SurfaceData::new(
+            spot,
+            value,
+            dates,
+            Array1::from(strikes),
+            group.market_datetime,
+            MarketDataLoader::currency(group)?,
+            MarketDataLoader::name(group),
+            group.id,
+        ))
+    }
+
+    fn currency<K, P>(group: &Group<K, P>) -> Result<Currency> {
+        group.currency.ok_or_else(|| {
+            anyhow!(
+                "({}:{}) currency is required for {}",
+                file!(),
+                line!(),
+                group.id
+            )
+        })
+    }
+
+    fn name<K, P>(group: &Group<K, P>) -> String {
+        group
+            .name
+            .clone()
+            .unwrap_or_else(|| group.id.code_str().to_string())
+    }
+
+    /// reads the rows of the file, groups them by their keys, and builds the data of the groups
+    fn load<K, P, T>(
+        &self,
+        file: &Path,
+        required_columns: &[&str],
+        key: impl Fn(&CsvRow, StaticId) -> Result<K>,
+        point: impl Fn(&MarketDataLoader, &CsvRow) -> Result<P>,
+        build: impl Fn(&Group<K, P>) -> Result<T>,
+    ) -> Result<LoadedMarketData<K, T>>
+    where
+        K: Hash + Eq + Copy,
+    {
+        let mut errors: Vec<LoadError> = Vec::new();
+        let (header, records) = MarketDataLoader::read_records(file, &mut errors)?;
+        let columns: FxHashMap<String, usize> = header
+            .iter()
+            .enumerate()
+            .map(|(i, column)| (column.to_ascii_lowercase(), i))
+            .collect();
+        for column in ["code", "venue"].iter().chain(required_columns) {
+            if !columns.contains_key(*column) {
+                return Err(anyhow!(
+                    "({}:{}) the column {} is missing in {}",
+                    file!(),
+                    line!(),
+                    column,
+                    file.display()
+                ));
+            }
+        }
+
+        let mut groups: Vec<Group<K, P>> = Vec::new();
+        let mut indices: FxHashMap<K, usize> = FxHashMap::default();
+        for (row, record) in records.iter() {
+            let csv_row = CsvRow {
+                columns: &columns,
+                record,
+            };
+            let res = (|| -> Result<()> {
+                let id = StaticId::from_str(csv_row.required("code")?, csv_row.required("venue")?);
+                let key = key(&csv_row, id)?;
+                let index = *indices.entry(key).or_insert_with(|| {
+                    groups.push(Group {
+                        key,
+                        id,
+                        row: *row,
+                        name: None,
+                        currency: None,
+                        market_datetime: self.market_datetime,
+                        points: Vec::new(),
+                        failed: false,
+                    });
+                    groups.len() - 1
+                });
+                let group = &mut groups[index];
+                let point = (|| -> Result<P> {
+                    if let Some(currency) = csv_row.parse::<Currency>("currency")? {
+                        match group.currency {
+                            Some(c) if c != currency => {
+                                return Err(anyhow!(
+                                "({}:{}) currency {} is different from {} in the other rows of {}",
+                                file!(),
+                                line!(),
+                                currency,
+                                c,
+                                id
+                            ))
+                            }
+                            _ => group.currency = Some(currency),
+                        }
+                    }
+                    if let Some(market_datetime) = self.parse_date(&csv_row, "market_datetime")? {
+                        group.market_datetime = Some(market_datetime);
+                    }
+                    if group.name.is_none() {
+                        group.name = csv_row.get("name").map(|name| name.to_string());
+                    }
+                    point(self, &csv_row)
+                })();
+                match point {
+                    Ok(point) => {
+                        group.points.push(point);
+                        Ok(())
+                    }
+                    Err(e) => {
+                        group.failed = true;
+                        Err(e)
+                    }
+                }
+            })();
+            if let Err(e) = res {
+                errors.push(LoadError {
+                    file: file.to_path_buf(),
+                    row: *row,
+                    message: e.to_string(),
+                });
+            }
+        }
+
+        let mut data = FxHashMap::default();
+        for group in groups.iter().filter(|group| !group.failed) {
+            match build(group) {
+                Ok(value) => {
+                    data.insert(group.key, value);
+                }
+                Err(e) => errors.push(LoadError {
+                    file: file.to_path_buf(),
+                    row: group.row,
+                    message: e.to_string(),
+                }),
+            }
+        }
+        Ok(LoadedMarketData { data, errors })
+    }
+
+    fn read_records(file: &Path, errors: &mut Vec<LoadError>) -> Result<Records> {
+        let extension = file
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("csv") => MarketDataLoader::read_csv(file, errors),
+            #[cfg(feature = "parquet")]
+            Some("parquet") => MarketDataLoader::read_parquet(file),
+            _ => Err(anyhow!(
+                "({}:{}) {} is not a csv file (or parquet with the feature parquet)",
+                file!(),
+                line!(),
+                file.display()
+            )),
+        }
+    }
+
+    fn read_csv(file: &Path, errors: &mut Vec<LoadError>) -> Result<Records> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_path(file)?;
+        let header: Vec<String> = reader.headers()?.iter().map(|c| c.to_string()).collect();
+        let mut records = Vec::new();
+        for record in reader.records() {
+            match record {
+                Ok(record) => records.push((record.position().map(|p| p.line() as usize), record)),
+                Err(e) => errors.push(LoadError {
+                    file: PathBuf::from(file),
+                    row: e.position().map(|p| p.line() as usize),
+                    message: e.to_string(),
+                }),
+            }
+        }
+        Ok((header, records))
+    }
+
+    #[cfg(feature = "parquet")]
+    fn read_parquet(file: &Path) -> Result<Records> {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(file)?)?;
+        let header: Vec<String> = builder
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect();
+        let mut records = Vec::new();
+        for batch in builder.build()? {
+            let batch = batch?;
+            let columns = batch
+                .columns()
+                .iter()
+                .zip(header.iter())
+                .map(|(array, name)| parquet_values::to_strings(array.as_ref(), name))
+                .collect::<Result<Vec<Vec<String>>>>()?;
+            for i in 0..batch.num_rows() {
+                let record: csv::StringRecord = columns.iter().map(|c| c[i].as_str()).collect();
+                records.push((Some(records.len() + 1), record));
+            }
+        }
+        Ok((header, records))
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_values {
+    use anyhow::{anyhow, Result};
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{
+        Date32Type, Float32Type, Float64Type, Int32Type, Int64Type, TimestampMicrosecondType,
+        TimestampMillisecondType, TimestampNanosecondType, TimestampSecondType,
+    };
+    use arrow_array::Array;
+    use arrow_schema::{DataType, TimeUnit};
+    use time::format_description::well_known::Rfc3339;
+    use time::macros::format_description;
+    use time::{Date, OffsetDateTime};
+
+    // julian day of 1970-01-01
+    const UNIX_EPOCH_JULIAN_DAY: i32 = 2_440_588;
+
+    /// the values of a column in the strings read by CsvRow, and "" for the nulls.
+    /// The timestamps with a time zone are in RFC 3339 of UTC,
+    /// and the timestamps without a time zone are in the offset of the loader.
+    pub(super) fn to_strings(array: &dyn Array, name: &str) -> Result<Vec<String>> {
+        let strings = |f: &dyn Fn(usize) -> Result<String>| -> Result<Vec<String>> {
+            (0..array.len())
+                .map(|i| {
+                    if array.is_null(i) {
+                        Ok(String::new())
+                    } else {
+                        f(i)
+                    }
+                })
+                .collect()
+        };
+        match array.data_type() {
+            DataType::Utf8 => strings(&|i| Ok(array.as_string::<i32>().value(i).to_string())),
+            DataType::LargeUtf8 => strings(&|i| Ok(array.as_string::<i64>().value(i).to_string())),
+            DataType::Float64 => {
+                strings(&|i| Ok(array.as_primitive::<Float64Type>().value(i).to_string()))
+            }
+            DataType::Float32 => {
+                strings(&|i| Ok(array.as_primitive::<Float32Type>().value(i).to_string()))
+            }
+            DataType::Int64 => {
+                strings(&|i| Ok(array.as_primitive::<Int64Type>().value(i).to_string()))
+            }
+            DataType::Int32 => {
+                strings(&|i| Ok(array.as_primitive::<Int32Type>().value(i).to_string()))
+            }
+            DataType::Date32 => strings(&|i| {
+                let days = array.as_primitive::<Date32Type>().value(i);
+                Ok(Date::from_julian_day(UNIX_EPOCH_JULIAN_DAY + days)?.to_string())
+            }),
+            DataType::Timestamp(unit, timezone) => strings(&|i| {
+                let (value, nanos_per_unit) = match unit {
+                    TimeUnit::Second => (
+                        array.as_primitive::<TimestampSecondType>().value(i),
+                        1_000_000_000,
+                    ),
+                    TimeUnit::Millisecond => (
+                        array.as_primitive::<TimestampMillisecondType>().value(i),
+                        1_000_000,
+                    ),
+                    TimeUnit::Microsecond => (
+                        array.as_primitive::<TimestampMicrosecondType>().value(i),
+                        1_000,
+                    ),
+                    TimeUnit::Nanosecond => {
+                        (array.as_primitive::<TimestampNanosecondType>().value(i), 1)
+                    }
+                };
+                let datetime =
+                    OffsetDateTime::from_unix_timestamp_nanos(value as i128 * nanos_per_unit)?;
+                match timezone {
+                    Some(_) => Ok(datetime.format(&Rfc3339)?),
+                    None => Ok(datetime.format(format_description!(
+                        "[year]-[month]-[day] [hour]:[minute]:[second]"
+                    ))?),
+                }
+            }),
+            data_type => Err(anyhow!(
+                "({}:{}) the column {} has an unsupported type {}",
+                file!(),
+                line!(),
+                name,
+                data_type
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn write_file(name: &str, contents: &str) -> Result<PathBuf> {
+        let file = std::env::temp_dir().join(format!(
+            "rustmetrics_market_data_{}_{}",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&file, contents)?;
+        Ok(file)
+    }
+
+    #[test]
+    fn test_load_values() -> Result<()> {
+        let file = write_file(
+            "values.csv",
+            "code,venue,currency,value,bid,ask,market_datetime\n\
+            KOSPI2,KRX,KRW,350.0,349.9,350.1,2024-03-13 15:40:00\n\
+            005930,KRX,KRW,72000,,,\n\
+            000660,KRX,KRW,,,,\n\
+            USDKRW,KAP,KRW,1300.0,1299.0,1301.0,\n\
+            000270,KRX,KRW,90000,89900,,\n",
+        )?;
+        let market_datetime = datetime!(2024-03-13 16:30:00 +09:00);
+        let loader = MarketDataLoader::new().with_market_datetime(market_datetime);
+        let loaded = loader.load_values(&file)?;
+        let fx = loader.load_fx_values(&file)?;
+        std::fs::remove_file(&file)?;
+
+        let values = loaded.get_data();
+        assert_eq!(values.len(), 3);
+        let kospi2 = &values[&StaticId::from_str("KOSPI2", "KRX")];
+        assert_eq!(kospi2.get_value(), 350.0);
+        assert_eq!(kospi2.get_bid(), Some(349.9));
+        assert_eq!(
+            kospi2.get_market_datetime(),
+            &Some(datetime!(2024-03-13 15:40:00 +09:00))
+        );
+        let samsung = &values[&StaticId::from_str("005930", "KRX")];
+        assert_eq!(samsung.get_market_datetime(), &Some(market_datetime));
+        assert_eq!(samsung.get_name(), "005930");
+
+        let errors: Vec<Option<usize>> = loaded.get_errors().iter().map(|e| e.row).collect();
+        assert_eq!(errors, vec![Some(4), Some(6)]);
+        assert!(loaded.get_errors()[0].message.contains("value is required"));
+        assert!(loaded.get_errors()[1].message.contains("bid"));
+
+        // the stock codes are not fx codes
+        assert_eq!(fx.get_errors().len(), 4);
+        let usdkrw = &fx.get_data()[&FxCode::new(Currency::USD, Currency::KRW)];
+        assert_eq!(usdkrw.get_mid(), 1300.0);
+        assert!(fx.into_data().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_curves() -> Result<()> {
+        let file = write_file(
+            "curves.csv",
+            "code,venue,currency,date,time,value\n\
+            KRWGOV,KAP,KRW,2025-03-13,,0.030\n\
+            KRWGOV,KAP,KRW,2024-09-13,,0.032\n\
+            KRWIRS,KAP,KRW,,5.0,0.034\n\
+            KRWIRS,KAP,KRW,,1.0,0.033\n\
+            KRWCD,KAP,KRW,,1.0,0.035\n\
+            KRWCD,KAP,USD,,2.0,0.035\n",
+        )?;
+        let market_datetime = datetime!(2024-03-13 16:30:00 +09:00);
+        let loaded = MarketDataLoader::new()
+            .with_market_datetime(market_datetime)
+            .load_curves(&file)?;
+        std::fs::remove_file(&file)?;
+
+        let curves = loaded.get_data();
+        assert_eq!(curves.len(), 2);
+        let gov = &curves[&StaticId::from_str("KRWGOV", "KAP")];
+        assert_eq!(gov.get_value_clone().to_vec(), vec![0.032, 0.030]);
+        assert_eq!(
+            gov.get_dates_clone().unwrap(),
+            vec![
+                datetime!(2024-09-13 00:00:00 +09:00),
+                datetime!(2025-03-13 00:00:00 +09:00)
+            ]
+        );
+        let irs = &curves[&StaticId::from_str("KRWIRS", "KAP")];
+        assert_eq!(irs.get_times_clone().to_vec(), vec![1.0, 5.0]);
+        assert_eq!(irs.get_value_clone().to_vec(), vec![0.033, 0.034]);
+
+        assert_eq!(loaded.get_errors().len(), 1);
+        assert_eq!(loaded.get_errors()[0].row, Some(7));
+        assert!(loaded.get_errors()[0].message.contains("currency"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_surfaces_and_daily_values() -> Result<()> {
+        let surface_file = write_file(
+            "surfaces.csv",
+            "code,venue,currency,date,strike,value,spot\n\
+            KOSPI2,KRX,KRW,2024-06-13,400,0.18,350.0\n\
+            KOSPI2,KRX,KRW,2024-06-13,300,0.22,\n\
+            KOSPI2,KRX,KRW,2024-09-12,300,0.21,\n\
+            KOSPI2,KRX,KRW,2024-09-12,400,0.17,\n\
+            SPX,CBOE,USD,2024-06-13,5000,0.15,\n\
+            SPX,CBOE,USD,2024-09-12,5100,0.14,\n",
+        )?;
+        let daily_file = write_file(
+            "daily.csv",
+            "code,venue,date,value\n\
+            KOSPI2,KRX,2024-03-12,348.0\n\
+            KOSPI2,KRX,2024-03-13,350.0\n",
+        )?;
+        let loader = MarketDataLoader::new();
+        let surfaces = loader.load_surfaces(&surface_file)?;
+        let daily_values = loader.load_daily_values(&daily_file)?;
+        std::fs::remove_file(&surface_file)?;
+        std::fs::remove_file(&daily_file)?;
+
+        let kospi2 = &surfaces.get_data()[&StaticId::from_str("KOSPI2", "KRX")];
+        assert_eq!(kospi2.get_spot(), Some(350.0));
+        assert_eq!(kospi2.get_strike().to_vec(), vec![300.0, 400.0]);
+        assert_eq!(
+            kospi2.get_value(),
+            &ndarray::array![[0.22, 0.18], [0.21, 0.17]]
+        );
+        // the points of SPX do not fill the grid
+        assert_eq!(surfaces.get_errors().len(), 1);
+        assert_eq!(surfaces.get_errors()[0].row, Some(6));
+
+        let daily = &daily_values.into_data()?[&StaticId::from_str("KOSPI2", "KRX")];
+        assert_eq!(daily.get_ordered_data_by_date().1, vec![348.0, 350.0]);
+        assert_eq!(daily.calendar, DailyValueData::default().calendar);
+        Ok(())
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_load_parquet() -> Result<()> {
+        use arrow_array::{ArrayRef, Date32Array, Float64Array, RecordBatch, StringArray};
+        use std::sync::Arc;
+
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "code",
+                Arc::new(StringArray::from(vec!["KRWGOV", "KRWGOV"])) as ArrayRef,
+            ),
+            (
+                "venue",
+                Arc::new(StringArray::from(vec!["KAP", "KAP"])) as ArrayRef,
+            ),
+            (
+                "currency",
+                Arc::new(StringArray::from(vec!["KRW", "KRW"])) as ArrayRef,
+            ),
+            // 2024-09-13 and 2025-03-13
+            (
+                "date",
+                Arc::new(Date32Array::from(vec![19_979, 20_160])) as ArrayRef,
+            ),
+            (
+                "value",
+                Arc::new(Float64Array::from(vec![0.032, 0.030])) as ArrayRef,
+            ),
+        ])?;
+        let file = std::env::temp_dir().join(format!(
+            "rustmetrics_market_data_{}_curves.parquet",
+            std::process::id()
+        ));
+        let mut writer = parquet::arrow::ArrowWriter::try_new(
+            std::fs::File::create(&file)?,
+            batch.schema(),
+            None,
+        )?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        let loaded = MarketDataLoader::new()
+            .with_market_datetime(datetime!(2024-03-13 16:30:00 +09:00))
+            .load_curves(&file)?;
+        std::fs::remove_file(&file)?;
+
+        let curve = &loaded.into_data()?[&StaticId::from_str("KRWGOV", "KAP")];
+        assert_eq!(curve.get_value_clone().to_vec(), vec![0.032, 0.030]);
+        assert_eq!(
+            curve.get_dates_clone().unwrap(),
+            vec![
+                datetime!(2024-09-13 00:00:00 +09:00),
+                datetime!(2025-03-13 00:00:00 +09:00)
+            ]
+        );
+        Ok(())
+    }
+}
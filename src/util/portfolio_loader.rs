@@ -0,0 +1,635 @@
+//! Loads the instruments of a portfolio from a file or the files in a directory.
+//!
+//! # JSON (OTC products)
+//! A json file has an instrument, a list of instruments, or their schema-versioned json
+//! (`utils::versioned::to_versioned_json` of `Instrument` or `Instruments`).
+//! The elements of a list are numbered from 1 in the errors.
+//!
+//! # CSV (listed products)
+//! A csv file has a header and an instrument per row. The columns may be in any order.
+//!
+//! | column | required | description |
+//! |---|---|---|
+//! | type | yes | Futures, FxFutures, VanillaOption, Stock or Cash |
+//! | code, venue | yes | instrument id, e.g., "KOSPI2 Fut", "KRX" |
+//! | name | | |
+//! | currency | yes | e.g., KRW |
+//! | unit_notional | | 1.0 if empty |
+//! | issue_date, maturity | maturity except Stock and Cash | e.g., 2024-06-13, 2024-06-13 15:45:00 or RFC 3339 |
+//! | settlement_date | | the maturity if empty |
+//! | accounting_level | | L1, L2 (default) or L3 |
+//! | underlying_code, underlying_venue | Futures, VanillaOption | the venue of the instrument if underlying_venue is empty. Stock: the stock itself if empty |
+//! | underlying_currency | FxFutures | the currency of the instrument if empty (Futures, VanillaOption) |
+//! | average_trade_price | Futures, FxFutures | |
+//! | strike, option_type | VanillaOption | option_type: Call or Put |
+//! | exercise_type | | European (default), American or Bermudan |
+//! | daily_settlement | | NotSettled (default) or Settled |
+//!
+//! The datetimes without offset are in the utc offset of the loader (+09:00 by default),
+//! and the rows are numbered by their lines in the file, i.e., the first instrument is on the row 2.
+//!
+//! The instruments are validated after they are read:
+//! the ids must be unique over all the files, the maturity must be after the issue date,
+//! and the unit notionals and the strikes must be positive.
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::enums::{OptionDailySettlementType, OptionExerciseType, OptionType};
+use crate::instrument::{Instrument, InstrumentTrait, Instruments};
+use crate::instruments::{
+    cash::Cash, futures::Futures, fx_futures::FxFutures, inst_info::InstInfo, stock::Stock,
+    vanilla_option::VanillaOption,
+};
+use crate::util::parse_offsetdatetime;
+use crate::utils::versioned::from_versioned_value;
+use crate::{AccountingLevel, InstType};
+//
+use anyhow::{anyhow, Context, Result};
+use rustc_hash::FxHashMap;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use static_id::static_id::StaticId;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use time::{OffsetDateTime, UtcOffset};
+
+/// An error of a row (a line of csv or an element of a json list) or of a whole file (row is None)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadError {
+    pub file: PathBuf,
+    pub row: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.row {
+            Some(row) => write!(f, "{} (row {}): {}", self.file.display(), row, self.message),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+/// The instruments read without errors and the errors of the other rows
+#[derive(Debug, Clone, Default)]
+pub struct LoadedPortfolio {
+    instruments: Instruments,
+    errors: Vec<LoadError>,
+}
+
+impl LoadedPortfolio {
+    pub fn get_instruments(&self) -> &Instruments {
+        &self.instruments
+    }
+
+    pub fn get_errors(&self) -> &Vec<LoadError> {
+        &self.errors
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// the instruments if there is no error, otherwise an error listing all the errors
+    pub fn into_instruments(self) -> Result<Instruments> {
+        if self.errors.is_empty() {
+            return Ok(self.instruments);
+        }
+        let messages: Vec<String> = self.errors.iter().map(|e| e.to_string()).collect();
+        Err(anyhow!(
+            "({}:{}) {} errors in loading the portfolio\n{}",
+            file!(),
+            line!(),
+            self.errors.len(),
+            messages.join("\n")
+        ))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PortfolioLoader {
+    utc_offset: UtcOffset,
+}
+
+impl Default for PortfolioLoader {
+    fn default() -> PortfolioLoader {
+        PortfolioLoader {
+            utc_offset: UtcOffset::from_hms(9, 0, 0).unwrap(),
+        }
+    }
+}
+
+/// A row of a csv file, or a row of a parquet file converted to strings (util::market_data_loader)
+pub(crate) struct CsvRow<'a> {
+    pub(crate) columns: &'a FxHashMap<String, usize>,
+    pub(crate) record: &'a csv::StringRecord,
+}
+
+impl CsvRow<'_> {
+    pub(crate) fn get(&self, column: &str) -> Option<&str> {
+        self.columns
+            .get(column)
+            .and_then(|i| self.record.get(*i))
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+    }
+
+    pub(crate) fn required(&self, column: &str) -> Result<&str> {
+        self.get(column)
+            .ok_or_else(|| anyhow!("({}:{}) {} is required", file!(), line!(), column))
+    }
+
+    pub(crate) fn parse<T: FromStr>(&self, column: &str) -> Result<Option<T>> {
+        match self.get(column) {
+            None => Ok(None),
+            Some(s) => s
+                .parse::<T>()
+                .map(Some)
+                .map_err(|_| anyhow!("({}:{}) invalid {}: {}", file!(), line!(), column, s)),
+        }
+    }
+
+    pub(crate) fn parse_required<T: FromStr>(&self, column: &str) -> Result<T> {
+        self.parse::<T>(column)?
+            .ok_or_else(|| anyhow!("({}:{}) {} is required", file!(), line!(), column))
+    }
+
+    // the enums are parsed by their serde names, e.g., "Call"
+    pub(crate) fn parse_enum<T: DeserializeOwned>(&self, column: &str) -> Result<Option<T>> {
+        match self.get(column) {
+            None => Ok(None),
+            Some(s) => serde_json::from_value(Value::String(s.to_string()))
+                .map(Some)
+                .map_err(|_| anyhow!("({}:{}) invalid {}: {}", file!(), line!(), column, s)),
+        }
+    }
+}
+
+impl PortfolioLoader {
+    pub fn new() -> PortfolioLoader {
+        PortfolioLoader::default()
+    }
+
+    /// offset of the datetimes without offset in the csv files
+    pub fn with_utc_offset(mut self, utc_offset: UtcOffset) -> PortfolioLoader {
+        self.utc_offset = utc_offset;
+        self
+    }
+
+    /// Loads a csv or json file, or the csv and json files in a directory in the order of their names.
+    /// The errors of the rows and the files are collected in LoadedPortfolio,
+    /// and an error is returned only if the path can not be read.
+    pub fn load<P: AsRef<Path>>(&self, path: P) -> Result<LoadedPortfolio> {
+        let path = path.as_ref();
+        let files: Vec<PathBuf> = if path.is_dir() {
+            let mut files = Vec::new();
+            let entries = std::fs::read_dir(path).with_context(|| {
+                anyhow!(
+                    "({}:{}) failed to read {}",
+                    file!(),
+                    line!(),
+                    path.display()
+                )
+            })?;
+            for entry in entries {
+                let file = entry?.path();
+                if file.is_file() && PortfolioLoader::is_portfolio_file(&file) {
+                    files.push(file);
+                }
+            }
+            files.sort();
+            files
+        } else if path.is_file() {
+            vec![path.to_path_buf()]
+        } else {
+            return Err(anyhow!(
+                "({}:{}) {} does not exist",
+                file!(),
+                line!(),
+                path.display()
+            ));
+        };
+
+        let mut rows: Vec<(PathBuf, Option<usize>, Instrument)> = Vec::new();
+        let mut errors: Vec<LoadError> = Vec::new();
+        for file in files.iter() {
+            let res = match PortfolioLoader::extension(file).as_deref() {
+                Some("csv") => self.read_csv(file, &mut rows, &mut errors),
+                Some("json") => PortfolioLoader::read_json(file, &mut rows, &mut errors),
+                _ => Err(anyhow!(
+                    "({}:{}) the file must be csv or json",
+                    file!(),
+                    line!()
+                )),
+            };
+            if let Err(e) = res {
+                errors.push(LoadError {
+                    file: file.clone(),
+                    row: None,
+                    message: e.to_string(),
+                });
+            }
+        }
+
+        let mut first_rows: FxHashMap<StaticId, (PathBuf, Option<usize>)> = FxHashMap::default();
+        let mut instruments: Vec<Arc<Instrument>> = Vec::new();
+        for (file, row, instrument) in rows {
+            if let Err(e) = PortfolioLoader::validate(&instrument) {
+                errors.push(LoadError {
+                    file,
+                    row,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+            let id = instrument.get_id();
+            if let Some((first_file, first_row)) = first_rows.get(&id) {
+                let position = match first_row {
+                    Some(first_row) => format!("{} (row {})", first_file.display(), first_row),
+                    None => first_file.display().to_string(),
+                };
+                errors.push(LoadError {
+                    file,
+                    row,
+                    message: format!("{} is already given in {}", id, position),
+                });
+                continue;
+            }
+            first_rows.insert(id, (file, row));
+            instruments.push(Arc::new(instrument));
+        }
+
+        Ok(LoadedPortfolio {
+            instruments: Instruments::new(instruments),
+            errors,
+        })
+    }
+
+    fn extension(file: &Path) -> Option<String> {
+        file.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+    }
+
+    fn is_portfolio_file(file: &Path) -> bool {
+        matches!(
+            PortfolioLoader::extension(file).as_deref(),
+            Some("csv") | Some("json")
+        )
+    }
+
+    fn validate(instrument: &Instrument) -> Result<()> {
+        let info = instrument.get_inst_info();
+        if !(info.unit_notional > 0.0 && info.unit_notional.is_finite()) {
+            return Err(anyhow!(
+                "({}:{}) unit_notional of {} must be positive, but {} is given",
+                file!(),
+                line!(),
+                info.id,
+                info.unit_notional
+            ));
+        }
+        if let (Some(issue_date), Some(maturity)) = (info.issue_date, info.maturity) {
+            if maturity < issue_date {
+                return Err(anyhow!(
+                    "({}:{}) maturity {} of {} is before the issue date {}",
+                    file!(),
+                    line!(),
+                    maturity,
+                    info.id,
+                    issue_date
+                ));
+            }
+        }
+        if let Ok(strike) = instrument.get_strike() {
+            if !(strike > 0.0 && strike.is_finite()) {
+                return Err(anyhow!(
+                    "({}:{}) strike of {} must be positive, but {} is given",
+                    file!(),
+                    line!(),
+                    info.id,
+                    strike
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn read_json(
+        file: &Path,
+        rows: &mut Vec<(PathBuf, Option<usize>, Instrument)>,
+        errors: &mut Vec<LoadError>,
+    ) -> Result<()> {
+        let text = std::fs::read_to_string(file)?;
+        let value: Value = serde_json::from_str(&text)?;
+        let value = match value {
+            Value::Object(mut object)
+                if object.get("schema").and_then(|s| s.as_str()) == Some("Instruments") =>
+            {
+                // the list of the instruments has the schema version of the instruments
+                let version = object.get("schema_version").cloned();
+                let mut elements = Vec::new();
+                if let Some(Value::Array(data)) = object.remove("data") {
+                    for element in data {
+                        elements.push(serde_json::json!({
+                            "schema_version": version,
+                            "schema": "Instrument",
+                            "data": element,
+                        }));
+                    }
+                }
+                Value::Array(elements)
+            }
+            value => value,
+        };
+
+        match value {
+            Value::Array(elements) => {
+                for (i, element) in elements.into_iter().enumerate() {
+                    match from_versioned_value::<Instrument>(element) {
+                        Ok(instrument) => rows.push((file.to_path_buf(), Some(i + 1), instrument)),
+                        Err(e) => errors.push(LoadError {
+                            file: file.to_path_buf(),
+                            row: Some(i + 1),
+                            message: format!("{:#}", e),
+                        }),
+                    }
+                }
+            }
+            value => {
+                let instrument = from_versioned_value::<Instrument>(value)?;
+                rows.push((file.to_path_buf(), None, instrument));
+            }
+        }
+        Ok(())
+    }
+
+    fn read_csv(
+        &self,
+        file: &Path,
+        rows: &mut Vec<(PathBuf, Option<usize>, Instrument)>,
+        errors: &mut Vec<LoadError>,
+    ) -> Result<()> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_path(file)?;
+        let columns: FxHashMap<String, usize> = reader
+            .headers()?
+            .iter()
+            .enumerate()
+            .map(|(i, column)| (column.to_ascii_lowercase(), i))
+            .collect();
+        for column in ["type", "code", "venue", "currency"] {
+            if !columns.contains_key(column) {
+                return Err(anyhow!(
+                    "({}:{}) the column {} is missing",
+                    file!(),
+                    line!(),
+                    column
+                ));
+            }
+        }
+
+        for record in reader.records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    errors.push(LoadError {
+                        file: file.to_path_buf(),
+                        row: e.position().map(|p| p.line() as usize),
+                        message: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+            let row = record.position().map(|p| p.line() as usize);
+            let csv_row = CsvRow {
+                columns: &columns,
+                record: &record,
+            };
+            match self.parse_csv_row(&csv_row) {
+                Ok(instrument) => rows.push((file.to_path_buf(), row, instrument)),
+                Err(e) => errors.push(LoadError {
+                    file: file.to_path_buf(),
+                    row,
+                    message: e.to_string(),
+                }),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_date(&self, row: &CsvRow, column: &str) -> Result<Option<OffsetDateTime>> {
+        row.get(column)
+            .map(|s| parse_offsetdatetime(s, self.utc_offset))
+            .transpose()
+    }
+
+    fn parse_csv_row(&self, row: &CsvRow) -> Result<Instrument> {
+        let type_name = row.required("type")?;
+        let venue = row.required("venue")?;
+        let id = StaticId::from_str(row.required("code")?, venue);
+        let currency = row.parse_required::<Currency>("currency")?;
+        let inst_type = match type_name {
+            "Futures" => InstType::Futures,
+            "FxFutures" => InstType::FxFutures,
+            "VanillaOption" => InstType::VanillaOption,
+            "Stock" => InstType::Stock,
+            "Cash" => InstType::Cash,
+            _ => {
+                return Err(anyhow!(
+                    "({}:{}) {} is not a listed product type",
+                    file!(),
+                    line!(),
+                    type_name
+                ))
+            }
+        };
+        let maturity = self.parse_date(row, "maturity")?;
+        let inst_info = InstInfo {
+            id,
+            name: row.get("name").unwrap_or("").to_string(),
+            inst_type,
+            currency,
+            unit_notional: row.parse::<Real>("unit_notional")?.unwrap_or(1.0),
+            issue_date: self.parse_date(row, "issue_date")?,
+            maturity,
+            accounting_level: row
+                .parse_enum::<AccountingLevel>("accounting_level")?
+                .unwrap_or_default(),
+        };
+        let settlement_date = self.parse_date(row, "settlement_date")?;
+        let underlying_id = row
+            .get("underlying_code")
+            .map(|code| StaticId::from_str(code, row.get("underlying_venue").unwrap_or(venue)));
+        let underlying_currency = row.parse::<Currency>("underlying_currency")?;
+        if maturity.is_none() && !matches!(inst_type, InstType::Stock | InstType::Cash) {
+            return Err(anyhow!(
+                "({}:{}) maturity is required for {}",
+                file!(),
+                line!(),
+                type_name
+            ));
+        }
+
+        let instrument = match inst_type {
+            InstType::Futures => Instrument::Futures(Futures::new(
+                inst_info,
+                row.parse_required::<Real>("average_trade_price")?,
+                settlement_date,
+                underlying_currency.unwrap_or(currency),
+                underlying_id.ok_or_else(|| {
+                    anyhow!("({}:{}) underlying_code is required", file!(), line!())
+                })?,
+            )),
+            InstType::FxFutures => Instrument::FxFutures(FxFutures::new(
+                inst_info,
+                row.parse_required::<Real>("average_trade_price")?,
+                settlement_date,
+                underlying_currency.ok_or_else(|| {
+                    anyhow!("({}:{}) underlying_currency is required", file!(), line!())
+                })?,
+            )),
+            InstType::VanillaOption => Instrument::VanillaOption(VanillaOption::new(
+                inst_info,
+                row.parse_required::<Real>("strike")?,
+                settlement_date,
+                underlying_id.ok_or_else(|| {
+                    anyhow!("({}:{}) underlying_code is required", file!(), line!())
+                })?,
+                underlying_currency.unwrap_or(currency),
+                row.parse_enum::<OptionType>("option_type")?
+                    .ok_or_else(|| anyhow!("({}:{}) option_type is required", file!(), line!()))?,
+                row.parse_enum::<OptionExerciseType>("exercise_type")?
+                    .unwrap_or_default(),
+                row.parse_enum::<OptionDailySettlementType>("daily_settlement")?
+                    .unwrap_or_default(),
+            )),
+            InstType::Stock => {
+                Instrument::Stock(Stock::new(inst_info, underlying_id.unwrap_or(id), None))
+            }
+            _ => Instrument::Cash(Cash::new_from_inst_info(InstInfo {
+                accounting_level: row
+                    .parse_enum::<AccountingLevel>("accounting_level")?
+                    .unwrap_or(AccountingLevel::L1),
+                ..inst_info
+            })?),
+        };
+        Ok(instrument)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::versioned::to_versioned_json;
+    use time::macros::datetime;
+
+    const LISTED: &str = "\
+type,code,venue,name,currency,unit_notional,maturity,average_trade_price,underlying_code,strike,option_type
+Futures,KOSPI2 Fut,KRX,KOSPI2 Futures,KRW,250000,2024-06-13 15:45:00,350.0,KOSPI2,,
+VanillaOption,KOSPI2 Call,KRX,\"KOSPI2 Call, 400\",KRW,250000,2024-06-13,,KOSPI2,400,Call
+VanillaOption,KOSPI2 Put,KRX,,KRW,250000,2024-06-13,,KOSPI2,-1,Put
+Futures,KOSPI2 Fut2,KRX,,KRW,250000,,350.0,KOSPI2,,
+Swap,IRS,KRX,,KRW,1,2025-01-01,,,,
+Stock,005930,KRX,Samsung,KRW,,,,,,
+";
+
+    #[test]
+    fn test_portfolio_loader() -> Result<()> {
+        let directory =
+            std::env::temp_dir().join(format!("rustmetrics_portfolio_{}", std::process::id()));
+        std::fs::create_dir_all(&directory)?;
+        std::fs::write(directory.join("a_listed.csv"), LISTED)?;
+
+        let stock = Stock::new(
+            InstInfo {
+                id: StaticId::from_str("000660", "KRX"),
+                inst_type: InstType::Stock,
+                currency: Currency::KRW,
+                ..InstInfo::default()
+            },
+            StaticId::from_str("000660", "KRX"),
+            None,
+        );
+        let instruments = Instruments::new(vec![Arc::new(Instrument::Stock(stock))]);
+        std::fs::write(
+            directory.join("b_otc.json"),
+            to_versioned_json(&instruments)?,
+        )?;
+        // the same id as in the csv, and an invalid element
+        let futures = serde_json::to_value(Instrument::Futures(Futures::new(
+            InstInfo {
+                id: StaticId::from_str("KOSPI2 Fut", "KRX"),
+                inst_type: InstType::Futures,
+                currency: Currency::KRW,
+                maturity: Some(datetime!(2024-06-13 15:45:00 +09:00)),
+                ..InstInfo::default()
+            },
+            350.0,
+            None,
+            Currency::KRW,
+            StaticId::from_str("KOSPI2", "KRX"),
+        )))?;
+        std::fs::write(
+            directory.join("c_otc.json"),
+            serde_json::to_string(&vec![futures, serde_json::json!({"Swap": {}})])?,
+        )?;
+        std::fs::write(directory.join("readme.txt"), "not a portfolio")?;
+
+        let loaded = PortfolioLoader::new().load(&directory)?;
+        std::fs::remove_dir_all(&directory)?;
+
+        let ids: Vec<String> = loaded
+            .get_instruments()
+            .iter()
+            .map(|inst| inst.get_id().to_string())
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                StaticId::from_str("KOSPI2 Fut", "KRX").to_string(),
+                StaticId::from_str("KOSPI2 Call", "KRX").to_string(),
+                StaticId::from_str("005930", "KRX").to_string(),
+                StaticId::from_str("000660", "KRX").to_string(),
+            ]
+        );
+        let futures = &loaded.get_instruments()[0];
+        assert_eq!(
+            futures.get_maturity(),
+            Some(&datetime!(2024-06-13 15:45:00 +09:00))
+        );
+        assert_eq!(futures.get_unit_notional(), 250_000.0);
+        assert_eq!(loaded.get_instruments()[1].get_name(), "KOSPI2 Call, 400");
+
+        let errors: Vec<(String, Option<usize>)> = loaded
+            .get_errors()
+            .iter()
+            .map(|e| {
+                (
+                    e.file.file_name().unwrap().to_string_lossy().to_string(),
+                    e.row,
+                )
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("a_listed.csv".to_string(), Some(5)),
+                ("a_listed.csv".to_string(), Some(6)),
+                ("c_otc.json".to_string(), Some(2)),
+                ("a_listed.csv".to_string(), Some(4)),
+                ("c_otc.json".to_string(), Some(1)),
+            ]
+        );
+        assert!(loaded.get_errors()[0]
+            .message
+            .contains("maturity is required"));
+        assert!(loaded.get_errors()[3].message.contains("strike"));
+        assert!(loaded.get_errors()[4].message.contains("already given"));
+        assert!(loaded.into_instruments().is_err());
+        Ok(())
+    }
+}
@@ -37,6 +37,7 @@ pub fn distribute_value_on_vector(
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_range_loop, clippy::if_same_then_else)]
 mod tests {
     use super::*;
     use anyhow::Result;
@@ -32,7 +32,7 @@ pub mod tests {
     #[test]
     fn test_currencies_to_fx_codes() -> Result<()> {
         let currs = vec![Currency::USD, Currency::EUR, Currency::JPY, Currency::KRW];
-        let _must_include = vec![FxCode::new(Currency::KRW, Currency::KRW)];
+        let _must_include = [FxCode::new(Currency::KRW, Currency::KRW)];
         let result = currencies_to_fx_codes(currs, None);
         for res in &result {
             println!("{:?}  ({:?})", res, res.to_string());
@@ -4,4 +4,6 @@ pub mod find_index_ndarray;
 pub mod make_fx;
 pub mod number_format;
 pub mod string_arithmetic;
-pub mod memory_investigation;
\ No newline at end of file
+pub mod memory_investigation;
+pub mod versioned;
+pub mod platform;
@@ -0,0 +1,103 @@
+//! The parts which differ on wasm32 (wasm32-unknown-unknown), where there are no threads
+//! and no clock without javascript, so that the pricers, the curves and the calendars compile to wasm32,
+//! e.g., for pricing tools running in browsers.
+//!
+//! On wasm32, the instruments and the groups are calculated sequentially on the calling thread,
+//! the timers (CalculationStatistics and CostModel) are zero, the logs are dropped,
+//! and the defaults which use the current time are at the unix epoch.
+//!
+//! The wasm32 build is checked by
+//! `rustup target add wasm32-unknown-unknown && cargo check --lib --target wasm32-unknown-unknown`
+//! (see README.md for the static-id dependency on criterion, which does not compile to wasm32).
+use time::OffsetDateTime;
+
+/// unix time in nanoseconds for the timers. Zero on wasm32
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_unix_nano() -> u64 {
+    flashlog::get_unix_nano()
+}
+
+/// unix time in nanoseconds for the timers. Zero on wasm32
+#[cfg(target_arch = "wasm32")]
+pub fn get_unix_nano() -> u64 {
+    0
+}
+
+/// the current datetime for the default values. The unix epoch on wasm32
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_utc() -> OffsetDateTime {
+    OffsetDateTime::now_utc()
+}
+
+/// the current datetime for the default values. The unix epoch on wasm32
+#[cfg(target_arch = "wasm32")]
+pub fn now_utc() -> OffsetDateTime {
+    OffsetDateTime::UNIX_EPOCH
+}
+
+/// the number of threads in the rayon thread pool. One on wasm32
+#[cfg(not(target_arch = "wasm32"))]
+pub fn current_num_threads() -> usize {
+    rayon::current_num_threads()
+}
+
+/// the number of threads in the rayon thread pool. One on wasm32
+#[cfg(target_arch = "wasm32")]
+pub fn current_num_threads() -> usize {
+    1
+}
+
+/// runs the job on the rayon thread pool, or on the calling thread on wasm32
+#[cfg(feature = "async")]
+pub(crate) fn spawn<F>(job: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    rayon::spawn(job);
+    #[cfg(target_arch = "wasm32")]
+    job();
+}
+
+// the logs of flashlog, e.g., log_info!("Tag"; "format {}", arg) or log_warn!("Tag"; key = value).
+// On wasm32, the arguments are only borrowed so that they are used without flashlog
+macro_rules! log_debug {
+    ($($arg:tt)*) => {{
+        #[cfg(not(target_arch = "wasm32"))]
+        flashlog::flash_debug!($($arg)*);
+        #[cfg(target_arch = "wasm32")]
+        $crate::utils::platform::drop_log!($($arg)*);
+    }};
+}
+
+macro_rules! log_info {
+    ($($arg:tt)*) => {{
+        #[cfg(not(target_arch = "wasm32"))]
+        flashlog::flash_info!($($arg)*);
+        #[cfg(target_arch = "wasm32")]
+        $crate::utils::platform::drop_log!($($arg)*);
+    }};
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => {{
+        #[cfg(not(target_arch = "wasm32"))]
+        flashlog::flash_warn!($($arg)*);
+        #[cfg(target_arch = "wasm32")]
+        $crate::utils::platform::drop_log!($($arg)*);
+    }};
+}
+
+#[cfg(target_arch = "wasm32")]
+macro_rules! drop_log {
+    ($tag:expr; $($key:ident = $value:expr),+ $(,)?) => {
+        let _ = ($tag, $(&$value),+);
+    };
+    ($tag:expr; $format:expr $(, $arg:expr)* $(,)?) => {
+        let _ = ($tag, $format, $(&$arg),*);
+    };
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) use drop_log;
+pub(crate) use {log_debug, log_info, log_warn};
@@ -195,7 +195,7 @@ pub fn sub_period(datetime: &OffsetDateTime, duration: &str) -> OffsetDateTime {
                 let month_i32 = from_month_to_i32(new_datetime.month());
                 let year = new_datetime.year();
                 let new_month = from_i32_to_month((month_i32 - value as i32 + 120000000) % 12);
-                let new_year = year - 1 + (month_i32 - value as i32 + 12) / 12;
+                let new_year = year + (month_i32 - value as i32).div_euclid(12);
                 let eom_new = NullCalendar::default()
                     .last_day_of_month(new_year, new_month)
                     .day();
@@ -296,7 +296,7 @@ pub fn sub_period_date(date: &Date, duration: &str) -> Date {
                 let month_i32 = from_month_to_i32(new_date.month());
                 let year = new_date.year();
                 let new_month = from_i32_to_month((month_i32 - value + 120000000) % 12);
-                let new_year = year - 1 + (month_i32 - value + 12) / 12;
+                let new_year = year + (month_i32 - value).div_euclid(12);
                 let eom_new = NullCalendar::default()
                     .last_day_of_month(new_year, new_month)
                     .day();
@@ -324,7 +324,7 @@ mod tests {
     fn test_month_operation() {
         let mut month = Month::January;
         let month_i32 = from_month_to_i32(month);
-        assert_eq!(from_i32_to_month((month_i32 + 0) % 12), Month::January);
+        assert_eq!(from_i32_to_month(month_i32 % 12), Month::January);
         assert_eq!(from_i32_to_month((month_i32 + 1) % 12), Month::February);
         assert_eq!(from_i32_to_month((month_i32 + 2) % 12), Month::March);
         assert_eq!(from_i32_to_month((month_i32 + 3) % 12), Month::April);
@@ -352,7 +352,7 @@ mod tests {
 
         month = Month::June;
         let month_i32 = from_month_to_i32(month);
-        assert_eq!(from_i32_to_month((month_i32 + 0) % 12), Month::June);
+        assert_eq!(from_i32_to_month(month_i32 % 12), Month::June);
         assert_eq!(from_i32_to_month((month_i32 + 1) % 12), Month::July);
         assert_eq!(from_i32_to_month((month_i32 + 2) % 12), Month::August);
         assert_eq!(from_i32_to_month((month_i32 + 3) % 12), Month::September);
@@ -364,14 +364,14 @@ mod tests {
 
         month = Month::November;
         let month_i32 = from_month_to_i32(month);
-        assert_eq!(from_i32_to_month((month_i32 + 0) % 12), Month::November);
+        assert_eq!(from_i32_to_month(month_i32 % 12), Month::November);
         assert_eq!(from_i32_to_month((month_i32 + 1) % 12), Month::December);
         assert_eq!(from_i32_to_month((month_i32 + 2) % 12), Month::January);
         assert_eq!(from_i32_to_month((month_i32 + 3) % 12), Month::February);
 
         month = Month::December;
         let month_i32 = from_month_to_i32(month);
-        assert_eq!(from_i32_to_month((month_i32 + 0) % 12), Month::December);
+        assert_eq!(from_i32_to_month(month_i32 % 12), Month::December);
         assert_eq!(from_i32_to_month((month_i32 + 1) % 12), Month::January);
         assert_eq!(from_i32_to_month((month_i32 + 2) % 12), Month::February);
         assert_eq!(from_i32_to_month((month_i32 + 3) % 12), Month::March);
@@ -462,7 +462,9 @@ mod tests {
     fn test_add_month_over12() {
         let x = datetime!(2021-01-31 00:00:00 UTC);
         let y = sub_period(&x, "18M");
-        println!("{:?}", y);
+        assert_eq!(y, datetime!(2019-07-31 00:00:00 UTC));
+        let y = sub_period(&datetime!(2022-03-01 00:00:00 UTC), "18M");
+        assert_eq!(y, datetime!(2020-09-01 00:00:00 UTC));
     }
 
     #[test]
@@ -476,6 +478,6 @@ mod tests {
     fn test_sub_period_date() {
         let x = date!(2021 - 01 - 31);
         let y = sub_period_date(&x, "1Y18M");
-        assert_eq!(y, date!(2019 - 07 - 31));
+        assert_eq!(y, date!(2018 - 07 - 31));
     }
 }
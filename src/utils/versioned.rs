@@ -0,0 +1,291 @@
+use crate::data::{
+    daily_value_data::DailyValueData, fixing_store::FixingStore, surface_data::SurfaceData,
+    value_data::ValueData, vector_data::VectorData,
+};
+use crate::instrument::{Instrument, Instruments};
+use crate::instruments::{
+    bond::Bond, bond_futures::BondFutures, cash::Cash, futures::Futures, fx_futures::FxFutures,
+    ktbf::KTBF, plain_swap::PlainSwap, stock::Stock, vanilla_option::VanillaOption,
+};
+use crate::pricing_engines::{match_parameter::MatchParameter, pnl_explain::MarketDataSet};
+//
+use anyhow::{anyhow, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Types saved with their schema version, e.g., portfolios and market data kept in files,
+/// so that the files written by the older versions of the crate keep loading.
+///
+/// to_versioned_json writes {"schema_version": SCHEMA_VERSION, "schema": SCHEMA_NAME, "data": ...}.
+/// from_versioned_json reads
+/// - the files of the current version
+/// - the files of the older versions, upgraded by migrate one version at a time
+/// - the plain serde json without the schema version (version 0, written before the versioning)
+///
+/// A change of the serde layout which is not covered by #[serde(default)] bumps SCHEMA_VERSION,
+/// and migrate converts the data of the previous version.
+pub trait Versioned: Serialize + DeserializeOwned {
+    const SCHEMA_NAME: &'static str;
+    const SCHEMA_VERSION: u32 = 1;
+
+    /// converts the data of the version to the next version.
+    /// Version 0 has the same layout as version 1.
+    fn migrate(_version: u32, data: Value) -> Result<Value> {
+        Ok(data)
+    }
+}
+
+pub fn to_versioned_value<T: Versioned>(data: &T) -> Result<Value> {
+    let data = serde_json::to_value(data).with_context(|| {
+        anyhow!(
+            "({}:{}) failed to serialize {}",
+            file!(),
+            line!(),
+            T::SCHEMA_NAME
+        )
+    })?;
+    Ok(json!({
+        "schema_version": T::SCHEMA_VERSION,
+        "schema": T::SCHEMA_NAME,
+        "data": data,
+    }))
+}
+
+pub fn to_versioned_json<T: Versioned>(data: &T) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&to_versioned_value(data)?)?)
+}
+
+pub fn from_versioned_value<T: Versioned>(value: Value) -> Result<T> {
+    let (version, mut data) = match value {
+        Value::Object(mut object)
+            if object.contains_key("schema_version") && object.contains_key("data") =>
+        {
+            let version = object["schema_version"].as_u64().ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) invalid schema_version {} of {}",
+                    file!(),
+                    line!(),
+                    object["schema_version"],
+                    T::SCHEMA_NAME
+                )
+            })?;
+            if let Some(schema) = object.get("schema") {
+                if schema.as_str() != Some(T::SCHEMA_NAME) {
+                    return Err(anyhow!(
+                        "({}:{}) the schema {} is given for {}",
+                        file!(),
+                        line!(),
+                        schema,
+                        T::SCHEMA_NAME
+                    ));
+                }
+            }
+            (version, object.remove("data").unwrap())
+        }
+        value => (0, value),
+    };
+
+    if version > T::SCHEMA_VERSION as u64 {
+        return Err(anyhow!(
+            "({}:{}) schema version {} of {} is newer than the supported version {}",
+            file!(),
+            line!(),
+            version,
+            T::SCHEMA_NAME,
+            T::SCHEMA_VERSION
+        ));
+    }
+    for from_version in (version as u32)..T::SCHEMA_VERSION {
+        data = T::migrate(from_version, data).with_context(|| {
+            anyhow!(
+                "({}:{}) failed to migrate {} from schema version {}",
+                file!(),
+                line!(),
+                T::SCHEMA_NAME,
+                from_version
+            )
+        })?;
+    }
+    serde_json::from_value(data).with_context(|| {
+        anyhow!(
+            "({}:{}) failed to deserialize {} of schema version {}",
+            file!(),
+            line!(),
+            T::SCHEMA_NAME,
+            version
+        )
+    })
+}
+
+pub fn from_versioned_json<T: Versioned>(json: &str) -> Result<T> {
+    let value: Value = serde_json::from_str(json).with_context(|| {
+        anyhow!(
+            "({}:{}) invalid json of {}",
+            file!(),
+            line!(),
+            T::SCHEMA_NAME
+        )
+    })?;
+    from_versioned_value(value)
+}
+
+macro_rules! impl_versioned {
+    ($($t:ty => $name:expr),* $(,)?) => {
+        $(
+            impl Versioned for $t {
+                const SCHEMA_NAME: &'static str = $name;
+            }
+        )*
+    };
+}
+
+impl_versioned!(
+    Instrument => "Instrument",
+    Instruments => "Instruments",
+    Futures => "Futures",
+    Bond => "Bond",
+    BondFutures => "BondFutures",
+    KTBF => "KTBF",
+    PlainSwap => "PlainSwap",
+    FxFutures => "FxFutures",
+    VanillaOption => "VanillaOption",
+    Stock => "Stock",
+    Cash => "Cash",
+    MatchParameter => "MatchParameter",
+    ValueData => "ValueData",
+    VectorData => "VectorData",
+    SurfaceData => "SurfaceData",
+    DailyValueData => "DailyValueData",
+    FixingStore => "FixingStore",
+    MarketDataSet => "MarketDataSet",
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::enums::{CreditRating, IssuerType};
+    use crate::instrument::InstrumentTrait;
+    use crate::instruments::inst_info::InstInfo;
+    use crate::InstType;
+    use rustc_hash::FxHashMap;
+    use serde::Deserialize;
+    use static_id::static_id::StaticId;
+    use std::sync::Arc;
+    use time::macros::datetime;
+
+    fn stock() -> Stock {
+        Stock::new(
+            InstInfo {
+                id: StaticId::from_str("005930", "KRX"),
+                name: "Samsung Electronics".to_string(),
+                inst_type: InstType::Stock,
+                currency: Currency::KRW,
+                ..InstInfo::default()
+            },
+            StaticId::from_str("005930", "KRX"),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_versioned_instruments() -> Result<()> {
+        let instruments = Instruments::new(vec![Arc::new(Instrument::Stock(stock()))]);
+        let json = to_versioned_json(&instruments)?;
+        let value: Value = serde_json::from_str(&json)?;
+        assert_eq!(value["schema_version"], 1);
+        assert_eq!(value["schema"], "Instruments");
+
+        let loaded: Instruments = from_versioned_json(&json)?;
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].get_id(), StaticId::from_str("005930", "KRX"));
+
+        // plain json written before the versioning
+        let legacy = serde_json::to_string(&stock())?;
+        let loaded: Stock = from_versioned_json(&legacy)?;
+        assert_eq!(loaded, stock());
+        // a variant saved as another schema
+        let json = to_versioned_json(&stock())?;
+        assert!(from_versioned_json::<Instrument>(&json).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_versioned_match_parameter() -> Result<()> {
+        let mut bond_discount_curve_map = FxHashMap::default();
+        bond_discount_curve_map.insert(
+            (
+                StaticId::from_str("KR", "KRX"),
+                IssuerType::Government,
+                CreditRating::None,
+                Currency::KRW,
+            ),
+            StaticId::from_str("KRWGOV", "KAP"),
+        );
+        let match_parameter = MatchParameter::new(
+            FxHashMap::default(),
+            FxHashMap::default(),
+            bond_discount_curve_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+        );
+        let json = to_versioned_json(&match_parameter)?;
+        let loaded: MatchParameter = from_versioned_json(&json)?;
+        assert_eq!(
+            serde_json::to_value(&loaded)?,
+            serde_json::to_value(&match_parameter)?
+        );
+
+        // the empty maps on tuple keys were written as {} before the versioning
+        let mut legacy = serde_json::to_value(MatchParameter::default())?;
+        legacy["bond_discount_curve_map"] = json!({});
+        legacy["funding_spread_curve_map"] = json!({});
+        let loaded: MatchParameter = from_versioned_value(legacy)?;
+        assert_eq!(
+            serde_json::to_value(&loaded)?,
+            serde_json::to_value(MatchParameter::default())?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_schema_migration() -> Result<()> {
+        // version 2 renames "price" to "value"
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Quote {
+            value: f64,
+        }
+        impl Versioned for Quote {
+            const SCHEMA_NAME: &'static str = "Quote";
+            const SCHEMA_VERSION: u32 = 2;
+
+            fn migrate(version: u32, mut data: Value) -> Result<Value> {
+                if version == 1 {
+                    let price = data["price"].take();
+                    data = json!({ "value": price });
+                }
+                Ok(data)
+            }
+        }
+
+        let old = json!({"schema_version": 1, "schema": "Quote", "data": {"price": 1.5}});
+        assert_eq!(from_versioned_value::<Quote>(old)?, Quote { value: 1.5 });
+        let legacy = json!({"price": 2.5});
+        assert_eq!(from_versioned_value::<Quote>(legacy)?, Quote { value: 2.5 });
+        let newer = json!({"schema_version": 3, "data": {"value": 1.0}});
+        assert!(from_versioned_value::<Quote>(newer).is_err());
+
+        let data = ValueData::new(
+            1.0,
+            Some(datetime!(2024-03-13 16:30:00 +09:00)),
+            Currency::KRW,
+            "KOSPI2".to_string(),
+            StaticId::from_str("KOSPI2", "KRX"),
+        )?;
+        let loaded: ValueData = from_versioned_json(&to_versioned_json(&data)?)?;
+        assert_eq!(loaded, data);
+        Ok(())
+    }
+}
@@ -0,0 +1,377 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::pricing_engines::calculation_result::CalculationResult;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use statrs::distribution::{ChiSquared, ContinuousCDF};
+use time::OffsetDateTime;
+
+/// results of the instruments on an evaluation date, e.g., stored at the end of each day
+pub type ResultSnapshot = FxHashMap<StaticId, CalculationResult>;
+
+/// A day of the backtest: the VaR predicted at the previous date and the P&L realized on the date
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BacktestObservation {
+    date: OffsetDateTime,
+    predicted_var: Real,
+    realized_pnl: Real,
+}
+
+impl BacktestObservation {
+    pub fn get_date(&self) -> &OffsetDateTime {
+        &self.date
+    }
+
+    pub fn get_predicted_var(&self) -> Real {
+        self.predicted_var
+    }
+
+    pub fn get_realized_pnl(&self) -> Real {
+        self.realized_pnl
+    }
+
+    /// the realized loss exceeds the predicted VaR
+    pub fn is_exception(&self) -> bool {
+        -self.realized_pnl > self.predicted_var
+    }
+}
+
+/// Backtest of the daily VaR against the realized P&Ls.
+///
+/// - Kupiec (1995) proportion of failures: LR_uc = -2 ln[(1-p)^(n-x) p^x / (1-x/n)^(n-x) (x/n)^x] ~ χ²(1)
+/// - Christoffersen (1998) independence: LR_ind = -2 ln[L(π) / L(π_0, π_1)] ~ χ²(1)
+///   on the transitions of the exception indicator, where π_i is the exception rate after a day in the state i
+/// - conditional coverage: LR_cc = LR_uc + LR_ind ~ χ²(2)
+///
+/// p = 1 - confidence level, n is the number of observations and x is the number of exceptions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VarBacktest {
+    confidence_level: Real,
+    currency: Currency,
+    observations: Vec<BacktestObservation>,
+}
+
+impl VarBacktest {
+    /// dates, realized_pnls and predicted_vars are in the same order,
+    /// where the VaR is predicted at the previous date for the P&L on the date
+    pub fn from_pnls(
+        confidence_level: Real,
+        currency: Currency,
+        dates: &[OffsetDateTime],
+        realized_pnls: &[Real],
+        predicted_vars: &[Real],
+    ) -> Result<VarBacktest> {
+        if confidence_level <= 0.0 || confidence_level >= 1.0 {
+            return Err(anyhow!(
+                "({}:{}) confidence level must be in (0, 1), but {} is given",
+                file!(),
+                line!(),
+                confidence_level,
+            ));
+        }
+        if dates.len() != realized_pnls.len() || dates.len() != predicted_vars.len() {
+            return Err(anyhow!(
+                "({}:{}) {} dates, {} realized P&Ls and {} predicted VaRs are given",
+                file!(),
+                line!(),
+                dates.len(),
+                realized_pnls.len(),
+                predicted_vars.len(),
+            ));
+        }
+        if dates.is_empty() {
+            return Err(anyhow!("({}:{}) no observation is given", file!(), line!()));
+        }
+
+        let observations = dates
+            .iter()
+            .zip(realized_pnls.iter())
+            .zip(predicted_vars.iter())
+            .map(|((date, pnl), var)| BacktestObservation {
+                date: *date,
+                predicted_var: *var,
+                realized_pnl: *pnl,
+            })
+            .collect();
+        Ok(VarBacktest {
+            confidence_level,
+            currency,
+            observations,
+        })
+    }
+
+    /// The realized P&L between the consecutive snapshots is the value change plus the expected amounts
+    /// paid in between, summed over the instruments held at both dates (hypothetical P&L).
+    /// The results converted in the engine (reporting_result) are used if they exist,
+    /// and they must be in the currency.
+    /// predicted_vars[i] is the VaR at snapshots[i], so one less than the snapshots are given.
+    pub fn from_snapshots(
+        confidence_level: Real,
+        currency: Currency,
+        snapshots: &[ResultSnapshot],
+        predicted_vars: &[Real],
+    ) -> Result<VarBacktest> {
+        if snapshots.len() != predicted_vars.len() + 1 {
+            return Err(anyhow!(
+                "({}:{}) {} snapshots require {} predicted VaRs, but {} are given",
+                file!(),
+                line!(),
+                snapshots.len(),
+                snapshots.len().saturating_sub(1),
+                predicted_vars.len(),
+            ));
+        }
+        let mut dates = Vec::with_capacity(predicted_vars.len());
+        let mut realized_pnls = Vec::with_capacity(predicted_vars.len());
+        for pair in snapshots.windows(2) {
+            let base_dt = get_snapshot_date(&pair[0])?;
+            let target_dt = get_snapshot_date(&pair[1])?;
+            let mut pnl: Real = 0.0;
+            for (inst_id, base_result) in pair[0].iter() {
+                let Some(target_result) = pair[1].get(inst_id) else {
+                    continue;
+                };
+                let base = get_result_in_currency(base_result, currency)?;
+                let target = get_result_in_currency(target_result, currency)?;
+                let unit_notional = base
+                    .get_instrument_info()
+                    .map(|info| info.get_unit_notional())
+                    .unwrap_or(1.0);
+                let cash_sum: Real = match base.get_npv_result() {
+                    Some(npv_result) => npv_result
+                        .get_expected_coupon_amount()?
+                        .iter()
+                        .filter(|(date, _)| {
+                            base_dt.date() < date.date() && date.date() <= target_dt.date()
+                        })
+                        .map(|(_, cash)| *cash)
+                        .sum(),
+                    None => 0.0,
+                };
+                pnl += get_value(target, inst_id)? - get_value(base, inst_id)?
+                    + cash_sum * unit_notional;
+            }
+            dates.push(target_dt);
+            realized_pnls.push(pnl);
+        }
+        VarBacktest::from_pnls(
+            confidence_level,
+            currency,
+            &dates,
+            &realized_pnls,
+            predicted_vars,
+        )
+    }
+
+    pub fn get_confidence_level(&self) -> Real {
+        self.confidence_level
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn get_observations(&self) -> &Vec<BacktestObservation> {
+        &self.observations
+    }
+
+    pub fn get_observation_number(&self) -> usize {
+        self.observations.len()
+    }
+
+    pub fn get_exception_number(&self) -> usize {
+        self.observations
+            .iter()
+            .filter(|o| o.is_exception())
+            .count()
+    }
+
+    pub fn get_exception_rate(&self) -> Real {
+        self.get_exception_number() as Real / self.observations.len() as Real
+    }
+
+    /// Kupiec likelihood ratio of the unconditional coverage
+    pub fn get_kupiec_statistic(&self) -> Real {
+        let p = 1.0 - self.confidence_level as f64;
+        let n = self.observations.len() as f64;
+        let x = self.get_exception_number() as f64;
+        let null = log_likelihood(n - x, x, p);
+        let alternative = log_likelihood(n - x, x, x / n);
+        (-2.0 * (null - alternative)) as Real
+    }
+
+    pub fn get_kupiec_p_value(&self) -> Result<Real> {
+        get_chi_squared_p_value(self.get_kupiec_statistic(), 1.0)
+    }
+
+    /// Christoffersen likelihood ratio of the independence of the exceptions
+    pub fn get_christoffersen_statistic(&self) -> Real {
+        // transitions[i][j]: number of days in the state j after a day in the state i
+        let mut transitions = [[0.0_f64; 2]; 2];
+        for pair in self.observations.windows(2) {
+            let i = pair[0].is_exception() as usize;
+            let j = pair[1].is_exception() as usize;
+            transitions[i][j] += 1.0;
+        }
+        let [[n00, n01], [n10, n11]] = transitions;
+        let rate = |no_exception: f64, exception: f64| match no_exception + exception {
+            total if total > 0.0 => exception / total,
+            _ => 0.0,
+        };
+        let pi = rate(n00 + n10, n01 + n11);
+        let null = log_likelihood(n00 + n10, n01 + n11, pi);
+        let alternative =
+            log_likelihood(n00, n01, rate(n00, n01)) + log_likelihood(n10, n11, rate(n10, n11));
+        (-2.0 * (null - alternative)) as Real
+    }
+
+    pub fn get_christoffersen_p_value(&self) -> Result<Real> {
+        get_chi_squared_p_value(self.get_christoffersen_statistic(), 1.0)
+    }
+
+    /// Christoffersen likelihood ratio of the conditional coverage, LR_uc + LR_ind
+    pub fn get_conditional_coverage_statistic(&self) -> Real {
+        self.get_kupiec_statistic() + self.get_christoffersen_statistic()
+    }
+
+    pub fn get_conditional_coverage_p_value(&self) -> Result<Real> {
+        get_chi_squared_p_value(self.get_conditional_coverage_statistic(), 2.0)
+    }
+}
+
+/// ln[(1-p)^n0 p^n1] with 0 ln 0 = 0
+fn log_likelihood(n0: f64, n1: f64, p: f64) -> f64 {
+    let term = |n: f64, q: f64| if n > 0.0 { n * q.ln() } else { 0.0 };
+    term(n0, 1.0 - p) + term(n1, p)
+}
+
+fn get_chi_squared_p_value(statistic: Real, freedom: f64) -> Result<Real> {
+    let dist = ChiSquared::new(freedom)?;
+    Ok((1.0 - dist.cdf(statistic.max(0.0) as f64)) as Real)
+}
+
+fn get_snapshot_date(snapshot: &ResultSnapshot) -> Result<OffsetDateTime> {
+    let mut res: Option<OffsetDateTime> = None;
+    for (inst_id, result) in snapshot.iter() {
+        let date = result.get_evaluation_date().ok_or_else(|| {
+            anyhow!(
+                "({}:{}) evaluation date is not set for {}",
+                file!(),
+                line!(),
+                inst_id,
+            )
+        })?;
+        match res {
+            Some(dt) if dt != *date => {
+                return Err(anyhow!(
+                    "({}:{}) {} is evaluated on {}, but the snapshot is on {}",
+                    file!(),
+                    line!(),
+                    inst_id,
+                    date,
+                    dt,
+                ));
+            }
+            _ => res = Some(*date),
+        }
+    }
+    res.ok_or_else(|| anyhow!("({}:{}) empty snapshot is given", file!(), line!()))
+}
+
+fn get_result_in_currency(
+    result: &CalculationResult,
+    currency: Currency,
+) -> Result<&CalculationResult> {
+    let res = result.get_reporting_result().unwrap_or(result);
+    let res_currency = res
+        .get_representation_currency()
+        .or_else(|| res.get_instrument_info().map(|info| info.currency))
+        .unwrap_or_default();
+    if res_currency != currency {
+        return Err(anyhow!(
+            "({}:{}) the result is in {}, but the backtest is in {}",
+            file!(),
+            line!(),
+            res_currency,
+            currency,
+        ));
+    }
+    Ok(res)
+}
+
+fn get_value(result: &CalculationResult, inst_id: &StaticId) -> Result<Real> {
+    result
+        .get_value()
+        .ok_or_else(|| anyhow!("({}:{}) value is not set for {}", file!(), line!(), inst_id,))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing_engines::npv_result::NpvResult;
+    use crate::InstInfo;
+    use time::{macros::datetime, Duration};
+
+    #[test]
+    fn test_var_backtest() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        // 4 exceptions in 100 days at 99%, clustered on two consecutive days twice
+        let dates: Vec<OffsetDateTime> = (1..=100).map(|i| dt + Duration::days(i)).collect();
+        let pnls: Vec<Real> = (0..100)
+            .map(|i| match i {
+                10 | 11 | 50 | 51 => -20.0,
+                _ => 1.0,
+            })
+            .collect();
+        let vars = vec![10.0; 100];
+        let backtest = VarBacktest::from_pnls(0.99, Currency::KRW, &dates, &pnls, &vars)?;
+        assert_eq!(backtest.get_exception_number(), 4);
+        assert!((backtest.get_exception_rate() - 0.04).abs() < 1.0e-6);
+        // -2 ln[0.99^96 0.01^4 / (0.96^96 0.04^4)]
+        assert!((backtest.get_kupiec_statistic() - 5.1822).abs() < 1.0e-3);
+        assert!(backtest.get_kupiec_p_value()? < 0.05);
+        // n00 = 93, n01 = 2, n10 = 2, n11 = 2
+        assert!((backtest.get_christoffersen_statistic() - 8.5611).abs() < 1.0e-3);
+        assert!(backtest.get_christoffersen_p_value()? < 0.01);
+        assert!(
+            (backtest.get_conditional_coverage_statistic()
+                - backtest.get_kupiec_statistic()
+                - backtest.get_christoffersen_statistic())
+            .abs()
+                < 1.0e-6
+        );
+
+        // the realized P&Ls from the snapshots of an instrument worth 100, 95 and 97 with 1 paid on the last day
+        let inst_info = InstInfo {
+            id: StaticId::from_str("A", "KRX"),
+            currency: Currency::KRW,
+            unit_notional: 1.0,
+            ..Default::default()
+        };
+        let mut snapshots = vec![];
+        for (i, value) in [100.0, 95.0, 97.0].into_iter().enumerate() {
+            let date = dt + Duration::days(i as i64);
+            let mut cashflows = FxHashMap::default();
+            let mut probabilities = FxHashMap::default();
+            cashflows.insert(0, (dt + Duration::days(2), 1.0));
+            probabilities.insert(0, (dt + Duration::days(2), 1.0));
+            let mut result = CalculationResult::new(inst_info.clone(), date);
+            result.set_npv(NpvResult::new(value, cashflows, probabilities));
+            result.set_value()?;
+            let mut snapshot = FxHashMap::default();
+            snapshot.insert(inst_info.id, result);
+            snapshots.push(snapshot);
+        }
+        let backtest = VarBacktest::from_snapshots(0.99, Currency::KRW, &snapshots, &[3.0, 3.0])?;
+        let observations = backtest.get_observations();
+        assert_eq!(observations[0].get_realized_pnl(), -5.0);
+        assert_eq!(observations[1].get_realized_pnl(), 3.0);
+        assert_eq!(backtest.get_exception_number(), 1);
+        assert!(VarBacktest::from_snapshots(0.99, Currency::USD, &snapshots, &[3.0, 3.0]).is_err());
+        assert!(VarBacktest::from_snapshots(0.99, Currency::KRW, &snapshots, &[3.0]).is_err());
+        Ok(())
+    }
+}
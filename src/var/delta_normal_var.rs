@@ -0,0 +1,265 @@
+use crate::currency::Currency;
+use crate::definitions::{Real, DELTA_PNL_UNIT, RHO_PNL_UNIT, VEGA_PNL_UNIT};
+use crate::instrument::{InstrumentTrait, Instruments};
+use crate::pricing_engines::{
+    calculation_result::CalculationResult, pnl_explain::MarketDataSet,
+    pnl_predictor::get_delta_underlying_id,
+};
+use crate::var::{risk_factor::RiskFactor, risk_statistics::RiskStatistics};
+//
+use anyhow::{anyhow, Result};
+use ndarray::{Array1, Array2};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use statrs::distribution::{Continuous, ContinuousCDF, Normal};
+
+/// Delta-normal VaR of a portfolio, reported as a positive loss
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParametricVarResult {
+    confidence_level: Real,
+    currency: Currency,
+    standard_deviation: Real,
+    var: Real,
+    expected_shortfall: Real,
+    exposures: Vec<Real>, // in the order of the risk factors
+    var_contributions: FxHashMap<StaticId, Real>,
+}
+
+impl ParametricVarResult {
+    pub fn get_confidence_level(&self) -> Real {
+        self.confidence_level
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn get_standard_deviation(&self) -> Real {
+        self.standard_deviation
+    }
+
+    pub fn get_var(&self) -> Real {
+        self.var
+    }
+
+    pub fn get_expected_shortfall(&self) -> Real {
+        self.expected_shortfall
+    }
+
+    /// P&L of the portfolio for a unit move of each risk factor
+    pub fn get_exposures(&self) -> &Vec<Real> {
+        &self.exposures
+    }
+
+    /// component VaR which adds up to the VaR
+    pub fn get_var_contributions(&self) -> &FxHashMap<StaticId, Real> {
+        &self.var_contributions
+    }
+
+    /// normal VaR/ES at the given confidence levels.
+    /// The delta-normal P&L has no skewness nor excess kurtosis unless they are given,
+    /// e.g., from the scenario P&Ls of the historical VaR, for the Cornish-Fisher adjustment.
+    pub fn get_risk_statistics(
+        &self,
+        confidence_levels: &[Real],
+        skewness: Real,
+        excess_kurtosis: Real,
+    ) -> Result<RiskStatistics> {
+        RiskStatistics::from_moments(
+            self.currency,
+            0.0,
+            self.standard_deviation,
+            skewness,
+            excess_kurtosis,
+            confidence_levels,
+        )
+    }
+}
+
+/// Parametric VaR on the greeks already in CalculationResult without revaluation.
+///
+/// The exposure of an instrument to a risk factor is its P&L for a unit move of the factor
+/// (delta / 1%, vega / 1%p, rho / 1bp) converted to the base currency.
+/// An fx factor of `currency/base` (or `base/currency`) has the fx exposure of the currency as its exposure.
+/// With the covariance Σ of the daily moves and the portfolio exposure e,
+///
+/// σ = sqrt(e^T Σ e), VaR = z_α σ, ES = σ φ(z_α) / (1 - α)
+///
+/// and the contribution of an instrument with the exposure e_i is z_α e_i^T Σ e / σ.
+/// Gamma is ignored, so it is a cheap measure for intraday monitoring beside the full revaluation VaR.
+#[derive(Debug, Clone)]
+pub struct DeltaNormalVar {
+    risk_factors: Vec<RiskFactor>,
+    covariance: Array2<Real>,
+    confidence_level: Real,
+    base_currency: Currency,
+}
+
+impl DeltaNormalVar {
+    pub fn new(
+        risk_factors: Vec<RiskFactor>,
+        covariance: Array2<Real>,
+        base_currency: Currency,
+    ) -> Result<DeltaNormalVar> {
+        if covariance.nrows() != risk_factors.len() || covariance.ncols() != risk_factors.len() {
+            return Err(anyhow!(
+                "({}:{}) covariance shape {:?} does not match the number of risk factors {}",
+                file!(),
+                line!(),
+                covariance.shape(),
+                risk_factors.len(),
+            ));
+        }
+        Ok(DeltaNormalVar {
+            risk_factors,
+            covariance,
+            confidence_level: 0.99,
+            base_currency,
+        })
+    }
+
+    pub fn with_confidence_level(mut self, confidence_level: Real) -> Result<DeltaNormalVar> {
+        if confidence_level <= 0.0 || confidence_level >= 1.0 {
+            return Err(anyhow!(
+                "({}:{}) confidence level must be in (0, 1), but {} is given",
+                file!(),
+                line!(),
+                confidence_level,
+            ));
+        }
+        self.confidence_level = confidence_level;
+        Ok(self)
+    }
+
+    pub fn get_risk_factors(&self) -> &Vec<RiskFactor> {
+        &self.risk_factors
+    }
+
+    pub fn get_covariance(&self) -> &Array2<Real> {
+        &self.covariance
+    }
+
+    /// exposure of each instrument to the risk factors in the base currency
+    pub fn get_exposures(
+        &self,
+        instruments: &Instruments,
+        results: &FxHashMap<StaticId, CalculationResult>,
+        market_data: &MarketDataSet,
+    ) -> Result<FxHashMap<StaticId, Array1<Real>>> {
+        let index: FxHashMap<RiskFactor, usize> = self
+            .risk_factors
+            .iter()
+            .enumerate()
+            .map(|(i, factor)| (*factor, i))
+            .collect();
+
+        let mut res = FxHashMap::default();
+        for inst in instruments.iter() {
+            let inst_id = inst.get_id();
+            let result = results.get(&inst_id).ok_or_else(|| {
+                anyhow!(
+                    "({}:{}) calculation result is not given for {}",
+                    file!(),
+                    line!(),
+                    inst_id,
+                )
+            })?;
+            let fx_rate = market_data.get_fx_rate(inst.get_currency(), self.base_currency)?;
+            let mut exposure = Array1::<Real>::zeros(self.risk_factors.len());
+
+            if let Some(delta) = result.get_delta() {
+                for (delta_id, value) in delta.iter() {
+                    let und_id = get_delta_underlying_id(inst.as_ref(), delta_id);
+                    if let Some(&i) = index.get(&RiskFactor::Spot(und_id)) {
+                        exposure[i] += value / DELTA_PNL_UNIT * fx_rate;
+                    }
+                }
+            }
+            if let Some(vega) = result.get_vega() {
+                for (und_id, value) in vega.iter() {
+                    if let Some(&i) = index.get(&RiskFactor::Volatility(*und_id)) {
+                        exposure[i] += value / VEGA_PNL_UNIT * fx_rate;
+                    }
+                }
+            }
+            if let Some(rho) = result.get_rho() {
+                for (curve_id, value) in rho.iter() {
+                    if let Some(&i) = index.get(&RiskFactor::Rate(*curve_id)) {
+                        exposure[i] += value / RHO_PNL_UNIT * fx_rate;
+                    }
+                }
+            }
+            if let Some(fx_exposure) = result.get_fx_exposure() {
+                for (i, factor) in self.risk_factors.iter().enumerate() {
+                    if let RiskFactor::Fx(fx_code) = factor {
+                        let (currency1, currency2) =
+                            (fx_code.get_currency1(), fx_code.get_currency2());
+                        let (currency, sign) = match (currency1, currency2) {
+                            (c, base) if base == self.base_currency => (c, 1.0),
+                            (base, c) if base == self.base_currency => (c, -1.0),
+                            _ => continue,
+                        };
+                        if let Some(amount) = fx_exposure.get(&currency) {
+                            let rate = market_data.get_fx_rate(currency, self.base_currency)?;
+                            exposure[i] += sign * amount * rate;
+                        }
+                    }
+                }
+            }
+            res.insert(inst_id, exposure);
+        }
+        Ok(res)
+    }
+
+    pub fn calculate(
+        &self,
+        instruments: &Instruments,
+        results: &FxHashMap<StaticId, CalculationResult>,
+        market_data: &MarketDataSet,
+    ) -> Result<ParametricVarResult> {
+        let instrument_exposures = self.get_exposures(instruments, results, market_data)?;
+        let mut inst_ids: Vec<&StaticId> = instrument_exposures.keys().collect();
+        inst_ids.sort_by_key(|id| id.to_string());
+        let mut exposures = Array1::<Real>::zeros(self.risk_factors.len());
+        for inst_id in inst_ids {
+            exposures += &instrument_exposures[inst_id];
+        }
+
+        let covariance_exposures = self.covariance.dot(&exposures);
+        let variance = exposures.dot(&covariance_exposures);
+        if variance < 0.0 {
+            return Err(anyhow!(
+                "({}:{}) negative portfolio variance {}: the covariance is not positive semi-definite",
+                file!(),
+                line!(),
+                variance,
+            ));
+        }
+        let standard_deviation = variance.sqrt();
+        let normal = Normal::new(0.0, 1.0)?;
+        let z = normal.inverse_cdf(self.confidence_level as f64);
+        let expected_shortfall =
+            standard_deviation * (normal.pdf(z) / (1.0 - self.confidence_level as f64)) as Real;
+        let z = z as Real;
+
+        let mut var_contributions = FxHashMap::default();
+        for (inst_id, exposure) in instrument_exposures.iter() {
+            let contribution = match standard_deviation > 0.0 {
+                true => z * exposure.dot(&covariance_exposures) / standard_deviation,
+                false => 0.0,
+            };
+            var_contributions.insert(*inst_id, contribution);
+        }
+
+        Ok(ParametricVarResult {
+            confidence_level: self.confidence_level,
+            currency: self.base_currency,
+            standard_deviation,
+            var: z * standard_deviation,
+            expected_shortfall,
+            exposures: exposures.to_vec(),
+            var_contributions,
+        })
+    }
+}
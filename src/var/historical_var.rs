@@ -0,0 +1,88 @@
+use crate::definitions::Real;
+use crate::var::{
+    risk_factor_history::RiskFactorHistory, scenario_revaluation::ScenarioRevaluation,
+    var_result::VarResult,
+};
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use static_id::static_id::StaticId;
+use time::Date;
+
+/// Historical simulation VaR.
+/// The daily moves of the risk factor history are applied to the base market data
+/// and the portfolio is repriced by ScenarioRevaluation (full revaluation or sensitivities).
+pub struct HistoricalVar {
+    revaluation: ScenarioRevaluation,
+    history: RiskFactorHistory,
+    confidence_level: Real,
+    //
+    scenario_dates: Vec<Date>,
+    instrument_pnls: FxHashMap<StaticId, Vec<Real>>,
+    result: Option<VarResult>,
+}
+
+impl HistoricalVar {
+    pub fn new(revaluation: ScenarioRevaluation, history: RiskFactorHistory) -> HistoricalVar {
+        HistoricalVar {
+            revaluation,
+            history,
+            confidence_level: 0.99,
+            scenario_dates: vec![],
+            instrument_pnls: FxHashMap::default(),
+            result: None,
+        }
+    }
+
+    pub fn with_confidence_level(mut self, confidence_level: Real) -> Result<HistoricalVar> {
+        if confidence_level <= 0.0 || confidence_level >= 1.0 {
+            return Err(anyhow!(
+                "({}:{}) confidence level must be in (0, 1), but {} is given",
+                file!(),
+                line!(),
+                confidence_level,
+            ));
+        }
+        self.confidence_level = confidence_level;
+        Ok(self)
+    }
+
+    pub fn calculate(&mut self) -> Result<()> {
+        let scenarios = self.history.get_scenarios()?;
+        let (scenario_dates, shocks): (Vec<Date>, Vec<_>) = scenarios.into_iter().unzip();
+
+        self.revaluation.initialize()?;
+        let instrument_pnls = self.revaluation.get_scenario_pnls(&shocks)?;
+        let result = VarResult::from_scenario_pnls(
+            self.confidence_level,
+            self.revaluation.get_base_currency(),
+            &instrument_pnls,
+        )?;
+
+        self.scenario_dates = scenario_dates;
+        self.instrument_pnls = instrument_pnls;
+        self.result = Some(result);
+        Ok(())
+    }
+
+    pub fn get_confidence_level(&self) -> Real {
+        self.confidence_level
+    }
+
+    pub fn get_revaluation(&self) -> &ScenarioRevaluation {
+        &self.revaluation
+    }
+
+    /// the dates of the scenarios in the order of the P&Ls
+    pub fn get_scenario_dates(&self) -> &Vec<Date> {
+        &self.scenario_dates
+    }
+
+    pub fn get_instrument_pnls(&self) -> &FxHashMap<StaticId, Vec<Real>> {
+        &self.instrument_pnls
+    }
+
+    pub fn get_result(&self) -> Option<&VarResult> {
+        self.result.as_ref()
+    }
+}
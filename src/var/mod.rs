@@ -0,0 +1,11 @@
+pub mod backtest;
+pub mod delta_normal_var;
+pub mod historical_var;
+pub mod monte_carlo_var;
+pub mod risk_factor;
+pub mod risk_factor_history;
+pub mod risk_statistics;
+pub mod scenario_revaluation;
+pub mod shock_file;
+pub mod stress_test;
+pub mod var_result;
@@ -0,0 +1,285 @@
+use crate::definitions::Real;
+use crate::enums::ScenarioDistribution;
+use crate::math::linalg::cholesky_with_fallback;
+use crate::pricing_engines::pnl_predictor::MarketShock;
+use crate::var::{
+    risk_factor::RiskFactor, scenario_revaluation::ScenarioRevaluation, var_result::VarResult,
+};
+//
+use anyhow::{anyhow, Result};
+use ndarray::{Array1, Array2};
+use rand::{rngs::StdRng, SeedableRng};
+use rand_distr::{ChiSquared, Distribution, StandardNormal};
+use rustc_hash::FxHashMap;
+use static_id::static_id::StaticId;
+
+/// Generates the moves of the risk factors from their covariance matrix (of the daily moves)
+/// with a seeded random number generator, so the same seed gives the same scenarios.
+/// Without its own seed, MonteCarloVar seeds it with the random seed of the calculation configuration.
+///
+/// Normal: x = L z where L L^T = covariance and z is standard normal.
+/// L is the Cholesky factor, or the pivoted one if the covariance is only positive semi-definite.
+/// StudentT: x = L z * sqrt((ν - 2) / w) where w ~ χ²(ν), which has the same covariance.
+#[derive(Debug, Clone)]
+pub struct ScenarioGenerator {
+    risk_factors: Vec<RiskFactor>,
+    cholesky: Array2<Real>,
+    distribution: ScenarioDistribution,
+    seed: Option<u64>,
+}
+
+impl ScenarioGenerator {
+    pub fn new(
+        risk_factors: Vec<RiskFactor>,
+        covariance: &Array2<Real>,
+    ) -> Result<ScenarioGenerator> {
+        if covariance.nrows() != risk_factors.len() || covariance.ncols() != risk_factors.len() {
+            return Err(anyhow!(
+                "({}:{}) covariance shape {:?} does not match the number of risk factors {}",
+                file!(),
+                line!(),
+                covariance.shape(),
+                risk_factors.len(),
+            ));
+        }
+        // the perfectly correlated risk factors, e.g., a spot and its futures, make the covariance singular
+        let cholesky = cholesky_with_fallback(covariance).map_err(|e| {
+            anyhow!(
+                "({}:{}) failed to factorize the covariance: {}",
+                file!(),
+                line!(),
+                e
+            )
+        })?;
+        Ok(ScenarioGenerator {
+            risk_factors,
+            cholesky,
+            distribution: ScenarioDistribution::default(),
+            seed: None,
+        })
+    }
+
+    pub fn with_distribution(
+        mut self,
+        distribution: ScenarioDistribution,
+    ) -> Result<ScenarioGenerator> {
+        if let ScenarioDistribution::StudentT { degrees_of_freedom } = distribution {
+            if degrees_of_freedom <= 2.0 {
+                return Err(anyhow!(
+                    "({}:{}) degrees of freedom must be larger than 2, but {} is given",
+                    file!(),
+                    line!(),
+                    degrees_of_freedom,
+                ));
+            }
+        }
+        self.distribution = distribution;
+        Ok(self)
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> ScenarioGenerator {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn get_risk_factors(&self) -> &Vec<RiskFactor> {
+        &self.risk_factors
+    }
+
+    pub fn get_distribution(&self) -> ScenarioDistribution {
+        self.distribution
+    }
+
+    pub fn get_seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn generate(&self, scenario_number: usize) -> Result<Vec<MarketShock>> {
+        let mut rng = StdRng::seed_from_u64(self.seed.unwrap_or_default());
+        let chi_squared = match self.distribution {
+            ScenarioDistribution::Normal => None,
+            ScenarioDistribution::StudentT { degrees_of_freedom } => {
+                Some((degrees_of_freedom, ChiSquared::new(degrees_of_freedom)?))
+            }
+        };
+
+        let factor_number = self.risk_factors.len();
+        let mut res = Vec::with_capacity(scenario_number);
+        for _ in 0..scenario_number {
+            let z: Array1<Real> = (0..factor_number)
+                .map(|_| StandardNormal.sample(&mut rng))
+                .collect();
+            let mut moves = self.cholesky.dot(&z);
+            if let Some((degrees_of_freedom, chi_squared)) = &chi_squared {
+                let w: Real = chi_squared.sample(&mut rng);
+                moves *= ((degrees_of_freedom - 2.0) / w).sqrt();
+            }
+
+            let mut shock = MarketShock::new();
+            for (risk_factor, value) in self.risk_factors.iter().zip(moves.iter()) {
+                shock = risk_factor.add_to_shock(shock, *value);
+            }
+            res.push(shock);
+        }
+        Ok(res)
+    }
+}
+
+/// Monte Carlo VaR.
+/// The scenarios from ScenarioGenerator are repriced by ScenarioRevaluation
+/// in the same way as the historical scenarios in HistoricalVar.
+pub struct MonteCarloVar {
+    revaluation: ScenarioRevaluation,
+    generator: ScenarioGenerator,
+    scenario_number: usize,
+    confidence_level: Real,
+    //
+    instrument_pnls: FxHashMap<StaticId, Vec<Real>>,
+    result: Option<VarResult>,
+}
+
+impl MonteCarloVar {
+    pub fn new(revaluation: ScenarioRevaluation, generator: ScenarioGenerator) -> MonteCarloVar {
+        let generator = match generator.get_seed() {
+            Some(_) => generator,
+            None => {
+                let seed = revaluation.get_calculation_configuration().get_random_seed();
+                generator.with_seed(seed)
+            }
+        };
+        MonteCarloVar {
+            revaluation,
+            generator,
+            scenario_number: 1_000,
+            confidence_level: 0.99,
+            instrument_pnls: FxHashMap::default(),
+            result: None,
+        }
+    }
+
+    pub fn with_scenario_number(mut self, scenario_number: usize) -> MonteCarloVar {
+        self.scenario_number = scenario_number;
+        self
+    }
+
+    pub fn with_confidence_level(mut self, confidence_level: Real) -> Result<MonteCarloVar> {
+        if confidence_level <= 0.0 || confidence_level >= 1.0 {
+            return Err(anyhow!(
+                "({}:{}) confidence level must be in (0, 1), but {} is given",
+                file!(),
+                line!(),
+                confidence_level,
+            ));
+        }
+        self.confidence_level = confidence_level;
+        Ok(self)
+    }
+
+    pub fn calculate(&mut self) -> Result<()> {
+        let shocks = self.generator.generate(self.scenario_number)?;
+
+        self.revaluation.initialize()?;
+        let instrument_pnls = self.revaluation.get_scenario_pnls(&shocks)?;
+        let result = VarResult::from_scenario_pnls(
+            self.confidence_level,
+            self.revaluation.get_base_currency(),
+            &instrument_pnls,
+        )?;
+
+        self.instrument_pnls = instrument_pnls;
+        self.result = Some(result);
+        Ok(())
+    }
+
+    pub fn get_confidence_level(&self) -> Real {
+        self.confidence_level
+    }
+
+    pub fn get_scenario_number(&self) -> usize {
+        self.scenario_number
+    }
+
+    pub fn get_generator(&self) -> &ScenarioGenerator {
+        &self.generator
+    }
+
+    pub fn get_revaluation(&self) -> &ScenarioRevaluation {
+        &self.revaluation
+    }
+
+    pub fn get_instrument_pnls(&self) -> &FxHashMap<StaticId, Vec<Real>> {
+        &self.instrument_pnls
+    }
+
+    pub fn get_result(&self) -> Option<&VarResult> {
+        self.result.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::{Currency, FxCode};
+    use ndarray::array;
+
+    #[test]
+    fn test_scenario_generator() -> Result<()> {
+        let spot = StaticId::from_str("KOSPI2", "KRX");
+        let fx_code = FxCode::new(Currency::USD, Currency::KRW);
+        let risk_factors = vec![RiskFactor::Spot(spot), RiskFactor::Fx(fx_code)];
+        // 1.5% and 0.5% daily volatility with -0.4 correlation
+        let covariance = array![
+            [0.015 * 0.015, -0.4 * 0.015 * 0.005],
+            [-0.4 * 0.015 * 0.005, 0.005 * 0.005]
+        ];
+
+        let distributions = [
+            ScenarioDistribution::Normal,
+            ScenarioDistribution::StudentT {
+                degrees_of_freedom: 5.0,
+            },
+        ];
+        for distribution in distributions {
+            let generator = ScenarioGenerator::new(risk_factors.clone(), &covariance)?
+                .with_distribution(distribution)?
+                .with_seed(42);
+            let shocks = generator.generate(20_000)?;
+            assert_eq!(shocks, generator.generate(20_000)?);
+
+            let n = shocks.len() as Real;
+            let (mut var1, mut var2, mut cov) = (0.0, 0.0, 0.0);
+            for shock in shocks.iter() {
+                let x1 = shock.get_spot_returns()[&spot];
+                let x2 = shock.get_fx_returns()[&fx_code];
+                var1 += x1 * x1 / n;
+                var2 += x2 * x2 / n;
+                cov += x1 * x2 / n;
+            }
+            for (sample, expected) in [
+                (var1, covariance[[0, 0]]),
+                (var2, covariance[[1, 1]]),
+                (cov, covariance[[0, 1]]),
+            ] {
+                assert!(
+                    (sample / expected - 1.0).abs() < 0.1,
+                    "{:?}: sample {}, expected {}",
+                    distribution,
+                    sample,
+                    expected,
+                );
+            }
+        }
+
+        let other_seed = ScenarioGenerator::new(risk_factors.clone(), &covariance)?.with_seed(7);
+        let seed_42 = ScenarioGenerator::new(risk_factors.clone(), &covariance)?.with_seed(42);
+        assert_ne!(other_seed.generate(10)?, seed_42.generate(10)?);
+
+        assert!(ScenarioGenerator::new(risk_factors.clone(), &covariance)?
+            .with_distribution(ScenarioDistribution::StudentT {
+                degrees_of_freedom: 2.0
+            })
+            .is_err());
+        assert!(ScenarioGenerator::new(risk_factors, &array![[1.0]]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,28 @@
+use crate::currency::FxCode;
+use crate::definitions::Real;
+use crate::pricing_engines::pnl_predictor::MarketShock;
+//
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+
+/// A risk factor keyed as in MarketShock.
+/// The moves of Spot and Fx are returns, those of Volatility and Rate are differences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RiskFactor {
+    Spot(StaticId),
+    Volatility(StaticId),
+    Rate(StaticId),
+    Fx(FxCode),
+}
+
+impl RiskFactor {
+    /// the shock with the move of this risk factor added
+    pub fn add_to_shock(&self, shock: MarketShock, value: Real) -> MarketShock {
+        match self {
+            RiskFactor::Spot(id) => shock.with_spot_return(*id, value),
+            RiskFactor::Volatility(id) => shock.with_volatility_change(*id, value),
+            RiskFactor::Rate(id) => shock.with_rate_change(*id, value),
+            RiskFactor::Fx(code) => shock.with_fx_return(*code, value),
+        }
+    }
+}
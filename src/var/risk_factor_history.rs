@@ -0,0 +1,192 @@
+use crate::currency::FxCode;
+use crate::data::daily_value_data::DailyValueData;
+use crate::pricing_engines::pnl_predictor::MarketShock;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::{FxHashMap, FxHashSet};
+use static_id::static_id::StaticId;
+use time::Date;
+
+/// Daily time series of the risk factors keyed as in MarketShock:
+/// spots and volatility levels by underlying ids, zero rate levels by curve ids and fx rates by FxCode.
+/// The daily moves on the dates observed in all the series are the historical scenarios.
+#[derive(Debug, Clone, Default)]
+pub struct RiskFactorHistory {
+    spot_data: FxHashMap<StaticId, DailyValueData>,
+    volatility_data: FxHashMap<StaticId, DailyValueData>,
+    rate_data: FxHashMap<StaticId, DailyValueData>,
+    fx_data: FxHashMap<FxCode, DailyValueData>,
+}
+
+impl RiskFactorHistory {
+    pub fn new() -> RiskFactorHistory {
+        RiskFactorHistory::default()
+    }
+
+    pub fn with_spot_data(
+        mut self,
+        spot_data: FxHashMap<StaticId, DailyValueData>,
+    ) -> RiskFactorHistory {
+        self.spot_data = spot_data;
+        self
+    }
+
+    pub fn with_volatility_data(
+        mut self,
+        volatility_data: FxHashMap<StaticId, DailyValueData>,
+    ) -> RiskFactorHistory {
+        self.volatility_data = volatility_data;
+        self
+    }
+
+    pub fn with_rate_data(
+        mut self,
+        rate_data: FxHashMap<StaticId, DailyValueData>,
+    ) -> RiskFactorHistory {
+        self.rate_data = rate_data;
+        self
+    }
+
+    pub fn with_fx_data(mut self, fx_data: FxHashMap<FxCode, DailyValueData>) -> RiskFactorHistory {
+        self.fx_data = fx_data;
+        self
+    }
+
+    pub fn get_spot_data(&self) -> &FxHashMap<StaticId, DailyValueData> {
+        &self.spot_data
+    }
+
+    pub fn get_volatility_data(&self) -> &FxHashMap<StaticId, DailyValueData> {
+        &self.volatility_data
+    }
+
+    pub fn get_rate_data(&self) -> &FxHashMap<StaticId, DailyValueData> {
+        &self.rate_data
+    }
+
+    pub fn get_fx_data(&self) -> &FxHashMap<FxCode, DailyValueData> {
+        &self.fx_data
+    }
+
+    fn all_series(&self) -> impl Iterator<Item = &DailyValueData> {
+        self.spot_data
+            .values()
+            .chain(self.volatility_data.values())
+            .chain(self.rate_data.values())
+            .chain(self.fx_data.values())
+    }
+
+    /// sorted dates observed in all the series
+    pub fn get_common_dates(&self) -> Vec<Date> {
+        let mut common: Option<FxHashSet<Date>> = None;
+        for series in self.all_series() {
+            let dates: FxHashSet<Date> = series.get_value().keys().copied().collect();
+            common = match common {
+                None => Some(dates),
+                Some(common) => Some(common.intersection(&dates).copied().collect()),
+            };
+        }
+        let mut res: Vec<Date> = common.unwrap_or_default().into_iter().collect();
+        res.sort();
+        res
+    }
+
+    /// daily moves between the consecutive common dates, paired with the later date.
+    /// The spots and fx rates move in returns, the volatilities and rates in differences.
+    pub fn get_scenarios(&self) -> Result<Vec<(Date, MarketShock)>> {
+        let dates = self.get_common_dates();
+        if dates.len() < 2 {
+            return Err(anyhow!(
+                "({}:{}) at least two common dates are needed for the scenarios, but {} are given",
+                file!(),
+                line!(),
+                dates.len(),
+            ));
+        }
+
+        let mut res = Vec::with_capacity(dates.len() - 1);
+        for window in dates.windows(2) {
+            let (prev, curr) = (&window[0], &window[1]);
+            let mut shock = MarketShock::new();
+            for (und_id, series) in self.spot_data.iter() {
+                let (v0, v1) = (series.get(prev).unwrap(), series.get(curr).unwrap());
+                shock = shock.with_spot_return(*und_id, v1 / v0 - 1.0);
+            }
+            for (und_id, series) in self.volatility_data.iter() {
+                let (v0, v1) = (series.get(prev).unwrap(), series.get(curr).unwrap());
+                shock = shock.with_volatility_change(*und_id, v1 - v0);
+            }
+            for (curve_id, series) in self.rate_data.iter() {
+                let (v0, v1) = (series.get(prev).unwrap(), series.get(curr).unwrap());
+                shock = shock.with_rate_change(*curve_id, v1 - v0);
+            }
+            for (fx_code, series) in self.fx_data.iter() {
+                let (v0, v1) = (series.get(prev).unwrap(), series.get(curr).unwrap());
+                shock = shock.with_fx_return(*fx_code, v1 / v0 - 1.0);
+            }
+            res.push((*curr, shock));
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use time::macros::date;
+
+    #[test]
+    fn test_risk_factor_history() -> Result<()> {
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let curve_id = StaticId::from_str("KSD", "DataProvider");
+        let fx_code = FxCode::new(Currency::USD, Currency::KRW);
+
+        let mut spot = DailyValueData::default();
+        spot.insert(date!(2024 - 03 - 11), 100.0);
+        spot.insert(date!(2024 - 03 - 12), 102.0);
+        spot.insert(date!(2024 - 03 - 13), 99.96);
+        let mut rate = DailyValueData::default();
+        rate.insert(date!(2024 - 03 - 11), 0.0330);
+        rate.insert(date!(2024 - 03 - 12), 0.0335);
+        rate.insert(date!(2024 - 03 - 13), 0.0335);
+        rate.insert(date!(2024 - 03 - 14), 0.0340);
+        let mut fx = DailyValueData::default();
+        fx.insert(date!(2024 - 03 - 11), 1300.0);
+        fx.insert(date!(2024 - 03 - 12), 1313.0);
+        fx.insert(date!(2024 - 03 - 13), 1313.0);
+
+        let mut spot_data = FxHashMap::default();
+        spot_data.insert(und_id, spot);
+        let mut rate_data = FxHashMap::default();
+        rate_data.insert(curve_id, rate);
+        let mut fx_data = FxHashMap::default();
+        fx_data.insert(fx_code, fx);
+        let history = RiskFactorHistory::new()
+            .with_spot_data(spot_data)
+            .with_rate_data(rate_data)
+            .with_fx_data(fx_data);
+
+        // 2024-03-14 is only in the rate series
+        assert_eq!(
+            history.get_common_dates(),
+            vec![
+                date!(2024 - 03 - 11),
+                date!(2024 - 03 - 12),
+                date!(2024 - 03 - 13)
+            ]
+        );
+
+        let scenarios = history.get_scenarios()?;
+        assert_eq!(scenarios.len(), 2);
+        let (date, shock) = &scenarios[1];
+        assert_eq!(*date, date!(2024 - 03 - 13));
+        assert!((shock.get_spot_returns()[&und_id] + 0.02).abs() < 1.0e-5);
+        assert!(shock.get_rate_changes()[&curve_id].abs() < 1.0e-7);
+        assert!((scenarios[0].1.get_fx_returns()[&fx_code] - 0.01).abs() < 1.0e-5);
+        assert_eq!(shock.get_days(), 0.0);
+
+        assert!(RiskFactorHistory::new().get_scenarios().is_err());
+        Ok(())
+    }
+}
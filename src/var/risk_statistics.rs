@@ -0,0 +1,268 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::var::var_result::get_tail_scenarios;
+//
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use statrs::distribution::{Continuous, ContinuousCDF, Normal};
+
+/// VaR and expected shortfall at a confidence level, reported as positive losses
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TailRisk {
+    confidence_level: Real,
+    var: Real,
+    expected_shortfall: Real,
+}
+
+impl TailRisk {
+    pub fn get_confidence_level(&self) -> Real {
+        self.confidence_level
+    }
+
+    pub fn get_var(&self) -> Real {
+        self.var
+    }
+
+    pub fn get_expected_shortfall(&self) -> Real {
+        self.expected_shortfall
+    }
+}
+
+/// Moments of the portfolio P&L and the tail risks at several confidence levels.
+///
+/// - empirical: the quantile and the tail average of the P&Ls as in VarResult
+/// - normal: VaR = -(μ + σ z), ES = -μ + σ φ(z) / (1 - α) where z = Φ^{-1}(1 - α)
+/// - cornish_fisher: z is replaced by the Cornish-Fisher expansion with the skewness S and the excess kurtosis K
+///
+/// z_cf = z + (z² - 1) S / 6 + (z³ - 3z) K / 24 - (2z³ - 5z) S² / 36
+///
+/// and ES takes the tail average of the expansion under the normal density.
+/// The parametric statistics from moments only (e.g., delta-normal VaR) have no empirical tail risks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskStatistics {
+    currency: Currency,
+    scenario_number: usize,
+    mean: Real,
+    standard_deviation: Real,
+    skewness: Real,
+    excess_kurtosis: Real,
+    empirical: Vec<TailRisk>,
+    normal: Vec<TailRisk>,
+    cornish_fisher: Vec<TailRisk>,
+}
+
+impl RiskStatistics {
+    /// statistics of the scenario P&Ls of a portfolio
+    pub fn from_pnls(
+        currency: Currency,
+        pnls: &[Real],
+        confidence_levels: &[Real],
+    ) -> Result<RiskStatistics> {
+        check_confidence_levels(confidence_levels)?;
+        if pnls.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) no scenario P&L is given",
+                file!(),
+                line!()
+            ));
+        }
+
+        // moments are accumulated in f64 since the P&Ls can be large
+        let n = pnls.len() as f64;
+        let mean = pnls.iter().map(|&x| x as f64).sum::<f64>() / n;
+        let (mut m2, mut m3, mut m4) = (0.0, 0.0, 0.0);
+        for &pnl in pnls.iter() {
+            let d = pnl as f64 - mean;
+            m2 += d * d / n;
+            m3 += d * d * d / n;
+            m4 += d * d * d * d / n;
+        }
+        let (skewness, excess_kurtosis) = match m2 > 0.0 {
+            true => (m3 / m2.powf(1.5), m4 / (m2 * m2) - 3.0),
+            false => (0.0, 0.0),
+        };
+
+        let mut empirical = Vec::with_capacity(confidence_levels.len());
+        for &confidence_level in confidence_levels.iter() {
+            let tail = get_tail_scenarios(pnls, confidence_level);
+            let var = -pnls[tail[tail.len() - 1]];
+            let expected_shortfall =
+                -tail.iter().map(|&i| pnls[i]).sum::<Real>() / tail.len() as Real;
+            empirical.push(TailRisk {
+                confidence_level,
+                var,
+                expected_shortfall,
+            });
+        }
+
+        let mut res = RiskStatistics::from_moments(
+            currency,
+            mean as Real,
+            m2.sqrt() as Real,
+            skewness as Real,
+            excess_kurtosis as Real,
+            confidence_levels,
+        )?;
+        res.scenario_number = pnls.len();
+        res.empirical = empirical;
+        Ok(res)
+    }
+
+    /// parametric statistics of a P&L with the given moments
+    pub fn from_moments(
+        currency: Currency,
+        mean: Real,
+        standard_deviation: Real,
+        skewness: Real,
+        excess_kurtosis: Real,
+        confidence_levels: &[Real],
+    ) -> Result<RiskStatistics> {
+        check_confidence_levels(confidence_levels)?;
+        if standard_deviation < 0.0 {
+            return Err(anyhow!(
+                "({}:{}) standard deviation must be non-negative, but {} is given",
+                file!(),
+                line!(),
+                standard_deviation,
+            ));
+        }
+
+        let normal_dist = Normal::new(0.0, 1.0)?;
+        let (mu, sigma) = (mean as f64, standard_deviation as f64);
+        let (s, k) = (skewness as f64, excess_kurtosis as f64);
+        let mut normal = Vec::with_capacity(confidence_levels.len());
+        let mut cornish_fisher = Vec::with_capacity(confidence_levels.len());
+        for &confidence_level in confidence_levels.iter() {
+            let p = 1.0 - confidence_level as f64;
+            let z = normal_dist.inverse_cdf(p);
+            let density = normal_dist.pdf(z);
+            normal.push(TailRisk {
+                confidence_level,
+                var: -(mu + sigma * z) as Real,
+                expected_shortfall: (-mu + sigma * density / p) as Real,
+            });
+
+            let z_cf = z + (z * z - 1.0) * s / 6.0 + (z.powi(3) - 3.0 * z) * k / 24.0
+                - (2.0 * z.powi(3) - 5.0 * z) * s * s / 36.0;
+            // I_j = ∫_{-∞}^{z} u^j φ(u) du
+            let i0 = p;
+            let i1 = -density;
+            let i2 = p - z * density;
+            let i3 = -(z * z + 2.0) * density;
+            let tail_mean_cf = (i1 + (i2 - i0) * s / 6.0 + (i3 - 3.0 * i1) * k / 24.0
+                - (2.0 * i3 - 5.0 * i1) * s * s / 36.0)
+                / p;
+            cornish_fisher.push(TailRisk {
+                confidence_level,
+                var: -(mu + sigma * z_cf) as Real,
+                expected_shortfall: -(mu + sigma * tail_mean_cf) as Real,
+            });
+        }
+
+        Ok(RiskStatistics {
+            currency,
+            scenario_number: 0,
+            mean,
+            standard_deviation,
+            skewness,
+            excess_kurtosis,
+            empirical: vec![],
+            normal,
+            cornish_fisher,
+        })
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// 0 for the statistics from moments
+    pub fn get_scenario_number(&self) -> usize {
+        self.scenario_number
+    }
+
+    pub fn get_mean(&self) -> Real {
+        self.mean
+    }
+
+    pub fn get_standard_deviation(&self) -> Real {
+        self.standard_deviation
+    }
+
+    pub fn get_skewness(&self) -> Real {
+        self.skewness
+    }
+
+    pub fn get_excess_kurtosis(&self) -> Real {
+        self.excess_kurtosis
+    }
+
+    pub fn get_empirical(&self) -> &Vec<TailRisk> {
+        &self.empirical
+    }
+
+    pub fn get_normal(&self) -> &Vec<TailRisk> {
+        &self.normal
+    }
+
+    pub fn get_cornish_fisher(&self) -> &Vec<TailRisk> {
+        &self.cornish_fisher
+    }
+}
+
+fn check_confidence_levels(confidence_levels: &[Real]) -> Result<()> {
+    for &confidence_level in confidence_levels.iter() {
+        if confidence_level <= 0.0 || confidence_level >= 1.0 {
+            return Err(anyhow!(
+                "({}:{}) confidence level must be in (0, 1), but {} is given",
+                file!(),
+                line!(),
+                confidence_level,
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_risk_statistics() -> Result<()> {
+        // -50, -49, ..., 49
+        let pnls: Vec<Real> = (0..100).map(|i| (i as Real) - 50.0).collect();
+        let stats = RiskStatistics::from_pnls(Currency::KRW, &pnls, &[0.95, 0.99])?;
+        assert_eq!(stats.get_scenario_number(), 100);
+        assert!((stats.get_mean() + 0.5).abs() < 1.0e-5);
+        assert!(stats.get_skewness().abs() < 1.0e-5);
+        // uniform distribution has the excess kurtosis -1.2
+        assert!((stats.get_excess_kurtosis() + 1.2).abs() < 1.0e-3);
+
+        let empirical = stats.get_empirical();
+        assert_eq!(empirical[0].get_var(), 46.0);
+        assert_eq!(empirical[0].get_expected_shortfall(), 48.0);
+        assert_eq!(empirical[1].get_var(), 50.0);
+        assert_eq!(empirical[1].get_expected_shortfall(), 50.0);
+
+        // standard normal at 99%: VaR = 2.326348, ES = 2.665214
+        let normal = RiskStatistics::from_moments(Currency::KRW, 0.0, 1.0, 0.0, 0.0, &[0.99])?;
+        assert!(normal.get_empirical().is_empty());
+        for tail in [normal.get_normal()[0], normal.get_cornish_fisher()[0]] {
+            assert!((tail.get_var() - 2.326_348).abs() < 1.0e-4);
+            assert!((tail.get_expected_shortfall() - 2.665_214).abs() < 1.0e-4);
+        }
+
+        // negative skewness and fat tails make the losses larger
+        let skewed = RiskStatistics::from_moments(Currency::KRW, 0.0, 1.0, -0.5, 3.0, &[0.99])?;
+        let (normal_tail, cf_tail) = (skewed.get_normal()[0], skewed.get_cornish_fisher()[0]);
+        // z_cf = z + (z² - 1)(-0.5)/6 + (z³ - 3z)(3)/24 - (2z³ - 5z)(0.25)/36 with z = -2.326348
+        assert!((cf_tail.get_var() - 3.301_284).abs() < 1.0e-3);
+        assert!(cf_tail.get_var() > normal_tail.get_var());
+        assert!(cf_tail.get_expected_shortfall() > cf_tail.get_var());
+
+        assert!(RiskStatistics::from_pnls(Currency::KRW, &pnls, &[1.0]).is_err());
+        assert!(RiskStatistics::from_pnls(Currency::KRW, &[], &[0.99]).is_err());
+        Ok(())
+    }
+}
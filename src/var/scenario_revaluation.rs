@@ -0,0 +1,202 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::enums::VarMethod;
+use crate::instrument::{InstrumentTrait, Instruments};
+use crate::pricing_engines::{
+    calculation_configuration::CalculationConfiguration,
+    calculation_result::CalculationResult,
+    engine_generator::InstrumentCategory,
+    match_parameter::MatchParameter,
+    pnl_explain::MarketDataSet,
+    pnl_predictor::{MarketShock, TaylorPnlPredictor},
+};
+//
+use anyhow::{anyhow, Context, Result};
+use rustc_hash::FxHashMap;
+use static_id::static_id::StaticId;
+
+/// Reprices a portfolio on market shocks from the base market data.
+/// The P&Ls are converted to the base currency with the (shocked) fx rates of the scenario.
+///
+/// In FullRevaluation, the instruments are valued again on the shocked data.
+/// In Sensitivity, the P&L is predicted by TaylorPnlPredictor on the greeks of the base date.
+/// The base results are calculated once in `initialize` and shared by all scenarios.
+pub struct ScenarioRevaluation {
+    calculation_configuration: CalculationConfiguration,
+    match_parameter: MatchParameter,
+    instrument_categories: Vec<InstrumentCategory>,
+    instruments: Instruments,
+    base_data: MarketDataSet,
+    base_currency: Currency,
+    method: VarMethod,
+    //
+    base_results: FxHashMap<StaticId, CalculationResult>,
+}
+
+impl ScenarioRevaluation {
+    pub fn new(
+        calculation_configuration: CalculationConfiguration,
+        match_parameter: MatchParameter,
+        instrument_categories: Vec<InstrumentCategory>,
+        instruments: Instruments,
+        base_data: MarketDataSet,
+        base_currency: Currency,
+    ) -> ScenarioRevaluation {
+        ScenarioRevaluation {
+            calculation_configuration,
+            match_parameter,
+            instrument_categories,
+            instruments,
+            base_data,
+            base_currency,
+            method: VarMethod::default(),
+            base_results: FxHashMap::default(),
+        }
+    }
+
+    pub fn with_method(mut self, method: VarMethod) -> ScenarioRevaluation {
+        self.method = method;
+        self
+    }
+
+    pub fn get_calculation_configuration(&self) -> &CalculationConfiguration {
+        &self.calculation_configuration
+    }
+
+    pub fn get_method(&self) -> VarMethod {
+        self.method
+    }
+
+    pub fn get_base_currency(&self) -> Currency {
+        self.base_currency
+    }
+
+    pub fn get_base_data(&self) -> &MarketDataSet {
+        &self.base_data
+    }
+
+    pub fn get_instruments(&self) -> &Instruments {
+        &self.instruments
+    }
+
+    pub fn get_base_results(&self) -> &FxHashMap<StaticId, CalculationResult> {
+        &self.base_results
+    }
+
+    fn run_engine(
+        &self,
+        calculation_configuration: CalculationConfiguration,
+        data: &MarketDataSet,
+    ) -> Result<FxHashMap<StaticId, CalculationResult>> {
+        data.run_engine(
+            calculation_configuration,
+            self.match_parameter.clone(),
+            self.instruments.clone(),
+            self.instrument_categories.clone(),
+        )
+    }
+
+    /// values (and greeks in Sensitivity) on the base data
+    pub fn initialize(&mut self) -> Result<()> {
+        let calculation_configuration = match self.method {
+            VarMethod::FullRevaluation => self.calculation_configuration.clone().npv_only(),
+            VarMethod::Sensitivity => self
+                .calculation_configuration
+                .clone()
+                .npv_only()
+                .with_delta_calculation(true)
+                .with_gamma_calculation(true)
+                .with_vega_calculation(true)
+                .with_rho_calculation(true),
+        };
+        self.base_results = self
+            .run_engine(calculation_configuration, &self.base_data)
+            .with_context(|| {
+                anyhow!(
+                    "({}:{}) failed to calculate the base results",
+                    file!(),
+                    line!()
+                )
+            })?;
+        Ok(())
+    }
+
+    fn get_value(
+        results: &FxHashMap<StaticId, CalculationResult>,
+        inst_id: &StaticId,
+    ) -> Result<Real> {
+        results
+            .get(inst_id)
+            .and_then(|res| res.get_value())
+            .ok_or_else(|| anyhow!("({}:{}) value is not set for {}", file!(), line!(), inst_id))
+    }
+
+    /// instrument id -> P&L in the base currency on each shock
+    pub fn get_scenario_pnls(
+        &self,
+        shocks: &[MarketShock],
+    ) -> Result<FxHashMap<StaticId, Vec<Real>>> {
+        if self.base_results.is_empty() && !self.instruments.is_empty() {
+            return Err(anyhow!(
+                "({}:{}) ScenarioRevaluation is not initialized",
+                file!(),
+                line!(),
+            ));
+        }
+
+        let mut res: FxHashMap<StaticId, Vec<Real>> = self
+            .instruments
+            .iter()
+            .map(|inst| (inst.get_id(), Vec::with_capacity(shocks.len())))
+            .collect();
+
+        let mut base_fx_rates = FxHashMap::default();
+        for inst in self.instruments.iter() {
+            let currency = inst.get_currency();
+            if let std::collections::hash_map::Entry::Vacant(e) = base_fx_rates.entry(currency) {
+                e.insert(self.base_data.get_fx_rate(currency, self.base_currency)?);
+            }
+        }
+
+        for (scenario_id, shock) in shocks.iter().enumerate() {
+            let shocked_data = self.base_data.get_shocked(shock);
+            let scenario_results = match self.method {
+                VarMethod::FullRevaluation => Some(
+                    self.run_engine(
+                        self.calculation_configuration.clone().npv_only(),
+                        &shocked_data,
+                    )
+                    .with_context(|| {
+                        anyhow!(
+                            "({}:{}) failed to revalue the scenario {}",
+                            file!(),
+                            line!(),
+                            scenario_id,
+                        )
+                    })?,
+                ),
+                VarMethod::Sensitivity => None,
+            };
+            let predictor = TaylorPnlPredictor::new(shock.clone());
+
+            for inst in self.instruments.iter() {
+                let inst_id = inst.get_id();
+                let currency = inst.get_currency();
+                let base_value = Self::get_value(&self.base_results, &inst_id)?;
+                let scenario_value = match &scenario_results {
+                    Some(results) => Self::get_value(results, &inst_id)?,
+                    None => {
+                        let base_result = self.base_results.get(&inst_id).ok_or_else(|| {
+                            anyhow!("({}:{}) no base result for {}", file!(), line!(), inst_id)
+                        })?;
+                        base_value + predictor.predict(inst.as_ref(), base_result).get_total()
+                    }
+                };
+                let scenario_fx = shocked_data.get_fx_rate(currency, self.base_currency)?;
+                let pnl = scenario_value * scenario_fx - base_value * base_fx_rates[&currency];
+                res.get_mut(&inst_id).unwrap().push(pnl);
+            }
+        }
+        Ok(res)
+    }
+}
@@ -0,0 +1,604 @@
+use crate::currency::FxCode;
+use crate::definitions::{Real, Time};
+use crate::frtb::sbm::get_tenor_years;
+use crate::math::interpolator::{ExtraPolationType, InterpolatorReal1D};
+use crate::math::interpolators::linear_interpolator::LinearInterpolator1D;
+use crate::pricing_engines::{
+    pnl_explain::{get_nearest_index, MarketDataSet},
+    pnl_predictor::MarketShock,
+};
+use crate::time::period::Tenor;
+use crate::var::stress_test::ScenarioSet;
+//
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+use std::str::FromStr;
+
+pub const SHOCK_CSV_HEADER: &str = "scenario,risk_factor,id,tenor,strike,shock_type,value";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ShockRiskFactor {
+    Spot,
+    Curve,
+    Volatility,
+    Fx,
+}
+
+impl ShockRiskFactor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShockRiskFactor::Spot => "Spot",
+            ShockRiskFactor::Curve => "Curve",
+            ShockRiskFactor::Volatility => "Volatility",
+            ShockRiskFactor::Fx => "Fx",
+        }
+    }
+}
+
+impl FromStr for ShockRiskFactor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Spot" => Ok(ShockRiskFactor::Spot),
+            "Curve" => Ok(ShockRiskFactor::Curve),
+            "Volatility" => Ok(ShockRiskFactor::Volatility),
+            "Fx" => Ok(ShockRiskFactor::Fx),
+            _ => Err(format!("Invalid shock risk factor: {}", s)),
+        }
+    }
+}
+
+/// Absolute: the value is added, e.g., 0.01 for +100bp or +1%p of volatility, 1000.0 for +1000 of the spot
+/// Relative: the value is multiplied by the base level and added, e.g., -0.3 for -30%
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ShockType {
+    Absolute,
+    Relative,
+}
+
+impl ShockType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShockType::Absolute => "Absolute",
+            ShockType::Relative => "Relative",
+        }
+    }
+}
+
+impl FromStr for ShockType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Absolute" => Ok(ShockType::Absolute),
+            "Relative" => Ok(ShockType::Relative),
+            _ => Err(format!("Invalid shock type: {}", s)),
+        }
+    }
+}
+
+/// A line of a shock file.
+///
+/// - id: underlying id (Spot, Volatility) or curve id (Curve) as "code@venue", fx code (Fx) as "USDKRW"
+/// - tenor: curve node (Curve) or option maturity (Volatility), e.g., "1Y", None for the parallel shocks
+/// - strike: strike of the surface point (Volatility) given with the tenor
+///
+/// The shocks on the same risk factor in a scenario are added up.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShockRecord {
+    scenario: String,
+    risk_factor: ShockRiskFactor,
+    id: String,
+    #[serde(default)]
+    tenor: Option<String>,
+    #[serde(default)]
+    strike: Option<Real>,
+    shock_type: ShockType,
+    value: Real,
+}
+
+impl ShockRecord {
+    pub fn new(
+        scenario: &str,
+        risk_factor: ShockRiskFactor,
+        id: &str,
+        shock_type: ShockType,
+        value: Real,
+    ) -> ShockRecord {
+        ShockRecord {
+            scenario: scenario.to_string(),
+            risk_factor,
+            id: id.to_string(),
+            tenor: None,
+            strike: None,
+            shock_type,
+            value,
+        }
+    }
+
+    pub fn with_tenor(mut self, tenor: &str) -> ShockRecord {
+        self.tenor = Some(tenor.to_string());
+        self
+    }
+
+    pub fn with_strike(mut self, strike: Real) -> ShockRecord {
+        self.strike = Some(strike);
+        self
+    }
+
+    pub fn get_scenario(&self) -> &String {
+        &self.scenario
+    }
+
+    pub fn get_risk_factor(&self) -> ShockRiskFactor {
+        self.risk_factor
+    }
+
+    pub fn get_id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn get_tenor(&self) -> Option<&String> {
+        self.tenor.as_ref()
+    }
+
+    pub fn get_strike(&self) -> Option<Real> {
+        self.strike
+    }
+
+    pub fn get_shock_type(&self) -> ShockType {
+        self.shock_type
+    }
+
+    pub fn get_value(&self) -> Real {
+        self.value
+    }
+
+    fn get_tenor_years(&self) -> Result<Option<Time>> {
+        match &self.tenor {
+            None => Ok(None),
+            Some(tenor) => {
+                let tenor = Tenor::new_from_string(tenor)?;
+                Ok(Some(get_tenor_years(&[tenor])[0]))
+            }
+        }
+    }
+
+    /// the shock with this record added, where the relative shocks are converted on the base data
+    fn add_to_shock(&self, shock: MarketShock, base_data: &MarketDataSet) -> Result<MarketShock> {
+        let tenor = self.get_tenor_years()?;
+        let value = self.value;
+        match self.risk_factor {
+            ShockRiskFactor::Spot => {
+                self.check_no_node()?;
+                let id = StaticId::from_combined_str(&self.id);
+                let spot_return = match self.shock_type {
+                    ShockType::Relative => value,
+                    ShockType::Absolute => {
+                        let spot = base_data.get_stock_data().get(&id).ok_or_else(|| {
+                            anyhow!("({}:{}) no spot of {}", file!(), line!(), id)
+                        })?;
+                        value / spot.get_value()
+                    }
+                };
+                let current = shock.get_spot_returns().get(&id).copied().unwrap_or(0.0);
+                Ok(shock.with_spot_return(id, current + spot_return))
+            }
+            ShockRiskFactor::Fx => {
+                self.check_no_node()?;
+                if self.id.len() != 6 {
+                    return Err(anyhow!(
+                        "({}:{}) fx code must be like USDKRW, but {} is given",
+                        file!(),
+                        line!(),
+                        self.id,
+                    ));
+                }
+                let fx_code = FxCode::from(self.id.as_str());
+                let fx_return = match self.shock_type {
+                    ShockType::Relative => value,
+                    ShockType::Absolute => {
+                        value
+                            / base_data
+                                .get_fx_rate(fx_code.get_currency1(), fx_code.get_currency2())?
+                    }
+                };
+                let current = shock.get_fx_returns().get(&fx_code).copied().unwrap_or(0.0);
+                Ok(shock.with_fx_return(fx_code, current + fx_return))
+            }
+            ShockRiskFactor::Curve => {
+                let id = StaticId::from_combined_str(&self.id);
+                match (self.shock_type, tenor) {
+                    (ShockType::Absolute, None) => {
+                        let current = shock.get_rate_changes().get(&id).copied().unwrap_or(0.0);
+                        Ok(shock.with_rate_change(id, current + value))
+                    }
+                    (ShockType::Absolute, Some(t)) => Ok(shock.with_rate_node_change(id, t, value)),
+                    (ShockType::Relative, _) => {
+                        let curve = base_data.get_curve_data().get(&id).ok_or_else(|| {
+                            anyhow!("({}:{}) no curve of {}", file!(), line!(), id)
+                        })?;
+                        match tenor {
+                            None => Ok(curve
+                                .times
+                                .iter()
+                                .zip(curve.value.iter())
+                                .fold(shock, |s, (t, rate)| {
+                                    s.with_rate_node_change(id, *t, rate * value)
+                                })),
+                            Some(t) => {
+                                let rate = LinearInterpolator1D::new(
+                                    curve.times.clone(),
+                                    curve.value.clone(),
+                                    ExtraPolationType::Flat,
+                                    true,
+                                )?
+                                .interpolate(t)?;
+                                Ok(shock.with_rate_node_change(id, t, rate * value))
+                            }
+                        }
+                    }
+                }
+            }
+            ShockRiskFactor::Volatility => {
+                let id = StaticId::from_combined_str(&self.id);
+                let point = match (tenor, self.strike) {
+                    (Some(t), Some(k)) => Some((t, k)),
+                    (None, None) => None,
+                    _ => {
+                        return Err(anyhow!(
+                            "({}:{}) both tenor and strike are needed for a volatility point of {}",
+                            file!(),
+                            line!(),
+                            self.id,
+                        ))
+                    }
+                };
+                if self.shock_type == ShockType::Absolute {
+                    return Ok(match point {
+                        None => {
+                            let current = shock
+                                .get_volatility_changes()
+                                .get(&id)
+                                .copied()
+                                .unwrap_or(0.0);
+                            shock.with_volatility_change(id, current + value)
+                        }
+                        Some((t, k)) => shock.with_volatility_node_change(id, t, k, value),
+                    });
+                }
+                if let Some(vol) = base_data.get_equity_constant_volatility_data().get(&id) {
+                    let current = shock
+                        .get_volatility_changes()
+                        .get(&id)
+                        .copied()
+                        .unwrap_or(0.0);
+                    return Ok(shock.with_volatility_change(id, current + vol.get_value() * value));
+                }
+                let surface = base_data
+                    .get_equity_volatility_surface_data()
+                    .get(&id)
+                    .ok_or_else(|| anyhow!("({}:{}) no volatility of {}", file!(), line!(), id))?;
+                let evaluation_datetime = base_data.get_evaluation_datetime();
+                let maturities: Vec<Time> = surface
+                    .dates
+                    .iter()
+                    .map(|dt| {
+                        (*dt - evaluation_datetime).whole_seconds() as Time / 86_400.0 / 365.0
+                    })
+                    .collect();
+                let mut res = shock;
+                for (row, maturity) in maturities.iter().enumerate() {
+                    for (col, strike) in surface.strikes.iter().enumerate() {
+                        let selected = match point {
+                            None => true,
+                            Some((t, k)) => {
+                                get_nearest_index(&maturities, t) == Some(row)
+                                    && get_nearest_index(surface.strikes.as_slice().unwrap_or(&[]), k)
+                                        == Some(col)
+                            }
+                        };
+                        if selected {
+                            res = res.with_volatility_node_change(
+                                id,
+                                *maturity,
+                                *strike,
+                                surface.value[[row, col]] * value,
+                            );
+                        }
+                    }
+                }
+                Ok(res)
+            }
+        }
+    }
+
+    fn check_no_node(&self) -> Result<()> {
+        if self.tenor.is_some() || self.strike.is_some() {
+            return Err(anyhow!(
+                "({}:{}) tenor and strike are not used for the {} shock of {}",
+                file!(),
+                line!(),
+                self.risk_factor.as_str(),
+                self.id,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Prescribed scenarios, e.g., of a regulator, in a json or csv file.
+///
+/// The json file is the serialized ShockFile, and the csv file has a line per ShockRecord
+/// with the header SHOCK_CSV_HEADER, where the empty tenor and strike are None, e.g.,
+///
+/// ```text
+/// scenario,risk_factor,id,tenor,strike,shock_type,value
+/// Equity crash,Spot,KOSPI2@KRX,,,Relative,-0.3
+/// Equity crash,Volatility,KOSPI2@KRX,,,Absolute,0.05
+/// Steepening,Curve,KRWIRS@DataProvider,10Y,,Absolute,0.005
+/// ```
+///
+/// `to_scenario_set` converts the records into the MarketShocks of a ScenarioSet
+/// for StressTest and ScenarioRevaluation in the order of the scenarios in the file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShockFile {
+    name: String,
+    records: Vec<ShockRecord>,
+}
+
+impl ShockFile {
+    pub fn new(name: &str) -> ShockFile {
+        ShockFile {
+            name: name.to_string(),
+            records: vec![],
+        }
+    }
+
+    pub fn with_record(mut self, record: ShockRecord) -> ShockFile {
+        self.records.push(record);
+        self
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_records(&self) -> &Vec<ShockRecord> {
+        &self.records
+    }
+
+    pub fn from_json(json: &str) -> Result<ShockFile> {
+        serde_json::from_str(json)
+            .with_context(|| anyhow!("({}:{}) failed to parse the shock file", file!(), line!()))
+    }
+
+    pub fn from_csv(name: &str, csv: &str) -> Result<ShockFile> {
+        let mut lines = csv
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        match lines.next() {
+            Some((_, header)) if header.trim() == SHOCK_CSV_HEADER => {}
+            _ => {
+                return Err(anyhow!(
+                    "({}:{}) the header of the shock file must be {}",
+                    file!(),
+                    line!(),
+                    SHOCK_CSV_HEADER,
+                ))
+            }
+        }
+
+        let mut res = ShockFile::new(name);
+        for (line_number, line) in lines {
+            let err = |msg: String| {
+                anyhow!(
+                    "({}:{}) line {} of the shock file {}: {}",
+                    file!(),
+                    line!(),
+                    line_number + 1,
+                    name,
+                    msg,
+                )
+            };
+            let columns: Vec<&str> = line.split(',').map(|c| c.trim()).collect();
+            if columns.len() != 7 {
+                return Err(err(format!("7 columns are expected in {}", line)));
+            }
+            let strike = match columns[4] {
+                "" => None,
+                s => Some(s.parse::<Real>().map_err(|e| err(e.to_string()))?),
+            };
+            res.records.push(ShockRecord {
+                scenario: columns[0].to_string(),
+                risk_factor: ShockRiskFactor::from_str(columns[1]).map_err(err)?,
+                id: columns[2].to_string(),
+                tenor: (!columns[3].is_empty()).then(|| columns[3].to_string()),
+                strike,
+                shock_type: ShockType::from_str(columns[5]).map_err(err)?,
+                value: columns[6].parse::<Real>().map_err(|e| err(e.to_string()))?,
+            });
+        }
+        Ok(res)
+    }
+
+    /// reads a csv file by the extension, otherwise a json file
+    pub fn from_file(path: &str) -> Result<ShockFile> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| anyhow!("({}:{}) failed to read {}", file!(), line!(), path))?;
+        match path.to_lowercase().ends_with(".csv") {
+            true => {
+                let name = std::path::Path::new(path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                ShockFile::from_csv(&name, &contents)
+            }
+            false => ShockFile::from_json(&contents),
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut res = String::from(SHOCK_CSV_HEADER);
+        for record in self.records.iter() {
+            res.push_str(&format!(
+                "\n{},{},{},{},{},{},{}",
+                record.scenario,
+                record.risk_factor.as_str(),
+                record.id,
+                record.tenor.clone().unwrap_or_default(),
+                record.strike.map(|k| k.to_string()).unwrap_or_default(),
+                record.shock_type.as_str(),
+                record.value,
+            ));
+        }
+        res
+    }
+
+    /// names of the scenarios in the order of appearance
+    pub fn get_scenario_names(&self) -> Vec<String> {
+        let mut res: Vec<String> = vec![];
+        for record in self.records.iter() {
+            if !res.contains(&record.scenario) {
+                res.push(record.scenario.clone());
+            }
+        }
+        res
+    }
+
+    pub fn to_scenario_set(&self, base_data: &MarketDataSet) -> Result<ScenarioSet> {
+        let mut res = ScenarioSet::new(self.name.clone());
+        for name in self.get_scenario_names() {
+            let mut shock = MarketShock::new();
+            for record in self.records.iter().filter(|r| r.scenario == name) {
+                shock = record.add_to_shock(shock, base_data).with_context(|| {
+                    anyhow!(
+                        "({}:{}) failed to convert the shock on {} in {}",
+                        file!(),
+                        line!(),
+                        record.id,
+                        name,
+                    )
+                })?;
+            }
+            res = res.with_scenario(&name, shock)?;
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::data::{surface_data::SurfaceData, value_data::ValueData, vector_data::VectorData};
+    use ndarray::{array, Array1};
+    use rustc_hash::FxHashMap;
+    use time::{macros::datetime, Duration};
+
+    #[test]
+    fn test_shock_file() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let curve_id = StaticId::from_str("KRWIRS", "DataProvider");
+        let csv = "scenario,risk_factor,id,tenor,strike,shock_type,value
+            Crash,Spot,KOSPI2@KRX,,,Relative,-0.3
+            Crash,Spot,KOSPI2@KRX,,,Absolute,-35.0
+            Crash,Volatility,KOSPI2@KRX,1Y,350,Absolute,0.1
+            Crash,Fx,USDKRW,,,Absolute,130.0
+
+            Rates,Curve,KRWIRS@DataProvider,,,Absolute,0.01
+            Rates,Curve,KRWIRS@DataProvider,1Y,,Absolute,0.002
+            Rates,Curve,KRWIRS@DataProvider,5Y,,Relative,0.5";
+        let file = ShockFile::from_csv("regulatory", csv)?;
+        assert_eq!(file.get_records().len(), 7);
+        assert_eq!(file.get_records()[2].get_strike(), Some(350.0));
+        let reparsed = ShockFile::from_csv("regulatory", &file.to_csv())?;
+        assert_eq!(reparsed, file);
+        let json = serde_json::to_string(&file)?;
+        assert_eq!(ShockFile::from_json(&json)?, file);
+
+        let mut stock_map = FxHashMap::default();
+        stock_map.insert(
+            und_id,
+            ValueData::new(350.0, Some(dt), Currency::KRW, "KOSPI2".to_string(), und_id)?,
+        );
+        let mut curve_map = FxHashMap::default();
+        curve_map.insert(
+            curve_id,
+            VectorData::new(
+                array![0.03, 0.04],
+                None,
+                Some(Array1::from(vec![1.0, 5.0])),
+                Some(dt),
+                Currency::KRW,
+                "KRWIRS".to_string(),
+                curve_id,
+            )?,
+        );
+        let mut surface_map = FxHashMap::default();
+        surface_map.insert(
+            und_id,
+            SurfaceData::new(
+                None,
+                array![[0.2, 0.2], [0.2, 0.2]],
+                vec![dt + Duration::days(182), dt + Duration::days(365)],
+                array![300.0, 350.0],
+                Some(dt),
+                Currency::KRW,
+                "KOSPI2".to_string(),
+                und_id,
+            ),
+        );
+        let mut fx_map = FxHashMap::default();
+        let fx_id = StaticId::from_str("USDKRW", "DataProvider");
+        fx_map.insert(
+            FxCode::new(Currency::USD, Currency::KRW),
+            ValueData::new(1300.0, Some(dt), Currency::KRW, "USDKRW".to_string(), fx_id)?,
+        );
+        let base_data = MarketDataSet::new(dt).with_data(
+            fx_map,
+            stock_map,
+            curve_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+            surface_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+        );
+
+        let scenario_set = file.to_scenario_set(&base_data)?;
+        assert_eq!(scenario_set.get_scenario_names(), vec!["Crash", "Rates"]);
+        let crash = scenario_set.get_scenario("Crash").unwrap().get_shock();
+        // -30% and -35 / 350
+        assert!((crash.get_spot_returns()[&und_id] + 0.4).abs() < 1.0e-6);
+        assert!(
+            (crash.get_fx_returns()[&FxCode::new(Currency::USD, Currency::KRW)] - 0.1).abs()
+                < 1.0e-6
+        );
+        let shocked = base_data.get_shocked(crash);
+        let surface = &shocked.get_equity_volatility_surface_data()[&und_id];
+        assert!((surface.value[[1, 1]] - 0.3).abs() < 1.0e-6);
+        assert!((surface.value[[0, 1]] - 0.2).abs() < 1.0e-6);
+        assert!((shocked.get_stock_data()[&und_id].get_value() - 210.0).abs() < 1.0e-3);
+
+        // 1% parallel, and the nodes 0.2% on 1Y and 2% (= 4% * 0.5) on 5Y
+        let rates = scenario_set.get_scenario("Rates").unwrap().get_shock();
+        assert_eq!(rates.get_rate_node_changes()[&curve_id].len(), 2);
+        let shocked = base_data.get_shocked(rates);
+        let curve = &shocked.get_curve_data()[&curve_id];
+        assert!((curve.value[0] - 0.042).abs() < 1.0e-6);
+        assert!((curve.value[1] - 0.07).abs() < 1.0e-6);
+
+        let invalid = "scenario,risk_factor,id,tenor,strike,shock_type,value
+            Crash,Spot,KOSPI2@KRX,1Y,,Relative,-0.3";
+        let invalid = ShockFile::from_csv("invalid", invalid)?;
+        assert!(invalid.to_scenario_set(&base_data).is_err());
+        assert!(ShockFile::from_csv("invalid", "Crash,Spot,KOSPI2@KRX,,,Relative,-0.3").is_err());
+        Ok(())
+    }
+}
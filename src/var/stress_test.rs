@@ -0,0 +1,227 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::instrument::InstrumentTrait;
+use crate::pricing_engines::pnl_predictor::MarketShock;
+use crate::var::scenario_revaluation::ScenarioRevaluation;
+//
+use anyhow::{anyhow, Result};
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+
+/// A named market shock, e.g., "KOSPI2 -30%" or "KRW curves +200bp"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StressScenario {
+    name: String,
+    shock: MarketShock,
+}
+
+impl StressScenario {
+    pub fn new(name: String, shock: MarketShock) -> StressScenario {
+        StressScenario { name, shock }
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_shock(&self) -> &MarketShock {
+        &self.shock
+    }
+}
+
+/// Ordered set of stress scenarios with unique names.
+/// It is serializable so that the scenarios can be kept in a json file, e.g.,
+///
+/// ```json
+/// {
+///     "name": "Monthly stress",
+///     "scenarios": [
+///         {
+///             "name": "Equity crash",
+///             "shock": {
+///                 "spot_returns": { "KOSPI2@KRX": -0.3 },
+///                 "volatility_changes": { "KOSPI2@KRX": 0.05 },
+///                 "rate_changes": {},
+///                 "fx_returns": {},
+///                 "days": 0.0
+///             }
+///         }
+///     ]
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioSet {
+    name: String,
+    scenarios: Vec<StressScenario>,
+}
+
+impl ScenarioSet {
+    pub fn new(name: String) -> ScenarioSet {
+        ScenarioSet {
+            name,
+            scenarios: vec![],
+        }
+    }
+
+    pub fn with_scenario(mut self, name: &str, shock: MarketShock) -> Result<ScenarioSet> {
+        if self.get_scenario(name).is_some() {
+            return Err(anyhow!(
+                "({}:{}) scenario {} is already in the scenario set {}",
+                file!(),
+                line!(),
+                name,
+                self.name,
+            ));
+        }
+        self.scenarios
+            .push(StressScenario::new(name.to_string(), shock));
+        Ok(self)
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_scenarios(&self) -> &Vec<StressScenario> {
+        &self.scenarios
+    }
+
+    pub fn get_scenario(&self, name: &str) -> Option<&StressScenario> {
+        self.scenarios.iter().find(|scenario| scenario.name == name)
+    }
+
+    pub fn get_scenario_names(&self) -> Vec<String> {
+        self.scenarios
+            .iter()
+            .map(|scenario| scenario.name.clone())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenarios.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenarios.is_empty()
+    }
+}
+
+/// P&L of each instrument (row) on each scenario (column) in the currency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioPnlMatrix {
+    currency: Currency,
+    scenario_names: Vec<String>,
+    instrument_ids: Vec<StaticId>,
+    pnls: Array2<Real>,
+}
+
+impl ScenarioPnlMatrix {
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn get_scenario_names(&self) -> &Vec<String> {
+        &self.scenario_names
+    }
+
+    pub fn get_instrument_ids(&self) -> &Vec<StaticId> {
+        &self.instrument_ids
+    }
+
+    /// rows in the order of get_instrument_ids and columns in the order of get_scenario_names
+    pub fn get_pnls(&self) -> &Array2<Real> {
+        &self.pnls
+    }
+
+    pub fn get_pnl(&self, inst_id: &StaticId, scenario_name: &str) -> Option<Real> {
+        let row = self.instrument_ids.iter().position(|id| id == inst_id)?;
+        let col = self
+            .scenario_names
+            .iter()
+            .position(|name| name == scenario_name)?;
+        Some(self.pnls[[row, col]])
+    }
+
+    /// P&L of the portfolio on each scenario
+    pub fn get_portfolio_pnls(&self) -> Vec<Real> {
+        self.pnls
+            .columns()
+            .into_iter()
+            .map(|col| col.sum())
+            .collect()
+    }
+
+    /// the scenario with the largest portfolio loss and its P&L
+    pub fn get_worst_scenario(&self) -> Option<(&String, Real)> {
+        self.scenario_names
+            .iter()
+            .zip(self.get_portfolio_pnls())
+            .min_by(|(_, x), (_, y)| x.total_cmp(y))
+    }
+}
+
+/// Revalues the portfolio on each scenario of a ScenarioSet by ScenarioRevaluation.
+/// The shocks are applied to the base data as in the VaR scenarios,
+/// so a large move such as -30% should be run in FullRevaluation.
+pub struct StressTest {
+    revaluation: ScenarioRevaluation,
+    scenario_set: ScenarioSet,
+    //
+    result: Option<ScenarioPnlMatrix>,
+}
+
+impl StressTest {
+    pub fn new(revaluation: ScenarioRevaluation, scenario_set: ScenarioSet) -> StressTest {
+        StressTest {
+            revaluation,
+            scenario_set,
+            result: None,
+        }
+    }
+
+    pub fn calculate(&mut self) -> Result<()> {
+        let shocks: Vec<MarketShock> = self
+            .scenario_set
+            .get_scenarios()
+            .iter()
+            .map(|scenario| scenario.get_shock().clone())
+            .collect();
+
+        self.revaluation.initialize()?;
+        let instrument_pnls = self.revaluation.get_scenario_pnls(&shocks)?;
+
+        let instrument_ids: Vec<StaticId> = self
+            .revaluation
+            .get_instruments()
+            .iter()
+            .map(|inst| inst.get_id())
+            .collect();
+        let mut pnls = Array2::<Real>::zeros((instrument_ids.len(), shocks.len()));
+        for (row, inst_id) in instrument_ids.iter().enumerate() {
+            for (col, pnl) in instrument_pnls[inst_id].iter().enumerate() {
+                pnls[[row, col]] = *pnl;
+            }
+        }
+
+        self.result = Some(ScenarioPnlMatrix {
+            currency: self.revaluation.get_base_currency(),
+            scenario_names: self.scenario_set.get_scenario_names(),
+            instrument_ids,
+            pnls,
+        });
+        Ok(())
+    }
+
+    pub fn get_scenario_set(&self) -> &ScenarioSet {
+        &self.scenario_set
+    }
+
+    pub fn get_revaluation(&self) -> &ScenarioRevaluation {
+        &self.revaluation
+    }
+
+    pub fn get_result(&self) -> Option<&ScenarioPnlMatrix> {
+        self.result.as_ref()
+    }
+}
@@ -0,0 +1,180 @@
+use crate::currency::Currency;
+use crate::definitions::Real;
+use crate::var::risk_statistics::RiskStatistics;
+//
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use static_id::static_id::StaticId;
+
+/// indices of the ceil(n * (1 - α)) worst P&Ls from the worst, where pnls must not be empty
+pub(crate) fn get_tail_scenarios(pnls: &[Real], confidence_level: Real) -> Vec<usize> {
+    let scenario_number = pnls.len();
+    let mut order: Vec<usize> = (0..scenario_number).collect();
+    order.sort_by(|&i, &j| pnls[i].total_cmp(&pnls[j]));
+    // the tolerance absorbs the floating point error of 1 - confidence_level
+    let tail_size = scenario_number as Real * (1.0 - confidence_level);
+    let tail_number = ((tail_size - 1.0e-4).ceil() as usize).clamp(1, scenario_number);
+    order.truncate(tail_number);
+    order
+}
+
+/// VaR and expected shortfall of a portfolio on the scenario P&Ls, reported as positive losses.
+///
+/// With n scenarios and the confidence level α, the tail is the k = ceil(n * (1 - α)) worst scenarios.
+/// VaR is the loss of the k-th worst scenario and ES is the average loss of the tail.
+/// The contribution of an instrument is its loss in the VaR scenario (VaR contribution)
+/// and its average loss in the tail (ES contribution), so that they add up to the VaR and ES.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VarResult {
+    confidence_level: Real,
+    currency: Currency,
+    var: Real,
+    expected_shortfall: Real,
+    portfolio_pnls: Vec<Real>,
+    var_contributions: FxHashMap<StaticId, Real>,
+    es_contributions: FxHashMap<StaticId, Real>,
+}
+
+impl VarResult {
+    /// instrument_pnls: instrument id -> P&L on each scenario in the currency
+    pub fn from_scenario_pnls(
+        confidence_level: Real,
+        currency: Currency,
+        instrument_pnls: &FxHashMap<StaticId, Vec<Real>>,
+    ) -> Result<VarResult> {
+        if confidence_level <= 0.0 || confidence_level >= 1.0 {
+            return Err(anyhow!(
+                "({}:{}) confidence level must be in (0, 1), but {} is given",
+                file!(),
+                line!(),
+                confidence_level,
+            ));
+        }
+
+        let scenario_number = instrument_pnls
+            .values()
+            .map(|pnls| pnls.len())
+            .max()
+            .unwrap_or(0);
+        if scenario_number == 0 {
+            return Err(anyhow!(
+                "({}:{}) no scenario P&L is given",
+                file!(),
+                line!()
+            ));
+        }
+
+        // summed in the order of the ids for the same portfolio P&Ls across runs
+        let mut inst_ids: Vec<&StaticId> = instrument_pnls.keys().collect();
+        inst_ids.sort_by_key(|id| id.to_string());
+        let mut portfolio_pnls = vec![0.0; scenario_number];
+        for (inst_id, pnls) in inst_ids.iter().map(|id| (id, &instrument_pnls[*id])) {
+            if pnls.len() != scenario_number {
+                return Err(anyhow!(
+                    "({}:{}) {} has {} scenario P&Ls, but {} are expected",
+                    file!(),
+                    line!(),
+                    inst_id,
+                    pnls.len(),
+                    scenario_number,
+                ));
+            }
+            for (total, pnl) in portfolio_pnls.iter_mut().zip(pnls.iter()) {
+                *total += pnl;
+            }
+        }
+
+        let tail = get_tail_scenarios(&portfolio_pnls, confidence_level);
+        let tail_number = tail.len();
+        let var_scenario = tail[tail_number - 1];
+
+        let var = -portfolio_pnls[var_scenario];
+        let expected_shortfall =
+            -tail.iter().map(|&i| portfolio_pnls[i]).sum::<Real>() / tail_number as Real;
+
+        let mut var_contributions = FxHashMap::default();
+        let mut es_contributions = FxHashMap::default();
+        for (inst_id, pnls) in instrument_pnls.iter() {
+            var_contributions.insert(*inst_id, -pnls[var_scenario]);
+            es_contributions.insert(
+                *inst_id,
+                -tail.iter().map(|&i| pnls[i]).sum::<Real>() / tail_number as Real,
+            );
+        }
+
+        Ok(VarResult {
+            confidence_level,
+            currency,
+            var,
+            expected_shortfall,
+            portfolio_pnls,
+            var_contributions,
+            es_contributions,
+        })
+    }
+
+    pub fn get_confidence_level(&self) -> Real {
+        self.confidence_level
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn get_var(&self) -> Real {
+        self.var
+    }
+
+    pub fn get_expected_shortfall(&self) -> Real {
+        self.expected_shortfall
+    }
+
+    pub fn get_portfolio_pnls(&self) -> &Vec<Real> {
+        &self.portfolio_pnls
+    }
+
+    pub fn get_var_contributions(&self) -> &FxHashMap<StaticId, Real> {
+        &self.var_contributions
+    }
+
+    pub fn get_es_contributions(&self) -> &FxHashMap<StaticId, Real> {
+        &self.es_contributions
+    }
+
+    /// moments and VaR/ES of the portfolio P&Ls at the given confidence levels
+    pub fn get_risk_statistics(&self, confidence_levels: &[Real]) -> Result<RiskStatistics> {
+        RiskStatistics::from_pnls(self.currency, &self.portfolio_pnls, confidence_levels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_var_result() -> Result<()> {
+        let id1 = StaticId::from_str("A", "test");
+        let id2 = StaticId::from_str("B", "test");
+        // A: -50, -49, ..., 49 and B loses 10 only in the first scenario
+        let pnls1: Vec<Real> = (0..100).map(|i| (i as Real) - 50.0).collect();
+        let pnls2: Vec<Real> = (0..100).map(|i| if i == 0 { -10.0 } else { 0.0 }).collect();
+        let mut instrument_pnls = FxHashMap::default();
+        instrument_pnls.insert(id1, pnls1);
+        instrument_pnls.insert(id2, pnls2);
+
+        let result = VarResult::from_scenario_pnls(0.95, Currency::KRW, &instrument_pnls)?;
+        // the worst five: -60, -49, -48, -47, -46
+        assert_eq!(result.get_var(), 46.0);
+        assert_eq!(result.get_expected_shortfall(), 50.0);
+        assert_eq!(result.get_var_contributions()[&id1], 46.0);
+        assert_eq!(result.get_var_contributions()[&id2], 0.0);
+        assert_eq!(result.get_es_contributions()[&id1], 48.0);
+        assert_eq!(result.get_es_contributions()[&id2], 2.0);
+
+        assert!(VarResult::from_scenario_pnls(1.0, Currency::KRW, &instrument_pnls).is_err());
+        instrument_pnls.insert(id2, vec![0.0]);
+        assert!(VarResult::from_scenario_pnls(0.99, Currency::KRW, &instrument_pnls).is_err());
+        Ok(())
+    }
+}
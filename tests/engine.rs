@@ -1,12 +1,17 @@
 #[cfg(test)]
 mod tests {
+    use rustmetrics::data::daily_value_data::DailyValueData;
     use rustmetrics::data::value_data::ValueData;
     use rustmetrics::data::vector_data::VectorData;
     use rustmetrics::definitions::Real;
-    use rustmetrics::enums::{OptionDailySettlementType, OptionExerciseType, OptionType};
+    use rustmetrics::enums::{
+        CurveRole, FiniteDifferenceScheme, GreekMethod, MarkingSide, OptionDailySettlementType,
+        OptionExerciseType, OptionType, RiskFactorClass, ThetaDayMode,
+    };
     use rustmetrics::instrument::{Instrument, Instruments};
     use rustmetrics::instruments::{
-        bond::Bond, cash::Cash, futures::Futures, stock::Stock, vanilla_option::VanillaOption,
+        bond::Bond, cash::Cash, cashflow::CashflowType, futures::Futures, stock::Stock,
+        vanilla_option::VanillaOption,
     };
     use rustmetrics::{
         Currency,
@@ -15,7 +20,10 @@ mod tests {
     use rustmetrics::pricing_engines::engine_generator::{EngineGenerator, InstrumentCategory};
     use rustmetrics::pricing_engines::match_parameter::MatchParameter;
     use rustmetrics::pricing_engines::{
-        calculation_configuration::CalculationConfiguration, calculation_result::CalculationResult,
+        calculation_configuration::{
+            BumpSize, CalculationConfiguration, PayoffSmoothing, VegaMatrixGrid,
+        },
+        calculation_result::CalculationResult,
     };
     use rustmetrics::time::calendar::Calendar;
     use rustmetrics::time::calendars::{southkorea::SouthKorea, southkorea::SouthKoreaType};
@@ -26,6 +34,7 @@ mod tests {
     use rustmetrics::{
         InstInfo,
         InstType,
+        Tenor,
         CreditRating,
         IssuerType,
         RankType,
@@ -36,21 +45,33 @@ mod tests {
     use ndarray::array;
     use ndarray::Array1;
     use rustc_hash::FxHashMap;
-    use std::rc::Rc;
-    use time::{macros::datetime, Duration};
+    use std::sync::{Arc, OnceLock};
+    use time::{macros::datetime, Duration, OffsetDateTime};
     use flashlog::{get_unix_nano, flash_info};
     use static_id::static_id::StaticId;
 
+    // the logger is process wide and closes when its guard drops,
+    // so the guard is kept for the whole test binary
+    static LOGGER: OnceLock<flashlog::logger::LoggerGuard> = OnceLock::new();
+
+    fn init_logger() -> Result<()> {
+        if LOGGER.get().is_none() {
+            let guard = flashlog::Logger::initialize()
+                .with_file("logs", "tests-engine")?
+                .with_console_report(true)
+                .with_msg_buffer_size(1_000_000)
+                .with_msg_flush_interval(1_000_000)
+                .with_max_log_level(flashlog::LogLevel::Info)
+                .with_timezone(flashlog::TimeZone::Local)
+                .launch();
+            let _ = LOGGER.set(guard);
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_engine() -> Result<()> {
-        let _logger = flashlog::Logger::initialize()
-            .with_file("logs", "tests-engine")?
-            .with_console_report(true)
-            .with_msg_buffer_size(1_000_000)
-            .with_msg_flush_interval(1_000_000)
-            .with_max_log_level(flashlog::LogLevel::Info)
-            .with_timezone(flashlog::TimeZone::Local)
-            .launch();
+        init_logger()?;
 
         flash_info!("EngineRun");
         let theta_day = 100;
@@ -69,7 +90,7 @@ mod tests {
         ];
 
         let times = None;
-        let market_datetime = dt.clone();
+        let market_datetime = dt;
         let zero_curve1_id = StaticId::from_str("KSD", "DataProvider");
         let zero_curve_data1 = VectorData::new(
             &value - 0.0005,
@@ -191,7 +212,9 @@ mod tests {
             stock_name,
             stock_code,
         )
-        .expect("failed to make a stock data");
+        .expect("failed to make a stock data")
+        .with_bid_ask(spot - 0.5, spot + 0.5)
+        .expect("failed to set bid and ask of the stock data");
 
         let mut stock_data_map = FxHashMap::default();
         stock_data_map.insert(stock_code, stock_data.clone());
@@ -260,8 +283,8 @@ mod tests {
 
         let bond1_inst_info = InstInfo {
             id: bond_code,
-            issue_date: Some(bond1_issuedate.clone()),
-            maturity: Some(bond1_maturity.clone()),
+            issue_date: Some(bond1_issuedate),
+            maturity: Some(bond1_maturity),
             currency: bond1_currency,
             inst_type: InstType::Bond,
             unit_notional: 10_000.0,
@@ -310,8 +333,8 @@ mod tests {
 
         let bond2_inst_info = InstInfo {
             id: bond2_code,
-            issue_date: Some(bond2_issuedate.clone()),
-            maturity: Some(bond2_maturity.clone()),
+            issue_date: Some(bond2_issuedate),
+            maturity: Some(bond2_maturity),
             currency: bond2_currency2,
             inst_type: InstType::Bond,
             unit_notional: 10_000.0,
@@ -326,12 +349,12 @@ mod tests {
             rank: bond2_rank,
         };
 
-        dbg!(dt.clone());
+        dbg!(dt);
         let bond2 = Bond::new_from_conventions(
             bond2_inst_info,
             bond2_bond_info,
             false,
-            Some(bond2_issuedate.clone()),
+            Some(bond2_issuedate),
             None,
             None,
             Some(0.0425),
@@ -418,13 +441,13 @@ mod tests {
         let inst7 = Instrument::Stock(stock);
 
         let inst_vec = vec![
-            Rc::new(inst1),
-            Rc::new(inst2),
-            Rc::new(inst3),
-            Rc::new(inst4),
-            Rc::new(inst5),
-            Rc::new(inst6),
-            Rc::new(inst7),
+            Arc::new(inst1),
+            Arc::new(inst2),
+            Arc::new(inst3),
+            Arc::new(inst4),
+            Arc::new(inst5),
+            Arc::new(inst6),
+            Arc::new(inst7),
         ];
 
         // make a calculation configuration
@@ -437,8 +460,16 @@ mod tests {
             .with_vega_structure_calculation(true)
             .with_div_delta_calculation(true)
             .with_rho_structure_calculation(true)
+            .with_gamma_structure_calculation(true)
             .with_div_structure_calculation(true)
             .with_vega_matrix_calculation(true)
+            .with_carry_roll_down_calculation(true)
+            .with_bid_ask_adjustment_calculation(true)
+            .with_bond_analytics_calculation(true)
+            .with_key_rate_dv01_calculation(true)
+            .with_theta_decomposition_calculation(true)
+            .with_rho_by_role_calculation(true)
+            .with_marking_side(MarkingSide::Conservative)
             .with_theta_day(theta_day);
 
         // make a match parameter
@@ -504,7 +535,7 @@ mod tests {
 
         let mut engine_builder = EngineGenerator::builder();
         let engine_generator = engine_builder
-            .with_configuration(calculation_configuration, dt.clone(), match_parameter)?
+            .with_configuration(calculation_configuration, dt, match_parameter)?
             .with_instruments(Instruments::new(inst_vec))?
             .with_instrument_categories(instrument_categories)?
             .with_data(
@@ -529,15 +560,14 @@ mod tests {
         let calculation_results: &FxHashMap<StaticId, CalculationResult> =
             engine_generator.get_calculation_results();
 
-        let key_npv = vec![
-            (bond_code, 0.99930),
-            (stock_futures1_id, 349.466208),
-            (stock_futures2_id, 356.310592),
-            (option1_id, 1.3148708),
+        let key_npv = [(bond_code, 0.99930),
+            (stock_futures1_id, 349.466_22),
+            (stock_futures2_id, 356.310_58),
+            // the option loses a digit in f32
+            (option1_id, if cfg!(feature = "f64") { 1.314_861_2 } else { 1.314_870_8 }),
             (cash_code, 1.0),
             (stock_code, 350.0),
-            (bond2_code, 1.0254111),
-        ];
+            (bond2_code, 1.0254111)];
 
         for (key, npv) in key_npv.iter() {
             let result = calculation_results
@@ -549,7 +579,7 @@ mod tests {
                 .get_npv();
             
             assert!(
-                (npv - npv_comp).abs() < 1e-6,
+                (npv - npv_comp).abs() < 1e-6 * npv.abs().max(1.0),
                 "npv comparison failed for key {}: expected {}, got {}",
                 key,
                 npv,
@@ -557,8 +587,169 @@ mod tests {
             );
         }
 
+        // carry and roll-down are only for bonds and swaps
+        for key in [bond_code, bond2_code].iter() {
+            let result = calculation_results.get(key).unwrap();
+            assert!(result.get_carry().is_some(), "carry is not set for {}", key);
+            assert!(result.get_roll_down().is_some(), "roll_down is not set for {}", key);
+        }
+        assert!(calculation_results.get(&stock_futures1_id).unwrap().get_carry().is_none());
+
+        // key-rate dv01s add up to the parallel dv01 (= -rho)
+        for key in [bond_code, bond2_code].iter() {
+            let result = calculation_results.get(key).unwrap();
+            let rho = result.get_rho().unwrap();
+            let key_rate_dv01 = result.get_key_rate_dv01().unwrap();
+            for (curve_id, dv01s) in key_rate_dv01.iter() {
+                let dv01_sum: Real = dv01s.iter().sum();
+                let parallel = -rho.get(curve_id).unwrap();
+                assert!(
+                    (dv01_sum - parallel).abs() <= 0.01 * parallel.abs() + 1.0e-3 && parallel > 0.0,
+                    "key-rate dv01 of {} on {}: {} vs {}",
+                    key,
+                    curve_id,
+                    dv01_sum,
+                    parallel,
+                );
+            }
+        }
+        assert!(calculation_results.get(&stock_futures1_id).unwrap().get_key_rate_dv01().is_none());
+
+        // bucketed gamma of the fixed coupon bonds is the positive convexity on the rho_structure tenors
+        for key in [bond_code, bond2_code].iter() {
+            let result = calculation_results.get(key).unwrap();
+            let rho_structure = result.get_rho_structure().unwrap();
+            for (curve_id, gammas) in result.get_gamma_structure().unwrap().iter() {
+                assert_eq!(gammas.len(), rho_structure.get(curve_id).unwrap().len());
+                let gamma_sum: Real = gammas.iter().sum();
+                assert!(gamma_sum > 0.0, "gamma structure of {} on {}: {:?}", key, curve_id, gammas);
+            }
+        }
+
+        // bond analytics are on the npv as the dirty price, and the duration explains rho
+        for key in [bond_code, bond2_code].iter() {
+            let result = calculation_results.get(key).unwrap();
+            let npv = result.get_npv_result().unwrap().get_npv();
+            let analytics = result.get_bond_analytics().unwrap();
+            assert_eq!(analytics.get_dirty_price(), npv);
+            assert!(
+                (analytics.get_clean_price() + analytics.get_accrued_interest() - npv).abs() < 1.0e-6,
+                "{}: {:?}",
+                key,
+                analytics
+            );
+            assert!(analytics.get_accrued_interest() >= 0.0, "{}: {:?}", key, analytics);
+            assert!(analytics.get_yield_to_maturity() > 0.0, "{}: {:?}", key, analytics);
+            assert!(analytics.get_z_spread().is_some() && analytics.get_oas().is_none());
+            let duration = analytics.get_modified_duration();
+            assert!(analytics.get_convexity() > duration * duration, "{}: {:?}", key, analytics);
+            let rho = *result.get_rho().unwrap().get(&zero_curve2_id).unwrap();
+            let unit_notional = result.get_instrument_info().unwrap().get_unit_notional();
+            let duration_rho = -duration * npv * unit_notional * 0.0001;
+            assert!(
+                (rho - duration_rho).abs() <= 0.05 * rho.abs(),
+                "rho of {}: {} vs duration rho: {}",
+                key,
+                rho,
+                duration_rho
+            );
+        }
+        assert!(calculation_results.get(&stock_futures1_id).unwrap().get_bond_analytics().is_none());
+
+        // typed cashflows of the bonds end with the redemption at the maturity
+        for key in [bond_code, bond2_code].iter() {
+            let result = calculation_results.get(key).unwrap();
+            let cashflows = result.get_cashflows().unwrap();
+            let redemption = cashflows.last().unwrap();
+            assert_eq!(redemption.get_cashflow_type(), CashflowType::Redemption);
+            assert_eq!(
+                redemption.get_payment_date(),
+                result.get_instrument_info().unwrap().get_maturity().unwrap()
+            );
+            for cashflow in cashflows.iter().filter(|c| c.get_cashflow_type() != CashflowType::Redemption) {
+                assert_eq!(cashflow.get_cashflow_type(), CashflowType::Fixed);
+                assert!(cashflow.get_rate().unwrap() > 0.0 && cashflow.get_amount() > 0.0);
+            }
+        }
+
+        // rho by curve role matches rho on the curves which have a single role for the instrument
+        let role_rho_pairs = [
+            (bond_code, CurveRole::Discount, zero_curve2_id),
+            (option1_id, CurveRole::Discount, funding_curve1_id),
+            (option1_id, CurveRole::Collateral, zero_curve1_id),
+            (stock_futures1_id, CurveRole::Collateral, zero_curve1_id),
+        ];
+        for (key, role, curve_id) in role_rho_pairs.iter() {
+            let result = calculation_results.get(key).unwrap();
+            let role_rho = *result.get_rho_by_role().unwrap().get(role).unwrap();
+            let rho = *result.get_rho().unwrap().get(curve_id).unwrap();
+            assert!(
+                (role_rho - rho).abs() <= 1.0e-2 * rho.abs() + 1.0e-3 && rho != 0.0,
+                "{:?} rho of {}: {} vs rho on {}: {}",
+                role,
+                key,
+                role_rho,
+                curve_id,
+                rho,
+            );
+        }
+        // the borrowing curve is not in rho but in rho by role, and it goes against the collateral curve
+        let futures_rho_by_role = calculation_results.get(&stock_futures1_id).unwrap().get_rho_by_role().unwrap();
+        assert!(
+            futures_rho_by_role[&CurveRole::Borrowing] < 0.0 && futures_rho_by_role[&CurveRole::Collateral] > 0.0,
+            "{:?}",
+            futures_rho_by_role
+        );
+        assert!(!futures_rho_by_role.contains_key(&CurveRole::Discount));
+        assert!(calculation_results.get(&stock_code).unwrap().get_rho_by_role().is_none());
+
+        // theta is decomposed into carry, roll-down and decay
+        for key in [bond_code, bond2_code, option1_id, stock_futures1_id].iter() {
+            let result = calculation_results.get(key).unwrap();
+            let theta = result.get_theta().unwrap();
+            let decomposition = result.get_theta_decomposition().unwrap();
+            assert!(
+                (decomposition.get_theta() - theta).abs() <= 1.0e-3 * theta.abs() + 1.0e-3,
+                "theta of {}: {} vs {:?}",
+                key,
+                theta,
+                decomposition,
+            );
+        }
+        // the bond theta is explained by carry and roll-down on the discount curve
+        for key in [bond_code, bond2_code].iter() {
+            let decomposition = calculation_results.get(key).unwrap().get_theta_decomposition().unwrap();
+            assert!(decomposition.get_carry() > 0.0, "{}: {:?}", key, decomposition);
+            assert!(
+                decomposition.get_decay().abs() < 1.0e-3 * decomposition.get_carry(),
+                "{}: {:?}",
+                key,
+                decomposition
+            );
+        }
+        // the option theta is the time decay
+        let option_decomposition = calculation_results.get(&option1_id).unwrap().get_theta_decomposition().unwrap();
+        assert!(option_decomposition.get_decay() < 0.0, "{:?}", option_decomposition);
+
+        // the stock is marked at bid in the conservative marking and the npv stays at the given value
+        let stock_result = calculation_results.get(&stock_code).unwrap();
+        let stock_unit = stock_result.get_instrument_info().unwrap().get_unit_notional();
+        let exit_value = stock_result.get_exit_value().unwrap();
+        assert!((exit_value - (spot - 0.5) * stock_unit).abs() < 1e-3, "exit value: {}", exit_value);
+        assert!(
+            (stock_result.get_bid_ask_adjustment().unwrap() + 0.5 * stock_unit).abs() < 1e-3,
+            "bid-ask adjustment: {:?}",
+            stock_result.get_bid_ask_adjustment()
+        );
+        for key in [stock_futures1_id, option1_id].iter() {
+            let adjustment = calculation_results.get(key).unwrap().get_bid_ask_adjustment().unwrap();
+            assert!(adjustment <= 0.0, "bid-ask adjustment of {}: {}", key, adjustment);
+        }
+        let cash_result = calculation_results.get(&cash_code).unwrap();
+        assert_eq!(cash_result.get_bid_ask_adjustment(), Some(0.0));
+
         let elapsed_nano = get_unix_nano() - start_time_nano;
-        let elapsed = rustmetrics::util::format_duration(elapsed_nano as f64 / 1_000_000_000 as f64);
+        let elapsed = rustmetrics::util::format_duration(elapsed_nano as f64 / 1_000_000_000_f64);
         flash_info!("EngineFinished"; "engine test finished {:?}", elapsed);
 
         for (key, value) in calculation_results.iter() {
@@ -567,4 +758,785 @@ mod tests {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// results of a KTB discounted on KRWGOV, or on a zero credit spread curve over KRWGOV
+    fn ktb_results(on_spread_curve: bool) -> Result<FxHashMap<StaticId, CalculationResult>> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let dates = vec![
+            datetime!(2025-03-13 00:00:00 +09:00),
+            datetime!(2026-03-13 00:00:00 +09:00),
+        ];
+        let gov_curve_id = StaticId::from_str("KRWGOV", "DataProvider");
+        let spread_curve_id = StaticId::from_str("KRWGOV-SPREAD", "DataProvider");
+        let mut zero_curve_map = FxHashMap::default();
+        for (id, rate) in [(gov_curve_id, 0.03358), (spread_curve_id, 0.0)] {
+            zero_curve_map.insert(
+                id,
+                VectorData::new(
+                    array![rate, rate],
+                    Some(dates.clone()),
+                    None,
+                    Some(dt),
+                    Currency::KRW,
+                    id.code_str().to_string(),
+                    id,
+                )?,
+            );
+        }
+
+        let issuer_id = StaticId::from_str("Government", "Korea");
+        let bond = Bond::new_from_conventions(
+            InstInfo {
+                id: StaticId::from_str("KR103501GCC0", "KRX"),
+                issue_date: Some(datetime!(2022-12-10 16:30:00 +09:00)),
+                maturity: Some(datetime!(2025-12-10 16:30:00 +09:00)),
+                currency: Currency::KRW,
+                inst_type: InstType::Bond,
+                unit_notional: 10_000.0,
+                name: "국고채권 04250-2512(22-13)".to_string(),
+                accounting_level: AccountingLevel::L1,
+            },
+            BondInfo {
+                credit_rating: CreditRating::None,
+                issuer_type: IssuerType::Government,
+                issuer_id,
+                rank: RankType::Undefined,
+            },
+            false,
+            None,
+            None,
+            None,
+            Some(0.0425),
+            None,
+            None,
+            None,
+            JointCalendar::new(vec![Calendar::SouthKorea(SouthKorea::new(
+                SouthKoreaType::Settlement,
+            ))])?,
+            true,
+            DayCountConvention::StreetConvention,
+            BusinessDayConvention::Unadjusted,
+            PaymentFrequency::SemiAnnually,
+            0,
+            0,
+        )?;
+
+        let mut bond_discount_curve_map = FxHashMap::default();
+        bond_discount_curve_map.insert(
+            (issuer_id, IssuerType::Government, CreditRating::None, Currency::KRW),
+            if on_spread_curve { spread_curve_id } else { gov_curve_id },
+        );
+        let mut spread_curve_base_map = FxHashMap::default();
+        spread_curve_base_map.insert(spread_curve_id, gov_curve_id);
+        let match_parameter = MatchParameter::new(
+            FxHashMap::default(),
+            FxHashMap::default(),
+            bond_discount_curve_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+        )
+        .with_spread_curve_base_map(spread_curve_base_map);
+
+        let calculation_configuration = CalculationConfiguration::default()
+            .with_rho_calculation(true)
+            .with_cs01_calculation(true)
+            .with_cs01_structure_calculation(true);
+        let mut engine_builder = EngineGenerator::builder();
+        let engine_generator = engine_builder
+            .with_configuration(calculation_configuration, dt, match_parameter)?
+            .with_instruments(Instruments::new(vec![Arc::new(Instrument::Bond(bond))]))?
+            .with_instrument_categories(vec![InstrumentCategory::new(
+                Some(vec!["Bond".to_string()]),
+                None,
+                None,
+            )])?
+            .with_data(
+                FxHashMap::default(),
+                FxHashMap::default(),
+                zero_curve_map,
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+            )?;
+        engine_generator.distribute_instruments()?;
+        engine_generator.calculate()?;
+        Ok(engine_generator.get_calculation_results().clone())
+    }
+
+    #[test]
+    fn test_cs01_on_spread_curve() -> Result<()> {
+        let bond_id = StaticId::from_str("KR103501GCC0", "KRX");
+        let spread_curve_id = StaticId::from_str("KRWGOV-SPREAD", "DataProvider");
+        let on_gov_curve = &ktb_results(false)?[&bond_id];
+        let on_spread_curve = &ktb_results(true)?[&bond_id];
+
+        // the zero spread does not change the price
+        let npv = on_gov_curve.get_npv_result().unwrap().get_npv();
+        let spread_npv = on_spread_curve.get_npv_result().unwrap().get_npv();
+        assert!((npv - spread_npv).abs() < 1.0e-6, "npv: {} vs {}", npv, spread_npv);
+        // no credit spread curve, no cs01
+        assert!(on_gov_curve
+            .get_cs01()
+            .is_none_or(|cs01| cs01.is_empty()));
+
+        // cs01 is the parallel dv01 on the credit spread curve, and its buckets add up to it
+        let cs01 = on_spread_curve.get_cs01().unwrap()[&spread_curve_id];
+        let rho = on_spread_curve.get_rho().unwrap()[&spread_curve_id];
+        assert!(cs01 > 0.0, "cs01: {}", cs01);
+        assert!((cs01 + rho).abs() <= 1.0e-3 * cs01, "cs01: {} vs rho: {}", cs01, rho);
+        let cs01_sum: Real = on_spread_curve.get_cs01_structure().unwrap()[&spread_curve_id]
+            .iter()
+            .sum();
+        assert!(
+            (cs01_sum - cs01).abs() <= 0.01 * cs01,
+            "cs01 structure: {} vs {}",
+            cs01_sum,
+            cs01
+        );
+        Ok(())
+    }
+
+    const COLLATERAL_RATE: Real = 0.0335;
+
+    /// delta, gamma, vega and rho on top of the configuration
+    fn with_sensitivities(config: CalculationConfiguration) -> CalculationConfiguration {
+        config
+            .with_delta_calculation(true)
+            .with_gamma_calculation(true)
+            .with_vega_calculation(true)
+            .with_rho_calculation(true)
+    }
+
+    fn option_portfolio_results(
+        dt: OffsetDateTime,
+        config: CalculationConfiguration,
+    ) -> Result<FxHashMap<StaticId, CalculationResult>> {
+        option_portfolio_results_on(dt, config, COLLATERAL_RATE, FxHashMap::default())
+    }
+
+    /// results of a KOSPI2 put, the KOSPI2 index and USD cash
+    /// on KOSPI2 350 with the volatility 0.2 and a dividend 3.0 going ex on 2024-06-01,
+    /// and flat curves of the collateral rate (KSD), the borrowing fee 0.005 and the discount rate 0.04
+    fn option_portfolio_results_on(
+        dt: OffsetDateTime,
+        config: CalculationConfiguration,
+        collateral_rate: Real,
+        past_daily_value_data: FxHashMap<StaticId, DailyValueData>,
+    ) -> Result<FxHashMap<StaticId, CalculationResult>> {
+        let dates = vec![
+            datetime!(2025-03-13 00:00:00 +09:00),
+            datetime!(2026-03-13 00:00:00 +09:00),
+        ];
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let mut curve_map = FxHashMap::default();
+        for (name, rate) in [
+            ("KSD", collateral_rate),
+            ("KOSPI2", 0.005),
+            ("Discount(KRW)", 0.04),
+        ] {
+            let id = StaticId::from_str(name, "DataProvider");
+            curve_map.insert(
+                id,
+                VectorData::new(
+                    array![rate, rate],
+                    Some(dates.clone()),
+                    None,
+                    Some(dt),
+                    Currency::KRW,
+                    name.to_string(),
+                    id,
+                )?,
+            );
+        }
+        let mut stock_map = FxHashMap::default();
+        stock_map.insert(
+            und_id,
+            ValueData::new(350.0, Some(dt), Currency::KRW, "KOSPI2".to_string(), und_id)?,
+        );
+        let mut vol_map = FxHashMap::default();
+        vol_map.insert(
+            und_id,
+            ValueData::new(0.2, Some(dt), Currency::KRW, "KOSPI2".to_string(), und_id)?,
+        );
+        let mut dividend_map = FxHashMap::default();
+        dividend_map.insert(
+            und_id,
+            VectorData::new(
+                Array1::from(vec![3.0]),
+                Some(vec![datetime!(2024-06-01 00:00:00 +09:00)]),
+                None,
+                Some(dt),
+                Currency::KRW,
+                "KOSPI2".to_string(),
+                und_id,
+            )?,
+        );
+        let mut fx_map = FxHashMap::default();
+        fx_map.insert(
+            FxCode::new(Currency::USD, Currency::KRW),
+            ValueData::new(
+                1300.0,
+                Some(dt),
+                Currency::KRW,
+                "USDKRW".to_string(),
+                StaticId::from_str("USDKRW", "DataProvider"),
+            )?,
+        );
+
+        let option = VanillaOption::new(
+            InstInfo {
+                id: StaticId::from_str("165XXX3", "KRX"),
+                issue_date: Some(datetime!(2021-01-01 00:00:00 +09:00)),
+                maturity: Some(datetime!(2024-09-13 00:00:00 +09:00)),
+                currency: Currency::KRW,
+                inst_type: InstType::VanillaOption,
+                unit_notional: 250_000.0,
+                name: "KOSPI2 Put Sep24".to_string(),
+                accounting_level: AccountingLevel::L1,
+            },
+            340.0,
+            None,
+            und_id,
+            Currency::KRW,
+            OptionType::Put,
+            OptionExerciseType::European,
+            OptionDailySettlementType::NotSettled,
+        );
+        let stock = Stock {
+            inst_info: InstInfo {
+                id: und_id,
+                currency: Currency::KRW,
+                inst_type: InstType::Stock,
+                unit_notional: 1.0,
+                name: "KOSPI2".to_string(),
+                ..Default::default()
+            },
+            underlying_ids: vec![und_id],
+            rank_type: rustmetrics::StockRankType::Common,
+        };
+        let cash = Cash {
+            inst_info: InstInfo {
+                id: StaticId::from_str("USD Cash", "Account"),
+                currency: Currency::USD,
+                inst_type: InstType::Cash,
+                unit_notional: 1.0,
+                name: "USD Cash".to_string(),
+                ..Default::default()
+            },
+        };
+        let instruments = Instruments::new(vec![
+            Arc::new(Instrument::VanillaOption(option)),
+            Arc::new(Instrument::Stock(stock)),
+            Arc::new(Instrument::Cash(cash)),
+        ]);
+
+        let mut collateral_curve_map = FxHashMap::default();
+        collateral_curve_map.insert(und_id, StaticId::from_str("KSD", "DataProvider"));
+        let mut borrowing_curve_map = FxHashMap::default();
+        borrowing_curve_map.insert(und_id, StaticId::from_str("KOSPI2", "DataProvider"));
+        let mut funding_cost_map = FxHashMap::default();
+        funding_cost_map.insert(
+            Currency::KRW,
+            StaticId::from_str("Discount(KRW)", "DataProvider"),
+        );
+        let match_parameter = MatchParameter::new(
+            collateral_curve_map,
+            borrowing_curve_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+            funding_cost_map,
+        );
+        let categories = vec![
+            InstrumentCategory::new(
+                Some(vec!["VanillaCall".to_string(), "VanillaPut".to_string()]),
+                Some(vec![Currency::KRW]),
+                Some(vec![und_id]),
+            ),
+            InstrumentCategory::new(
+                Some(vec!["Stock".to_string(), "Cash".to_string()]),
+                Some(vec![Currency::KRW, Currency::USD]),
+                None,
+            ),
+        ];
+
+        let mut engine_builder = EngineGenerator::builder();
+        let engine_generator = engine_builder
+            .with_configuration(config, dt, match_parameter)?
+            .with_instruments(instruments)?
+            .with_instrument_categories(categories)?
+            .with_data(
+                fx_map,
+                stock_map,
+                curve_map,
+                dividend_map,
+                vol_map,
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                past_daily_value_data,
+            )?;
+        engine_generator.distribute_instruments()?;
+        engine_generator.calculate()?;
+        Ok(engine_generator.get_calculation_results().clone())
+    }
+
+    #[test]
+    fn test_expiry_at_market_close() -> Result<()> {
+        init_logger()?;
+        // 10:00 on the expiry date of the put booked with its maturity date only
+        let dt = datetime!(2024-09-13 10:00:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let config = CalculationConfiguration::default().with_npv_calculation(true);
+
+        // without the close of the market, the put expired at the midnight
+        let err = option_portfolio_results_on(dt, config.clone(), COLLATERAL_RATE, FxHashMap::default())
+            .unwrap_err();
+        assert!(format!("{:?}", err).contains("maturity within the evaluation date"), "{:?}", err);
+
+        // it expires at the close (15:40 KST) of the KOSPI2 daily data
+        let mut past_daily_value_data = FxHashMap::default();
+        past_daily_value_data.insert(und_id, DailyValueData::default());
+        let results = option_portfolio_results_on(dt, config, COLLATERAL_RATE, past_daily_value_data)?;
+        // the put 340 on KOSPI2 350 is worth almost nothing in the last hours
+        let npv = results[&option_id].get_npv_result().unwrap().get_npv();
+        assert!((0.0..1.0e-6).contains(&npv), "npv: {}", npv);
+        Ok(())
+    }
+
+    #[test]
+    fn test_greeks_by_automatic_differentiation() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+
+        let bumped = option_portfolio_results(dt, with_sensitivities(CalculationConfiguration::default()))?;
+        let ad = option_portfolio_results(
+            dt,
+            with_sensitivities(
+                CalculationConfiguration::default()
+                    .with_greek_method(GreekMethod::AutomaticDifferentiation),
+            ),
+        )?;
+
+        let bumped_result = &bumped[&option_id];
+        let ad_result = &ad[&option_id];
+        assert!(bumped_result.get_npv_result() == ad_result.get_npv_result());
+        // the central difference of delta and the forward differences of vega and rho
+        // agree with the exact derivatives up to the bump sizes
+        let (bumped_delta, ad_delta) = (
+            bumped_result.get_delta().unwrap()[&und_id],
+            ad_result.get_delta().unwrap()[&und_id],
+        );
+        assert!((ad_delta - bumped_delta).abs() < 1.0e-3 * bumped_delta.abs());
+        let (bumped_vega, ad_vega) = (
+            bumped_result.get_vega().unwrap()[&und_id],
+            ad_result.get_vega().unwrap()[&und_id],
+        );
+        assert!((ad_vega - bumped_vega).abs() < 2.0e-2 * bumped_vega.abs());
+        // the flat surface from the constant volatility data is differentiated, not bumped
+        assert!(ad_vega != bumped_vega);
+        let (bumped_rho, ad_rho) = (
+            bumped_result.get_rho().unwrap(),
+            ad_result.get_rho().unwrap(),
+        );
+        assert_eq!(bumped_rho.len(), ad_rho.len());
+        for (curve_id, rho) in bumped_rho.iter() {
+            assert!(
+                (ad_rho[curve_id] - rho).abs() < 1.0e-2 * rho.abs() + 1.0,
+                "curve: {}, bumped rho: {}, ad rho: {}",
+                curve_id,
+                rho,
+                ad_rho[curve_id]
+            );
+        }
+        // gamma is still from bump and revalue
+        assert_eq!(bumped_result.get_gamma(), ad_result.get_gamma());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bump_sizes_per_risk_factor_class() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let default = option_portfolio_results(dt, with_sensitivities(CalculationConfiguration::default()))?;
+
+        // the same bumps in the other units: 1% of the spot 350, 5% of the volatility 0.2,
+        // and the KRW override of 1bp
+        let config = CalculationConfiguration::default()
+            .with_bump_size(RiskFactorClass::Spot, BumpSize::absolute(3.5)?)
+            .with_bump_size(RiskFactorClass::Volatility, BumpSize::relative(0.05)?)
+            .with_bump_size(RiskFactorClass::Rate, BumpSize::absolute(0.01)?)
+            .with_currency_bump_size(
+                Currency::KRW,
+                RiskFactorClass::Rate,
+                BumpSize::absolute(0.0001)?,
+            );
+        let converted = option_portfolio_results(dt, with_sensitivities(config.clone()))?;
+
+        let (default_result, converted_result) = (&default[&option_id], &converted[&option_id]);
+        let close = |x: Real, y: Real| (x - y).abs() <= 1.0e-4 * x.abs().max(1.0);
+        assert!(close(
+            default_result.get_delta().unwrap()[&und_id],
+            converted_result.get_delta().unwrap()[&und_id]
+        ));
+        assert!(close(
+            default_result.get_gamma().unwrap()[&und_id],
+            converted_result.get_gamma().unwrap()[&und_id]
+        ));
+        assert!(close(
+            default_result.get_vega().unwrap()[&und_id],
+            converted_result.get_vega().unwrap()[&und_id]
+        ));
+        for (curve_id, rho) in default_result.get_rho().unwrap().iter() {
+            assert!(close(*rho, converted_result.get_rho().unwrap()[curve_id]));
+        }
+
+        // a larger spot bump changes the finite difference
+        let larger = option_portfolio_results(
+            dt,
+            with_sensitivities(config.with_currency_bump_size(
+                Currency::KRW,
+                RiskFactorClass::Spot,
+                BumpSize::relative(0.1)?,
+            )),
+        )?;
+        let larger_delta = larger[&option_id].get_delta().unwrap()[&und_id];
+        assert!(!close(
+            default_result.get_delta().unwrap()[&und_id],
+            larger_delta
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_finite_difference_schemes() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let base_result = |config: CalculationConfiguration| -> Result<CalculationResult> {
+            Ok(option_portfolio_results(dt, with_sensitivities(config))?
+                .remove(&option_id)
+                .unwrap())
+        };
+        // central delta, forward vega and rho
+        let default = base_result(CalculationConfiguration::default())?;
+        let forward = base_result(
+            CalculationConfiguration::default()
+                .with_delta_scheme(FiniteDifferenceScheme::Forward)
+                .with_vega_scheme(FiniteDifferenceScheme::Central)
+                .with_rho_scheme(FiniteDifferenceScheme::Central)
+                .with_gamma_richardson_extrapolation(true),
+        )?;
+        let backward = base_result(
+            CalculationConfiguration::default().with_delta_scheme(FiniteDifferenceScheme::Backward),
+        )?;
+        let exact = base_result(
+            CalculationConfiguration::default()
+                .with_greek_method(GreekMethod::AutomaticDifferentiation),
+        )?;
+
+        // the central difference is the average of the one-sided differences
+        let delta = |res: &CalculationResult| res.get_delta().unwrap()[&und_id];
+        assert!(((delta(&forward) + delta(&backward)) / 2.0 - delta(&default)).abs() < 1.0e-2);
+        assert!(delta(&forward) > delta(&default) && delta(&default) > delta(&backward));
+
+        // the central differences are closer to the exact derivatives
+        let vega = |res: &CalculationResult| res.get_vega().unwrap()[&und_id];
+        assert!((vega(&forward) - vega(&exact)).abs() < (vega(&default) - vega(&exact)).abs());
+        for (curve_id, exact_rho) in exact.get_rho().unwrap().iter() {
+            let central_rho = forward.get_rho().unwrap()[curve_id];
+            let forward_rho = default.get_rho().unwrap()[curve_id];
+            assert!((central_rho - exact_rho).abs() <= (forward_rho - exact_rho).abs());
+        }
+
+        // Richardson extrapolation only corrects the gamma of the bump size
+        let gamma = |res: &CalculationResult| res.get_gamma().unwrap()[&und_id];
+        assert!(gamma(&forward) != gamma(&default));
+        assert!((gamma(&forward) - gamma(&default)).abs() < 1.0e-2 * gamma(&default).abs());
+        assert_eq!(gamma(&backward), gamma(&default));
+        Ok(())
+    }
+
+    #[test]
+    fn test_payoff_smoothing() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let base_result = |config: CalculationConfiguration| -> Result<CalculationResult> {
+            Ok(option_portfolio_results(dt, with_sensitivities(config))?
+                .remove(&option_id)
+                .unwrap())
+        };
+        let delta = |res: &CalculationResult| res.get_delta().unwrap()[&und_id];
+        let gamma = |res: &CalculationResult| res.get_gamma().unwrap()[&und_id];
+
+        // the call spread of 10% width is the central difference on the 5% spot bump
+        let wide_bump = base_result(
+            CalculationConfiguration::default()
+                .with_bump_size(RiskFactorClass::Spot, BumpSize::relative(0.05)?),
+        )?;
+        let call_spread = base_result(
+            CalculationConfiguration::default()
+                .with_payoff_smoothing(PayoffSmoothing::call_spread(0.1)?)
+                .with_discontinuous_payoff_ids(vec![option_id]),
+        )?;
+        let close = |x: Real, y: Real| (x - y).abs() <= 1.0e-5 * x.abs().max(1.0);
+        assert!(close(delta(&call_spread), delta(&wide_bump)));
+        assert!(close(gamma(&call_spread), gamma(&wide_bump)));
+
+        // only the flagged instruments are smoothed
+        let default = base_result(CalculationConfiguration::default())?;
+        let not_flagged = base_result(
+            CalculationConfiguration::default()
+                .with_payoff_smoothing(PayoffSmoothing::call_spread(0.1)?),
+        )?;
+        assert_eq!(delta(&not_flagged), delta(&default));
+
+        // the kernel averages the gamma of the smooth payoff over the nearby spots
+        let kernel = base_result(
+            CalculationConfiguration::default()
+                .with_payoff_smoothing(PayoffSmoothing::gaussian_kernel(0.02)?)
+                .with_discontinuous_payoff_ids(vec![option_id]),
+        )?;
+        assert!((delta(&kernel) - delta(&default)).abs() < 2.0e-2 * delta(&default).abs());
+        assert!((gamma(&kernel) - gamma(&default)).abs() < 1.0e-1 * gamma(&default).abs());
+        assert!(gamma(&kernel) != gamma(&default));
+
+        assert!(PayoffSmoothing::call_spread(0.0).is_err());
+        assert!(PayoffSmoothing::gaussian_kernel(1.5).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dividend_carry() -> Result<()> {
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let config = CalculationConfiguration::default().with_div_carry_calculation(true);
+        let base_results =
+            |dt: OffsetDateTime| option_portfolio_results(dt, config.clone());
+
+        // the dividend 3.0 goes ex on 2024-06-01 (Saturday), which is before the next business day of Friday
+        let results = base_results(datetime!(2024-05-31 16:30:00 +09:00))?;
+        let stock_carry = results[&und_id].get_div_carry().unwrap()[&und_id];
+        assert!(
+            (stock_carry - 3.0).abs() < 1.0e-3,
+            "stock dividend carry: {}",
+            stock_carry
+        );
+        // the put is short the underlying
+        let option_carry = results[&option_id].get_div_carry().unwrap()[&und_id];
+        assert!(
+            option_carry < 0.0,
+            "option dividend carry: {}",
+            option_carry
+        );
+
+        // no dividend goes ex by the next business day
+        let results = base_results(datetime!(2024-05-29 16:30:00 +09:00))?;
+        assert_eq!(results[&option_id].get_div_carry().unwrap()[&und_id], 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_delta_rho() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let ksd_id = StaticId::from_str("KSD", "DataProvider");
+        let config = CalculationConfiguration::default()
+            .with_delta_calculation(true)
+            .with_delta_rho_calculation(true);
+        let run = |collateral_rate: Real| {
+            option_portfolio_results_on(dt, config.clone(), collateral_rate, FxHashMap::default())
+        };
+
+        let results = run(COLLATERAL_RATE)?;
+        let cross = results[&option_id].get_delta_rho().unwrap()[&und_id][&ksd_id];
+
+        // delta change on the +-10bp collateral curve over 1bp
+        let change = 0.001;
+        let delta_on = |rate_change: Real| -> Result<Real> {
+            Ok(run(COLLATERAL_RATE + rate_change)?[&option_id]
+                .get_delta()
+                .unwrap()[&und_id])
+        };
+        let expected = (delta_on(change)? - delta_on(-change)?) / (2.0 * change / 0.0001);
+        assert!(
+            (cross - expected).abs() <= 0.1 * expected.abs(),
+            "delta-rho: {}, delta difference: {}",
+            cross,
+            expected
+        );
+
+        // the stock does not depend on the curves
+        assert!(results[&und_id].get_delta_rho().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_business_day_theta() -> Result<()> {
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let run = |dt: OffsetDateTime, mode: ThetaDayMode| -> Result<CalculationResult> {
+            let config = CalculationConfiguration::default()
+                .with_theta_calculation(true)
+                .with_theta_day_mode(mode)
+                .with_weekend_theta_calculation(true);
+            Ok(option_portfolio_results(dt, config)?.remove(&option_id).unwrap())
+        };
+
+        // Friday: the business day theta runs over the weekend to Monday
+        let friday = datetime!(2024-05-31 16:30:00 +09:00);
+        let fixed = run(friday, ThetaDayMode::Fixed)?;
+        assert_eq!(fixed.get_theta_day(), Some(1));
+        assert_eq!(fixed.get_weekend_theta(), fixed.get_theta());
+
+        let business_day = run(friday, ThetaDayMode::BusinessDay)?;
+        assert_eq!(business_day.get_theta_day(), Some(3));
+        let theta = business_day.get_theta().unwrap();
+        let weekend_theta = business_day.get_weekend_theta().unwrap();
+        assert!(
+            (weekend_theta - 2.0 * theta).abs() <= 1.0e-4 * theta.abs(),
+            "theta: {}, weekend theta: {}",
+            theta,
+            weekend_theta
+        );
+
+        // Tuesday: the next business day is the next day
+        let tuesday = datetime!(2024-05-28 16:30:00 +09:00);
+        let fixed = run(tuesday, ThetaDayMode::Fixed)?;
+        let business_day = run(tuesday, ThetaDayMode::BusinessDay)?;
+        assert_eq!(business_day.get_theta_day(), Some(1));
+        assert_eq!(business_day.get_theta(), fixed.get_theta());
+        assert_eq!(business_day.get_weekend_theta(), Some(0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_vega_matrix_grid() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let tenors: Vec<Tenor> = ["3M", "6M", "1Y"]
+            .iter()
+            .map(|x| Tenor::new_from_string(x))
+            .collect::<Result<_>>()?;
+        let grid = VegaMatrixGrid::new(tenors, vec![0.9, 1.0, 1.1, 1.5])?;
+        let config = CalculationConfiguration::default()
+            .with_vega_calculation(true)
+            .with_vega_matrix_calculation(true)
+            .with_vega_matrix_grid(und_id, grid.clone());
+        let results = option_portfolio_results(dt, config)?;
+
+        let result = &results[&option_id];
+        assert_eq!(result.get_vega_matrix_grid().unwrap()[&und_id], grid);
+        let vega_matrix = &result.get_vega_matrix().unwrap()[&und_id];
+        assert_eq!(vega_matrix.dim(), grid.shape());
+
+        // the buckets cover the constant volatility surface, so they add up to the parallel vega
+        let vega = result.get_vega().unwrap()[&und_id];
+        let sum: Real = vega_matrix.sum();
+        assert!(
+            (sum - vega).abs() <= 0.05 * vega.abs(),
+            "vega matrix sum: {}, vega: {}",
+            sum,
+            vega
+        );
+        // the put expires in September, so nothing is in the 1Y bucket
+        assert!(vega_matrix.row(2).iter().all(|v| *v == 0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ladder() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let spot_shifts = vec![-0.1, 0.0, 0.1];
+        let vol_shifts = vec![0.0, 0.05];
+        let config = CalculationConfiguration::default()
+            .with_delta_calculation(true)
+            .with_ladder_calculation(true)
+            .with_ladder_delta_calculation(true)
+            .with_ladder_shifts(spot_shifts.clone(), vol_shifts.clone());
+        let results = option_portfolio_results(dt, config)?;
+
+        // the stock moves with the spot only
+        let stock_ladder = &results[&und_id].get_ladder().unwrap()[&und_id];
+        for (i, spot_shift) in spot_shifts.iter().enumerate() {
+            for j in 0..vol_shifts.len() {
+                let value = stock_ladder.get_values()[[i, j]];
+                assert!((value - 350.0 * (1.0 + spot_shift)).abs() < 1.0e-3);
+            }
+        }
+
+        let result = &results[&option_id];
+        let ladder = &result.get_ladder().unwrap()[&und_id];
+        assert_eq!(ladder.get_values().dim(), (3, 2));
+        assert_eq!(ladder.get_spot_shifts(), &spot_shifts);
+        let values = ladder.get_values();
+        assert!((values[[1, 0]] - result.get_value().unwrap()).abs() < 1.0);
+        assert!(ladder.get_pnls()[[1, 0]].abs() < 1.0);
+        // the put loses on the spot up and gains on the vol up
+        assert!(values[[0, 0]] > values[[1, 0]] && values[[1, 0]] > values[[2, 0]]);
+        assert!(values[[1, 1]] > values[[1, 0]]);
+
+        let deltas = ladder.get_deltas().unwrap();
+        let delta = result.get_delta().unwrap()[&und_id];
+        assert!(
+            (deltas[[1, 0]] - delta).abs() <= 1.0e-3 * delta.abs(),
+            "ladder delta: {}, delta: {}",
+            deltas[[1, 0]],
+            delta
+        );
+        assert!(deltas[[0, 0]] < deltas[[2, 0]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fx_exposure_ladder() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let tenors = ["1M", "3M", "6M", "1Y"]
+            .iter()
+            .map(|t| Tenor::new_from_string(t))
+            .collect::<Result<Vec<Tenor>>>()?;
+        let config = CalculationConfiguration::default()
+            .with_fx_exposure_ladder_calculation(true)
+            .with_fx_exposure_ladder_tenors(tenors.clone());
+        let results = option_portfolio_results(dt, config)?;
+
+        // the option settles on the maturity in (3M, 6M]
+        let result = &results[&option_id];
+        let exposure = result.get_fx_exposure().unwrap()[&Currency::KRW];
+        let ladder = &result.get_fx_exposure_ladder().unwrap()[&Currency::KRW];
+        assert_eq!(ladder.len(), tenors.len() + 1);
+        assert_eq!(ladder[2], exposure);
+        assert_eq!(ladder.iter().sum::<Real>(), exposure);
+
+        // the stock without maturity is in the first bucket
+        let stock_ladder = &results[&und_id].get_fx_exposure_ladder().unwrap()[&Currency::KRW];
+        assert_eq!(
+            stock_ladder[0],
+            results[&und_id].get_fx_exposure().unwrap()[&Currency::KRW]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_engine_is_thread_safe() {
+        // engines and the shared parameters can be moved to and read from other threads
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<rustmetrics::pricing_engines::engine::Engine>();
+        assert_send_sync::<EngineGenerator>();
+        assert_send_sync::<Instruments>();
+        assert_send_sync::<rustmetrics::parameters::zero_curve::ZeroCurve>();
+    }
+}
@@ -0,0 +1,918 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use ndarray::array;
+    use rustc_hash::FxHashMap;
+    use rustmetrics::data::{value_data::ValueData, vector_data::VectorData};
+    use rustmetrics::enums::{
+        MarkingSide, OptionDailySettlementType, OptionExerciseType, OptionType,
+    };
+    use rustmetrics::instrument::{Instrument, Instruments};
+    use rustmetrics::instruments::{
+        futures::Futures, fx_futures::FxFutures, vanilla_option::VanillaOption,
+    };
+    use rustmetrics::limits::{LimitChecker, LimitMetric, LimitStatus};
+    use rustmetrics::position::{Position, Positions};
+    use rustmetrics::pricing_engines::calculation_configuration::CalculationConfiguration;
+    use rustmetrics::pricing_engines::cancellation::CancellationToken;
+    use rustmetrics::pricing_engines::cost_model::CostModel;
+    use rustmetrics::pricing_engines::engine_generator::{
+        EngineGenerator, InstrumentCategory, MarketDataKey, MarketDataUpdate,
+    };
+    use rustmetrics::pricing_engines::greek_diagnostics::{DiagnosticCheck, GreekDiagnostics};
+    use rustmetrics::pricing_engines::market_data_source::{
+        InMemoryMarketDataSource, MarketDataSource,
+    };
+    use rustmetrics::pricing_engines::match_parameter::MatchParameter;
+    use rustmetrics::pricing_engines::pricing_session::PricingSession;
+    use rustmetrics::pricing_engines::progress::ProgressEvent;
+    use rustmetrics::pricing_engines::result_diff::{DiffTolerance, ResultComparator};
+    use rustmetrics::{AccountingLevel, Currency, FxCode, InstInfo, InstType, Real};
+    use static_id::static_id::StaticId;
+    use std::sync::{Arc, Mutex};
+    use time::{macros::datetime, OffsetDateTime};
+
+    struct MarketData {
+        fx_data: FxHashMap<FxCode, ValueData>,
+        stock_data: FxHashMap<StaticId, ValueData>,
+        curve_data: FxHashMap<StaticId, VectorData>,
+    }
+
+    fn curve(dt: OffsetDateTime, id: StaticId, rate: Real, currency: Currency) -> VectorData {
+        VectorData::new(
+            array![rate, rate],
+            Some(vec![
+                datetime!(2025-03-13 00:00:00 +09:00),
+                datetime!(2026-03-13 00:00:00 +09:00),
+            ]),
+            None,
+            Some(dt),
+            currency,
+            id.code_str().to_string(),
+            id,
+        )
+        .unwrap()
+    }
+
+    fn value(dt: OffsetDateTime, id: StaticId, v: Real) -> ValueData {
+        ValueData::new(v, Some(dt), Currency::KRW, id.code_str().to_string(), id).unwrap()
+    }
+
+    fn market_data(dt: OffsetDateTime, fx_rate: Real, collateral_rate: Real) -> MarketData {
+        let mut curve_data = FxHashMap::default();
+        for (name, rate, currency) in [
+            ("KRWCRS", 0.035, Currency::KRW),
+            ("USDOIS", 0.05, Currency::USD),
+            ("KSD", collateral_rate, Currency::KRW),
+            ("KOSPI2", 0.005, Currency::KRW),
+        ] {
+            let id = StaticId::from_str(name, "DataProvider");
+            curve_data.insert(id, curve(dt, id, rate, currency));
+        }
+        let mut fx_data = FxHashMap::default();
+        fx_data.insert(
+            FxCode::new(Currency::USD, Currency::KRW),
+            value(dt, StaticId::from_str("USDKRW", "DataProvider"), fx_rate),
+        );
+        let mut stock_data = FxHashMap::default();
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        stock_data.insert(und_id, value(dt, und_id, 350.0));
+        MarketData {
+            fx_data,
+            stock_data,
+            curve_data,
+        }
+    }
+
+    fn build_engine_generator(
+        dt: OffsetDateTime,
+        data: MarketData,
+        calculation_configuration: CalculationConfiguration,
+    ) -> Result<EngineGenerator> {
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let fx_futures = FxFutures::new(
+            InstInfo {
+                id: StaticId::from_str("USDKRW Fut", "KRX"),
+                issue_date: Some(datetime!(2024-01-02 00:00:00 +09:00)),
+                maturity: Some(datetime!(2024-09-13 00:00:00 +09:00)),
+                currency: Currency::KRW,
+                inst_type: InstType::FxFutures,
+                unit_notional: 10_000.0,
+                name: "USDKRW Fut Sep24".to_string(),
+                accounting_level: AccountingLevel::L1,
+            },
+            1_300.0,
+            None,
+            Currency::USD,
+        );
+        let futures = Futures::new(
+            InstInfo {
+                id: StaticId::from_str("KOSPI2 Fut", "KRX"),
+                issue_date: Some(datetime!(2024-01-02 00:00:00 +09:00)),
+                maturity: Some(datetime!(2024-09-13 00:00:00 +09:00)),
+                currency: Currency::KRW,
+                inst_type: InstType::Futures,
+                unit_notional: 250_000.0,
+                name: "KOSPI2 Fut Sep24".to_string(),
+                accounting_level: AccountingLevel::L1,
+            },
+            350.0,
+            None,
+            Currency::KRW,
+            und_id,
+        );
+        let fx_futures = Arc::new(Instrument::FxFutures(fx_futures));
+        let futures = Arc::new(Instrument::Futures(futures));
+        let book = StaticId::from_str("Trading", "Book");
+        let positions = Positions::new(vec![
+            Position::new(fx_futures, -5.0, book),
+            Position::new(futures, 2.0, book),
+        ])?;
+
+        let mut collateral_curve_map = FxHashMap::default();
+        collateral_curve_map.insert(und_id, StaticId::from_str("KSD", "DataProvider"));
+        let mut borrowing_curve_map = FxHashMap::default();
+        borrowing_curve_map.insert(und_id, StaticId::from_str("KOSPI2", "DataProvider"));
+        let mut crs_curve_map = FxHashMap::default();
+        crs_curve_map.insert(Currency::KRW, StaticId::from_str("KRWCRS", "DataProvider"));
+        crs_curve_map.insert(Currency::USD, StaticId::from_str("USDOIS", "DataProvider"));
+        let match_parameter = MatchParameter::new(
+            collateral_curve_map,
+            borrowing_curve_map,
+            FxHashMap::default(),
+            crs_curve_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+        );
+
+        let categories = vec![
+            InstrumentCategory::new(Some(vec!["FxFutures".to_string()]), None, None),
+            InstrumentCategory::new(Some(vec!["Futures".to_string()]), None, Some(vec![und_id])),
+        ];
+        let mut engine_generator = EngineGenerator::builder();
+        engine_generator
+            .with_configuration(calculation_configuration, dt, match_parameter)?
+            .with_positions(positions)?
+            .with_instrument_categories(categories)?
+            .with_data(
+                data.fx_data,
+                data.stock_data,
+                data.curve_data,
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+            )?;
+        engine_generator.distribute_instruments()?;
+        Ok(engine_generator)
+    }
+
+    fn value_of(engine_generator: &EngineGenerator, name: &str) -> Real {
+        engine_generator.get_calculation_results()[&StaticId::from_str(name, "KRX")]
+            .get_value()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_update_data() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let mut engine_generator = build_engine_generator(
+            dt,
+            market_data(dt, 1300.0, 0.0335),
+            CalculationConfiguration::default(),
+        )?;
+        let update = MarketDataUpdate::Fx(
+            FxCode::new(Currency::USD, Currency::KRW),
+            value(dt, StaticId::from_str("USDKRW", "DataProvider"), 1310.0),
+        );
+        assert!(engine_generator.update_data(update.clone()).is_err());
+
+        engine_generator.calculate()?;
+        let futures_value = value_of(&engine_generator, "KOSPI2 Fut");
+
+        // only the fx futures uses the fx rate
+        let updated = engine_generator.update_data(update)?;
+        assert_eq!(updated, vec![StaticId::from_str("USDKRW Fut", "KRX")]);
+        let mut expected = build_engine_generator(
+            dt,
+            market_data(dt, 1310.0, 0.0335),
+            CalculationConfiguration::default(),
+        )?;
+        expected.calculate()?;
+        assert_eq!(
+            value_of(&engine_generator, "USDKRW Fut"),
+            value_of(&expected, "USDKRW Fut")
+        );
+        assert_eq!(value_of(&engine_generator, "KOSPI2 Fut"), futures_value);
+
+        // only the equity futures uses the collateral curve
+        let ksd_id = StaticId::from_str("KSD", "DataProvider");
+        let updated = engine_generator.update_data(MarketDataUpdate::Curve(
+            ksd_id,
+            curve(dt, ksd_id, 0.04, Currency::KRW),
+        ))?;
+        assert_eq!(updated, vec![StaticId::from_str("KOSPI2 Fut", "KRX")]);
+        let mut expected = build_engine_generator(
+            dt,
+            market_data(dt, 1310.0, 0.04),
+            CalculationConfiguration::default(),
+        )?;
+        expected.calculate()?;
+        let futures_value = value_of(&engine_generator, "KOSPI2 Fut");
+        assert_eq!(futures_value, value_of(&expected, "KOSPI2 Fut"));
+
+        // the position results follow the updates
+        let book = StaticId::from_str("Trading", "Book");
+        let position_result = &engine_generator.get_position_results()[&book]
+            [&StaticId::from_str("KOSPI2 Fut", "KRX")];
+        assert_eq!(position_result.get_value(), Some(2.0 * futures_value));
+
+        // the limits are checked on the position results
+        let checker =
+            LimitChecker::new(Currency::KRW).with_limit(Some(book), LimitMetric::Value, 1.0)?;
+        engine_generator.with_limit_checker(checker)?;
+        engine_generator.calculate()?;
+        let book_value: Real = engine_generator.get_position_results()[&book]
+            .values()
+            .map(|result| result.get_value().unwrap())
+            .sum();
+        let check = engine_generator.get_limit_report().unwrap().get_checks()[0];
+        assert!((check.get_exposure() - book_value).abs() < 1.0e-3 * book_value.abs().max(1.0));
+        assert_eq!(check.get_status(), LimitStatus::Breach);
+        Ok(())
+    }
+
+    #[test]
+    fn test_warm_start() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let mut engine_generator = build_engine_generator(
+            dt,
+            market_data(dt, 1300.0, 0.0335),
+            CalculationConfiguration::default(),
+        )?;
+        engine_generator.with_warm_start(true)?;
+        engine_generator.calculate()?;
+        assert_eq!(engine_generator.get_recalculated_ids().len(), 2);
+        let futures_value = value_of(&engine_generator, "KOSPI2 Fut");
+
+        // the daily roll of the data where only the fx rate is changed
+        let set_data = |engine_generator: &mut EngineGenerator, data: MarketData| -> Result<()> {
+            engine_generator.with_data(
+                data.fx_data,
+                data.stock_data,
+                data.curve_data,
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+            )?;
+            Ok(())
+        };
+        set_data(&mut engine_generator, market_data(dt, 1310.0, 0.0335))?;
+        engine_generator.calculate()?;
+        assert_eq!(
+            engine_generator.get_recalculated_ids(),
+            &vec![StaticId::from_str("USDKRW Fut", "KRX")]
+        );
+        let mut expected = build_engine_generator(
+            dt,
+            market_data(dt, 1310.0, 0.0335),
+            CalculationConfiguration::default(),
+        )?;
+        expected.calculate()?;
+        assert_eq!(
+            value_of(&engine_generator, "USDKRW Fut"),
+            value_of(&expected, "USDKRW Fut")
+        );
+        assert_eq!(value_of(&engine_generator, "KOSPI2 Fut"), futures_value);
+
+        // the same data leaves nothing to recalculate
+        set_data(&mut engine_generator, market_data(dt, 1310.0, 0.0335))?;
+        engine_generator.calculate()?;
+        assert!(engine_generator.get_recalculated_ids().is_empty());
+
+        // a new configuration invalidates all
+        engine_generator.with_instrument_categories(vec![InstrumentCategory::default()])?;
+        engine_generator.distribute_instruments()?;
+        engine_generator.calculate()?;
+        assert_eq!(engine_generator.get_recalculated_ids().len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pricing_session() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let engine_generator = build_engine_generator(
+            dt,
+            market_data(dt, 1300.0, 0.0335),
+            CalculationConfiguration::default(),
+        )?;
+        let sequences = Arc::new(Mutex::new(Vec::<u64>::new()));
+        let sequences_in_callback = sequences.clone();
+        let mut session = PricingSession::new(engine_generator)?.with_callback(move |update| {
+            sequences_in_callback
+                .lock()
+                .unwrap()
+                .push(update.get_sequence())
+        });
+        let receiver = session.subscribe();
+
+        // ticks pushed from another thread
+        let (sender, updates) = std::sync::mpsc::channel();
+        let feeder = std::thread::spawn(move || {
+            let fx_id = StaticId::from_str("USDKRW", "DataProvider");
+            let und_id = StaticId::from_str("KOSPI2", "KRX");
+            sender
+                .send(MarketDataUpdate::Fx(
+                    FxCode::new(Currency::USD, Currency::KRW),
+                    value(dt, fx_id, 1310.0),
+                ))
+                .unwrap();
+            sender
+                .send(MarketDataUpdate::Stock(und_id, value(dt, und_id, 355.0)))
+                .unwrap();
+        });
+        assert_eq!(session.run(updates)?, 2);
+        feeder.join().unwrap();
+        assert_eq!(*sequences.lock().unwrap(), vec![1, 2]);
+
+        let fx_update = receiver.recv()?;
+        let fx_futures_id = StaticId::from_str("USDKRW Fut", "KRX");
+        assert_eq!(fx_update.get_updated_ids(), &vec![fx_futures_id]);
+        let mut expected = build_engine_generator(
+            dt,
+            market_data(dt, 1310.0, 0.0335),
+            CalculationConfiguration::default(),
+        )?;
+        expected.calculate()?;
+        assert_eq!(
+            fx_update.get_results()[&fx_futures_id].get_value(),
+            Some(value_of(&expected, "USDKRW Fut"))
+        );
+        let stock_update = receiver.recv()?;
+        assert_eq!(stock_update.get_sequence(), 2);
+        assert_eq!(
+            stock_update.get_updated_ids(),
+            &vec![StaticId::from_str("KOSPI2 Fut", "KRX")]
+        );
+        assert_eq!(
+            value_of(session.get_engine_generator(), "KOSPI2 Fut"),
+            stock_update.get_results()[&StaticId::from_str("KOSPI2 Fut", "KRX")]
+                .get_value()
+                .unwrap()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_market_data_source() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let engine_generator = build_engine_generator(
+            dt,
+            market_data(dt, 1300.0, 0.0335),
+            CalculationConfiguration::default(),
+        )?;
+        let mut session = PricingSession::new(engine_generator)?;
+        let usdkrw = FxCode::new(Currency::USD, Currency::KRW);
+        let fx_id = StaticId::from_str("USDKRW", "DataProvider");
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let spx_id = StaticId::from_str("SPX", "CME");
+
+        // the fx tick before the connection is pushed as the snapshot
+        let mut source = InMemoryMarketDataSource::new();
+        source.publish(MarketDataUpdate::Fx(usdkrw, value(dt, fx_id, 1310.0)));
+        let stream = session.connect(&mut source)?;
+        assert_eq!(session.get_sequence(), 1);
+        assert!(source.is_subscribed(&MarketDataKey::Fx(usdkrw)));
+        assert!(source.is_subscribed(&MarketDataKey::Stock(und_id)));
+        assert_eq!(source.snapshot()?.len(), 1);
+
+        // the data not used by the session is not streamed
+        source.publish(MarketDataUpdate::Stock(spx_id, value(dt, spx_id, 5000.0)));
+        source.publish(MarketDataUpdate::Stock(und_id, value(dt, und_id, 355.0)));
+        drop(source);
+        assert_eq!(session.run(stream)?, 1);
+        assert_eq!(session.get_sequence(), 2);
+
+        let mut expected = build_engine_generator(
+            dt,
+            market_data(dt, 1310.0, 0.0335),
+            CalculationConfiguration::default(),
+        )?;
+        expected.calculate()?;
+        assert_eq!(
+            value_of(session.get_engine_generator(), "USDKRW Fut"),
+            value_of(&expected, "USDKRW Fut")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_sweep() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let mut engine_generator = build_engine_generator(
+            dt,
+            market_data(dt, 1300.0, 0.0335),
+            CalculationConfiguration::default(),
+        )?;
+        engine_generator.calculate()?;
+        let kospi2_value = value_of(&engine_generator, "KOSPI2 Fut");
+
+        let dates = vec![
+            dt,
+            datetime!(2024-06-13 16:30:00 +09:00),
+            datetime!(2024-09-20 16:30:00 +09:00),
+        ];
+        let sweep = engine_generator.get_date_sweep(&dates)?;
+        assert_eq!(sweep.get_dates(), &dates);
+        assert_eq!(sweep.get_inst_ids().len(), 2);
+
+        let kospi2_id = StaticId::from_str("KOSPI2 Fut", "KRX");
+        let values = sweep.get_values(&kospi2_id).unwrap();
+        assert_eq!(sweep.get_base_value(&kospi2_id), Some(kospi2_value));
+        assert_eq!(values[0], kospi2_value);
+        assert_ne!(values[1], kospi2_value);
+        // matured
+        assert_eq!(values[2], 0.0);
+        let aging_pnls = sweep.get_aging_pnls(&kospi2_id).unwrap();
+        assert_eq!(aging_pnls[0], 0.0);
+        assert_eq!(aging_pnls[1], values[1] - kospi2_value);
+
+        // the evaluation date and the results of the generator are kept
+        assert_eq!(value_of(&engine_generator, "KOSPI2 Fut"), kospi2_value);
+        let second_sweep = engine_generator.get_date_sweep(&dates[..1])?;
+        assert_eq!(second_sweep.get_values(&kospi2_id).unwrap()[0], kospi2_value);
+
+        assert!(engine_generator
+            .get_date_sweep(&[datetime!(2024-03-12 16:30:00 +09:00)])
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reporting_currency() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let configuration = CalculationConfiguration::default()
+            .with_delta_calculation(true)
+            .with_reporting_currency(Currency::USD);
+        let mut engine_generator =
+            build_engine_generator(dt, market_data(dt, 1300.0, 0.0335), configuration)?;
+        engine_generator.calculate()?;
+
+        let futures_id = StaticId::from_str("KOSPI2 Fut", "KRX");
+        let result = &engine_generator.get_calculation_results()[&futures_id];
+        let reporting = result.get_reporting_result().unwrap();
+        assert_eq!(reporting.get_representation_currency(), Some(Currency::USD));
+        let value = result.get_value().unwrap();
+        assert!((reporting.get_value().unwrap() - value / 1300.0).abs() <= 1.0e-5 * value.abs());
+        let delta = result.get_delta().unwrap()[&futures_id];
+        let reporting_delta = reporting.get_delta().unwrap()[&futures_id];
+        assert!((reporting_delta - delta / 1300.0).abs() <= 1.0e-5 * delta.abs());
+
+        // the local result is kept in the instrument currency
+        assert_eq!(result.get_representation_currency(), Some(Currency::KRW));
+        assert!(
+            engine_generator.get_position_results()[&StaticId::from_str("Trading", "Book")]
+                [&futures_id]
+                .get_reporting_result()
+                .is_some()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_conservative_marking_of_short_positions() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let configuration = CalculationConfiguration::default()
+            .with_bid_ask_adjustment_calculation(true)
+            .with_marking_side(MarkingSide::Conservative);
+        let mut data = market_data(dt, 1300.0, 0.0335);
+        for fx in data.fx_data.values_mut() {
+            *fx = fx.clone().with_bid_ask(1299.5, 1300.5)?;
+        }
+        for stock in data.stock_data.values_mut() {
+            *stock = stock.clone().with_bid_ask(349.5, 350.5)?;
+        }
+        let mut engine_generator = build_engine_generator(dt, data, configuration)?;
+        engine_generator.calculate()?;
+
+        let book = StaticId::from_str("Trading", "Book");
+        // (instrument, quantity): the fx futures is held short and the futures long
+        for (name, quantity) in [("USDKRW Fut", -5.0 as Real), ("KOSPI2 Fut", 2.0)] {
+            let id = StaticId::from_str(name, "KRX");
+            let result = &engine_generator.get_calculation_results()[&id];
+            let position_result = &engine_generator.get_position_results()[&book][&id];
+            // the unit result is marked at bid for the long holder
+            let adjustment = result.get_bid_ask_adjustment().unwrap();
+            assert!(adjustment < 0.0, "{}: {}", name, adjustment);
+            // both positions are marked at the side worse for them
+            let position_adjustment = position_result.get_bid_ask_adjustment().unwrap();
+            assert!(position_adjustment < 0.0, "{}: {}", name, position_adjustment);
+            assert!(
+                (position_adjustment - quantity.abs() * adjustment).abs()
+                    <= 1.0e-2 * adjustment.abs(),
+                "{}: {} vs {}",
+                name,
+                position_adjustment,
+                adjustment
+            );
+            let mid_value = position_result.get_value().unwrap();
+            assert!(
+                (position_result.get_exit_value().unwrap() - mid_value - position_adjustment)
+                    .abs()
+                    <= 1.0e-6 * mid_value.abs()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_pricing() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let configuration = CalculationConfiguration::default()
+            .with_delta_calculation(true)
+            .with_gamma_calculation(true)
+            .with_rho_calculation(true)
+            .with_rho_structure_calculation(true);
+        let mut sequential = build_engine_generator(
+            dt,
+            market_data(dt, 1300.0, 0.0335),
+            configuration.clone().with_parallel_pricing_threshold(usize::MAX),
+        )?;
+        sequential.calculate()?;
+        let mut parallel = build_engine_generator(
+            dt,
+            market_data(dt, 1300.0, 0.0335),
+            configuration.with_parallel_pricing_threshold(1),
+        )?;
+        parallel.calculate()?;
+
+        let comparator = ResultComparator::new(DiffTolerance::new(0.0, 0.0)?);
+        let report = comparator.compare(
+            sequential.get_calculation_results(),
+            parallel.get_calculation_results(),
+        );
+        assert_eq!(report.get_compared_number(), 2);
+        assert!(report.is_identical(), "{}", report.to_json()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reproducibility() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let configuration = CalculationConfiguration::default()
+            .with_delta_calculation(true)
+            .with_rho_calculation(true)
+            .with_parallel_pricing_threshold(1)
+            .with_reproducibility(true);
+        let mut cold = build_engine_generator(
+            dt,
+            market_data(dt, 1310.0, 0.0335),
+            configuration.clone(),
+        )?;
+        cold.calculate()?;
+
+        // the same results in the same order from a partial recalculation
+        let mut updated =
+            build_engine_generator(dt, market_data(dt, 1300.0, 0.0335), configuration.clone())?;
+        updated.calculate()?;
+        updated.update_data(MarketDataUpdate::Fx(
+            FxCode::new(Currency::USD, Currency::KRW),
+            value(dt, StaticId::from_str("USDKRW", "DataProvider"), 1310.0),
+        ))?;
+
+        let cold_results = cold.get_sorted_calculation_results();
+        let updated_results = updated.get_sorted_calculation_results();
+        assert_eq!(cold_results, updated_results);
+
+        // two cold runs are identical
+        let mut rerun = build_engine_generator(
+            dt,
+            market_data(dt, 1310.0, 0.0335),
+            configuration.clone(),
+        )?;
+        rerun.calculate()?;
+        assert_eq!(cold_results, rerun.get_sorted_calculation_results());
+        let comparator = ResultComparator::new(DiffTolerance::new(0.0, 0.0)?);
+        let report = comparator.compare(
+            cold.get_calculation_results(),
+            updated.get_calculation_results(),
+        );
+        assert!(report.is_identical(), "{}", report.to_json()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cost_model() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let mut expected = build_engine_generator(
+            dt,
+            market_data(dt, 1300.0, 0.0335),
+            CalculationConfiguration::default(),
+        )?;
+        expected.calculate()?;
+
+        // a single category balanced over two groups
+        let mut engine_generator = build_engine_generator(
+            dt,
+            market_data(dt, 1300.0, 0.0335),
+            CalculationConfiguration::default(),
+        )?;
+        engine_generator
+            .with_instrument_categories(vec![InstrumentCategory::new(
+                Some(vec!["FxFutures".to_string(), "Futures".to_string()]),
+                None,
+                None,
+            )])?
+            .with_cost_model(CostModel::new().with_group_number(2)?)?;
+        engine_generator.distribute_instruments()?;
+        assert_eq!(engine_generator.get_instrument_groups().len(), 2);
+        engine_generator.calculate()?;
+
+        let comparator = ResultComparator::new(DiffTolerance::new(0.0, 0.0)?);
+        let report = comparator.compare(
+            expected.get_calculation_results(),
+            engine_generator.get_calculation_results(),
+        );
+        assert!(report.is_identical(), "{}", report.to_json()?);
+        let cost_model = engine_generator.get_cost_model().unwrap();
+        assert!(cost_model
+            .get_timing(&StaticId::from_str("KOSPI2 Fut", "KRX"))
+            .is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_progress_and_cancellation() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let configuration = CalculationConfiguration::default()
+            .with_delta_calculation(true)
+            .with_rho_calculation(true);
+        let mut engine_generator =
+            build_engine_generator(dt, market_data(dt, 1300.0, 0.0335), configuration)?;
+        let events = Arc::new(Mutex::new(Vec::<ProgressEvent>::new()));
+        let events_in_callback = events.clone();
+        let token = CancellationToken::new();
+        let token_in_callback = token.clone();
+        let cancel_after_npv = Arc::new(Mutex::new(false));
+        let cancel_in_callback = cancel_after_npv.clone();
+        engine_generator
+            .with_cancellation_token(token.clone())?
+            .with_progress_callback(move |event| {
+                if *cancel_in_callback.lock().unwrap() {
+                    token_in_callback.cancel();
+                }
+                events_in_callback.lock().unwrap().push(event.clone());
+            })?;
+        engine_generator.calculate()?;
+
+        let events_of_run = events.lock().unwrap().clone();
+        assert_eq!(
+            events_of_run[0],
+            ProgressEvent::Started {
+                group_number: 2,
+                instrument_number: 2
+            }
+        );
+        for stage in ["npv", "delta", "rho"] {
+            let stage_number = events_of_run
+                .iter()
+                .filter(|event| {
+                    matches!(event, ProgressEvent::StageFinished { stage: s, .. } if *s == stage)
+                })
+                .count();
+            assert_eq!(stage_number, 2);
+        }
+        let finished: Vec<usize> = events_of_run
+            .iter()
+            .filter_map(|event| match event {
+                ProgressEvent::GroupFinished {
+                    finished_instrument_number,
+                    ..
+                } => Some(*finished_instrument_number),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(finished.len(), 2);
+        assert!(finished.contains(&2));
+
+        // cancelled in the middle of the run, after the npv of a group
+        let kospi2_value = value_of(&engine_generator, "KOSPI2 Fut");
+        *cancel_after_npv.lock().unwrap() = true;
+        events.lock().unwrap().clear();
+        assert!(engine_generator.calculate().is_err());
+        assert!(token.is_cancelled());
+        assert!(!events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|event| matches!(event, ProgressEvent::GroupFinished { .. })));
+        assert_eq!(value_of(&engine_generator, "KOSPI2 Fut"), kospi2_value);
+        Ok(())
+    }
+
+    #[test]
+    fn test_calculation_statistics() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let configuration = CalculationConfiguration::default()
+            .with_delta_calculation(true)
+            .with_rho_calculation(true);
+        let mut engine_generator =
+            build_engine_generator(dt, market_data(dt, 1300.0, 0.0335), configuration.clone())?;
+        engine_generator.calculate()?;
+        assert!(engine_generator.get_calculation_statistics().is_none());
+
+        let mut engine_generator = build_engine_generator(
+            dt,
+            market_data(dt, 1300.0, 0.0335),
+            configuration.with_statistics_collection(true),
+        )?;
+        engine_generator.calculate()?;
+        let statistics = engine_generator.get_calculation_statistics().unwrap();
+        assert_eq!(
+            statistics.get_groups().len(),
+            engine_generator.get_instrument_groups().len()
+        );
+        assert_eq!(
+            statistics.get_instrument_number(),
+            engine_generator.get_calculation_results().len()
+        );
+        let phases: Vec<String> = statistics
+            .get_phase_timings()
+            .into_iter()
+            .map(|(phase, _)| phase)
+            .collect();
+        assert_eq!(
+            phases,
+            vec!["parameter build", "pricer init", "npv", "fx exposure", "delta", "rho"]
+        );
+        for group in statistics.get_groups() {
+            let phase_sum: Real = group.get_phase_timings().iter().map(|(_, t)| t).sum();
+            assert!(phase_sum <= group.get_elapsed());
+        }
+        assert!(statistics.get_elapsed() >= 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_greek_diagnostics() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option = |name: &str, option_type: OptionType| {
+            let option = VanillaOption::new(
+                InstInfo {
+                    id: StaticId::from_str(name, "KRX"),
+                    issue_date: Some(datetime!(2024-01-02 00:00:00 +09:00)),
+                    maturity: Some(datetime!(2024-09-13 00:00:00 +09:00)),
+                    currency: Currency::KRW,
+                    inst_type: InstType::VanillaOption,
+                    unit_notional: 250_000.0,
+                    name: name.to_string(),
+                    accounting_level: AccountingLevel::L1,
+                },
+                350.0,
+                None,
+                und_id,
+                Currency::KRW,
+                option_type,
+                OptionExerciseType::European,
+                OptionDailySettlementType::NotSettled,
+            );
+            Arc::new(Instrument::VanillaOption(option))
+        };
+        let instruments = Instruments::new(vec![
+            option("KOSPI2 C350", OptionType::Call),
+            option("KOSPI2 P350", OptionType::Put),
+        ]);
+
+        let data = market_data(dt, 1300.0, 0.0335);
+        let mut volatility_data = FxHashMap::default();
+        volatility_data.insert(und_id, value(dt, und_id, 0.2));
+        let mut collateral_curve_map = FxHashMap::default();
+        collateral_curve_map.insert(und_id, StaticId::from_str("KSD", "DataProvider"));
+        let mut borrowing_curve_map = FxHashMap::default();
+        borrowing_curve_map.insert(und_id, StaticId::from_str("KOSPI2", "DataProvider"));
+        let mut funding_cost_map = FxHashMap::default();
+        funding_cost_map.insert(Currency::KRW, StaticId::from_str("KRWCRS", "DataProvider"));
+        let match_parameter = MatchParameter::new(
+            collateral_curve_map,
+            borrowing_curve_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+            funding_cost_map,
+        );
+        let configuration = CalculationConfiguration::default()
+            .with_delta_calculation(true)
+            .with_gamma_calculation(true)
+            .with_theta_calculation(true);
+
+        let mut engine_generator = EngineGenerator::builder();
+        engine_generator
+            .with_configuration(configuration, dt, match_parameter)?
+            .with_instruments(instruments)?
+            .with_instrument_categories(vec![InstrumentCategory::new(
+                None,
+                None,
+                Some(vec![und_id]),
+            )])?
+            .with_diagnostics(GreekDiagnostics::new())?
+            .with_data(
+                data.fx_data,
+                data.stock_data,
+                data.curve_data,
+                FxHashMap::default(),
+                volatility_data,
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+            )?;
+        engine_generator.distribute_instruments()?;
+        engine_generator.calculate()?;
+
+        let report = engine_generator.get_diagnostics_report().unwrap();
+        assert_eq!(
+            report.get_checked_number(DiagnosticCheck::ThetaGammaCarry),
+            2
+        );
+        assert_eq!(report.get_checked_number(DiagnosticCheck::PutCallParity), 1);
+        assert!(report.is_consistent(), "{:?}", report.get_warnings());
+
+        // the equations hold only approximately on the bumped greeks
+        engine_generator.with_diagnostics(GreekDiagnostics::new().with_theta_tolerance(1.0e-6)?)?;
+        engine_generator.calculate()?;
+        let report = engine_generator.get_diagnostics_report().unwrap();
+        assert!(!report
+            .get_warnings_of(DiagnosticCheck::ThetaGammaCarry)
+            .is_empty());
+        assert!(report
+            .get_warnings_of(DiagnosticCheck::PutCallParity)
+            .is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct ThreadWaker(std::thread::Thread);
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut context = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_calculate_async() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let configuration = CalculationConfiguration::default()
+            .with_delta_calculation(true)
+            .with_rho_calculation(true);
+        let mut expected =
+            build_engine_generator(dt, market_data(dt, 1300.0, 0.0335), configuration.clone())?;
+        expected.calculate()?;
+
+        let mut engine_generator =
+            build_engine_generator(dt, market_data(dt, 1300.0, 0.0335), configuration)?;
+        let token = CancellationToken::new();
+        let calculations = engine_generator.calculate_async(&token);
+        assert_eq!(calculations.len(), 2);
+        let group_results = calculations
+            .into_iter()
+            .map(block_on)
+            .collect::<Result<Vec<_>>>()?;
+        engine_generator.set_calculation_results(group_results)?;
+
+        let comparator = ResultComparator::new(DiffTolerance::new(0.0, 0.0)?);
+        let report = comparator.compare(
+            expected.get_calculation_results(),
+            engine_generator.get_calculation_results(),
+        );
+        assert_eq!(report.get_compared_number(), 2);
+        assert!(report.is_identical(), "{}", report.to_json()?);
+
+        // a cancelled run resolves to errors
+        token.cancel();
+        for calculation in engine_generator.calculate_async(&token) {
+            assert!(block_on(calculation).is_err());
+        }
+        Ok(())
+    }
+}
@@ -10,9 +10,8 @@ mod tests {
     use rustmetrics::parameters::discrete_ratio_dividend::DiscreteRatioDividend;
     use rustmetrics::parameters::zero_curve::ZeroCurve;
     use static_id::static_id::StaticId;
-    use std::cell::RefCell;
-    use std::rc::Rc;
-    use time;
+    use std::sync::RwLock;
+    use std::sync::Arc;
 
     #[test]
     fn test_shared_evaluation_date() -> Result<()> {
@@ -27,8 +26,8 @@ mod tests {
             evaluation_offset,
         );
 
-        let evaluation_date = Rc::new(RefCell::new(EvaluationDate::new(
-            evaluation_offsetdatetime.clone(),
+        let evaluation_date = Arc::new(RwLock::new(EvaluationDate::new(
+            evaluation_offsetdatetime,
         )));
 
         let spot = 1.0;
@@ -39,7 +38,7 @@ mod tests {
             evaluation_offsetdatetime + time::Duration::days(365),
         ];
         let times = None;
-        let market_datetime = evaluation_offsetdatetime.clone();
+        let market_datetime = evaluation_offsetdatetime;
         let name = "zero curve data".to_string();
         let zero_curve_data = VectorData::new(
             value,
@@ -60,7 +59,7 @@ mod tests {
         )
         .expect("Failed to create ZeroCurve");
 
-        let zero_curve = Rc::new(RefCell::new(_zero_curve));
+        let zero_curve = Arc::new(RwLock::new(_zero_curve));
 
         // For constructing DiscreteRatioDividend, make a vector data object which has two data points after the evaluation_date
         let value = array![0.1, 0.2];
@@ -69,7 +68,7 @@ mod tests {
             evaluation_offsetdatetime + time::Duration::days(365),
         ];
         let times = None;
-        let market_datetime = evaluation_offsetdatetime.clone();
+        let market_datetime = evaluation_offsetdatetime;
         let name = "dividend amount data".to_string();
         let dividend_data = VectorData::new(
             value,
@@ -91,13 +90,13 @@ mod tests {
         )
         .expect("Failed to create DiscreteRatioDividend");
 
-        let dividend = Rc::new(RefCell::new(_dividend));
+        let dividend = Arc::new(RwLock::new(_dividend));
         evaluation_date
-            .borrow_mut()
+            .write().unwrap()
             .add_dividend_observer(dividend.clone());
 
         // test two dates
-        let test_dates = vec![
+        let test_dates = [
             dates[0] + time::Duration::days(1),
             dates[1] + time::Duration::days(1),
         ];
@@ -107,16 +106,16 @@ mod tests {
         let mut first_dividend_deductions = vec![0.0, 0.0];
         for i in 0..test_dates.len() {
             let date = test_dates[i];
-            first_zero_curve_values[i] = zero_curve.borrow().get_discount_factor_at_date(&date)?;
-            first_dividend_deductions[i] = dividend.borrow().get_deduction_ratio(&date)?;
+            first_zero_curve_values[i] = zero_curve.read().unwrap().get_discount_factor_at_date(&date)?;
+            first_dividend_deductions[i] = dividend.read().unwrap().get_deduction_ratio(&date)?;
         }
 
         // purturb the evaluation_date by three day
-        *evaluation_date.borrow_mut() += "3D1sec";
+        *evaluation_date.write().unwrap() += "3D1sec";
 
         println!(
             "2) evaluation_date of dividend after purturbation: {:?}",
-            dividend.borrow().get_evaluation_date_clone(),
+            dividend.read().unwrap().get_evaluation_date_clone(),
         );
 
         let mut second_zero_curve_values = vec![0.0, 0.0];
@@ -124,8 +123,8 @@ mod tests {
 
         for i in 0..test_dates.len() {
             let date = test_dates[i];
-            second_zero_curve_values[i] = zero_curve.borrow().get_discount_factor_at_date(&date)?;
-            second_dividend_deductions[i] = dividend.borrow().get_deduction_ratio(&date)?;
+            second_zero_curve_values[i] = zero_curve.read().unwrap().get_discount_factor_at_date(&date)?;
+            second_dividend_deductions[i] = dividend.read().unwrap().get_deduction_ratio(&date)?;
         }
 
         for i in 0..test_dates.len() {
@@ -146,14 +145,14 @@ mod tests {
         }
 
         // purturb back the evaluation_date by one day
-        *evaluation_date.borrow_mut() -= "3D1sec";
+        *evaluation_date.write().unwrap() -= "3D1sec";
 
         let mut third_zero_curve_values = vec![0.0, 0.0];
         let mut third_dividend_deductions = vec![0.0, 0.0];
         for i in 0..test_dates.len() {
             let date = test_dates[i];
-            third_zero_curve_values[i] = zero_curve.borrow().get_discount_factor_at_date(&date)?;
-            third_dividend_deductions[i] = dividend.borrow().get_deduction_ratio(&date)?;
+            third_zero_curve_values[i] = zero_curve.read().unwrap().get_discount_factor_at_date(&date)?;
+            third_dividend_deductions[i] = dividend.read().unwrap().get_deduction_ratio(&date)?;
         }
 
         // now the first and third should be the same
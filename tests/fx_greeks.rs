@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use ndarray::array;
+    use rustc_hash::FxHashMap;
+    use rustmetrics::data::{value_data::ValueData, vector_data::VectorData};
+    use rustmetrics::instrument::{Instrument, Instruments};
+    use rustmetrics::instruments::fx_futures::FxFutures;
+    use rustmetrics::pricing_engines::calculation_configuration::CalculationConfiguration;
+    use rustmetrics::pricing_engines::engine_generator::{EngineGenerator, InstrumentCategory};
+    use rustmetrics::pricing_engines::match_parameter::MatchParameter;
+    use rustmetrics::{AccountingLevel, Currency, FxCode, InstInfo, InstType};
+    use static_id::static_id::StaticId;
+    use std::sync::Arc;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_fx_delta_gamma() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let dates = vec![
+            datetime!(2025-03-13 00:00:00 +09:00),
+            datetime!(2026-03-13 00:00:00 +09:00),
+        ];
+        let krw_curve_id = StaticId::from_str("KRWCRS", "DataProvider");
+        let usd_curve_id = StaticId::from_str("USDOIS", "DataProvider");
+        let mut zero_curve_map = FxHashMap::default();
+        for (curve_id, rate, currency) in [
+            (krw_curve_id, 0.035, Currency::KRW),
+            (usd_curve_id, 0.05, Currency::USD),
+        ] {
+            let data = VectorData::new(
+                array![rate, rate],
+                Some(dates.clone()),
+                None,
+                Some(dt),
+                currency,
+                curve_id.code_str().to_string(),
+                curve_id,
+            )?;
+            zero_curve_map.insert(curve_id, data);
+        }
+
+        let fx_code = FxCode::new(Currency::USD, Currency::KRW);
+        let fx_data = ValueData::new(
+            1300.0,
+            Some(dt),
+            Currency::KRW,
+            "USDKRW".to_string(),
+            StaticId::from_str("USDKRW", "DataProvider"),
+        )?;
+        let mut fx_data_map = FxHashMap::default();
+        fx_data_map.insert(fx_code, fx_data);
+
+        let fx_futures_id = StaticId::from_str("USDKRW Fut", "KRX");
+        let fx_futures_info = InstInfo {
+            id: fx_futures_id,
+            issue_date: Some(datetime!(2024-01-02 00:00:00 +09:00)),
+            maturity: Some(datetime!(2024-09-13 00:00:00 +09:00)),
+            currency: Currency::KRW,
+            inst_type: InstType::FxFutures,
+            unit_notional: 10_000.0,
+            name: "USDKRW Fut Sep24".to_string(),
+            accounting_level: AccountingLevel::L1,
+        };
+        let fx_futures = FxFutures::new(fx_futures_info, 1_300.0, None, Currency::USD);
+
+        let mut crs_curve_map = FxHashMap::default();
+        crs_curve_map.insert(Currency::KRW, krw_curve_id);
+        crs_curve_map.insert(Currency::USD, usd_curve_id);
+        let match_parameter = MatchParameter::new(
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+            crs_curve_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+        );
+
+        let calculation_configuration =
+            CalculationConfiguration::default().with_fx_delta_calculation(true);
+        let category = InstrumentCategory::new(
+            Some(vec!["FxFutures".to_string()]),
+            Some(vec![Currency::KRW]),
+            None,
+        );
+
+        let mut engine_builder = EngineGenerator::builder();
+        let engine_generator = engine_builder
+            .with_configuration(calculation_configuration, dt, match_parameter)?
+            .with_instruments(Instruments::new(vec![Arc::new(Instrument::FxFutures(
+                fx_futures,
+            ))]))?
+            .with_instrument_categories(vec![category])?
+            .with_data(
+                fx_data_map,
+                FxHashMap::default(),
+                zero_curve_map,
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+                FxHashMap::default(),
+            )?;
+        engine_generator.distribute_instruments()?;
+        engine_generator.calculate()?;
+
+        let result = engine_generator
+            .get_calculation_results()
+            .get(&fx_futures_id)
+            .ok_or_else(|| anyhow::anyhow!("No result found for {}", fx_futures_id))?;
+        let npv = result.get_npv_result().unwrap().get_npv();
+        // the fx futures is linear in the fx rate, so fx delta is the value change on the 1% move
+        // and fx gamma vanishes
+        let fx_delta = *result.get_fx_delta().unwrap().get(&fx_code).unwrap();
+        let fx_gamma = *result.get_fx_gamma().unwrap().get(&fx_code).unwrap();
+        let expected_fx_delta = npv * 0.01 * 10_000.0;
+        assert!(
+            (fx_delta - expected_fx_delta).abs() <= 1.0e-3 * expected_fx_delta,
+            "fx delta: {} vs {}",
+            fx_delta,
+            expected_fx_delta,
+        );
+        assert!(
+            fx_gamma.abs() <= 1.0e-3 * expected_fx_delta,
+            "fx gamma: {}",
+            fx_gamma
+        );
+        // fx delta is separate from the fx exposure and the equity delta
+        assert!(result.get_fx_exposure().is_some());
+        assert!(result.get_delta().is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+#[cfg(test)]
+mod tests {
+    use rustmetrics::data::value_data::ValueData;
+    use rustmetrics::data::vector_data::VectorData;
+    use rustmetrics::definitions::Real;
+    use rustmetrics::enums::{OptionDailySettlementType, OptionExerciseType, OptionType};
+    use rustmetrics::instrument::{Instrument, Instruments};
+    use rustmetrics::instruments::{futures::Futures, stock::Stock, vanilla_option::VanillaOption};
+    use rustmetrics::pricing_engines::calculation_configuration::CalculationConfiguration;
+    use rustmetrics::pricing_engines::engine_generator::InstrumentCategory;
+    use rustmetrics::pricing_engines::match_parameter::MatchParameter;
+    use rustmetrics::pricing_engines::pnl_explain::{MarketDataSet, PnlExplain};
+    use rustmetrics::storage::{CalculationRun, InMemoryResultStore, ResultStore};
+    use rustmetrics::{AccountingLevel, Currency, InstInfo, InstType};
+    use anyhow::Result;
+    use ndarray::{array, Array1};
+    use rustc_hash::FxHashMap;
+    use static_id::static_id::StaticId;
+    use std::sync::Arc;
+    use time::{macros::datetime, Duration, OffsetDateTime};
+
+    fn market_data(
+        dt: OffsetDateTime,
+        spot: Real,
+        vol: Real,
+        rate: Real,
+    ) -> Result<MarketDataSet> {
+        let dates = vec![
+            datetime!(2025-03-13 00:00:00 +09:00),
+            datetime!(2026-03-13 00:00:00 +09:00),
+        ];
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let ksd_id = StaticId::from_str("KSD", "DataProvider");
+        let bor_id = StaticId::from_str("KOSPI2", "DataProvider");
+        let funding_id = StaticId::from_str("Discount(KRW)", "DataProvider");
+
+        let mut curve_map = FxHashMap::default();
+        curve_map.insert(
+            ksd_id,
+            VectorData::new(
+                array![rate, rate],
+                Some(dates.clone()),
+                None,
+                Some(dt),
+                Currency::KRW,
+                "KSD".to_string(),
+                ksd_id,
+            )?,
+        );
+        curve_map.insert(
+            bor_id,
+            VectorData::new(
+                array![0.005, 0.005],
+                Some(dates.clone()),
+                None,
+                Some(dt),
+                Currency::KRW,
+                "KOSPI2".to_string(),
+                bor_id,
+            )?,
+        );
+        curve_map.insert(
+            funding_id,
+            VectorData::new(
+                array![0.04, 0.04],
+                Some(dates),
+                None,
+                Some(dt),
+                Currency::KRW,
+                "Discount(KRW)".to_string(),
+                funding_id,
+            )?,
+        );
+
+        let mut stock_map = FxHashMap::default();
+        stock_map.insert(
+            und_id,
+            ValueData::new(spot, Some(dt), Currency::KRW, "KOSPI2".to_string(), und_id)?,
+        );
+
+        let mut vol_map = FxHashMap::default();
+        vol_map.insert(
+            und_id,
+            ValueData::new(vol, Some(dt), Currency::KRW, "KOSPI2".to_string(), und_id)?,
+        );
+
+        let mut dividend_map = FxHashMap::default();
+        dividend_map.insert(
+            und_id,
+            VectorData::new(
+                Array1::from(vec![3.0]),
+                Some(vec![datetime!(2024-06-01 00:00:00 +09:00)]),
+                None,
+                Some(dt),
+                Currency::KRW,
+                "KOSPI2".to_string(),
+                und_id,
+            )?,
+        );
+
+        Ok(MarketDataSet::new(dt).with_data(
+            FxHashMap::default(),
+            stock_map,
+            curve_map,
+            dividend_map,
+            vol_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+        ))
+    }
+
+    fn futures(code: &str, trade_price: Real) -> Instrument {
+        let inst_info = InstInfo {
+            id: StaticId::from_str(code, "KRX"),
+            issue_date: Some(datetime!(2021-01-01 00:00:00 +09:00)),
+            maturity: Some(datetime!(2024-06-14 00:00:00 +09:00)),
+            currency: Currency::KRW,
+            inst_type: InstType::Futures,
+            unit_notional: 250_000.0,
+            name: code.to_string(),
+            accounting_level: AccountingLevel::L1,
+        };
+        Instrument::Futures(Futures::new(
+            inst_info,
+            trade_price,
+            None,
+            Currency::KRW,
+            StaticId::from_str("KOSPI2", "KRX"),
+        ))
+    }
+
+    #[test]
+    fn test_pnl_explain() -> Result<()> {
+        let base_dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let target_dt = base_dt + Duration::days(1);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let option = VanillaOption::new(
+            InstInfo {
+                id: option_id,
+                issue_date: Some(datetime!(2021-01-01 00:00:00 +09:00)),
+                maturity: Some(datetime!(2024-09-13 00:00:00 +09:00)),
+                currency: Currency::KRW,
+                inst_type: InstType::VanillaOption,
+                unit_notional: 250_000.0,
+                name: "KOSPI2 Put Sep24".to_string(),
+                accounting_level: AccountingLevel::L1,
+            },
+            340.0,
+            None,
+            und_id,
+            Currency::KRW,
+            OptionType::Put,
+            OptionExerciseType::European,
+            OptionDailySettlementType::NotSettled,
+        );
+
+        let stock = Stock {
+            inst_info: InstInfo {
+                id: und_id,
+                issue_date: None,
+                maturity: None,
+                currency: Currency::KRW,
+                inst_type: InstType::Stock,
+                unit_notional: 1.0,
+                name: "KOSPI2".to_string(),
+                accounting_level: AccountingLevel::L1,
+            },
+            underlying_ids: vec![und_id],
+            rank_type: rustmetrics::StockRankType::Common,
+        };
+
+        let old_futures_id = StaticId::from_str("165XXX1", "KRX");
+        let new_futures_id = StaticId::from_str("165XXX2", "KRX");
+        let base_instruments = vec![
+            Arc::new(Instrument::VanillaOption(option)),
+            Arc::new(Instrument::Stock(stock)),
+            Arc::new(futures("165XXX1", 350.0)),
+        ];
+        let mut target_instruments = base_instruments.clone();
+        target_instruments.push(Arc::new(futures("165XXX2", 353.0)));
+
+        let mut collateral_curve_map = FxHashMap::default();
+        collateral_curve_map.insert(und_id, StaticId::from_str("KSD", "DataProvider"));
+        let mut borrowing_curve_map = FxHashMap::default();
+        borrowing_curve_map.insert(und_id, StaticId::from_str("KOSPI2", "DataProvider"));
+        let mut funding_cost_map = FxHashMap::default();
+        funding_cost_map.insert(Currency::KRW, StaticId::from_str("Discount(KRW)", "DataProvider"));
+        let match_parameter = MatchParameter::new(
+            collateral_curve_map,
+            borrowing_curve_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+            funding_cost_map,
+        );
+
+        let category1 = InstrumentCategory::new(
+            Some(vec![
+                "Futures".to_string(),
+                "VanillaCall".to_string(),
+                "VanillaPut".to_string(),
+            ]),
+            Some(vec![Currency::KRW]),
+            Some(vec![und_id]),
+        );
+        let category2 = InstrumentCategory::new(
+            Some(vec!["Stock".to_string()]),
+            Some(vec![Currency::KRW]),
+            Some(vec![und_id]),
+        );
+
+        // spot +1%, vol +1%p, collateral rate +5bp over a day
+        let base_data = market_data(base_dt, 350.0, 0.2, 0.0335)?;
+        let target_data = market_data(target_dt, 353.5, 0.21, 0.0340)?;
+
+        let mut pnl_explain = PnlExplain::new(
+            CalculationConfiguration::default(),
+            match_parameter.clone(),
+            vec![category1.clone(), category2.clone()],
+            Instruments::new(base_instruments.clone()),
+            base_data.clone(),
+            target_data.clone(),
+        )
+        .with_target_instruments(Instruments::new(target_instruments.clone()));
+
+        pnl_explain.calculate()?;
+
+        let waterfalls = pnl_explain.get_waterfalls();
+        assert_eq!(waterfalls.len(), 4);
+        for (id, waterfall) in waterfalls.iter() {
+            let total = waterfall.get_explained() + waterfall.get_unexplained();
+            assert!(
+                (total - waterfall.get_actual()).abs() <= 1.0e-3 * waterfall.get_actual().abs().max(1.0),
+                "{}: {:?}",
+                id,
+                waterfall,
+            );
+        }
+
+        // a stock is explained by its delta
+        let stock_waterfall = pnl_explain.get_waterfall(&und_id).unwrap();
+        assert!((stock_waterfall.get_actual() - 3.5).abs() < 1.0e-3, "{:?}", stock_waterfall);
+        assert!((stock_waterfall.get_delta() - 3.5).abs() < 1.0e-3, "{:?}", stock_waterfall);
+
+        // the greeks explain most of the option P&L (the rest is mostly the spot-vol cross term)
+        let option_waterfall = pnl_explain.get_waterfall(&option_id).unwrap();
+        assert!(option_waterfall.get_delta() < 0.0, "{:?}", option_waterfall);
+        assert!(option_waterfall.get_vega() > 0.0, "{:?}", option_waterfall);
+        assert!(
+            option_waterfall.get_unexplained().abs() < 0.1 * option_waterfall.get_actual().abs(),
+            "{:?}",
+            option_waterfall,
+        );
+
+        let futures_waterfall = pnl_explain.get_waterfall(&old_futures_id).unwrap();
+        assert!(
+            futures_waterfall.get_unexplained().abs() < 0.05 * futures_waterfall.get_actual().abs(),
+            "{:?}",
+            futures_waterfall,
+        );
+
+        // the new futures has only the new trade P&L
+        let new_waterfall = pnl_explain.get_waterfall(&new_futures_id).unwrap();
+        let new_value = pnl_explain.get_target_results()[&new_futures_id].get_value().unwrap();
+        assert_eq!(new_waterfall.get_new_trades(), new_value - 353.0 * 250_000.0);
+        assert_eq!(new_waterfall.get_delta(), 0.0);
+
+        let aggregated = pnl_explain.get_aggregated_waterfalls();
+        let krw = aggregated.get(&Currency::KRW).unwrap();
+        let actual_sum: Real = waterfalls.values().map(|w| w.get_actual()).sum();
+        assert!((krw.get_actual() - actual_sum).abs() <= 1.0e-3 * actual_sum.abs().max(1.0));
+
+        // the base results saved at the base date and reloaded give the same waterfalls
+        let mut store = InMemoryResultStore::default();
+        store.save_run(&CalculationRun::new(
+            "eod-base".to_string(),
+            base_dt,
+            &CalculationConfiguration::default(),
+            pnl_explain.get_base_results().unwrap().clone(),
+        )?)?;
+        let base_results = store.load_run("eod-base")?.unwrap().into_results();
+        let mut reloaded = PnlExplain::new(
+            CalculationConfiguration::default(),
+            match_parameter.clone(),
+            vec![category1.clone(), category2.clone()],
+            Instruments::new(base_instruments.clone()),
+            base_data.clone(),
+            target_data.clone(),
+        )
+        .with_target_instruments(Instruments::new(target_instruments.clone()))
+        .with_base_results(base_results);
+        reloaded.calculate()?;
+        assert_eq!(reloaded.get_waterfalls(), pnl_explain.get_waterfalls());
+
+        // empty base results are an empty base portfolio, not results to be calculated
+        let mut empty_base = PnlExplain::new(
+            CalculationConfiguration::default(),
+            match_parameter,
+            vec![category1, category2],
+            Instruments::new(base_instruments),
+            base_data,
+            target_data,
+        )
+        .with_target_instruments(Instruments::new(target_instruments))
+        .with_base_results(FxHashMap::default());
+        empty_base.calculate()?;
+        assert!(empty_base.get_base_results().unwrap().is_empty());
+        for waterfall in empty_base.get_waterfalls().values() {
+            assert_eq!(waterfall.get_new_trades(), waterfall.get_actual());
+        }
+        Ok(())
+    }
+}
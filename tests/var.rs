@@ -0,0 +1,517 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use ndarray::{array, Array1};
+    use rustc_hash::FxHashMap;
+    use rustmetrics::data::daily_value_data::DailyValueData;
+    use rustmetrics::data::value_data::ValueData;
+    use rustmetrics::data::vector_data::VectorData;
+    use rustmetrics::definitions::Real;
+    use rustmetrics::enums::{
+        OptionDailySettlementType, OptionExerciseType, OptionType, ScenarioDistribution,
+        VarMethod,
+    };
+    use rustmetrics::instrument::{Instrument, Instruments};
+    use rustmetrics::instruments::{cash::Cash, stock::Stock, vanilla_option::VanillaOption};
+    use rustmetrics::pricing_engines::calculation_configuration::CalculationConfiguration;
+    use rustmetrics::pricing_engines::engine_generator::InstrumentCategory;
+    use rustmetrics::pricing_engines::match_parameter::MatchParameter;
+    use rustmetrics::pricing_engines::pnl_explain::MarketDataSet;
+    use rustmetrics::pricing_engines::pnl_predictor::MarketShock;
+    use rustmetrics::var::{
+        delta_normal_var::DeltaNormalVar,
+        historical_var::HistoricalVar,
+        monte_carlo_var::{MonteCarloVar, ScenarioGenerator},
+        risk_factor::RiskFactor,
+        risk_factor_history::RiskFactorHistory,
+        scenario_revaluation::ScenarioRevaluation,
+        stress_test::{ScenarioSet, StressTest},
+    };
+    use rustmetrics::{AccountingLevel, Currency, FxCode, InstInfo, InstType};
+    use static_id::static_id::StaticId;
+    use std::sync::Arc;
+    use time::{macros::datetime, Duration, OffsetDateTime};
+
+    fn market_data(dt: OffsetDateTime) -> Result<MarketDataSet> {
+        let dates = vec![
+            datetime!(2025-03-13 00:00:00 +09:00),
+            datetime!(2026-03-13 00:00:00 +09:00),
+        ];
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let mut curve_map = FxHashMap::default();
+        for (name, source, rate) in [
+            ("KSD", "DataProvider", 0.0335),
+            ("KOSPI2", "DataProvider", 0.005),
+            ("Discount(KRW)", "DataProvider", 0.04),
+        ] {
+            let id = StaticId::from_str(name, source);
+            curve_map.insert(
+                id,
+                VectorData::new(
+                    array![rate, rate],
+                    Some(dates.clone()),
+                    None,
+                    Some(dt),
+                    Currency::KRW,
+                    name.to_string(),
+                    id,
+                )?,
+            );
+        }
+
+        let mut stock_map = FxHashMap::default();
+        stock_map.insert(
+            und_id,
+            ValueData::new(350.0, Some(dt), Currency::KRW, "KOSPI2".to_string(), und_id)?,
+        );
+        let mut vol_map = FxHashMap::default();
+        vol_map.insert(
+            und_id,
+            ValueData::new(0.2, Some(dt), Currency::KRW, "KOSPI2".to_string(), und_id)?,
+        );
+        let mut dividend_map = FxHashMap::default();
+        dividend_map.insert(
+            und_id,
+            VectorData::new(
+                Array1::from(vec![3.0]),
+                Some(vec![datetime!(2024-06-01 00:00:00 +09:00)]),
+                None,
+                Some(dt),
+                Currency::KRW,
+                "KOSPI2".to_string(),
+                und_id,
+            )?,
+        );
+        let fx_id = StaticId::from_str("USDKRW", "DataProvider");
+        let mut fx_map = FxHashMap::default();
+        fx_map.insert(
+            FxCode::new(Currency::USD, Currency::KRW),
+            ValueData::new(1300.0, Some(dt), Currency::KRW, "USDKRW".to_string(), fx_id)?,
+        );
+
+        Ok(MarketDataSet::new(dt).with_data(
+            fx_map,
+            stock_map,
+            curve_map,
+            dividend_map,
+            vol_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+        ))
+    }
+
+    fn portfolio() -> (Instruments, MatchParameter, Vec<InstrumentCategory>) {
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option = VanillaOption::new(
+            InstInfo {
+                id: StaticId::from_str("165XXX3", "KRX"),
+                issue_date: Some(datetime!(2021-01-01 00:00:00 +09:00)),
+                maturity: Some(datetime!(2024-09-13 00:00:00 +09:00)),
+                currency: Currency::KRW,
+                inst_type: InstType::VanillaOption,
+                unit_notional: 250_000.0,
+                name: "KOSPI2 Put Sep24".to_string(),
+                accounting_level: AccountingLevel::L1,
+            },
+            340.0,
+            None,
+            und_id,
+            Currency::KRW,
+            OptionType::Put,
+            OptionExerciseType::European,
+            OptionDailySettlementType::NotSettled,
+        );
+        let stock = Stock {
+            inst_info: InstInfo {
+                id: und_id,
+                currency: Currency::KRW,
+                inst_type: InstType::Stock,
+                unit_notional: 1.0,
+                name: "KOSPI2".to_string(),
+                ..Default::default()
+            },
+            underlying_ids: vec![und_id],
+            rank_type: rustmetrics::StockRankType::Common,
+        };
+        let cash = Cash {
+            inst_info: InstInfo {
+                id: StaticId::from_str("USD Cash", "Account"),
+                currency: Currency::USD,
+                inst_type: InstType::Cash,
+                unit_notional: 1.0,
+                name: "USD Cash".to_string(),
+                ..Default::default()
+            },
+        };
+        let instruments = Instruments::new(vec![
+            Arc::new(Instrument::VanillaOption(option)),
+            Arc::new(Instrument::Stock(stock)),
+            Arc::new(Instrument::Cash(cash)),
+        ]);
+
+        let mut collateral_curve_map = FxHashMap::default();
+        collateral_curve_map.insert(und_id, StaticId::from_str("KSD", "DataProvider"));
+        let mut borrowing_curve_map = FxHashMap::default();
+        borrowing_curve_map.insert(und_id, StaticId::from_str("KOSPI2", "DataProvider"));
+        let mut funding_cost_map = FxHashMap::default();
+        funding_cost_map.insert(
+            Currency::KRW,
+            StaticId::from_str("Discount(KRW)", "DataProvider"),
+        );
+        let match_parameter = MatchParameter::new(
+            collateral_curve_map,
+            borrowing_curve_map,
+            FxHashMap::default(),
+            FxHashMap::default(),
+            FxHashMap::default(),
+            funding_cost_map,
+        );
+
+        let categories = vec![
+            InstrumentCategory::new(
+                Some(vec!["VanillaCall".to_string(), "VanillaPut".to_string()]),
+                Some(vec![Currency::KRW]),
+                Some(vec![und_id]),
+            ),
+            InstrumentCategory::new(
+                Some(vec!["Stock".to_string(), "Cash".to_string()]),
+                Some(vec![Currency::KRW, Currency::USD]),
+                None,
+            ),
+        ];
+
+        (instruments, match_parameter, categories)
+    }
+
+    fn revaluation(dt: OffsetDateTime, method: VarMethod) -> Result<ScenarioRevaluation> {
+        revaluation_on(dt, method, CalculationConfiguration::default())
+    }
+
+    fn revaluation_on(
+        dt: OffsetDateTime,
+        method: VarMethod,
+        calculation_configuration: CalculationConfiguration,
+    ) -> Result<ScenarioRevaluation> {
+        let (instruments, match_parameter, categories) = portfolio();
+        Ok(ScenarioRevaluation::new(
+            calculation_configuration,
+            match_parameter,
+            categories,
+            instruments,
+            market_data(dt)?,
+            Currency::KRW,
+        )
+        .with_method(method))
+    }
+
+    fn history(dt: OffsetDateTime, days: i64) -> RiskFactorHistory {
+        let mut spot = DailyValueData::default();
+        let mut vol = DailyValueData::default();
+        let mut rate = DailyValueData::default();
+        let mut fx = DailyValueData::default();
+        let (mut s, mut v, mut r, mut f): (Real, Real, Real, Real) = (300.0, 0.2, 0.03, 1250.0);
+        for i in 0..=days {
+            let date = (dt - Duration::days(days - i)).date();
+            let x = i as Real;
+            s *= 1.0 + 0.015 * (1.3 * x).sin();
+            v += 0.003 * (0.7 * x + 1.0).sin();
+            r += 0.0002 * (1.9 * x).cos();
+            f *= 1.0 + 0.004 * (0.5 * x + 2.0).sin();
+            spot.insert(date, s);
+            vol.insert(date, v);
+            rate.insert(date, r);
+            fx.insert(date, f);
+        }
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let mut spot_data = FxHashMap::default();
+        spot_data.insert(und_id, spot);
+        let mut vol_data = FxHashMap::default();
+        vol_data.insert(und_id, vol);
+        let mut rate_data = FxHashMap::default();
+        rate_data.insert(StaticId::from_str("KSD", "DataProvider"), rate);
+        let mut fx_data = FxHashMap::default();
+        fx_data.insert(FxCode::new(Currency::USD, Currency::KRW), fx);
+
+        RiskFactorHistory::new()
+            .with_spot_data(spot_data)
+            .with_volatility_data(vol_data)
+            .with_rate_data(rate_data)
+            .with_fx_data(fx_data)
+    }
+
+    #[test]
+    fn test_historical_var() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let days = 20;
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let cash_id = StaticId::from_str("USD Cash", "Account");
+
+        let mut full_var = HistoricalVar::new(
+            revaluation(dt, VarMethod::FullRevaluation)?,
+            history(dt, days),
+        )
+        .with_confidence_level(0.95)?;
+        full_var.calculate()?;
+
+        let mut sensitivity_var =
+            HistoricalVar::new(revaluation(dt, VarMethod::Sensitivity)?, history(dt, days))
+                .with_confidence_level(0.95)?;
+        sensitivity_var.calculate()?;
+
+        assert_eq!(full_var.get_scenario_dates().len(), days as usize);
+        let history = history(dt, days);
+        let shocks = history.get_scenarios()?;
+        let full_pnls = full_var.get_instrument_pnls();
+        let sensitivity_pnls = sensitivity_var.get_instrument_pnls();
+        for (i, (_, shock)) in shocks.iter().enumerate() {
+            // linear instruments are repriced exactly
+            let stock_pnl = 350.0 * shock.get_spot_returns()[&und_id];
+            assert!((full_pnls[&und_id][i] - stock_pnl).abs() < 1.0e-3);
+            assert!((sensitivity_pnls[&und_id][i] - stock_pnl).abs() < 1.0e-3);
+            let fx_return = shock.get_fx_returns()[&FxCode::new(Currency::USD, Currency::KRW)];
+            let cash_pnl = 1300.0 * fx_return;
+            assert!((full_pnls[&cash_id][i] - cash_pnl).abs() < 1.0e-3);
+        }
+
+        let full = full_var.get_result().unwrap();
+        let sensitivity = sensitivity_var.get_result().unwrap();
+        assert!(full.get_var() > 0.0);
+        assert!(full.get_expected_shortfall() >= full.get_var());
+        let var_sum: Real = full.get_var_contributions().values().sum();
+        let es_sum: Real = full.get_es_contributions().values().sum();
+        assert!((var_sum - full.get_var()).abs() < 1.0e-3 * full.get_var());
+        assert!((es_sum - full.get_expected_shortfall()).abs() < 1.0e-3 * full.get_var());
+        assert!(full.get_var_contributions().contains_key(&option_id));
+
+        let stats = full.get_risk_statistics(&[full.get_confidence_level(), 0.9])?;
+        assert_eq!(stats.get_scenario_number(), full.get_portfolio_pnls().len());
+        assert_eq!(stats.get_empirical()[0].get_var(), full.get_var());
+        assert_eq!(
+            stats.get_empirical()[0].get_expected_shortfall(),
+            full.get_expected_shortfall()
+        );
+        assert!(stats.get_empirical()[1].get_var() <= full.get_var());
+
+        // the second order expansion is close to the full revaluation on daily moves
+        assert!(
+            (sensitivity.get_var() - full.get_var()).abs() < 0.1 * full.get_var(),
+            "sensitivity: {}, full: {}",
+            sensitivity.get_var(),
+            full.get_var(),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_monte_carlo_var() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let risk_factors = vec![
+            RiskFactor::Spot(und_id),
+            RiskFactor::Volatility(und_id),
+            RiskFactor::Rate(StaticId::from_str("KSD", "DataProvider")),
+            RiskFactor::Fx(FxCode::new(Currency::USD, Currency::KRW)),
+        ];
+        let covariance = array![
+            [2.25e-4, -1.5e-5, 0.0, -1.5e-5],
+            [-1.5e-5, 9.0e-6, 0.0, 0.0],
+            [0.0, 0.0, 4.0e-8, 0.0],
+            [-1.5e-5, 0.0, 0.0, 2.5e-5],
+        ];
+        let generator = ScenarioGenerator::new(risk_factors, &covariance)?
+            .with_distribution(ScenarioDistribution::StudentT {
+                degrees_of_freedom: 4.0,
+            })?
+            .with_seed(1234);
+
+        let mut results = vec![];
+        for method in [
+            VarMethod::Sensitivity,
+            VarMethod::Sensitivity,
+            VarMethod::FullRevaluation,
+        ] {
+            let mut mc_var = MonteCarloVar::new(revaluation(dt, method)?, generator.clone())
+                .with_scenario_number(20)
+                .with_confidence_level(0.9)?;
+            mc_var.calculate()?;
+            results.push(mc_var);
+        }
+
+        // the same seed gives the same VaR
+        let (first, second) = (
+            results[0].get_result().unwrap(),
+            results[1].get_result().unwrap(),
+        );
+        assert_eq!(first, second);
+        assert!(first.get_var() > 0.0);
+
+        // both revaluations see the same scenarios
+        let shocks = generator.generate(20)?;
+        let full_pnls = results[2].get_instrument_pnls();
+        for (i, shock) in shocks.iter().enumerate() {
+            let stock_pnl = 350.0 * shock.get_spot_returns()[&und_id];
+            assert!((full_pnls[&und_id][i] - stock_pnl).abs() < 1.0e-3);
+            assert!((results[0].get_instrument_pnls()[&und_id][i] - stock_pnl).abs() < 1.0e-3);
+        }
+        let full = results[2].get_result().unwrap();
+        assert!((first.get_var() - full.get_var()).abs() < 0.1 * full.get_var());
+        Ok(())
+    }
+
+    #[test]
+    fn test_monte_carlo_var_seeded_by_configuration() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let risk_factors = vec![RiskFactor::Spot(und_id)];
+        let covariance = array![[2.25e-4]];
+        let generator = ScenarioGenerator::new(risk_factors, &covariance)?;
+        let configuration = CalculationConfiguration::default()
+            .with_reproducibility(true)
+            .with_random_seed(7);
+
+        let mut runs = vec![];
+        for _ in 0..2 {
+            let revaluation =
+                revaluation_on(dt, VarMethod::Sensitivity, configuration.clone())?;
+            let mut mc_var = MonteCarloVar::new(revaluation, generator.clone())
+                .with_scenario_number(20)
+                .with_confidence_level(0.9)?;
+            mc_var.calculate()?;
+            runs.push(mc_var);
+        }
+
+        // two runs on the same configuration are identical
+        assert_eq!(runs[0].get_generator().get_seed(), Some(7));
+        assert_eq!(runs[0].get_result(), runs[1].get_result());
+        assert_eq!(
+            runs[0].get_instrument_pnls()[&und_id],
+            runs[1].get_instrument_pnls()[&und_id]
+        );
+
+        // the seed of the generator is kept over the one of the configuration
+        let seeded = MonteCarloVar::new(
+            revaluation_on(dt, VarMethod::Sensitivity, configuration)?,
+            generator.with_seed(8),
+        );
+        assert_eq!(seeded.get_generator().get_seed(), Some(8));
+        Ok(())
+    }
+
+    #[test]
+    fn test_delta_normal_var() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let cash_id = StaticId::from_str("USD Cash", "Account");
+        let fx_code = FxCode::new(Currency::USD, Currency::KRW);
+        let risk_factors = vec![
+            RiskFactor::Spot(und_id),
+            RiskFactor::Volatility(und_id),
+            RiskFactor::Fx(fx_code),
+        ];
+        let covariance = array![
+            [2.25e-4, -1.5e-5, -1.5e-5],
+            [-1.5e-5, 9.0e-6, 0.0],
+            [-1.5e-5, 0.0, 2.5e-5],
+        ];
+
+        let mut revaluation = revaluation(dt, VarMethod::Sensitivity)?;
+        revaluation.initialize()?;
+        let results = revaluation.get_base_results();
+        let delta_normal = DeltaNormalVar::new(risk_factors, covariance.clone(), Currency::KRW)?
+            .with_confidence_level(0.99)?;
+        let result = delta_normal.calculate(
+            revaluation.get_instruments(),
+            results,
+            revaluation.get_base_data(),
+        )?;
+
+        // exposures per unit move: delta / 1%, vega / 1%p and the USD amount in KRW
+        let option_result = &results[&option_id];
+        let spot_exposure = (option_result.get_delta().unwrap()[&und_id] + 3.5) / 0.01;
+        let vol_exposure = option_result.get_vega().unwrap()[&und_id] / 0.01;
+        let exposures = result.get_exposures();
+        assert!((exposures[0] - spot_exposure).abs() < 1.0e-3 * spot_exposure.abs());
+        assert!((exposures[1] - vol_exposure).abs() < 1.0e-3 * vol_exposure.abs());
+        assert!((exposures[2] - 1300.0).abs() < 1.0e-3);
+
+        let e = Array1::from(exposures.clone());
+        let sigma = e.dot(&covariance.dot(&e)).sqrt();
+        assert!((result.get_standard_deviation() - sigma).abs() < 1.0e-3 * sigma);
+        assert!((result.get_var() - 2.326_348 * sigma).abs() < 1.0e-3 * sigma);
+        assert!((result.get_expected_shortfall() - 2.665_214 * sigma).abs() < 1.0e-3 * sigma);
+        let stats = result.get_risk_statistics(&[0.975, 0.99], -0.5, 3.0)?;
+        let normal_99 = stats.get_normal()[1];
+        assert!((normal_99.get_var() - result.get_var()).abs() < 1.0e-3 * sigma);
+        assert!(stats.get_cornish_fisher()[1].get_var() > result.get_var());
+
+        let contribution_sum: Real = result.get_var_contributions().values().sum();
+        assert!((contribution_sum - result.get_var()).abs() < 1.0e-3 * result.get_var());
+        assert!(result.get_var_contributions()[&cash_id] != 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stress_test() -> Result<()> {
+        let dt = datetime!(2024-03-13 16:30:00 +09:00);
+        let und_id = StaticId::from_str("KOSPI2", "KRX");
+        let option_id = StaticId::from_str("165XXX3", "KRX");
+        let cash_id = StaticId::from_str("USD Cash", "Account");
+        let fx_code = FxCode::new(Currency::USD, Currency::KRW);
+        let mut rate_shock = MarketShock::new();
+        for curve in ["KSD", "KOSPI2", "Discount(KRW)"] {
+            rate_shock =
+                rate_shock.with_rate_change(StaticId::from_str(curve, "DataProvider"), 0.02);
+        }
+        let scenario_set = ScenarioSet::new("Monthly stress".to_string())
+            .with_scenario(
+                "Equity -30%",
+                MarketShock::new().with_spot_return(und_id, -0.3),
+            )?
+            .with_scenario(
+                "Vol +5pts",
+                MarketShock::new().with_volatility_change(und_id, 0.05),
+            )?
+            .with_scenario("Curves +200bp", rate_shock)?
+            .with_scenario("USD +10%", MarketShock::new().with_fx_return(fx_code, 0.1))?
+            .with_scenario("USD -10%", MarketShock::new().with_fx_return(fx_code, -0.1))?;
+        assert!(scenario_set
+            .clone()
+            .with_scenario("USD +10%", MarketShock::new())
+            .is_err());
+
+        let json = serde_json::to_string(&scenario_set)?;
+        let deserialized: ScenarioSet = serde_json::from_str(&json)?;
+        assert_eq!(deserialized, scenario_set);
+
+        let mut stress_test =
+            StressTest::new(revaluation(dt, VarMethod::FullRevaluation)?, deserialized);
+        stress_test.calculate()?;
+        let matrix = stress_test.get_result().unwrap();
+        assert_eq!(matrix.get_pnls().shape(), &[3, 5]);
+        assert_eq!(matrix.get_scenario_names()[2], "Curves +200bp");
+
+        assert!((matrix.get_pnl(&und_id, "Equity -30%").unwrap() + 105.0).abs() < 1.0e-3);
+        assert!(matrix.get_pnl(&option_id, "Equity -30%").unwrap() > 0.0);
+        assert!(matrix.get_pnl(&option_id, "Vol +5pts").unwrap() > 0.0);
+        assert!((matrix.get_pnl(&cash_id, "USD +10%").unwrap() - 130.0).abs() < 1.0e-3);
+        assert!((matrix.get_pnl(&cash_id, "USD -10%").unwrap() + 130.0).abs() < 1.0e-3);
+        assert_eq!(matrix.get_pnl(&cash_id, "Equity -30%").unwrap(), 0.0);
+        assert!(matrix.get_pnl(&cash_id, "Unknown").is_none());
+
+        let portfolio_pnls = matrix.get_portfolio_pnls();
+        let (worst_name, worst_pnl) = matrix.get_worst_scenario().unwrap();
+        assert_eq!(
+            worst_pnl,
+            portfolio_pnls
+                .iter()
+                .cloned()
+                .fold(Real::INFINITY, Real::min)
+        );
+        assert!(matrix.get_scenario_names().contains(worst_name));
+        Ok(())
+    }
+}